@@ -0,0 +1,119 @@
+// Answers to prompts seen recently, so the same prompt twice (the demo page's example buttons)
+// costs one upstream call. The cache holds at most `capacity` answers; when it is full, the
+// one used least recently goes (LRU). Answers also expire `ttl` after they were stored.
+
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::PromptRequest;
+
+/// Answers kept when `CACHE_SIZE` isn't set
+pub const DEFAULT_CACHE_SIZE: usize = 100;
+/// How long answers are kept when `CACHE_TTL_SECS` isn't set
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(300);
+
+struct Entry {
+    answer: String,
+    stored: Instant,
+    // The `clock` value of the last lookup; the smallest is the least recently used
+    used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    map: HashMap<u64, Entry>,
+    clock: u64,
+}
+
+/// The answer cache, shared by all workers through `web::Data`. Worker threads take turns
+/// through the `Mutex`; each lookup or insert only holds it for a moment.
+pub struct ResponseCache {
+    entries: Mutex<Entries>,
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl ResponseCache {
+    /// A cache of up to `capacity` answers that expire after `ttl`. A capacity of 0 turns
+    /// caching off.
+    pub fn new(capacity: usize, ttl: Duration) -> ResponseCache {
+        ResponseCache {
+            entries: Mutex::new(Entries::default()),
+            capacity,
+            ttl,
+        }
+    }
+
+    // The key is a hash of everything that changes the answer, so the prompt itself (up to
+    // 64 KB) isn't stored twice. Two different prompts with the same 64-bit hash are
+    // possible in theory, but won't happen among a few hundred entries.
+    fn key(request: &PromptRequest) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        request.text.hash(&mut hasher);
+        request.model.hash(&mut hasher);
+        // f32 isn't Hash (NaN != NaN); its bits are
+        request.temperature.map(f32::to_bits).hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The cached answer to `request`, unless there is none or it has expired
+    pub fn get(&self, request: &PromptRequest) -> Option<String> {
+        let key = ResponseCache::key(request);
+        let mut entries = self.entries.lock().unwrap();
+        if entries
+            .map
+            .get(&key)
+            .is_some_and(|entry| entry.stored.elapsed() >= self.ttl)
+        {
+            entries.map.remove(&key);
+            return None;
+        }
+        entries.clock += 1;
+        let now = entries.clock;
+        let entry = entries.map.get_mut(&key)?;
+        entry.used = now;
+        Some(entry.answer.clone())
+    }
+
+    /// Store the answer to `request`, making room by dropping the least recently used one
+    pub fn insert(&self, request: &PromptRequest, answer: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        let key = ResponseCache::key(request);
+        let mut entries = self.entries.lock().unwrap();
+        if !entries.map.contains_key(&key) && entries.map.len() >= self.capacity {
+            // A linear scan: fine for the hundreds of entries this cache is meant for
+            let oldest = entries
+                .map
+                .iter()
+                .min_by_key(|(_, entry)| entry.used)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                entries.map.remove(&oldest);
+            }
+        }
+        entries.clock += 1;
+        let used = entries.clock;
+        entries.map.insert(
+            key,
+            Entry {
+                answer,
+                stored: Instant::now(),
+                used,
+            },
+        );
+    }
+
+    /// How many answers are stored, expired ones included
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,282 @@
+// Startup settings, all from environment variables so two copies of the server (or one
+// pointed at a local `extender`) need no rebuild:
+//
+//   BIND_ADDR              where to listen          (0.0.0.0:8089)
+//   UPSTREAM_URL           the Gemini proxy         (https://gemini.weolopez.com/proxy)
+//   UPSTREAM_TIMEOUT_SECS  how long to wait for it  (30)
+//   GEMINI_API_KEY         the key sent to it
+//   ALLOWED_MODELS         models clients may use (gemini-2.0-flash; the first is the default)
+//   MAX_BODY_BYTES         largest POST body        (65536)
+//   MAX_PROMPT_BYTES       longest prompt text      (32768)
+//   CACHE_SIZE             answers cached           (100)
+//   CACHE_TTL_SECS         how long they are kept   (300)
+//   RATE_LIMIT_PER_MINUTE  requests per client IP   (30; 0 turns the limit off)
+//   RATE_LIMIT_BURST       requests at once         (10)
+//   TRUST_PROXY            1 to limit by X-Forwarded-For, behind a reverse proxy
+//
+// Invalid values stop the server at startup, instead of surfacing as odd errors on the
+// first request.
+
+use std::fmt;
+use std::time::Duration;
+
+use actix_web::web;
+use reqwest::{Client, Url};
+
+use crate::cache::{DEFAULT_CACHE_SIZE, DEFAULT_CACHE_TTL, ResponseCache};
+use crate::limits::{DEFAULT_MAX_PROMPT_BYTES, Limits};
+use crate::models::AllowedModels;
+use crate::rate_limit::{DEFAULT_BURST, DEFAULT_RATE_PER_MINUTE, RateLimiter};
+use crate::{AppState, DEFAULT_UPSTREAM_TIMEOUT, DEFAULT_UPSTREAM_URL, MAX_BODY_BYTES, Upstream};
+
+/// Where the server listens when `BIND_ADDR` isn't set
+pub const DEFAULT_BIND_ADDR: &str = "0.0.0.0:8089";
+
+/// An environment variable with a value the server can't use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub name: &'static str,
+    pub value: String,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={:?}: {}", self.name, self.value, self.reason)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// The server's settings.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub bind_addr: String,
+    pub upstream_url: Url,
+    pub upstream_timeout: Duration,
+    pub api_key: Option<String>,
+    pub allowed_models: AllowedModels,
+    pub limits: Limits,
+    pub cache_size: usize,
+    pub cache_ttl: Duration,
+    /// Requests a minute per client; 0 means no limit
+    pub rate_per_minute: u32,
+    pub rate_burst: u32,
+    pub trust_proxy: bool,
+}
+
+impl Config {
+    /// Read the settings from the environment
+    pub fn from_env() -> Result<Config, ConfigError> {
+        Config::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Read the settings through `var`, which returns a variable's value if it is set.
+    /// Tests pass a closure over a map instead of changing the real environment.
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> Result<Config, ConfigError> {
+        // A set but empty variable counts as not set, like `UPSTREAM_URL= cargo run`
+        let var = |name: &str| var(name).filter(|value| !value.trim().is_empty());
+
+        let upstream_url = match var("UPSTREAM_URL") {
+            Some(value) => parse_url(&value)?,
+            None => Url::parse(DEFAULT_UPSTREAM_URL).expect("the default URL is valid"),
+        };
+        let allowed_models = match var("ALLOWED_MODELS") {
+            Some(value) => AllowedModels::parse(&value).ok_or(ConfigError {
+                name: "ALLOWED_MODELS",
+                value,
+                reason: "expected model names separated by commas, like gemini-2.0-flash",
+            })?,
+            None => AllowedModels::default(),
+        };
+        let seconds = |name: &'static str, default: Duration| match var(name) {
+            Some(value) => match value.trim().parse::<u64>() {
+                Ok(secs) if secs > 0 => Ok(Duration::from_secs(secs)),
+                _ => Err(ConfigError {
+                    name,
+                    value,
+                    reason: "expected a whole number of seconds, at least 1",
+                }),
+            },
+            None => Ok(default),
+        };
+        // A whole number, or `default` when the variable isn't set
+        fn number<T: std::str::FromStr>(
+            value: Option<String>,
+            name: &'static str,
+            default: T,
+            reason: &'static str,
+        ) -> Result<T, ConfigError> {
+            match value {
+                Some(value) => value.trim().parse().map_err(|_| ConfigError {
+                    name,
+                    value,
+                    reason,
+                }),
+                None => Ok(default),
+            }
+        }
+        let cache_size = number(
+            var("CACHE_SIZE"),
+            "CACHE_SIZE",
+            DEFAULT_CACHE_SIZE,
+            "expected a number of answers (0 turns the cache off)",
+        )?;
+        let rate_per_minute = number(
+            var("RATE_LIMIT_PER_MINUTE"),
+            "RATE_LIMIT_PER_MINUTE",
+            DEFAULT_RATE_PER_MINUTE,
+            "expected a number of requests (0 turns the limit off)",
+        )?;
+        let rate_burst = number(
+            var("RATE_LIMIT_BURST"),
+            "RATE_LIMIT_BURST",
+            DEFAULT_BURST,
+            "expected a number of requests, at least 1",
+        )?;
+        // A byte count of at least 1
+        let bytes = |name: &'static str, default: usize| {
+            let reason = "expected a number of bytes, at least 1";
+            match number(var(name), name, default, reason)? {
+                0 => Err(ConfigError {
+                    name,
+                    value: "0".to_string(),
+                    reason,
+                }),
+                bytes => Ok(bytes),
+            }
+        };
+        let limits = Limits {
+            max_prompt_bytes: bytes("MAX_PROMPT_BYTES", DEFAULT_MAX_PROMPT_BYTES)?,
+            max_body_bytes: bytes("MAX_BODY_BYTES", MAX_BODY_BYTES)?,
+        };
+        if rate_burst == 0 {
+            return Err(ConfigError {
+                name: "RATE_LIMIT_BURST",
+                value: "0".to_string(),
+                reason: "expected a number of requests, at least 1",
+            });
+        }
+        let trust_proxy = match var("TRUST_PROXY").as_deref().map(str::trim) {
+            None | Some("0" | "false") => false,
+            Some("1" | "true") => true,
+            Some(value) => {
+                return Err(ConfigError {
+                    name: "TRUST_PROXY",
+                    value: value.to_string(),
+                    reason: "expected 1 or 0",
+                });
+            }
+        };
+
+        Ok(Config {
+            bind_addr: var("BIND_ADDR").unwrap_or_else(|| DEFAULT_BIND_ADDR.to_string()),
+            upstream_url,
+            upstream_timeout: seconds("UPSTREAM_TIMEOUT_SECS", DEFAULT_UPSTREAM_TIMEOUT)?,
+            api_key: var("GEMINI_API_KEY"),
+            allowed_models,
+            limits,
+            cache_size,
+            cache_ttl: seconds("CACHE_TTL_SECS", DEFAULT_CACHE_TTL)?,
+            rate_per_minute,
+            rate_burst,
+            trust_proxy,
+        })
+    }
+
+    /// The upstream these settings describe.
+    ///
+    /// The client only gets a connect timeout: a timeout on the client would also cut off
+    /// streamed answers that take longer than it. `Upstream` applies `upstream_timeout` to
+    /// each request instead.
+    pub fn upstream(&self) -> Upstream {
+        let client = Client::builder()
+            .connect_timeout(self.upstream_timeout)
+            .build()
+            .expect("the HTTP client should build");
+        Upstream::new(client, self.upstream_url.as_str(), self.api_key.clone())
+            .with_timeout(self.upstream_timeout)
+    }
+
+    /// Everything the app needs, to share between workers
+    pub fn state(&self) -> AppState {
+        let cache = ResponseCache::new(self.cache_size, self.cache_ttl);
+        let state = AppState::new(self.upstream())
+            .with_models(web::Data::new(self.allowed_models.clone()))
+            .with_limits(web::Data::new(self.limits))
+            .with_cache(web::Data::new(cache));
+        match self.rate_per_minute {
+            0 => state,
+            rate => state.with_rate_limit(web::Data::new(
+                RateLimiter::new(rate, self.rate_burst).trust_proxy(self.trust_proxy),
+            )),
+        }
+    }
+}
+
+// Only absolute http(s) URLs: a typo like `localhost:3000` (no scheme) parses as a URL
+// with scheme "localhost", and would only fail on the first request
+fn parse_url(value: &str) -> Result<Url, ConfigError> {
+    let error = |reason| ConfigError {
+        name: "UPSTREAM_URL",
+        value: value.to_string(),
+        reason,
+    };
+    let url = Url::parse(value.trim()).map_err(|_| error("not a valid URL"))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return Err(error("expected an http:// or https:// URL"));
+    }
+    Ok(url)
+}
+
+/// The effective settings, for the startup log. The API key itself is never shown.
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Listening on {}", self.bind_addr)?;
+        writeln!(
+            f,
+            "  upstream: {} (timeout {}s)",
+            self.upstream_url,
+            self.upstream_timeout.as_secs()
+        )?;
+        let key = if self.api_key.is_some() {
+            "set"
+        } else {
+            "NOT SET"
+        };
+        writeln!(f, "  GEMINI_API_KEY: {}", key)?;
+        writeln!(
+            f,
+            "  models: {} (default {})",
+            self.allowed_models.names().join(", "),
+            self.allowed_models.default_model()
+        )?;
+        writeln!(
+            f,
+            "  limits: prompts up to {} bytes, bodies up to {} bytes",
+            self.limits.max_prompt_bytes, self.limits.max_body_bytes
+        )?;
+        match self.cache_size {
+            0 => writeln!(f, "  cache: off")?,
+            size => writeln!(
+                f,
+                "  cache: {} answers for {}s",
+                size,
+                self.cache_ttl.as_secs()
+            )?,
+        }
+        let by = if self.trust_proxy {
+            "X-Forwarded-For"
+        } else {
+            "client IP"
+        };
+        match self.rate_per_minute {
+            0 => write!(f, "  rate limit: off"),
+            rate => write!(
+                f,
+                "  rate limit: {} a minute, bursts of {}, by {}",
+                rate, self.rate_burst, by
+            ),
+        }
+    }
+}
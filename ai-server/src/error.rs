@@ -0,0 +1,186 @@
+// Every error ai-server sends, whatever went wrong, has the same JSON shape:
+//
+//   {"error": {"code": "UPSTREAM_TIMEOUT", "message": "The upstream didn't answer within 30s"}}
+//
+// `code` is stable, for programs to match on; `message` is for people. An error the upstream
+// itself answered with also has `upstream_status`, and Gemini's own code and message when its
+// body is Gemini's error JSON:
+//
+//   {"error": {"code": "RESOURCE_EXHAUSTED", "message": "Quota exceeded", "upstream_status": 429}}
+//
+// Handlers return `Result<_, ApiError>` and actix turns the error into the response through
+// `ResponseError`; streamed answers put the same JSON in their last `event: error`.
+
+use std::fmt;
+use std::time::Duration;
+
+use actix_web::http::{StatusCode, header};
+use actix_web::{HttpResponse, ResponseError};
+use serde_json::{Value, json};
+
+/// Everything that can go wrong with a request, as one error type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApiError {
+    /// A required parameter, e.g. `text`, is missing or blank
+    MissingParameter(&'static str),
+    /// A parameter has a value that can't be used; the message says why
+    InvalidParameter(String),
+    /// `model` isn't in `ALLOWED_MODELS`
+    UnknownModel { model: String, allowed: Vec<String> },
+    /// The body isn't the JSON `POST /prompt` expects
+    InvalidBody(String),
+    /// The body is over the limit, this many bytes
+    BodyTooLarge(usize),
+    /// The prompt's text is over the limit, this many bytes
+    PromptTooLong(usize),
+    /// The client is over its rate limit and may try again after this many seconds
+    RateLimited(u64),
+    /// The server was started without `GEMINI_API_KEY`
+    MissingApiKey,
+    /// The upstream couldn't be reached
+    Unreachable,
+    /// The upstream didn't answer in time
+    Timeout(Duration),
+    /// The upstream answered with something that isn't Gemini JSON
+    Parse,
+    /// Gemini's answer had no text where it should be
+    EmptyResponse,
+    /// A streamed answer stopped before it was finished
+    Interrupted(&'static str),
+    /// The upstream answered with an error status. `code` and `message` are Gemini's when
+    /// the body was Gemini's error JSON.
+    Upstream {
+        status: u16,
+        code: String,
+        message: String,
+    },
+}
+
+impl ApiError {
+    /// The error for an upstream answer with an error `status` and `body`. Gemini's errors
+    /// look like `{"error": {"code": 429, "message": "...", "status": "RESOURCE_EXHAUSTED"}}`;
+    /// any other body becomes the message as is.
+    pub fn from_upstream(status: u16, body: &str) -> ApiError {
+        let gemini = serde_json::from_str::<Value>(body).ok();
+        let gemini = gemini.as_ref().map(|body| &body["error"]);
+        let code = gemini
+            .and_then(|error| error["status"].as_str())
+            .unwrap_or("UPSTREAM_ERROR");
+        let message = gemini
+            .and_then(|error| error["message"].as_str())
+            .unwrap_or(body.trim());
+        ApiError::Upstream {
+            status,
+            code: code.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    /// The stable name of the error, the `code` field of the JSON
+    pub fn code(&self) -> &str {
+        match self {
+            ApiError::MissingParameter(_) => "MISSING_PARAMETER",
+            ApiError::InvalidParameter(_) => "INVALID_PARAMETER",
+            ApiError::UnknownModel { .. } => "UNKNOWN_MODEL",
+            ApiError::InvalidBody(_) => "INVALID_BODY",
+            ApiError::BodyTooLarge(_) => "BODY_TOO_LARGE",
+            ApiError::PromptTooLong(_) => "PROMPT_TOO_LONG",
+            ApiError::RateLimited(_) => "RATE_LIMITED",
+            ApiError::MissingApiKey => "MISSING_API_KEY",
+            ApiError::Unreachable => "UPSTREAM_UNREACHABLE",
+            ApiError::Timeout(_) => "UPSTREAM_TIMEOUT",
+            ApiError::Parse => "PARSE_ERROR",
+            ApiError::EmptyResponse => "EMPTY_RESPONSE",
+            ApiError::Interrupted(_) => "STREAM_INTERRUPTED",
+            ApiError::Upstream { code, .. } => code,
+        }
+    }
+
+    /// The whole JSON body: `{"error": {...}}`
+    pub fn to_json(&self) -> Value {
+        let mut error = json!({ "code": self.code(), "message": self.to_string() });
+        match self {
+            ApiError::Upstream { status, .. } => error["upstream_status"] = json!(status),
+            ApiError::UnknownModel { allowed, .. } => error["allowed"] = json!(allowed),
+            _ => {}
+        }
+        json!({ "error": error })
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::MissingParameter(name) => write!(f, "Missing '{}'", name),
+            ApiError::InvalidParameter(message) => write!(f, "{}", message),
+            ApiError::UnknownModel { model, allowed } => write!(
+                f,
+                "Unknown model '{}'; allowed: {}",
+                model,
+                allowed.join(", ")
+            ),
+            ApiError::InvalidBody(reason) => write!(f, "Invalid request body: {}", reason),
+            ApiError::BodyTooLarge(limit) => {
+                write!(f, "Request body is larger than {}", size(*limit))
+            }
+            ApiError::PromptTooLong(limit) => write!(f, "'text' is longer than {}", size(*limit)),
+            ApiError::RateLimited(seconds) => {
+                write!(f, "Too many requests; try again in {} seconds", seconds)
+            }
+            ApiError::MissingApiKey => write!(f, "Missing GEMINI_API_KEY env variable"),
+            ApiError::Unreachable => write!(f, "The upstream couldn't be reached"),
+            ApiError::Timeout(limit) => write!(
+                f,
+                "The upstream didn't answer within {}s",
+                limit.as_secs_f32()
+            ),
+            ApiError::Parse => write!(f, "The upstream's answer isn't Gemini JSON"),
+            ApiError::EmptyResponse => write!(f, "The upstream's answer has no text"),
+            ApiError::Interrupted(reason) => write!(f, "{}", reason),
+            ApiError::Upstream { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+// `64 KB` for whole kilobytes, like the defaults; bytes otherwise
+fn size(bytes: usize) -> String {
+    if bytes >= 1024 && bytes.is_multiple_of(1024) {
+        format!("{} KB", bytes / 1024)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::MissingParameter(_)
+            | ApiError::InvalidParameter(_)
+            | ApiError::UnknownModel { .. }
+            | ApiError::InvalidBody(_)
+            | ApiError::PromptTooLong(_) => StatusCode::BAD_REQUEST,
+            ApiError::BodyTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::RateLimited(_) => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::MissingApiKey => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            ApiError::Unreachable
+            | ApiError::Parse
+            | ApiError::EmptyResponse
+            | ApiError::Interrupted(_) => StatusCode::BAD_GATEWAY,
+            // The upstream's status is passed on, unless it isn't a valid one
+            ApiError::Upstream { status, .. } => {
+                StatusCode::from_u16(*status).unwrap_or(StatusCode::BAD_GATEWAY)
+            }
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if let ApiError::RateLimited(seconds) = self {
+            response.insert_header((header::RETRY_AFTER, seconds.to_string()));
+        }
+        response.json(self.to_json())
+    }
+}
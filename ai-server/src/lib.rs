@@ -0,0 +1,460 @@
+//! The routes of ai-server, a small HTTP front end for the Gemini proxy at
+//! `https://gemini.weolopez.com/proxy`. `main.rs` only starts the server; everything here can
+//! be tested with `actix_web::test` against a fake upstream.
+//!
+//! - `POST /prompt` with a JSON body `{"text": "...", "model": "...", "temperature": 0.7}`
+//! - `GET /?text=...&model=...&temperature=...`, the same request in the query string
+//!
+//! Both answer with the plain text of Gemini's reply, or stream it as Server-Sent Events when
+//! the client sends `Accept: text/event-stream` or adds `?stream=1` (see `sse.rs`).
+//!
+//! Whole answers are cached (see `cache.rs`): the `X-Cache` header says `HIT` or `MISS`, and
+//! `?no_cache=1` asks for a fresh answer. Clients over their rate limit get 429 (see
+//! `rate_limit.rs`).
+//!
+//! `model` must be one of `ALLOWED_MODELS` (see `models.rs`, and `GET /models` for the list);
+//! a request without one uses the first.
+//!
+//! Prompts are size-limited and cleaned of control characters before they are sent (see
+//! `limits.rs`).
+//!
+//! Every error is JSON, `{"error": {"code": "...", "message": "..."}}` (see `error.rs`).
+//!
+//! `GET /metrics` has request, cache and upstream latency counters for Prometheus
+//! (`metrics.rs`), and every request is logged without its prompt (`logging.rs`).
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use actix_web::error::JsonPayloadError;
+use actix_web::http::header;
+use actix_web::middleware::from_fn;
+use actix_web::{App, HttpRequest, HttpResponse, web};
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing_actix_web::TracingLogger;
+
+mod cache;
+mod config;
+mod error;
+mod limits;
+mod logging;
+mod metrics;
+mod models;
+mod rate_limit;
+mod sse;
+
+pub use cache::{DEFAULT_CACHE_SIZE, DEFAULT_CACHE_TTL, ResponseCache};
+pub use config::{Config, ConfigError, DEFAULT_BIND_ADDR};
+pub use error::ApiError;
+pub use limits::{DEFAULT_MAX_PROMPT_BYTES, Limits, normalize};
+pub use logging::{RequestSpan, init_logging};
+pub use metrics::Metrics;
+pub use models::AllowedModels;
+pub use rate_limit::{DEFAULT_BURST, DEFAULT_RATE_PER_MINUTE, RateLimiter};
+
+/// Where prompts are sent unless `UPSTREAM_URL` says otherwise
+pub const DEFAULT_UPSTREAM_URL: &str = "https://gemini.weolopez.com/proxy";
+
+/// How long to wait for the upstream when `UPSTREAM_TIMEOUT_SECS` isn't set
+pub const DEFAULT_UPSTREAM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The only allowed model, and so the default, when `ALLOWED_MODELS` isn't set
+pub const DEFAULT_MODEL: &str = "gemini-2.0-flash";
+
+/// The largest `POST /prompt` body accepted when `MAX_BODY_BYTES` isn't set: 64 KB.
+/// Anything bigger gets 413.
+pub const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// A prompt, from the JSON body of `POST /prompt` or the query string of `GET /`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct PromptRequest {
+    /// The prompt. `#[serde(default)]` turns a missing field into "", so both get the same
+    /// 400 answer from `validate`.
+    #[serde(default)]
+    pub text: String,
+    /// The Gemini model, e.g. `gemini-2.0-flash`; the first allowed model when `None`
+    #[serde(default)]
+    pub model: Option<String>,
+    /// From 0.0 to 2.0; Gemini's default when `None`
+    #[serde(default)]
+    pub temperature: Option<f32>,
+}
+
+impl PromptRequest {
+    /// Check the request before anything is sent upstream. `model` is checked against the
+    /// allowlist separately, by `AllowedModels::resolve`.
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if self.text.trim().is_empty() {
+            return Err(ApiError::MissingParameter("text"));
+        }
+        if let Some(temperature) = self.temperature
+            && !(0.0..=2.0).contains(&temperature)
+        {
+            return Err(ApiError::InvalidParameter(
+                "'temperature' must be from 0.0 to 2.0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The upstream proxy: where prompts go and the key that goes with them. Shared by all
+/// requests through `web::Data`.
+#[derive(Clone)]
+pub struct Upstream {
+    client: Client,
+    url: String,
+    api_key: Option<String>,
+    timeout: Duration,
+}
+
+impl Upstream {
+    /// Send prompts to `url` (the proxy's base URL) with `api_key`
+    pub fn new(client: Client, url: impl Into<String>, api_key: Option<String>) -> Upstream {
+        Upstream {
+            client,
+            url: url.into().trim_end_matches('/').to_string(),
+            api_key,
+            timeout: DEFAULT_UPSTREAM_TIMEOUT,
+        }
+    }
+
+    /// Give up on a whole answer after `timeout`; for a streamed one, `timeout` is how long
+    /// to wait for the stream to start
+    pub fn with_timeout(mut self, timeout: Duration) -> Upstream {
+        self.timeout = timeout;
+        self
+    }
+
+    // The proxy's base URL uses its default model; `<base>/<model>` picks another, like
+    // extender's `/proxy/{model}` route
+    fn endpoint(&self, model: Option<&str>) -> String {
+        match model {
+            Some(model) if model != DEFAULT_MODEL => format!("{}/{}", self.url, model),
+            _ => self.url.clone(),
+        }
+    }
+
+    // The streaming form of `endpoint`, like extender's `/proxy/stream` and
+    // `/proxy/{model}/stream`
+    fn stream_endpoint(&self, model: Option<&str>) -> String {
+        format!("{}/stream", self.endpoint(model))
+    }
+
+    // The POST of `request` to `url`, ready to send
+    fn post(&self, url: String, request: &PromptRequest) -> Result<RequestBuilder, ApiError> {
+        let api_key = self.api_key.as_ref().ok_or(ApiError::MissingApiKey)?;
+        let mut payload = json!({
+            "contents": [
+                { "parts": [ { "text": request.text } ] }
+            ]
+        });
+        if let Some(temperature) = request.temperature {
+            payload["generationConfig"] = json!({ "temperature": temperature });
+        }
+
+        Ok(self
+            .client
+            .post(url)
+            .header("X-Gemini-API-Key", api_key)
+            .json(&payload))
+    }
+
+    fn request_error(&self, error: reqwest::Error) -> ApiError {
+        if error.is_timeout() {
+            ApiError::Timeout(self.timeout)
+        } else {
+            ApiError::Unreachable
+        }
+    }
+
+    // Only a success status comes back as Ok
+    async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, ApiError> {
+        if !response.status().is_success() {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApiError::from_upstream(status, &body));
+        }
+        Ok(response)
+    }
+
+    /// Send `request` upstream and return the text of the answer
+    pub async fn generate(&self, request: &PromptRequest) -> Result<String, ApiError> {
+        // The request's timeout covers everything up to the last byte of the body
+        let response = self
+            .post(self.endpoint(request.model.as_deref()), request)?
+            .timeout(self.timeout)
+            .send()
+            .await
+            .map_err(|e| self.request_error(e))?;
+        let response = Upstream::check_status(response).await?;
+        let body: serde_json::Value = response.json().await.map_err(|e| {
+            if e.is_timeout() {
+                ApiError::Timeout(self.timeout)
+            } else {
+                ApiError::Parse
+            }
+        })?;
+        // Extract only the content text. Gemini leaves it out when it blocked the answer, and
+        // an empty 200 would look like an answer that was nothing
+        body["candidates"]
+            .get(0)
+            .and_then(|c| c["content"]["parts"].get(0))
+            .and_then(|p| p["text"].as_str())
+            .map(str::to_string)
+            .ok_or(ApiError::EmptyResponse)
+    }
+
+    /// Start streaming the answer to `request`. Errors before the first byte (no key, an error
+    /// status) are returned here, so they can still be sent as normal HTTP errors.
+    pub async fn stream(&self, request: &PromptRequest) -> Result<reqwest::Response, ApiError> {
+        // A long answer can stream for minutes, so `timeout` only bounds the wait for the
+        // response headers; after that, `sse::IDLE_TIMEOUT` bounds each gap between chunks
+        let send = self
+            .post(self.stream_endpoint(request.model.as_deref()), request)?
+            .send();
+        let response = tokio::time::timeout(self.timeout, send)
+            .await
+            .map_err(|_| ApiError::Timeout(self.timeout))?
+            .map_err(|e| self.request_error(e))?;
+        Upstream::check_status(response).await
+    }
+}
+
+/// Whether a switch like `?stream=1` is on, on either route
+fn query_flag(http: &HttpRequest, name: &str) -> bool {
+    let query = web::Query::<HashMap<String, String>>::from_query(http.query_string());
+    query.is_ok_and(|query| {
+        query
+            .get(name)
+            .is_some_and(|value| value == "1" || value == "true")
+    })
+}
+
+/// Whether the client asked for a streamed answer: `Accept: text/event-stream` (what the
+/// browser's `EventSource` sends) or `?stream=1`
+fn wants_stream(http: &HttpRequest) -> bool {
+    let accepts_sse = http
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"));
+    accepts_sse || query_flag(http, "stream")
+}
+
+// Call the upstream through `call`, timing it for /metrics and the log
+async fn timed<T>(
+    metrics: &Metrics,
+    call: impl Future<Output = Result<T, ApiError>>,
+) -> Result<T, ApiError> {
+    let started = Instant::now();
+    let result = call.await;
+    // Without a key nothing was sent, so there's no latency to record
+    if !matches!(result, Err(ApiError::MissingApiKey)) {
+        let elapsed = started.elapsed();
+        metrics.record_upstream(elapsed);
+        logging::record_upstream_ms(elapsed.as_millis());
+    }
+    result
+}
+
+/// Clean and validate `request`, fill in the default model, send it upstream and answer with the text, all at once or streamed.
+///
+/// Whole answers go through the cache: a cached one is returned without calling upstream,
+/// and a new one is stored once it arrived successfully. `?no_cache=1` skips the lookup but
+/// still stores the fresh answer. Streamed answers always go upstream.
+async fn answer(
+    http: &HttpRequest,
+    mut request: PromptRequest,
+    upstream: &Upstream,
+    models: &AllowedModels,
+    limits: &Limits,
+    cache: &ResponseCache,
+    metrics: &Metrics,
+) -> Result<HttpResponse, ApiError> {
+    logging::record_prompt_len(request.text.len());
+    request.text = limits.prompt(&request.text)?;
+    request.validate()?;
+    // Naming the model even when the client didn't also gives "no model" and "the default
+    // model" the same cache key
+    request.model = Some(models.resolve(request.model.as_deref())?);
+    if wants_stream(http) {
+        let response = timed(metrics, upstream.stream(&request)).await?;
+        return Ok(HttpResponse::Ok()
+            .content_type("text/event-stream")
+            // Proxies must pass each event on as it comes rather than wait for the whole body
+            .insert_header((header::CACHE_CONTROL, "no-cache"))
+            .insert_header(("X-Accel-Buffering", "no"))
+            .streaming(sse::relay(response)));
+    }
+    if !query_flag(http, "no_cache")
+        && let Some(text) = cache.get(&request)
+    {
+        metrics.record_cache(true);
+        logging::record_cache(true);
+        return Ok(HttpResponse::Ok()
+            .insert_header(("X-Cache", "HIT"))
+            .body(text));
+    }
+    metrics.record_cache(false);
+    logging::record_cache(false);
+    // `?` returns errors before the insert, so they are never cached
+    let text = timed(metrics, upstream.generate(&request)).await?;
+    cache.insert(&request, text.clone());
+    Ok(HttpResponse::Ok()
+        .insert_header(("X-Cache", "MISS"))
+        .body(text))
+}
+
+/// `POST /prompt`: the prompt as JSON. `web::Json` parses the body into `PromptRequest`
+/// before this runs; a body that can't be parsed is answered by `json_error`.
+async fn post_prompt(
+    http: HttpRequest,
+    body: web::Json<PromptRequest>,
+    upstream: web::Data<Upstream>,
+    models: web::Data<AllowedModels>,
+    limits: web::Data<Limits>,
+    cache: web::Data<ResponseCache>,
+    metrics: web::Data<Metrics>,
+) -> Result<HttpResponse, ApiError> {
+    answer(
+        &http,
+        body.into_inner(),
+        &upstream,
+        &models,
+        &limits,
+        &cache,
+        &metrics,
+    )
+    .await
+}
+
+/// `GET /?text=...`: builds the same `PromptRequest` from the query string
+async fn get_prompt(
+    http: HttpRequest,
+    query: web::Query<HashMap<String, String>>,
+    upstream: web::Data<Upstream>,
+    models: web::Data<AllowedModels>,
+    limits: web::Data<Limits>,
+    cache: web::Data<ResponseCache>,
+    metrics: web::Data<Metrics>,
+) -> Result<HttpResponse, ApiError> {
+    let temperature = match query.get("temperature").map(|t| t.parse::<f32>()) {
+        Some(Ok(temperature)) => Some(temperature),
+        Some(Err(_)) => {
+            return Err(ApiError::InvalidParameter(
+                "'temperature' must be a number".to_string(),
+            ));
+        }
+        None => None,
+    };
+    let request = PromptRequest {
+        text: query.get("text").cloned().unwrap_or_default(),
+        model: query.get("model").cloned(),
+        temperature,
+    };
+    answer(
+        &http, request, &upstream, &models, &limits, &cache, &metrics,
+    )
+    .await
+}
+
+/// Errors for bodies `web::Json` rejects: 413 when too big, 400 otherwise (not JSON, wrong
+/// field types, ...)
+fn json_error(error: JsonPayloadError, _request: &HttpRequest) -> actix_web::Error {
+    match &error {
+        JsonPayloadError::Overflow { limit }
+        | JsonPayloadError::OverflowKnownLength { limit, .. } => {
+            ApiError::BodyTooLarge(*limit).into()
+        }
+        _ => ApiError::InvalidBody(error.to_string()).into(),
+    }
+}
+
+/// What the app's handlers share. It is built once, outside `HttpServer::new`: that closure
+/// runs once per worker thread, and cloning the state into each worker's app only copies the
+/// `web::Data` pointers, so all workers use the same cache and rate limits.
+#[derive(Clone)]
+pub struct AppState {
+    pub upstream: Upstream,
+    pub models: web::Data<AllowedModels>,
+    pub limits: web::Data<Limits>,
+    pub cache: web::Data<ResponseCache>,
+    pub limiter: Option<web::Data<RateLimiter>>,
+    pub metrics: web::Data<Metrics>,
+}
+
+impl AppState {
+    /// Just the upstream: only `DEFAULT_MODEL`, the default limits, no caching and no rate
+    /// limit
+    pub fn new(upstream: Upstream) -> AppState {
+        AppState {
+            upstream,
+            models: web::Data::new(AllowedModels::default()),
+            limits: web::Data::new(Limits::default()),
+            cache: web::Data::new(ResponseCache::new(0, Duration::ZERO)),
+            limiter: None,
+            metrics: web::Data::new(Metrics::default()),
+        }
+    }
+
+    pub fn with_models(mut self, models: web::Data<AllowedModels>) -> AppState {
+        self.models = models;
+        self
+    }
+
+    pub fn with_limits(mut self, limits: web::Data<Limits>) -> AppState {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_cache(mut self, cache: web::Data<ResponseCache>) -> AppState {
+        self.cache = cache;
+        self
+    }
+
+    pub fn with_rate_limit(mut self, limiter: web::Data<RateLimiter>) -> AppState {
+        self.limiter = Some(limiter);
+        self
+    }
+}
+
+/// The app, for `HttpServer::new` or a test
+pub fn create_app(
+    state: AppState,
+) -> App<
+    impl actix_web::dev::ServiceFactory<
+        actix_web::dev::ServiceRequest,
+        Config = (),
+        Response = actix_web::dev::ServiceResponse<impl actix_web::body::MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let max_body_bytes = state.limits.max_body_bytes;
+    let mut app = App::new()
+        .app_data(web::Data::new(state.upstream))
+        .app_data(state.models)
+        .app_data(state.limits)
+        .app_data(state.cache)
+        .app_data(state.metrics);
+    if let Some(limiter) = state.limiter {
+        app = app.app_data(limiter);
+    }
+    // The last `wrap` sees each request first: the logger, then the request counter (so it
+    // counts the rate limiter's 429s), then the rate limiter
+    app.wrap(from_fn(rate_limit::rate_limit))
+        .wrap(from_fn(metrics::count_requests))
+        .wrap(TracingLogger::<RequestSpan>::new())
+        .app_data(
+            web::JsonConfig::default()
+                .limit(max_body_bytes)
+                .error_handler(json_error),
+        )
+        .route("/", web::get().to(get_prompt))
+        .route("/prompt", web::post().to(post_prompt))
+        .route("/models", web::get().to(models::list_models))
+        .route("/metrics", web::get().to(metrics::metrics_endpoint))
+}
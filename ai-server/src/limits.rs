@@ -0,0 +1,52 @@
+// How much a client may send. Every prompt is relayed upstream and billed to the server's key,
+// so there are two caps:
+//
+//   MAX_BODY_BYTES    the whole `POST /prompt` body (64 KB); over it is 413 before any parsing
+//   MAX_PROMPT_BYTES  the prompt's `text`, on either route (32 KB); over it is 400
+//
+// The text is also cleaned before it goes anywhere: control characters (NUL, escape codes, ...)
+// are dropped and line endings become `\n`, so a prompt pasted from a terminal or a Windows
+// file asks the same thing, and hits the same cache entry, as a plain one.
+
+use crate::{ApiError, MAX_BODY_BYTES};
+
+/// The prompt length allowed when `MAX_PROMPT_BYTES` isn't set: 32 KB
+pub const DEFAULT_MAX_PROMPT_BYTES: usize = 32 * 1024;
+
+/// The size limits, shared by all workers through `web::Data`. Tests lower them to check the
+/// boundaries without sending megabytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    pub max_prompt_bytes: usize,
+    pub max_body_bytes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_prompt_bytes: DEFAULT_MAX_PROMPT_BYTES,
+            max_body_bytes: MAX_BODY_BYTES,
+        }
+    }
+}
+
+impl Limits {
+    /// `text` as it is sent upstream: cleaned, and no longer than `max_prompt_bytes`
+    pub fn prompt(&self, text: &str) -> Result<String, ApiError> {
+        let text = normalize(text);
+        if text.len() > self.max_prompt_bytes {
+            return Err(ApiError::PromptTooLong(self.max_prompt_bytes));
+        }
+        Ok(text)
+    }
+}
+
+/// `text` with `\r\n` and lone `\r` turned into `\n`, and every other control character but
+/// tab removed
+pub fn normalize(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .chars()
+        .filter(|c| !c.is_control() || matches!(c, '\n' | '\t'))
+        .collect()
+}
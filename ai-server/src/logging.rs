@@ -0,0 +1,66 @@
+// One log line per request, through `tracing`. tracing-actix-web's `TracingLogger` opens a
+// span for each request and keeps it open while the handler runs; the handler fills in what
+// it learns (prompt length, upstream latency) and `on_request_end` logs the finished span:
+//
+//   INFO request{method=POST path=/prompt prompt_len=24 cache="miss" upstream_ms=812 status=200}: done
+//
+// The default span of tracing-actix-web records the whole URL, query string included, and a
+// GET prompt is in the query string. This span records only the path, and the prompt only as
+// its length: prompts can hold anything the user typed, so they never go into the logs.
+
+use actix_web::Error;
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use tracing::Span;
+use tracing::field::Empty;
+use tracing_actix_web::RootSpanBuilder;
+
+/// The span for each request, for `TracingLogger::<RequestSpan>::new()`
+pub struct RequestSpan;
+
+impl RootSpanBuilder for RequestSpan {
+    fn on_request_start(request: &ServiceRequest) -> Span {
+        tracing::info_span!(
+            "request",
+            method = %request.method(),
+            path = %request.path(),
+            prompt_len = Empty,
+            cache = Empty,
+            upstream_ms = Empty,
+            status = Empty,
+        )
+    }
+
+    fn on_request_end<B: MessageBody>(span: Span, outcome: &Result<ServiceResponse<B>, Error>) {
+        let status = match outcome {
+            Ok(response) => response.status(),
+            Err(error) => error.as_response_error().status_code(),
+        };
+        span.record("status", status.as_u16());
+        span.in_scope(|| tracing::info!("done"));
+    }
+}
+
+/// Fields the handlers record on the current request's span
+pub(crate) fn record_prompt_len(len: usize) {
+    Span::current().record("prompt_len", len);
+}
+
+pub(crate) fn record_cache(hit: bool) {
+    Span::current().record("cache", if hit { "hit" } else { "miss" });
+}
+
+pub(crate) fn record_upstream_ms(ms: u128) {
+    Span::current().record("upstream_ms", ms as u64);
+}
+
+/// Print log lines to stderr. `RUST_LOG` picks what is shown (default `info`), e.g.
+/// `RUST_LOG=ai_server=debug,actix_web=info`.
+pub fn init_logging() {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+}
@@ -0,0 +1,145 @@
+// Counters for `GET /metrics`, in the Prometheus text format so Prometheus (or `curl`) can
+// read them:
+//
+//   ai_server_requests_total{status="200"} 42
+//   ai_server_cache_hits_total 10
+//   ai_server_upstream_duration_seconds_bucket{le="1"} 30
+//
+// The counters are atomics, so workers update them without taking turns. Only the per-status
+// map needs a lock, since a new status code adds an entry.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{Error, HttpResponse, web};
+
+// Upper bounds of the latency histogram's buckets, in seconds. Gemini answers take from a
+// fraction of a second to tens of seconds.
+const LATENCY_BUCKETS: [f64; 9] = [0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+/// The server's counters, shared by all workers through `web::Data`.
+#[derive(Default)]
+pub struct Metrics {
+    requests: Mutex<BTreeMap<u16, u64>>,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    // How many upstream calls took at most LATENCY_BUCKETS[i]; the `+Inf` bucket is `calls`
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS.len()],
+    latency_micros: AtomicU64,
+    calls: AtomicU64,
+}
+
+impl Metrics {
+    /// Count a response with `status`
+    pub fn record_request(&self, status: u16) {
+        *self.requests.lock().unwrap().entry(status).or_default() += 1;
+    }
+
+    /// Count a cache lookup
+    pub fn record_cache(&self, hit: bool) {
+        let counter = if hit {
+            &self.cache_hits
+        } else {
+            &self.cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Add an upstream call that took `elapsed` to the latency histogram
+    pub fn record_upstream(&self, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f64();
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(&self.latency_buckets) {
+            if seconds <= *bound {
+                count.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.latency_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+        self.calls.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Everything, in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        // Writing to a String can't fail, so the fmt::Results are ignored
+        let _ = writeln!(
+            out,
+            "# HELP ai_server_requests_total Responses sent, by status code"
+        );
+        let _ = writeln!(out, "# TYPE ai_server_requests_total counter");
+        for (status, count) in self.requests.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "ai_server_requests_total{{status=\"{}\"}} {}",
+                status, count
+            );
+        }
+
+        for (name, help, counter) in [
+            (
+                "cache_hits",
+                "Answers served from the cache",
+                &self.cache_hits,
+            ),
+            (
+                "cache_misses",
+                "Answers not in the cache",
+                &self.cache_misses,
+            ),
+        ] {
+            let _ = writeln!(out, "# HELP ai_server_{}_total {}", name, help);
+            let _ = writeln!(out, "# TYPE ai_server_{}_total counter", name);
+            let _ = writeln!(
+                out,
+                "ai_server_{}_total {}",
+                name,
+                counter.load(Ordering::Relaxed)
+            );
+        }
+
+        let histogram = "ai_server_upstream_duration_seconds";
+        let _ = writeln!(out, "# HELP {} Time until the upstream answered", histogram);
+        let _ = writeln!(out, "# TYPE {} histogram", histogram);
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(&self.latency_buckets) {
+            let count = count.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", histogram, bound, count);
+        }
+        let calls = self.calls.load(Ordering::Relaxed);
+        let seconds = self.latency_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", histogram, calls);
+        let _ = writeln!(out, "{}_sum {}", histogram, seconds);
+        let _ = writeln!(out, "{}_count {}", histogram, calls);
+        out
+    }
+}
+
+/// `GET /metrics`
+pub(crate) async fn metrics_endpoint(metrics: web::Data<Metrics>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics.render())
+}
+
+/// The middleware that counts every response by status, including the ones other
+/// middleware (like the rate limiter) sent without reaching a handler
+pub(crate) async fn count_requests(
+    request: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let metrics = request.app_data::<web::Data<Metrics>>().cloned();
+    let result = next.call(request).await;
+    if let Some(metrics) = metrics {
+        let status = match &result {
+            Ok(response) => response.status(),
+            Err(error) => error.as_response_error().status_code(),
+        };
+        metrics.record_request(status.as_u16());
+    }
+    Ok(result?.map_into_boxed_body())
+}
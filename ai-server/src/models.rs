@@ -0,0 +1,84 @@
+// The models clients may ask for. Every prompt runs on the server's own API key, so the list
+// (from `ALLOWED_MODELS`) keeps clients on the models the key's owner is willing to pay for.
+// The first model is the default, for requests that don't name one:
+//
+//   ALLOWED_MODELS=gemini-2.0-flash,gemini-1.5-pro
+//
+// `GET /models` shows the list, so a client can offer a choice instead of guessing names.
+
+use actix_web::{HttpResponse, web};
+use serde_json::json;
+
+use crate::{ApiError, DEFAULT_MODEL};
+
+/// The allowlist of models, shared by all workers through `web::Data`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllowedModels {
+    // Never empty; `names[0]` is the default
+    names: Vec<String>,
+}
+
+impl AllowedModels {
+    /// Allow `names`, the first being the default. `None` when the list is empty or a name
+    /// isn't a plain model name (the name becomes part of the upstream URL).
+    pub fn new<S: Into<String>>(names: impl IntoIterator<Item = S>) -> Option<AllowedModels> {
+        let names: Vec<String> = names.into_iter().map(Into::into).collect();
+        let plain = |name: &String| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_'))
+        };
+        if names.is_empty() || !names.iter().all(plain) {
+            return None;
+        }
+        Some(AllowedModels { names })
+    }
+
+    /// A comma separated list, as in `ALLOWED_MODELS`; spaces and empty entries are skipped
+    pub fn parse(list: &str) -> Option<AllowedModels> {
+        AllowedModels::new(
+            list.split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty()),
+        )
+    }
+
+    /// The model for requests that don't name one
+    pub fn default_model(&self) -> &str {
+        &self.names[0]
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The model to use for a request asking for `model`
+    pub fn resolve(&self, model: Option<&str>) -> Result<String, ApiError> {
+        match model {
+            None => Ok(self.default_model().to_string()),
+            Some(model) if self.names.iter().any(|name| name == model) => Ok(model.to_string()),
+            Some(model) => Err(ApiError::UnknownModel {
+                model: model.to_string(),
+                allowed: self.names.clone(),
+            }),
+        }
+    }
+}
+
+/// Only `DEFAULT_MODEL`
+impl Default for AllowedModels {
+    fn default() -> AllowedModels {
+        AllowedModels {
+            names: vec![DEFAULT_MODEL.to_string()],
+        }
+    }
+}
+
+/// `GET /models`: `{"models": [...], "default": "..."}`
+pub(crate) async fn list_models(models: web::Data<AllowedModels>) -> HttpResponse {
+    HttpResponse::Ok().json(json!({
+        "models": models.names(),
+        "default": models.default_model(),
+    }))
+}
@@ -0,0 +1,147 @@
+// Per-client rate limiting. Every prompt is paid for with the server's own GEMINI_API_KEY,
+// so one client (or a script that found the port) mustn't be able to use up the quota.
+//
+// Each client IP gets a token bucket: it holds up to `burst` tokens, refills at `per_minute`
+// tokens a minute, and each request takes one. A client can send `burst` requests at once,
+// then `per_minute` a minute after that. An empty bucket means 429 Too Many Requests, with a
+// `Retry-After` header saying when the next token arrives.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use actix_web::body::{BoxBody, MessageBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::middleware::Next;
+use actix_web::{Error, ResponseError, web};
+
+use crate::ApiError;
+
+/// Requests a minute per client when `RATE_LIMIT_PER_MINUTE` isn't set
+pub const DEFAULT_RATE_PER_MINUTE: u32 = 30;
+/// Requests a client can send at once when `RATE_LIMIT_BURST` isn't set
+pub const DEFAULT_BURST: u32 = 10;
+
+// How often buckets of clients that went quiet are dropped
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+struct Buckets {
+    by_ip: HashMap<IpAddr, Bucket>,
+    pruned: Instant,
+}
+
+/// The buckets of all clients, shared by all workers through `web::Data`.
+pub struct RateLimiter {
+    buckets: Mutex<Buckets>,
+    // Tokens a second
+    rate: f64,
+    burst: f64,
+    trust_proxy: bool,
+}
+
+impl RateLimiter {
+    /// Allow `burst` requests at once and `per_minute` a minute after that, per client IP.
+    /// Both are at least 1.
+    pub fn new(per_minute: u32, burst: u32) -> RateLimiter {
+        RateLimiter {
+            buckets: Mutex::new(Buckets {
+                by_ip: HashMap::new(),
+                pruned: Instant::now(),
+            }),
+            rate: f64::from(per_minute.max(1)) / 60.0,
+            burst: f64::from(burst.max(1)),
+            trust_proxy: false,
+        }
+    }
+
+    /// Take the client IP from `X-Forwarded-For`. Only for a server behind a reverse proxy
+    /// that sets it: otherwise any client could send a made-up address with each request
+    /// and never be limited.
+    pub fn trust_proxy(mut self, trust: bool) -> RateLimiter {
+        self.trust_proxy = trust;
+        self
+    }
+
+    /// Take a token from `ip`'s bucket. When it is empty, Err holds how long until the next
+    /// token.
+    pub fn check(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        if now.saturating_duration_since(buckets.pruned) >= PRUNE_INTERVAL {
+            self.prune(&mut buckets, now);
+        }
+        let bucket = buckets.by_ip.entry(ip).or_insert(Bucket {
+            tokens: self.burst,
+            refilled: now,
+        });
+        self.refill(bucket, now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate))
+        }
+    }
+
+    fn refill(&self, bucket: &mut Bucket, now: Instant) {
+        let elapsed = now.saturating_duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.refilled = now;
+    }
+
+    // Without this, every address that ever sent a request would stay in the map. A bucket
+    // that has refilled completely is the same as no bucket, so it can go.
+    fn prune(&self, buckets: &mut Buckets, now: Instant) {
+        buckets.by_ip.retain(|_, bucket| {
+            self.refill(bucket, now);
+            bucket.tokens < self.burst
+        });
+        buckets.pruned = now;
+    }
+
+    /// How many clients have a bucket
+    pub fn clients(&self) -> usize {
+        self.buckets.lock().unwrap().by_ip.len()
+    }
+
+    // The address to limit: the TCP peer, or with `trust_proxy` the last address in
+    // `X-Forwarded-For`. The last one, because the proxy appends the address it saw to
+    // whatever the client sent; earlier entries come from the client and can be anything.
+    fn client_ip(&self, request: &ServiceRequest) -> Option<IpAddr> {
+        if self.trust_proxy
+            && let Some(ip) = request
+                .headers()
+                .get("X-Forwarded-For")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.rsplit(',').next())
+                .and_then(|last| last.trim().parse().ok())
+        {
+            return Some(ip);
+        }
+        request.peer_addr().map(|addr| addr.ip())
+    }
+}
+
+/// The middleware, for `App::wrap(from_fn(rate_limit))`. Without a `RateLimiter` in the app
+/// data every request is let through.
+pub(crate) async fn rate_limit(
+    request: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    let limiter = request.app_data::<web::Data<RateLimiter>>().cloned();
+    if let Some(limiter) = limiter
+        && let Some(ip) = limiter.client_ip(&request)
+        && let Err(wait) = limiter.check(ip, Instant::now())
+    {
+        // Retry-After is in whole seconds; round up so a client that waits that long succeeds
+        let seconds = wait.as_secs_f64().ceil().max(1.0) as u64;
+        let response = ApiError::RateLimited(seconds).error_response();
+        return Ok(request.into_response(response));
+    }
+    Ok(next.call(request).await?.map_into_boxed_body())
+}
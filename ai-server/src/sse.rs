@@ -0,0 +1,170 @@
+// Streaming answers: relay Gemini's `streamGenerateContent?alt=sse` output to the browser as
+// Server-Sent Events while it is still being generated.
+//
+// Gemini sends `data: {<response chunk>}` lines; the browser gets one event per text delta:
+//
+//   data: {"text":"Once upon"}
+//
+//   data: {"text":" a time"}
+//
+//   event: done
+//   data: {}
+//
+// A stream that stops early (upstream disconnect, nothing received for `IDLE_TIMEOUT`, a
+// chunk that isn't Gemini JSON) ends with `event: error` instead of `done`, so a client
+// never waits for an answer that isn't coming.
+
+use std::convert::Infallible;
+use std::time::Duration;
+
+use actix_web::web::Bytes;
+use futures_util::stream::{self, BoxStream, Stream, StreamExt};
+use serde_json::{Value, json};
+
+use crate::ApiError;
+
+/// How long the upstream may go quiet between chunks before the stream is given up
+pub(crate) const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+// Reads `data:` lines out of an SSE body. Network chunks can end anywhere, even mid-line,
+// so bytes stay buffered until their line is complete.
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut payloads = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            payloads.extend(data_payload(&line));
+        }
+        payloads
+    }
+
+    // A last line without a trailing newline
+    fn finish(&mut self) -> Option<String> {
+        data_payload(&std::mem::take(&mut self.buffer))
+    }
+}
+
+fn data_payload(line: &[u8]) -> Option<String> {
+    let line = String::from_utf8_lossy(line);
+    let payload = line
+        .trim_end_matches(['\r', '\n'])
+        .strip_prefix("data:")?
+        .trim_start();
+    (!payload.is_empty()).then(|| payload.to_string())
+}
+
+/// One Gemini response chunk: its text, and whether it is the last one (has a finishReason)
+fn parse_chunk(payload: &str) -> Option<(String, bool)> {
+    let chunk: Value = serde_json::from_str(payload).ok()?;
+    let candidate = &chunk["candidates"][0];
+    let text = candidate["content"]["parts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|part| part["text"].as_str())
+        .collect();
+    Some((text, candidate["finishReason"].is_string()))
+}
+
+/// A text delta event
+pub(crate) fn text_event(text: &str) -> String {
+    // JSON-encoding the text keeps its newlines from ending the event early
+    format!("data: {}\n\n", json!({ "text": text }))
+}
+
+/// The last event of a complete answer
+pub(crate) fn done_event() -> String {
+    "event: done\ndata: {}\n\n".to_string()
+}
+
+/// The last event of an answer that didn't complete, with the same JSON as an error response
+pub(crate) fn error_event(error: &ApiError) -> String {
+    format!("event: error\ndata: {}\n\n", error.to_json())
+}
+
+struct Relay {
+    body: BoxStream<'static, reqwest::Result<Bytes>>,
+    parser: SseParser,
+    // Gemini marks its last chunk with a finishReason; a body that ends without one was cut off
+    finished: bool,
+    closed: bool,
+}
+
+impl Relay {
+    // The events for some upstream payloads. Err means the stream has to end.
+    fn events(&mut self, payloads: impl IntoIterator<Item = String>) -> Result<String, String> {
+        let mut events = String::new();
+        for payload in payloads {
+            let Some((text, finished)) = parse_chunk(&payload) else {
+                events.push_str(&error_event(&ApiError::Parse));
+                return Err(events);
+            };
+            if !text.is_empty() {
+                events.push_str(&text_event(&text));
+            }
+            self.finished |= finished;
+        }
+        Ok(events)
+    }
+}
+
+/// The SSE body for a successful upstream streaming response. Each upstream chunk becomes one
+/// body chunk, so actix writes it to the client as soon as it arrives.
+pub(crate) fn relay(response: reqwest::Response) -> impl Stream<Item = Result<Bytes, Infallible>> {
+    let relay = Relay {
+        body: response.bytes_stream().boxed(),
+        parser: SseParser::default(),
+        finished: false,
+        closed: false,
+    };
+    stream::unfold(relay, next_chunk)
+}
+
+async fn next_chunk(mut relay: Relay) -> Option<(Result<Bytes, Infallible>, Relay)> {
+    if relay.closed {
+        return None;
+    }
+    loop {
+        let events = match tokio::time::timeout(IDLE_TIMEOUT, relay.body.next()).await {
+            Err(_) => Err(error_event(&ApiError::Timeout(IDLE_TIMEOUT))),
+            Ok(Some(Ok(bytes))) => {
+                let payloads = relay.parser.push(&bytes);
+                relay.events(payloads)
+            }
+            Ok(Some(Err(_))) => Err(error_event(&ApiError::Interrupted(
+                "The upstream disconnected",
+            ))),
+            Ok(None) => {
+                let last = relay.parser.finish();
+                match relay.events(last) {
+                    Ok(mut events) if relay.finished => {
+                        events.push_str(&done_event());
+                        Err(events)
+                    }
+                    Ok(mut events) => {
+                        events.push_str(&error_event(&ApiError::Interrupted(
+                            "The upstream closed the stream before the answer finished",
+                        )));
+                        Err(events)
+                    }
+                    Err(events) => Err(events),
+                }
+            }
+        };
+        // Err: these are the last events. Ok: keep going, skipping chunks with no new text.
+        match events {
+            Ok(events) if events.is_empty() => continue,
+            Ok(events) => return Some((Ok(Bytes::from(events)), relay)),
+            Err(events) => {
+                relay.closed = true;
+                return Some((Ok(Bytes::from(events)), relay));
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use std::time::Duration;
+
+use actix_web::dev::ServiceResponse;
+use actix_web::http::StatusCode;
+use actix_web::{test, web};
+use ai_server::{AllowedModels, AppState, PromptRequest, ResponseCache, Upstream, create_app};
+use reqwest::Client;
+use serde_json::json;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn gemini_reply(text: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "candidates": [ { "content": { "parts": [ { "text": text } ] } } ]
+    }))
+}
+
+// A mock upstream that answers "Hi!" and must be called exactly `calls` times
+async fn upstream_expecting(calls: u64) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(gemini_reply("Hi!"))
+        .expect(calls)
+        .mount(&server)
+        .await;
+    server
+}
+
+fn upstream(server: &MockServer) -> Upstream {
+    Upstream::new(Client::new(), server.uri(), Some("test-key".to_string()))
+}
+
+fn x_cache<B>(response: &ServiceResponse<B>) -> &str {
+    response.headers().get("X-Cache").unwrap().to_str().unwrap()
+}
+
+fn prompt(text: &str) -> PromptRequest {
+    PromptRequest {
+        text: text.to_string(),
+        ..PromptRequest::default()
+    }
+}
+
+#[actix_web::test]
+async fn second_identical_request_is_a_hit() {
+    let server = upstream_expecting(1).await;
+    let cache = web::Data::new(ResponseCache::new(10, Duration::from_secs(60)));
+    let app = test::init_service(create_app(
+        AppState::new(upstream(&server)).with_cache(cache),
+    ))
+    .await;
+
+    let first =
+        test::call_service(&app, test::TestRequest::get().uri("/?text=Hi").to_request()).await;
+    assert_eq!(x_cache(&first), "MISS");
+    assert_eq!(test::read_body(first).await, "Hi!");
+
+    let second =
+        test::call_service(&app, test::TestRequest::get().uri("/?text=Hi").to_request()).await;
+    assert_eq!(x_cache(&second), "HIT");
+    assert_eq!(test::read_body(second).await, "Hi!");
+
+    // Both routes build the same request, so they share cached answers
+    let post = test::TestRequest::post()
+        .uri("/prompt")
+        .set_json(json!({ "text": "Hi" }))
+        .to_request();
+    assert_eq!(x_cache(&test::call_service(&app, post).await), "HIT");
+}
+
+#[actix_web::test]
+async fn model_and_temperature_are_part_of_the_key() {
+    let server = upstream_expecting(3).await;
+    let cache = web::Data::new(ResponseCache::new(10, Duration::from_secs(60)));
+    let models = AllowedModels::new(["gemini-2.0-flash", "gemini-1.5-pro"]).unwrap();
+    let app = test::init_service(create_app(
+        AppState::new(upstream(&server))
+            .with_models(web::Data::new(models))
+            .with_cache(cache),
+    ))
+    .await;
+
+    for uri in [
+        "/?text=Hi",
+        "/?text=Hi&model=gemini-1.5-pro",
+        "/?text=Hi&temperature=1",
+    ] {
+        let response =
+            test::call_service(&app, test::TestRequest::get().uri(uri).to_request()).await;
+        assert_eq!(x_cache(&response), "MISS", "{}", uri);
+    }
+}
+
+#[actix_web::test]
+async fn expired_answers_are_fetched_again() {
+    let server = upstream_expecting(2).await;
+    let cache = web::Data::new(ResponseCache::new(10, Duration::from_millis(200)));
+    let app = test::init_service(create_app(
+        AppState::new(upstream(&server)).with_cache(cache.clone()),
+    ))
+    .await;
+
+    let first =
+        test::call_service(&app, test::TestRequest::get().uri("/?text=Hi").to_request()).await;
+    assert_eq!(x_cache(&first), "MISS");
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let second =
+        test::call_service(&app, test::TestRequest::get().uri("/?text=Hi").to_request()).await;
+    assert_eq!(x_cache(&second), "MISS");
+    // The fresh answer replaced the expired one
+    assert_eq!(cache.len(), 1);
+}
+
+#[actix_web::test]
+async fn no_cache_asks_upstream_again() {
+    let server = upstream_expecting(2).await;
+    let cache = web::Data::new(ResponseCache::new(10, Duration::from_secs(60)));
+    let app = test::init_service(create_app(
+        AppState::new(upstream(&server)).with_cache(cache),
+    ))
+    .await;
+
+    test::call_service(&app, test::TestRequest::get().uri("/?text=Hi").to_request()).await;
+    let bypass = test::TestRequest::post()
+        .uri("/prompt?no_cache=1")
+        .set_json(json!({ "text": "Hi" }))
+        .to_request();
+    assert_eq!(x_cache(&test::call_service(&app, bypass).await), "MISS");
+}
+
+#[actix_web::test]
+async fn errors_are_never_cached() {
+    let server = MockServer::start().await;
+    // The first call fails, later ones succeed
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503).set_body_string("Overloaded"))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(gemini_reply("Hi!"))
+        .mount(&server)
+        .await;
+    let cache = web::Data::new(ResponseCache::new(10, Duration::from_secs(60)));
+    let app = test::init_service(create_app(
+        AppState::new(upstream(&server)).with_cache(cache.clone()),
+    ))
+    .await;
+
+    let failed =
+        test::call_service(&app, test::TestRequest::get().uri("/?text=Hi").to_request()).await;
+    assert_eq!(failed.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(cache.is_empty());
+
+    let retried =
+        test::call_service(&app, test::TestRequest::get().uri("/?text=Hi").to_request()).await;
+    assert_eq!(retried.status(), StatusCode::OK);
+    assert_eq!(x_cache(&retried), "MISS");
+}
+
+#[actix_web::test]
+async fn the_least_recently_used_answer_is_dropped_first() {
+    let cache = ResponseCache::new(2, Duration::from_secs(60));
+    cache.insert(&prompt("a"), "A".to_string());
+    cache.insert(&prompt("b"), "B".to_string());
+    // Reading "a" makes "b" the least recently used
+    assert_eq!(cache.get(&prompt("a")).as_deref(), Some("A"));
+
+    cache.insert(&prompt("c"), "C".to_string());
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get(&prompt("b")), None);
+    assert_eq!(cache.get(&prompt("a")).as_deref(), Some("A"));
+    assert_eq!(cache.get(&prompt("c")).as_deref(), Some("C"));
+}
+
+#[actix_web::test]
+async fn capacity_zero_turns_caching_off() {
+    let cache = ResponseCache::new(0, Duration::from_secs(60));
+    cache.insert(&prompt("a"), "A".to_string());
+    assert!(cache.is_empty());
+    assert_eq!(cache.get(&prompt("a")), None);
+}
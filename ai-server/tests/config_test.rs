@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use actix_web::http::StatusCode;
+use actix_web::test::{TestRequest, call_service, init_service, read_body_json};
+use ai_server::{Config, DEFAULT_BIND_ADDR, create_app};
+use serde_json::{Value, json};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn config(vars: &[(&str, &str)]) -> Result<Config, ai_server::ConfigError> {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    Config::from_vars(|name| vars.get(name).cloned())
+}
+
+#[test]
+fn defaults_match_the_old_hardcoded_values() {
+    let config = config(&[]).unwrap();
+    assert_eq!(config.bind_addr, DEFAULT_BIND_ADDR);
+    assert_eq!(config.bind_addr, "0.0.0.0:8089");
+    assert_eq!(
+        config.upstream_url.as_str(),
+        "https://gemini.weolopez.com/proxy"
+    );
+    assert_eq!(config.upstream_timeout, Duration::from_secs(30));
+    assert_eq!(config.api_key, None);
+}
+
+#[test]
+fn variables_override_the_defaults() {
+    let config = config(&[
+        ("BIND_ADDR", "127.0.0.1:9000"),
+        ("UPSTREAM_URL", "http://localhost:3000/proxy"),
+        ("UPSTREAM_TIMEOUT_SECS", "5"),
+        ("GEMINI_API_KEY", "secret"),
+        ("CACHE_SIZE", "0"),
+    ])
+    .unwrap();
+    assert_eq!(config.bind_addr, "127.0.0.1:9000");
+    assert_eq!(config.upstream_url.as_str(), "http://localhost:3000/proxy");
+    assert_eq!(config.upstream_timeout, Duration::from_secs(5));
+
+    // The startup log shows the settings but never the key
+    let log = config.to_string();
+    assert!(log.contains("127.0.0.1:9000"), "{}", log);
+    assert!(log.contains("timeout 5s"), "{}", log);
+    assert!(log.contains("cache: off"), "{}", log);
+    assert!(!log.contains("secret"), "{}", log);
+}
+
+#[test]
+fn invalid_values_are_rejected() {
+    for (name, value) in [
+        ("UPSTREAM_URL", "not a url"),
+        // No scheme: parses as a URL with scheme "localhost"
+        ("UPSTREAM_URL", "localhost:3000"),
+        ("UPSTREAM_URL", "ftp://example.com"),
+        ("UPSTREAM_TIMEOUT_SECS", "0"),
+        ("UPSTREAM_TIMEOUT_SECS", "soon"),
+        ("CACHE_SIZE", "-1"),
+        ("RATE_LIMIT_PER_MINUTE", "lots"),
+        ("RATE_LIMIT_BURST", "0"),
+        ("TRUST_PROXY", "yes"),
+        ("ALLOWED_MODELS", "gemini-2.0-flash,../admin"),
+        ("ALLOWED_MODELS", " , "),
+        ("MAX_PROMPT_BYTES", "0"),
+        ("MAX_BODY_BYTES", "64KB"),
+    ] {
+        let error = config(&[(name, value)]).unwrap_err();
+        assert_eq!(error.name, name);
+        assert!(error.to_string().contains(value), "{}", error);
+    }
+}
+
+#[actix_web::test]
+async fn a_slow_upstream_is_a_504_with_a_json_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({ "candidates": [] }))
+                .set_delay(Duration::from_secs(3)),
+        )
+        .mount(&server)
+        .await;
+    let config = config(&[
+        ("UPSTREAM_URL", &server.uri()),
+        ("UPSTREAM_TIMEOUT_SECS", "1"),
+        ("GEMINI_API_KEY", "key"),
+    ])
+    .unwrap();
+    let app = init_service(create_app(config.state())).await;
+
+    let started = Instant::now();
+    let request = TestRequest::get().uri("/?text=Hi").to_request();
+    let response = call_service(&app, request).await;
+
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    assert!(started.elapsed() < Duration::from_secs(2));
+    let body: Value = read_body_json(response).await;
+    assert!(
+        body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("within 1s"),
+        "{}",
+        body
+    );
+}
+
+#[actix_web::test]
+async fn a_slow_stream_start_is_a_504_too() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(3)))
+        .mount(&server)
+        .await;
+    let config = config(&[
+        ("UPSTREAM_URL", &server.uri()),
+        ("UPSTREAM_TIMEOUT_SECS", "1"),
+        ("GEMINI_API_KEY", "key"),
+    ])
+    .unwrap();
+    let app = init_service(create_app(config.state())).await;
+
+    let request = TestRequest::get().uri("/?text=Hi&stream=1").to_request();
+    let response = call_service(&app, request).await;
+    assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+}
@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use actix_web::ResponseError;
+use actix_web::body::to_bytes;
+use actix_web::http::StatusCode;
+use actix_web::test::{TestRequest, call_service, init_service, read_body_json};
+use ai_server::{ApiError, AppState, Upstream, create_app};
+use reqwest::Client;
+use serde_json::{Value, json};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// The status and JSON body `error` is sent as
+async fn render(error: ApiError) -> (StatusCode, Value) {
+    let response = error.error_response();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body()).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap())
+}
+
+#[actix_web::test]
+async fn missing_parameter() {
+    let (status, body) = render(ApiError::MissingParameter("text")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        body,
+        json!({ "error": { "code": "MISSING_PARAMETER", "message": "Missing 'text'" } })
+    );
+}
+
+#[actix_web::test]
+async fn invalid_parameter_and_body() {
+    let (status, body) = render(ApiError::InvalidParameter(
+        "'temperature' must be a number".to_string(),
+    ))
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_PARAMETER");
+    assert_eq!(body["error"]["message"], "'temperature' must be a number");
+
+    let (status, body) = render(ApiError::InvalidBody("expected value".to_string())).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "INVALID_BODY");
+    assert_eq!(
+        body["error"]["message"],
+        "Invalid request body: expected value"
+    );
+
+    let (status, body) = render(ApiError::BodyTooLarge(64 * 1024)).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert_eq!(body["error"]["code"], "BODY_TOO_LARGE");
+    assert_eq!(
+        body["error"]["message"],
+        "Request body is larger than 64 KB"
+    );
+
+    let (status, body) = render(ApiError::PromptTooLong(100)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], "PROMPT_TOO_LONG");
+    assert_eq!(body["error"]["message"], "'text' is longer than 100 bytes");
+}
+
+#[actix_web::test]
+async fn rate_limited_has_retry_after() {
+    let response = ApiError::RateLimited(3).error_response();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers().get("Retry-After").unwrap(), "3");
+
+    let (_, body) = render(ApiError::RateLimited(3)).await;
+    assert_eq!(body["error"]["code"], "RATE_LIMITED");
+    assert_eq!(
+        body["error"]["message"],
+        "Too many requests; try again in 3 seconds"
+    );
+}
+
+#[actix_web::test]
+async fn upstream_failures() {
+    for (error, status, code) in [
+        (
+            ApiError::MissingApiKey,
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "MISSING_API_KEY",
+        ),
+        (
+            ApiError::Unreachable,
+            StatusCode::BAD_GATEWAY,
+            "UPSTREAM_UNREACHABLE",
+        ),
+        (ApiError::Parse, StatusCode::BAD_GATEWAY, "PARSE_ERROR"),
+        (
+            ApiError::EmptyResponse,
+            StatusCode::BAD_GATEWAY,
+            "EMPTY_RESPONSE",
+        ),
+        (
+            ApiError::Timeout(Duration::from_secs(30)),
+            StatusCode::GATEWAY_TIMEOUT,
+            "UPSTREAM_TIMEOUT",
+        ),
+    ] {
+        let (got_status, body) = render(error.clone()).await;
+        assert_eq!(got_status, status, "{:?}", error);
+        assert_eq!(body["error"]["code"], code);
+        assert_eq!(body["error"]["message"], error.to_string());
+        // Only errors the upstream answered with have its status
+        assert!(body["error"].get("upstream_status").is_none());
+    }
+}
+
+#[actix_web::test]
+async fn gemini_error_json_is_mapped() {
+    let gemini = json!({
+        "error": {
+            "code": 429,
+            "message": "Resource has been exhausted",
+            "status": "RESOURCE_EXHAUSTED"
+        }
+    });
+    let (status, body) = render(ApiError::from_upstream(429, &gemini.to_string())).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(
+        body,
+        json!({
+            "error": {
+                "code": "RESOURCE_EXHAUSTED",
+                "message": "Resource has been exhausted",
+                "upstream_status": 429
+            }
+        })
+    );
+}
+
+#[actix_web::test]
+async fn other_upstream_bodies_become_the_message() {
+    let (status, body) = render(ApiError::from_upstream(503, "Overloaded\n")).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        body,
+        json!({
+            "error": {
+                "code": "UPSTREAM_ERROR",
+                "message": "Overloaded",
+                "upstream_status": 503
+            }
+        })
+    );
+}
+
+#[actix_web::test]
+async fn an_answer_without_text_is_empty_response() {
+    let server = MockServer::start().await;
+    // What Gemini sends for a blocked prompt: a candidate, but no content
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "candidates": [ { "finishReason": "SAFETY" } ]
+        })))
+        .mount(&server)
+        .await;
+    let upstream = Upstream::new(Client::new(), server.uri(), Some("key".to_string()));
+    let app = init_service(create_app(AppState::new(upstream))).await;
+
+    let response = call_service(&app, TestRequest::get().uri("/?text=Hi").to_request()).await;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    let body: Value = read_body_json(response).await;
+    assert_eq!(body["error"]["code"], "EMPTY_RESPONSE");
+}
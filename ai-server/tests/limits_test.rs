@@ -0,0 +1,114 @@
+use actix_web::http::StatusCode;
+use actix_web::test::{TestRequest, call_service, init_service, read_body_json};
+use actix_web::web;
+use ai_server::{AppState, Limits, Upstream, create_app, normalize};
+use reqwest::Client;
+use serde_json::{Value, json};
+use wiremock::matchers::{body_partial_json, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn gemini() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "candidates": [ { "content": { "parts": [ { "text": "Hi!" } ] } } ]
+        })))
+        .mount(&server)
+        .await;
+    server
+}
+
+// Small limits, so the boundaries are easy to reach
+fn state(server: &MockServer) -> AppState {
+    let upstream = Upstream::new(Client::new(), server.uri(), Some("key".to_string()));
+    let limits = Limits {
+        max_prompt_bytes: 10,
+        max_body_bytes: 100,
+    };
+    AppState::new(upstream).with_limits(web::Data::new(limits))
+}
+
+fn post(text: &str) -> TestRequest {
+    TestRequest::post()
+        .uri("/prompt")
+        .set_json(json!({ "text": text }))
+}
+
+#[actix_web::test]
+async fn prompts_up_to_the_limit_are_accepted() {
+    let server = gemini().await;
+    let app = init_service(create_app(state(&server))).await;
+
+    let exactly = call_service(&app, post("0123456789").to_request()).await;
+    assert_eq!(exactly.status(), StatusCode::OK);
+    let get = TestRequest::get().uri("/?text=0123456789");
+    assert_eq!(
+        call_service(&app, get.to_request()).await.status(),
+        StatusCode::OK
+    );
+
+    // One byte more is a 400 on both routes, and never reaches the upstream
+    for request in [
+        post("0123456789a"),
+        TestRequest::get().uri("/?text=0123456789a"),
+    ] {
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = read_body_json(response).await;
+        assert_eq!(body["error"]["code"], "PROMPT_TOO_LONG");
+    }
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[actix_web::test]
+async fn bodies_over_the_limit_are_413() {
+    let server = gemini().await;
+    let app = init_service(create_app(state(&server))).await;
+
+    // A short text, but padded past 100 bytes with a field nobody reads
+    let request = TestRequest::post()
+        .uri("/prompt")
+        .set_json(json!({ "text": "Hi", "padding": "x".repeat(100) }));
+    let response = call_service(&app, request.to_request()).await;
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body: Value = read_body_json(response).await;
+    assert_eq!(
+        body["error"]["message"],
+        "Request body is larger than 100 bytes"
+    );
+}
+
+#[actix_web::test]
+async fn control_characters_are_stripped_before_forwarding() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_partial_json(json!({
+            "contents": [ { "parts": [ { "text": "Hi\nthere" } ] } ]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "candidates": [ { "content": { "parts": [ { "text": "Hello!" } ] } } ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let app = init_service(create_app(state(&server))).await;
+
+    // NUL bytes and an escape code go; the Windows line ending becomes `\n`
+    let response = call_service(&app, post("Hi\0\r\n\u{1b}there\0").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Nothing but control characters is no prompt at all
+    let response = call_service(&app, post("\0\0\0").to_request()).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = read_body_json(response).await;
+    assert_eq!(body["error"]["code"], "MISSING_PARAMETER");
+}
+
+#[test]
+fn normalize_keeps_text_newlines_and_tabs() {
+    assert_eq!(normalize("a\tb\nc"), "a\tb\nc");
+    assert_eq!(normalize("a\r\nb\rc"), "a\nb\nc");
+    assert_eq!(normalize("a\0b\u{7f}c\u{1b}[31md"), "abc[31md");
+    assert_eq!(normalize("héllo ✓"), "héllo ✓");
+}
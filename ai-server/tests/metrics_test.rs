@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actix_web::test::{TestRequest, call_service, init_service, read_body};
+use actix_web::web;
+use ai_server::{AppState, ResponseCache, Upstream, create_app};
+use reqwest::Client;
+use serde_json::json;
+use tracing_subscriber::fmt::MakeWriter;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn gemini() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "candidates": [ { "content": { "parts": [ { "text": "Hi!" } ] } } ]
+        })))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn state(server: &MockServer) -> AppState {
+    let upstream = Upstream::new(Client::new(), server.uri(), Some("key".to_string()));
+    let cache = ResponseCache::new(10, Duration::from_secs(60));
+    AppState::new(upstream).with_cache(web::Data::new(cache))
+}
+
+// `name{labels}` -> value, for every sample in a /metrics page
+fn samples(page: &str) -> HashMap<String, f64> {
+    page.lines()
+        .filter(|line| !line.starts_with('#') && !line.is_empty())
+        .map(|line| {
+            let (name, value) = line.rsplit_once(' ').unwrap();
+            (name.to_string(), value.parse().unwrap())
+        })
+        .collect()
+}
+
+#[actix_web::test]
+async fn metrics_count_requests_cache_and_upstream_calls() {
+    let server = gemini().await;
+    let app = init_service(create_app(state(&server))).await;
+
+    for request in [
+        TestRequest::get().uri("/?text=Hi"),
+        TestRequest::get().uri("/?text=Hi"),
+        TestRequest::get().uri("/"),
+        TestRequest::post()
+            .uri("/prompt")
+            .set_json(json!({ "text": 1 })),
+    ] {
+        call_service(&app, request.to_request()).await;
+    }
+
+    let response = call_service(&app, TestRequest::get().uri("/metrics").to_request()).await;
+    assert!(response.status().is_success());
+    let page = String::from_utf8(read_body(response).await.to_vec()).unwrap();
+    let samples = samples(&page);
+    let sample = |name: &str| {
+        samples
+            .get(name)
+            .copied()
+            .unwrap_or_else(|| panic!("{} in\n{}", name, page))
+    };
+
+    assert_eq!(sample(r#"ai_server_requests_total{status="200"}"#), 2.0);
+    assert_eq!(sample(r#"ai_server_requests_total{status="400"}"#), 2.0);
+    assert_eq!(sample("ai_server_cache_hits_total"), 1.0);
+    assert_eq!(sample("ai_server_cache_misses_total"), 1.0);
+    // Only the first request reached the upstream
+    assert_eq!(sample("ai_server_upstream_duration_seconds_count"), 1.0);
+    assert_eq!(
+        sample(r#"ai_server_upstream_duration_seconds_bucket{le="+Inf"}"#),
+        1.0
+    );
+    assert_eq!(
+        sample(r#"ai_server_upstream_duration_seconds_bucket{le="60"}"#),
+        1.0
+    );
+    assert!(sample("ai_server_upstream_duration_seconds_sum") > 0.0);
+}
+
+// Collects what the log subscriber writes
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Captured {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for Captured {
+    type Writer = Captured;
+
+    fn make_writer(&'a self) -> Captured {
+        self.clone()
+    }
+}
+
+#[actix_web::test]
+async fn requests_are_logged_without_the_prompt() {
+    let logs = Captured::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(logs.clone())
+        .with_ansi(false)
+        .finish();
+    // Only for this test's thread, which runs the whole app
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let server = gemini().await;
+    let app = init_service(create_app(state(&server))).await;
+    let get = TestRequest::get().uri("/?text=my%20secret%20question");
+    let post = TestRequest::post()
+        .uri("/prompt")
+        .set_json(json!({ "text": "another secret" }));
+    call_service(&app, get.to_request()).await;
+    call_service(&app, post.to_request()).await;
+
+    let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    let lines: Vec<&str> = logs.lines().filter(|line| line.contains("done")).collect();
+    assert_eq!(lines.len(), 2, "{}", logs);
+    assert!(
+        lines[0].contains("method=GET path=/ prompt_len=18"),
+        "{}",
+        lines[0]
+    );
+    assert!(
+        lines[1].contains("path=/prompt prompt_len=14"),
+        "{}",
+        lines[1]
+    );
+    for line in &lines {
+        assert!(line.contains("status=200"), "{}", line);
+        assert!(line.contains("upstream_ms="), "{}", line);
+    }
+    assert!(!logs.contains("secret"), "{}", logs);
+}
@@ -0,0 +1,128 @@
+use actix_web::http::StatusCode;
+use actix_web::test::{TestRequest, call_service, init_service, read_body, read_body_json};
+use actix_web::web;
+use ai_server::{AllowedModels, AppState, Upstream, create_app};
+use reqwest::Client;
+use serde_json::{Value, json};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// A mock upstream that answers with the model it was asked for, so each test can see which
+// one was used. Like extender, its base URL is the default model and `/<model>` the others.
+async fn gemini() -> MockServer {
+    let server = MockServer::start().await;
+    for (model, route) in [
+        ("gemini-2.0-flash", "/"),
+        ("gemini-1.5-pro", "/gemini-1.5-pro"),
+    ] {
+        Mock::given(method("POST"))
+            .and(path(route))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "candidates": [ { "content": { "parts": [ { "text": model } ] } } ]
+            })))
+            .mount(&server)
+            .await;
+    }
+    server
+}
+
+fn state(server: &MockServer, models: &[&str]) -> AppState {
+    let upstream = Upstream::new(Client::new(), server.uri(), Some("key".to_string()));
+    let models = AllowedModels::new(models.iter().copied()).unwrap();
+    AppState::new(upstream).with_models(web::Data::new(models))
+}
+
+#[actix_web::test]
+async fn an_allowed_model_is_forwarded() {
+    let server = gemini().await;
+    let app = init_service(create_app(state(
+        &server,
+        &["gemini-2.0-flash", "gemini-1.5-pro"],
+    )))
+    .await;
+
+    let get = TestRequest::get().uri("/?text=Hi&model=gemini-1.5-pro");
+    let response = call_service(&app, get.to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(read_body(response).await, "gemini-1.5-pro");
+
+    let post = TestRequest::post()
+        .uri("/prompt")
+        .set_json(json!({ "text": "Hi", "model": "gemini-1.5-pro" }));
+    let response = call_service(&app, post.to_request()).await;
+    assert_eq!(read_body(response).await, "gemini-1.5-pro");
+}
+
+#[actix_web::test]
+async fn an_unknown_model_is_a_400_listing_the_allowed_ones() {
+    let server = gemini().await;
+    let app = init_service(create_app(state(&server, &["gemini-2.0-flash"]))).await;
+
+    for request in [
+        TestRequest::get().uri("/?text=Hi&model=gemini-1.5-pro"),
+        TestRequest::post()
+            .uri("/prompt")
+            .set_json(json!({ "text": "Hi", "model": "../admin" })),
+    ] {
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: Value = read_body_json(response).await;
+        assert_eq!(body["error"]["code"], "UNKNOWN_MODEL");
+        assert_eq!(body["error"]["allowed"], json!(["gemini-2.0-flash"]));
+        let error = body["error"]["message"].as_str().unwrap();
+        assert!(error.contains("allowed: gemini-2.0-flash"), "{}", error);
+    }
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn the_first_allowed_model_is_the_default() {
+    let server = gemini().await;
+    let app = init_service(create_app(state(
+        &server,
+        &["gemini-1.5-pro", "gemini-2.0-flash"],
+    )))
+    .await;
+
+    let response = call_service(&app, TestRequest::get().uri("/?text=Hi").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(read_body(response).await, "gemini-1.5-pro");
+
+    // And with no ALLOWED_MODELS, the only model is gemini-2.0-flash
+    let server = gemini().await;
+    let upstream = Upstream::new(Client::new(), server.uri(), Some("key".to_string()));
+    let app = init_service(create_app(AppState::new(upstream))).await;
+    let response = call_service(&app, TestRequest::get().uri("/?text=Hi").to_request()).await;
+    assert_eq!(read_body(response).await, "gemini-2.0-flash");
+}
+
+#[actix_web::test]
+async fn models_lists_the_allowlist() {
+    let server = gemini().await;
+    let app = init_service(create_app(state(
+        &server,
+        &["gemini-2.0-flash", "gemini-1.5-pro"],
+    )))
+    .await;
+
+    let response = call_service(&app, TestRequest::get().uri("/models").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = read_body_json(response).await;
+    assert_eq!(
+        body,
+        json!({
+            "models": ["gemini-2.0-flash", "gemini-1.5-pro"],
+            "default": "gemini-2.0-flash"
+        })
+    );
+}
+
+#[test]
+fn allowed_models_parses_a_comma_separated_list() {
+    let models = AllowedModels::parse(" gemini-2.0-flash, gemini-1.5-pro ,").unwrap();
+    assert_eq!(models.names(), ["gemini-2.0-flash", "gemini-1.5-pro"]);
+    assert_eq!(models.default_model(), "gemini-2.0-flash");
+
+    assert_eq!(AllowedModels::parse(","), None);
+    assert_eq!(AllowedModels::parse("gemini-2.0-flash,models/x"), None);
+}
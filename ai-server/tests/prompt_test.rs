@@ -0,0 +1,195 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::ServiceResponse;
+use actix_web::http::StatusCode;
+use actix_web::test;
+use ai_server::{AppState, MAX_BODY_BYTES, Upstream, create_app};
+use reqwest::Client;
+use serde_json::{Value, json};
+use wiremock::matchers::{body_partial_json, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// What the Gemini proxy answers with, cut down to the part ai-server reads
+fn gemini_reply(text: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "candidates": [ { "content": { "parts": [ { "text": text } ] } } ]
+    }))
+}
+
+fn upstream(server: &MockServer) -> Upstream {
+    Upstream::new(Client::new(), server.uri(), Some("test-key".to_string()))
+}
+
+async fn error_message<B: MessageBody>(response: ServiceResponse<B>) -> String {
+    let body: Value = test::read_body_json(response).await;
+    body["error"]["message"].as_str().unwrap().to_string()
+}
+
+#[actix_web::test]
+async fn post_prompt_returns_the_answer_text() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .and(header("X-Gemini-API-Key", "test-key"))
+        .and(body_partial_json(json!({
+            "contents": [ { "parts": [ { "text": "Say hi" } ] } ]
+        })))
+        .respond_with(gemini_reply("Hi!"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let app = test::init_service(create_app(AppState::new(upstream(&server)))).await;
+    let request = test::TestRequest::post()
+        .uri("/prompt")
+        .set_json(json!({ "text": "Say hi" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(test::read_body(response).await, "Hi!");
+}
+
+#[actix_web::test]
+async fn model_and_temperature_are_forwarded() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .and(body_partial_json(
+            json!({ "generationConfig": { "temperature": 0.5 } }),
+        ))
+        .respond_with(gemini_reply("Cool."))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let app = test::init_service(create_app(AppState::new(upstream(&server)))).await;
+    let request = test::TestRequest::post()
+        .uri("/prompt")
+        .set_json(json!({ "text": "Hi", "model": "gemini-2.0-flash", "temperature": 0.5 }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(test::read_body(response).await, "Cool.");
+}
+
+#[actix_web::test]
+async fn get_builds_the_same_request_from_the_query() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/"))
+        .and(body_partial_json(json!({
+            "contents": [ { "parts": [ { "text": "hello world" } ] } ],
+            "generationConfig": { "temperature": 1.5 }
+        })))
+        .respond_with(gemini_reply("Hello!"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let app = test::init_service(create_app(AppState::new(upstream(&server)))).await;
+    let request = test::TestRequest::get()
+        .uri("/?text=hello%20world&model=gemini-2.0-flash&temperature=1.5")
+        .to_request();
+    let response = test::call_service(&app, request).await;
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(test::read_body(response).await, "Hello!");
+}
+
+#[actix_web::test]
+async fn missing_text_is_a_json_400_on_both_routes() {
+    // Nothing is mounted: the upstream must not be called
+    let server = MockServer::start().await;
+    let app = test::init_service(create_app(AppState::new(upstream(&server)))).await;
+
+    let requests = [
+        test::TestRequest::get().uri("/").to_request(),
+        test::TestRequest::post()
+            .uri("/prompt")
+            .set_json(json!({}))
+            .to_request(),
+        test::TestRequest::post()
+            .uri("/prompt")
+            .set_json(json!({ "text": "   " }))
+            .to_request(),
+    ];
+    for request in requests {
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(error_message(response).await, "Missing 'text'");
+    }
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn invalid_fields_are_a_json_400() {
+    let server = MockServer::start().await;
+    let app = test::init_service(create_app(AppState::new(upstream(&server)))).await;
+
+    for body in [
+        json!({ "text": "Hi", "model": "../admin" }),
+        json!({ "text": "Hi", "temperature": 3.0 }),
+        json!({ "text": 42 }),
+    ] {
+        let request = test::TestRequest::post()
+            .uri("/prompt")
+            .set_json(&body)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", body);
+        assert!(!error_message(response).await.is_empty());
+    }
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn oversized_body_is_413() {
+    let server = MockServer::start().await;
+    let app = test::init_service(create_app(AppState::new(upstream(&server)))).await;
+
+    let text = "a".repeat(MAX_BODY_BYTES);
+    let request = test::TestRequest::post()
+        .uri("/prompt")
+        .set_json(json!({ "text": text }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    assert!(error_message(response).await.contains("64 KB"));
+}
+
+#[actix_web::test]
+async fn upstream_errors_are_passed_on() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(429).set_body_string("Quota exceeded"))
+        .mount(&server)
+        .await;
+
+    let app = test::init_service(create_app(AppState::new(upstream(&server)))).await;
+    let request = test::TestRequest::post()
+        .uri("/prompt")
+        .set_json(json!({ "text": "Hi" }))
+        .to_request();
+    let response = test::call_service(&app, request).await;
+
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(error_message(response).await, "Quota exceeded");
+}
+
+#[actix_web::test]
+async fn missing_api_key_is_a_500() {
+    let server = MockServer::start().await;
+    let upstream = Upstream::new(Client::new(), server.uri(), None);
+    let app = test::init_service(create_app(AppState::new(upstream))).await;
+
+    let request = test::TestRequest::get().uri("/?text=Hi").to_request();
+    let response = test::call_service(&app, request).await;
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(
+        error_message(response).await,
+        "Missing GEMINI_API_KEY env variable"
+    );
+}
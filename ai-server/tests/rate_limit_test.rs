@@ -0,0 +1,137 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+use actix_web::http::StatusCode;
+use actix_web::test::{TestRequest, call_service, init_service, read_body_json};
+use actix_web::web;
+use ai_server::{AppState, RateLimiter, Upstream, create_app};
+use reqwest::Client;
+use serde_json::{Value, json};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn gemini() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "candidates": [ { "content": { "parts": [ { "text": "Hi!" } ] } } ]
+        })))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn state(server: &MockServer, limiter: RateLimiter) -> AppState {
+    let upstream = Upstream::new(Client::new(), server.uri(), Some("key".to_string()));
+    AppState::new(upstream).with_rate_limit(web::Data::new(limiter))
+}
+
+fn from(ip: &str) -> TestRequest {
+    let peer = SocketAddr::new(ip.parse().unwrap(), 40000);
+    TestRequest::get().uri("/?text=Hi").peer_addr(peer)
+}
+
+fn ip(ip: &str) -> IpAddr {
+    ip.parse().unwrap()
+}
+
+#[actix_web::test]
+async fn one_client_over_the_limit_does_not_block_another() {
+    let server = gemini().await;
+    let app = init_service(create_app(state(&server, RateLimiter::new(60, 2)))).await;
+
+    for _ in 0..2 {
+        let response = call_service(&app, from("10.0.0.1").to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let limited = call_service(&app, from("10.0.0.1").to_request()).await;
+    assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+    // 60 a minute is one a second, so the next token is at most a second away
+    assert_eq!(limited.headers().get("Retry-After").unwrap(), "1");
+    let body: Value = read_body_json(limited).await;
+    assert!(
+        body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("Too many requests")
+    );
+
+    let other = call_service(&app, from("10.0.0.2").to_request()).await;
+    assert_eq!(other.status(), StatusCode::OK);
+    // The limited client's requests never reached the upstream
+    assert_eq!(server.received_requests().await.unwrap().len(), 3);
+}
+
+#[actix_web::test]
+async fn x_forwarded_for_is_only_used_when_trusted() {
+    let server = gemini().await;
+    let forwarded = |client: &str| {
+        from("192.168.1.1")
+            .insert_header(("X-Forwarded-For", format!("1.2.3.4, {}", client)))
+            .to_request()
+    };
+
+    // Not trusted: every request comes from the proxy's address, whatever the header says
+    let app = init_service(create_app(state(&server, RateLimiter::new(60, 1)))).await;
+    assert_eq!(
+        call_service(&app, forwarded("10.0.0.1")).await.status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        call_service(&app, forwarded("10.0.0.2")).await.status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+
+    // Trusted: the last address (the one the proxy added) is the client
+    let limiter = RateLimiter::new(60, 1).trust_proxy(true);
+    let app = init_service(create_app(state(&server, limiter))).await;
+    assert_eq!(
+        call_service(&app, forwarded("10.0.0.1")).await.status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        call_service(&app, forwarded("10.0.0.2")).await.status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        call_service(&app, forwarded("10.0.0.1")).await.status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
+
+#[test]
+fn the_bucket_refills_over_time() {
+    let limiter = RateLimiter::new(60, 1);
+    let start = Instant::now();
+    assert!(limiter.check(ip("10.0.0.1"), start).is_ok());
+
+    let wait = limiter.check(ip("10.0.0.1"), start).unwrap_err();
+    assert_eq!(wait, Duration::from_secs(1));
+    let wait = limiter
+        .check(ip("10.0.0.1"), start + Duration::from_millis(500))
+        .unwrap_err();
+    assert_eq!(wait, Duration::from_millis(500));
+
+    assert!(
+        limiter
+            .check(ip("10.0.0.1"), start + Duration::from_secs(1))
+            .is_ok()
+    );
+}
+
+#[test]
+fn quiet_clients_are_pruned() {
+    let limiter = RateLimiter::new(60, 5);
+    let start = Instant::now();
+    for n in 0..100 {
+        let client = IpAddr::from([10, 0, 0, n]);
+        limiter.check(client, start).unwrap();
+    }
+    assert_eq!(limiter.clients(), 100);
+
+    // Two minutes later every bucket has refilled, so the next check drops them all
+    limiter
+        .check(ip("10.0.1.1"), start + Duration::from_secs(120))
+        .unwrap();
+    assert_eq!(limiter.clients(), 1);
+}
@@ -0,0 +1,223 @@
+use std::time::{Duration, Instant};
+
+use actix_web::HttpServer;
+use ai_server::{AppState, Upstream, create_app};
+use futures_util::StreamExt;
+use reqwest::Client;
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+const GAP: Duration = Duration::from_millis(300);
+
+// One `streamGenerateContent?alt=sse` event, the way Gemini sends it
+fn gemini_chunk(text: &str, last: bool) -> String {
+    let mut candidate = json!({ "content": { "parts": [ { "text": text } ] } });
+    if last {
+        candidate["finishReason"] = json!("STOP");
+    }
+    format!("data: {}\r\n\r\n", json!({ "candidates": [candidate] }))
+}
+
+// A fake upstream that answers one request by sending `chunks` with a pause before each.
+// wiremock can only delay a whole response, so this writes chunked HTTP by hand. Without
+// `complete` the connection is dropped before the final empty chunk: a disconnect mid-answer.
+// Returns its URL, which looks like extender's `/proxy`, and, once the request arrives, the
+// request line.
+async fn stub_upstream(
+    chunks: Vec<String>,
+    complete: bool,
+) -> (String, tokio::sync::oneshot::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/proxy", listener.local_addr().unwrap());
+    let (request_line, received) = tokio::sync::oneshot::channel();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        // Read the whole request: the headers, then Content-Length bytes of body
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        loop {
+            let n = socket.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..n]);
+            let text = String::from_utf8_lossy(&request).to_string();
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length: usize = text
+                    .lines()
+                    .find_map(|line| {
+                        line.to_ascii_lowercase()
+                            .strip_prefix("content-length:")
+                            .map(|v| v.trim().parse().unwrap())
+                    })
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + length {
+                    let _ = request_line.send(text.lines().next().unwrap().to_string());
+                    break;
+                }
+            }
+        }
+        socket
+            .write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n")
+            .await
+            .unwrap();
+        for chunk in chunks {
+            tokio::time::sleep(GAP).await;
+            let framed = format!("{:x}\r\n{}\r\n", chunk.len(), chunk);
+            socket.write_all(framed.as_bytes()).await.unwrap();
+            socket.flush().await.unwrap();
+        }
+        if complete {
+            socket.write_all(b"0\r\n\r\n").await.unwrap();
+        }
+        // Dropping the socket closes the connection
+    });
+    (url, received)
+}
+
+// Run ai-server on a free port, the way main.rs does
+fn start_server(upstream: Upstream) -> String {
+    let state = AppState::new(upstream);
+    let server = HttpServer::new(move || create_app(state.clone()))
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+    let url = format!("http://{}", server.addrs()[0]);
+    actix_web::rt::spawn(server.run());
+    url
+}
+
+// An SSE event as the client saw it: its name ("message" unless set), its data, and how long
+// after the request it arrived
+#[derive(Debug)]
+struct Event {
+    name: String,
+    data: serde_json::Value,
+    at: Duration,
+}
+
+async fn read_events(response: reqwest::Response, started: Instant) -> Vec<Event> {
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let mut events = Vec::new();
+    let mut buffer = String::new();
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        buffer.push_str(&String::from_utf8_lossy(&chunk.unwrap()));
+        while let Some(end) = buffer.find("\n\n") {
+            let block: String = buffer.drain(..end + 2).collect();
+            let mut event = Event {
+                name: "message".to_string(),
+                data: serde_json::Value::Null,
+                at: started.elapsed(),
+            };
+            for line in block.lines() {
+                if let Some(name) = line.strip_prefix("event: ") {
+                    event.name = name.to_string();
+                } else if let Some(data) = line.strip_prefix("data: ") {
+                    event.data = serde_json::from_str(data).unwrap();
+                }
+            }
+            events.push(event);
+        }
+    }
+    events
+}
+
+fn texts(events: &[Event]) -> Vec<&str> {
+    events
+        .iter()
+        .filter_map(|e| e.data["text"].as_str())
+        .collect()
+}
+
+#[actix_web::test]
+async fn chunks_are_relayed_as_they_arrive() {
+    let chunks = vec![
+        gemini_chunk("Once", false),
+        gemini_chunk(" upon", false),
+        gemini_chunk(" a time", true),
+    ];
+    let (upstream_url, request_line) = stub_upstream(chunks, true).await;
+    let server = start_server(Upstream::new(
+        Client::new(),
+        upstream_url,
+        Some("key".into()),
+    ));
+
+    let started = Instant::now();
+    let response = Client::new()
+        .post(format!("{}/prompt", server))
+        .header("Accept", "text/event-stream")
+        .json(&json!({ "text": "Tell me a story" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let events = read_events(response, started).await;
+
+    assert_eq!(request_line.await.unwrap(), "POST /proxy/stream HTTP/1.1");
+    let names: Vec<&str> = events.iter().map(|e| e.name.as_str()).collect();
+    assert_eq!(names, ["message", "message", "message", "done"]);
+    assert_eq!(texts(&events), ["Once", " upon", " a time"]);
+
+    // Each text arrives about when the upstream sent it, not all together at the end:
+    // the first long before the last was even sent, the others a GAP apart
+    assert!(events[0].at < GAP * 2, "{:?}", events);
+    for pair in events[..3].windows(2) {
+        assert!(pair[1].at - pair[0].at >= GAP / 2, "{:?}", events);
+    }
+    assert!(events[3].at - events[2].at < GAP / 2, "{:?}", events);
+}
+
+#[actix_web::test]
+async fn a_disconnect_ends_with_an_error_event() {
+    let (upstream_url, _) = stub_upstream(vec![gemini_chunk("Once", false)], false).await;
+    let server = start_server(Upstream::new(
+        Client::new(),
+        upstream_url,
+        Some("key".into()),
+    ));
+
+    let started = Instant::now();
+    let response = Client::new()
+        .get(format!("{}/?text=story&stream=1", server))
+        .send()
+        .await
+        .unwrap();
+    // A timeout here would mean the stream hangs instead of ending
+    let events = tokio::time::timeout(Duration::from_secs(5), read_events(response, started))
+        .await
+        .expect("the stream should end");
+
+    assert_eq!(texts(&events), ["Once"]);
+    let last = events.last().unwrap();
+    assert_eq!(last.name, "error");
+    assert!(
+        last.data["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("disconnected")
+    );
+}
+
+#[actix_web::test]
+async fn a_stream_closed_before_the_last_chunk_is_an_error() {
+    // A clean end of the body, but the answer never got its finishReason
+    let chunks = vec![gemini_chunk("Once", false), gemini_chunk(" upon", false)];
+    let (upstream_url, _) = stub_upstream(chunks, true).await;
+    let server = start_server(Upstream::new(
+        Client::new(),
+        upstream_url,
+        Some("key".into()),
+    ));
+
+    let started = Instant::now();
+    let response = Client::new()
+        .get(format!("{}/?text=story", server))
+        .header("Accept", "text/event-stream")
+        .send()
+        .await
+        .unwrap();
+    let events = read_events(response, started).await;
+
+    assert_eq!(texts(&events), ["Once", " upon"]);
+    assert_eq!(events.last().unwrap().name, "error");
+}
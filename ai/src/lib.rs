@@ -0,0 +1,440 @@
+//! The HTTP side of `ai`, kept out of `main.rs` so tests can check the exact bytes sent and
+//! received. The HTTP and the JSON are written by hand; the only dependency is rustls, for
+//! `https://` servers, and it can be left out (`--no-default-features`).
+
+#[cfg(feature = "tls")]
+mod tls;
+
+/// Where requests go without `AI_SERVER_URL` or `-u`: ai-server on this machine
+pub const DEFAULT_URL: &str = "http://127.0.0.1:8089";
+
+/// Prompts longer than this, in bytes, go in a `POST /prompt` body instead of the URL
+pub const POST_THRESHOLD: usize = 1024;
+
+/// The prompt from the arguments and piped-in text: the arguments come first, as an
+/// instruction about the text (`git diff | ai review this`)
+pub fn prompt(args: &str, stdin: Option<&str>) -> String {
+    match stdin.map(str::trim_end).filter(|s| !s.is_empty()) {
+        Some(s) if args.is_empty() => s.to_string(),
+        Some(s) => format!("{}\n\n{}", args, s),
+        None => args.to_string(),
+    }
+}
+
+/// Where the server is: from a URL like `https://gemini.weolopez.com/api`
+#[derive(Debug, PartialEq)]
+pub struct Target {
+    pub tls: bool,
+    /// Without the brackets of an IPv6 address
+    pub host: String,
+    pub port: u16,
+    /// Put before `/?text=` and `/prompt`; empty, or starting with `/` and without a trailing one
+    pub path: String,
+}
+
+impl Target {
+    /// `http://host[:port][/path]` or `https://...`; the port defaults to 80 or 443
+    pub fn parse(url: &str) -> Result<Target, String> {
+        let bad = |why: &str| format!("bad server URL {:?}: {}", url, why);
+        let (tls, rest) = match url.split_once("://") {
+            Some(("https", rest)) => (true, rest),
+            Some(("http", rest)) => (false, rest),
+            _ => return Err(bad("expected http://... or https://...")),
+        };
+        if rest.contains(['?', '#']) {
+            return Err(bad("no query or fragment, please"));
+        }
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        // [::1]:8443, or host:port
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(v6) => {
+                let (host, after) = v6.split_once(']').ok_or_else(|| bad("unclosed ["))?;
+                (host, after.strip_prefix(':'))
+            }
+            None => match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+        if host.is_empty() {
+            return Err(bad("no host"));
+        }
+        let port = match port {
+            Some(port) => port
+                .parse()
+                .ok()
+                .filter(|&p: &u16| p > 0)
+                .ok_or_else(|| bad("the port must be a number from 1 to 65535"))?,
+            None => {
+                if tls {
+                    443
+                } else {
+                    80
+                }
+            }
+        };
+        Ok(Target {
+            tls,
+            host: host.to_string(),
+            port,
+            path: path.trim_end_matches('/').to_string(),
+        })
+    }
+
+    // For the `Host` header: the port only when it isn't the scheme's default
+    fn authority(&self) -> String {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        if self.port == if self.tls { 443 } else { 80 } {
+            host
+        } else {
+            format!("{}:{}", host, self.port)
+        }
+    }
+}
+
+/// The target as one URL, port always included, for messages about it
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let host = if self.host.contains(':') {
+            format!("[{}]", self.host)
+        } else {
+            self.host.clone()
+        };
+        write!(
+            f,
+            "{}://{}:{}{}",
+            if self.tls { "https" } else { "http" },
+            host,
+            self.port,
+            self.path
+        )
+    }
+}
+
+/// A connection to the server, plain or TLS; `relay` and `write_all` don't mind which
+pub trait Stream: std::io::Read + std::io::Write {}
+impl<T: std::io::Read + std::io::Write> Stream for T {}
+
+/// Connect to `target`. With `insecure`, a TLS certificate is accepted without checking
+/// it: only for servers you know, like a test server with a self-signed certificate.
+pub fn connect(target: &Target, insecure: bool) -> std::io::Result<Box<dyn Stream>> {
+    let tcp = std::net::TcpStream::connect((target.host.as_str(), target.port))?;
+    if !target.tls {
+        return Ok(Box::new(tcp));
+    }
+    #[cfg(feature = "tls")]
+    return Ok(Box::new(tls::wrap(tcp, &target.host, insecure)?));
+    #[cfg(not(feature = "tls"))]
+    {
+        let _ = insecure;
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "this ai was built without TLS support",
+        ))
+    }
+}
+
+/// Percent-encode `t` as one query parameter.
+/// - Unreserved chars pass through (RFC 3986): A-Z a-z 0-9 - _ . ~
+/// - Space becomes '+'
+/// - All other bytes become %HH (uppercase hex)
+pub fn encode(t: &str) -> String {
+    let mut q = String::with_capacity(t.len());
+    for b in t.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                q.push(b as char)
+            }
+            b' ' => q.push('+'),
+            _ => {
+                q.push('%');
+                for h in [(b >> 4) & 0xF, b & 0xF] {
+                    q.push(hex(h));
+                }
+            }
+        }
+    }
+    q
+}
+
+/// `t` as a JSON string, quotes included. `"` and `\` are escaped, and so are control
+/// characters, since JSON strings can't hold them raw; everything else is UTF-8 as it is.
+pub fn json(t: &str) -> String {
+    let mut j = String::with_capacity(t.len() + 2);
+    j.push('"');
+    for c in t.chars() {
+        match c {
+            '"' => j.push_str("\\\""),
+            '\\' => j.push_str("\\\\"),
+            '\n' => j.push_str("\\n"),
+            '\r' => j.push_str("\\r"),
+            '\t' => j.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                j.push_str("\\u00");
+                for h in [(c as u8) >> 4, (c as u8) & 0xF] {
+                    j.push(hex(h));
+                }
+            }
+            c => j.push(c),
+        }
+    }
+    j.push('"');
+    j
+}
+
+/// `GET /?text=...`: the prompt in the URL. Note: HTTP requires CRLF line endings (\r\n)
+pub fn get_request(target: &Target, t: &str) -> String {
+    format!(
+        "GET {}/?text={} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        target.path,
+        encode(t),
+        target.authority()
+    )
+}
+
+/// `POST /prompt` with `{"text": ...}`. `Content-Length` counts bytes, not characters.
+pub fn post_request(target: &Target, t: &str) -> String {
+    let body = format!("{{\"text\":{}}}", json(t));
+    format!(
+        "POST {}/prompt HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        target.path,
+        target.authority(),
+        body.len(),
+        body
+    )
+}
+
+/// The request for `t`: POST when asked to (`-p`) or when the prompt is too long for a URL
+pub fn request(target: &Target, t: &str, post: bool) -> String {
+    if post || t.len() > POST_THRESHOLD {
+        post_request(target, t)
+    } else {
+        get_request(target, t)
+    }
+}
+
+// One hex digit, uppercase
+fn hex(h: u8) -> char {
+    if h < 10 {
+        (b'0' + h) as char
+    } else {
+        (b'A' + (h - 10)) as char
+    }
+}
+
+/// What the response head says about the body
+#[derive(Debug, PartialEq)]
+pub struct Head {
+    pub status: u16,
+    pub reason: String,
+    pub chunked: bool,
+    pub content_length: Option<usize>,
+}
+
+/// Parse the status line and the headers (everything before the blank line).
+/// Header names are case-insensitive; other headers are skipped.
+pub fn parse_head(h: &[u8]) -> Option<Head> {
+    let h = std::str::from_utf8(h).ok()?;
+    let mut lines = h.split("\r\n");
+    // HTTP/1.1 200 OK
+    let mut status_line = lines.next()?.splitn(3, ' ');
+    if !status_line.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    let status = status_line.next()?.parse().ok()?;
+    let reason = status_line.next().unwrap_or("").to_string();
+    let mut head = Head {
+        status,
+        reason,
+        chunked: false,
+        content_length: None,
+    };
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("transfer-encoding") {
+            // `gzip, chunked`: chunked is always applied last
+            head.chunked = value
+                .rsplit(',')
+                .next()
+                .is_some_and(|v| v.trim().eq_ignore_ascii_case("chunked"));
+        } else if name.eq_ignore_ascii_case("content-length") {
+            head.content_length = Some(value.parse().ok()?);
+        }
+    }
+    Some(head)
+}
+
+/// Decodes `Transfer-Encoding: chunked`, whatever pieces the bytes arrive in. Each chunk is
+/// its size in hex, CRLF, that many bytes, CRLF; a size of 0 ends the body, after optional
+/// trailer headers and a blank line.
+#[derive(Default)]
+pub struct Chunked {
+    state: State,
+    // The size or trailer line read so far
+    line: Vec<u8>,
+}
+
+#[derive(Default, Clone, Copy, PartialEq)]
+enum State {
+    #[default]
+    Size,
+    Data(usize),
+    // The CRLF after a chunk's data; how many of its bytes are still to come
+    DataEnd(u8),
+    Trailer,
+    Done,
+}
+
+impl Chunked {
+    /// Decode `input`, appending the payload to `out`. `Ok(true)` once the last chunk is done;
+    /// anything after it is ignored.
+    pub fn feed(&mut self, mut input: &[u8], out: &mut Vec<u8>) -> std::io::Result<bool> {
+        while !input.is_empty() && self.state != State::Done {
+            match self.state {
+                State::Size | State::Trailer => {
+                    let Some(line) = self.line_from(&mut input) else {
+                        break;
+                    };
+                    self.state = match (self.state, line) {
+                        (State::Size, line) => match chunk_size(&line)? {
+                            0 => State::Trailer,
+                            n => State::Data(n),
+                        },
+                        // The blank line after the trailers
+                        (_, line) if line.is_empty() => State::Done,
+                        _ => State::Trailer,
+                    };
+                }
+                State::Data(n) => {
+                    let k = n.min(input.len());
+                    out.extend_from_slice(&input[..k]);
+                    input = &input[k..];
+                    self.state = if k == n {
+                        State::DataEnd(2)
+                    } else {
+                        State::Data(n - k)
+                    };
+                }
+                State::DataEnd(n) => {
+                    let expected = if n == 2 { b'\r' } else { b'\n' };
+                    if input[0] != expected {
+                        return Err(invalid("chunk data is not followed by CRLF"));
+                    }
+                    input = &input[1..];
+                    self.state = if n == 2 {
+                        State::DataEnd(1)
+                    } else {
+                        State::Size
+                    };
+                }
+                State::Done => {}
+            }
+        }
+        Ok(self.state == State::Done)
+    }
+
+    // The rest of the current line, without its CRLF, once it is all there
+    fn line_from(&mut self, input: &mut &[u8]) -> Option<Vec<u8>> {
+        match input.iter().position(|&b| b == b'\n') {
+            Some(p) => {
+                self.line.extend_from_slice(&input[..p]);
+                *input = &input[p + 1..];
+                let mut line = std::mem::take(&mut self.line);
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+                Some(line)
+            }
+            None => {
+                self.line.extend_from_slice(input);
+                *input = &[];
+                None
+            }
+        }
+    }
+}
+
+// `1a` or `1a;name=value`: chunk extensions are allowed and ignored
+fn chunk_size(line: &[u8]) -> std::io::Result<usize> {
+    let hex = std::str::from_utf8(line)
+        .ok()
+        .and_then(|l| l.split(';').next())
+        .map(str::trim)
+        .unwrap_or("");
+    usize::from_str_radix(hex, 16).map_err(|_| invalid("bad chunk size"))
+}
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+/// Read one response from `r` and write its body to `out` for a 2xx, or the status line and
+/// the body to `err` otherwise. The body ends where `Content-Length` or the last chunk says,
+/// so nothing waits for the server to close the connection; only a response with neither is
+/// read to the end. Returns the status.
+pub fn relay<'a>(
+    r: &mut impl std::io::Read,
+    out: &'a mut dyn std::io::Write,
+    err: &'a mut dyn std::io::Write,
+) -> std::io::Result<u16> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let p = loop {
+        if let Some(p) = buf
+            .windows(4)
+            .position(|w| w == [b'\r', b'\n', b'\r', b'\n'])
+        {
+            break p;
+        }
+        let n = r.read(&mut chunk)?;
+        if n == 0 {
+            return Err(invalid(
+                "the connection closed before the response head ended",
+            ));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+    let head = parse_head(&buf[..p]).ok_or_else(|| invalid("bad response head"))?;
+    let w = if (200..300).contains(&head.status) {
+        out
+    } else {
+        writeln!(err, "{} {}", head.status, head.reason)?;
+        err
+    };
+    // Whatever came after the head in the same read is the start of the body
+    let start = &buf[p + 4..];
+
+    if head.chunked {
+        let (mut decoder, mut payload) = (Chunked::default(), Vec::new());
+        let mut input = start.to_vec();
+        while !decoder.feed(&input, &mut payload)? {
+            w.write_all(&payload)?;
+            payload.clear();
+            let n = r.read(&mut chunk)?;
+            if n == 0 {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+            input = chunk[..n].to_vec();
+        }
+        w.write_all(&payload)?;
+    } else if let Some(len) = head.content_length {
+        let k = len.min(start.len());
+        w.write_all(&start[..k])?;
+        let rest = (len - k) as u64;
+        if std::io::copy(&mut std::io::Read::take(&mut *r, rest), w)? < rest {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+    } else {
+        w.write_all(start)?;
+        std::io::copy(r, w)?;
+    }
+    w.flush()?;
+    Ok(head.status)
+}
@@ -1,28 +1,53 @@
-use std::io::{Read, Write};
-use std::net::TcpStream;
+use std::io::{IsTerminal, Read, Write};
 
 fn main() {
-    let t = std::env::args().skip(1).collect::<Vec<_>>().join(" ");
-    if t.is_empty() { return; }
-
-    // inline percent-encode
-    let mut q = String::with_capacity(t.len());
-    for b in t.bytes() {
-        match b {
-            b'A'..=b'Z'|b'a'..=b'z'|b'0'..=b'9'|b'-'|b'_'|b'.'|b'~' => q.push(b as char),
-            b' ' => q.push('+'),
-            _ => { q.push('%'); for h in [(b>>4)&0xF, b&0xF] { q.push(if h<10 {(b'0'+h) as char} else {(b'A'+(h-10)) as char}); } }
+    // flags first: `-p` forces a POST, `-k` skips the TLS certificate check, `-u URL` picks the server
+    let mut args = std::env::args().skip(1).peekable();
+    let (mut post, mut insecure, mut url) = (
+        false,
+        false,
+        std::env::var("AI_SERVER_URL")
+            .ok()
+            .filter(|u| !u.is_empty()),
+    );
+    while let Some(f) = args.next_if(|a| matches!(a.as_str(), "-p" | "-k" | "-u")) {
+        match f.as_str() {
+            "-p" => post = true,
+            "-k" => insecure = true,
+            _ => url = Some(args.next().unwrap_or_default()),
         }
     }
+    let a = args.collect::<Vec<_>>().join(" ");
+    let target = match ai::Target::parse(url.as_deref().unwrap_or(ai::DEFAULT_URL)) {
+        Ok(target) => target,
+        Err(e) => {
+            eprintln!("ai: {}", e);
+            std::process::exit(2);
+        }
+    };
 
-    let req = format!("GET /?text={} HTTP/1.1\r\nHost: localhost:8089\r\nConnection: close\r\n\r\n", q);
+    // piped-in text, e.g. `cat notes.md | ai summarize`
+    let mut s = String::new();
+    let stdin = std::io::stdin();
+    let piped = !stdin.is_terminal() && stdin.lock().read_to_string(&mut s).is_ok();
+    let t = ai::prompt(&a, piped.then_some(s.as_str()));
+    if t.is_empty() {
+        return;
+    }
 
-    if let Ok(mut s) = TcpStream::connect(("127.0.0.1", 8089)) {
-        let _ = s.write_all(req.as_bytes());
-        let mut buf = Vec::new();
-        let _ = s.read_to_end(&mut buf);
-        if let Some(p) = buf.windows(4).position(|w| w==[b'\r',b'\n',b'\r',b'\n']) {
-            let _ = std::io::stdout().write_all(&buf[p+4..]);
-        }
+    let req = ai::request(&target, &t, post);
+
+    // every failure names the server, so a wrong AI_SERVER_URL is easy to spot
+    let fail = |e: std::io::Error| -> ! {
+        eprintln!("ai: {}: {}", target, e);
+        std::process::exit(1)
+    };
+    let mut s = ai::connect(&target, insecure).unwrap_or_else(|e| fail(e));
+    s.write_all(req.as_bytes()).unwrap_or_else(|e| fail(e));
+    // the answer on stdout; an error status and its body on stderr, and exit code 1
+    match ai::relay(&mut s, &mut std::io::stdout(), &mut std::io::stderr()) {
+        Ok(200..=299) => {}
+        Ok(_) => std::process::exit(1),
+        Err(e) => fail(e),
     }
 }
@@ -0,0 +1,70 @@
+use ai::{
+    DEFAULT_URL, POST_THRESHOLD, Target, encode, get_request, json, post_request, prompt, request,
+};
+
+fn local() -> Target {
+    Target::parse(DEFAULT_URL).unwrap()
+}
+
+#[test]
+fn get_keeps_the_prompt_percent_encoded() {
+    assert_eq!(encode("a b&c=d/é~"), "a+b%26c%3Dd%2F%C3%A9~");
+    assert_eq!(
+        get_request(&local(), "two\nlines"),
+        "GET /?text=two%0Alines HTTP/1.1\r\nHost: 127.0.0.1:8089\r\nConnection: close\r\n\r\n"
+    );
+}
+
+#[test]
+fn post_sends_json_with_its_length_in_bytes() {
+    let req = post_request(&local(), "héllo");
+    let (head, body) = req.split_once("\r\n\r\n").unwrap();
+    assert_eq!(body, r#"{"text":"héllo"}"#);
+    assert_eq!(
+        head,
+        "POST /prompt HTTP/1.1\r\nHost: 127.0.0.1:8089\r\nContent-Type: application/json\r\nContent-Length: 17\r\nConnection: close"
+    );
+    // 16 characters, but é is two bytes
+    assert_eq!(body.len(), 17);
+}
+
+#[test]
+fn json_escapes_what_json_strings_cannot_hold() {
+    assert_eq!(json(r#"say "hi" \ bye"#), r#""say \"hi\" \\ bye""#);
+    assert_eq!(json("a\nb\r\tc\u{1}"), r#""a\nb\r\tc\u0001""#);
+    assert_eq!(json("ü 🦀"), "\"ü 🦀\"");
+}
+
+#[test]
+fn long_prompts_and_dash_p_use_post() {
+    assert!(request(&local(), "short", false).starts_with("GET /?text=short "));
+    assert!(request(&local(), "short", true).starts_with("POST /prompt "));
+    assert!(request(&local(), &"x".repeat(POST_THRESHOLD), false).starts_with("GET "));
+    assert!(request(&local(), &"x".repeat(POST_THRESHOLD + 1), false).starts_with("POST "));
+}
+
+#[test]
+fn piped_text_follows_the_arguments() {
+    assert_eq!(prompt("explain", None), "explain");
+    assert_eq!(
+        prompt("explain", Some("fn main() {}\n")),
+        "explain\n\nfn main() {}"
+    );
+    assert_eq!(prompt("", Some("just stdin\n")), "just stdin");
+    // An empty pipe changes nothing
+    assert_eq!(prompt("explain", Some("\n")), "explain");
+    assert_eq!(prompt("", Some("")), "");
+}
+
+#[test]
+fn requests_go_under_the_targets_path_with_its_host() {
+    let target = Target::parse("https://gemini.weolopez.com/api/").unwrap();
+    assert!(
+        get_request(&target, "hi")
+            .starts_with("GET /api/?text=hi HTTP/1.1\r\nHost: gemini.weolopez.com\r\n")
+    );
+    assert!(
+        post_request(&target, "hi")
+            .starts_with("POST /api/prompt HTTP/1.1\r\nHost: gemini.weolopez.com\r\n")
+    );
+}
@@ -0,0 +1,147 @@
+use std::io::{Cursor, Read};
+
+use ai::{Chunked, Head, parse_head, relay};
+
+const BODY: &[u8] = b"5\r\nHello\r\n8;lang=en\r\n, world!\r\n0\r\nX-Trailer: yes\r\n\r\n";
+
+// Feeds `parts` one after the other, like reads from a socket
+fn decode(parts: &[&[u8]]) -> (Vec<u8>, bool) {
+    let (mut decoder, mut out, mut done) = (Chunked::default(), Vec::new(), false);
+    for part in parts {
+        done = decoder.feed(part, &mut out).unwrap();
+    }
+    (out, done)
+}
+
+#[test]
+fn chunks_are_decoded_whole() {
+    assert_eq!(decode(&[BODY]), (b"Hello, world!".to_vec(), true));
+}
+
+#[test]
+fn chunks_split_anywhere_decode_the_same() {
+    // Every split point: inside sizes, data, CRLFs and the trailer
+    for i in 0..=BODY.len() {
+        assert_eq!(
+            decode(&[&BODY[..i], &BODY[i..]]),
+            (b"Hello, world!".to_vec(), true),
+            "split at {}",
+            i
+        );
+    }
+    // And one byte at a time
+    let bytes: Vec<&[u8]> = BODY.chunks(1).collect();
+    assert_eq!(decode(&bytes), (b"Hello, world!".to_vec(), true));
+}
+
+#[test]
+fn the_body_ends_only_with_the_zero_chunk() {
+    assert_eq!(decode(&[b"5\r\nHello\r\n"]), (b"Hello".to_vec(), false));
+    assert_eq!(
+        decode(&[b"5\r\nHello\r\n0\r\n"]),
+        (b"Hello".to_vec(), false)
+    );
+    assert_eq!(
+        decode(&[b"5\r\nHello\r\n0\r\n\r\n"]),
+        (b"Hello".to_vec(), true)
+    );
+    // Anything after the last chunk isn't part of the body
+    assert_eq!(decode(&[b"0\r\n\r\nHTTP/1.1 200 OK"]), (Vec::new(), true));
+}
+
+#[test]
+fn bad_chunks_are_errors() {
+    let mut out = Vec::new();
+    assert!(Chunked::default().feed(b"zz\r\n", &mut out).is_err());
+    assert!(Chunked::default().feed(b"2\r\nabX", &mut out).is_err());
+}
+
+#[test]
+fn the_head_says_how_the_body_ends() {
+    assert_eq!(
+        parse_head(b"HTTP/1.1 404 Not Found\r\ncontent-length: 12\r\nContent-Type: text/plain"),
+        Some(Head {
+            status: 404,
+            reason: "Not Found".to_string(),
+            chunked: false,
+            content_length: Some(12)
+        })
+    );
+    assert!(
+        parse_head(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, Chunked")
+            .unwrap()
+            .chunked
+    );
+    assert_eq!(parse_head(b"hello"), None);
+}
+
+// A socket that is never closed: reading past the response is a bug
+struct KeptOpen(Cursor<Vec<u8>>);
+
+impl Read for KeptOpen {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.0.read(buf)?;
+        assert!(n > 0, "read past the end of the response");
+        Ok(n)
+    }
+}
+
+fn run(response: &[u8]) -> (u16, String, String) {
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let status = relay(
+        &mut KeptOpen(Cursor::new(response.to_vec())),
+        &mut out,
+        &mut err,
+    )
+    .unwrap();
+    (
+        status,
+        String::from_utf8(out).unwrap(),
+        String::from_utf8(err).unwrap(),
+    )
+}
+
+#[test]
+fn only_the_payload_is_printed() {
+    let mut chunked = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+    chunked.extend_from_slice(BODY);
+    assert_eq!(
+        run(&chunked),
+        (200, "Hello, world!".to_string(), String::new())
+    );
+
+    let sized = b"HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nHello";
+    assert_eq!(run(sized), (200, "Hello".to_string(), String::new()));
+}
+
+#[test]
+fn error_statuses_go_to_stderr() {
+    let response = b"HTTP/1.1 400 Bad Request\r\nContent-Length: 17\r\n\r\n{\"error\": \"nope\"}";
+    assert_eq!(
+        run(response),
+        (
+            400,
+            String::new(),
+            "400 Bad Request\n{\"error\": \"nope\"}".to_string()
+        )
+    );
+}
+
+#[test]
+fn without_a_length_the_body_runs_to_the_close() {
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let status = relay(
+        &mut Cursor::new(b"HTTP/1.0 200 OK\r\n\r\nall of it".to_vec()),
+        &mut out,
+        &mut err,
+    )
+    .unwrap();
+    assert_eq!((status, out), (200, b"all of it".to_vec()));
+
+    let cut = relay(
+        &mut Cursor::new(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort".to_vec()),
+        &mut Vec::new(),
+        &mut Vec::new(),
+    );
+    assert_eq!(cut.unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+}
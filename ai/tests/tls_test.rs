@@ -0,0 +1,100 @@
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+
+use ai::{Target, connect, get_request, relay};
+use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+
+// An HTTPS server on a free port with a fresh self-signed certificate for `localhost`. It
+// answers `connections` requests with their request line, then stops.
+fn server(connections: usize) -> u16 {
+    let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(cert.key_pair.serialize_der()));
+    let config =
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.cert.der().clone()], key)
+            .unwrap();
+    let config = Arc::new(config);
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    thread::spawn(move || {
+        for tcp in listener.incoming().take(connections) {
+            let mut tls =
+                StreamOwned::new(ServerConnection::new(config.clone()).unwrap(), tcp.unwrap());
+            let mut request = Vec::new();
+            let mut byte = [0u8; 1];
+            // A failed handshake (the client refused the certificate) ends up here as an error
+            while !request.ends_with(b"\r\n\r\n") && tls.read(&mut byte).is_ok_and(|n| n == 1) {
+                request.push(byte[0]);
+            }
+            let line = String::from_utf8_lossy(&request)
+                .lines()
+                .next()
+                .unwrap_or("")
+                .to_string();
+            let body = format!("you sent {}", line);
+            let _ = write!(
+                tls,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            tls.conn.send_close_notify();
+            let _ = tls.flush();
+        }
+    });
+    port
+}
+
+#[test]
+fn insecure_accepts_a_self_signed_certificate() {
+    let port = server(1);
+    let target = Target::parse(&format!("https://localhost:{}/api", port)).unwrap();
+    let mut stream = connect(&target, true).unwrap();
+    stream
+        .write_all(get_request(&target, "hi there").as_bytes())
+        .unwrap();
+
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    assert_eq!(relay(&mut stream, &mut out, &mut err).unwrap(), 200);
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "you sent GET /api/?text=hi+there HTTP/1.1"
+    );
+}
+
+#[test]
+fn without_insecure_the_certificate_is_checked() {
+    let port = server(1);
+    let target = Target::parse(&format!("https://localhost:{}", port)).unwrap();
+    let mut stream = connect(&target, false).unwrap();
+    let e = stream
+        .write_all(get_request(&target, "hi").as_bytes())
+        .and_then(|_| stream.flush())
+        .and_then(|_| relay(&mut stream, &mut Vec::new(), &mut Vec::new()).map(|_| ()))
+        .unwrap_err();
+    assert!(e.to_string().contains("certificate"), "{}", e);
+}
+
+#[test]
+fn the_binary_talks_https_with_dash_k() {
+    let port = server(1);
+    let output = Command::new(env!("CARGO_BIN_EXE_ai"))
+        .args(["-k", "-u", &format!("https://localhost:{}", port), "-p"])
+        .arg("hello")
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    // Flags come in any order before the prompt
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "you sent POST /prompt HTTP/1.1"
+    );
+    assert!(output.status.success());
+}
@@ -0,0 +1,105 @@
+use std::process::{Command, Stdio};
+
+use ai::{DEFAULT_URL, Target};
+
+fn target(tls: bool, host: &str, port: u16, path: &str) -> Target {
+    Target {
+        tls,
+        host: host.to_string(),
+        port,
+        path: path.to_string(),
+    }
+}
+
+#[test]
+fn urls_name_scheme_host_port_and_path() {
+    assert_eq!(
+        Target::parse(DEFAULT_URL),
+        Ok(target(false, "127.0.0.1", 8089, ""))
+    );
+    assert_eq!(
+        Target::parse("https://gemini.weolopez.com"),
+        Ok(target(true, "gemini.weolopez.com", 443, ""))
+    );
+    assert_eq!(
+        Target::parse("http://example.com/"),
+        Ok(target(false, "example.com", 80, ""))
+    );
+    assert_eq!(
+        Target::parse("https://example.com:8443/ai/v1/"),
+        Ok(target(true, "example.com", 8443, "/ai/v1"))
+    );
+    assert_eq!(
+        Target::parse("http://[::1]:8089/x"),
+        Ok(target(false, "::1", 8089, "/x"))
+    );
+}
+
+#[test]
+fn bad_urls_say_why() {
+    for (url, why) in [
+        ("localhost:8089", "expected http://... or https://..."),
+        ("ftp://example.com", "expected http://... or https://..."),
+        ("http://", "no host"),
+        ("http://:8089", "no host"),
+        ("http://example.com:0", "the port must be a number"),
+        ("http://example.com:99999", "the port must be a number"),
+        ("http://example.com/?text=x", "no query"),
+        ("http://[::1/", "unclosed ["),
+    ] {
+        let e = Target::parse(url).unwrap_err();
+        assert!(e.contains(why) && e.contains(url), "{}: {}", url, e);
+    }
+}
+
+#[test]
+fn the_target_prints_as_one_full_url() {
+    assert_eq!(
+        Target::parse("https://gemini.weolopez.com/api")
+            .unwrap()
+            .to_string(),
+        "https://gemini.weolopez.com:443/api"
+    );
+    assert_eq!(
+        Target::parse("http://[::1]:8089").unwrap().to_string(),
+        "http://[::1]:8089"
+    );
+}
+
+#[test]
+fn a_server_that_is_not_there_is_named_in_the_error() {
+    // Bound and dropped at once, so nothing listens there
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let url = format!("http://127.0.0.1:{}", port);
+    for args in [vec!["-u", url.as_str(), "hi"], vec!["hi"]] {
+        let output = Command::new(env!("CARGO_BIN_EXE_ai"))
+            .args(&args)
+            .env("AI_SERVER_URL", &url)
+            .stdin(Stdio::null())
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.starts_with(&format!("ai: {}: ", url)), "{}", stderr);
+        assert!(output.stdout.is_empty());
+    }
+}
+
+#[test]
+fn a_bad_url_is_a_usage_error() {
+    let output = Command::new(env!("CARGO_BIN_EXE_ai"))
+        .args(["-u", "localhost", "hi"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("bad server URL \"localhost\"")
+    );
+}
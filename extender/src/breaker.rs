@@ -0,0 +1,145 @@
+// A circuit breaker for Gemini, so an outage costs callers one quick 503 instead of a slow 500.
+//
+// Like the one in a fuse box, it has three states:
+//
+//   closed     requests go through; every failure in a row is counted
+//   open       after `failures` in a row, nothing goes through for `open_for`: callers get a
+//              503 with `Retry-After` straight away, and Gemini gets a rest
+//   half-open  once `open_for` is over, one request goes through as a probe. If it works the
+//              breaker closes again; if not, it opens for another `open_for`. A probe that never
+//              reports back (its caller hung up, and the future was dropped) is given up on after
+//              another `open_for`, and the next request becomes the probe instead
+//
+// A failure is Gemini not being reachable, or a 5xx. A 4xx, a 429 included, means Gemini is up
+// and answering, so it counts as a success here.
+//
+//   BREAKER_FAILURES   failures in a row that open the breaker (5)
+//   BREAKER_OPEN_SECS  how long it stays open before the probe (30)
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+
+/// Failures in a row that open the breaker when `BREAKER_FAILURES` isn't set
+pub const DEFAULT_BREAKER_FAILURES: u32 = 5;
+
+/// How long the breaker stays open when `BREAKER_OPEN_SECS` isn't set
+pub const DEFAULT_BREAKER_OPEN: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    // The probe went out at `since`; everyone else still waits
+    HalfOpen { since: Instant },
+}
+
+/// Shared by all workers, so they all see Gemini the same way
+pub struct Breaker {
+    state: Mutex<State>,
+    failures: u32,
+    open_for: Duration,
+}
+
+impl Default for Breaker {
+    fn default() -> Breaker {
+        Breaker::new(DEFAULT_BREAKER_FAILURES, DEFAULT_BREAKER_OPEN)
+    }
+}
+
+impl Breaker {
+    /// Open after `failures` in a row (at least 1), for `open_for`
+    pub fn new(failures: u32, open_for: Duration) -> Breaker {
+        Breaker {
+            state: Mutex::new(State::Closed { failures: 0 }),
+            failures: failures.max(1),
+            open_for,
+        }
+    }
+
+    /// The breaker from `BREAKER_FAILURES` and `BREAKER_OPEN_SECS`. The error says which value
+    /// is wrong.
+    pub fn from_env() -> Result<Breaker, String> {
+        let number = |name: &str, default: u64| match std::env::var(name)
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+        {
+            Some(value) => match value.trim().parse::<u64>() {
+                Ok(number) if number > 0 => Ok(number),
+                _ => Err(format!(
+                    "{}={:?}: expected a whole number, at least 1",
+                    name, value
+                )),
+            },
+            None => Ok(default),
+        };
+        let failures = number("BREAKER_FAILURES", DEFAULT_BREAKER_FAILURES.into())?;
+        let open_for = number("BREAKER_OPEN_SECS", DEFAULT_BREAKER_OPEN.as_secs())?;
+        Ok(Breaker::new(
+            u32::try_from(failures).unwrap_or(u32::MAX),
+            Duration::from_secs(open_for),
+        ))
+    }
+
+    /// Whether a request may go to Gemini at `now`. `Err` is how long the caller should wait.
+    /// The first request after `open_for` is let through as the probe.
+    pub fn allow(&self, now: Instant) -> Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            State::Closed { .. } => Ok(()),
+            State::Open { until } if now < until => Err(until - now),
+            State::Open { .. } => {
+                *state = State::HalfOpen { since: now };
+                Ok(())
+            }
+            // The probe will know soon; a second is a fair guess
+            State::HalfOpen { since } if now < since + self.open_for => Err(Duration::from_secs(1)),
+            // The probe was lost, so this request takes its place
+            State::HalfOpen { .. } => {
+                *state = State::HalfOpen { since: now };
+                Ok(())
+            }
+        }
+    }
+
+    pub fn success(&self) {
+        *self.state.lock().unwrap() = State::Closed { failures: 0 };
+    }
+
+    pub fn failure(&self, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        *state = match *state {
+            State::Closed { failures } if failures + 1 < self.failures => State::Closed {
+                failures: failures + 1,
+            },
+            // The last failure in a row, or a failed probe
+            State::Closed { .. } | State::HalfOpen { .. } => State::Open {
+                until: now + self.open_for,
+            },
+            // A request from before the breaker opened; it stays open as it was
+            open @ State::Open { .. } => open,
+        };
+    }
+
+    /// Whether callers are being turned away at `now`
+    pub fn is_open(&self, now: Instant) -> bool {
+        matches!(*self.state.lock().unwrap(), State::Open { until } if now < until)
+    }
+
+    /// The state for `GET /healthz`
+    pub fn status(&self, now: Instant) -> Value {
+        let (state, failures, retry_after) = match *self.state.lock().unwrap() {
+            State::Closed { failures } => ("closed", failures, Duration::ZERO),
+            State::Open { until } => ("open", self.failures, until.saturating_duration_since(now)),
+            State::HalfOpen { .. } => ("half_open", self.failures, Duration::ZERO),
+        };
+        json!({
+            "state": state,
+            "consecutive_failures": failures,
+            "failure_threshold": self.failures,
+            "open_secs": self.open_for.as_secs(),
+            "retry_after_secs": retry_after.as_secs_f64().ceil() as u64,
+        })
+    }
+}
@@ -0,0 +1,97 @@
+// Which web pages may call the proxy from a browser.
+//
+// A page on another origin can only read our answers if we say so in CORS headers. For a JSON
+// POST with `X-Gemini-API-Key` the browser first asks with an OPTIONS "preflight", and only
+// sends the real request if the answer allows its origin, the method and the headers.
+// `actix-cors` answers the preflights; a request from an origin that isn't listed is
+// rejected by it with 400, before any handler runs.
+//
+//   ALLOWED_ORIGINS  exact origins like `https://demo.example.com`, separated by commas;
+//                    without it no CORS headers are sent at all
+//   CORS_ALLOW_ANY   must be `1` for `ALLOWED_ORIGINS=*`, which lets every site in
+
+use actix_cors::Cors;
+use actix_web::http::header::{CONTENT_TYPE, RETRY_AFTER};
+
+/// How long browsers may reuse a preflight answer, in seconds
+pub const PREFLIGHT_MAX_AGE: usize = 3600;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AllowedOrigins {
+    Any,
+    List(Vec<String>),
+}
+
+impl AllowedOrigins {
+    /// `origins` as `ALLOWED_ORIGINS` writes them. `*` only counts when `allow_any` is set,
+    /// so a wildcard can't slip in by accident.
+    pub fn parse(origins: &str, allow_any: bool) -> Result<AllowedOrigins, String> {
+        let origins: Vec<&str> = origins
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .collect();
+        if origins.contains(&"*") {
+            return if allow_any {
+                Ok(AllowedOrigins::Any)
+            } else {
+                Err(
+                    "ALLOWED_ORIGINS=*: also set CORS_ALLOW_ANY=1 to let every site call the proxy"
+                        .to_string(),
+                )
+            };
+        }
+        if let Some(origin) = origins.iter().find(|origin| !is_origin(origin)) {
+            return Err(format!(
+                "ALLOWED_ORIGINS: {:?} is not an origin like https://example.com (no path, no trailing slash)",
+                origin
+            ));
+        }
+        Ok(AllowedOrigins::List(
+            origins.into_iter().map(String::from).collect(),
+        ))
+    }
+
+    /// The origins from `ALLOWED_ORIGINS` and `CORS_ALLOW_ANY`, or `None` when no origin is set
+    pub fn from_env() -> Result<Option<AllowedOrigins>, String> {
+        let Some(origins) = std::env::var("ALLOWED_ORIGINS")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+        else {
+            return Ok(None);
+        };
+        let allow_any = std::env::var("CORS_ALLOW_ANY").is_ok_and(|value| value.trim() == "1");
+        AllowedOrigins::parse(&origins, allow_any).map(Some)
+    }
+
+    /// The middleware: POST with JSON and `X-Gemini-API-Key`, from these origins only
+    pub(crate) fn cors(&self) -> Cors {
+        let cors = Cors::default()
+            .allowed_methods(["POST"])
+            .allowed_headers([CONTENT_TYPE.as_str(), "X-Gemini-API-Key"])
+            // So pages can wait as long as the key pool asks
+            .expose_headers([RETRY_AFTER])
+            .max_age(PREFLIGHT_MAX_AGE)
+            // Off by default in actix-cors, which only leaves out the headers: the browser then
+            // hides the answer from the page, but the request has already reached Gemini on
+            // one of our keys
+            .block_on_origin_mismatch(true);
+        match self {
+            AllowedOrigins::Any => cors.allow_any_origin(),
+            AllowedOrigins::List(origins) => origins
+                .iter()
+                .fold(cors, |cors, origin| cors.allowed_origin(origin)),
+        }
+    }
+}
+
+// `scheme://host[:port]` and nothing more: browsers send the origin exactly like that, so
+// anything else would never match
+fn is_origin(origin: &str) -> bool {
+    match origin.split_once("://") {
+        Some(("http" | "https", host)) => {
+            !host.is_empty() && !host.contains(['/', '?', '#', '*', ' '])
+        }
+        _ => false,
+    }
+}
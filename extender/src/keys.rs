@@ -0,0 +1,189 @@
+// A pool of the server's own Gemini keys, for callers that don't send `X-Gemini-API-Key`.
+//
+// Requests take the keys in turn (round robin), so the load, and the quota, is spread over all
+// of them. A key that Gemini answers 429 for has used up its quota for now: it "cools down" and
+// is skipped for `cooldown`, and the request is tried once more with the next key.
+//
+//   GEMINI_API_KEYS    the keys, separated by commas
+//   KEY_COOLDOWN_SECS  how long a key is skipped after a 429 (60)
+//   ADMIN_TOKEN        the bearer token for `GET /keys/status`; without it the route is off
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use serde_json::{Value, json};
+
+/// How long a key is skipped after a 429 when `KEY_COOLDOWN_SECS` isn't set
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(60);
+
+struct Key {
+    value: String,
+    requests: AtomicU64,
+    rate_limited: AtomicU64,
+    // Skipped until then
+    cooling_until: Mutex<Option<Instant>>,
+}
+
+/// The keys, their counters and their cooldowns. Shared by all workers, so a key that
+/// cooled down on one is skipped on all of them.
+pub struct KeyPool {
+    keys: Vec<Key>,
+    // The index the next request starts looking from
+    next: AtomicUsize,
+    cooldown: Duration,
+    admin_token: Option<String>,
+}
+
+impl KeyPool {
+    pub fn new(keys: Vec<String>, cooldown: Duration) -> KeyPool {
+        let keys = keys.into_iter().map(|value| Key {
+            value,
+            requests: AtomicU64::new(0),
+            rate_limited: AtomicU64::new(0),
+            cooling_until: Mutex::new(None),
+        });
+        KeyPool {
+            keys: keys.collect(),
+            next: AtomicUsize::new(0),
+            cooldown,
+            admin_token: None,
+        }
+    }
+
+    /// Who may see `GET /keys/status`: requests with `Authorization: Bearer <token>`
+    pub fn with_admin_token(mut self, token: impl Into<String>) -> KeyPool {
+        self.admin_token = Some(token.into());
+        self
+    }
+
+    /// The pool from `GEMINI_API_KEYS`, `KEY_COOLDOWN_SECS` and `ADMIN_TOKEN`. The error
+    /// says which value is wrong.
+    pub fn from_env() -> Result<KeyPool, String> {
+        let var = |name| {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        };
+        let keys = var("GEMINI_API_KEYS").unwrap_or_default();
+        let keys = keys
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(String::from)
+            .collect();
+        let cooldown = match var("KEY_COOLDOWN_SECS") {
+            Some(value) => match value.trim().parse::<u64>() {
+                Ok(secs) if secs > 0 => Duration::from_secs(secs),
+                _ => {
+                    return Err(format!(
+                        "KEY_COOLDOWN_SECS={:?}: expected a whole number of seconds, at least 1",
+                        value
+                    ));
+                }
+            },
+            None => DEFAULT_COOLDOWN,
+        };
+        let pool = KeyPool::new(keys, cooldown);
+        Ok(match var("ADMIN_TOKEN") {
+            Some(token) => pool.with_admin_token(token),
+            None => pool,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The next key that isn't cooling down at `now`, counted as used. `None` when every
+    /// key is cooling down (or there are none).
+    pub fn pick(&self, now: Instant) -> Option<&str> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let len = self.keys.len();
+        let key = (0..len)
+            .map(|i| &self.keys[(start + i) % len])
+            .find(|key| !key.cooling(now))?;
+        key.requests.fetch_add(1, Ordering::Relaxed);
+        Some(&key.value)
+    }
+
+    /// Skip `value` until `cooldown` after `now`; Gemini said its quota is used up
+    pub fn cool_down(&self, value: &str, now: Instant) {
+        if let Some(key) = self.keys.iter().find(|key| key.value == value) {
+            key.rate_limited.fetch_add(1, Ordering::Relaxed);
+            *key.cooling_until.lock().unwrap() = Some(now + self.cooldown);
+        }
+    }
+
+    /// How long until the first key is usable again, when all of them are cooling down
+    pub fn wait(&self, now: Instant) -> Duration {
+        self.keys
+            .iter()
+            .filter_map(|key| *key.cooling_until.lock().unwrap())
+            .map(|until| until.saturating_duration_since(now))
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// Whether `authorization` (the whole header value) lets a caller see the status
+    pub fn is_admin(&self, authorization: Option<&str>) -> bool {
+        match (
+            &self.admin_token,
+            authorization.and_then(|value| value.strip_prefix("Bearer ")),
+        ) {
+            (Some(token), Some(given)) => token == given,
+            _ => false,
+        }
+    }
+
+    pub fn has_admin_token(&self) -> bool {
+        self.admin_token.is_some()
+    }
+
+    /// Each key's counters and cooldown, for `GET /keys/status`. Keys are shown by their last
+    /// four characters only.
+    pub fn status(&self, now: Instant) -> Value {
+        let keys: Vec<Value> = self
+            .keys
+            .iter()
+            .map(|key| {
+                let remaining = key
+                    .cooling_until
+                    .lock()
+                    .unwrap()
+                    .map(|until| until.saturating_duration_since(now))
+                    .unwrap_or_default();
+                json!({
+                    "key": mask(&key.value),
+                    "requests": key.requests.load(Ordering::Relaxed),
+                    "rate_limited": key.rate_limited.load(Ordering::Relaxed),
+                    "cooling_down": !remaining.is_zero(),
+                    "cooldown_remaining_secs": remaining.as_secs_f64().ceil() as u64,
+                })
+            })
+            .collect();
+        json!({ "keys": keys, "cooldown_secs": self.cooldown.as_secs() })
+    }
+}
+
+impl Key {
+    fn cooling(&self, now: Instant) -> bool {
+        self.cooling_until
+            .lock()
+            .unwrap()
+            .is_some_and(|until| now < until)
+    }
+}
+
+// `…wxyz`: enough to tell keys apart, not enough to use one. Short keys are hidden entirely.
+fn mask(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() < 12 {
+        return "…".to_string();
+    }
+    format!("…{}", chars[chars.len() - 4..].iter().collect::<String>())
+}
@@ -0,0 +1,537 @@
+//! extender: a small proxy in front of the Gemini API. Clients send their key in the
+//! `X-Gemini-API-Key` header and the proxy adds it to the Gemini URL, so browser code never
+//! builds Gemini URLs itself.
+//!
+//! - `POST /proxy` answers with Gemini's whole `generateContent` JSON
+//! - `POST /proxy/stream` passes `streamGenerateContent?alt=sse` through as it arrives
+//! - `POST /proxy/{model}` and `POST /proxy/{model}/stream` do the same with another model
+//! - `GET /keys/status` shows how the server's own keys are doing, for admins
+//! - `GET /usage/summary` shows how much each key was used over the last day, for admins
+//! - `GET /healthz` says whether the proxy is up, and how Gemini has been doing
+//! - `GET /readyz` says whether it should get traffic: Gemini is reachable (see `ready.rs`)
+//!
+//! Callers without a key use the server's key pool instead, when it has one (see `keys.rs`).
+//! Empty or oversized requests are answered 400 before Gemini sees them (see `limits.rs`).
+//! Browsers may call the proxy from the origins in `ALLOWED_ORIGINS` (see `cors.rs`).
+//! Every call to Gemini is accounted for in `usage.rs`. When Gemini fails, calls are retried
+//! once, and `breaker.rs` stops sending it anything while it keeps failing.
+//!
+//! `main.rs` only starts the server; tests point `Gemini` at a fake upstream.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use actix_web::error::JsonPayloadError;
+use actix_web::http::header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
+use actix_web::middleware::Condition;
+use actix_web::{App, Error, HttpResponse, web};
+use futures_util::StreamExt;
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+
+mod breaker;
+mod cors;
+mod keys;
+mod limits;
+mod models;
+mod ready;
+mod usage;
+
+pub use breaker::{Breaker, DEFAULT_BREAKER_FAILURES, DEFAULT_BREAKER_OPEN};
+pub use cors::{AllowedOrigins, PREFLIGHT_MAX_AGE};
+pub use keys::{DEFAULT_COOLDOWN, KeyPool};
+pub use limits::{DEFAULT_MAX_BODY_BYTES, DEFAULT_MAX_TEXT_BYTES, Limits};
+pub use models::{allowed_models_from_env, is_valid_model};
+pub use ready::{Check, DEFAULT_READY_REFRESH, Readiness};
+pub use usage::{SUMMARY_WINDOW, USAGE_CAPACITY, UsageEntry, UsageLog, key_hash};
+
+use usage::Pending;
+
+/// The real Gemini API; tests use a local stub instead
+pub const DEFAULT_GEMINI_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// The model `/proxy` and `/proxy/stream` use, when the path doesn't name one. It is always
+/// allowed, whatever `ALLOWED_MODELS` says.
+pub const DEFAULT_MODEL: &str = "gemini-2.0-flash";
+
+/// This build's version, in `/healthz` and `/readyz`
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Tries per call when Gemini can't be reached or answers 5xx: the first and one retry
+pub const MAX_ATTEMPTS: u32 = 2;
+
+/// The wait before the first retry; it doubles for each one after that
+pub const DEFAULT_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+#[derive(Deserialize, Serialize)]
+struct GeminiRequest {
+    contents: Vec<Content>,
+}
+#[derive(Deserialize, Serialize)]
+struct Content {
+    parts: Vec<Part>,
+}
+#[derive(Deserialize, Serialize)]
+struct Part {
+    text: String,
+}
+
+impl GeminiRequest {
+    // For the usage log
+    fn text_chars(&self) -> usize {
+        self.contents
+            .iter()
+            .flat_map(|content| &content.parts)
+            .map(|part| part.text.chars().count())
+            .sum()
+    }
+}
+
+/// Where requests go: the HTTP client, the API's base URL, the server's own keys, the models
+/// callers may pick, how big a request may be, which web pages may send one, where usage is
+/// logged, whether Gemini is failing and whether it can be reached. Shared through `web::Data`;
+/// clones share one key pool, one usage log, one breaker and one readiness check.
+#[derive(Clone)]
+pub struct Gemini {
+    client: Client,
+    base_url: String,
+    keys: Arc<KeyPool>,
+    // `None`: any valid model name
+    allowed_models: Option<Arc<[String]>>,
+    limits: Limits,
+    // `None`: no CORS headers, as before there was a demo site
+    origins: Option<AllowedOrigins>,
+    usage: Arc<UsageLog>,
+    breaker: Arc<Breaker>,
+    retry_backoff: Duration,
+    readiness: Arc<Readiness>,
+}
+
+impl Gemini {
+    /// Google's API, with no keys of its own: callers must send theirs
+    pub fn new(client: Client) -> Gemini {
+        Gemini {
+            client,
+            base_url: DEFAULT_GEMINI_URL.to_string(),
+            keys: Arc::new(KeyPool::new(Vec::new(), DEFAULT_COOLDOWN)),
+            allowed_models: None,
+            limits: Limits::default(),
+            origins: None,
+            usage: Arc::new(UsageLog::default()),
+            breaker: Arc::new(Breaker::default()),
+            retry_backoff: DEFAULT_RETRY_BACKOFF,
+            readiness: Arc::new(Readiness::default()),
+        }
+    }
+
+    /// Use `keys` for callers that don't send `X-Gemini-API-Key`
+    pub fn with_key_pool(mut self, keys: KeyPool) -> Gemini {
+        self.keys = Arc::new(keys);
+        self
+    }
+
+    /// Only let `/proxy/{model}` use these models (and `DEFAULT_MODEL`)
+    pub fn with_allowed_models(mut self, models: Vec<String>) -> Gemini {
+        self.allowed_models = Some(models.into());
+        self
+    }
+
+    /// Check requests against `limits` instead of the defaults
+    pub fn with_limits(mut self, limits: Limits) -> Gemini {
+        self.limits = limits;
+        self
+    }
+
+    /// Let browsers on `origins` call the proxy
+    pub fn with_allowed_origins(mut self, origins: AllowedOrigins) -> Gemini {
+        self.origins = Some(origins);
+        self
+    }
+
+    /// Record calls in `usage` instead of a memory-only log
+    pub fn with_usage_log(mut self, usage: UsageLog) -> Gemini {
+        self.usage = Arc::new(usage);
+        self
+    }
+
+    /// What has been recorded so far
+    pub fn usage_log(&self) -> &UsageLog {
+        &self.usage
+    }
+
+    /// Trip on `breaker` instead of the default thresholds
+    pub fn with_breaker(mut self, breaker: Breaker) -> Gemini {
+        self.breaker = Arc::new(breaker);
+        self
+    }
+
+    /// Wait `backoff` before the first retry instead of `DEFAULT_RETRY_BACKOFF`
+    pub fn with_retry_backoff(mut self, backoff: Duration) -> Gemini {
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Answer `/readyz` with `readiness` instead of connecting to the base URL every 30 seconds
+    pub fn with_readiness(mut self, readiness: Readiness) -> Gemini {
+        self.readiness = Arc::new(readiness);
+        self
+    }
+
+    /// Send requests to `base_url` (`.../v1beta`) instead of Google
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Gemini {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    // `method` is `generateContent` or `streamGenerateContent?alt=sse`; `model` was checked by `model()`.
+    // The call's usage entry comes along, to be completed by whoever reads the body.
+    //
+    // A connection error or a 5xx is tried again after `retry_backoff`, doubling each time, up to
+    // `MAX_ATTEMPTS` in all, and each one counts against the breaker. The last 5xx is passed on
+    // as it is. Nothing has reached the caller yet at this point, so a retried stream is safe.
+    async fn post(
+        &self,
+        model: &str,
+        method: &str,
+        key: &str,
+        body: &GeminiRequest,
+    ) -> Result<(reqwest::Response, Pending), Error> {
+        let separator = if method.contains('?') { '&' } else { '?' };
+        let url = format!(
+            "{}/models/{}:{}{}key={}",
+            self.base_url, model, method, separator, key
+        );
+        let mut backoff = self.retry_backoff;
+        for attempt in 1..=MAX_ATTEMPTS {
+            if let Err(wait) = self.breaker.allow(Instant::now()) {
+                let response = HttpResponse::ServiceUnavailable()
+                    .insert_header((RETRY_AFTER, seconds(wait).to_string()))
+                    .json(serde_json::json!({"error": "Gemini is failing; try again later"}));
+                return Err(error(response, "Gemini is failing"));
+            }
+            let started = Instant::now();
+            let last = attempt == MAX_ATTEMPTS;
+            match self.client.post(&url).json(body).send().await {
+                Ok(response) if !response.status().is_server_error() => {
+                    self.breaker.success();
+                    let usage = Pending::new(
+                        self.usage.clone(),
+                        key,
+                        model,
+                        body.text_chars(),
+                        response.status().as_u16(),
+                        started,
+                    );
+                    return Ok((response, usage));
+                }
+                Ok(response) => {
+                    self.breaker.failure(Instant::now());
+                    // Recorded, unread, when it is dropped for the retry
+                    let usage = Pending::new(
+                        self.usage.clone(),
+                        key,
+                        model,
+                        body.text_chars(),
+                        response.status().as_u16(),
+                        started,
+                    );
+                    if last {
+                        return Ok((response, usage));
+                    }
+                }
+                Err(_) => {
+                    self.breaker.failure(Instant::now());
+                    if last {
+                        let response = HttpResponse::BadGateway()
+                            .json(serde_json::json!({"error": "Gemini could not be reached"}));
+                        return Err(error(response, "Gemini could not be reached"));
+                    }
+                }
+            }
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+        unreachable!("the last attempt always returns")
+    }
+}
+
+// An error response as an `Error`, so handlers can return it with `?`
+fn error(response: HttpResponse, message: &'static str) -> Error {
+    actix_web::error::InternalError::from_response(message, response).into()
+}
+
+// For `Retry-After`: whole seconds, rounded up, and never 0, which would mean "right now"
+fn seconds(wait: Duration) -> u64 {
+    wait.as_secs_f64().ceil().max(1.0) as u64
+}
+
+/// The model the path names, or `DEFAULT_MODEL` for `/proxy` and `/proxy/stream`. A name that
+/// isn't valid, or isn't allowed, is a 400 here, before any key is picked or Gemini is called.
+fn model<'a>(gemini: &Gemini, req: &'a actix_web::HttpRequest) -> Result<&'a str, Error> {
+    let Some(model) = req.match_info().get("model") else {
+        return Ok(DEFAULT_MODEL);
+    };
+    if !is_valid_model(model) {
+        let response = HttpResponse::BadRequest()
+            .json(serde_json::json!({"error": "Invalid model name", "model": model}));
+        return Err(error(response, "Invalid model name"));
+    }
+    if let Some(allowed) = &gemini.allowed_models
+        && model != DEFAULT_MODEL
+        && !allowed.iter().any(|name| name == model)
+    {
+        let response = HttpResponse::BadRequest().json(serde_json::json!({"error": "Model not allowed", "model": model, "allowed": &allowed[..]}));
+        return Err(error(response, "Model not allowed"));
+    }
+    Ok(model)
+}
+
+/// Send `body` to `method` with the caller's key, or else with a key from the pool. Both
+/// routes use this, so they treat the header, and the pool, the same way.
+///
+/// A pool key that gets a 429 cools down, and the request is sent once more with the next
+/// key. The caller's own key is used as is: it has no other key to fall back to.
+async fn send(
+    gemini: &Gemini,
+    req: &actix_web::HttpRequest,
+    method: &str,
+    body: &GeminiRequest,
+) -> Result<(reqwest::Response, Pending), Error> {
+    if let Err(message) = gemini.limits.check(body) {
+        return Err(error(
+            HttpResponse::BadRequest().json(serde_json::json!({"error": message})),
+            "Invalid request",
+        ));
+    }
+    let model = model(gemini, req)?;
+    if let Some(key) = req
+        .headers()
+        .get("X-Gemini-API-Key")
+        .and_then(|v| v.to_str().ok())
+    {
+        return gemini.post(model, method, key, body).await;
+    }
+    let pool = &gemini.keys;
+    if pool.is_empty() {
+        return Err(error(
+            HttpResponse::Unauthorized().json(serde_json::json!({"error": "Missing API key"})),
+            "Missing API key",
+        ));
+    }
+
+    let now = Instant::now();
+    let Some(key) = pool.pick(now) else {
+        let response = HttpResponse::TooManyRequests()
+            .insert_header((RETRY_AFTER, seconds(pool.wait(now)).to_string()))
+            .json(serde_json::json!({"error": "Every API key is cooling down"}));
+        return Err(error(response, "Every API key is cooling down"));
+    };
+    let (response, usage) = gemini.post(model, method, key, body).await?;
+    if response.status() != StatusCode::TOO_MANY_REQUESTS {
+        return Ok((response, usage));
+    }
+    pool.cool_down(key, now);
+    let Some(next) = pool.pick(now) else {
+        return Ok((response, usage));
+    };
+    // The first try's entry is recorded here, as it is dropped
+    let retried = gemini.post(model, method, next, body).await?;
+    if retried.0.status() == StatusCode::TOO_MANY_REQUESTS {
+        pool.cool_down(next, Instant::now());
+    }
+    Ok(retried)
+}
+
+// An upstream error status, passed on with its body
+async fn upstream_error(response: reqwest::Response, mut usage: Pending) -> HttpResponse {
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    usage.add_response(text.as_bytes());
+    HttpResponse::build(status).body(text)
+}
+
+// A 200 from Gemini that isn't an answer: 502, with what Gemini sent attached for debugging
+fn bad_gateway(message: &str, upstream: serde_json::Value) -> HttpResponse {
+    HttpResponse::BadGateway().json(serde_json::json!({"error": message, "upstream": upstream}))
+}
+
+// Bodies that aren't JSON, or are over `max_body_bytes`, get a JSON error like everything else
+fn json_error(err: JsonPayloadError, _req: &actix_web::HttpRequest) -> Error {
+    let response = match &err {
+        JsonPayloadError::Overflow { limit }
+        | JsonPayloadError::OverflowKnownLength { limit, .. } => HttpResponse::PayloadTooLarge()
+            .json(
+                serde_json::json!({"error": format!("The body is over the {} byte limit", limit)}),
+            ),
+        _ => HttpResponse::BadRequest()
+            .json(serde_json::json!({"error": format!("Invalid JSON body: {}", err)})),
+    };
+    actix_web::error::InternalError::from_response(err, response).into()
+}
+
+async fn proxy_gemini(
+    req_body: web::Json<GeminiRequest>,
+    gemini: web::Data<Gemini>,
+    req: actix_web::HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let (response, mut usage) = send(&gemini, &req, "generateContent", &req_body).await?;
+
+    if !response.status().is_success() {
+        return Ok(upstream_error(response, usage).await);
+    }
+    // A blocked prompt, for one, is a 200 with `promptFeedback` and no candidates
+    let text = response
+        .text()
+        .await
+        .map_err(|_| actix_web::error::ErrorBadGateway("Gemini's answer was cut off"))?;
+    usage.add_response(text.as_bytes());
+    let Ok(body) = serde_json::from_str::<serde_json::Value>(&text) else {
+        return Ok(bad_gateway("Gemini's answer is not JSON", text.into()));
+    };
+    if body["candidates"]
+        .as_array()
+        .is_none_or(|candidates| candidates.is_empty())
+    {
+        return Ok(bad_gateway("Gemini's answer has no candidates", body));
+    }
+    Ok(HttpResponse::Ok().json(body))
+}
+
+/// `POST /proxy/stream`: the same request as `/proxy`, answered with Gemini's SSE stream.
+///
+/// The upstream body goes back to the caller chunk by chunk, without being parsed. actix owns
+/// the stream while it sends the response; when the caller disconnects, actix drops it, which
+/// drops the upstream response and closes that connection too, so Gemini stops generating an
+/// answer nobody reads.
+async fn proxy_gemini_stream(
+    req_body: web::Json<GeminiRequest>,
+    gemini: web::Data<Gemini>,
+    req: actix_web::HttpRequest,
+) -> Result<HttpResponse, Error> {
+    let (response, mut usage) =
+        send(&gemini, &req, "streamGenerateContent?alt=sse", &req_body).await?;
+    if !response.status().is_success() {
+        return Ok(upstream_error(response, usage).await);
+    }
+
+    let mut builder = HttpResponse::Ok();
+    // Gemini sends `text/event-stream`; pass on whatever it was
+    if let Some(content_type) = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+    {
+        builder.insert_header((CONTENT_TYPE, content_type.to_string()));
+    }
+    // Proxies in front of this one must not buffer the events either
+    builder.insert_header(("X-Accel-Buffering", "no"));
+    // `usage` lives in the closure, so it is recorded when the stream ends or the caller hangs up
+    let body = response.bytes_stream().map(move |chunk| {
+        if let Ok(bytes) = &chunk {
+            usage.add_response(bytes);
+        }
+        chunk
+    });
+    Ok(builder.streaming(body))
+}
+
+// Only whoever has the admin token gets past this. Without an `ADMIN_TOKEN` the admin routes
+// are off: 404, as if they didn't exist.
+fn admin(gemini: &Gemini, req: &actix_web::HttpRequest) -> Result<(), Error> {
+    let pool = &gemini.keys;
+    if !pool.has_admin_token() {
+        return Err(error(HttpResponse::NotFound().finish(), "Not found"));
+    }
+    let authorization = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+    if !pool.is_admin(authorization) {
+        return Err(error(
+            HttpResponse::Unauthorized()
+                .json(serde_json::json!({"error": "Missing or wrong admin token"})),
+            "Missing or wrong admin token",
+        ));
+    }
+    Ok(())
+}
+
+/// `GET /keys/status`: each pool key's request count and cooldown, for admins
+async fn keys_status(
+    gemini: web::Data<Gemini>,
+    req: actix_web::HttpRequest,
+) -> Result<HttpResponse, Error> {
+    admin(&gemini, &req)?;
+    Ok(HttpResponse::Ok().json(gemini.keys.status(Instant::now())))
+}
+
+/// `GET /usage/summary`: calls, errors, text and average latency per key hash over the last
+/// 24 hours, for admins. Callers' own keys are in it as well as the pool's.
+async fn usage_summary(
+    gemini: web::Data<Gemini>,
+    req: actix_web::HttpRequest,
+) -> Result<HttpResponse, Error> {
+    admin(&gemini, &req)?;
+    Ok(HttpResponse::Ok().json(gemini.usage.summary(usage::unix_now())))
+}
+
+/// `GET /healthz`: 200 whenever the proxy is up, with the breaker's state. `degraded` means the
+/// breaker is open: the proxy works, Gemini doesn't.
+async fn healthz(gemini: web::Data<Gemini>) -> HttpResponse {
+    let now = Instant::now();
+    let status = if gemini.breaker.is_open(now) {
+        "degraded"
+    } else {
+        "ok"
+    };
+    HttpResponse::Ok().json(serde_json::json!({"status": status, "version": VERSION, "breaker": gemini.breaker.status(now)}))
+}
+
+/// `GET /readyz`: 200 when requests would get through, 503 with the reason when the breaker
+/// is open or Gemini can't be reached
+async fn readyz(gemini: web::Data<Gemini>) -> HttpResponse {
+    let not_ready = |reason: String| {
+        HttpResponse::ServiceUnavailable()
+            .json(serde_json::json!({"status": "not_ready", "version": VERSION, "reason": reason}))
+    };
+    if gemini.breaker.is_open(Instant::now()) {
+        return not_ready("The circuit breaker is open: Gemini keeps failing".to_string());
+    }
+    match gemini.readiness.upstream(&gemini.base_url).await {
+        Ok(()) => {
+            HttpResponse::Ok().json(serde_json::json!({"status": "ready", "version": VERSION}))
+        }
+        Err(reason) => not_ready(reason),
+    }
+}
+
+/// The app, for `HttpServer::new` or a test
+pub fn create_app(
+    gemini: Gemini,
+) -> App<
+    impl actix_web::dev::ServiceFactory<
+        actix_web::dev::ServiceRequest,
+        Config = (),
+        Response = actix_web::dev::ServiceResponse<impl actix_web::body::MessageBody>,
+        Error = actix_web::Error,
+        InitError = (),
+    >,
+> {
+    let json = web::JsonConfig::default()
+        .limit(gemini.limits.max_body_bytes)
+        .error_handler(json_error);
+    let cors = gemini.origins.as_ref().map(AllowedOrigins::cors);
+    App::new()
+        .wrap(Condition::new(cors.is_some(), cors.unwrap_or_default()))
+        .app_data(json)
+        .app_data(web::Data::new(gemini))
+        .route("/proxy", web::post().to(proxy_gemini))
+        // Before `/proxy/{model}`, so `stream` is never taken for a model name
+        .route("/proxy/stream", web::post().to(proxy_gemini_stream))
+        .route("/proxy/{model}", web::post().to(proxy_gemini))
+        .route("/proxy/{model}/stream", web::post().to(proxy_gemini_stream))
+        .route("/keys/status", web::get().to(keys_status))
+        .route("/usage/summary", web::get().to(usage_summary))
+        .route("/healthz", web::get().to(healthz))
+        .route("/readyz", web::get().to(readyz))
+}
@@ -0,0 +1,80 @@
+// What a request must look like before it is sent to Gemini.
+//
+// Serde only checks the shape: `{"contents": []}` or a part with a megabyte of text both
+// deserialize fine, and Google would charge for (or reject) them after a round trip. These
+// checks answer 400 straight away, with a message that says which part is wrong.
+//
+//   MAX_TEXT_BYTES  the most text, over all parts, one request may send (32 KB)
+//   MAX_BODY_BYTES  the largest JSON body actix reads at all; bigger ones are 413 (64 KB)
+
+use crate::GeminiRequest;
+
+/// The text budget when `MAX_TEXT_BYTES` isn't set
+pub const DEFAULT_MAX_TEXT_BYTES: usize = 32 * 1024;
+
+/// The body limit when `MAX_BODY_BYTES` isn't set. Leaves room for the JSON around the text.
+pub const DEFAULT_MAX_BODY_BYTES: usize = 64 * 1024;
+
+#[derive(Clone, Copy, Debug)]
+pub struct Limits {
+    pub max_text_bytes: usize,
+    pub max_body_bytes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_text_bytes: DEFAULT_MAX_TEXT_BYTES,
+            max_body_bytes: DEFAULT_MAX_BODY_BYTES,
+        }
+    }
+}
+
+impl Limits {
+    /// The limits from `MAX_TEXT_BYTES` and `MAX_BODY_BYTES`. The error says which value is wrong.
+    pub fn from_env() -> Result<Limits, String> {
+        let bytes = |name: &str, default: usize| match std::env::var(name)
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+        {
+            Some(value) => match value.trim().parse::<usize>() {
+                Ok(bytes) if bytes > 0 => Ok(bytes),
+                _ => Err(format!(
+                    "{}={:?}: expected a number of bytes, at least 1",
+                    name, value
+                )),
+            },
+            None => Ok(default),
+        };
+        Ok(Limits {
+            max_text_bytes: bytes("MAX_TEXT_BYTES", DEFAULT_MAX_TEXT_BYTES)?,
+            max_body_bytes: bytes("MAX_BODY_BYTES", DEFAULT_MAX_BODY_BYTES)?,
+        })
+    }
+
+    // `Err` is the message for the 400
+    pub(crate) fn check(&self, request: &GeminiRequest) -> Result<(), String> {
+        if request.contents.is_empty() {
+            return Err("`contents` is empty".to_string());
+        }
+        let mut total = 0;
+        for (i, content) in request.contents.iter().enumerate() {
+            if content.parts.is_empty() {
+                return Err(format!("`contents[{}].parts` is empty", i));
+            }
+            for (j, part) in content.parts.iter().enumerate() {
+                if part.text.trim().is_empty() {
+                    return Err(format!("`contents[{}].parts[{}].text` is empty", i, j));
+                }
+                total += part.text.len();
+            }
+        }
+        if total > self.max_text_bytes {
+            return Err(format!(
+                "The text is {} bytes; the limit is {}",
+                total, self.max_text_bytes
+            ));
+        }
+        Ok(())
+    }
+}
@@ -1,38 +1,49 @@
-use actix_web::{web, App, HttpServer, HttpResponse, Error};
+use actix_web::HttpServer;
+use extender::{
+    AllowedOrigins, Breaker, Gemini, KeyPool, Limits, UsageLog, allowed_models_from_env, create_app,
+};
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
-#[derive(Deserialize, Serialize)]
-struct GeminiRequest { contents: Vec<Content> }
-#[derive(Deserialize, Serialize)]
-struct Content { parts: Vec<Part> }
-#[derive(Deserialize, Serialize)]
-struct Part { text: String }
-async fn proxy_gemini(
-    req_body: web::Json<GeminiRequest>,
-    client: web::Data<Client>, // Keep client for making HTTP requests
-    req: actix_web::HttpRequest,
-) -> Result<HttpResponse, Error> {
-    let api_key = req.headers().get("X-Gemini-API-Key").and_then(|v| v.to_str().ok()).map(|s| s.to_string());
-    if api_key.is_none() {
-        return Ok(HttpResponse::Unauthorized().json(serde_json::json!({"error": "Missing API key"})));
-    }
-
-    let url = format!("{}?key={}", "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.0-flash:generateContent", api_key.unwrap());
-    let response = client.post(&url).json(&req_body.into_inner()).send().await.map_err(|_| actix_web::error::ErrorInternalServerError("Request failed"))?;
 
-    if response.status().is_success() {
-        let body: serde_json::Value = response.json().await.map_err(|_| actix_web::error::ErrorInternalServerError("Parse failed"))?;
-        Ok(HttpResponse::Ok().json(body))
-    } else {
-        Ok(HttpResponse::build(response.status()).body(response.text().await.unwrap_or_default()))
-    }
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    // Settings can also come from a `.env` file next to the binary
+    dotenvy::dotenv().ok();
+    // Built once, outside the closure, so every worker shares the same key pool
+    let gemini = match config() {
+        Ok(gemini) => gemini,
+        Err(e) => {
+            eprintln!("Invalid configuration: {}", e);
+            std::process::exit(2);
+        }
+    };
+    HttpServer::new(move || create_app(gemini.clone()))
+        .bind("0.0.0.0:8089")?
+        .run()
+        .await
 }
 
-fn create_app(client: Client) -> App<impl actix_web::dev::ServiceFactory<actix_web::dev::ServiceRequest, Config = (), Response = actix_web::dev::ServiceResponse<impl actix_web::body::MessageBody>, Error = actix_web::Error, InitError = ()>> {
-    App::new().app_data(web::Data::new(client)).route("/proxy", web::post().to(proxy_gemini))
+// Everything the environment sets, or the first value it got wrong
+fn config() -> Result<Gemini, String> {
+    let keys = KeyPool::from_env()?;
+    println!(
+        "Listening on 0.0.0.0:8089 with {} pooled API keys",
+        keys.len()
+    );
+    let mut gemini = Gemini::new(Client::new())
+        .with_key_pool(keys)
+        .with_limits(Limits::from_env()?)
+        .with_usage_log(UsageLog::from_env()?)
+        .with_breaker(Breaker::from_env()?);
+    if let Some(models) = allowed_models_from_env()? {
+        println!("Allowed models: {}", models.join(", "));
+        gemini = gemini.with_allowed_models(models);
+    }
+    if let Some(origins) = AllowedOrigins::from_env()? {
+        match &origins {
+            AllowedOrigins::Any => println!("CORS: any origin"),
+            AllowedOrigins::List(list) => println!("CORS: {}", list.join(", ")),
+        }
+        gemini = gemini.with_allowed_origins(origins);
+    }
+    Ok(gemini)
 }
-
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    HttpServer::new(|| create_app(Client::new())).bind("0.0.0.0:8089")?.run().await
-}
\ No newline at end of file
@@ -0,0 +1,44 @@
+// Which models `/proxy/{model}` may name.
+//
+// The name goes straight into the upstream URL (`.../models/{model}:generateContent`), so it has
+// to look like a model name and nothing else: a `/`, `..`, `?` or `:` in it would point the
+// request somewhere else on Gemini. Names start with a letter or digit and then use only
+// letters, digits, `.`, `_` and `-`, like `gemini-1.5-pro` or `gemini-2.0-flash-001`.
+//
+//   ALLOWED_MODELS  the models callers may choose, separated by commas; any valid name without it
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+static MODEL_NAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9][A-Za-z0-9._-]{0,63}$").unwrap());
+
+/// Whether `name` is safe to put in the upstream URL
+pub fn is_valid_model(name: &str) -> bool {
+    MODEL_NAME.is_match(name)
+}
+
+/// The allowlist from `ALLOWED_MODELS`, or `None` when it isn't set. The error names the
+/// first entry that isn't a valid model name.
+pub fn allowed_models_from_env() -> Result<Option<Vec<String>>, String> {
+    let Some(value) = std::env::var("ALLOWED_MODELS")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+    else {
+        return Ok(None);
+    };
+    let models: Vec<String> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|model| !model.is_empty())
+        .map(String::from)
+        .collect();
+    match models.iter().find(|model| !is_valid_model(model)) {
+        Some(model) => Err(format!(
+            "ALLOWED_MODELS: {:?} is not a valid model name",
+            model
+        )),
+        None => Ok(Some(models)),
+    }
+}
@@ -0,0 +1,100 @@
+// Whether the proxy can do its job right now, for a load balancer's readiness check.
+//
+// Alive (`/healthz`) and ready (`/readyz`) are different questions: a proxy that can't reach
+// Gemini is alive, and restarting it won't help, but new requests are better sent to another
+// instance. Ready means the breaker isn't open and Gemini's host can be reached.
+//
+// The default check resolves Gemini's host and opens a TCP connection to it: no request, so no
+// key and no quota. Load balancers ask every few seconds, so the answer is cached for `every`
+// (30 seconds), and however often `/readyz` is called Google sees at most one connection per
+// period from each instance.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures_util::future::BoxFuture;
+use reqwest::Url;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+
+/// How long a check's answer is reused
+pub const DEFAULT_READY_REFRESH: Duration = Duration::from_secs(30);
+
+// Longer than this and Gemini is as good as unreachable
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A connectivity check: `Err` says what went wrong
+pub type Check = Arc<dyn Fn() -> BoxFuture<'static, Result<(), String>> + Send + Sync>;
+
+pub struct Readiness {
+    // `None`: connect to the base URL's host
+    check: Option<Check>,
+    every: Duration,
+    // Held while checking, so callers arriving meanwhile wait for that answer instead of
+    // starting checks of their own
+    cached: Mutex<Option<(Instant, Result<(), String>)>>,
+}
+
+impl Default for Readiness {
+    fn default() -> Readiness {
+        Readiness {
+            check: None,
+            every: DEFAULT_READY_REFRESH,
+            cached: Mutex::new(None),
+        }
+    }
+}
+
+impl Readiness {
+    /// Use `check` instead of connecting to Gemini, like tests do
+    pub fn with_check<F>(mut self, check: F) -> Readiness
+    where
+        F: Fn() -> BoxFuture<'static, Result<(), String>> + Send + Sync + 'static,
+    {
+        self.check = Some(Arc::new(check));
+        self
+    }
+
+    /// Reuse an answer for `every` instead of `DEFAULT_READY_REFRESH`
+    pub fn with_refresh(mut self, every: Duration) -> Readiness {
+        self.every = every;
+        self
+    }
+
+    /// Whether Gemini at `base_url` can be reached, checked again only when the last answer
+    /// is older than `every`
+    pub async fn upstream(&self, base_url: &str) -> Result<(), String> {
+        let mut cached = self.cached.lock().await;
+        if let Some((checked, result)) = &*cached
+            && checked.elapsed() < self.every
+        {
+            return result.clone();
+        }
+        let result = match &self.check {
+            Some(check) => check().await,
+            None => connect(base_url).await,
+        };
+        *cached = Some((Instant::now(), result.clone()));
+        result
+    }
+}
+
+// DNS and a TCP handshake with the host behind `base_url`, and nothing more
+async fn connect(base_url: &str) -> Result<(), String> {
+    let url = Url::parse(base_url).map_err(|e| format!("Bad Gemini URL {}: {}", base_url, e))?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| format!("No host in {}", base_url))?
+        .to_string();
+    let port = url.port_or_known_default().unwrap_or(443);
+    match tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((host.as_str(), port))).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(format!("Cannot connect to {}:{}: {}", host, port, e)),
+        Err(_) => Err(format!(
+            "Connecting to {}:{} took over {} seconds",
+            host,
+            port,
+            CONNECT_TIMEOUT.as_secs()
+        )),
+    }
+}
@@ -0,0 +1,202 @@
+// Who used how much of Gemini through the proxy.
+//
+// Every upstream call leaves one `UsageEntry`: which key (as a hash, never the key itself), the
+// model, how much text went each way, Gemini's status and how long it took to answer. The
+// newest entries stay in memory for `GET /usage/summary`; with `USAGE_LOG` set they are also
+// appended to that file, one JSON object per line, for anything longer-term.
+//
+// Accounting must never cost a caller their answer, so nothing here returns an error once the
+// server is running: a write that fails is reported on stderr and the request goes on.
+//
+//   USAGE_LOG  a JSONL file to append every entry to; memory only without it
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+/// How many entries are kept in memory; older ones are only in `USAGE_LOG`
+pub const USAGE_CAPACITY: usize = 10_000;
+
+/// How far back `GET /usage/summary` looks
+pub const SUMMARY_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// One upstream call
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UsageEntry {
+    /// Unix seconds
+    pub timestamp: u64,
+    pub key_hash: String,
+    pub model: String,
+    pub prompt_chars: usize,
+    pub response_chars: usize,
+    /// Gemini's HTTP status
+    pub status: u16,
+    /// Until Gemini's response headers arrived; a stream keeps going after that
+    pub latency_ms: u64,
+}
+
+/// The newest entries, and the file they are copied to. Shared by all workers.
+pub struct UsageLog {
+    entries: Mutex<VecDeque<UsageEntry>>,
+    capacity: usize,
+    file: Option<Mutex<File>>,
+}
+
+impl Default for UsageLog {
+    fn default() -> UsageLog {
+        UsageLog::new(USAGE_CAPACITY)
+    }
+}
+
+impl UsageLog {
+    /// Keep the newest `capacity` entries, in memory only
+    pub fn new(capacity: usize) -> UsageLog {
+        UsageLog {
+            entries: Mutex::new(VecDeque::new()),
+            capacity,
+            file: None,
+        }
+    }
+
+    /// Also append every entry to `path`, which is created if needed
+    pub fn with_file(mut self, path: &str) -> std::io::Result<UsageLog> {
+        self.file = Some(Mutex::new(
+            OpenOptions::new().create(true).append(true).open(path)?,
+        ));
+        Ok(self)
+    }
+
+    /// The log `USAGE_LOG` asks for. The error says why the file can't be opened.
+    pub fn from_env() -> Result<UsageLog, String> {
+        match std::env::var("USAGE_LOG")
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+        {
+            Some(path) => UsageLog::default()
+                .with_file(path.trim())
+                .map_err(|e| format!("USAGE_LOG={:?}: {}", path, e)),
+            None => Ok(UsageLog::default()),
+        }
+    }
+
+    pub fn record(&self, entry: UsageEntry) {
+        if let Some(file) = &self.file {
+            // One `write_all` per line, so lines from different workers don't interleave
+            let line = format!("{}\n", serde_json::to_string(&entry).unwrap_or_default());
+            if let Err(e) = file.lock().unwrap().write_all(line.as_bytes()) {
+                eprintln!("Could not write to USAGE_LOG: {}", e);
+            }
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// The entries still in memory, oldest first
+    pub fn entries(&self) -> Vec<UsageEntry> {
+        self.entries.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Per key hash over the `SUMMARY_WINDOW` before `now` (Unix seconds): how many calls,
+    /// how many weren't 2xx, the text both ways and the average latency
+    pub fn summary(&self, now: u64) -> Value {
+        let since = now.saturating_sub(SUMMARY_WINDOW.as_secs());
+        let mut keys: BTreeMap<&str, (u64, u64, usize, usize, u64)> = BTreeMap::new();
+        let entries = self.entries.lock().unwrap();
+        for entry in entries.iter().filter(|entry| entry.timestamp >= since) {
+            let key = keys.entry(&entry.key_hash).or_default();
+            key.0 += 1;
+            key.1 += u64::from(!(200..300).contains(&entry.status));
+            key.2 += entry.prompt_chars;
+            key.3 += entry.response_chars;
+            key.4 += entry.latency_ms;
+        }
+        let keys: Vec<Value> = keys
+            .into_iter()
+            .map(
+                |(key_hash, (requests, errors, prompt_chars, response_chars, latency_ms))| {
+                    json!({
+                        "key_hash": key_hash,
+                        "requests": requests,
+                        "errors": errors,
+                        "prompt_chars": prompt_chars,
+                        "response_chars": response_chars,
+                        "average_latency_ms": latency_ms / requests,
+                    })
+                },
+            )
+            .collect();
+        json!({ "since": since, "keys": keys })
+    }
+}
+
+/// How `key` shows up in the log: the first 12 hex digits of its SHA-256. Enough to tell
+/// teams apart, and a team can work out its own with `sha256sum`.
+pub fn key_hash(key: &str) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .take(6)
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub(crate) fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+// Characters in UTF-8 `bytes`, counted without decoding: every byte but a continuation byte
+// starts one. Works on stream chunks that split a character, too.
+pub(crate) fn chars(bytes: &[u8]) -> usize {
+    bytes.iter().filter(|byte| *byte & 0xC0 != 0x80).count()
+}
+
+/// An entry waiting for the response text to be counted. Whoever reads the body adds to it;
+/// dropping it records it, so every call is logged exactly once however the handler ends,
+/// including when a streaming caller hangs up.
+pub(crate) struct Pending {
+    log: Arc<UsageLog>,
+    entry: UsageEntry,
+}
+
+impl Pending {
+    pub(crate) fn new(
+        log: Arc<UsageLog>,
+        key: &str,
+        model: &str,
+        prompt_chars: usize,
+        status: u16,
+        started: Instant,
+    ) -> Pending {
+        let entry = UsageEntry {
+            timestamp: unix_now(),
+            key_hash: key_hash(key),
+            model: model.to_string(),
+            prompt_chars,
+            response_chars: 0,
+            status,
+            latency_ms: started.elapsed().as_millis() as u64,
+        };
+        Pending { log, entry }
+    }
+
+    pub(crate) fn add_response(&mut self, bytes: &[u8]) {
+        self.entry.response_chars += chars(bytes);
+    }
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        self.log.record(self.entry.clone());
+    }
+}
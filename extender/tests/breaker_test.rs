@@ -0,0 +1,191 @@
+use std::time::{Duration, Instant};
+
+use actix_web::http::StatusCode;
+use actix_web::test::{TestRequest, call_service, init_service, read_body_json};
+use extender::{Breaker, Gemini, create_app};
+use reqwest::Client;
+use serde_json::{Value, json};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn answer() -> ResponseTemplate {
+    ResponseTemplate::new(200)
+        .set_body_json(json!({"candidates": [{"content": {"parts": [{"text": "Hi!"}]}}]}))
+}
+
+fn broken() -> ResponseTemplate {
+    ResponseTemplate::new(500).set_body_json(json!({"error": {"code": 500, "status": "INTERNAL"}}))
+}
+
+fn proxy(base_url: String, breaker: Breaker) -> Gemini {
+    Gemini::new(Client::new())
+        .with_base_url(base_url)
+        .with_breaker(breaker)
+        .with_retry_backoff(Duration::from_millis(10))
+}
+
+fn prompt(route: &str) -> TestRequest {
+    TestRequest::post()
+        .uri(route)
+        .insert_header(("X-Gemini-API-Key", "test-key"))
+        .set_json(json!({"contents": [{"parts": [{"text": "Hi"}]}]}))
+}
+
+fn healthz() -> TestRequest {
+    TestRequest::get().uri("/healthz")
+}
+
+#[actix_web::test]
+async fn a_5xx_is_retried_once() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(broken())
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(answer())
+        .mount(&server)
+        .await;
+    let app = init_service(create_app(proxy(server.uri(), Breaker::default()))).await;
+
+    let response = call_service(&app, prompt("/proxy").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    // The success wiped the failure out
+    assert_eq!(
+        read_body_json::<Value, _>(call_service(&app, healthz().to_request()).await).await["breaker"]
+            ["consecutive_failures"],
+        0
+    );
+}
+
+#[actix_web::test]
+async fn two_attempts_at_most_and_then_the_5xx_is_passed_on() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(broken())
+        .mount(&server)
+        .await;
+    let app = init_service(create_app(proxy(server.uri(), Breaker::default()))).await;
+
+    let response = call_service(&app, prompt("/proxy/stream").to_request()).await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    assert_eq!(
+        read_body_json::<Value, _>(call_service(&app, healthz().to_request()).await).await["breaker"]
+            ["consecutive_failures"],
+        2
+    );
+}
+
+#[actix_web::test]
+async fn an_unreachable_gemini_is_a_bad_gateway() {
+    // Bound and dropped at once, so nothing listens there
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let app = init_service(create_app(proxy(
+        format!("http://127.0.0.1:{}", port),
+        Breaker::default(),
+    )))
+    .await;
+
+    let response = call_service(&app, prompt("/proxy").to_request()).await;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    let body: Value = read_body_json(response).await;
+    assert_eq!(body["error"], "Gemini could not be reached");
+    assert_eq!(
+        read_body_json::<Value, _>(call_service(&app, healthz().to_request()).await).await["breaker"]
+            ["consecutive_failures"],
+        2
+    );
+}
+
+#[actix_web::test]
+async fn the_breaker_opens_and_a_probe_closes_it() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(broken())
+        .mount(&server)
+        .await;
+    let app = init_service(create_app(proxy(
+        server.uri(),
+        Breaker::new(2, Duration::from_millis(300)),
+    )))
+    .await;
+
+    // Both attempts fail, which is the threshold
+    call_service(&app, prompt("/proxy").to_request()).await;
+    let health_now: Value = read_body_json(call_service(&app, healthz().to_request()).await).await;
+    assert_eq!(health_now["status"], "degraded");
+    assert_eq!(health_now["breaker"]["state"], "open");
+
+    // Open: answered at once, without calling Gemini
+    let response = call_service(&app, prompt("/proxy").to_request()).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers().get("Retry-After").unwrap(), "1");
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+
+    // Gemini recovers; after the open period the next request is the probe
+    server.reset().await;
+    Mock::given(method("POST"))
+        .respond_with(answer())
+        .mount(&server)
+        .await;
+    tokio::time::sleep(Duration::from_millis(350)).await;
+    let response = call_service(&app, prompt("/proxy").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let health_now: Value = read_body_json(call_service(&app, healthz().to_request()).await).await;
+    assert_eq!(health_now["status"], "ok");
+    assert_eq!(health_now["breaker"]["state"], "closed");
+}
+
+#[test]
+fn a_failed_probe_opens_the_breaker_again() {
+    let breaker = Breaker::new(2, Duration::from_secs(30));
+    let start = Instant::now();
+    breaker.failure(start);
+    assert_eq!(breaker.allow(start), Ok(()));
+    breaker.failure(start);
+    assert_eq!(
+        breaker.allow(start + Duration::from_secs(10)),
+        Err(Duration::from_secs(20))
+    );
+
+    // The probe goes through; everyone else waits for it
+    let later = start + Duration::from_secs(30);
+    assert_eq!(breaker.allow(later), Ok(()));
+    assert!(breaker.allow(later).is_err());
+    assert_eq!(breaker.status(later)["state"], "half_open");
+
+    breaker.failure(later);
+    assert_eq!(breaker.allow(later), Err(Duration::from_secs(30)));
+
+    let even_later = later + Duration::from_secs(30);
+    assert_eq!(breaker.allow(even_later), Ok(()));
+    breaker.success();
+    assert_eq!(breaker.allow(even_later), Ok(()));
+    assert_eq!(breaker.status(even_later)["state"], "closed");
+}
+
+#[test]
+fn a_probe_that_never_reports_back_is_replaced() {
+    let breaker = Breaker::new(1, Duration::from_secs(30));
+    let start = Instant::now();
+    breaker.failure(start);
+
+    // The probe goes out, and its caller hangs up before Gemini answers
+    let probe = start + Duration::from_secs(30);
+    assert_eq!(breaker.allow(probe), Ok(()));
+    assert!(breaker.allow(probe + Duration::from_secs(29)).is_err());
+
+    // After another open period the next request is the probe
+    let retry = probe + Duration::from_secs(30);
+    assert_eq!(breaker.allow(retry), Ok(()));
+    assert!(breaker.allow(retry).is_err());
+    breaker.success();
+    assert_eq!(breaker.status(retry)["state"], "closed");
+}
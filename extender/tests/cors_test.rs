@@ -0,0 +1,157 @@
+use actix_web::http::StatusCode;
+use actix_web::test::{TestRequest, call_service, init_service};
+use extender::{AllowedOrigins, Gemini, create_app};
+use reqwest::Client;
+use serde_json::json;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const DEMO: &str = "https://demo.example.com";
+
+async fn gemini() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"candidates": [{"content": {"parts": [{"text": "Hi!"}]}}]})),
+        )
+        .mount(&server)
+        .await;
+    server
+}
+
+fn proxy(server: &MockServer, origins: AllowedOrigins) -> Gemini {
+    Gemini::new(Client::new())
+        .with_base_url(server.uri())
+        .with_allowed_origins(origins)
+}
+
+// What a browser sends before a JSON POST with the key header
+fn preflight(origin: &str) -> TestRequest {
+    TestRequest::default()
+        .method(actix_web::http::Method::OPTIONS)
+        .uri("/proxy")
+        .insert_header(("Origin", origin))
+        .insert_header(("Access-Control-Request-Method", "POST"))
+        .insert_header((
+            "Access-Control-Request-Headers",
+            "content-type,x-gemini-api-key",
+        ))
+}
+
+fn header<'a, B>(response: &'a actix_web::dev::ServiceResponse<B>, name: &str) -> Option<&'a str> {
+    response.headers().get(name).and_then(|v| v.to_str().ok())
+}
+
+#[actix_web::test]
+async fn an_allowed_origin_gets_a_cached_preflight() {
+    let server = gemini().await;
+    let app = init_service(create_app(proxy(
+        &server,
+        AllowedOrigins::List(vec![DEMO.to_string()]),
+    )))
+    .await;
+
+    let response = call_service(&app, preflight(DEMO).to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header(&response, "Access-Control-Allow-Origin"), Some(DEMO));
+    assert_eq!(
+        header(&response, "Access-Control-Allow-Methods"),
+        Some("POST")
+    );
+    assert!(
+        header(&response, "Access-Control-Allow-Headers")
+            .unwrap()
+            .contains("x-gemini-api-key")
+    );
+    assert_eq!(header(&response, "Access-Control-Max-Age"), Some("3600"));
+
+    // And the real request after it
+    let request = TestRequest::post()
+        .uri("/proxy")
+        .insert_header(("Origin", DEMO))
+        .insert_header(("X-Gemini-API-Key", "test-key"))
+        .set_json(json!({"contents": [{"parts": [{"text": "Hi"}]}]}));
+    let response = call_service(&app, request.to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header(&response, "Access-Control-Allow-Origin"), Some(DEMO));
+}
+
+#[actix_web::test]
+async fn other_origins_are_stopped_by_the_middleware() {
+    let server = gemini().await;
+    let app = init_service(create_app(proxy(
+        &server,
+        AllowedOrigins::List(vec![DEMO.to_string()]),
+    )))
+    .await;
+
+    let response = call_service(&app, preflight("https://evil.example.com").to_request()).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(header(&response, "Access-Control-Allow-Origin"), None);
+
+    // Without a preflight too: the handler never runs, so Gemini is never called
+    let request = TestRequest::post()
+        .uri("/proxy")
+        .insert_header(("Origin", "https://evil.example.com"))
+        .insert_header(("X-Gemini-API-Key", "test-key"))
+        .set_json(json!({"contents": [{"parts": [{"text": "Hi"}]}]}));
+    assert_eq!(
+        call_service(&app, request.to_request()).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn any_origin_when_explicitly_allowed() {
+    let server = gemini().await;
+    let app = init_service(create_app(proxy(&server, AllowedOrigins::Any))).await;
+
+    let response = call_service(&app, preflight("https://anywhere.example.org").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(header(&response, "Access-Control-Allow-Origin").is_some());
+}
+
+#[actix_web::test]
+async fn no_cors_headers_without_allowed_origins() {
+    let server = gemini().await;
+    let app = init_service(create_app(
+        Gemini::new(Client::new()).with_base_url(server.uri()),
+    ))
+    .await;
+
+    let request = TestRequest::post()
+        .uri("/proxy")
+        .insert_header(("Origin", DEMO))
+        .insert_header(("X-Gemini-API-Key", "test-key"))
+        .set_json(json!({"contents": [{"parts": [{"text": "Hi"}]}]}));
+    let response = call_service(&app, request.to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header(&response, "Access-Control-Allow-Origin"), None);
+}
+
+#[test]
+fn parsing_allowed_origins() {
+    assert_eq!(
+        AllowedOrigins::parse(" https://a.example.com , http://localhost:8080 ", false),
+        Ok(AllowedOrigins::List(vec![
+            "https://a.example.com".to_string(),
+            "http://localhost:8080".to_string()
+        ]))
+    );
+    assert_eq!(AllowedOrigins::parse("*", true), Ok(AllowedOrigins::Any));
+    assert!(
+        AllowedOrigins::parse("*", false)
+            .unwrap_err()
+            .contains("CORS_ALLOW_ANY=1")
+    );
+    for origin in [
+        "https://a.example.com/",
+        "a.example.com",
+        "ftp://a.example.com",
+        "https://*.example.com",
+    ] {
+        assert!(AllowedOrigins::parse(origin, true).is_err(), "{}", origin);
+    }
+}
@@ -0,0 +1,198 @@
+use std::time::{Duration, Instant};
+
+use actix_web::http::StatusCode;
+use actix_web::test::{TestRequest, call_service, init_service, read_body_json};
+use extender::{Gemini, KeyPool, create_app};
+use reqwest::Client;
+use serde_json::{Value, json};
+use wiremock::matchers::{method, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const EXHAUSTED: &str = "exhausted-key-0001";
+const ALSO_EXHAUSTED: &str = "exhausted-key-0003";
+const FRESH: &str = "fresh-key-00000002";
+
+// A stub Gemini where FRESH still works and every other key is over its quota
+async fn gemini() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(query_param("key", FRESH))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"candidates": [{"content": {"parts": [{"text": "Hi!"}]}}]})),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .set_body_json(json!({"error": {"code": 429, "status": "RESOURCE_EXHAUSTED"}})),
+        )
+        .mount(&server)
+        .await;
+    server
+}
+
+fn pool(keys: &[&str]) -> KeyPool {
+    KeyPool::new(
+        keys.iter().map(|key| key.to_string()).collect(),
+        Duration::from_secs(60),
+    )
+    .with_admin_token("admin-secret")
+}
+
+fn proxy(server: &MockServer, keys: KeyPool) -> Gemini {
+    Gemini::new(Client::new())
+        .with_base_url(server.uri())
+        .with_key_pool(keys)
+}
+
+fn prompt(route: &str) -> TestRequest {
+    TestRequest::post()
+        .uri(route)
+        .set_json(json!({"contents": [{"parts": [{"text": "Hi"}]}]}))
+}
+
+// The keys each upstream request used, in order
+async fn keys_used(server: &MockServer) -> Vec<String> {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| {
+            request
+                .url
+                .query_pairs()
+                .find(|(name, _)| name == "key")
+                .unwrap()
+                .1
+                .to_string()
+        })
+        .collect()
+}
+
+#[actix_web::test]
+async fn a_429_cools_the_key_down_and_retries_with_the_next() {
+    let server = gemini().await;
+    let app = init_service(create_app(proxy(&server, pool(&[EXHAUSTED, FRESH])))).await;
+
+    let response = call_service(&app, prompt("/proxy").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(keys_used(&server).await, [EXHAUSTED, FRESH]);
+
+    // The exhausted key is skipped from now on, on both routes
+    let response = call_service(&app, prompt("/proxy").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = call_service(&app, prompt("/proxy/stream").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(keys_used(&server).await, [EXHAUSTED, FRESH, FRESH, FRESH]);
+}
+
+#[actix_web::test]
+async fn only_one_retry_and_then_every_key_is_cooling_down() {
+    let server = gemini().await;
+    let app = init_service(create_app(proxy(
+        &server,
+        pool(&[EXHAUSTED, ALSO_EXHAUSTED]),
+    )))
+    .await;
+
+    // Both tries get a 429, which is passed on
+    let response = call_service(&app, prompt("/proxy").to_request()).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+
+    // Now no key is left to try, and the upstream isn't called at all
+    let response = call_service(&app, prompt("/proxy").to_request()).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().get("Retry-After").is_some());
+    assert_eq!(server.received_requests().await.unwrap().len(), 2);
+}
+
+#[actix_web::test]
+async fn the_callers_own_key_is_used_as_is() {
+    let server = gemini().await;
+    let app = init_service(create_app(proxy(&server, pool(&[FRESH])))).await;
+
+    let request = prompt("/proxy")
+        .insert_header(("X-Gemini-API-Key", EXHAUSTED))
+        .to_request();
+    let response = call_service(&app, request).await;
+    // No failover to the pool: the 429 is the caller's to handle
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(keys_used(&server).await, [EXHAUSTED]);
+}
+
+#[actix_web::test]
+async fn keys_status_needs_the_admin_token_and_masks_keys() {
+    let server = gemini().await;
+    let app = init_service(create_app(proxy(&server, pool(&[EXHAUSTED, FRESH])))).await;
+    call_service(&app, prompt("/proxy").to_request()).await;
+
+    for authorization in [None, Some("Bearer wrong"), Some("admin-secret")] {
+        let mut request = TestRequest::get().uri("/keys/status");
+        if let Some(value) = authorization {
+            request = request.insert_header(("Authorization", value));
+        }
+        let response = call_service(&app, request.to_request()).await;
+        assert_eq!(
+            response.status(),
+            StatusCode::UNAUTHORIZED,
+            "{:?}",
+            authorization
+        );
+    }
+
+    let request = TestRequest::get()
+        .uri("/keys/status")
+        .insert_header(("Authorization", "Bearer admin-secret"));
+    let response = call_service(&app, request.to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let status: Value = read_body_json(response).await;
+    assert_eq!(status["keys"][0]["key"], "…0001");
+    assert_eq!(status["keys"][0]["requests"], 1);
+    assert_eq!(status["keys"][0]["rate_limited"], 1);
+    assert_eq!(status["keys"][0]["cooling_down"], true);
+    assert_eq!(status["keys"][1]["requests"], 1);
+    assert_eq!(status["keys"][1]["cooling_down"], false);
+    assert!(
+        !status.to_string().contains(EXHAUSTED) && !status.to_string().contains(FRESH),
+        "{}",
+        status
+    );
+}
+
+#[actix_web::test]
+async fn keys_status_is_off_without_an_admin_token() {
+    let server = gemini().await;
+    let keys = KeyPool::new(vec![FRESH.to_string()], Duration::from_secs(60));
+    let app = init_service(create_app(proxy(&server, keys))).await;
+
+    let request = TestRequest::get()
+        .uri("/keys/status")
+        .insert_header(("Authorization", "Bearer anything"));
+    assert_eq!(
+        call_service(&app, request.to_request()).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn keys_rotate_and_come_back_after_the_cooldown() {
+    let pool = KeyPool::new(
+        vec!["a".to_string(), "b".to_string(), "c".to_string()],
+        Duration::from_secs(60),
+    );
+    let start = Instant::now();
+    let picks: Vec<&str> = (0..4).map(|_| pool.pick(start).unwrap()).collect();
+    assert_eq!(picks, ["a", "b", "c", "a"]);
+
+    pool.cool_down("b", start);
+    let picks: Vec<&str> = (0..3).map(|_| pool.pick(start).unwrap()).collect();
+    assert_eq!(picks, ["c", "c", "a"]);
+
+    let later = start + Duration::from_secs(60);
+    let picks: Vec<&str> = (0..3).map(|_| pool.pick(later).unwrap()).collect();
+    assert_eq!(picks, ["b", "c", "a"]);
+}
@@ -0,0 +1,158 @@
+use actix_web::http::StatusCode;
+use actix_web::test::{TestRequest, call_service, init_service, read_body_json};
+use extender::{Gemini, Limits, create_app};
+use reqwest::Client;
+use serde_json::{Value, json};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn gemini(answer: ResponseTemplate) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(answer)
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn answering() -> MockServer {
+    gemini(
+        ResponseTemplate::new(200)
+            .set_body_json(json!({"candidates": [{"content": {"parts": [{"text": "Hi!"}]}}]})),
+    )
+    .await
+}
+
+fn proxy(server: &MockServer) -> Gemini {
+    Gemini::new(Client::new())
+        .with_base_url(server.uri())
+        .with_limits(Limits {
+            max_text_bytes: 10,
+            max_body_bytes: 200,
+        })
+}
+
+fn prompt(body: Value) -> TestRequest {
+    TestRequest::post()
+        .uri("/proxy")
+        .insert_header(("X-Gemini-API-Key", "test-key"))
+        .set_json(body)
+}
+
+#[actix_web::test]
+async fn empty_requests_are_rejected_with_what_is_missing() {
+    let server = answering().await;
+    let app = init_service(create_app(proxy(&server))).await;
+
+    let cases = [
+        (json!({"contents": []}), "`contents` is empty"),
+        (
+            json!({"contents": [{"parts": [{"text": "Hi"}]}, {"parts": []}]}),
+            "`contents[1].parts` is empty",
+        ),
+        (
+            json!({"contents": [{"parts": [{"text": "Hi"}, {"text": "  "}]}]}),
+            "`contents[0].parts[1].text` is empty",
+        ),
+    ];
+    for (body, message) in cases {
+        let response = call_service(&app, prompt(body).to_request()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", message);
+        let body: Value = read_body_json(response).await;
+        assert_eq!(body, json!({"error": message}));
+    }
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn the_text_budget_counts_every_part() {
+    let server = answering().await;
+    let app = init_service(create_app(proxy(&server))).await;
+
+    // 5 + 5 bytes is exactly the budget
+    let body =
+        json!({"contents": [{"parts": [{"text": "Hello"}]}, {"parts": [{"text": "World"}]}]});
+    assert_eq!(
+        call_service(&app, prompt(body).to_request()).await.status(),
+        StatusCode::OK
+    );
+
+    let body =
+        json!({"contents": [{"parts": [{"text": "Hello"}]}, {"parts": [{"text": "World!"}]}]});
+    let response = call_service(&app, prompt(body).to_request()).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = read_body_json(response).await;
+    assert_eq!(body["error"], "The text is 11 bytes; the limit is 10");
+    assert_eq!(server.received_requests().await.unwrap().len(), 1);
+}
+
+#[actix_web::test]
+async fn bodies_over_the_limit_or_not_json_get_json_errors() {
+    let server = answering().await;
+    let app = init_service(create_app(proxy(&server))).await;
+
+    let response = call_service(
+        &app,
+        prompt(json!({"contents": [{"parts": [{"text": "x".repeat(300)}]}]})).to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body: Value = read_body_json(response).await;
+    assert_eq!(body["error"], "The body is over the 200 byte limit");
+
+    let request = TestRequest::post()
+        .uri("/proxy/stream")
+        .insert_header(("Content-Type", "application/json"))
+        .set_payload("{\"contents\": ");
+    let response = call_service(&app, request.to_request()).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = read_body_json(response).await;
+    assert!(
+        body["error"]
+            .as_str()
+            .unwrap()
+            .starts_with("Invalid JSON body"),
+        "{}",
+        body
+    );
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[actix_web::test]
+async fn an_answer_without_candidates_is_a_bad_gateway() {
+    let blocked = json!({"promptFeedback": {"blockReason": "SAFETY"}});
+    for upstream in [json!({"candidates": []}), blocked] {
+        let server = gemini(ResponseTemplate::new(200).set_body_json(&upstream)).await;
+        let app = init_service(create_app(proxy(&server))).await;
+
+        let response = call_service(
+            &app,
+            prompt(json!({"contents": [{"parts": [{"text": "Hi"}]}]})).to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let body: Value = read_body_json(response).await;
+        assert_eq!(
+            body,
+            json!({"error": "Gemini's answer has no candidates", "upstream": upstream})
+        );
+    }
+}
+
+#[actix_web::test]
+async fn an_answer_that_is_not_json_is_a_bad_gateway() {
+    let server = gemini(ResponseTemplate::new(200).set_body_string("<html>oops</html>")).await;
+    let app = init_service(create_app(proxy(&server))).await;
+
+    let response = call_service(
+        &app,
+        prompt(json!({"contents": [{"parts": [{"text": "Hi"}]}]})).to_request(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    let body: Value = read_body_json(response).await;
+    assert_eq!(
+        body,
+        json!({"error": "Gemini's answer is not JSON", "upstream": "<html>oops</html>"})
+    );
+}
@@ -0,0 +1,138 @@
+use actix_web::http::StatusCode;
+use actix_web::test::{TestRequest, call_service, init_service, read_body_json};
+use extender::{Gemini, create_app, is_valid_model};
+use reqwest::Client;
+use serde_json::{Value, json};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// A stub Gemini that answers any model
+async fn gemini() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"candidates": [{"content": {"parts": [{"text": "Hi!"}]}}]})),
+        )
+        .mount(&server)
+        .await;
+    server
+}
+
+fn prompt(route: &str) -> TestRequest {
+    TestRequest::post()
+        .uri(route)
+        .insert_header(("X-Gemini-API-Key", "test-key"))
+        .set_json(json!({"contents": [{"parts": [{"text": "Hi"}]}]}))
+}
+
+// The paths of the upstream requests, in order
+async fn paths(server: &MockServer) -> Vec<String> {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| request.url.path().to_string())
+        .collect()
+}
+
+#[actix_web::test]
+async fn the_path_names_the_upstream_model() {
+    let server = gemini().await;
+    let app = init_service(create_app(
+        Gemini::new(Client::new()).with_base_url(server.uri()),
+    ))
+    .await;
+
+    for route in [
+        "/proxy/gemini-1.5-pro",
+        "/proxy/gemini-1.5-pro/stream",
+        "/proxy",
+        "/proxy/stream",
+    ] {
+        let response = call_service(&app, prompt(route).to_request()).await;
+        assert_eq!(response.status(), StatusCode::OK, "{}", route);
+    }
+    assert_eq!(
+        paths(&server).await,
+        [
+            "/models/gemini-1.5-pro:generateContent",
+            "/models/gemini-1.5-pro:streamGenerateContent",
+            "/models/gemini-2.0-flash:generateContent",
+            "/models/gemini-2.0-flash:streamGenerateContent",
+        ]
+    );
+}
+
+#[actix_web::test]
+async fn names_that_could_leave_the_models_path_are_rejected() {
+    let server = gemini().await;
+    let app = init_service(create_app(
+        Gemini::new(Client::new()).with_base_url(server.uri()),
+    ))
+    .await;
+
+    for route in [
+        "/proxy/..%2Ffoo",
+        "/proxy/..",
+        "/proxy/gemini%3AcountTokens",
+        "/proxy/.hidden/stream",
+    ] {
+        let response = call_service(&app, prompt(route).to_request()).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{}", route);
+        let body: Value = read_body_json(response).await;
+        assert_eq!(body["error"], "Invalid model name", "{}", route);
+    }
+    // Rejected before anything was sent upstream
+    assert!(paths(&server).await.is_empty());
+}
+
+#[actix_web::test]
+async fn the_allowlist_limits_the_models_but_not_the_default() {
+    let server = gemini().await;
+    let proxy = Gemini::new(Client::new())
+        .with_base_url(server.uri())
+        .with_allowed_models(vec!["gemini-1.5-pro".to_string()]);
+    let app = init_service(create_app(proxy)).await;
+
+    let response = call_service(&app, prompt("/proxy/gemini-1.5-flash").to_request()).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = read_body_json(response).await;
+    assert_eq!(
+        body,
+        json!({"error": "Model not allowed", "model": "gemini-1.5-flash", "allowed": ["gemini-1.5-pro"]})
+    );
+    assert!(paths(&server).await.is_empty());
+
+    for route in ["/proxy/gemini-1.5-pro", "/proxy", "/proxy/gemini-2.0-flash"] {
+        assert_eq!(
+            call_service(&app, prompt(route).to_request())
+                .await
+                .status(),
+            StatusCode::OK,
+            "{}",
+            route
+        );
+    }
+}
+
+#[test]
+fn model_names() {
+    for name in ["gemini-2.0-flash", "gemini-1.5-pro-002", "text_model.v2"] {
+        assert!(is_valid_model(name), "{}", name);
+    }
+    for name in [
+        "",
+        "../foo",
+        "..",
+        "-flag",
+        "a/b",
+        "a:b",
+        "a?key=x",
+        "a b",
+        &"x".repeat(65),
+    ] {
+        assert!(!is_valid_model(name), "{}", name);
+    }
+}
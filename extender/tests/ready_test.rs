@@ -0,0 +1,159 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{TestRequest, call_service, init_service, read_body_json};
+use extender::{Breaker, Gemini, Readiness, VERSION, create_app};
+use futures_util::FutureExt;
+use reqwest::Client;
+use serde_json::{Value, json};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// A check that answers whatever `up` says, and counts how often it was asked
+fn checker(up: Arc<AtomicBool>, checks: Arc<AtomicUsize>) -> Readiness {
+    Readiness::default()
+        .with_refresh(Duration::from_millis(100))
+        .with_check(move || {
+            checks.fetch_add(1, Ordering::SeqCst);
+            let up = up.load(Ordering::SeqCst);
+            async move {
+                if up {
+                    Ok(())
+                } else {
+                    Err("Cannot connect to gemini: connection refused".to_string())
+                }
+            }
+            .boxed()
+        })
+}
+
+fn readyz() -> TestRequest {
+    TestRequest::get().uri("/readyz")
+}
+
+#[actix_web::test]
+async fn readiness_follows_the_check_at_most_once_per_refresh() {
+    let (up, checks) = (
+        Arc::new(AtomicBool::new(false)),
+        Arc::new(AtomicUsize::new(0)),
+    );
+    let app = init_service(create_app(
+        Gemini::new(Client::new()).with_readiness(checker(up.clone(), checks.clone())),
+    ))
+    .await;
+
+    let response = call_service(&app, readyz().to_request()).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = read_body_json(response).await;
+    assert_eq!(
+        body,
+        json!({"status": "not_ready", "version": VERSION, "reason": "Cannot connect to gemini: connection refused"})
+    );
+
+    // Back up, but the failure is still cached
+    up.store(true, Ordering::SeqCst);
+    assert_eq!(
+        call_service(&app, readyz().to_request()).await.status(),
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    assert_eq!(checks.load(Ordering::SeqCst), 1);
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    let response = call_service(&app, readyz().to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = read_body_json(response).await;
+    assert_eq!(body, json!({"status": "ready", "version": VERSION}));
+
+    // And down again after the next refresh
+    up.store(false, Ordering::SeqCst);
+    assert_eq!(
+        call_service(&app, readyz().to_request()).await.status(),
+        StatusCode::OK
+    );
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    assert_eq!(
+        call_service(&app, readyz().to_request()).await.status(),
+        StatusCode::SERVICE_UNAVAILABLE
+    );
+    assert_eq!(checks.load(Ordering::SeqCst), 3);
+}
+
+#[actix_web::test]
+async fn an_open_breaker_is_not_ready_even_when_gemini_is_reachable() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+    let (up, checks) = (
+        Arc::new(AtomicBool::new(true)),
+        Arc::new(AtomicUsize::new(0)),
+    );
+    let gemini = Gemini::new(Client::new())
+        .with_base_url(server.uri())
+        .with_readiness(checker(up, checks))
+        .with_breaker(Breaker::new(2, Duration::from_secs(30)))
+        .with_retry_backoff(Duration::from_millis(10));
+    let app = init_service(create_app(gemini)).await;
+    assert_eq!(
+        call_service(&app, readyz().to_request()).await.status(),
+        StatusCode::OK
+    );
+
+    let request = TestRequest::post()
+        .uri("/proxy")
+        .insert_header(("X-Gemini-API-Key", "test-key"))
+        .set_json(json!({"contents": [{"parts": [{"text": "Hi"}]}]}));
+    call_service(&app, request.to_request()).await;
+
+    let response = call_service(&app, readyz().to_request()).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = read_body_json(response).await;
+    assert_eq!(
+        body["reason"],
+        "The circuit breaker is open: Gemini keeps failing"
+    );
+
+    // Alive all the same
+    let response = call_service(&app, TestRequest::get().uri("/healthz").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = read_body_json(response).await;
+    assert_eq!(body["version"], VERSION);
+}
+
+#[actix_web::test]
+async fn the_default_check_connects_to_the_base_url() {
+    let server = MockServer::start().await;
+    let app = init_service(create_app(
+        Gemini::new(Client::new()).with_base_url(server.uri()),
+    ))
+    .await;
+    assert_eq!(
+        call_service(&app, readyz().to_request()).await.status(),
+        StatusCode::OK
+    );
+
+    // Bound and dropped at once, so nothing listens there
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let app = init_service(create_app(
+        Gemini::new(Client::new()).with_base_url(format!("http://127.0.0.1:{}", port)),
+    ))
+    .await;
+    let response = call_service(&app, readyz().to_request()).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = read_body_json(response).await;
+    assert!(
+        body["reason"]
+            .as_str()
+            .unwrap()
+            .starts_with(&format!("Cannot connect to 127.0.0.1:{}", port)),
+        "{}",
+        body
+    );
+}
@@ -0,0 +1,177 @@
+use std::time::{Duration, Instant};
+
+use actix_web::HttpServer;
+use extender::{Gemini, create_app};
+use futures_util::StreamExt;
+use reqwest::{Client, StatusCode};
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+const GAP: Duration = Duration::from_millis(300);
+
+fn frame(text: &str) -> String {
+    format!(
+        "data: {}\r\n\r\n",
+        json!({ "candidates": [ { "content": { "parts": [ { "text": text } ] } } ] })
+    )
+}
+
+// Read one whole request (headers, then Content-Length bytes of body) and return its request line
+async fn read_request(socket: &mut TcpStream) -> String {
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    loop {
+        let n = socket.read(&mut buffer).await.unwrap();
+        request.extend_from_slice(&buffer[..n]);
+        let text = String::from_utf8_lossy(&request).to_string();
+        if let Some(end) = text.find("\r\n\r\n") {
+            let length: usize = text
+                .lines()
+                .find_map(|line| {
+                    line.to_ascii_lowercase()
+                        .strip_prefix("content-length:")
+                        .map(|v| v.trim().parse().unwrap())
+                })
+                .unwrap_or(0);
+            if request.len() >= end + 4 + length {
+                return text.lines().next().unwrap().to_string();
+            }
+        }
+    }
+}
+
+// A fake Gemini that answers one request with chunked SSE: `frames`, with a pause before each.
+// wiremock sends a body all at once, so this writes the HTTP by hand. It reports the request
+// line, then whether the proxy hung up on it before it sent `frames` (and then `endless` more).
+async fn stub_gemini(
+    frames: Vec<String>,
+    endless: bool,
+) -> (String, oneshot::Receiver<String>, oneshot::Receiver<bool>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (request_line, received) = oneshot::channel();
+    let (hung_up, closed) = oneshot::channel();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let _ = request_line.send(read_request(&mut socket).await);
+        socket.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n").await.unwrap();
+        let mut frames = frames
+            .into_iter()
+            .chain(std::iter::repeat_with(|| frame("more")).take_while(|_| endless));
+        let gone = loop {
+            let Some(frame) = frames.next() else {
+                break false;
+            };
+            tokio::time::sleep(GAP).await;
+            let chunk = format!("{:x}\r\n{}\r\n", frame.len(), frame);
+            if socket.write_all(chunk.as_bytes()).await.is_err() || socket.flush().await.is_err() {
+                break true;
+            }
+        };
+        if !gone {
+            let _ = socket.write_all(b"0\r\n\r\n").await;
+        }
+        let _ = hung_up.send(gone);
+    });
+    (url, received, closed)
+}
+
+// Run extender on a free port, the way main.rs does
+fn start_proxy(gemini_url: &str) -> String {
+    let gemini = Gemini::new(Client::new()).with_base_url(gemini_url);
+    let server = HttpServer::new(move || create_app(gemini.clone()))
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+    let url = format!("http://{}", server.addrs()[0]);
+    actix_web::rt::spawn(server.run());
+    url
+}
+
+fn prompt() -> serde_json::Value {
+    json!({ "contents": [ { "parts": [ { "text": "Tell me a story" } ] } ] })
+}
+
+#[actix_web::test]
+async fn frames_are_passed_through_as_they_arrive() {
+    let (gemini, request_line, _) =
+        stub_gemini(vec![frame("Once"), frame(" upon"), frame(" a time")], false).await;
+    let proxy = start_proxy(&gemini);
+
+    let started = Instant::now();
+    let response = Client::new()
+        .post(format!("{}/proxy/stream", proxy))
+        .header("X-Gemini-API-Key", "test-key")
+        .json(&prompt())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+
+    // (when, bytes) for each chunk the caller got
+    let mut chunks = Vec::new();
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        chunks.push((started.elapsed(), chunk.unwrap()));
+    }
+    let all: Vec<u8> = chunks
+        .iter()
+        .flat_map(|(_, bytes)| bytes.to_vec())
+        .collect();
+    assert_eq!(
+        String::from_utf8(all).unwrap(),
+        [frame("Once"), frame(" upon"), frame(" a time")].concat()
+    );
+    // The first frame arrived long before the last one was sent, so nothing was buffered
+    assert!(chunks.len() >= 3, "{:?}", chunks);
+    assert!(chunks[0].0 < GAP * 2, "{:?}", chunks);
+
+    assert_eq!(
+        request_line.await.unwrap(),
+        "POST /models/gemini-2.0-flash:streamGenerateContent?alt=sse&key=test-key HTTP/1.1"
+    );
+}
+
+#[actix_web::test]
+async fn a_caller_that_hangs_up_closes_the_upstream_request() {
+    let (gemini, _, closed) = stub_gemini(vec![frame("Once")], true).await;
+    let proxy = start_proxy(&gemini);
+
+    let response = Client::new()
+        .post(format!("{}/proxy/stream", proxy))
+        .header("X-Gemini-API-Key", "test-key")
+        .json(&prompt())
+        .send()
+        .await
+        .unwrap();
+    let mut body = response.bytes_stream();
+    body.next().await.unwrap().unwrap();
+    drop(body);
+
+    // The stub would send frames forever; it stops only because its connection was closed
+    let hung_up = tokio::time::timeout(Duration::from_secs(5), closed)
+        .await
+        .expect("the upstream connection should close")
+        .unwrap();
+    assert!(hung_up);
+}
+
+#[actix_web::test]
+async fn both_routes_need_the_api_key_header() {
+    // Nothing listens here: a request that got through would fail with a 500, not a 401
+    let proxy = start_proxy("http://127.0.0.1:9");
+    for route in ["/proxy", "/proxy/stream"] {
+        let response = Client::new()
+            .post(format!("{}{}", proxy, route))
+            .json(&prompt())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED, "{}", route);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"], "Missing API key");
+    }
+}
@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use actix_web::http::StatusCode;
+use actix_web::test::{TestRequest, call_service, init_service, read_body, read_body_json};
+use extender::{Gemini, KeyPool, UsageEntry, UsageLog, create_app, key_hash};
+use reqwest::Client;
+use serde_json::{Value, json};
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const ANSWER: &str = r#"{"candidates": [{"content": {"parts": [{"text": "Héllo!"}]}}]}"#;
+
+async fn gemini() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(ANSWER, "application/json")
+                .set_delay(Duration::from_millis(20)),
+        )
+        .mount(&server)
+        .await;
+    server
+}
+
+fn proxy(server: &MockServer, usage: UsageLog) -> Gemini {
+    let keys = KeyPool::new(Vec::new(), Duration::from_secs(60)).with_admin_token("admin-secret");
+    Gemini::new(Client::new())
+        .with_base_url(server.uri())
+        .with_key_pool(keys)
+        .with_usage_log(usage)
+}
+
+fn prompt(route: &str, key: &str) -> TestRequest {
+    TestRequest::post()
+        .uri(route)
+        .insert_header(("X-Gemini-API-Key", key))
+        .set_json(json!({"contents": [{"parts": [{"text": "Grüß"}, {"text": " dich"}]}]}))
+}
+
+fn entry(timestamp: u64, key: &str, status: u16, latency_ms: u64) -> UsageEntry {
+    UsageEntry {
+        timestamp,
+        key_hash: key.to_string(),
+        model: "gemini-2.0-flash".to_string(),
+        prompt_chars: 10,
+        response_chars: 100,
+        status,
+        latency_ms,
+    }
+}
+
+#[actix_web::test]
+async fn every_upstream_call_is_one_entry() {
+    let server = gemini().await;
+    let gemini = proxy(&server, UsageLog::default());
+    let app = init_service(create_app(gemini.clone())).await;
+
+    let response = call_service(&app, prompt("/proxy", "team-a-key").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = call_service(
+        &app,
+        prompt("/proxy/gemini-1.5-pro/stream", "team-b-key").to_request(),
+    )
+    .await;
+    // The stream's entry is complete once its body has been read
+    read_body(response).await;
+
+    let entries = gemini.usage_log().entries();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].key_hash, key_hash("team-a-key"));
+    assert_eq!(entries[0].model, "gemini-2.0-flash");
+    assert_eq!(entries[1].key_hash, key_hash("team-b-key"));
+    assert_eq!(entries[1].model, "gemini-1.5-pro");
+    for entry in &entries {
+        // "Grüß" + " dich", counted in characters, not bytes
+        assert_eq!(entry.prompt_chars, 9);
+        assert_eq!(entry.response_chars, ANSWER.chars().count());
+        assert_eq!(entry.status, 200);
+        assert!(entry.latency_ms >= 20, "{:?}", entry);
+    }
+    // Only the hash is kept, never the key
+    assert_eq!(entries[0].key_hash.len(), 12);
+    assert!(!format!("{:?}", entries).contains("team-a-key"));
+}
+
+#[actix_web::test]
+async fn the_summary_aggregates_the_last_day_per_key() {
+    let log = UsageLog::new(10);
+    let now = 1_800_000_000;
+    log.record(entry(now - 10, "aaaa", 200, 100));
+    log.record(entry(now - 20, "aaaa", 429, 300));
+    log.record(entry(now - 30, "bbbb", 200, 50));
+    // Older than 24 hours
+    log.record(entry(now - 25 * 60 * 60, "aaaa", 200, 9999));
+
+    assert_eq!(
+        log.summary(now),
+        json!({
+            "since": now - 24 * 60 * 60,
+            "keys": [
+                {"key_hash": "aaaa", "requests": 2, "errors": 1, "prompt_chars": 20, "response_chars": 200, "average_latency_ms": 200},
+                {"key_hash": "bbbb", "requests": 1, "errors": 0, "prompt_chars": 10, "response_chars": 100, "average_latency_ms": 50},
+            ],
+        })
+    );
+}
+
+#[actix_web::test]
+async fn the_summary_route_is_for_admins() {
+    let server = gemini().await;
+    let app = init_service(create_app(proxy(&server, UsageLog::default()))).await;
+    for _ in 0..2 {
+        call_service(&app, prompt("/proxy", "team-a-key").to_request()).await;
+    }
+
+    assert_eq!(
+        call_service(&app, TestRequest::get().uri("/usage/summary").to_request())
+            .await
+            .status(),
+        StatusCode::UNAUTHORIZED
+    );
+    let request = TestRequest::get()
+        .uri("/usage/summary")
+        .insert_header(("Authorization", "Bearer admin-secret"));
+    let response = call_service(&app, request.to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let summary: Value = read_body_json(response).await;
+    assert_eq!(summary["keys"].as_array().unwrap().len(), 1);
+    assert_eq!(summary["keys"][0]["key_hash"], key_hash("team-a-key"));
+    assert_eq!(summary["keys"][0]["requests"], 2);
+}
+
+#[test]
+fn the_ring_buffer_keeps_the_newest_entries() {
+    let log = UsageLog::new(2);
+    for timestamp in 1..=3 {
+        log.record(entry(timestamp, "aaaa", 200, 1));
+    }
+    let timestamps: Vec<u64> = log.entries().iter().map(|entry| entry.timestamp).collect();
+    assert_eq!(timestamps, [2, 3]);
+}
+
+#[test]
+fn usage_log_appends_json_lines() {
+    let path = std::env::temp_dir().join(format!("extender-usage-{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let log = UsageLog::default()
+        .with_file(path.to_str().unwrap())
+        .unwrap();
+    log.record(entry(1, "aaaa", 200, 1));
+    log.record(entry(2, "bbbb", 500, 2));
+
+    let lines: Vec<UsageEntry> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(lines, [entry(1, "aaaa", 200, 1), entry(2, "bbbb", 500, 2)]);
+}
+
+// Every write to /dev/full fails with "no space left on device"
+#[cfg(target_os = "linux")]
+#[actix_web::test]
+async fn a_failing_usage_log_does_not_fail_the_request() {
+    let server = gemini().await;
+    let gemini = proxy(&server, UsageLog::default().with_file("/dev/full").unwrap());
+    let app = init_service(create_app(gemini.clone())).await;
+
+    let response = call_service(&app, prompt("/proxy", "team-a-key").to_request()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(gemini.usage_log().entries().len(), 1);
+}
@@ -1,14 +1,22 @@
 //! Application state
-//! 
+//!
 //! Contains the main App struct that holds all application state.
 
 use macroquad::prelude::*;
 use macroquad::ui::root_ui;
 
+use crate::config::AppConfig;
 use crate::constants::screen_to_world;
-use crate::physics::{PhysicsWorld, BallManager};
-use crate::rendering::SceneRenderer;
-use crate::ui::{ChatPanel, ChatCommand, ControlsPanel, HasBounds, create_custom_skin};
+use crate::narrator::EventNarrator;
+use crate::physics::{BallManager, EventTracker, PhysicsWorld};
+use crate::rendering::{SceneRenderer, BACKGROUND_COLOR};
+use crate::ui::{
+    create_custom_skin, ChatCommand, ChatMessage, ChatPanel, ControlsPanel, HasBounds,
+};
+use crate::weather::{scene_from_weather, WeatherFetcher};
+
+/// Rain stops once the scene holds this many balls, so a long shower can't flood it
+const MAX_BALLS_FOR_RAIN: usize = 300;
 
 /// Main application state
 pub struct App {
@@ -20,25 +28,45 @@ pub struct App {
     pub chat: ChatPanel,
     /// Controls UI panel
     pub controls: ControlsPanel,
+    /// Turns raw physics state into notable events
+    pub events: EventTracker,
+    /// Posts chat messages about notable events
+    pub narrator: EventNarrator,
+    /// Background `/weather` requests
+    pub weather: WeatherFetcher,
+    /// Rain balls spawned per second (0 = dry)
+    pub rain_per_second: f32,
+    /// Fractional balls owed by the rain since the last spawn
+    rain_budget: f32,
+    /// Scene background (tinted by `/weather`)
+    pub background: Color,
 }
 
 impl App {
-    /// Create a new application instance
+    /// Create a new application instance (reads `physics.toml` if present)
     pub fn new() -> Self {
+        Self::with_config(AppConfig::load_or_default())
+    }
+
+    /// Create a new application instance with explicit settings
+    pub fn with_config(config: AppConfig) -> Self {
         let mut physics = PhysicsWorld::new();
         let mut balls = BallManager::new();
-        
+
         // Create initial balls
-        balls.create_initial_balls(
-            &mut physics.rigid_body_set,
-            &mut physics.collider_set,
-        );
+        balls.create_initial_balls(&mut physics.rigid_body_set, &mut physics.collider_set);
 
         Self {
+            events: EventTracker::new(balls.count()),
             physics,
             balls,
             chat: ChatPanel::new(),
             controls: ControlsPanel::new(),
+            narrator: EventNarrator::new(config.narrator),
+            weather: WeatherFetcher::new(),
+            rain_per_second: 0.0,
+            rain_budget: 0.0,
+            background: BACKGROUND_COLOR,
         }
     }
 
@@ -48,9 +76,29 @@ impl App {
         root_ui().push_skin(&skin);
     }
 
-    /// Step the physics simulation
+    /// Step the physics simulation and let the narrator comment on it
     pub fn update_physics(&mut self) {
-        self.physics.step();
+        self.apply_weather_result();
+        self.spawn_rain(get_frame_time());
+
+        self.events
+            .before_step(&self.balls, &self.physics.rigid_body_set);
+        let collisions = self.physics.step();
+        let events = self.events.after_step(
+            &collisions,
+            &self.balls,
+            &self.physics.rigid_body_set,
+            &self.physics.collider_set,
+            self.physics.ground_collider,
+        );
+
+        for narration in self.narrator.observe(&events, get_time()) {
+            self.chat.add_message(ChatMessage::new(
+                "Narrator",
+                narration.text,
+                narration.color,
+            ));
+        }
     }
 
     /// Render the scene
@@ -58,16 +106,56 @@ impl App {
         SceneRenderer::draw_scene(
             &self.balls.handles,
             &self.physics.rigid_body_set,
+            self.background,
         );
     }
 
+    /// Apply a finished `/weather` request to the scene (or report its error)
+    fn apply_weather_result(&mut self) {
+        match self.weather.poll() {
+            Some(Ok(summary)) => {
+                let scene = scene_from_weather(&summary);
+                self.physics.wind = scene.wind;
+                self.rain_per_second = scene.rain_per_second;
+                self.background = scene.background;
+                self.chat.add_system_message(
+                    format!(
+                        "{} → wind {:.1}, rain {:.1} balls/s",
+                        scene.summary, scene.wind, scene.rain_per_second
+                    ),
+                    SKYBLUE,
+                );
+            }
+            // Failures leave the scene exactly as it was
+            Some(Err(message)) => self.chat.add_system_message(message, RED),
+            None => {}
+        }
+    }
+
+    /// Drop rain balls from the top of the scene at `rain_per_second`
+    fn spawn_rain(&mut self, dt: f32) {
+        if self.rain_per_second <= 0.0 || self.balls.count() >= MAX_BALLS_FOR_RAIN {
+            self.rain_budget = 0.0;
+            return;
+        }
+        self.rain_budget += self.rain_per_second * dt;
+        while self.rain_budget >= 1.0 {
+            self.rain_budget -= 1.0;
+            self.balls.add_ball_at(
+                &mut self.physics.rigid_body_set,
+                &mut self.physics.collider_set,
+                macroquad::rand::gen_range(-7.0, 7.0),
+                8.0,
+                0.0,
+                -1.0,
+            );
+        }
+    }
+
     /// Render UI and handle UI interactions
     pub fn render_ui(&mut self) {
         // Render controls panel (note: render needs &mut self to track window position)
-        let controls_result = self.controls.render(
-            self.balls.count(),
-            self.chat.visible,
-        );
+        let controls_result = self.controls.render(self.balls.count(), self.chat.visible);
 
         if controls_result.add_ball_clicked {
             self.add_random_ball();
@@ -79,9 +167,51 @@ impl App {
 
         // Render chat panel
         let chat_result = self.chat.render(self.balls.count());
-        
-        if chat_result.command == ChatCommand::AddBall {
-            self.add_random_ball();
+
+        match chat_result.command {
+            ChatCommand::AddBall => self.add_random_ball(),
+            ChatCommand::Narrator(Some(enabled)) => {
+                self.narrator.set_enabled(enabled);
+                let state = if enabled { "on" } else { "off" };
+                self.chat
+                    .add_system_message(format!("Narrator turned {}.", state), YELLOW);
+            }
+            ChatCommand::Narrator(None) => {
+                let state = if self.narrator.is_enabled() {
+                    "on"
+                } else {
+                    "off"
+                };
+                self.chat.add_system_message(
+                    format!("Narrator is {}. Use /narrator on|off.", state),
+                    YELLOW,
+                );
+            }
+            ChatCommand::Wind(Some(force)) => {
+                self.physics.wind = force;
+                self.chat
+                    .add_system_message(format!("Wind set to {:.1}.", force), YELLOW);
+            }
+            ChatCommand::Wind(None) => {
+                self.chat.add_system_message(
+                    format!(
+                        "Wind is {:.1}. Use /wind <force>, e.g. /wind -2.5.",
+                        self.physics.wind
+                    ),
+                    YELLOW,
+                );
+            }
+            ChatCommand::Weather(Some(city)) => match self.weather.start(&city) {
+                Ok(()) => self
+                    .chat
+                    .add_system_message(format!("Checking the weather in {}...", city), GRAY),
+                Err(message) => self.chat.add_system_message(message, RED),
+            },
+            ChatCommand::Weather(None) => {
+                self.chat
+                    .add_system_message("Usage: /weather <city>", YELLOW);
+            }
+            _ => {}
         }
     }
 
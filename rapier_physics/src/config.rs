@@ -0,0 +1,185 @@
+//! Application configuration file
+//!
+//! Settings are read from a small TOML-style file (`physics.toml` by default):
+//!
+//! ```text
+//! [narrator]
+//! enabled = true
+//! impact_energy = 50.0      # joules
+//! milestones = [50, 100, 500]
+//! ```
+//!
+//! Only the subset of TOML the app needs is supported: `[section]` headers,
+//! `key = value` lines with booleans, numbers, and number arrays, and `#` comments.
+//! Unknown keys are ignored so older binaries can read newer files.
+
+use std::fmt;
+use std::path::Path;
+
+/// Default location of the config file (relative to the working directory)
+pub const DEFAULT_CONFIG_PATH: &str = "physics.toml";
+
+/// Thresholds and cooldowns for the event narrator
+#[derive(Debug, Clone, PartialEq)]
+pub struct NarratorConfig {
+    /// Whether the narrator posts messages at all (also toggled with `/narrator on|off`)
+    pub enabled: bool,
+    /// Minimum kinetic energy (J) for an impact to be announced
+    pub impact_energy: f32,
+    /// Minimum fall height (m) before a ball coming to rest is announced
+    pub fall_height: f32,
+    /// Ball counts that trigger a milestone message when crossed upwards
+    pub milestones: Vec<usize>,
+    /// Seconds between two impact messages
+    pub impact_cooldown: f64,
+    /// Seconds between two "came to rest" messages
+    pub settle_cooldown: f64,
+    /// Seconds between two milestone messages
+    pub milestone_cooldown: f64,
+}
+
+impl Default for NarratorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            impact_energy: 50.0,
+            fall_height: 4.0,
+            milestones: vec![50, 100, 500],
+            impact_cooldown: 3.0,
+            settle_cooldown: 5.0,
+            milestone_cooldown: 0.0,
+        }
+    }
+}
+
+/// All application settings
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AppConfig {
+    pub narrator: NarratorConfig,
+}
+
+/// Error produced when the config file cannot be read or parsed
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    /// A line could not be understood (1-based line number and explanation)
+    Parse {
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "could not read config: {}", e),
+            ConfigError::Parse { line, message } => write!(f, "config line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl AppConfig {
+    /// Load settings from a file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        Self::parse(&text)
+    }
+
+    /// Load `physics.toml` if present, falling back to defaults (with a warning on errors)
+    pub fn load_or_default() -> Self {
+        if !Path::new(DEFAULT_CONFIG_PATH).exists() {
+            return Self::default();
+        }
+        Self::load(DEFAULT_CONFIG_PATH).unwrap_or_else(|e| {
+            eprintln!("Ignoring {}: {}", DEFAULT_CONFIG_PATH, e);
+            Self::default()
+        })
+    }
+
+    /// Parse settings from the file contents
+    pub fn parse(text: &str) -> Result<Self, ConfigError> {
+        let mut config = Self::default();
+        let mut section = String::new();
+
+        for (index, raw) in text.lines().enumerate() {
+            let line_no = index + 1;
+            // Strip comments and surrounding whitespace
+            let line = raw.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name.trim().to_string();
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(ConfigError::Parse {
+                    line: line_no,
+                    message: format!("expected `key = value`, found `{}`", line),
+                });
+            };
+            let (key, value) = (key.trim(), value.trim());
+            let err = |message: String| ConfigError::Parse {
+                line: line_no,
+                message,
+            };
+
+            if section != "narrator" {
+                continue;
+            }
+            let narrator = &mut config.narrator;
+            match key {
+                "enabled" => narrator.enabled = parse_bool(value).map_err(err)?,
+                "impact_energy" => {
+                    narrator.impact_energy = parse_number(value).map_err(err)? as f32
+                }
+                "fall_height" => narrator.fall_height = parse_number(value).map_err(err)? as f32,
+                "impact_cooldown" => narrator.impact_cooldown = parse_number(value).map_err(err)?,
+                "settle_cooldown" => narrator.settle_cooldown = parse_number(value).map_err(err)?,
+                "milestone_cooldown" => {
+                    narrator.milestone_cooldown = parse_number(value).map_err(err)?
+                }
+                "milestones" => narrator.milestones = parse_count_list(value).map_err(err)?,
+                _ => {}
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        other => Err(format!("expected true or false, found `{}`", other)),
+    }
+}
+
+fn parse_number(value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|n| n.is_finite() && *n >= 0.0)
+        .ok_or_else(|| format!("expected a non-negative number, found `{}`", value))
+}
+
+fn parse_count_list(value: &str) -> Result<Vec<usize>, String> {
+    let inner = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .ok_or_else(|| format!("expected a list like [50, 100], found `{}`", value))?;
+    inner
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            item.parse::<usize>()
+                .map_err(|_| format!("`{}` is not a whole number", item))
+        })
+        .collect()
+}
@@ -3,7 +3,10 @@
 //! This crate provides a physics simulation with an interactive chat interface.
 
 pub mod app;
+pub mod config;
 pub mod constants;
+pub mod narrator;
 pub mod physics;
 pub mod rendering;
-pub mod ui;
\ No newline at end of file
+pub mod ui;
+pub mod weather;
@@ -0,0 +1,191 @@
+//! Event narrator
+//!
+//! A tiny chat "persona" that watches physics events and posts short messages
+//! about the notable ones ("💥 Big impact! Ball #12 hit the ground at 14 m/s").
+//!
+//! The narrator is pure logic: it receives events plus the current time and
+//! returns the messages to post. Keeping time as a parameter (instead of calling
+//! `get_time()` inside) makes the cooldown behaviour easy to unit test.
+
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+
+use crate::config::NarratorConfig;
+use crate::physics::{ImpactTarget, PhysicsEvent};
+
+/// The kinds of events the narrator talks about; each has its own cooldown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NarrationKind {
+    Impact,
+    Settled,
+    Milestone,
+}
+
+/// A message the narrator wants to post to the chat
+#[derive(Debug, Clone, PartialEq)]
+pub struct Narration {
+    pub kind: NarrationKind,
+    pub text: String,
+    pub color: Color,
+}
+
+/// Watches physics events and decides what is worth announcing
+pub struct EventNarrator {
+    config: NarratorConfig,
+    /// When each kind of message was last posted (seconds)
+    last_posted: HashMap<NarrationKind, f64>,
+}
+
+impl EventNarrator {
+    /// Create a narrator with the given thresholds
+    pub fn new(config: NarratorConfig) -> Self {
+        Self {
+            config,
+            last_posted: HashMap::new(),
+        }
+    }
+
+    /// Whether the narrator currently posts messages
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Turn the narrator on or off (`/narrator on|off`)
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.config.enabled = enabled;
+    }
+
+    /// The active configuration
+    pub fn config(&self) -> &NarratorConfig {
+        &self.config
+    }
+
+    /// Look at one frame's events and return the messages to post.
+    ///
+    /// At most one message per kind is produced per call, and a kind stays
+    /// quiet until its cooldown has elapsed.
+    pub fn observe(&mut self, events: &[PhysicsEvent], now: f64) -> Vec<Narration> {
+        if !self.config.enabled {
+            return Vec::new();
+        }
+
+        let mut narrations = Vec::new();
+
+        // The hardest impact above the threshold wins this frame
+        let biggest_impact = events
+            .iter()
+            .filter_map(|event| match event {
+                PhysicsEvent::Impact {
+                    ball,
+                    target,
+                    speed,
+                    energy,
+                } if *energy >= self.config.impact_energy => {
+                    Some((*ball, *target, *speed, *energy))
+                }
+                _ => None,
+            })
+            .max_by(|a, b| a.3.total_cmp(&b.3));
+        if let Some((ball, target, speed, _)) = biggest_impact {
+            let what = match target {
+                ImpactTarget::Ground => "the ground".to_string(),
+                ImpactTarget::Wall => "a wall".to_string(),
+                ImpactTarget::Ball(other) => format!("ball #{}", other + 1),
+            };
+            self.post(
+                &mut narrations,
+                NarrationKind::Impact,
+                format!(
+                    "💥 Big impact! Ball #{} hit {} at {:.0} m/s",
+                    ball + 1,
+                    what,
+                    speed
+                ),
+                ORANGE,
+                now,
+            );
+        }
+
+        // The longest fall above the threshold wins this frame
+        let longest_fall = events
+            .iter()
+            .filter_map(|event| match event {
+                PhysicsEvent::Settled { ball, fall_height }
+                    if *fall_height >= self.config.fall_height =>
+                {
+                    Some((*ball, *fall_height))
+                }
+                _ => None,
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((ball, fall_height)) = longest_fall {
+            self.post(
+                &mut narrations,
+                NarrationKind::Settled,
+                format!(
+                    "😴 Ball #{} came to rest after a {:.1} m fall",
+                    ball + 1,
+                    fall_height
+                ),
+                SKYBLUE,
+                now,
+            );
+        }
+
+        // Announce the largest milestone crossed upwards
+        let milestone = events
+            .iter()
+            .filter_map(|event| match event {
+                PhysicsEvent::BodyCount { previous, current } => self
+                    .config
+                    .milestones
+                    .iter()
+                    .copied()
+                    .filter(|m| previous < m && m <= current)
+                    .max(),
+                _ => None,
+            })
+            .max();
+        if let Some(milestone) = milestone {
+            self.post(
+                &mut narrations,
+                NarrationKind::Milestone,
+                format!("🎉 {} balls in the scene!", milestone),
+                GOLD,
+                now,
+            );
+        }
+
+        narrations
+    }
+
+    /// Push a message unless its kind is still cooling down
+    fn post(
+        &mut self,
+        out: &mut Vec<Narration>,
+        kind: NarrationKind,
+        text: String,
+        color: Color,
+        now: f64,
+    ) {
+        let cooldown = match kind {
+            NarrationKind::Impact => self.config.impact_cooldown,
+            NarrationKind::Settled => self.config.settle_cooldown,
+            NarrationKind::Milestone => self.config.milestone_cooldown,
+        };
+        if let Some(&last) = self.last_posted.get(&kind) {
+            if now - last < cooldown {
+                return;
+            }
+        }
+        self.last_posted.insert(kind, now);
+        out.push(Narration { kind, text, color });
+    }
+}
+
+impl Default for EventNarrator {
+    fn default() -> Self {
+        Self::new(NarratorConfig::default())
+    }
+}
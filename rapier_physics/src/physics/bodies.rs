@@ -1,9 +1,9 @@
 //! Ball body management
-//! 
+//!
 //! Handles creation, removal, and management of ball rigid bodies.
 
-use rapier2d::prelude::*;
 use crate::constants::{BALL_RADIUS, BALL_RESTITUTION};
+use rapier2d::prelude::*;
 
 /// Manages ball rigid bodies in the physics simulation
 pub struct BallManager {
@@ -52,6 +52,8 @@ impl BallManager {
             .build();
         let collider = ColliderBuilder::ball(BALL_RADIUS)
             .restitution(BALL_RESTITUTION)
+            // Report collisions so the narrator can comment on big impacts
+            .active_events(ActiveEvents::COLLISION_EVENTS)
             .build();
         let handle = rigid_body_set.insert(rigid_body);
         collider_set.insert_with_parent(collider, handle, rigid_body_set);
@@ -104,10 +106,15 @@ impl BallManager {
     pub fn count(&self) -> usize {
         self.handles.len()
     }
+
+    /// Get the index of a ball (0-based, in creation order) from its body handle
+    pub fn index_of(&self, handle: RigidBodyHandle) -> Option<usize> {
+        self.handles.iter().position(|&h| h == handle)
+    }
 }
 
 impl Default for BallManager {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
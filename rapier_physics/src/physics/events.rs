@@ -0,0 +1,229 @@
+//! Physics event tracking
+//!
+//! Turns raw Rapier collision events and per-frame body state into a small set of
+//! "notable" events (big impacts, balls settling after a fall, body count changes)
+//! that other modules such as the narrator can react to.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use rapier2d::prelude::*;
+
+use super::BallManager;
+
+/// Something interesting that happened during a physics step
+#[derive(Debug, Clone, PartialEq)]
+pub enum PhysicsEvent {
+    /// A ball hit something. `ball` is the 0-based ball index.
+    Impact {
+        ball: usize,
+        target: ImpactTarget,
+        /// Speed of the ball just before the collision (m/s)
+        speed: f32,
+        /// Kinetic energy of the ball just before the collision (J)
+        energy: f32,
+    },
+    /// A ball fell asleep (came to rest) after falling `fall_height` meters
+    Settled { ball: usize, fall_height: f32 },
+    /// The number of balls changed
+    BodyCount { previous: usize, current: usize },
+}
+
+/// What a ball collided with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImpactTarget {
+    Ground,
+    Wall,
+    Ball(usize),
+}
+
+/// Rapier `EventHandler` that stores collision events until they are drained.
+///
+/// Rapier calls the handler through a shared reference (`&self`), possibly from
+/// several threads, so the buffer lives behind a `Mutex` (interior mutability).
+#[derive(Default)]
+pub struct CollisionRecorder {
+    events: Mutex<Vec<CollisionEvent>>,
+}
+
+impl CollisionRecorder {
+    /// Take all events recorded since the last call
+    pub fn drain(&self) -> Vec<CollisionEvent> {
+        match self.events.lock() {
+            Ok(mut events) => std::mem::take(&mut *events),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+impl EventHandler for CollisionRecorder {
+    fn handle_collision_event(
+        &self,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        event: CollisionEvent,
+        _contact_pair: Option<&ContactPair>,
+    ) {
+        if let Ok(mut events) = self.events.lock() {
+            events.push(event);
+        }
+    }
+
+    fn handle_contact_force_event(
+        &self,
+        _dt: Real,
+        _bodies: &RigidBodySet,
+        _colliders: &ColliderSet,
+        _contact_pair: &ContactPair,
+        _total_force_magnitude: Real,
+    ) {
+    }
+}
+
+/// Per-ball bookkeeping between frames
+#[derive(Debug, Clone, Copy)]
+struct BodyTrack {
+    /// Speed before the most recent step
+    speed: f32,
+    /// Highest point reached since the ball last rested
+    peak_height: f32,
+    was_sleeping: bool,
+}
+
+/// Tracks body state across frames and produces `PhysicsEvent`s
+#[derive(Default)]
+pub struct EventTracker {
+    tracks: HashMap<RigidBodyHandle, BodyTrack>,
+    last_body_count: usize,
+}
+
+impl EventTracker {
+    /// Create a tracker that treats `initial_count` balls as already known
+    pub fn new(initial_count: usize) -> Self {
+        Self {
+            tracks: HashMap::new(),
+            last_body_count: initial_count,
+        }
+    }
+
+    /// Record velocities and heights before the physics step.
+    ///
+    /// Collision events are reported after the solver has already bounced the
+    /// ball, so the pre-step speed is the one that describes the impact.
+    pub fn before_step(&mut self, balls: &BallManager, bodies: &RigidBodySet) {
+        // Forget balls that no longer exist (e.g. after a reset)
+        self.tracks.retain(|handle, _| bodies.contains(*handle));
+
+        for &handle in &balls.handles {
+            if let Some(body) = bodies.get(handle) {
+                let height = body.translation().y;
+                let track = self.tracks.entry(handle).or_insert(BodyTrack {
+                    speed: 0.0,
+                    peak_height: height,
+                    was_sleeping: body.is_sleeping(),
+                });
+                track.speed = body.linvel().norm();
+                track.peak_height = track.peak_height.max(height);
+            }
+        }
+    }
+
+    /// Turn this step's collisions and body state changes into events
+    pub fn after_step(
+        &mut self,
+        collisions: &[CollisionEvent],
+        balls: &BallManager,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        ground: ColliderHandle,
+    ) -> Vec<PhysicsEvent> {
+        let mut events = Vec::new();
+
+        for collision in collisions.iter().filter(|c| c.started()) {
+            if let Some(event) = self.impact_event(collision, balls, bodies, colliders, ground) {
+                events.push(event);
+            }
+        }
+
+        for (index, &handle) in balls.handles.iter().enumerate() {
+            let (Some(body), Some(track)) = (bodies.get(handle), self.tracks.get_mut(&handle))
+            else {
+                continue;
+            };
+            let sleeping = body.is_sleeping();
+            if sleeping && !track.was_sleeping {
+                let fall_height = track.peak_height - body.translation().y;
+                events.push(PhysicsEvent::Settled {
+                    ball: index,
+                    fall_height,
+                });
+            }
+            if sleeping {
+                // The next fall is measured from where the ball rests now
+                track.peak_height = body.translation().y;
+            }
+            track.was_sleeping = sleeping;
+        }
+
+        let current = balls.count();
+        if current != self.last_body_count {
+            events.push(PhysicsEvent::BodyCount {
+                previous: self.last_body_count,
+                current,
+            });
+            self.last_body_count = current;
+        }
+
+        events
+    }
+
+    /// Build an `Impact` for the faster ball involved in a collision
+    fn impact_event(
+        &self,
+        collision: &CollisionEvent,
+        balls: &BallManager,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        ground: ColliderHandle,
+    ) -> Option<PhysicsEvent> {
+        // Map a collider to (ball index, body handle) if it belongs to a ball
+        let ball_of = |collider: ColliderHandle| {
+            let body = colliders.get(collider)?.parent()?;
+            Some((balls.index_of(body)?, body))
+        };
+        let first = (collision.collider1(), ball_of(collision.collider1()));
+        let second = (collision.collider2(), ball_of(collision.collider2()));
+
+        let speed_of =
+            |handle: RigidBodyHandle| self.tracks.get(&handle).map(|t| t.speed).unwrap_or(0.0);
+
+        // Pick the ball that was moving faster; the other collider is the target
+        let ((ball, body), other) = match (first, second) {
+            ((_, Some(a)), (_, Some(b))) => {
+                if speed_of(a.1) >= speed_of(b.1) {
+                    (a, ImpactTarget::Ball(b.0))
+                } else {
+                    (b, ImpactTarget::Ball(a.0))
+                }
+            }
+            ((_, Some(a)), (other, None)) | ((other, None), (_, Some(a))) => {
+                let target = if other == ground {
+                    ImpactTarget::Ground
+                } else {
+                    ImpactTarget::Wall
+                };
+                (a, target)
+            }
+            _ => return None,
+        };
+
+        let speed = speed_of(body);
+        let mass = bodies.get(body).map(|b| b.mass()).unwrap_or(0.0);
+        Some(PhysicsEvent::Impact {
+            ball,
+            target: other,
+            speed,
+            energy: 0.5 * mass * speed * speed,
+        })
+    }
+}
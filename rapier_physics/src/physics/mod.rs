@@ -1,9 +1,11 @@
 //! Physics engine module
-//! 
+//!
 //! Contains all Rapier2D physics-related functionality.
 
-mod world;
 mod bodies;
+mod events;
+mod world;
 
+pub use bodies::BallManager;
+pub use events::{CollisionRecorder, EventTracker, ImpactTarget, PhysicsEvent};
 pub use world::PhysicsWorld;
-pub use bodies::BallManager;
\ No newline at end of file
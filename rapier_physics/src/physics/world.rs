@@ -1,12 +1,16 @@
 //! Physics world management
-//! 
+//!
 //! Contains the PhysicsWorld struct that encapsulates all Rapier2D physics state.
 
 use rapier2d::prelude::*;
 
+use super::events::CollisionRecorder;
+
 /// Encapsulates all physics simulation state
 pub struct PhysicsWorld {
     pub gravity: Vector<Real>,
+    /// Horizontal wind acceleration (m/s², positive = to the right) applied to every ball
+    pub wind: Real,
     pub integration_parameters: IntegrationParameters,
     pub physics_pipeline: PhysicsPipeline,
     pub island_manager: IslandManager,
@@ -17,18 +21,22 @@ pub struct PhysicsWorld {
     pub impulse_joint_set: ImpulseJointSet,
     pub multibody_joint_set: MultibodyJointSet,
     pub ccd_solver: CCDSolver,
+    /// Handle of the ground collider (lets event code tell "hit the ground" from "hit a wall")
+    pub ground_collider: ColliderHandle,
+    /// Collects collision events reported during `step`
+    collision_recorder: CollisionRecorder,
 }
 
 impl PhysicsWorld {
     /// Create a new physics world with default settings
     pub fn new() -> Self {
         let mut collider_set = ColliderSet::new();
-        
+
         // Create the ground (static box at bottom)
         let ground_collider = ColliderBuilder::cuboid(10.0, 0.5)
             .translation(vector![0.0, -5.0])
             .build();
-        collider_set.insert(ground_collider);
+        let ground_collider = collider_set.insert(ground_collider);
 
         // Create walls
         let left_wall = ColliderBuilder::cuboid(0.5, 8.0)
@@ -43,6 +51,7 @@ impl PhysicsWorld {
 
         Self {
             gravity: vector![0.0, -9.81],
+            wind: 0.0,
             integration_parameters: IntegrationParameters::default(),
             physics_pipeline: PhysicsPipeline::new(),
             island_manager: IslandManager::new(),
@@ -53,13 +62,18 @@ impl PhysicsWorld {
             impulse_joint_set: ImpulseJointSet::new(),
             multibody_joint_set: MultibodyJointSet::new(),
             ccd_solver: CCDSolver::new(),
+            ground_collider,
+            collision_recorder: CollisionRecorder::default(),
         }
     }
 
     /// Step the physics simulation forward
-    pub fn step(&mut self) {
+    ///
+    /// Returns the collision events that happened during this step
+    /// (only colliders with `ActiveEvents::COLLISION_EVENTS` report them).
+    pub fn step(&mut self) -> Vec<CollisionEvent> {
         let physics_hooks = ();
-        let event_handler = ();
+        self.apply_wind();
 
         self.physics_pipeline.step(
             &self.gravity,
@@ -74,8 +88,29 @@ impl PhysicsWorld {
             &mut self.ccd_solver,
             None,
             &physics_hooks,
-            &event_handler,
+            &self.collision_recorder,
         );
+
+        self.collision_recorder.drain()
+    }
+
+    /// Push every dynamic body sideways with the current wind.
+    ///
+    /// Rapier keeps user forces until they are reset, so the force is replaced each
+    /// step; scaling by mass makes light and heavy balls drift at the same rate.
+    fn apply_wind(&mut self) {
+        let wind = self.wind;
+        for (_, body) in self.rigid_body_set.iter_mut() {
+            if !body.is_dynamic() {
+                continue;
+            }
+            body.reset_forces(false);
+            if wind != 0.0 {
+                let force = vector![wind * body.mass(), 0.0];
+                // Wake sleeping balls: wind should move them even if they came to rest
+                body.add_force(force, true);
+            }
+        }
     }
 }
 
@@ -83,4 +118,4 @@ impl Default for PhysicsWorld {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
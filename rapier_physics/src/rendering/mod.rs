@@ -1,7 +1,7 @@
 //! Rendering module
-//! 
+//!
 //! Contains all graphics rendering functionality for the physics simulation.
 
 mod scene;
 
-pub use scene::SceneRenderer;
\ No newline at end of file
+pub use scene::{SceneRenderer, BACKGROUND_COLOR};
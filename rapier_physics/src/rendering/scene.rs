@@ -1,11 +1,11 @@
 //! Scene rendering
-//! 
+//!
 //! Handles rendering of the physics scene including background, walls, and balls.
 
 use macroquad::prelude::*;
 use rapier2d::prelude::*;
 
-use crate::constants::{world_to_screen, SCALE, BALL_COLORS, BALL_RADIUS};
+use crate::constants::{world_to_screen, BALL_COLORS, BALL_RADIUS, SCALE};
 
 /// Background color for the scene
 pub const BACKGROUND_COLOR: Color = Color::new(0.2, 0.2, 0.25, 1.0);
@@ -15,8 +15,8 @@ pub struct SceneRenderer;
 
 impl SceneRenderer {
     /// Clear the background
-    pub fn clear_background() {
-        clear_background(BACKGROUND_COLOR);
+    pub fn clear_background(color: Color) {
+        clear_background(color);
     }
 
     /// Draw the ground platform
@@ -55,32 +55,30 @@ impl SceneRenderer {
     }
 
     /// Draw all balls in the simulation
-    pub fn draw_balls(
-        ball_handles: &[RigidBodyHandle],
-        rigid_body_set: &RigidBodySet,
-    ) {
+    pub fn draw_balls(ball_handles: &[RigidBodyHandle], rigid_body_set: &RigidBodySet) {
         for (i, &handle) in ball_handles.iter().enumerate() {
             if let Some(ball_body) = rigid_body_set.get(handle) {
                 let pos = world_to_screen(ball_body.translation());
                 let color = BALL_COLORS[i % BALL_COLORS.len()];
-                
+
                 // Draw main ball
                 draw_circle(pos.x, pos.y, BALL_RADIUS * SCALE, color);
-                
+
                 // Add a highlight for 3D effect
                 draw_circle(pos.x - 5.0, pos.y - 5.0, 0.15 * SCALE, WHITE);
             }
         }
     }
 
-    /// Draw the entire scene
+    /// Draw the entire scene on a `background` color (see `BACKGROUND_COLOR`)
     pub fn draw_scene(
         ball_handles: &[RigidBodyHandle],
         rigid_body_set: &RigidBodySet,
+        background: Color,
     ) {
-        Self::clear_background();
+        Self::clear_background(background);
         Self::draw_ground();
         Self::draw_walls();
         Self::draw_balls(ball_handles, rigid_body_set);
     }
-}
\ No newline at end of file
+}
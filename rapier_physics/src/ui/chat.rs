@@ -2,9 +2,9 @@
 //!
 //! Provides a chat interface with message display and command handling.
 
+use super::{Bounds, HasBounds};
 use macroquad::prelude::*;
 use macroquad::ui::{hash, root_ui, widgets};
-use super::{Bounds, HasBounds};
 
 /// A single chat message
 #[derive(Clone)]
@@ -35,6 +35,12 @@ pub enum ChatCommand {
     Clear,
     Help,
     Count,
+    /// `/narrator on|off` (None = just show the current state)
+    Narrator(Option<bool>),
+    /// `/wind <force>` in m/s² (None = show the current wind)
+    Wind(Option<f32>),
+    /// `/weather <city>` (None = no city given)
+    Weather(Option<String>),
     None,
 }
 
@@ -108,12 +114,30 @@ impl ChatPanel {
     }
 
     /// Parse a command from input text
-    fn parse_command(input: &str) -> ChatCommand {
-        match input.trim().to_lowercase().as_str() {
-            "/ball" | "/add" => ChatCommand::AddBall,
-            "/clear" => ChatCommand::Clear,
-            "/help" => ChatCommand::Help,
-            "/count" => ChatCommand::Count,
+    pub fn parse_command(input: &str) -> ChatCommand {
+        let input = input.trim().to_lowercase();
+        // Split "/narrator on" into the command word and its (optional) argument
+        let mut words = input.split_whitespace();
+        let command = words.next().unwrap_or("");
+        let rest: Vec<&str> = words.collect();
+        let argument = rest.first().copied();
+
+        match (command, argument) {
+            ("/ball" | "/add", None) => ChatCommand::AddBall,
+            ("/clear", None) => ChatCommand::Clear,
+            ("/help", None) => ChatCommand::Help,
+            ("/count", None) => ChatCommand::Count,
+            ("/narrator", None) => ChatCommand::Narrator(None),
+            ("/narrator", Some("on")) => ChatCommand::Narrator(Some(true)),
+            ("/narrator", Some("off")) => ChatCommand::Narrator(Some(false)),
+            ("/wind", None) => ChatCommand::Wind(None),
+            ("/wind", Some(value)) => match value.parse::<f32>() {
+                Ok(force) if force.is_finite() => ChatCommand::Wind(Some(force)),
+                _ => ChatCommand::Wind(None),
+            },
+            // City names can have several words ("new york")
+            ("/weather", None) => ChatCommand::Weather(None),
+            ("/weather", Some(_)) => ChatCommand::Weather(Some(rest.join(" "))),
             _ => ChatCommand::None,
         }
     }
@@ -190,7 +214,7 @@ impl ChatPanel {
                             }
                             ChatCommand::Help => {
                                 self.add_system_message(
-                                    "Commands: /ball, /clear, /help, /count",
+                                    "Commands: /ball, /clear, /help, /count, /narrator on|off, /wind <force>, /weather <city>",
                                     YELLOW,
                                 );
                             }
@@ -200,7 +224,11 @@ impl ChatPanel {
                                     YELLOW,
                                 );
                             }
-                            ChatCommand::None => {}
+                            // Handled by App, which owns the narrator, physics world, and weather fetcher
+                            ChatCommand::Narrator(_)
+                            | ChatCommand::Wind(_)
+                            | ChatCommand::Weather(_)
+                            | ChatCommand::None => {}
                         }
 
                         result.command = command;
@@ -212,7 +240,6 @@ impl ChatPanel {
 
         result
     }
-
 }
 
 /// Implementing HasBounds gives us contains_point() for free via the default implementation.
@@ -242,4 +269,4 @@ impl Default for ChatPanel {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,168 @@
+//! Weather-driven scene
+//!
+//! `/weather <city>` fetches the current weather with the `rust-weather-api-client`
+//! crate and themes the simulation with it:
+//!
+//! - wind speed and direction become a horizontal wind force on every ball
+//! - the chance of rain spawns a gentle "rain" of small balls
+//! - temperature tints the background from icy blue to warm orange
+//!
+//! The mapping (`scene_from_weather`) is a pure function so it can be tested with
+//! fixtures; the network call runs on a background thread (`WeatherFetcher`) so the
+//! game loop never blocks.
+
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use macroquad::prelude::*;
+use rust_weather_api_client::{api_key_from_env, fetch_summary_blocking, WeatherSummary};
+
+use crate::rendering::BACKGROUND_COLOR;
+
+/// Wind acceleration (m/s²) per m/s of real wind speed
+pub const WIND_PER_METER_PER_SECOND: f32 = 0.2;
+/// Strongest wind the scene allows (m/s²), so a storm doesn't pin every ball to a wall
+pub const MAX_WIND: f32 = 6.0;
+/// Rain rate (balls per second) at a 100% chance of rain
+pub const MAX_RAIN_PER_SECOND: f32 = 3.0;
+/// Below this chance of rain nothing falls
+pub const MIN_RAIN_PROBABILITY: f64 = 0.1;
+
+/// Background at or below this temperature is fully "cold"
+const COLD_C: f64 = -10.0;
+/// Background at this temperature is the normal scene color
+const MILD_C: f64 = 15.0;
+/// Background at or above this temperature is fully "hot"
+const HOT_C: f64 = 35.0;
+const COLD_COLOR: Color = Color::new(0.12, 0.2, 0.38, 1.0);
+const HOT_COLOR: Color = Color::new(0.4, 0.22, 0.12, 1.0);
+
+/// Scene parameters derived from the weather
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneWeather {
+    /// Horizontal wind acceleration in m/s² (positive = blowing to the right/east)
+    pub wind: f32,
+    /// How many rain balls to spawn per second
+    pub rain_per_second: f32,
+    /// Background color of the scene
+    pub background: Color,
+    /// One-line description for the chat
+    pub summary: String,
+}
+
+/// Map a weather report to scene parameters
+pub fn scene_from_weather(weather: &WeatherSummary) -> SceneWeather {
+    // Meteorological degrees say where the wind comes *from*; it blows the other way.
+    // East is +x in the scene, so the x component is -sin(direction).
+    let direction = (weather.wind_deg as f32).to_radians();
+    let strength = (weather.wind_speed_ms as f32 * WIND_PER_METER_PER_SECOND).min(MAX_WIND);
+    let wind = -direction.sin() * strength;
+
+    let rain_per_second = if weather.rain_probability >= MIN_RAIN_PROBABILITY {
+        weather.rain_probability.min(1.0) as f32 * MAX_RAIN_PER_SECOND
+    } else {
+        0.0
+    };
+
+    let background = temperature_color(weather.temperature_c);
+
+    let summary = format!(
+        "{}: {}, {:.0}°C, wind {:.1} m/s, {:.0}% chance of rain",
+        weather.city,
+        weather.description,
+        weather.temperature_c,
+        weather.wind_speed_ms,
+        weather.rain_probability * 100.0,
+    );
+
+    SceneWeather {
+        wind,
+        rain_per_second,
+        background,
+        summary,
+    }
+}
+
+/// Blend from cold blue through the normal background to warm orange
+fn temperature_color(celsius: f64) -> Color {
+    if celsius <= MILD_C {
+        let t = ((MILD_C - celsius) / (MILD_C - COLD_C)).clamp(0.0, 1.0) as f32;
+        mix(BACKGROUND_COLOR, COLD_COLOR, t)
+    } else {
+        let t = ((celsius - MILD_C) / (HOT_C - MILD_C)).clamp(0.0, 1.0) as f32;
+        mix(BACKGROUND_COLOR, HOT_COLOR, t)
+    }
+}
+
+/// Linear interpolation between two colors (t = 0 gives `a`, t = 1 gives `b`)
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    Color::new(
+        a.r + (b.r - a.r) * t,
+        a.g + (b.g - a.g) * t,
+        a.b + (b.b - a.b) * t,
+        1.0,
+    )
+}
+
+/// Fetches weather on a background thread and hands the result back to the game loop.
+///
+/// The thread sends its result through a channel (`mpsc` = multi-producer,
+/// single-consumer queue, like a `BlockingQueue` in Java); the game loop checks it
+/// once per frame with `poll`, which never blocks.
+#[derive(Default)]
+pub struct WeatherFetcher {
+    pending: Option<Receiver<Result<WeatherSummary, String>>>,
+}
+
+impl WeatherFetcher {
+    /// Create an idle fetcher
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a request is in flight
+    pub fn is_busy(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Start fetching the weather for `city`.
+    ///
+    /// Fails immediately (without spawning a thread) if no API key is configured
+    /// or another request is still running.
+    pub fn start(&mut self, city: &str) -> Result<(), String> {
+        if self.is_busy() {
+            return Err("Still fetching the previous weather report.".to_string());
+        }
+        let api_key = api_key_from_env().ok_or_else(|| {
+            "OPENWEATHER_API_KEY is not set, so /weather is unavailable.".to_string()
+        })?;
+
+        let (sender, receiver) = mpsc::channel();
+        let city = city.to_string();
+        thread::spawn(move || {
+            let result = fetch_summary_blocking(&city, &api_key)
+                .map_err(|e| format!("Could not get the weather for {}: {}", city, e));
+            // The receiver may be gone if the app closed; nothing to do then
+            let _ = sender.send(result);
+        });
+
+        self.pending = Some(receiver);
+        Ok(())
+    }
+
+    /// The finished result, if the background request is done
+    pub fn poll(&mut self) -> Option<Result<WeatherSummary, String>> {
+        let receiver = self.pending.as_ref()?;
+        match receiver.try_recv() {
+            Ok(result) => {
+                self.pending = None;
+                Some(result)
+            }
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                self.pending = None;
+                Some(Err("The weather request stopped unexpectedly.".to_string()))
+            }
+        }
+    }
+}
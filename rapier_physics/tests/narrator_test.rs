@@ -0,0 +1,166 @@
+//! Narrator threshold and cooldown tests driven by synthetic event streams.
+
+use rapier_physics::config::{AppConfig, NarratorConfig};
+use rapier_physics::narrator::{EventNarrator, NarrationKind};
+use rapier_physics::physics::{ImpactTarget, PhysicsEvent};
+
+fn impact(ball: usize, energy: f32) -> PhysicsEvent {
+    PhysicsEvent::Impact {
+        ball,
+        target: ImpactTarget::Ground,
+        speed: 14.0,
+        energy,
+    }
+}
+
+fn config() -> NarratorConfig {
+    NarratorConfig {
+        enabled: true,
+        impact_energy: 50.0,
+        fall_height: 4.0,
+        milestones: vec![50, 100, 500],
+        impact_cooldown: 3.0,
+        settle_cooldown: 5.0,
+        milestone_cooldown: 0.0,
+    }
+}
+
+#[test]
+fn impacts_below_threshold_are_ignored() {
+    let mut narrator = EventNarrator::new(config());
+    assert!(narrator
+        .observe(&[impact(0, 10.0), impact(1, 49.9)], 0.0)
+        .is_empty());
+}
+
+#[test]
+fn biggest_impact_is_announced_once_per_frame() {
+    let mut narrator = EventNarrator::new(config());
+    let out = narrator.observe(&[impact(0, 60.0), impact(11, 90.0)], 0.0);
+
+    assert_eq!(out.len(), 1);
+    assert_eq!(out[0].kind, NarrationKind::Impact);
+    assert_eq!(
+        out[0].text,
+        "💥 Big impact! Ball #12 hit the ground at 14 m/s"
+    );
+}
+
+#[test]
+fn cooldown_suppresses_repeats_until_it_expires() {
+    let mut narrator = EventNarrator::new(config());
+
+    assert_eq!(narrator.observe(&[impact(0, 80.0)], 10.0).len(), 1);
+    assert!(narrator.observe(&[impact(1, 80.0)], 11.0).is_empty());
+    assert!(narrator.observe(&[impact(2, 80.0)], 12.9).is_empty());
+    assert_eq!(narrator.observe(&[impact(3, 80.0)], 13.0).len(), 1);
+}
+
+#[test]
+fn cooldowns_are_tracked_per_event_kind() {
+    let mut narrator = EventNarrator::new(config());
+    narrator.observe(&[impact(0, 80.0)], 0.0);
+
+    // An impact is cooling down, but a settle event is a different kind
+    let out = narrator.observe(
+        &[
+            impact(1, 80.0),
+            PhysicsEvent::Settled {
+                ball: 4,
+                fall_height: 6.25,
+            },
+        ],
+        1.0,
+    );
+    assert_eq!(out.len(), 1);
+    assert_eq!(out[0].kind, NarrationKind::Settled);
+    assert_eq!(out[0].text, "😴 Ball #5 came to rest after a 6.2 m fall");
+}
+
+#[test]
+fn short_falls_are_not_announced() {
+    let mut narrator = EventNarrator::new(config());
+    let out = narrator.observe(
+        &[PhysicsEvent::Settled {
+            ball: 0,
+            fall_height: 1.0,
+        }],
+        0.0,
+    );
+    assert!(out.is_empty());
+}
+
+#[test]
+fn milestones_fire_when_crossed_upwards() {
+    let mut narrator = EventNarrator::new(config());
+
+    assert!(narrator
+        .observe(
+            &[PhysicsEvent::BodyCount {
+                previous: 48,
+                current: 49
+            }],
+            0.0
+        )
+        .is_empty());
+
+    let out = narrator.observe(
+        &[PhysicsEvent::BodyCount {
+            previous: 49,
+            current: 50,
+        }],
+        0.1,
+    );
+    assert_eq!(out.len(), 1);
+    assert_eq!(out[0].text, "🎉 50 balls in the scene!");
+
+    // Going back down (e.g. a reset) is not a milestone
+    assert!(narrator
+        .observe(
+            &[PhysicsEvent::BodyCount {
+                previous: 50,
+                current: 3
+            }],
+            0.2
+        )
+        .is_empty());
+
+    // Jumping over several milestones announces the largest one
+    let out = narrator.observe(
+        &[PhysicsEvent::BodyCount {
+            previous: 40,
+            current: 120,
+        }],
+        0.3,
+    );
+    assert_eq!(out[0].text, "🎉 100 balls in the scene!");
+}
+
+#[test]
+fn disabled_narrator_stays_quiet() {
+    let mut narrator = EventNarrator::new(config());
+    narrator.set_enabled(false);
+    assert!(narrator.observe(&[impact(0, 500.0)], 0.0).is_empty());
+
+    narrator.set_enabled(true);
+    assert_eq!(narrator.observe(&[impact(0, 500.0)], 0.0).len(), 1);
+}
+
+#[test]
+fn config_file_overrides_thresholds() {
+    let config = AppConfig::parse(
+        "# demo settings\n[narrator]\nenabled = false\nimpact_energy = 120 # joules\nmilestones = [10, 20]\n[other]\nimpact_energy = 1\n",
+    )
+    .unwrap();
+
+    assert!(!config.narrator.enabled);
+    assert_eq!(config.narrator.impact_energy, 120.0);
+    assert_eq!(config.narrator.milestones, vec![10, 20]);
+    // Untouched keys keep their defaults
+    assert_eq!(
+        config.narrator.fall_height,
+        NarratorConfig::default().fall_height
+    );
+
+    assert!(AppConfig::parse("[narrator]\nimpact_energy = lots\n").is_err());
+}
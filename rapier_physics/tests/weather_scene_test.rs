@@ -0,0 +1,78 @@
+//! Mapping from weather reports to scene parameters, across typical conditions.
+
+use rapier_physics::rendering::BACKGROUND_COLOR;
+use rapier_physics::weather::{scene_from_weather, MAX_RAIN_PER_SECOND, MAX_WIND};
+use rust_weather_api_client::WeatherSummary;
+
+fn weather(
+    temperature_c: f64,
+    wind_speed_ms: f64,
+    wind_deg: u32,
+    rain_probability: f64,
+) -> WeatherSummary {
+    WeatherSummary {
+        city: "Testville".to_string(),
+        description: "test conditions".to_string(),
+        temperature_c,
+        wind_speed_ms,
+        wind_deg,
+        rain_probability,
+    }
+}
+
+#[test]
+fn mild_calm_dry_day_leaves_the_scene_alone() {
+    let scene = scene_from_weather(&weather(15.0, 0.0, 0, 0.0));
+    assert_eq!(scene.wind, 0.0);
+    assert_eq!(scene.rain_per_second, 0.0);
+    assert_eq!(scene.background, BACKGROUND_COLOR);
+    assert!(scene
+        .summary
+        .starts_with("Testville: test conditions, 15°C"));
+}
+
+#[test]
+fn hot_day_tints_the_background_warm() {
+    let scene = scene_from_weather(&weather(38.0, 0.0, 0, 0.0));
+    assert!(scene.background.r > BACKGROUND_COLOR.r);
+    assert!(scene.background.b < BACKGROUND_COLOR.b);
+}
+
+#[test]
+fn cold_day_tints_the_background_blue() {
+    let scene = scene_from_weather(&weather(-15.0, 0.0, 0, 0.0));
+    assert!(scene.background.b > BACKGROUND_COLOR.b);
+    assert!(scene.background.r < BACKGROUND_COLOR.r);
+}
+
+#[test]
+fn westerly_wind_blows_to_the_right() {
+    // Wind *from* the west (270°) pushes balls east (+x)
+    let scene = scene_from_weather(&weather(15.0, 10.0, 270, 0.0));
+    assert!((scene.wind - 2.0).abs() < 1e-4, "wind was {}", scene.wind);
+
+    let easterly = scene_from_weather(&weather(15.0, 10.0, 90, 0.0));
+    assert!(
+        (easterly.wind + 2.0).abs() < 1e-4,
+        "wind was {}",
+        easterly.wind
+    );
+}
+
+#[test]
+fn storm_wind_is_capped() {
+    let scene = scene_from_weather(&weather(15.0, 100.0, 270, 0.0));
+    assert!((scene.wind - MAX_WIND).abs() < 1e-4);
+}
+
+#[test]
+fn rain_rate_follows_the_chance_of_rain() {
+    let drizzle = scene_from_weather(&weather(12.0, 2.0, 180, 0.5));
+    assert!((drizzle.rain_per_second - MAX_RAIN_PER_SECOND * 0.5).abs() < 1e-4);
+
+    let downpour = scene_from_weather(&weather(12.0, 2.0, 180, 1.0));
+    assert!((downpour.rain_per_second - MAX_RAIN_PER_SECOND).abs() < 1e-4);
+
+    let barely = scene_from_weather(&weather(12.0, 2.0, 180, 0.05));
+    assert_eq!(barely.rain_per_second, 0.0);
+}
@@ -0,0 +1,360 @@
+// The command line, described with clap's derive macros: each struct field is a flag or an
+// argument, and its doc comment becomes the `--help` text.
+//
+// Subcommands: `ask` (the default), `az`, `repl`, `sessions` and `completions`. The older
+// forms without a subcommand (`rust-cli-echo "prompt"`, `rust-cli-echo --explain az ...`,
+// `--list-sessions`) are rewritten into the subcommand forms before clap sees them.
+
+use std::ffi::OsString;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::error::ErrorKind;
+use clap::{ArgGroup, Args, Parser, Subcommand};
+use clap_complete::Shell;
+use rust_gemini_llm_client::TEMPERATURE_RANGE;
+
+use crate::az::EXPLAIN_INSTRUCTION;
+use crate::az_exec::AzRunner;
+use crate::gemini::GeminiOptions;
+use crate::prompt::{StdinInput, read_capped};
+use crate::session::validate_name;
+
+/// Default cap on piped input: 256 KB
+pub const DEFAULT_MAX_STDIN: usize = 256 * 1024;
+
+/// Ask Gemini from the terminal, run Azure CLI commands, or both.
+#[derive(Debug, Parser)]
+#[command(name = "rust-cli-echo", version)]
+pub struct Cli {
+    #[command(flatten)]
+    pub gemini: GeminiOptions,
+
+    /// Print the answer at once instead of as it arrives
+    #[arg(long, global = true)]
+    pub no_stream: bool,
+
+    /// Print one JSON object instead of text: {"ok", "output", "error", "model", "elapsed_ms"}
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Send a prompt to Gemini (the default: `rust-cli-echo "prompt"` means `ask "prompt"`)
+    Ask(AskArgs),
+    /// Run an Azure CLI command, optionally sending its output to Gemini
+    // The az arguments are passed on as typed, so `az ... --help` must reach az
+    #[command(disable_help_flag = true)]
+    Az(AzArgs),
+    /// Chat with Gemini: each line you type is the next turn of the conversation
+    Repl(ReplArgs),
+    /// List or delete saved conversations
+    Sessions {
+        #[command(subcommand)]
+        action: SessionsAction,
+    },
+    /// Print a shell completion script, or write it to a directory
+    Completions(CompletionsArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct AskArgs {
+    /// The prompt; several words are joined with spaces. Piped input is added below it.
+    pub prompt: Vec<String>,
+
+    /// Continue this saved conversation, and save the new turn to it
+    #[arg(long, value_name = "NAME", value_parser = session_name)]
+    pub session: Option<String>,
+
+    /// Read at most this many bytes of piped input
+    #[arg(long, value_name = "BYTES", default_value_t = DEFAULT_MAX_STDIN)]
+    pub max_stdin: usize,
+
+    /// Don't read stdin, even when it's a pipe
+    #[arg(long)]
+    pub no_stdin: bool,
+
+    #[command(flatten)]
+    pub save: SaveArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct AzArgs {
+    /// After the command, ask Gemini to explain its output
+    #[arg(long, conflicts_with = "pipe_to_gemini")]
+    pub explain: bool,
+
+    /// After the command, send its output to Gemini with this instruction
+    #[arg(long, value_name = "INSTRUCTION", value_parser = non_empty)]
+    pub pipe_to_gemini: Option<String>,
+
+    /// Stop az if it runs longer than this, e.g. when it waits at a login prompt
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub timeout: Option<u64>,
+
+    /// Run az again, up to this many times, after connection or throttling errors
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub retries: u32,
+
+    #[command(flatten)]
+    pub save: SaveArgs,
+
+    /// The az command and its arguments, e.g. `group list --output table`
+    // trailing_var_arg: from the first of these on, everything (flags included) belongs to az
+    #[arg(
+        required = true,
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        value_name = "AZ_ARGS"
+    )]
+    pub args: Vec<String>,
+}
+
+/// Where to keep the answer besides the terminal. With az, "the answer" is Gemini's answer
+/// when there is one, otherwise az's output.
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("destination").args(["out", "copy"]).multiple(true)))]
+pub struct SaveArgs {
+    /// Also write the answer to this file, creating its directory if needed
+    #[arg(long, value_name = "PATH")]
+    pub out: Option<PathBuf>,
+
+    /// Replace the --out file if it already exists
+    #[arg(long, requires = "out")]
+    pub force: bool,
+
+    /// Also copy the answer to the clipboard
+    #[arg(long)]
+    pub copy: bool,
+
+    /// Write or copy only the answer's code blocks
+    #[arg(long, requires = "destination")]
+    pub extract_code: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ReplArgs {
+    /// Continue this saved conversation, and save every turn to it
+    #[arg(long, value_name = "NAME", value_parser = session_name)]
+    pub session: Option<String>,
+}
+
+#[derive(Debug, Subcommand, PartialEq, Eq)]
+pub enum SessionsAction {
+    /// List saved sessions
+    List,
+    /// Delete a saved session
+    Delete {
+        #[arg(value_parser = session_name)]
+        name: String,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct CompletionsArgs {
+    /// The shell to generate completions for
+    pub shell: Shell,
+
+    /// Write the script into this directory instead of printing it
+    #[arg(long, value_name = "DIR")]
+    pub dir: Option<PathBuf>,
+}
+
+impl AskArgs {
+    /// The prompt words joined with spaces, if there were any
+    pub fn prompt(&self) -> Option<String> {
+        if self.prompt.is_empty() {
+            None
+        } else {
+            Some(self.prompt.join(" "))
+        }
+    }
+
+    /// Read the piped input (`None` when stdin is a terminal) to EOF, keeping at most
+    /// `--max-stdin` bytes. Nothing is read with `--no-stdin`.
+    pub fn read_stdin(&self, stdin: Option<impl Read>) -> io::Result<Option<StdinInput>> {
+        match stdin {
+            Some(reader) if !self.no_stdin => read_capped(reader, self.max_stdin).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl AzArgs {
+    /// The instruction for Gemini, if the output should be sent there
+    pub fn gemini_instruction(&self) -> Option<String> {
+        if self.explain {
+            Some(EXPLAIN_INSTRUCTION.to_string())
+        } else {
+            self.pipe_to_gemini.clone()
+        }
+    }
+
+    /// An `AzRunner` with this command's `--timeout` and `--retries`
+    pub fn runner(&self) -> AzRunner {
+        let runner = AzRunner::new().with_retries(self.retries);
+        match self.timeout {
+            Some(secs) => runner.with_timeout(Duration::from_secs(secs)),
+            None => runner,
+        }
+    }
+}
+
+/// Parse the arguments (without the program name), accepting the older forms too.
+///
+/// Errors are clap's: they know how to print themselves with usage, and `--help` and
+/// `--version` arrive as "errors" too. `error.exit()` handles all of them.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Cli, clap::Error> {
+    let args = legacy_to_subcommand(args.into_iter().collect());
+    let program = OsString::from("rust-cli-echo");
+    Cli::try_parse_from(std::iter::once(program).chain(args.into_iter().map(OsString::from)))
+}
+
+// Subcommand names, and flags that mean "show help/version" rather than "ask"
+const SUBCOMMANDS: [&str; 6] = ["ask", "az", "repl", "sessions", "completions", "help"];
+const INFO_FLAGS: [&str; 4] = ["-h", "--help", "-V", "--version"];
+
+// Flags that take a value as the next argument. Needed to find the first positional
+// argument: in `--model gemini-2.0-flash az ...`, `gemini-2.0-flash` isn't it.
+const VALUE_OPTIONS: [&str; 13] = [
+    "-k",
+    "--key",
+    "--model",
+    "--temperature",
+    "--max-tokens",
+    "--system",
+    "--max-stdin",
+    "--pipe-to-gemini",
+    "--session",
+    "--delete-session",
+    "--timeout",
+    "--retries",
+    "--out",
+];
+
+/// Rewrite the forms from before subcommands existed:
+///
+/// - `[OPTIONS] <prompt>`, and no arguments at all, become `ask [OPTIONS] <prompt>`
+/// - `[OPTIONS] az <args>` becomes `az [OPTIONS] <args>`
+/// - `--list-sessions` becomes `sessions list`, `--delete-session NAME` `sessions delete NAME`
+///
+/// Arguments that already name a subcommand, or only ask for help, are left alone. A prompt
+/// that merely starts with a subcommand's name (`help me write a poem`, `sessions are hard`)
+/// is still a prompt: the words only go to that subcommand when they make sense to it.
+pub fn legacy_to_subcommand(mut args: Vec<String>) -> Vec<String> {
+    // Find the first positional argument, skipping flags and their values
+    let mut first = 0;
+    while let Some(arg) = args.get(first) {
+        if arg == "--" || !arg.starts_with('-') {
+            break;
+        }
+        first += if VALUE_OPTIONS.contains(&arg.as_str()) {
+            2
+        } else {
+            1
+        };
+    }
+    let first = first.min(args.len());
+    let leading = &args[..first];
+
+    let delete = leading
+        .iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg.as_str() {
+            "--delete-session" => args.get(i + 1).cloned(),
+            _ => arg.strip_prefix("--delete-session=").map(str::to_string),
+        });
+    if let Some(name) = delete {
+        return vec!["sessions".into(), "delete".into(), name];
+    }
+    if leading.iter().any(|arg| arg == "--list-sessions") {
+        return vec!["sessions".into(), "list".into()];
+    }
+
+    match args.get(first).map(String::as_str) {
+        // az's own options used to go before it: move "az" to the front
+        Some("az") => {
+            args.remove(first);
+            args.insert(0, "az".into());
+        }
+        // Options before a subcommand are fine: they are all global
+        Some(command) if SUBCOMMANDS.contains(&command) && fits_subcommand(command, &args) => {}
+        None if leading.iter().any(|arg| INFO_FLAGS.contains(&arg.as_str())) => {}
+        _ => args.insert(0, "ask".into()),
+    }
+    args
+}
+
+// Whether `args` mean `command` rather than a prompt that starts with its name. They do when
+// clap accepts them, or rejects them for a reason other than words it doesn't know: a bad
+// session name in `sessions delete ../x` should still be reported as one.
+fn fits_subcommand(command: &str, args: &[String]) -> bool {
+    if command == "ask" {
+        return true;
+    }
+    let program = OsString::from("rust-cli-echo");
+    match Cli::try_parse_from(std::iter::once(program).chain(args.iter().map(OsString::from))) {
+        Ok(_) => true,
+        Err(error) => !matches!(
+            error.kind(),
+            ErrorKind::UnknownArgument | ErrorKind::InvalidSubcommand
+        ),
+    }
+}
+
+/// Whether `--json` was given, found without a full parse so that even a usage error can be
+/// reported as JSON. Anything after `--`, and az's own arguments, don't count.
+pub fn json_requested(args: &[String]) -> bool {
+    let args = legacy_to_subcommand(args.to_vec());
+    let az = args.first().is_some_and(|arg| arg == "az");
+    let mut is_value = false;
+    for arg in args.iter().skip(usize::from(az)) {
+        match arg.as_str() {
+            _ if is_value => is_value = false,
+            "--json" => return true,
+            "--" => return false,
+            // The az command starts at the first positional argument
+            arg if az && !arg.starts_with('-') => return false,
+            arg => is_value = VALUE_OPTIONS.contains(&arg),
+        }
+    }
+    false
+}
+
+// clap value parsers: a function from the raw text to the value or an error message, which
+// clap prints after "invalid value '...' for '--flag'"
+
+fn session_name(value: &str) -> Result<String, String> {
+    validate_name(value).map(|()| value.to_string())
+}
+
+pub(crate) fn parse_temperature(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(temperature) if TEMPERATURE_RANGE.contains(&temperature) => Ok(temperature),
+        _ => Err(format!(
+            "expected a number from {:.1} to {:.1}",
+            TEMPERATURE_RANGE.start(),
+            TEMPERATURE_RANGE.end()
+        )),
+    }
+}
+
+pub(crate) fn parse_max_tokens(value: &str) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(tokens) if tokens > 0 => Ok(tokens),
+        _ => Err("expected a whole number above 0".to_string()),
+    }
+}
+
+pub(crate) fn non_empty(value: &str) -> Result<String, String> {
+    if value.trim().is_empty() {
+        Err("the value must not be empty".to_string())
+    } else {
+        Ok(value.to_string())
+    }
+}
@@ -0,0 +1,91 @@
+// Sending an az command's output to Gemini (`--explain` / `--pipe-to-gemini`).
+
+use crate::fence::fence_for;
+
+/// The instruction `--explain` uses
+pub const EXPLAIN_INSTRUCTION: &str = "Explain this Azure CLI output";
+
+/// How much az output goes into a prompt: 32 KB, roughly 8,000 tokens at ~4 bytes per token.
+/// `az ... list` commands can return megabytes of JSON, far more than is useful to send.
+pub const MAX_AZ_OUTPUT: usize = 32 * 1024;
+
+/// What an az command printed, and whether it succeeded.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AzOutput {
+    /// The arguments after `az`
+    pub args: Vec<String>,
+    pub stdout: String,
+    pub stderr: String,
+    pub success: bool,
+    /// `None` if az was stopped by a signal
+    pub exit_code: Option<i32>,
+}
+
+/// Cut `text` to at most `max` bytes. Returns the kept part and whether anything was dropped.
+///
+/// The cut goes after the last complete line that fits, so JSON isn't split mid-value; a
+/// single line longer than `max` is cut at the last whole character instead.
+pub fn truncate_output(text: &str, max: usize) -> (&str, bool) {
+    if text.len() <= max {
+        return (text, false);
+    }
+    // is_char_boundary: slicing a &str inside a multi-byte character would panic
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let end = match text[..end].rfind('\n') {
+        Some(newline) => newline + 1,
+        None => end,
+    };
+    (&text[..end], true)
+}
+
+/// Build the prompt for `instruction` about `output`: the instruction, the command and how it
+/// ended, then stdout and stderr in fenced blocks (either left out when empty).
+///
+/// Together the two streams are cut to `max` bytes. stderr (usually a short error message)
+/// may use up to a quarter of that; stdout gets the rest. Each cut is followed by a note
+/// giving the original size, so the model knows it sees only part of the output.
+pub fn az_prompt(instruction: &str, output: &AzOutput, max: usize) -> String {
+    let status = match (output.success, output.exit_code) {
+        (true, _) => "succeeded".to_string(),
+        (false, Some(code)) => format!("failed with exit code {}", code),
+        (false, None) => "was terminated".to_string(),
+    };
+    let mut prompt = format!(
+        "{}\n\nCommand: az {}\nThe command {}.",
+        instruction,
+        output.args.join(" "),
+        status
+    );
+
+    let (stderr, _) = truncate_output(&output.stderr, max / 4);
+    let stdout_budget = max - stderr.len();
+    for (name, text, budget) in [
+        ("stdout", &output.stdout, stdout_budget),
+        ("stderr", &output.stderr, max / 4),
+    ] {
+        if text.trim().is_empty() {
+            continue;
+        }
+        let (kept, truncated) = truncate_output(text, budget);
+        let fence = fence_for(kept);
+        prompt.push_str(&format!(
+            "\n\n{}:\n{}\n{}\n{}",
+            name,
+            fence,
+            kept.trim_end_matches('\n'),
+            fence
+        ));
+        if truncated {
+            prompt.push_str(&format!(
+                "\n[{} truncated: showing the first {} of {} bytes.]",
+                name,
+                kept.len(),
+                text.len()
+            ));
+        }
+    }
+    prompt
+}
@@ -0,0 +1,153 @@
+// Running az: a deadline (`--timeout`) and retries for transient failures (`--retries`).
+
+use std::ffi::OsString;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+use tokio::process::Command;
+
+use crate::az::AzOutput;
+
+/// Pieces of az's stderr that mark a failure worth retrying: the network dropped, or Azure
+/// asked us to slow down. Matched case-insensitively.
+pub const TRANSIENT_PATTERNS: [&str; 10] = [
+    "connection reset",
+    "connection aborted",
+    "connection refused",
+    "connectionerror",
+    "timed out",
+    "temporarily unavailable",
+    "too many requests",
+    "throttl",
+    "429",
+    "503",
+];
+
+/// Whether a failed command's stderr looks like a transient problem rather than a mistake in
+/// the command. Retrying `az group show --name typo` would just fail again.
+pub fn is_transient(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    TRANSIENT_PATTERNS
+        .iter()
+        .any(|pattern| stderr.contains(pattern))
+}
+
+/// Why az produced no output at all.
+#[derive(Debug)]
+pub enum AzExecError {
+    /// az couldn't be started, e.g. it isn't installed
+    Spawn(io::Error),
+    /// az ran past the deadline and was killed
+    TimedOut(Duration),
+}
+
+impl fmt::Display for AzExecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AzExecError::Spawn(e) => write!(f, "Failed to execute az command: {}", e),
+            AzExecError::TimedOut(limit) => write!(
+                f,
+                "az command timed out after {}s and was stopped",
+                limit.as_secs_f64()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AzExecError {}
+
+/// Runs az commands. Without `with_timeout` a command may run forever, as before.
+#[derive(Debug, Clone)]
+pub struct AzRunner {
+    program: OsString,
+    timeout: Option<Duration>,
+    retries: u32,
+    retry_delay: Duration,
+}
+
+impl Default for AzRunner {
+    fn default() -> Self {
+        AzRunner {
+            program: OsString::from("az"),
+            timeout: None,
+            retries: 0,
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl AzRunner {
+    pub fn new() -> AzRunner {
+        AzRunner::default()
+    }
+
+    /// Run this program instead of `az` from PATH
+    pub fn with_program(mut self, program: impl Into<OsString>) -> AzRunner {
+        self.program = program.into();
+        self
+    }
+
+    /// Kill az if one attempt takes longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> AzRunner {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Try up to `retries` more times after a transient failure (see `is_transient`)
+    pub fn with_retries(mut self, retries: u32) -> AzRunner {
+        self.retries = retries;
+        self
+    }
+
+    /// The wait before the first retry; it doubles for each one after that. Default 1s.
+    pub fn with_retry_delay(mut self, delay: Duration) -> AzRunner {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// Run `az <args>` and capture what it printed.
+    ///
+    /// A failed command is still `Ok`: its output says how it failed. `on_retry(attempt,
+    /// failed_output)` is called before each retry, so the caller can say what's happening.
+    /// A timeout is not retried: a command stuck on a login prompt would only hang again.
+    pub async fn run(
+        &self,
+        args: &[String],
+        mut on_retry: impl FnMut(u32, &AzOutput),
+    ) -> Result<AzOutput, AzExecError> {
+        let mut attempt = 0;
+        loop {
+            let output = self.run_once(args).await?;
+            if output.success || attempt == self.retries || !is_transient(&output.stderr) {
+                return Ok(output);
+            }
+            attempt += 1;
+            on_retry(attempt, &output);
+            tokio::time::sleep(self.retry_delay * 2u32.saturating_pow(attempt - 1)).await;
+        }
+    }
+
+    async fn run_once(&self, args: &[String]) -> Result<AzOutput, AzExecError> {
+        let mut command = Command::new(&self.program);
+        // kill_on_drop: when the timeout drops the output() future, az is killed with it
+        // instead of carrying on in the background
+        command.args(args).kill_on_drop(true);
+
+        let output = match self.timeout {
+            Some(limit) => tokio::time::timeout(limit, command.output())
+                .await
+                .map_err(|_| AzExecError::TimedOut(limit))?,
+            None => command.output().await,
+        }
+        .map_err(AzExecError::Spawn)?;
+
+        Ok(AzOutput {
+            args: args.to_vec(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            success: output.status.success(),
+            exit_code: output.status.code(),
+        })
+    }
+}
@@ -0,0 +1,74 @@
+// Markdown code fences: writing them around text (prompts), and finding them in text
+// (`--extract-code` on an answer).
+
+/// A fenced code block from a Markdown answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    /// The first word after the opening fence, e.g. `rust`
+    pub lang: Option<String>,
+    /// The lines between the fences, each ending in a newline
+    pub code: String,
+}
+
+// A Markdown fence longer than any run of backticks in `text`, so input that itself contains
+// ``` (a diff of a README, say) can't close the block early
+pub(crate) fn fence_for(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+// An opening or closing fence: up to 3 spaces, then 3 or more backticks or tildes.
+// Returns the fence character, its length and what follows it.
+fn parse_fence(line: &str) -> Option<(char, usize, &str)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let line = &line[indent..];
+    let marker = line.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+    let length = line.len() - line.trim_start_matches(marker).len();
+    (length >= 3).then(|| (marker, length, &line[length..]))
+}
+
+/// The fenced code blocks in `markdown`, in order.
+///
+/// Follows the CommonMark rules models write by: a block closes at a fence of the same
+/// character that is at least as long as the opening one, so a block opened with four
+/// backticks can contain three. A block the answer never closes (cut off by the token limit,
+/// say) runs to the end. Indented code and inline `code` are not blocks.
+pub fn code_blocks(markdown: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    // The open block: its fence character and length, and the block so far
+    let mut open: Option<(char, usize, CodeBlock)> = None;
+
+    for line in markdown.lines() {
+        let fence = parse_fence(line);
+        let closes = match (&open, fence) {
+            (Some((marker, length, _)), Some((closing, closing_length, rest))) => {
+                closing == *marker && closing_length >= *length && rest.trim().is_empty()
+            }
+            _ => false,
+        };
+        if closes {
+            blocks.extend(open.take().map(|(_, _, block)| block));
+        } else if let Some((_, _, block)) = &mut open {
+            block.code.push_str(line);
+            block.code.push('\n');
+        } else if let Some((marker, length, info)) = fence
+            // Backtick fences can't have backticks in their info string
+            && !(marker == '`' && info.contains('`'))
+        {
+            let lang = info.split_whitespace().next().map(str::to_string);
+            open = Some((
+                marker,
+                length,
+                CodeBlock {
+                    lang,
+                    code: String::new(),
+                },
+            ));
+        }
+    }
+    blocks.extend(open.map(|(_, _, block)| block));
+    blocks
+}
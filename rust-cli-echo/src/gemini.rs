@@ -0,0 +1,77 @@
+// How to call Gemini: which key and model, and the generation settings from the command line.
+
+use clap::Args;
+use rust_gemini_llm_client::{DEFAULT_MODEL, GeminiClient, GeminiError, GenerationOptions};
+
+use crate::args::{non_empty, parse_max_tokens, parse_temperature};
+
+/// Gemini settings from the command line. Anything not given on the command line falls back
+/// to an environment variable (see `apply_env`), then to the client's defaults.
+///
+/// These are global flags: they work before or after any subcommand.
+#[derive(Debug, Clone, Default, PartialEq, Args)]
+pub struct GeminiOptions {
+    /// Gemini API key [default: $GEMINI_API_KEY]
+    #[arg(short = 'k', long = "key", value_name = "API_KEY", global = true)]
+    pub api_key: Option<String>,
+    /// Model to use, e.g. gemini-2.0-flash [default: $GEMINI_MODEL, then gemini-flash-latest]
+    #[arg(long, value_name = "NAME", global = true, value_parser = non_empty)]
+    pub model: Option<String>,
+    /// Randomness, from 0.0 (most predictable) to 2.0
+    // allow_negative_numbers: "-0.5" is a (wrong) value, not an unknown flag
+    #[arg(
+        long,
+        value_name = "T",
+        global = true,
+        allow_negative_numbers = true,
+        value_parser = parse_temperature
+    )]
+    pub temperature: Option<f32>,
+    /// Longest answer, in tokens
+    #[arg(long, value_name = "N", global = true, value_parser = parse_max_tokens)]
+    pub max_tokens: Option<u32>,
+    /// Instructions sent with every prompt [default: $GEMINI_SYSTEM_PROMPT]
+    #[arg(long, value_name = "PROMPT", global = true, value_parser = non_empty)]
+    pub system: Option<String>,
+}
+
+impl GeminiOptions {
+    /// Fill in what the command line left out from the environment: `GEMINI_MODEL` and
+    /// `GEMINI_SYSTEM_PROMPT`. A flag always wins over its variable.
+    ///
+    /// `var` looks a variable up (`|name| std::env::var(name).ok()` in the program), so
+    /// tests can pass their own values instead of changing the real environment.
+    pub fn apply_env(&mut self, var: impl Fn(&str) -> Option<String>) {
+        // Empty variables count as unset
+        let var = |name: &str| var(name).filter(|value| !value.trim().is_empty());
+        if self.model.is_none() {
+            self.model = var("GEMINI_MODEL");
+        }
+        if self.system.is_none() {
+            self.system = var("GEMINI_SYSTEM_PROMPT");
+        }
+    }
+
+    /// The model the client will use
+    pub fn model_name(&self) -> &str {
+        self.model.as_deref().unwrap_or(DEFAULT_MODEL)
+    }
+
+    /// A client with these settings. Without a key, `GEMINI_API_KEY` is used.
+    pub fn client(&self) -> Result<GeminiClient, GeminiError> {
+        let mut client = match &self.api_key {
+            Some(key) => GeminiClient::new(key.as_str()),
+            None => GeminiClient::from_env()?,
+        };
+        if let Some(model) = &self.model {
+            client = client.with_model(model);
+        }
+        if let Some(system) = &self.system {
+            client = client.with_system_instruction(system);
+        }
+        Ok(client.with_generation_options(GenerationOptions {
+            temperature: self.temperature,
+            max_output_tokens: self.max_tokens,
+        }))
+    }
+}
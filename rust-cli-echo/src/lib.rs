@@ -0,0 +1,29 @@
+//! The testable half of `rust-cli-echo`: argument parsing, prompt assembly (from piped input
+//! or az output), saved sessions, printing and saving
+//! the answer, and exit codes.
+//! `main.rs` does the I/O (stdin, `az`, Gemini) on top of it.
+
+pub mod args;
+pub mod az;
+pub mod az_exec;
+pub mod fence;
+pub mod gemini;
+pub mod outcome;
+pub mod prompt;
+pub mod save;
+pub mod session;
+pub mod stream;
+
+pub use args::{
+    AskArgs, AzArgs, Cli, Command, CompletionsArgs, DEFAULT_MAX_STDIN, ReplArgs, SaveArgs,
+    SessionsAction, json_requested, legacy_to_subcommand, parse_args,
+};
+pub use az::{AzOutput, EXPLAIN_INSTRUCTION, MAX_AZ_OUTPUT, az_prompt, truncate_output};
+pub use az_exec::{AzExecError, AzRunner, TRANSIENT_PATTERNS, is_transient};
+pub use fence::{CodeBlock, code_blocks};
+pub use gemini::GeminiOptions;
+pub use outcome::{Exit, Failure, JsonReport};
+pub use prompt::{StdinInput, assemble_prompt, read_capped};
+pub use save::{SaveError, copy_to_clipboard, extract_code, save_answer, write_file};
+pub use session::{LoadedSession, MAX_SESSION_MESSAGES, SessionStore, cap_history};
+pub use stream::{PrintError, Spinner, collect_stream, print_stream};
@@ -1,103 +1,469 @@
+use clap::CommandFactory;
+use dotenv::dotenv;
+use futures_util::StreamExt;
+use rust_cli_echo::session::default_dir;
+use rust_cli_echo::{
+    AskArgs, AzArgs, AzExecError, AzOutput, Cli, Command as CliCommand, CompletionsArgs, Exit,
+    Failure, GeminiOptions, JsonReport, MAX_AZ_OUTPUT, PrintError, SaveArgs, SessionStore,
+    SessionsAction, Spinner, assemble_prompt, az_prompt, collect_stream, json_requested,
+    parse_args, print_stream, save_answer,
+};
+use rust_gemini_llm_client::{ChatMessage, GeminiClient, Prompt};
 use std::env;
-use tokio::process::Command;
-use rust_gemini_llm_client::generate_content;
-
-/// Parse arguments: if starts with "az", treat as az command; else, handle as gemini prompt.
-fn parse_args() -> Result<(Option<String>, Option<String>, Option<Vec<String>>), String> {
-    let mut args: Vec<String> = env::args().skip(1).collect();
-
-    if args.first().map(|s| s == "az").unwrap_or(false) {
-        // AZ command mode: remove "az", rest are az args
-        args.remove(0);
-        return Ok((None, None, Some(args)));
-    }
-
-    // Gemini mode: parse API key and prompt
-    let mut api_key: Option<String> = None;
-    let mut i = 0;
-    while i < args.len() {
-        match args[i].as_str() {
-            "-k" | "--key" => {
-                if i + 1 < args.len() {
-                    api_key = Some(args.remove(i + 1));
-                    args.remove(i);
-                    continue;
-                } else {
-                    return Err(format!("Missing value for {}", args[i]));
-                }
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::pin::Pin;
+use std::process;
+use std::time::Instant;
+
+/// How to show Gemini's answer
+#[derive(Clone, Copy, PartialEq)]
+enum Answer {
+    /// Print it as it arrives (the default)
+    Stream,
+    /// Print it once it is complete (`--no-stream`)
+    Buffered,
+    /// Don't print it; `--json` reports it at the end
+    Quiet,
+}
+
+/// Execute az command and capture its output. Fails only if az couldn't be run at all, or
+/// ran past `--timeout`.
+async fn execute_az_command(az: &AzArgs) -> Result<AzOutput, Failure> {
+    if az.args.is_empty() {
+        return Err(Failure::new(Exit::Usage, "No az command provided"));
+    }
+
+    eprintln!("Executing az command: az {}", az.args.join(" "));
+
+    let retry_note = |attempt: u32, failed: &AzOutput| {
+        eprintln!(
+            "az failed with what looks like a temporary error ({}); retry {} of {}",
+            failed.stderr.lines().last().unwrap_or_default().trim(),
+            attempt,
+            az.retries
+        )
+    };
+    az.runner().run(&az.args, retry_note).await.map_err(|e| {
+        let exit = match e {
+            AzExecError::TimedOut(_) => Exit::Timeout,
+            AzExecError::Spawn(_) => Exit::AzFailed,
+        };
+        Failure::new(exit, e.to_string())
+    })
+}
+
+/// Print what az printed: its stdout on success, the details on failure. The error itself
+/// is reported when the run ends.
+fn print_az_output(output: &AzOutput) {
+    if output.success {
+        println!("{}", output.stdout);
+    } else {
+        eprintln!("AZ Command Stderr: {}", output.stderr);
+        eprintln!("AZ Command Stdout: {}", output.stdout);
+        match output.exit_code {
+            Some(code) => eprintln!("AZ Command Status: exit status: {}", code),
+            None => eprintln!("AZ Command Status: terminated by a signal"),
+        }
+    }
+}
+
+/// Print Gemini's answer piece by piece as it is generated
+async fn stream_answer(client: &GeminiClient, prompt: Prompt<'_>) -> Result<String, Failure> {
+    // peekable() lets us wait for the first event without taking it out of the stream,
+    // so the spinner runs exactly until Gemini starts answering
+    let mut stream = client.generate_content_stream(prompt).peekable();
+    let spinner = Spinner::start("Waiting for Gemini...");
+    let started = matches!(Pin::new(&mut stream).peek().await, Some(Ok(_)));
+    spinner.stop().await;
+    if started {
+        eprintln!("Response:");
+    }
+
+    match print_stream(stream, &mut io::stdout().lock()).await {
+        Ok(answer) => Ok(answer),
+        Err(PrintError::Gemini { error, .. }) => Err(Failure::gemini(&error)),
+        Err(PrintError::Io(e)) => Err(Failure::new(
+            Exit::Gemini,
+            format!("Error writing response: {}", e),
+        )),
+    }
+}
+
+/// Send `prompt` (one message or a whole conversation) to Gemini and show the answer as
+/// `answer` says. Returns the answer.
+async fn ask_gemini(
+    prompt: Prompt<'_>,
+    options: &GeminiOptions,
+    answer: Answer,
+) -> Result<String, Failure> {
+    let client = options.client().map_err(|e| Failure::gemini(&e))?;
+    if answer == Answer::Stream {
+        return stream_answer(&client, prompt).await;
+    }
+    // Wait for the whole answer
+    let text = collect_stream(client.generate_content_stream(prompt))
+        .await
+        .map_err(|e| Failure::gemini(&e))?;
+    if answer == Answer::Buffered {
+        eprintln!("Response:");
+        println!("{}", text);
+    }
+    Ok(text)
+}
+
+/// Where sessions are kept, or exit if there is no home directory to keep them in
+fn session_store() -> SessionStore {
+    match default_dir() {
+        Some(dir) => SessionStore::new(dir),
+        None => {
+            eprintln!("Error: cannot find a home directory to store sessions in (set HOME)");
+            process::exit(1);
+        }
+    }
+}
+
+/// `sessions list` and `sessions delete <name>`
+fn manage_sessions(action: SessionsAction) {
+    let store = session_store();
+    match action {
+        SessionsAction::Delete { name } => match store.delete(&name) {
+            Ok(true) => eprintln!("Deleted session '{}'", name),
+            Ok(false) => {
+                eprintln!("No session named '{}'", name);
+                process::exit(1);
             }
-            s if s.starts_with("--key=") => {
-                let val = s.splitn(2, '=').nth(1).map(|s| s.to_string());
-                api_key = val;
-                args.remove(i);
-                continue;
+            Err(e) => {
+                eprintln!("Error deleting session '{}': {}", name, e);
+                process::exit(1);
             }
-            _ => i += 1,
-        }
+        },
+        SessionsAction::List => match store.list() {
+            Ok(names) if names.is_empty() => {
+                eprintln!("No saved sessions in {}", store.dir().display())
+            }
+            Ok(names) => names.iter().for_each(|name| println!("{}", name)),
+            Err(e) => {
+                eprintln!("Error listing sessions: {}", e);
+                process::exit(1);
+            }
+        },
     }
+}
 
-    let prompt = if !args.is_empty() { Some(args.join(" ")) } else { None };
-    Ok((api_key, prompt, None))
+/// The saved messages of session `name`, warning if a broken file had to be set aside
+fn load_session(store: &SessionStore, name: &str) -> Result<Vec<ChatMessage>, String> {
+    let loaded = store
+        .load(name)
+        .map_err(|e| format!("Error loading session '{}': {}", name, e))?;
+    if let Some(backup) = &loaded.backup {
+        eprintln!(
+            "Warning: session '{}' could not be read; moved it to {} and started a new one",
+            name,
+            backup.display()
+        );
+    }
+    Ok(loaded.messages)
 }
 
-/// Execute az command and return output
-async fn execute_az_command(args: Vec<String>) -> Result<String, String> {
-    if args.is_empty() {
-        return Err("No az command provided".to_string());
+/// Ask `prompt` as the next turn of session `name`, then save both sides of the exchange
+async fn ask_in_session(
+    name: &str,
+    prompt: String,
+    options: &GeminiOptions,
+    answer: Answer,
+) -> Result<String, Failure> {
+    let store = session_store();
+    let mut messages = load_session(&store, name).map_err(|e| Failure::new(Exit::Gemini, e))?;
+    messages.push(ChatMessage::user(prompt));
+    // A failed call saves nothing, so the question can simply be asked again
+    let text = ask_gemini((&messages).into(), options, answer).await?;
+    messages.push(ChatMessage::model(text.clone()));
+    store.save(name, &messages).map_err(|e| {
+        Failure::new(
+            Exit::Gemini,
+            format!("Error saving session '{}': {}", name, e),
+        )
+    })?;
+    Ok(text)
+}
+
+/// `az`: run the command, print its output, and optionally send that output to Gemini.
+/// The result is Gemini's answer when there was an instruction, otherwise az's stdout.
+async fn run_az(az: &AzArgs, options: &GeminiOptions, answer: Answer) -> Result<String, Failure> {
+    let instruction = az.gemini_instruction();
+    let output = execute_az_command(az).await?;
+    // With --json, az's stdout is the report's output instead
+    if answer != Answer::Quiet {
+        print_az_output(&output);
     }
 
-    let mut command = Command::new("az");
-    command.args(&args);
+    // --explain / --pipe-to-gemini: the raw output above, then Gemini's take on it.
+    // A failed command is sent too; explaining an error is often the point.
+    let result = match instruction {
+        Some(instruction) => {
+            if output.stdout.len() + output.stderr.len() > MAX_AZ_OUTPUT {
+                eprintln!(
+                    "Note: az output is longer than {} bytes; only the start is sent to Gemini",
+                    MAX_AZ_OUTPUT
+                );
+            }
+            eprintln!("Sending az output to Gemini: {}", instruction);
+            let prompt = az_prompt(&instruction, &output, MAX_AZ_OUTPUT);
+            ask_gemini((&prompt).into(), options, answer).await?
+        }
+        None => output.stdout.clone(),
+    };
 
-    println!("Executing az command: {:?}", command);
+    if output.success {
+        return Ok(result);
+    }
+    // az's own exit code becomes ours, so scripts see what they'd see running az directly
+    let (exit, status) = match output.exit_code {
+        Some(code) => (Exit::Az(code), format!("exit code {}", code)),
+        None => (Exit::AzFailed, "a signal".to_string()),
+    };
+    Err(Failure::new(
+        exit,
+        format!(
+            "az command failed with {}: {}",
+            status,
+            output.stderr.trim()
+        ),
+    )
+    .with_output(result))
+}
+
+/// `ask` (the default): one prompt from the arguments and/or piped stdin
+async fn run_ask(
+    ask: &AskArgs,
+    options: &GeminiOptions,
+    answer: Answer,
+) -> Result<String, Failure> {
+    // Piped input (stdin not a terminal) is read to EOF, up to --max-stdin bytes
+    let stdin = io::stdin();
+    let piped = if stdin.is_terminal() {
+        None
+    } else {
+        Some(stdin.lock())
+    };
+    let stdin = ask
+        .read_stdin(piped)
+        .map_err(|e| Failure::new(Exit::Gemini, format!("Error reading stdin: {}", e)))?;
+    if let Some(cap) = stdin.as_ref().and_then(|input| input.truncated_at) {
+        eprintln!(
+            "Note: piped input was longer than {} bytes and has been truncated",
+            cap
+        );
+    }
+    // Blank input (e.g. `< /dev/null`) is ignored, as assemble_prompt does
+    let stdin = stdin.filter(|input| !input.text.trim().is_empty());
+    let prompt = ask.prompt();
+
+    let Some(full_prompt) = assemble_prompt(prompt.as_deref(), stdin.as_ref()) else {
+        // Nothing to ask: show what the program can do
+        if answer != Answer::Quiet {
+            eprintln!("{}", Cli::command().render_help());
+        }
+        return Err(Failure::new(
+            Exit::Usage,
+            "No prompt: pass one as an argument or pipe it in",
+        ));
+    };
+    // Piped input can be long, so only its size is echoed
+    match (&prompt, &stdin) {
+        (Some(prompt), Some(input)) => eprintln!(
+            "Sending prompt: {} (+ {} bytes from stdin)",
+            prompt,
+            input.text.len()
+        ),
+        (None, Some(input)) => eprintln!("Sending prompt: {} bytes from stdin", input.text.len()),
+        _ => eprintln!("Sending prompt: {}", full_prompt),
+    }
+    match &ask.session {
+        Some(name) => ask_in_session(name, full_prompt, options, answer).await,
+        None => ask_gemini((&full_prompt).into(), options, answer).await,
+    }
+}
 
-    match command.output().await {
-        Ok(output) => {
-            if output.status.success() {
-                Ok(String::from_utf8_lossy(&output.stdout).to_string())
-            } else {
-                eprintln!("AZ Command Stderr: {}", String::from_utf8_lossy(&output.stderr));
-                eprintln!("AZ Command Stdout: {}", String::from_utf8_lossy(&output.stdout));
-                eprintln!("AZ Command Status: {}", output.status);
+/// `repl`: a conversation, one line per turn, until `exit`, `quit` or end of input (Ctrl-D).
+/// With a session, the conversation continues from it and every turn is saved.
+async fn run_repl(session: Option<String>, options: &GeminiOptions, answer: Answer) {
+    // Report a missing key before the first question is typed, not after it
+    if let Err(e) = options.client() {
+        eprintln!("Error calling Gemini: {}", e);
+        process::exit(1);
+    }
+    let store = session.as_ref().map(|_| session_store());
+    let mut messages = match (&store, &session) {
+        (Some(store), Some(name)) => load_session(store, name).unwrap_or_else(|e| {
+            eprintln!("{}", e);
+            process::exit(1);
+        }),
+        _ => Vec::new(),
+    };
+    if !messages.is_empty() {
+        println!(
+            "Continuing session '{}' ({} messages)",
+            session.as_deref().unwrap_or_default(),
+            messages.len()
+        );
+    }
+    println!("Type a message and press Enter. `exit`, `quit` or Ctrl-D leaves.");
 
-                let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-                Err(format!("az command failed: {}", stderr))
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        print!("> ");
+        io::stdout().flush().ok();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            Some(Err(e)) => {
+                eprintln!("Error reading input: {}", e);
+                process::exit(1);
+            }
+            // End of input: finish the prompt line before leaving
+            None => {
+                println!();
+                break;
             }
+        };
+        let line = line.trim();
+        match line {
+            "" => continue,
+            "exit" | "quit" => break,
+            _ => {}
+        }
+
+        messages.push(ChatMessage::user(line));
+        match ask_gemini((&messages).into(), options, answer).await {
+            Ok(text) => messages.push(ChatMessage::model(text)),
+            // Forget the failed question so the history still alternates user/model
+            Err(failure) => {
+                messages.pop();
+                eprintln!("{}", failure.message);
+                continue;
+            }
+        }
+        if let (Some(store), Some(name)) = (&store, &session)
+            && let Err(e) = store.save(name, &messages)
+        {
+            eprintln!("Error saving session '{}': {}", name, e);
         }
-        Err(e) => Err(format!("Failed to execute az command: {}", e)),
     }
 }
 
+/// `completions <shell>`: print the script, or write it into `--dir`
+fn write_completions(completions: CompletionsArgs) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    match completions.dir {
+        Some(dir) => {
+            match clap_complete::generate_to(completions.shell, &mut command, name, &dir) {
+                Ok(path) => println!("Wrote {}", path.display()),
+                Err(e) => {
+                    eprintln!("Error writing completions to {}: {}", dir.display(), e);
+                    process::exit(1);
+                }
+            }
+        }
+        None => clap_complete::generate(completions.shell, &mut command, name, &mut io::stdout()),
+    }
+}
+
+/// `--out`, `--copy` and `--extract-code`, once the whole answer is in. A failed run saves
+/// nothing.
+fn save_result(result: Result<String, Failure>, save: &SaveArgs) -> Result<String, Failure> {
+    let text = result?;
+    match save_answer(&text, save) {
+        Ok(done) => {
+            done.iter().for_each(|line| eprintln!("{}", line));
+            Ok(text)
+        }
+        Err(e) => Err(Failure::new(Exit::Gemini, e.to_string()).with_output(text)),
+    }
+}
+
+/// End the run: print the `--json` report, or the error on stderr, and exit with its code
+fn finish(
+    result: Result<String, Failure>,
+    json: bool,
+    model: Option<String>,
+    started: Instant,
+) -> ! {
+    let exit = match &result {
+        Ok(_) => Exit::Success,
+        Err(failure) => failure.exit,
+    };
+    if json {
+        let report = JsonReport::new(&result, model, started.elapsed().as_millis());
+        println!(
+            "{}",
+            serde_json::to_string(&report).expect("a report always serializes")
+        );
+    } else if let Err(failure) = &result {
+        eprintln!("{}", failure.message);
+    }
+    process::exit(exit.code());
+}
+
 #[tokio::main]
 async fn main() {
-    // 1. Handle the Result from parse_args()
-    let args_result = parse_args();
+    let started = Instant::now();
+    // Load .env first, so GEMINI_MODEL and GEMINI_SYSTEM_PROMPT can come from it too
+    dotenv().ok();
 
-    // 2. Match on the Result to handle success and error cases
-    match args_result {
-        Ok((_, Some(_), Some(_))) => {
-            eprintln!("Error: Invalid state - both prompt and az command detected.");
+    // 1. Parse the arguments. Settings not given as flags come from env vars.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let json = json_requested(&args);
+    let Cli {
+        mut gemini,
+        no_stream,
+        json,
+        command,
+    } = match parse_args(args) {
+        Ok(cli) => cli,
+        // --help and --version arrive as errors too, but go to stdout and exit 0
+        Err(e) if !e.use_stderr() => e.exit(),
+        Err(e) if json => {
+            // clap's message is "error: ...", a blank line, then usage and hints: keep
+            // the first paragraph, on one line
+            let rendered = e.render().to_string();
+            let paragraph = rendered.split("\n\n").next().unwrap_or_default();
+            let message = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+            let message = message.strip_prefix("error: ").unwrap_or(&message);
+            finish(Err(Failure::new(Exit::Usage, message)), true, None, started)
         }
-        Ok((api_key, Some(prompt), None)) => {
-            // Gemini mode
-            println!("Sending prompt: {}", prompt);
-            match generate_content(&prompt, api_key).await {
-                Ok(resp) => println!("Response:\n{}", resp),
-                Err(e) => eprintln!("Error calling Gemini: {}", e),
-            }
+        Err(e) => {
+            e.print().ok();
+            process::exit(Exit::Usage.code());
         }
-        Ok((_, None, Some(az_args))) => {
-            // Azure CLI mode
-            match execute_az_command(az_args).await {
-                Ok(output) => println!("{}", output),
-                Err(e) => eprintln!("Error executing az command: {}", e),
-            }
+    };
+    gemini.apply_env(|name| env::var(name).ok());
+    let answer = match (json, no_stream) {
+        (true, _) => Answer::Quiet,
+        (false, true) => Answer::Buffered,
+        (false, false) => Answer::Stream,
+    };
+
+    // 2. Run the subcommand. ask and az end in finish(), with an exit code per outcome.
+    match command {
+        CliCommand::Ask(ask) => {
+            let result = save_result(run_ask(&ask, &gemini, answer).await, &ask.save);
+            finish(result, json, Some(gemini.model_name().to_string()), started)
         }
-        Ok((_, None, None)) => {
-            // No prompt or az command, print usage
-            let exe = env::args().next().unwrap_or_else(|| "rust-cli-echo".into());
-            eprintln!("Usage: {} [az <command> | [-k API_KEY] <prompt>]", exe);
+        CliCommand::Az(az) => {
+            // The model only matters when Gemini gets the output
+            let model = az
+                .gemini_instruction()
+                .map(|_| gemini.model_name().to_string());
+            let result = save_result(run_az(&az, &gemini, answer).await, &az.save);
+            finish(result, json, model, started)
         }
-        Err(e) => eprintln!("Argument parsing error: {}", e),
+        _ if json => {
+            let failure = Failure::new(Exit::Usage, "--json works with ask and az only");
+            finish(Err(failure), true, None, started)
+        }
+        CliCommand::Repl(repl) => run_repl(repl.session, &gemini, answer).await,
+        CliCommand::Sessions { action } => manage_sessions(action),
+        CliCommand::Completions(completions) => write_completions(completions),
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,103 @@
+// How a run ends: the exit code scripts can branch on, and the `--json` report.
+
+use rust_gemini_llm_client::GeminiError;
+use serde::Serialize;
+
+/// The exit codes. Each failure has its own, so a script can tell "fix the command line"
+/// from "az failed" from "try again later".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// 0: everything worked
+    Success,
+    /// 1: Gemini failed (missing key, HTTP error, blocked answer, ...) or another runtime error
+    Gemini,
+    /// 2: the command line was wrong, or there was nothing to do
+    Usage,
+    /// 3: az couldn't be started, or was stopped by a signal
+    AzFailed,
+    /// 4: a request to Gemini, or the az command, timed out
+    Timeout,
+    /// The az command failed with this exit code, which the CLI exits with too
+    Az(i32),
+}
+
+impl Exit {
+    pub fn code(self) -> i32 {
+        match self {
+            Exit::Success => 0,
+            Exit::Gemini => 1,
+            Exit::Usage => 2,
+            Exit::AzFailed => 3,
+            Exit::Timeout => 4,
+            Exit::Az(code) => code,
+        }
+    }
+}
+
+/// Why a run failed: the exit code and the message for the user (or the JSON `error`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Failure {
+    pub exit: Exit,
+    pub message: String,
+    /// Whatever was produced before the failure, e.g. az's stdout when the command failed
+    pub output: Option<String>,
+}
+
+impl Failure {
+    pub fn new(exit: Exit, message: impl Into<String>) -> Failure {
+        Failure {
+            exit,
+            message: message.into(),
+            output: None,
+        }
+    }
+
+    pub fn with_output(mut self, output: impl Into<String>) -> Failure {
+        self.output = Some(output.into());
+        self
+    }
+
+    /// A failed Gemini call: timeouts get their own code, everything else is `Exit::Gemini`.
+    pub fn gemini(error: &GeminiError) -> Failure {
+        let exit = match error {
+            GeminiError::Timeout => Exit::Timeout,
+            // reqwest's own timeouts (e.g. while connecting) arrive as network errors
+            GeminiError::Network(e) if e.is_timeout() => Exit::Timeout,
+            _ => Exit::Gemini,
+        };
+        Failure::new(exit, format!("Error calling Gemini: {}", error))
+    }
+}
+
+/// What `--json` prints on stdout, always with all five keys:
+/// `{"ok":true,"output":"...","error":null,"model":"gemini-flash-latest","elapsed_ms":812}`
+#[derive(Debug, Serialize)]
+pub struct JsonReport {
+    pub ok: bool,
+    pub output: Option<String>,
+    pub error: Option<String>,
+    /// The Gemini model, or null when Gemini wasn't involved (a plain az command)
+    pub model: Option<String>,
+    pub elapsed_ms: u128,
+}
+
+impl JsonReport {
+    pub fn new(result: &Result<String, Failure>, model: Option<String>, elapsed_ms: u128) -> Self {
+        match result {
+            Ok(output) => JsonReport {
+                ok: true,
+                output: Some(output.clone()),
+                error: None,
+                model,
+                elapsed_ms,
+            },
+            Err(failure) => JsonReport {
+                ok: false,
+                output: failure.output.clone(),
+                error: Some(failure.message.clone()),
+                model,
+                elapsed_ms,
+            },
+        }
+    }
+}
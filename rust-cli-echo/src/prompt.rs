@@ -0,0 +1,66 @@
+use std::io::{self, Read};
+
+use crate::fence::fence_for;
+
+/// Text piped in on stdin, cut to the size cap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StdinInput {
+    pub text: String,
+    /// Set when the input was longer than the cap; `text` holds the first `cap` bytes
+    pub truncated_at: Option<usize>,
+}
+
+/// Read `reader` to EOF, keeping at most `cap` bytes.
+///
+/// Invalid UTF-8 is replaced rather than rejected (piped input may be binary), and a cut that
+/// lands inside a multi-byte character drops that partial character.
+pub fn read_capped(reader: impl Read, cap: usize) -> io::Result<StdinInput> {
+    let mut bytes = Vec::new();
+    // One byte past the cap is enough to know the input was longer
+    reader.take(cap as u64 + 1).read_to_end(&mut bytes)?;
+    let truncated_at = if bytes.len() > cap {
+        bytes.truncate(cap);
+        Some(cap)
+    } else {
+        None
+    };
+    let mut text = String::from_utf8_lossy(&bytes).into_owned();
+    // A character split by the cut decodes as U+FFFD at the very end
+    if truncated_at.is_some() && text.ends_with('\u{FFFD}') {
+        text.pop();
+    }
+    Ok(StdinInput { text, truncated_at })
+}
+
+/// Build the prompt from the positional arguments and piped input.
+///
+/// - arguments and input: the arguments, then the input in a fenced code block
+/// - only input: the input is the whole prompt
+/// - only arguments: the arguments
+/// - neither: `None`
+///
+/// Truncated input is followed by a note saying so, so the model knows it's incomplete.
+pub fn assemble_prompt(args_prompt: Option<&str>, stdin: Option<&StdinInput>) -> Option<String> {
+    // Empty or whitespace-only input (e.g. `true | rust-cli-echo ...`) counts as none
+    let stdin = stdin.filter(|input| !input.text.trim().is_empty());
+    let notice = stdin
+        .and_then(|input| input.truncated_at)
+        .map(|cap| format!("\n\n[Input truncated to the first {} bytes.]", cap))
+        .unwrap_or_default();
+    match (args_prompt, stdin) {
+        (Some(prompt), Some(input)) => {
+            let fence = fence_for(&input.text);
+            Some(format!(
+                "{}\n\n{}\n{}\n{}{}",
+                prompt,
+                fence,
+                input.text.trim_end_matches('\n'),
+                fence,
+                notice
+            ))
+        }
+        (None, Some(input)) => Some(format!("{}{}", input.text, notice)),
+        (Some(prompt), None) => Some(prompt.to_string()),
+        (None, None) => None,
+    }
+}
@@ -0,0 +1,106 @@
+// Keeping the answer: `--out` writes it to a file, `--copy` puts it on the clipboard, and
+// `--extract-code` keeps only its code blocks. All of them work on the complete answer, after
+// it has been streamed to the terminal.
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::args::SaveArgs;
+use crate::fence::code_blocks;
+
+/// Why the answer couldn't be saved.
+#[derive(Debug)]
+pub enum SaveError {
+    /// `--out` names an existing file and `--force` wasn't given
+    Exists(PathBuf),
+    /// `--extract-code`, but the answer has no code blocks
+    NoCode,
+    Io(PathBuf, io::Error),
+    Clipboard(arboard::Error),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Exists(path) => write!(
+                f,
+                "{} already exists; use --force to overwrite it",
+                path.display()
+            ),
+            SaveError::NoCode => write!(f, "--extract-code: the answer has no code blocks"),
+            SaveError::Io(path, e) => write!(f, "Error writing {}: {}", path.display(), e),
+            SaveError::Clipboard(e) => write!(f, "Error copying to the clipboard: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+/// The code blocks of `answer`, one after another with a blank line between them.
+pub fn extract_code(answer: &str) -> Result<String, SaveError> {
+    let blocks = code_blocks(answer);
+    if blocks.is_empty() {
+        return Err(SaveError::NoCode);
+    }
+    let code: Vec<&str> = blocks.iter().map(|block| block.code.as_str()).collect();
+    Ok(code.join("\n"))
+}
+
+/// Write `text` to `path`, creating its parent directories. An existing file is only
+/// replaced when `force` is set.
+pub fn write_file(path: &Path, text: &str, force: bool) -> Result<(), SaveError> {
+    let io_error = |e| SaveError::Io(path.to_path_buf(), e);
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    // create_new checks and creates in one step, so there's no gap in which another process
+    // could create the file between "does it exist?" and writing it
+    let mut options = OpenOptions::new();
+    options.write(true);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    let mut file = options.open(path).map_err(|e| match e.kind() {
+        io::ErrorKind::AlreadyExists => SaveError::Exists(path.to_path_buf()),
+        _ => io_error(e),
+    })?;
+    file.write_all(text.as_bytes()).map_err(io_error)
+}
+
+/// Put `text` on the system clipboard.
+///
+/// On Linux the clipboard is owned by the program that set it; arboard hands the text to
+/// the desktop's clipboard manager when we exit, so it stays available after that.
+pub fn copy_to_clipboard(text: &str) -> Result<(), SaveError> {
+    let mut clipboard = arboard::Clipboard::new().map_err(SaveError::Clipboard)?;
+    clipboard.set_text(text).map_err(SaveError::Clipboard)
+}
+
+/// Do what `save` asks with `answer`. Returns a line for each thing done, for the user.
+pub fn save_answer(answer: &str, save: &SaveArgs) -> Result<Vec<String>, SaveError> {
+    let mut done = Vec::new();
+    if save.out.is_none() && !save.copy {
+        return Ok(done);
+    }
+    let (text, what) = if save.extract_code {
+        (extract_code(answer)?, "code from the answer")
+    } else {
+        (answer.to_string(), "answer")
+    };
+    if let Some(path) = &save.out {
+        write_file(path, &text, save.force)?;
+        done.push(format!("Wrote the {} to {}", what, path.display()));
+    }
+    if save.copy {
+        copy_to_clipboard(&text)?;
+        done.push(format!("Copied the {} to the clipboard", what));
+    }
+    Ok(done)
+}
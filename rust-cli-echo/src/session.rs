@@ -0,0 +1,222 @@
+// Saved conversations (`--session <NAME>`): one JSON file of messages per session, so a later
+// run can continue where the last one stopped. Gemini keeps no state between calls; the
+// whole history is sent again with every new prompt.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rust_gemini_llm_client::{ChatMessage, Role};
+use serde::{Deserialize, Serialize};
+
+/// How many messages a session keeps; older turns are dropped first. 40 messages is
+/// 20 question/answer pairs.
+pub const MAX_SESSION_MESSAGES: usize = 40;
+
+/// `$XDG_CONFIG_HOME/rust-cli-echo/sessions`, or `~/.config/rust-cli-echo/sessions` when
+/// that isn't set. `None` if there is no home directory to put it in.
+pub fn default_dir() -> Option<PathBuf> {
+    let config = match std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        // USERPROFILE is Windows' equivalent of HOME
+        None => {
+            PathBuf::from(std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?)
+                .join(".config")
+        }
+    };
+    Some(config.join("rust-cli-echo").join("sessions"))
+}
+
+/// Check a session name: letters, digits, `-`, `_` and `.`, not starting with a dot.
+/// The name becomes a file name, so anything like `../x` must be refused.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid session name '{}': use letters, digits, '-', '_' and '.'",
+            name
+        ))
+    }
+}
+
+/// Drop the oldest messages until at most `max` are left. The history then starts with a
+/// user message, as Gemini expects, so an orphaned answer at the front goes too.
+pub fn cap_history(messages: &mut Vec<ChatMessage>, max: usize) {
+    let excess = messages.len().saturating_sub(max);
+    messages.drain(..excess);
+    let leading_answers = messages
+        .iter()
+        .take_while(|message| message.role == Role::Model)
+        .count();
+    messages.drain(..leading_answers);
+}
+
+// The file format: {"messages": [{"role": "user", "text": "..."}, ...]}. The client's
+// ChatMessage has no serde support, so these mirror it for storage.
+#[derive(Serialize, Deserialize)]
+struct SessionFile {
+    messages: Vec<StoredMessage>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredMessage {
+    role: StoredRole,
+    text: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum StoredRole {
+    User,
+    Model,
+}
+
+/// A loaded session.
+#[derive(Debug, Default, PartialEq)]
+pub struct LoadedSession {
+    pub messages: Vec<ChatMessage>,
+    /// Set when the file couldn't be read as a session: it was moved here and the session
+    /// starts over empty
+    pub backup: Option<PathBuf>,
+}
+
+/// The session files in one directory.
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    dir: PathBuf,
+    max_messages: usize,
+}
+
+impl SessionStore {
+    /// Sessions in `dir`, which is created on the first save
+    pub fn new(dir: impl Into<PathBuf>) -> SessionStore {
+        SessionStore {
+            dir: dir.into(),
+            max_messages: MAX_SESSION_MESSAGES,
+        }
+    }
+
+    /// Keep at most `max` messages per session instead of `MAX_SESSION_MESSAGES`
+    pub fn with_max_messages(mut self, max: usize) -> SessionStore {
+        self.max_messages = max;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Where session `name` is stored
+    pub fn path(&self, name: &str) -> io::Result<PathBuf> {
+        validate_name(name).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    /// Load session `name`; a session that doesn't exist yet is empty.
+    ///
+    /// A file that isn't valid session JSON (hand-edited, or cut short by a crash) is renamed
+    /// to `<name>.json.corrupt-<timestamp>` and the session starts fresh, rather than
+    /// failing every run until someone deletes it.
+    pub fn load(&self, name: &str) -> io::Result<LoadedSession> {
+        let path = self.path(name)?;
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(LoadedSession::default()),
+            Err(e) => return Err(e),
+        };
+        match serde_json::from_str::<SessionFile>(&json) {
+            Ok(file) => Ok(LoadedSession {
+                messages: file.messages.into_iter().map(ChatMessage::from).collect(),
+                backup: None,
+            }),
+            Err(_) => {
+                let seconds = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                let backup = self.dir.join(format!("{}.json.corrupt-{}", name, seconds));
+                fs::rename(&path, &backup)?;
+                Ok(LoadedSession {
+                    messages: Vec::new(),
+                    backup: Some(backup),
+                })
+            }
+        }
+    }
+
+    /// Save `messages` as session `name`, dropping the oldest beyond the cap.
+    pub fn save(&self, name: &str, messages: &[ChatMessage]) -> io::Result<()> {
+        let path = self.path(name)?;
+        let mut messages = messages.to_vec();
+        cap_history(&mut messages, self.max_messages);
+        let file = SessionFile {
+            messages: messages.iter().map(StoredMessage::from).collect(),
+        };
+        let json = serde_json::to_string_pretty(&file)?;
+
+        fs::create_dir_all(&self.dir)?;
+        // Write a temporary file and rename it over the old one. A rename replaces the file
+        // in one step, so a crash mid-write can't leave a half-written session behind.
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, json)?;
+        fs::rename(&temp, &path)
+    }
+
+    /// The names of all saved sessions, sorted. No directory yet means no sessions.
+    pub fn list(&self) -> io::Result<Vec<String>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
+            let file_name = entry?.file_name();
+            // Backups (.json.corrupt-...) and temporary files (.json.tmp) don't end in .json
+            if let Some(name) = file_name.to_str().and_then(|n| n.strip_suffix(".json"))
+                && validate_name(name).is_ok()
+            {
+                names.push(name.to_string());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Delete session `name`. Returns false if there was no such session.
+    pub fn delete(&self, name: &str) -> io::Result<bool> {
+        match fs::remove_file(self.path(name)?) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl From<StoredMessage> for ChatMessage {
+    fn from(message: StoredMessage) -> Self {
+        match message.role {
+            StoredRole::User => ChatMessage::user(message.text),
+            StoredRole::Model => ChatMessage::model(message.text),
+        }
+    }
+}
+
+impl From<&ChatMessage> for StoredMessage {
+    fn from(message: &ChatMessage) -> Self {
+        StoredMessage {
+            role: match message.role {
+                Role::User => StoredRole::User,
+                Role::Model => StoredRole::Model,
+            },
+            text: message.text.clone(),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+// Printing a streamed Gemini answer as it arrives, plus the spinner shown until it starts.
+
+use std::io::{self, IsTerminal, Write};
+use std::time::Duration;
+
+use futures_util::{Stream, StreamExt};
+use rust_gemini_llm_client::{GeminiError, StreamEvent};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+/// Why `print_stream` stopped early.
+#[derive(Debug)]
+pub enum PrintError {
+    /// Gemini (or the connection) failed. `received` says whether any text was printed
+    /// before the failure.
+    Gemini { error: GeminiError, received: bool },
+    /// Writing to the output failed, e.g. because stdout was a pipe that got closed
+    Io(io::Error),
+}
+
+impl From<io::Error> for PrintError {
+    fn from(error: io::Error) -> Self {
+        PrintError::Io(error)
+    }
+}
+
+/// Write each text chunk of `stream` to `out` as soon as it arrives, flushing after every one
+/// so it shows up immediately rather than when a buffer fills.
+///
+/// If the stream fails after some text was printed, that text is left in place and followed
+/// by a trailer line naming the error, so a partial answer can't be mistaken for a complete
+/// one. The output always ends with a newline.
+///
+/// Returns the complete answer.
+pub async fn print_stream<S>(mut stream: S, out: &mut impl Write) -> Result<String, PrintError>
+where
+    // Unpin: the stream can be polled through `&mut` without pinning it first
+    S: Stream<Item = Result<StreamEvent, GeminiError>> + Unpin,
+{
+    let mut answer = String::new();
+    let mut at_line_start = true;
+    while let Some(event) = stream.next().await {
+        match event {
+            Ok(StreamEvent::Text(text)) => {
+                if text.is_empty() {
+                    continue;
+                }
+                out.write_all(text.as_bytes())?;
+                out.flush()?;
+                at_line_start = text.ends_with('\n');
+                answer.push_str(&text);
+            }
+            Ok(StreamEvent::Done(_)) => break,
+            Err(error) => {
+                let received = !answer.is_empty();
+                if received {
+                    if !at_line_start {
+                        writeln!(out)?;
+                    }
+                    writeln!(out, "[Response interrupted: {}]", error)?;
+                    out.flush()?;
+                }
+                return Err(PrintError::Gemini { error, received });
+            }
+        }
+    }
+    if !at_line_start {
+        writeln!(out)?;
+    }
+    out.flush()?;
+    Ok(answer)
+}
+
+/// Wait for the whole answer without printing anything (`--no-stream`).
+pub async fn collect_stream<S>(mut stream: S) -> Result<String, GeminiError>
+where
+    S: Stream<Item = Result<StreamEvent, GeminiError>> + Unpin,
+{
+    let mut answer = String::new();
+    while let Some(event) = stream.next().await {
+        match event? {
+            StreamEvent::Text(text) => answer.push_str(&text),
+            StreamEvent::Done(_) => break,
+        }
+    }
+    Ok(answer)
+}
+
+/// An animation on stderr while waiting for the first token, so a slow answer doesn't look
+/// like a hung program.
+pub struct Spinner {
+    // `None` when stderr isn't a terminal: in a log file the frames would just be noise
+    running: Option<(oneshot::Sender<()>, JoinHandle<()>)>,
+}
+
+impl Spinner {
+    const FRAMES: [&'static str; 4] = ["|", "/", "-", "\\"];
+    const INTERVAL: Duration = Duration::from_millis(100);
+
+    /// Start spinning (only if stderr is a terminal).
+    pub fn start(message: &str) -> Spinner {
+        if !io::stderr().is_terminal() {
+            return Spinner { running: None };
+        }
+        let message = message.to_string();
+        let (stop, mut stopped) = oneshot::channel();
+        let task = tokio::spawn(async move {
+            let mut stderr = io::stderr();
+            for frame in Spinner::FRAMES.iter().cycle() {
+                let _ = write!(stderr, "\r{} {}", frame, message);
+                let _ = stderr.flush();
+                // select! waits for whichever finishes first: the stop signal or the next tick
+                tokio::select! {
+                    _ = &mut stopped => break,
+                    _ = tokio::time::sleep(Spinner::INTERVAL) => {}
+                }
+            }
+            // "\x1b[2K" clears the line, so the answer starts on a clean one
+            let _ = write!(stderr, "\r\x1b[2K");
+            let _ = stderr.flush();
+        });
+        Spinner {
+            running: Some((stop, task)),
+        }
+    }
+
+    /// Stop and erase the spinner. Waits for the task, so nothing is drawn afterwards.
+    pub async fn stop(self) {
+        if let Some((stop, task)) = self.running {
+            let _ = stop.send(());
+            let _ = task.await;
+        }
+    }
+}
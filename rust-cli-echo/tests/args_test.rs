@@ -0,0 +1,306 @@
+use std::io::Cursor;
+
+use clap::error::ErrorKind;
+use clap_complete::Shell;
+use rust_cli_echo::{
+    Cli, Command, DEFAULT_MAX_STDIN, EXPLAIN_INSTRUCTION, SessionsAction, legacy_to_subcommand,
+    parse_args,
+};
+
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+fn parse(args: &[&str]) -> Cli {
+    parse_args(strings(args)).unwrap()
+}
+
+fn error_kind(args: &[&str]) -> ErrorKind {
+    parse_args(strings(args)).unwrap_err().kind()
+}
+
+fn stdin(text: &str) -> Option<Cursor<Vec<u8>>> {
+    Some(Cursor::new(text.as_bytes().to_vec()))
+}
+
+#[test]
+fn legacy_prompt_means_ask() {
+    let cli = parse(&["-k", "key", "hello", "world"]);
+    assert_eq!(cli.gemini.api_key.as_deref(), Some("key"));
+    let Command::Ask(ask) = cli.command else {
+        panic!("expected ask, got {:?}", cli.command)
+    };
+    assert_eq!(ask.prompt().as_deref(), Some("hello world"));
+    assert_eq!(ask.session, None);
+}
+
+#[test]
+fn ask_subcommand_takes_global_flags_on_either_side() {
+    for args in [
+        &["ask", "--model", "gemini-2.0-flash", "hi"][..],
+        &["--model", "gemini-2.0-flash", "ask", "hi"],
+        &["--model=gemini-2.0-flash", "hi"],
+    ] {
+        let cli = parse(args);
+        assert_eq!(
+            cli.gemini.model.as_deref(),
+            Some("gemini-2.0-flash"),
+            "{:?}",
+            args
+        );
+        assert!(matches!(cli.command, Command::Ask(ask) if ask.prompt().as_deref() == Some("hi")));
+    }
+}
+
+#[test]
+fn no_arguments_is_an_empty_ask() {
+    // The prompt may still come from a pipe: `echo hi | rust-cli-echo`
+    let Command::Ask(ask) = parse(&[]).command else {
+        panic!("expected ask")
+    };
+    assert_eq!(ask.prompt(), None);
+    let input = ask.read_stdin(stdin("hi\n")).unwrap().unwrap();
+    assert_eq!(input.text, "hi\n");
+}
+
+#[test]
+fn generation_flags() {
+    let cli = parse(&[
+        "--key=k",
+        "--temperature",
+        "0.5",
+        "--max-tokens=256",
+        "--system",
+        "Be brief",
+        "--no-stream",
+        "hi",
+    ]);
+    assert_eq!(cli.gemini.api_key.as_deref(), Some("k"));
+    assert_eq!(cli.gemini.temperature, Some(0.5));
+    assert_eq!(cli.gemini.max_tokens, Some(256));
+    assert_eq!(cli.gemini.system.as_deref(), Some("Be brief"));
+    assert!(cli.no_stream);
+}
+
+#[test]
+fn out_of_range_values_are_rejected() {
+    for value in ["2.5", "-0.1", "warm"] {
+        let error = parse_args(strings(&["--temperature", value, "hi"])).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ValueValidation);
+        assert!(
+            error
+                .to_string()
+                .contains("expected a number from 0.0 to 2.0"),
+            "{}",
+            error
+        );
+    }
+    assert_eq!(
+        error_kind(&["--max-tokens", "0", "hi"]),
+        ErrorKind::ValueValidation
+    );
+    assert_eq!(
+        error_kind(&["--model", " ", "hi"]),
+        ErrorKind::ValueValidation
+    );
+    assert_eq!(
+        error_kind(&["--max-stdin", "lots", "hi"]),
+        ErrorKind::ValueValidation
+    );
+}
+
+#[test]
+fn piped_input_is_capped() {
+    let Command::Ask(ask) = parse(&["--max-stdin", "4", "x"]).command else {
+        panic!("expected ask")
+    };
+    let input = ask.read_stdin(stdin("123456")).unwrap().unwrap();
+    assert_eq!(input.text, "1234");
+    assert_eq!(input.truncated_at, Some(4));
+
+    let Command::Ask(ask) = parse(&["x"]).command else {
+        panic!("expected ask")
+    };
+    assert_eq!(ask.max_stdin, DEFAULT_MAX_STDIN);
+}
+
+#[test]
+fn no_stdin_leaves_the_pipe_unread() {
+    let Command::Ask(ask) = parse(&["--no-stdin", "x"]).command else {
+        panic!("expected ask")
+    };
+    assert_eq!(ask.read_stdin(stdin("ignored")).unwrap(), None);
+}
+
+#[test]
+fn az_arguments_pass_through_unchanged() {
+    // Flags after the az command belong to az, --help included
+    for args in [
+        &["az", "group", "list", "--output", "table", "--help"][..],
+        &["az", "--", "group", "list", "--output", "table", "--help"],
+    ] {
+        let Command::Az(az) = parse(args).command else {
+            panic!("expected az for {:?}", args)
+        };
+        assert_eq!(
+            az.args,
+            strings(&["group", "list", "--output", "table", "--help"])
+        );
+        assert_eq!(az.gemini_instruction(), None);
+    }
+    assert_eq!(error_kind(&["az"]), ErrorKind::MissingRequiredArgument);
+}
+
+#[test]
+fn legacy_explain_before_az() {
+    let cli = parse(&["-k", "key", "--explain", "az", "group", "list"]);
+    assert_eq!(cli.gemini.api_key.as_deref(), Some("key"));
+    let Command::Az(az) = cli.command else {
+        panic!("expected az")
+    };
+    assert_eq!(az.args, strings(&["group", "list"]));
+    assert_eq!(
+        az.gemini_instruction().as_deref(),
+        Some(EXPLAIN_INSTRUCTION)
+    );
+}
+
+#[test]
+fn pipe_to_gemini_in_both_forms() {
+    for args in [
+        &["--pipe-to-gemini", "Summarize", "az", "vm", "list"][..],
+        &["az", "--pipe-to-gemini=Summarize", "vm", "list"],
+    ] {
+        let Command::Az(az) = parse(args).command else {
+            panic!("expected az for {:?}", args)
+        };
+        assert_eq!(az.args, strings(&["vm", "list"]));
+        assert_eq!(az.gemini_instruction().as_deref(), Some("Summarize"));
+    }
+    assert_eq!(
+        error_kind(&["--explain", "--pipe-to-gemini", "x", "az", "vm", "list"]),
+        ErrorKind::ArgumentConflict
+    );
+}
+
+#[test]
+fn az_only_when_it_is_the_first_word() {
+    let Command::Ask(ask) = parse(&["what", "is", "az"]).command else {
+        panic!("expected ask")
+    };
+    assert_eq!(ask.prompt().as_deref(), Some("what is az"));
+}
+
+#[test]
+fn sessions_in_both_forms() {
+    for args in [&["--list-sessions"][..], &["sessions", "list"]] {
+        assert!(matches!(
+            parse(args).command,
+            Command::Sessions {
+                action: SessionsAction::List
+            }
+        ));
+    }
+    for args in [
+        &["--delete-session", "work"][..],
+        &["--delete-session=work"],
+        &["sessions", "delete", "work"],
+    ] {
+        let Command::Sessions { action } = parse(args).command else {
+            panic!("expected sessions for {:?}", args)
+        };
+        assert_eq!(
+            action,
+            SessionsAction::Delete {
+                name: "work".to_string()
+            }
+        );
+    }
+    assert_eq!(
+        error_kind(&["sessions", "delete", "../x"]),
+        ErrorKind::ValueValidation
+    );
+}
+
+#[test]
+fn session_flag_for_ask_and_repl() {
+    let Command::Ask(ask) = parse(&["--session", "work", "next question"]).command else {
+        panic!("expected ask")
+    };
+    assert_eq!(ask.session.as_deref(), Some("work"));
+    assert_eq!(
+        error_kind(&["--session", "a/b", "hi"]),
+        ErrorKind::ValueValidation
+    );
+
+    let Command::Repl(repl) = parse(&["--no-stream", "repl", "--session=work"]).command else {
+        panic!("expected repl")
+    };
+    assert_eq!(repl.session.as_deref(), Some("work"));
+}
+
+#[test]
+fn completions_subcommand() {
+    let Command::Completions(completions) = parse(&["completions", "zsh"]).command else {
+        panic!("expected completions")
+    };
+    assert_eq!(completions.shell, Shell::Zsh);
+    assert_eq!(completions.dir, None);
+    assert_eq!(
+        error_kind(&["completions", "tcsh"]),
+        ErrorKind::InvalidValue
+    );
+}
+
+#[test]
+fn help_and_version_are_not_prompts() {
+    assert_eq!(error_kind(&["--help"]), ErrorKind::DisplayHelp);
+    assert_eq!(error_kind(&["-V"]), ErrorKind::DisplayVersion);
+    assert_eq!(
+        error_kind(&["az", "--explain"]),
+        ErrorKind::MissingRequiredArgument
+    );
+    assert_eq!(error_kind(&["ask", "--help"]), ErrorKind::DisplayHelp);
+    assert_eq!(error_kind(&["--bogus", "hi"]), ErrorKind::UnknownArgument);
+}
+
+#[test]
+fn legacy_rewriting() {
+    let rewrite = |args: &[&str]| legacy_to_subcommand(strings(args));
+    assert_eq!(rewrite(&["hi"]), strings(&["ask", "hi"]));
+    assert_eq!(
+        rewrite(&["--model", "m", "az", "vm", "list"]),
+        strings(&["az", "--model", "m", "vm", "list"])
+    );
+    assert_eq!(rewrite(&["repl"]), strings(&["repl"]));
+    assert_eq!(rewrite(&["-k", "k", "repl"]), strings(&["-k", "k", "repl"]));
+    assert_eq!(rewrite(&["--help"]), strings(&["--help"]));
+    assert_eq!(rewrite(&[]), strings(&["ask"]));
+}
+
+#[test]
+fn a_prompt_may_start_with_a_subcommand_name() {
+    for words in [
+        &["help", "me", "write", "a", "poem"][..],
+        &["sessions", "are", "hard"],
+        &["repl", "means", "what"],
+        &["completions", "for", "bash", "please"],
+    ] {
+        let Command::Ask(ask) = parse(words).command else {
+            panic!("expected ask for {:?}", words)
+        };
+        assert_eq!(ask.prompt(), Some(words.join(" ")));
+    }
+    // Still the subcommands when the words make sense to them
+    assert!(matches!(
+        parse(&["sessions", "list"]).command,
+        Command::Sessions {
+            action: SessionsAction::List
+        }
+    ));
+    assert_eq!(error_kind(&["help", "sessions"]), ErrorKind::DisplayHelp);
+    assert_eq!(
+        error_kind(&["completions", "tcsh"]),
+        ErrorKind::InvalidValue
+    );
+}
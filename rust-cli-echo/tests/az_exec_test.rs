@@ -0,0 +1,178 @@
+// A fake `az`: small shell scripts that fail, hang or succeed on cue, so the timeout and
+// retry logic can be tested without the Azure CLI or a network.
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use assert_cmd::Command;
+use rust_cli_echo::{AzExecError, AzRunner, is_transient};
+use serde_json::Value;
+
+// A fresh directory holding an executable `az` with `body` as its script
+fn fake_az(name: &str, body: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("rust-cli-echo-az-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let script = dir.join("az");
+    fs::write(&script, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+    dir
+}
+
+// Fails with a connection error the first `failures` times, then lists a resource group.
+// The attempt count is kept in a file next to the script.
+fn flaky_az(name: &str, failures: u32) -> PathBuf {
+    fake_az(
+        name,
+        &format!(
+            r#"count_file="$(dirname "$0")/count"
+n=$(( $(cat "$count_file" 2>/dev/null || echo 0) + 1 ))
+echo $n > "$count_file"
+if [ $n -le {} ]; then
+  echo "ERROR: ('Connection aborted.', ConnectionResetError(104, 'Connection reset by peer'))" >&2
+  exit 1
+fi
+echo '[{{"name": "rg-rust-app"}}]'"#,
+            failures
+        ),
+    )
+}
+
+fn attempts(dir: &Path) -> u32 {
+    fs::read_to_string(dir.join("count"))
+        .unwrap()
+        .trim()
+        .parse()
+        .unwrap()
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+// The CLI with the fake az first on PATH
+fn cli(dir: &Path) -> Command {
+    let path = format!(
+        "{}:{}",
+        dir.display(),
+        std::env::var("PATH").unwrap_or_default()
+    );
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rust-cli-echo"));
+    command.current_dir(dir).env("PATH", path);
+    command
+}
+
+#[test]
+fn transient_errors_are_recognised() {
+    assert!(is_transient("ERROR: Connection reset by peer"));
+    assert!(is_transient("(TooManyRequests) Too Many Requests"));
+    assert!(is_transient("Request was throttled. Retry after 10s"));
+    assert!(!is_transient(
+        "ERROR: Resource group 'typo' could not be found."
+    ));
+    assert!(!is_transient(""));
+}
+
+#[tokio::test]
+async fn retries_until_az_succeeds() {
+    let dir = flaky_az("retry", 2);
+    let mut notes = Vec::new();
+    let output = AzRunner::new()
+        .with_program(dir.join("az"))
+        .with_retries(3)
+        .with_retry_delay(Duration::from_millis(10))
+        .run(&args(&["group", "list"]), |attempt, _| notes.push(attempt))
+        .await
+        .unwrap();
+    assert!(output.success);
+    assert!(output.stdout.contains("rg-rust-app"));
+    assert_eq!(notes, [1, 2]);
+    assert_eq!(attempts(&dir), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_the_last_retry() {
+    let dir = flaky_az("give-up", 5);
+    let output = AzRunner::new()
+        .with_program(dir.join("az"))
+        .with_retries(1)
+        .with_retry_delay(Duration::from_millis(10))
+        .run(&args(&["group", "list"]), |_, _| {})
+        .await
+        .unwrap();
+    assert!(!output.success);
+    assert_eq!(output.exit_code, Some(1));
+    assert_eq!(attempts(&dir), 2);
+}
+
+#[tokio::test]
+async fn other_failures_are_not_retried() {
+    let dir = fake_az(
+        "no-retry",
+        r#"echo 1 >> "$(dirname "$0")/count"; echo "ERROR: unrecognized arguments" >&2; exit 2"#,
+    );
+    let output = AzRunner::new()
+        .with_program(dir.join("az"))
+        .with_retries(3)
+        .run(&args(&["grup"]), |_, _| panic!("retried"))
+        .await
+        .unwrap();
+    assert_eq!(output.exit_code, Some(2));
+    assert_eq!(attempts(&dir), 1);
+}
+
+#[tokio::test]
+async fn a_hanging_az_is_killed_at_the_timeout() {
+    // exec: the sleep replaces the shell, so killing "az" kills the sleep
+    let dir = fake_az("hang", "exec sleep 30");
+    let started = Instant::now();
+    let error = AzRunner::new()
+        .with_program(dir.join("az"))
+        .with_timeout(Duration::from_millis(200))
+        .run(&args(&["login"]), |_, _| {})
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AzExecError::TimedOut(_)), "{:?}", error);
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[test]
+fn cli_exits_with_the_az_exit_code() {
+    let dir = fake_az("exit-code", r#"echo "ERROR: not logged in" >&2; exit 7"#);
+    let output = cli(&dir).args(["az", "account", "show"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(7));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not logged in"));
+}
+
+#[test]
+fn cli_timeout_is_exit_4() {
+    let dir = fake_az("cli-hang", "exec sleep 30");
+    let started = Instant::now();
+    let output = cli(&dir)
+        .args(["--json", "az", "--timeout", "1", "login"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    assert!(started.elapsed() < Duration::from_secs(10));
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["ok"], false);
+    assert!(report["error"].as_str().unwrap().contains("timed out"));
+}
+
+#[test]
+fn cli_retries_a_transient_failure() {
+    // Legacy order: the flags before `az`
+    let dir = flaky_az("cli-retry", 1);
+    let output = cli(&dir)
+        .args(["--retries", "2", "az", "group", "list"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("rg-rust-app"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("retry 1 of 2"));
+    assert_eq!(attempts(&dir), 2);
+}
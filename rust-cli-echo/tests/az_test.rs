@@ -0,0 +1,109 @@
+use rust_cli_echo::{AzOutput, EXPLAIN_INSTRUCTION, MAX_AZ_OUTPUT, az_prompt, truncate_output};
+
+const GROUP_LIST: &str = include_str!("fixtures/az_group_list.json");
+
+fn group_list() -> AzOutput {
+    AzOutput {
+        args: ["group", "list", "--output", "json"]
+            .map(String::from)
+            .to_vec(),
+        stdout: GROUP_LIST.to_string(),
+        stderr: String::new(),
+        success: true,
+        exit_code: Some(0),
+    }
+}
+
+#[test]
+fn explain_prompt_from_json_output() {
+    let prompt = az_prompt(EXPLAIN_INSTRUCTION, &group_list(), MAX_AZ_OUTPUT);
+
+    let expected = format!(
+        "Explain this Azure CLI output\n\n\
+         Command: az group list --output json\n\
+         The command succeeded.\n\n\
+         stdout:\n```\n{}\n```",
+        GROUP_LIST.trim_end()
+    );
+    assert_eq!(prompt, expected);
+}
+
+#[test]
+fn failed_command_includes_stderr_and_exit_code() {
+    let output = AzOutput {
+        args: ["group", "show", "--name", "missing"]
+            .map(String::from)
+            .to_vec(),
+        stdout: String::new(),
+        stderr: "ERROR: (ResourceGroupNotFound) Resource group 'missing' could not be found.\n"
+            .to_string(),
+        success: false,
+        exit_code: Some(3),
+    };
+
+    let prompt = az_prompt("Why did this fail?", &output, MAX_AZ_OUTPUT);
+
+    assert_eq!(
+        prompt,
+        "Why did this fail?\n\n\
+         Command: az group show --name missing\n\
+         The command failed with exit code 3.\n\n\
+         stderr:\n```\nERROR: (ResourceGroupNotFound) Resource group 'missing' could not be found.\n```"
+    );
+}
+
+#[test]
+fn short_output_is_not_truncated() {
+    assert_eq!(truncate_output("abc\n", 4), ("abc\n", false));
+    assert_eq!(truncate_output("", 0), ("", false));
+}
+
+#[test]
+fn truncation_ends_at_a_whole_line() {
+    let (kept, truncated) = truncate_output("line one\nline two\nline three\n", 20);
+    assert_eq!(kept, "line one\nline two\n");
+    assert!(truncated);
+}
+
+#[test]
+fn a_single_long_line_is_cut_at_a_character() {
+    // "é" is two bytes; a cut at byte 3 would split the second one
+    let (kept, truncated) = truncate_output("éééé", 3);
+    assert_eq!(kept, "é");
+    assert!(truncated);
+}
+
+#[test]
+fn large_output_is_truncated_with_a_note() {
+    let mut output = group_list();
+    output.stdout = GROUP_LIST.repeat(100);
+    let total = output.stdout.len();
+
+    let prompt = az_prompt(EXPLAIN_INSTRUCTION, &output, 1024);
+
+    let (kept, _) = truncate_output(&output.stdout, 1024);
+    assert!(prompt.contains(&format!("```\n{}\n```", kept.trim_end())));
+    assert!(prompt.ends_with(&format!(
+        "[stdout truncated: showing the first {} of {} bytes.]",
+        kept.len(),
+        total
+    )));
+    assert!(prompt.len() < 1024 + 200);
+}
+
+#[test]
+fn stderr_gets_at_most_a_quarter_of_the_budget() {
+    let output = AzOutput {
+        args: vec!["vm".to_string(), "list".to_string()],
+        stdout: "x\n".repeat(1000),
+        stderr: "WARNING: something\n".repeat(100),
+        success: true,
+        exit_code: Some(0),
+    };
+
+    let prompt = az_prompt("Summarize", &output, 400);
+
+    assert!(prompt.contains("[stderr truncated: showing the first 95 of 1900 bytes.]"));
+    // stdout gets what stderr left over: 400 - 95
+    assert!(prompt.contains("[stdout truncated: showing the first 304 of 2000 bytes.]"));
+}
@@ -0,0 +1,149 @@
+// These run the real binary, the way a script would, and check exit codes and `--json`.
+
+use std::path::PathBuf;
+
+use assert_cmd::Command;
+use rust_cli_echo::{Exit, Failure, json_requested};
+use rust_gemini_llm_client::GeminiError;
+use serde_json::Value;
+
+// A clean environment: no API key or model from the real one, and an empty working
+// directory so no `.env` file is found either
+fn cli(name: &str) -> Command {
+    let dir: PathBuf = std::env::temp_dir().join(format!(
+        "rust-cli-echo-exit-{}-{}",
+        std::process::id(),
+        name
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rust-cli-echo"));
+    command
+        .current_dir(dir)
+        .env_remove("GEMINI_API_KEY")
+        .env_remove("GEMINI_MODEL")
+        .env_remove("GEMINI_SYSTEM_PROMPT");
+    command
+}
+
+fn json_stdout(output: &std::process::Output) -> Value {
+    serde_json::from_slice(&output.stdout).expect("stdout is one JSON object")
+}
+
+// Every report has the same five keys, whatever happened
+fn assert_report_shape(report: &Value) {
+    let keys: Vec<&str> = report
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    assert_eq!(keys.len(), 5, "{}", report);
+    for key in ["ok", "output", "error", "model", "elapsed_ms"] {
+        assert!(keys.contains(&key), "missing {} in {}", key, report);
+    }
+    assert!(report["elapsed_ms"].is_u64());
+}
+
+#[test]
+fn exit_codes_are_distinct() {
+    let codes = [
+        Exit::Success,
+        Exit::Gemini,
+        Exit::Usage,
+        Exit::AzFailed,
+        Exit::Timeout,
+    ]
+    .map(Exit::code);
+    assert_eq!(codes, [0, 1, 2, 3, 4]);
+    assert_eq!(Failure::gemini(&GeminiError::Timeout).exit, Exit::Timeout);
+    assert_eq!(
+        Failure::gemini(&GeminiError::MissingApiKey).exit,
+        Exit::Gemini
+    );
+}
+
+#[test]
+fn usage_error_exits_2() {
+    let output = cli("usage")
+        .args(["--temperature", "9", "hi"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--temperature"), "{}", stderr);
+}
+
+#[test]
+fn usage_error_as_json() {
+    let output = cli("usage-json")
+        .args(["--json", "--temperature", "9", "hi"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let report = json_stdout(&output);
+    assert_report_shape(&report);
+    assert_eq!(report["ok"], false);
+    assert_eq!(report["output"], Value::Null);
+    assert_eq!(report["model"], Value::Null);
+    let error = report["error"].as_str().unwrap();
+    assert!(
+        error.contains("expected a number from 0.0 to 2.0"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn nothing_to_ask_is_a_usage_error() {
+    cli("empty").arg("--no-stdin").assert().code(2).stdout("");
+}
+
+#[test]
+fn missing_api_key_exits_1() {
+    let output = cli("no-key").args(["--no-stdin", "hi"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    // Diagnostics go to stderr only
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("GEMINI_API_KEY"), "{}", stderr);
+}
+
+#[test]
+fn missing_api_key_as_json() {
+    let output = cli("no-key-json")
+        .args(["--json", "--model", "gemini-2.0-flash", "--no-stdin", "hi"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let report = json_stdout(&output);
+    assert_report_shape(&report);
+    assert_eq!(report["ok"], false);
+    assert_eq!(report["output"], Value::Null);
+    assert_eq!(report["model"], "gemini-2.0-flash");
+    assert!(report["error"].as_str().unwrap().contains("GEMINI_API_KEY"));
+}
+
+#[test]
+fn json_is_only_for_ask_and_az() {
+    let output = cli("repl-json").args(["--json", "repl"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(json_stdout(&output)["ok"], false);
+}
+
+#[test]
+fn json_flag_is_found_before_parsing() {
+    let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    assert!(json_requested(&args(&[
+        "--json",
+        "--temperature",
+        "9",
+        "hi"
+    ])));
+    assert!(json_requested(&args(&["--json", "az", "group", "list"])));
+    assert!(json_requested(&args(&["ask", "hi", "--json"])));
+    // az's own arguments and values of other flags don't count
+    assert!(!json_requested(&args(&["az", "group", "list", "--json"])));
+    assert!(!json_requested(&args(&["--system", "--json", "hi"])));
+    assert!(!json_requested(&args(&["hi"])));
+}
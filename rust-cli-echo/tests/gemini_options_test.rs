@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use rust_cli_echo::GeminiOptions;
+use rust_gemini_llm_client::GenerationOptions;
+
+// A fake environment, so tests don't touch (or race on) the real one
+fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    move |name| vars.get(name).cloned()
+}
+
+#[test]
+fn env_fills_in_missing_settings() {
+    let mut options = GeminiOptions::default();
+    options.apply_env(env(&[
+        ("GEMINI_MODEL", "gemini-1.5-pro"),
+        ("GEMINI_SYSTEM_PROMPT", "You are terse"),
+    ]));
+    assert_eq!(options.model.as_deref(), Some("gemini-1.5-pro"));
+    assert_eq!(options.system.as_deref(), Some("You are terse"));
+}
+
+#[test]
+fn flags_win_over_env() {
+    let mut options = GeminiOptions {
+        model: Some("gemini-2.0-flash".to_string()),
+        system: Some("Answer like a pirate".to_string()),
+        ..GeminiOptions::default()
+    };
+    options.apply_env(env(&[
+        ("GEMINI_MODEL", "gemini-1.5-pro"),
+        ("GEMINI_SYSTEM_PROMPT", "You are terse"),
+    ]));
+    assert_eq!(options.model.as_deref(), Some("gemini-2.0-flash"));
+    assert_eq!(options.system.as_deref(), Some("Answer like a pirate"));
+}
+
+#[test]
+fn empty_env_vars_are_ignored() {
+    let mut options = GeminiOptions::default();
+    options.apply_env(env(&[("GEMINI_MODEL", ""), ("GEMINI_SYSTEM_PROMPT", "  ")]));
+    assert_eq!(options, GeminiOptions::default());
+}
+
+#[test]
+fn client_gets_every_setting() {
+    let options = GeminiOptions {
+        api_key: Some("test-key".to_string()),
+        model: Some("gemini-2.0-flash".to_string()),
+        temperature: Some(0.7),
+        max_tokens: Some(300),
+        system: Some("Be brief".to_string()),
+    };
+
+    let client = options.client().unwrap();
+
+    assert_eq!(client.model(), "gemini-2.0-flash");
+    assert_eq!(client.system_instruction(), Some("Be brief"));
+    assert_eq!(
+        client.generation_options(),
+        GenerationOptions {
+            temperature: Some(0.7),
+            max_output_tokens: Some(300),
+        }
+    );
+}
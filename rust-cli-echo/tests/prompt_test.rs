@@ -0,0 +1,96 @@
+use rust_cli_echo::{StdinInput, assemble_prompt, read_capped};
+
+fn piped(text: &str) -> StdinInput {
+    StdinInput {
+        text: text.to_string(),
+        truncated_at: None,
+    }
+}
+
+#[test]
+fn arguments_only() {
+    assert_eq!(
+        assemble_prompt(Some("tell me a joke"), None).as_deref(),
+        Some("tell me a joke")
+    );
+}
+
+#[test]
+fn arguments_and_stdin_use_a_code_block() {
+    let diff = "diff --git a/x b/x\n+added line\n";
+    assert_eq!(
+        assemble_prompt(Some("review this diff"), Some(&piped(diff))).unwrap(),
+        "review this diff\n\n```\ndiff --git a/x b/x\n+added line\n```"
+    );
+}
+
+#[test]
+fn stdin_alone_is_the_whole_prompt() {
+    assert_eq!(
+        assemble_prompt(None, Some(&piped("What is Rust?\n"))).as_deref(),
+        Some("What is Rust?\n")
+    );
+}
+
+#[test]
+fn nothing_at_all() {
+    assert_eq!(assemble_prompt(None, None), None);
+    // Blank input counts as no input
+    assert_eq!(assemble_prompt(None, Some(&piped(" \n"))), None);
+    assert_eq!(
+        assemble_prompt(Some("hi"), Some(&piped(""))).as_deref(),
+        Some("hi")
+    );
+}
+
+#[test]
+fn fence_outgrows_backticks_in_the_input() {
+    let readme = "Run:\n```bash\ncargo run\n```\n";
+    let prompt = assemble_prompt(Some("summarize"), Some(&piped(readme))).unwrap();
+    assert!(prompt.starts_with("summarize\n\n````\nRun:"));
+    assert!(prompt.ends_with("```\n````"));
+}
+
+#[test]
+fn truncated_input_gets_a_notice() {
+    let input = StdinInput {
+        text: "abc".to_string(),
+        truncated_at: Some(3),
+    };
+    assert_eq!(
+        assemble_prompt(Some("explain"), Some(&input)).unwrap(),
+        "explain\n\n```\nabc\n```\n\n[Input truncated to the first 3 bytes.]"
+    );
+    assert_eq!(
+        assemble_prompt(None, Some(&input)).unwrap(),
+        "abc\n\n[Input truncated to the first 3 bytes.]"
+    );
+}
+
+#[test]
+fn reading_exactly_the_cap_is_not_truncation() {
+    let input = read_capped("12345".as_bytes(), 5).unwrap();
+    assert_eq!(input.text, "12345");
+    assert_eq!(input.truncated_at, None);
+}
+
+#[test]
+fn one_byte_over_the_cap_is_truncated() {
+    let input = read_capped("123456".as_bytes(), 5).unwrap();
+    assert_eq!(input.text, "12345");
+    assert_eq!(input.truncated_at, Some(5));
+}
+
+#[test]
+fn cut_inside_a_character_drops_it() {
+    // "é" is two bytes in UTF-8; a 2-byte cap splits it
+    let input = read_capped("aé".as_bytes(), 2).unwrap();
+    assert_eq!(input.text, "a");
+    assert_eq!(input.truncated_at, Some(2));
+}
+
+#[test]
+fn invalid_utf8_is_replaced() {
+    let input = read_capped(&[b'o', b'k', 0xff][..], 10).unwrap();
+    assert_eq!(input.text, "ok\u{FFFD}");
+}
@@ -0,0 +1,183 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::error::ErrorKind;
+use rust_cli_echo::{
+    CodeBlock, Command, SaveError, code_blocks, extract_code, parse_args, save_answer, write_file,
+};
+
+// A fresh, empty directory per test
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "rust-cli-echo-save-{}-{}",
+        std::process::id(),
+        name
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn block(lang: Option<&str>, code: &str) -> CodeBlock {
+    CodeBlock {
+        lang: lang.map(str::to_string),
+        code: code.to_string(),
+    }
+}
+
+const ANSWER: &str = "\
+Create the project first:
+
+```bash
+cargo new hello
+```
+
+Then replace `src/main.rs` with:
+
+```rust
+fn main() {
+    println!(\"Hello\");
+}
+```
+
+That's it. Run it with:
+
+~~~
+cargo run
+~~~
+";
+
+#[test]
+fn finds_every_block_in_order() {
+    assert_eq!(
+        code_blocks(ANSWER),
+        [
+            block(Some("bash"), "cargo new hello\n"),
+            block(Some("rust"), "fn main() {\n    println!(\"Hello\");\n}\n"),
+            block(None, "cargo run\n"),
+        ]
+    );
+}
+
+#[test]
+fn extracted_blocks_are_separated_by_a_blank_line() {
+    assert_eq!(
+        extract_code(ANSWER).unwrap(),
+        "cargo new hello\n\nfn main() {\n    println!(\"Hello\");\n}\n\ncargo run\n"
+    );
+}
+
+#[test]
+fn a_longer_fence_can_contain_a_shorter_one() {
+    let answer = "````markdown\n# README\n```rust\nlet x = 1;\n```\n````\n";
+    assert_eq!(
+        code_blocks(answer),
+        [block(
+            Some("markdown"),
+            "# README\n```rust\nlet x = 1;\n```\n"
+        )]
+    );
+}
+
+#[test]
+fn unclosed_block_runs_to_the_end() {
+    // e.g. an answer cut off at the token limit
+    let answer = "Sure:\n```python\nprint('a')\nprint('b')";
+    assert_eq!(
+        code_blocks(answer),
+        [block(Some("python"), "print('a')\nprint('b')\n")]
+    );
+}
+
+#[test]
+fn inline_code_and_text_are_not_blocks() {
+    assert_eq!(code_blocks("Use `cargo run`, or ``x``.\n    indented"), []);
+    assert!(matches!(
+        extract_code("No code here."),
+        Err(SaveError::NoCode)
+    ));
+}
+
+#[test]
+fn out_creates_parent_directories() {
+    let dir = temp_dir("parents");
+    let path = dir.join("nested").join("answer.md");
+    write_file(&path, "hello\n", false).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "hello\n");
+}
+
+#[test]
+fn existing_file_is_not_overwritten_without_force() {
+    let dir = temp_dir("overwrite");
+    let path = dir.join("answer.md");
+    write_file(&path, "first\n", false).unwrap();
+
+    let error = write_file(&path, "second\n", false).unwrap_err();
+    assert!(
+        matches!(&error, SaveError::Exists(p) if p == &path),
+        "{:?}",
+        error
+    );
+    assert!(error.to_string().contains("--force"));
+    assert_eq!(fs::read_to_string(&path).unwrap(), "first\n");
+
+    // --force replaces the whole file, even with shorter content
+    write_file(&path, "2\n", true).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "2\n");
+}
+
+#[test]
+fn save_answer_with_extract_code() {
+    let dir = temp_dir("extract");
+    let path = dir.join("main.rs");
+    let cli = parse_args(
+        [
+            "--out",
+            path.to_str().unwrap(),
+            "--extract-code",
+            "write hello world",
+        ]
+        .map(String::from),
+    )
+    .unwrap();
+    let Command::Ask(ask) = cli.command else {
+        panic!("expected ask")
+    };
+
+    let done = save_answer(ANSWER, &ask.save).unwrap();
+    assert_eq!(done.len(), 1);
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        extract_code(ANSWER).unwrap()
+    );
+    // A second run must not clobber it
+    assert!(matches!(
+        save_answer(ANSWER, &ask.save),
+        Err(SaveError::Exists(_))
+    ));
+}
+
+#[test]
+fn save_flags_need_a_destination() {
+    let kind = |args: &[&str]| {
+        parse_args(args.iter().map(|s| s.to_string()))
+            .unwrap_err()
+            .kind()
+    };
+    assert_eq!(kind(&["--force", "hi"]), ErrorKind::MissingRequiredArgument);
+    assert_eq!(
+        kind(&["--extract-code", "hi"]),
+        ErrorKind::MissingRequiredArgument
+    );
+    // Both legacy and subcommand forms, for ask and az
+    for args in [
+        &["--copy", "--extract-code", "hi"][..],
+        &["az", "--out", "o.json", "group", "list"],
+        &["--out", "o.json", "--force", "az", "group", "list"],
+    ] {
+        assert!(
+            parse_args(args.iter().map(|s| s.to_string())).is_ok(),
+            "{:?}",
+            args
+        );
+    }
+}
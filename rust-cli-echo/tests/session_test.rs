@@ -0,0 +1,168 @@
+use std::fs;
+use std::path::PathBuf;
+
+use rust_cli_echo::{SessionStore, cap_history};
+use rust_gemini_llm_client::ChatMessage;
+
+// A fresh, empty directory per test
+fn temp_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("cli-echo-sessions-{}-{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+fn conversation(turns: usize) -> Vec<ChatMessage> {
+    (1..=turns)
+        .flat_map(|n| {
+            [
+                ChatMessage::user(format!("question {}", n)),
+                ChatMessage::model(format!("answer {}", n)),
+            ]
+        })
+        .collect()
+}
+
+#[test]
+fn save_and_load_round_trip() {
+    let store = SessionStore::new(temp_dir("round-trip"));
+    let messages = vec![
+        ChatMessage::user("What is a lifetime?"),
+        ChatMessage::model(
+            "A lifetime says how long a reference is valid.\n\n```rust\nfn f<'a>() {}\n```",
+        ),
+    ];
+
+    store.save("work", &messages).unwrap();
+    let loaded = store.load("work").unwrap();
+
+    assert_eq!(loaded.messages, messages);
+    assert_eq!(loaded.backup, None);
+}
+
+#[test]
+fn unknown_session_is_empty() {
+    let store = SessionStore::new(temp_dir("unknown"));
+    let loaded = store.load("never-saved").unwrap();
+    assert!(loaded.messages.is_empty());
+    assert_eq!(loaded.backup, None);
+}
+
+#[test]
+fn file_format_is_readable_json() {
+    let dir = temp_dir("format");
+    let store = SessionStore::new(&dir);
+    store.save("notes", &conversation(1)).unwrap();
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("notes.json")).unwrap()).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"messages": [
+            {"role": "user", "text": "question 1"},
+            {"role": "model", "text": "answer 1"}
+        ]})
+    );
+}
+
+#[test]
+fn save_drops_the_oldest_turns_beyond_the_cap() {
+    let store = SessionStore::new(temp_dir("cap")).with_max_messages(4);
+
+    store.save("long", &conversation(5)).unwrap();
+
+    let loaded = store.load("long").unwrap();
+    assert_eq!(loaded.messages, conversation(5)[6..].to_vec());
+    assert_eq!(loaded.messages[0], ChatMessage::user("question 4"));
+}
+
+#[test]
+fn cap_never_starts_with_an_answer() {
+    let mut messages = conversation(3);
+    // Dropping the oldest 3 of 6 leaves "answer 2" first, so it goes too
+    cap_history(&mut messages, 3);
+    assert_eq!(
+        messages,
+        vec![
+            ChatMessage::user("question 3"),
+            ChatMessage::model("answer 3")
+        ]
+    );
+
+    let mut short = conversation(2);
+    cap_history(&mut short, 10);
+    assert_eq!(short, conversation(2));
+}
+
+#[test]
+fn corrupt_file_is_backed_up_and_the_session_restarts() {
+    let dir = temp_dir("corrupt");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("broken.json"),
+        "{\"messages\": [{\"role\": \"user\"",
+    )
+    .unwrap();
+    let store = SessionStore::new(&dir);
+
+    let loaded = store.load("broken").unwrap();
+
+    assert!(loaded.messages.is_empty());
+    let backup = loaded.backup.expect("a backup path");
+    assert!(
+        backup
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("broken.json.corrupt-")
+    );
+    assert_eq!(
+        fs::read_to_string(&backup).unwrap(),
+        "{\"messages\": [{\"role\": \"user\""
+    );
+    assert!(!dir.join("broken.json").exists());
+
+    // The fresh session saves normally, and the backup isn't listed as a session
+    store.save("broken", &conversation(1)).unwrap();
+    assert_eq!(store.load("broken").unwrap().messages, conversation(1));
+    assert_eq!(store.list().unwrap(), vec!["broken"]);
+}
+
+#[test]
+fn wrong_shape_counts_as_corrupt() {
+    let dir = temp_dir("shape");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("old.json"),
+        r#"{"messages": [{"role": "system", "text": "hi"}]}"#,
+    )
+    .unwrap();
+
+    let loaded = SessionStore::new(&dir).load("old").unwrap();
+
+    assert!(loaded.messages.is_empty());
+    assert!(loaded.backup.is_some());
+}
+
+#[test]
+fn list_and_delete() {
+    let store = SessionStore::new(temp_dir("list"));
+    assert!(store.list().unwrap().is_empty());
+
+    store.save("work", &conversation(1)).unwrap();
+    store.save("home", &conversation(1)).unwrap();
+    assert_eq!(store.list().unwrap(), vec!["home", "work"]);
+
+    assert!(store.delete("work").unwrap());
+    assert!(!store.delete("work").unwrap());
+    assert_eq!(store.list().unwrap(), vec!["home"]);
+}
+
+#[test]
+fn names_cannot_leave_the_directory() {
+    let store = SessionStore::new(temp_dir("names"));
+    assert!(store.load("../secrets").is_err());
+    assert!(store.save("a/b", &[]).is_err());
+    assert!(store.delete(".hidden").is_err());
+}
@@ -0,0 +1,150 @@
+use std::io::{self, Write};
+
+use futures_util::stream;
+use rust_cli_echo::{PrintError, collect_stream, print_stream};
+use rust_gemini_llm_client::{GeminiError, GenerationResult, StreamEvent};
+
+fn text(chunk: &str) -> Result<StreamEvent, GeminiError> {
+    Ok(StreamEvent::Text(chunk.to_string()))
+}
+
+fn done(full: &str) -> Result<StreamEvent, GeminiError> {
+    Ok(StreamEvent::Done(GenerationResult {
+        text: full.to_string(),
+        finish_reason: Some("STOP".to_string()),
+        ..GenerationResult::default()
+    }))
+}
+
+// Records what was written and how many times it was flushed
+#[derive(Default)]
+struct Terminal {
+    written: Vec<u8>,
+    // The output as it looked at each flush
+    flushes: Vec<String>,
+}
+
+impl Write for Terminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.written.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushes
+            .push(String::from_utf8_lossy(&self.written).into_owned());
+        Ok(())
+    }
+}
+
+impl Terminal {
+    fn output(&self) -> &str {
+        std::str::from_utf8(&self.written).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn chunks_are_printed_and_flushed_one_by_one() {
+    let events = stream::iter(vec![
+        text("Rust "),
+        text("is "),
+        text("fast."),
+        done("Rust is fast."),
+    ]);
+    let mut out = Terminal::default();
+
+    print_stream(events, &mut out).await.unwrap();
+
+    assert_eq!(out.output(), "Rust is fast.\n");
+    assert_eq!(&out.flushes[..3], ["Rust ", "Rust is ", "Rust is fast."]);
+}
+
+#[tokio::test]
+async fn no_extra_newline_after_one_already_sent() {
+    let events = stream::iter(vec![text("line one\n"), text("line two\n"), done("")]);
+    let mut out = Terminal::default();
+
+    print_stream(events, &mut out).await.unwrap();
+
+    assert_eq!(out.output(), "line one\nline two\n");
+}
+
+#[tokio::test]
+async fn error_mid_stream_keeps_the_text_and_adds_a_trailer() {
+    let events = stream::iter(vec![
+        text("The answer "),
+        text("is"),
+        Err(GeminiError::Timeout),
+    ]);
+    let mut out = Terminal::default();
+
+    let error = print_stream(events, &mut out).await.unwrap_err();
+
+    assert_eq!(
+        out.output(),
+        "The answer is\n[Response interrupted: Request to Gemini timed out]\n"
+    );
+    assert!(matches!(
+        error,
+        PrintError::Gemini {
+            error: GeminiError::Timeout,
+            received: true
+        }
+    ));
+}
+
+#[tokio::test]
+async fn error_before_any_text_prints_nothing() {
+    let events = stream::iter(vec![Err(GeminiError::MissingApiKey)]);
+    let mut out = Terminal::default();
+
+    let error = print_stream(events, &mut out).await.unwrap_err();
+
+    assert_eq!(out.output(), "");
+    assert!(matches!(
+        error,
+        PrintError::Gemini {
+            received: false,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn closed_output_is_an_io_error() {
+    struct Closed;
+    impl Write for Closed {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let events = stream::iter(vec![text("hello"), done("hello")]);
+    let error = print_stream(events, &mut Closed).await.unwrap_err();
+
+    assert!(matches!(error, PrintError::Io(e) if e.kind() == io::ErrorKind::BrokenPipe));
+}
+
+#[tokio::test]
+async fn print_stream_returns_the_whole_answer() {
+    let events = stream::iter(vec![text("a"), text("b\n"), done("ab\n")]);
+    let answer = print_stream(events, &mut Terminal::default())
+        .await
+        .unwrap();
+    assert_eq!(answer, "ab\n");
+}
+
+#[tokio::test]
+async fn collect_stream_buffers_everything() {
+    let events = stream::iter(vec![text("Rust "), text("is fast."), done("Rust is fast.")]);
+    assert_eq!(collect_stream(events).await.unwrap(), "Rust is fast.");
+
+    let events = stream::iter(vec![text("Rust "), Err(GeminiError::Timeout)]);
+    assert!(matches!(
+        collect_stream(events).await,
+        Err(GeminiError::Timeout)
+    ));
+}
@@ -0,0 +1,16 @@
+//! Library half of rust-docker-server: request parsing and connection handling, kept out of
+//! `main.rs` so tests can run them on their own sockets.
+
+pub mod pool;
+pub mod request;
+pub mod server;
+pub mod stats;
+
+pub use pool::WorkerPool;
+pub use request::{Request, RequestError, RequestReader, DEFAULT_MAX_HEADER_SIZE};
+pub use server::{
+    handle_connection, respond, route, serve, wants_keep_alive, Body, Response, Settings,
+    CHUNK_SIZE,
+};
+pub use static_serve_core::{parse_range, ByteRange, RangeOutcome};
+pub use stats::{ConnectionGuard, ServerStats};
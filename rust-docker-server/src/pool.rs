@@ -0,0 +1,94 @@
+// A fixed number of worker threads fed from a bounded queue of accepted connections.
+//
+// Spawning a thread per connection lets a flood of connections use up every thread the OS will
+// give us. Here the thread count is fixed, and so is the number of connections waiting for a
+// thread: once the queue is full, new connections are turned away straight away (with a 503)
+// instead of piling up.
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// Worker threads taking connections from a queue of limited size.
+pub struct WorkerPool {
+    // `Option` so dropping the pool can close the queue before joining the workers
+    sender: Option<SyncSender<TcpStream>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    /// Start `size` workers (at least one) that each call `handler` for every connection they
+    /// take off a queue holding at most `capacity` waiting connections.
+    pub fn new<F>(size: usize, capacity: usize, handler: F) -> WorkerPool
+    where
+        F: Fn(TcpStream) + Send + Sync + 'static,
+    {
+        // `sync_channel` is the bounded version of `channel`: `try_send` fails when it's full
+        let (sender, receiver) = mpsc::sync_channel::<TcpStream>(capacity);
+        // Only one thread may own a Receiver, so the workers share it behind a Mutex
+        let receiver = Arc::new(Mutex::new(receiver));
+        let handler = Arc::new(handler);
+
+        let workers = (0..size.max(1))
+            .map(|id| {
+                let receiver = Arc::clone(&receiver);
+                let handler = Arc::clone(&handler);
+                thread::Builder::new()
+                    .name(format!("worker-{}", id))
+                    .spawn(move || work(&receiver, &*handler))
+                    .expect("failed to spawn worker thread")
+            })
+            .collect();
+
+        WorkerPool {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    /// Number of worker threads
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Queue a connection for the next free worker. If the queue is full (or every worker has
+    /// died) the connection is handed back so the caller can turn it away.
+    pub fn dispatch(&self, stream: TcpStream) -> Result<(), TcpStream> {
+        let Some(sender) = &self.sender else {
+            return Err(stream);
+        };
+        match sender.try_send(stream) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(stream) | TrySendError::Disconnected(stream)) => Err(stream),
+        }
+    }
+}
+
+// Dropping the pool closes the queue, lets the workers finish the connections already queued,
+// and waits for them
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                eprintln!("a worker thread panicked");
+            }
+        }
+    }
+}
+
+// The body of each worker thread: handle connections until the queue is closed
+fn work(receiver: &Mutex<Receiver<TcpStream>>, handler: &(dyn Fn(TcpStream) + Send + Sync)) {
+    loop {
+        // The guard is dropped at the end of this statement, so the lock isn't held while the
+        // connection is handled
+        let next = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            Err(poisoned) => poisoned.into_inner().recv(),
+        };
+        match next {
+            Ok(stream) => handler(stream),
+            Err(_) => break,
+        }
+    }
+}
@@ -0,0 +1,163 @@
+// HTTP request parsing.
+//
+// TCP is a byte stream, not a message stream: one `read` may return half a request (a big
+// cookie header split across packets) or a request and a half. So bytes are collected until
+// the blank line (`\r\n\r\n`) that ends the headers, and only then parsed.
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read};
+
+/// Default limit on the request line plus headers, in bytes (the same as nginx's default buffer)
+pub const DEFAULT_MAX_HEADER_SIZE: usize = 8 * 1024;
+
+/// A parsed request line and headers. Requests this server handles have no body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    /// e.g. `GET`
+    pub method: String,
+    /// The request target as sent, e.g. `/css/site.css?v=2`
+    pub path: String,
+    /// e.g. `HTTP/1.1`
+    pub version: String,
+    /// Header names are lowercased, since HTTP header names are case-insensitive
+    pub headers: HashMap<String, String>,
+}
+
+impl Request {
+    /// Parse the head of a request: everything before the blank line, without it.
+    pub fn parse(head: &[u8]) -> Result<Request, RequestError> {
+        let head = std::str::from_utf8(head)
+            .map_err(|_| RequestError::Malformed("request is not valid UTF-8".to_string()))?;
+        let mut lines = head.split("\r\n");
+        let request_line = lines.next().unwrap_or("");
+
+        // Exactly three parts: `GET /path HTTP/1.1`
+        let parts: Vec<&str> = request_line.split(' ').collect();
+        let [method, path, version] = parts.as_slice() else {
+            return Err(RequestError::Malformed(format!(
+                "bad request line `{}`",
+                request_line
+            )));
+        };
+        if method.is_empty() || !method.bytes().all(|b| b.is_ascii_uppercase()) {
+            return Err(RequestError::Malformed(format!("bad method `{}`", method)));
+        }
+        if !path.starts_with('/') {
+            return Err(RequestError::Malformed(format!("bad path `{}`", path)));
+        }
+        if !version.starts_with("HTTP/") {
+            return Err(RequestError::Malformed(format!(
+                "bad version `{}`",
+                version
+            )));
+        }
+
+        let mut headers = HashMap::new();
+        for line in lines {
+            let Some((name, value)) = line.split_once(':') else {
+                return Err(RequestError::Malformed(format!("bad header `{}`", line)));
+            };
+            if name.is_empty() || name.contains(' ') {
+                return Err(RequestError::Malformed(format!("bad header `{}`", line)));
+            }
+            headers.insert(name.to_ascii_lowercase(), value.trim().to_string());
+        }
+
+        Ok(Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            version: version.to_string(),
+            headers,
+        })
+    }
+
+    /// A header's value, looked up case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+}
+
+/// Why no request could be read
+#[derive(Debug)]
+pub enum RequestError {
+    /// Reading from the connection failed
+    Io(io::Error),
+    /// The headers didn't end within the size limit: answer 431
+    TooLarge,
+    /// Not an HTTP request: answer 400
+    Malformed(String),
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::Io(error) => write!(f, "read failed: {}", error),
+            RequestError::TooLarge => write!(f, "request headers too large"),
+            RequestError::Malformed(reason) => write!(f, "malformed request: {}", reason),
+        }
+    }
+}
+
+// Lets `?` turn an io::Error into a RequestError
+impl From<io::Error> for RequestError {
+    fn from(error: io::Error) -> Self {
+        RequestError::Io(error)
+    }
+}
+
+/// Reads requests from a connection (or any `Read`), keeping bytes that arrive after the end
+/// of one request's headers for the next one.
+pub struct RequestReader<R> {
+    inner: R,
+    buffer: Vec<u8>,
+    max_header_size: usize,
+}
+
+impl<R: Read> RequestReader<R> {
+    /// Read from `inner`, rejecting requests whose head is longer than `max_header_size` bytes
+    pub fn new(inner: R, max_header_size: usize) -> RequestReader<R> {
+        RequestReader {
+            inner,
+            buffer: Vec::new(),
+            max_header_size,
+        }
+    }
+
+    /// The next request, or `None` if the client closed the connection before sending one.
+    pub fn next_request(&mut self) -> Result<Option<Request>, RequestError> {
+        let mut chunk = [0u8; 1024];
+        loop {
+            if let Some(end) = find_terminator(&self.buffer) {
+                if end > self.max_header_size {
+                    return Err(RequestError::TooLarge);
+                }
+                let request = Request::parse(&self.buffer[..end]);
+                // `drain` removes the head and its blank line, keeping anything after it
+                self.buffer.drain(..end + 4);
+                return request.map(Some);
+            }
+            // No terminator yet: more than the limit buffered means it will never fit
+            if self.buffer.len() > self.max_header_size + 3 {
+                return Err(RequestError::TooLarge);
+            }
+            let n = self.inner.read(&mut chunk)?;
+            if n == 0 {
+                return if self.buffer.is_empty() {
+                    Ok(None)
+                } else {
+                    Err(RequestError::Malformed(
+                        "connection closed mid-request".to_string(),
+                    ))
+                };
+            }
+            self.buffer.extend_from_slice(&chunk[..n]);
+        }
+    }
+}
+
+// Where the blank line ending the headers starts, if it has arrived
+fn find_terminator(bytes: &[u8]) -> Option<usize> {
+    bytes.windows(4).position(|window| window == b"\r\n\r\n")
+}
@@ -0,0 +1,399 @@
+// One client connection: read a request, write a response, and repeat while the client wants
+// to keep the connection open (HTTP keep-alive). Reusing the TCP connection saves a handshake
+// per request, which adds up on a page that loads ten assets.
+use std::fs::File;
+use std::io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use static_serve_core::{mime_for_path, parse_range, resolve_safe_path, PathError, RangeOutcome};
+
+use crate::{
+    Request, RequestError, RequestReader, ServerStats, WorkerPool, DEFAULT_MAX_HEADER_SIZE,
+};
+
+/// How connections are handled. Shared read-only by every connection thread.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    /// The directory files are served from
+    pub static_root: PathBuf,
+    /// `MAX_HEADER_SIZE`: bytes of request line + headers accepted before answering 431
+    pub max_header_size: usize,
+    /// `KEEP_ALIVE_TIMEOUT` (seconds): how long an idle connection is kept open
+    pub idle_timeout: Duration,
+    /// `MAX_KEEP_ALIVE_REQUESTS`: requests served on one connection before closing it
+    pub max_requests: usize,
+    /// `READ_TIMEOUT` (seconds): how long a new connection may take to send its first request,
+    /// and how long writing a response may stall, before the connection is dropped
+    pub read_timeout: Duration,
+    /// `WORKERS`: threads handling connections, default two per CPU
+    pub workers: usize,
+    /// `QUEUE_SIZE`: accepted connections that may wait for a free worker before new ones get 503
+    pub queue_size: usize,
+}
+
+impl Settings {
+    /// Serve `static_root` with the default limits
+    pub fn new(static_root: impl Into<PathBuf>) -> Settings {
+        Settings {
+            static_root: static_root.into(),
+            max_header_size: DEFAULT_MAX_HEADER_SIZE,
+            idle_timeout: Duration::from_secs(5),
+            max_requests: 100,
+            read_timeout: Duration::from_secs(10),
+            // Connections spend much of their time waiting on the network, so use more
+            // threads than cores
+            workers: std::thread::available_parallelism().map_or(1, |n| n.get()) * 2,
+            queue_size: 64,
+        }
+    }
+
+    /// `Settings::new` with limits overridden by environment variables, where set and valid
+    pub fn from_env(static_root: impl Into<PathBuf>) -> Settings {
+        let mut settings = Settings::new(static_root);
+        if let Some(size) = env_number("MAX_HEADER_SIZE") {
+            settings.max_header_size = size;
+        }
+        if let Some(secs) = env_number("KEEP_ALIVE_TIMEOUT") {
+            settings.idle_timeout = Duration::from_secs(secs as u64);
+        }
+        if let Some(max) = env_number("MAX_KEEP_ALIVE_REQUESTS") {
+            settings.max_requests = max.max(1);
+        }
+        if let Some(secs) = env_number("READ_TIMEOUT") {
+            settings.read_timeout = Duration::from_secs(secs as u64);
+        }
+        if let Some(workers) = env_number("WORKERS").filter(|&n| n > 0) {
+            settings.workers = workers;
+        }
+        if let Some(size) = env_number("QUEUE_SIZE") {
+            settings.queue_size = size;
+        }
+        settings
+    }
+}
+
+fn env_number(name: &str) -> Option<usize> {
+    std::env::var(name).ok().and_then(|v| v.trim().parse().ok())
+}
+
+/// Bytes read from a file and written to the socket at a time, so memory use per response
+/// stays the same whether the file is 1KB or 500MB
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// What follows a response's headers
+#[derive(Debug)]
+pub enum Body {
+    /// A small body built in memory: health checks and error messages
+    Bytes(Vec<u8>),
+    /// The next `len` bytes of an open file, streamed in `CHUNK_SIZE` pieces
+    File { file: File, len: u64 },
+}
+
+impl Body {
+    /// Length in bytes, for the Content-Length header
+    pub fn len(&self) -> u64 {
+        match self {
+            Body::Bytes(bytes) => bytes.len() as u64,
+            Body::File { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A response ready to be written: status line text, content type, extra headers and body.
+#[derive(Debug)]
+pub struct Response {
+    /// e.g. `200 OK`
+    pub status: &'static str,
+    pub content_type: &'static str,
+    /// Headers besides Content-Length, Content-Type and Connection, e.g. `Content-Range`
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Body,
+}
+
+impl Response {
+    /// A plain-text response whose body is the status text, e.g. `Not Found`
+    pub fn text(status: &'static str) -> Response {
+        // "404 Not Found" -> "Not Found"
+        let reason = status.split_once(' ').map_or(status, |(_, reason)| reason);
+        Response {
+            status,
+            content_type: "text/plain",
+            headers: Vec::new(),
+            body: Body::Bytes(reason.as_bytes().to_vec()),
+        }
+    }
+
+    /// The numeric status, e.g. 404 for `404 Not Found`
+    pub fn status_code(&self) -> u16 {
+        self.status
+            .split(' ')
+            .next()
+            .and_then(|code| code.parse().ok())
+            .unwrap_or(500)
+    }
+
+    /// Write the status line, headers and body. `Content-Length` is always sent: on a
+    /// kept-alive connection it is how the client knows where this response ends.
+    // `self` (not `&self`): writing a file body reads the file, which uses it up
+    pub fn write_to(self, out: &mut impl Write, keep_alive: bool) -> io::Result<()> {
+        let mut header = format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nContent-Type: {}\r\nConnection: {}\r\n",
+            self.status,
+            self.body.len(),
+            self.content_type,
+            if keep_alive { "keep-alive" } else { "close" }
+        );
+        for (name, value) in &self.headers {
+            header.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        header.push_str("\r\n");
+        out.write_all(header.as_bytes())?;
+
+        match self.body {
+            Body::Bytes(bytes) => out.write_all(&bytes)?,
+            Body::File { file, len } => {
+                // `take` stops reading after `len` bytes, so a ranged response ends where the
+                // range does even though the file goes on
+                let mut reader = BufReader::with_capacity(CHUNK_SIZE, file.take(len));
+                let mut chunk = vec![0u8; CHUNK_SIZE];
+                let mut sent = 0;
+                loop {
+                    let n = reader.read(&mut chunk)?;
+                    if n == 0 {
+                        break;
+                    }
+                    out.write_all(&chunk[..n])?;
+                    sent += n as u64;
+                }
+                // The file shrank after Content-Length was sent: the client would wait for
+                // bytes that never come, so end the connection with an error instead
+                if sent != len {
+                    return Err(io::Error::new(
+                        ErrorKind::UnexpectedEof,
+                        format!("file ended after {} of {} bytes", sent, len),
+                    ));
+                }
+            }
+        }
+        out.flush()
+    }
+}
+
+/// Accept connections on `listener` forever, handing each to the worker pool. Connections
+/// that arrive while the pool's queue is full are answered with 503 and closed.
+pub fn serve(listener: TcpListener, settings: Settings) {
+    let settings = Arc::new(settings);
+    let stats = Arc::new(ServerStats::new());
+    let shared = Arc::clone(&settings);
+    let counters = Arc::clone(&stats);
+    let pool = WorkerPool::new(settings.workers, settings.queue_size, move |stream| {
+        if let Err(e) = handle_connection(stream, &shared, &counters) {
+            eprintln!("connection error: {}", e);
+        }
+    });
+    println!(
+        "{} workers, up to {} queued connections",
+        pool.size(),
+        settings.queue_size
+    );
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(stream) = pool.dispatch(stream) {
+                    reject(stream, &stats);
+                }
+            }
+            Err(e) => eprintln!("accept error: {}", e),
+        }
+    }
+}
+
+// Turn a connection away with 503. This runs on the accept thread, so a client that doesn't
+// read its response may only hold it up briefly.
+fn reject(stream: TcpStream, stats: &ServerStats) {
+    let _ = stream.set_write_timeout(Some(Duration::from_millis(500)));
+    let response = Response::text("503 Service Unavailable");
+    if let Err(e) = send(response, &mut &stream, false, stats) {
+        eprintln!("could not send 503: {}", e);
+    }
+}
+
+// Write a response and count it in the stats once it has been sent
+fn send(
+    response: Response,
+    out: &mut impl Write,
+    keep_alive: bool,
+    stats: &ServerStats,
+) -> io::Result<()> {
+    let status = response.status_code();
+    let bytes = response.body.len();
+    response.write_to(out, keep_alive)?;
+    stats.record(status, bytes);
+    Ok(())
+}
+
+/// Serve every request the client sends on `stream` until it closes the connection, asks
+/// for it to be closed, goes idle for longer than the timeout, or reaches the request limit.
+pub fn handle_connection(
+    stream: TcpStream,
+    settings: &Settings,
+    stats: &ServerStats,
+) -> io::Result<()> {
+    // Counted as active until this function returns, whichever way it does
+    let _active = stats.connection();
+    // Blocked reads and writes give up after a while instead of tying up this worker forever,
+    // e.g. for a client that connected and then vanished without closing the socket
+    stream.set_read_timeout(Some(settings.read_timeout))?;
+    stream.set_write_timeout(Some(settings.read_timeout))?;
+    // `&TcpStream` implements both Read and Write, so the reader and the writer can share it
+    let mut reader = RequestReader::new(&stream, settings.max_header_size);
+    let mut out = &stream;
+    let mut served = 0;
+
+    loop {
+        let request = match reader.next_request() {
+            Ok(Some(request)) => request,
+            // The client closed the connection between requests
+            Ok(None) => return Ok(()),
+            // Silent for too long: close quietly, the client will reconnect if it needs to
+            Err(RequestError::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                return Ok(())
+            }
+            Err(RequestError::Io(e)) => return Err(e),
+            Err(RequestError::TooLarge) => {
+                let response = Response::text("431 Request Header Fields Too Large");
+                return send(response, &mut out, false, stats);
+            }
+            Err(RequestError::Malformed(reason)) => {
+                println!("bad request: {}", reason);
+                return send(Response::text("400 Bad Request"), &mut out, false, stats);
+            }
+        };
+        served += 1;
+        println!("{} {}", request.method, request.path);
+
+        let keep_alive = wants_keep_alive(&request) && served < settings.max_requests;
+        let response = route(&request, settings, stats);
+        send(response, &mut out, keep_alive, stats)?;
+        if !keep_alive {
+            return Ok(());
+        }
+        // From now on the connection is idle between requests
+        stream.set_read_timeout(Some(settings.idle_timeout))?;
+    }
+}
+
+/// Whether the client asked to reuse the connection. HTTP/1.1 keeps connections open unless
+/// told `Connection: close`; HTTP/1.0 closes them unless told `Connection: keep-alive`.
+pub fn wants_keep_alive(request: &Request) -> bool {
+    // The header is a comma-separated list of options, e.g. `keep-alive, Upgrade`
+    let has_option = |option: &str| {
+        request.header("connection").is_some_and(|value| {
+            value
+                .split(',')
+                .any(|v| v.trim().eq_ignore_ascii_case(option))
+        })
+    };
+    // A request body would have to be skipped to find the next request; this server doesn't
+    // expect bodies, so it closes the connection after one rather than misreading it
+    let has_body = request
+        .header("content-length")
+        .is_some_and(|len| len.trim() != "0")
+        || request.header("transfer-encoding").is_some();
+    if has_body {
+        return false;
+    }
+    if request.version == "HTTP/1.0" {
+        has_option("keep-alive")
+    } else {
+        !has_option("close")
+    }
+}
+
+/// The response for one request. `/health` and `/metrics` are answered from the stats and
+/// never touch the static files; every other path goes to `respond`.
+pub fn route(request: &Request, settings: &Settings, stats: &ServerStats) -> Response {
+    match request.path.as_str() {
+        "/health" => {
+            let (healthy, json) = stats.health(&settings.static_root);
+            Response {
+                // 503 makes an orchestrator's probe fail, so the container gets restarted
+                status: if healthy {
+                    "200 OK"
+                } else {
+                    "503 Service Unavailable"
+                },
+                content_type: "application/json",
+                headers: Vec::new(),
+                body: Body::Bytes(json.into_bytes()),
+            }
+        }
+        "/metrics" => Response {
+            status: "200 OK",
+            content_type: "text/plain; version=0.0.4",
+            headers: Vec::new(),
+            body: Body::Bytes(stats.metrics().into_bytes()),
+        },
+        _ => respond(request, &settings.static_root),
+    }
+}
+
+/// The response for a static file request: a file (or part of one) from `static_root`, or an
+/// error.
+pub fn respond(request: &Request, static_root: &Path) -> Response {
+    // Percent-decoded and normalized by the shared crate; encoded `..` (`%2e%2e`) is refused
+    let mut file_path = match resolve_safe_path(static_root, &request.path) {
+        Ok(path) => path,
+        Err(PathError::Traversal) => return Response::text("403 Forbidden"),
+        Err(PathError::Malformed) => return Response::text("400 Bad Request"),
+    };
+    // A directory (including `/`) is served by its index.html
+    if file_path.is_dir() {
+        file_path.push("index.html");
+    }
+    if !file_path.is_file() {
+        return Response::text("404 Not Found");
+    }
+    // Only the length is needed up front; the contents are read while they're being sent
+    let opened = File::open(&file_path).and_then(|file| {
+        let len = file.metadata()?.len();
+        Ok((file, len))
+    });
+    let Ok((mut file, file_len)) = opened else {
+        return Response::text("500 Internal Server Error");
+    };
+    let mut headers = vec![("Accept-Ranges", "bytes".to_string())];
+    let (status, len) = match parse_range(request.header("range"), file_len) {
+        RangeOutcome::Full => ("200 OK", file_len),
+        RangeOutcome::Partial(range) => {
+            if file.seek(SeekFrom::Start(range.start)).is_err() {
+                return Response::text("500 Internal Server Error");
+            }
+            headers.push(("Content-Range", range.content_range(file_len)));
+            ("206 Partial Content", range.size())
+        }
+        RangeOutcome::Unsatisfiable => {
+            let mut response = Response::text("416 Range Not Satisfiable");
+            response
+                .headers
+                .push(("Content-Range", format!("bytes */{}", file_len)));
+            return response;
+        }
+    };
+    Response {
+        status,
+        content_type: mime_for_path(&file_path),
+        headers,
+        body: Body::File { file, len },
+    }
+}
@@ -0,0 +1,152 @@
+// Counters behind `/metrics`, and the checks behind `/health`.
+//
+// Every worker thread updates the same counters. Atomics let them do that without a lock:
+// `fetch_add` is a single indivisible "read, add, write", like Java's AtomicLong.incrementAndGet.
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Server-wide counters, shared by all connection threads behind an `Arc`.
+#[derive(Debug)]
+pub struct ServerStats {
+    started: Instant,
+    // Responses by status class: index 0 is 1xx, 1 is 2xx, ... 4 is 5xx
+    responses: [AtomicU64; 5],
+    active_connections: AtomicU64,
+    bytes_served: AtomicU64,
+}
+
+impl Default for ServerStats {
+    fn default() -> Self {
+        ServerStats::new()
+    }
+}
+
+impl ServerStats {
+    /// Fresh counters; uptime counts from now
+    pub fn new() -> ServerStats {
+        ServerStats {
+            started: Instant::now(),
+            responses: Default::default(),
+            active_connections: AtomicU64::new(0),
+            bytes_served: AtomicU64::new(0),
+        }
+    }
+
+    /// Count one response with this status code and body size
+    pub fn record(&self, status: u16, body_bytes: u64) {
+        // Relaxed ordering: each counter only needs to be right on its own, it doesn't order
+        // any other memory accesses
+        if let Some(counter) = (status / 100)
+            .checked_sub(1)
+            .and_then(|class| self.responses.get(class as usize))
+        {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+        self.bytes_served.fetch_add(body_bytes, Ordering::Relaxed);
+    }
+
+    /// Count a connection as active until the returned guard is dropped
+    pub fn connection(&self) -> ConnectionGuard<'_> {
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+        ConnectionGuard { stats: self }
+    }
+
+    /// Responses sent so far with a status in `class`xx, e.g. `responses(4)` for 4xx
+    pub fn responses(&self, class: u16) -> u64 {
+        (class as usize)
+            .checked_sub(1)
+            .and_then(|i| self.responses.get(i))
+            .map_or(0, |counter| counter.load(Ordering::Relaxed))
+    }
+
+    /// Connections currently open
+    pub fn active_connections(&self) -> u64 {
+        self.active_connections.load(Ordering::Relaxed)
+    }
+
+    /// Body bytes written to clients so far
+    pub fn bytes_served(&self) -> u64 {
+        self.bytes_served.load(Ordering::Relaxed)
+    }
+
+    /// Whole seconds since the server started
+    pub fn uptime_seconds(&self) -> u64 {
+        self.started.elapsed().as_secs()
+    }
+
+    /// The `/metrics` page, in the Prometheus text format so standard scrapers can read it
+    pub fn metrics(&self) -> String {
+        let mut out = String::from(
+            "# HELP http_responses_total Responses sent, by status class.\n# TYPE http_responses_total counter\n",
+        );
+        for class in 1..=5 {
+            out.push_str(&format!(
+                "http_responses_total{{class=\"{}xx\"}} {}\n",
+                class,
+                self.responses(class)
+            ));
+        }
+        out.push_str(&format!(
+            "# HELP http_active_connections Connections currently open.\n# TYPE http_active_connections gauge\nhttp_active_connections {}\n",
+            self.active_connections()
+        ));
+        out.push_str(&format!(
+            "# HELP http_response_bytes_total Response body bytes sent.\n# TYPE http_response_bytes_total counter\nhttp_response_bytes_total {}\n",
+            self.bytes_served()
+        ));
+        out.push_str(&format!(
+            "# HELP process_uptime_seconds Seconds since the server started.\n# TYPE process_uptime_seconds gauge\nprocess_uptime_seconds {}\n",
+            self.uptime_seconds()
+        ));
+        out
+    }
+
+    /// The `/health` report as JSON, and whether the server is healthy: the static root must
+    /// be an existing directory.
+    pub fn health(&self, static_root: &Path) -> (bool, String) {
+        let metadata = fs::metadata(static_root).ok();
+        let exists = metadata.as_ref().is_some_and(|m| m.is_dir());
+        // Only the permission bits are checked; nothing is written to the directory
+        let writable = metadata.is_some_and(|m| !m.permissions().readonly());
+        let json = format!(
+            "{{\"status\":\"{}\",\"uptime_seconds\":{},\"static_root\":{},\"root_exists\":{},\"root_writable\":{}}}",
+            if exists { "ok" } else { "unavailable" },
+            self.uptime_seconds(),
+            json_string(&static_root.display().to_string()),
+            exists,
+            writable
+        );
+        (exists, json)
+    }
+}
+
+/// Marks a connection as active for as long as it lives. Dropping it (however the connection
+/// handler returns, even on an error) decrements the count, like a `finally` block.
+pub struct ConnectionGuard<'a> {
+    stats: &'a ServerStats,
+}
+
+impl Drop for ConnectionGuard<'_> {
+    fn drop(&mut self) {
+        self.stats
+            .active_connections
+            .fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+// A JSON string literal: the path may contain quotes or backslashes (Windows)
+fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -16,7 +16,7 @@ fn spawn_server() -> Child {
 fn wait_for_listen(timeout: Duration) -> bool {
     let start = Instant::now();
     while start.elapsed() < timeout {
-        if let Ok(mut stream) = TcpStream::connect("127.0.0.1:8080") {
+        if let Ok(stream) = TcpStream::connect("127.0.0.1:8080") {
             let _ = stream.shutdown(std::net::Shutdown::Both);
             return true;
         }
@@ -29,7 +29,10 @@ fn wait_for_listen(timeout: Duration) -> bool {
 fn health_endpoint_returns_ok() {
     let mut child = spawn_server();
     // wait up to 5s for server to start
-    assert!(wait_for_listen(Duration::from_secs(5)), "server did not start in time");
+    assert!(
+        wait_for_listen(Duration::from_secs(5)),
+        "server did not start in time"
+    );
 
     // Connect and request /health
     let mut stream = TcpStream::connect("127.0.0.1:8080").expect("connect failed");
@@ -0,0 +1,207 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rust_docker_server::{handle_connection, wants_keep_alive, Request, ServerStats, Settings};
+use static_serve_core::test_support::TempSite;
+
+const FILES: &[(&str, &str)] = &[
+    ("index.html", "<h1>home</h1>"),
+    ("app.js", "console.log(1);"),
+];
+
+/// Accept connections on an ephemeral port, each handled on its own thread
+fn start(settings: Settings) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let settings = Arc::new(settings);
+    let stats = Arc::new(ServerStats::new());
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let settings = Arc::clone(&settings);
+            let stats = Arc::clone(&stats);
+            thread::spawn(move || handle_connection(stream, &settings, &stats));
+        }
+    });
+    addr
+}
+
+struct Reply {
+    status: String,
+    connection: String,
+    body: String,
+}
+
+/// Read exactly one response, using Content-Length to find its end
+fn read_reply(reader: &mut BufReader<TcpStream>) -> Reply {
+    let mut status = String::new();
+    reader.read_line(&mut status).unwrap();
+    let mut length = 0;
+    let mut connection = String::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(": ").unwrap();
+        match name {
+            "Content-Length" => length = value.parse().unwrap(),
+            "Connection" => connection = value.to_string(),
+            _ => {}
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    Reply {
+        status: status.trim_end().to_string(),
+        connection,
+        body: String::from_utf8(body).unwrap(),
+    }
+}
+
+fn connect(addr: SocketAddr) -> (TcpStream, BufReader<TcpStream>) {
+    let stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let reader = BufReader::new(stream.try_clone().unwrap());
+    (stream, reader)
+}
+
+/// True once the server has closed its side of the connection
+fn closed(reader: &mut BufReader<TcpStream>) -> bool {
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap() == 0
+}
+
+#[test]
+fn sequential_requests_share_one_connection() {
+    let root = TempSite::with_files("sequential", FILES);
+    let addr = start(Settings::new(root.path()));
+    let (mut stream, mut reader) = connect(addr);
+
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let first = read_reply(&mut reader);
+    assert_eq!(first.status, "HTTP/1.1 200 OK");
+    assert_eq!(first.connection, "keep-alive");
+    assert_eq!(first.body, "<h1>home</h1>");
+
+    stream
+        .write_all(b"GET /app.js HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .unwrap();
+    let second = read_reply(&mut reader);
+    assert_eq!(second.status, "HTTP/1.1 200 OK");
+    assert_eq!(second.body, "console.log(1);");
+}
+
+#[test]
+fn pipelined_requests_get_responses_in_order() {
+    let root = TempSite::with_files("pipelined", FILES);
+    let addr = start(Settings::new(root.path()));
+    let (mut stream, mut reader) = connect(addr);
+
+    stream
+        .write_all(
+            b"GET /app.js HTTP/1.1\r\nHost: x\r\n\r\nGET /missing HTTP/1.1\r\nHost: x\r\n\r\nGET / HTTP/1.1\r\nHost: x\r\nConnection: close\r\n\r\n",
+        )
+        .unwrap();
+    assert_eq!(read_reply(&mut reader).body, "console.log(1);");
+    let missing = read_reply(&mut reader);
+    assert_eq!(missing.status, "HTTP/1.1 404 Not Found");
+    assert_eq!(missing.connection, "keep-alive");
+    let last = read_reply(&mut reader);
+    assert_eq!(last.body, "<h1>home</h1>");
+    assert_eq!(last.connection, "close");
+    assert!(closed(&mut reader));
+}
+
+#[test]
+fn connection_close_is_honoured() {
+    let root = TempSite::with_files("close", FILES);
+    let addr = start(Settings::new(root.path()));
+    let (mut stream, mut reader) = connect(addr);
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    assert_eq!(read_reply(&mut reader).connection, "close");
+    assert!(closed(&mut reader));
+}
+
+#[test]
+fn http_1_0_closes_unless_asked_to_keep_alive() {
+    let root = TempSite::with_files("http10", FILES);
+    let addr = start(Settings::new(root.path()));
+
+    let (mut stream, mut reader) = connect(addr);
+    stream.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+    assert_eq!(read_reply(&mut reader).connection, "close");
+    assert!(closed(&mut reader));
+
+    let (mut stream, mut reader) = connect(addr);
+    stream
+        .write_all(b"GET / HTTP/1.0\r\nConnection: Keep-Alive\r\n\r\n")
+        .unwrap();
+    assert_eq!(read_reply(&mut reader).connection, "keep-alive");
+    stream.write_all(b"GET /app.js HTTP/1.0\r\n\r\n").unwrap();
+    assert_eq!(read_reply(&mut reader).connection, "close");
+    assert!(closed(&mut reader));
+}
+
+#[test]
+fn connection_closes_after_the_request_limit() {
+    let root = TempSite::with_files("limit", FILES);
+    let mut settings = Settings::new(root.path());
+    settings.max_requests = 2;
+    let addr = start(settings);
+    let (mut stream, mut reader) = connect(addr);
+    stream
+        .write_all(b"GET / HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\n\r\nGET / HTTP/1.1\r\n\r\n")
+        .unwrap();
+    assert_eq!(read_reply(&mut reader).connection, "keep-alive");
+    assert_eq!(read_reply(&mut reader).connection, "close");
+    assert!(closed(&mut reader));
+}
+
+#[test]
+fn idle_connections_are_closed_after_the_timeout() {
+    let root = TempSite::with_files("idle", FILES);
+    let mut settings = Settings::new(root.path());
+    settings.idle_timeout = Duration::from_millis(200);
+    let addr = start(settings);
+    let (mut stream, mut reader) = connect(addr);
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+    assert_eq!(read_reply(&mut reader).connection, "keep-alive");
+
+    let started = Instant::now();
+    assert!(closed(&mut reader));
+    assert!(started.elapsed() < Duration::from_secs(4));
+}
+
+#[test]
+fn keep_alive_decision() {
+    let request = |raw: &str| Request::parse(raw.as_bytes()).unwrap();
+    assert!(wants_keep_alive(&request("GET / HTTP/1.1")));
+    assert!(!wants_keep_alive(&request(
+        "GET / HTTP/1.1\r\nConnection: close"
+    )));
+    assert!(!wants_keep_alive(&request(
+        "GET / HTTP/1.1\r\nConnection: Upgrade, Close"
+    )));
+    assert!(!wants_keep_alive(&request("GET / HTTP/1.0")));
+    assert!(wants_keep_alive(&request(
+        "GET / HTTP/1.0\r\nConnection: keep-alive"
+    )));
+    // A body this server won't read would be mistaken for the next request
+    assert!(!wants_keep_alive(&request(
+        "POST / HTTP/1.1\r\nContent-Length: 5"
+    )));
+    assert!(wants_keep_alive(&request(
+        "POST / HTTP/1.1\r\nContent-Length: 0"
+    )));
+}
@@ -0,0 +1,115 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+
+use rust_docker_server::{serve, ServerStats, Settings};
+use static_serve_core::test_support::TempSite;
+
+const FILES: &[(&str, &str)] = &[
+    ("index.html", "<h1>home</h1>"),
+    // Files with the endpoints' names must not shadow them
+    ("health", "file"),
+    ("metrics", "file"),
+];
+
+fn start(settings: Settings) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || serve(listener, settings));
+    addr
+}
+
+/// GET `path` on a fresh connection; returns the status line and the body
+fn get(addr: SocketAddr, path: &str) -> (String, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "GET {} HTTP/1.1\r\nConnection: close\r\n\r\n", path).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.lines().next().unwrap().to_string();
+    (status, body.to_string())
+}
+
+/// The value of an unlabelled or labelled metric line, e.g. `http_active_connections`
+fn metric(page: &str, name: &str) -> u64 {
+    page.lines()
+        .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+        .unwrap_or_else(|| panic!("no {} in\n{}", name, page))
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn metrics_count_requests_and_bytes() {
+    let root = TempSite::with_files("counts", FILES);
+    let addr = start(Settings::new(root.path()));
+    let (_, before) = get(addr, "/metrics");
+    assert_eq!(metric(&before, "http_responses_total{class=\"2xx\"}"), 0);
+    assert_eq!(metric(&before, "http_response_bytes_total"), 0);
+
+    assert_eq!(get(addr, "/").0, "HTTP/1.1 200 OK");
+    assert_eq!(get(addr, "/index.html").0, "HTTP/1.1 200 OK");
+    assert_eq!(get(addr, "/missing.css").0, "HTTP/1.1 404 Not Found");
+    assert_eq!(get(addr, "/../secret").0, "HTTP/1.1 403 Forbidden");
+
+    let (status, after) = get(addr, "/metrics");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    // The first /metrics response counts as a 2xx too
+    assert_eq!(metric(&after, "http_responses_total{class=\"2xx\"}"), 3);
+    assert_eq!(metric(&after, "http_responses_total{class=\"4xx\"}"), 2);
+    assert_eq!(metric(&after, "http_responses_total{class=\"5xx\"}"), 0);
+    // This connection is the one open right now
+    assert_eq!(metric(&after, "http_active_connections"), 1);
+    let bytes = metric(&after, "http_response_bytes_total");
+    let expected = before.len() + 2 * "<h1>home</h1>".len() + "Not Found".len() + "Forbidden".len();
+    assert_eq!(bytes, expected as u64);
+}
+
+#[test]
+fn health_reports_the_root() {
+    let root = TempSite::with_files("health", FILES);
+    let addr = start(Settings::new(root.path()));
+    let (status, body) = get(addr, "/health");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(
+        body.starts_with("{\"status\":\"ok\",\"uptime_seconds\":"),
+        "{}",
+        body
+    );
+    assert!(body.contains(&format!("\"static_root\":\"{}\"", root.display())));
+    assert!(body.contains("\"root_exists\":true"));
+    assert!(body.contains("\"root_writable\":true"));
+}
+
+#[test]
+fn health_fails_when_the_root_is_missing() {
+    let root = std::env::temp_dir().join(format!("docker-metrics-{}-gone", std::process::id()));
+    let addr = start(Settings::new(&root));
+    let (status, body) = get(addr, "/health");
+    assert_eq!(status, "HTTP/1.1 503 Service Unavailable");
+    assert!(body.contains("\"status\":\"unavailable\""));
+    assert!(body.contains("\"root_exists\":false"));
+}
+
+#[test]
+fn stats_track_classes_and_connections() {
+    let stats = ServerStats::new();
+    stats.record(200, 10);
+    stats.record(206, 5);
+    stats.record(304, 0);
+    stats.record(503, 19);
+    stats.record(999, 0);
+    assert_eq!(stats.responses(2), 2);
+    assert_eq!(stats.responses(3), 1);
+    assert_eq!(stats.responses(4), 0);
+    assert_eq!(stats.responses(5), 1);
+    assert_eq!(stats.bytes_served(), 34);
+
+    let first = stats.connection();
+    let second = stats.connection();
+    assert_eq!(stats.active_connections(), 2);
+    drop(first);
+    assert_eq!(stats.active_connections(), 1);
+    drop(second);
+    assert_eq!(stats.active_connections(), 0);
+}
@@ -0,0 +1,58 @@
+use std::path::Path;
+
+use rust_docker_server::{respond, Request, Response};
+use static_serve_core::test_support::TempSite;
+
+const FILES: &[(&str, &str)] = &[
+    ("index.html", "<h1>home</h1>"),
+    ("docs/index.html", "<h1>docs</h1>"),
+    ("my file.txt", "spaced"),
+    ("logo.svg", "<svg/>"),
+];
+
+fn get(root: &Path, path: &str) -> Response {
+    let request = Request::parse(format!("GET {} HTTP/1.1", path).as_bytes()).unwrap();
+    respond(&request, root)
+}
+
+#[test]
+fn traversal_is_refused_in_any_spelling() {
+    let root = TempSite::with_files("traversal", FILES);
+    for path in [
+        "/../secret",
+        "/%2e%2e/secret",
+        "/docs/%2E%2E/%2e%2e/secret",
+        "/..%5csecret",
+    ] {
+        assert_eq!(get(&root, path).status, "403 Forbidden", "{}", path);
+    }
+    assert_eq!(get(&root, "/%zz").status, "400 Bad Request");
+}
+
+#[test]
+fn encoded_names_and_directories_resolve() {
+    let root = TempSite::with_files("resolve", FILES);
+    let spaced = get(&root, "/my%20file.txt");
+    assert_eq!(spaced.status, "200 OK");
+    assert_eq!(spaced.content_type, "text/plain");
+    assert_eq!(spaced.body.len(), 6);
+
+    assert_eq!(get(&root, "/").body.len(), "<h1>home</h1>".len() as u64);
+    assert_eq!(
+        get(&root, "/docs/").body.len(),
+        "<h1>docs</h1>".len() as u64
+    );
+    assert_eq!(get(&root, "/docs").content_type, "text/html");
+    assert_eq!(get(&root, "/index.html?v=2").status, "200 OK");
+}
+
+#[test]
+fn content_types_come_from_the_shared_table() {
+    let root = TempSite::with_files("mime", FILES);
+    assert_eq!(get(&root, "/logo.svg").content_type, "image/svg+xml");
+    root.write("LICENSE", "MIT");
+    assert_eq!(
+        get(&root, "/LICENSE").content_type,
+        "application/octet-stream"
+    );
+}
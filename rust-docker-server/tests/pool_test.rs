@@ -0,0 +1,105 @@
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use rust_docker_server::{serve, Settings, WorkerPool};
+use static_serve_core::test_support::TempSite;
+
+const FILES: &[(&str, &str)] = &[("index.html", "<h1>home</h1>")];
+
+/// Run `serve` on an ephemeral port in the background
+fn start(settings: Settings) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || serve(listener, settings));
+    addr
+}
+
+fn connect(addr: SocketAddr) -> TcpStream {
+    let stream = TcpStream::connect(addr).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    stream
+}
+
+/// Send one request on `stream` and read the response until the server closes
+fn get(mut stream: TcpStream) -> String {
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    read_all(stream)
+}
+
+fn read_all(mut stream: TcpStream) -> String {
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn full_queue_gets_503() {
+    let root = TempSite::with_files("full", FILES);
+    let mut settings = Settings::new(root.path());
+    settings.workers = 1;
+    settings.queue_size = 1;
+    let addr = start(settings);
+
+    // The only worker picks this connection up and waits for its request...
+    let busy = connect(addr);
+    thread::sleep(Duration::from_millis(300));
+    // ...this one takes the only queue slot...
+    let queued = connect(addr);
+    thread::sleep(Duration::from_millis(100));
+    // ...so this one is turned away immediately, without sending anything
+    let rejected = read_all(connect(addr));
+    assert!(
+        rejected.starts_with("HTTP/1.1 503 Service Unavailable\r\n"),
+        "{}",
+        rejected
+    );
+    assert!(rejected.contains("Connection: close\r\n"));
+
+    // The accepted connections are still served, in turn
+    assert!(get(busy).starts_with("HTTP/1.1 200 OK"));
+    assert!(get(queued).starts_with("HTTP/1.1 200 OK"));
+}
+
+#[test]
+fn normal_load_is_served_by_the_pool() {
+    let root = TempSite::with_files("load", FILES);
+    let mut settings = Settings::new(root.path());
+    settings.workers = 4;
+    let addr = start(settings);
+
+    let clients: Vec<_> = (0..20)
+        .map(|_| thread::spawn(move || get(connect(addr))))
+        .collect();
+    for client in clients {
+        let response = client.join().unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.ends_with("<h1>home</h1>"));
+    }
+}
+
+#[test]
+fn silent_connections_are_dropped_after_the_read_timeout() {
+    let root = TempSite::with_files("silent", FILES);
+    let mut settings = Settings::new(root.path());
+    settings.workers = 1;
+    settings.read_timeout = Duration::from_millis(200);
+    let addr = start(settings);
+
+    // Never sends a request: the worker gives up on it and closes the connection...
+    let silent = connect(addr);
+    assert_eq!(read_all(silent), "");
+    // ...and is free for the next client
+    assert!(get(connect(addr)).starts_with("HTTP/1.1 200 OK"));
+}
+
+#[test]
+fn pool_has_at_least_one_worker() {
+    assert_eq!(WorkerPool::new(0, 1, drop).size(), 1);
+    assert_eq!(WorkerPool::new(3, 1, drop).size(), 3);
+}
@@ -0,0 +1,110 @@
+use std::io::{self, Read};
+
+use rust_docker_server::{Request, RequestError, RequestReader};
+
+/// A reader that hands out one byte per `read`, like a very slow network
+struct ByteByByte<'a>(&'a [u8]);
+
+impl Read for ByteByByte<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.split_first() {
+            Some((&byte, rest)) if !buf.is_empty() => {
+                buf[0] = byte;
+                self.0 = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+const REQUEST: &[u8] =
+    b"GET /css/site.css HTTP/1.1\r\nHost: localhost\r\nCookie: a=1; b=2\r\nX-Empty:\r\n\r\n";
+
+#[test]
+fn parses_request_fed_byte_by_byte() {
+    let mut reader = RequestReader::new(ByteByByte(REQUEST), 8192);
+    let request = reader.next_request().unwrap().unwrap();
+    assert_eq!(request.method, "GET");
+    assert_eq!(request.path, "/css/site.css");
+    assert_eq!(request.version, "HTTP/1.1");
+    assert_eq!(request.header("host"), Some("localhost"));
+    assert_eq!(request.header("COOKIE"), Some("a=1; b=2"));
+    assert_eq!(request.header("x-empty"), Some(""));
+    assert_eq!(request.headers.len(), 3);
+    // The stream is exhausted: the client is done
+    assert!(reader.next_request().unwrap().is_none());
+}
+
+#[test]
+fn keeps_bytes_after_the_headers_for_the_next_request() {
+    let two = b"GET /a HTTP/1.1\r\n\r\nGET /b HTTP/1.1\r\nHost: x\r\n\r\n";
+    let mut reader = RequestReader::new(&two[..], 8192);
+    assert_eq!(reader.next_request().unwrap().unwrap().path, "/a");
+    assert_eq!(reader.next_request().unwrap().unwrap().path, "/b");
+    assert!(reader.next_request().unwrap().is_none());
+}
+
+#[test]
+fn large_cookie_within_the_limit_is_read_whole() {
+    let cookie = "c".repeat(5000);
+    let raw = format!("GET / HTTP/1.1\r\nCookie: {}\r\n\r\n", cookie);
+    let mut reader = RequestReader::new(ByteByByte(raw.as_bytes()), 8192);
+    let request = reader.next_request().unwrap().unwrap();
+    assert_eq!(request.header("cookie"), Some(cookie.as_str()));
+}
+
+#[test]
+fn oversized_headers_are_rejected() {
+    let raw = format!("GET / HTTP/1.1\r\nCookie: {}\r\n\r\n", "c".repeat(10_000));
+    let mut reader = RequestReader::new(raw.as_bytes(), 8192);
+    assert!(matches!(reader.next_request(), Err(RequestError::TooLarge)));
+
+    // Also when the terminator never comes: the reader gives up instead of buffering forever
+    let endless = "X-Filler: y\r\n".repeat(1000);
+    let raw = format!("GET / HTTP/1.1\r\n{}", endless);
+    let mut reader = RequestReader::new(ByteByByte(raw.as_bytes()), 1024);
+    assert!(matches!(reader.next_request(), Err(RequestError::TooLarge)));
+}
+
+#[test]
+fn head_exactly_at_the_limit_is_accepted() {
+    let head = b"GET /abc HTTP/1.1";
+    let mut raw = head.to_vec();
+    raw.extend_from_slice(b"\r\n\r\n");
+    let mut reader = RequestReader::new(&raw[..], head.len());
+    assert!(reader.next_request().unwrap().is_some());
+    let mut reader = RequestReader::new(&raw[..], head.len() - 1);
+    assert!(matches!(reader.next_request(), Err(RequestError::TooLarge)));
+}
+
+#[test]
+fn malformed_request_lines_are_errors() {
+    for bad in [
+        &b""[..],
+        b"GET\r\n\r\n",
+        b"GET /\r\n\r\n",
+        b"GET / HTTP/1.1 extra\r\n\r\n",
+        b"get / HTTP/1.1\r\n\r\n",
+        b"GET index.html HTTP/1.1\r\n\r\n",
+        b"GET / FTP/1.0\r\n\r\n",
+        b"GET / HTTP/1.1\r\nno colon here\r\n\r\n",
+        b"\xff\xfe / HTTP/1.1\r\n\r\n",
+    ] {
+        let head = &bad[..bad.len().saturating_sub(4)];
+        assert!(
+            matches!(Request::parse(head), Err(RequestError::Malformed(_))),
+            "accepted {:?}",
+            String::from_utf8_lossy(bad)
+        );
+    }
+}
+
+#[test]
+fn connection_closed_mid_request_is_malformed() {
+    let mut reader = RequestReader::new(&b"GET / HTTP/1.1\r\nHost: x"[..], 8192);
+    assert!(matches!(
+        reader.next_request(),
+        Err(RequestError::Malformed(_))
+    ));
+}
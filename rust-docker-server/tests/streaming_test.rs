@@ -0,0 +1,130 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+
+use rust_docker_server::{respond, serve, Request, Settings, CHUNK_SIZE};
+use static_serve_core::test_support::TempSite;
+
+const SIZE: usize = 5 * 1024 * 1024 + 123;
+
+/// A multi-megabyte file whose bytes don't repeat in a simple pattern
+fn big_file() -> Vec<u8> {
+    let mut state: u32 = 12345;
+    (0..SIZE)
+        .map(|_| {
+            // A linear congruential generator: cheap, deterministic "random" bytes
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) as u8
+        })
+        .collect()
+}
+
+/// FNV-1a, a simple 64-bit checksum
+fn checksum(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn start(settings: Settings) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || serve(listener, settings));
+    addr
+}
+
+/// Send `request` and return the status line, headers and body
+fn fetch(addr: SocketAddr, request: &str) -> (String, Vec<String>, Vec<u8>) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut status = String::new();
+    reader.read_line(&mut status).unwrap();
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        if line.trim_end().is_empty() {
+            break;
+        }
+        headers.push(line.trim_end().to_string());
+    }
+    let mut body = Vec::new();
+    reader.read_to_end(&mut body).unwrap();
+    (status.trim_end().to_string(), headers, body)
+}
+
+#[test]
+fn serves_a_large_file_intact() {
+    let data = big_file();
+    let root = TempSite::with_files("full", &[("big.bin", &data)]);
+    let addr = start(Settings::new(root.path()));
+    let (status, headers, body) = fetch(addr, "GET /big.bin HTTP/1.1\r\nConnection: close\r\n\r\n");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert!(headers.contains(&format!("Content-Length: {}", SIZE)));
+    assert!(headers.contains(&"Accept-Ranges: bytes".to_string()));
+    assert_eq!(body.len(), SIZE);
+    assert_eq!(checksum(&body), checksum(&data));
+}
+
+#[test]
+fn serves_a_range_of_a_large_file() {
+    let data = big_file();
+    let root = TempSite::with_files("range", &[("big.bin", &data)]);
+    let addr = start(Settings::new(root.path()));
+    let (status, headers, body) = fetch(
+        addr,
+        "GET /big.bin HTTP/1.1\r\nRange: bytes=1000000-3999999\r\nConnection: close\r\n\r\n",
+    );
+    assert_eq!(status, "HTTP/1.1 206 Partial Content");
+    assert!(headers.contains(&"Content-Length: 3000000".to_string()));
+    assert!(headers.contains(&format!("Content-Range: bytes 1000000-3999999/{}", SIZE)));
+    assert_eq!(checksum(&body), checksum(&data[1_000_000..4_000_000]));
+
+    let (status, _, body) = fetch(
+        addr,
+        "GET /big.bin HTTP/1.1\r\nRange: bytes=-100\r\nConnection: close\r\n\r\n",
+    );
+    assert_eq!(status, "HTTP/1.1 206 Partial Content");
+    assert_eq!(body, &data[SIZE - 100..]);
+
+    let (status, headers, _) = fetch(
+        addr,
+        &format!(
+            "GET /big.bin HTTP/1.1\r\nRange: bytes={}-\r\nConnection: close\r\n\r\n",
+            SIZE
+        ),
+    );
+    assert_eq!(status, "HTTP/1.1 416 Range Not Satisfiable");
+    assert!(headers.contains(&format!("Content-Range: bytes */{}", SIZE)));
+}
+
+/// Records the largest single write, to check the body is sent in bounded pieces
+#[derive(Default)]
+struct Sink {
+    total: usize,
+    largest_write: usize,
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.total += buf.len();
+        self.largest_write = self.largest_write.max(buf.len());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn file_bodies_are_written_in_chunks() {
+    let root = TempSite::with_files("chunks", &[("big.bin", big_file())]);
+    let request = Request::parse(b"GET /big.bin HTTP/1.1").unwrap();
+    let response = respond(&request, &root);
+    let mut sink = Sink::default();
+    response.write_to(&mut sink, false).unwrap();
+    assert!(sink.total > SIZE);
+    assert!(sink.largest_write <= CHUNK_SIZE, "{}", sink.largest_write);
+}
@@ -0,0 +1,71 @@
+// Many prompts at once, without tripping rate limits.
+//
+// Firing 200 requests with a bare join_all opens 200 connections at the same moment
+// and the API answers most of them with 429. A semaphore (a counter of "permits")
+// lets only `max_concurrency` requests run at a time; the rest wait for a permit.
+use futures_util::future::join_all;
+use tokio::sync::Semaphore;
+
+use crate::{GeminiClient, GeminiError, RetryPolicy};
+
+/// How `generate_content_batch` runs its prompts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatchOptions {
+    /// Most requests in flight at once (0 is treated as 1)
+    pub max_concurrency: usize,
+    /// Retry each prompt with the client's `RetryPolicy` (false = one attempt each)
+    pub retry: bool,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 4,
+            retry: true,
+        }
+    }
+}
+
+impl BatchOptions {
+    /// Default options with a different concurrency limit
+    pub fn with_max_concurrency(max_concurrency: usize) -> Self {
+        Self {
+            max_concurrency,
+            ..Self::default()
+        }
+    }
+}
+
+impl GeminiClient {
+    /// Generate an answer for every prompt, at most `options.max_concurrency` at a time.
+    ///
+    /// The output has one entry per prompt, in the same order. A failed prompt is an
+    /// `Err` in its slot; the others still run.
+    pub async fn generate_content_batch(
+        &self,
+        prompts: Vec<String>,
+        options: BatchOptions,
+    ) -> Vec<Result<String, GeminiError>> {
+        let client = if options.retry {
+            self.clone()
+        } else {
+            self.clone().with_retry_policy(RetryPolicy::none())
+        };
+        let permits = Semaphore::new(options.max_concurrency.max(1));
+
+        // Build one future per prompt. Nothing runs until join_all polls them, and each
+        // waits for a permit first, so only max_concurrency requests are ever in flight.
+        let requests = prompts.iter().map(|prompt| {
+            let client = &client;
+            let permits = &permits;
+            async move {
+                // acquire() only fails if the semaphore is closed, which never happens here
+                let _permit = permits.acquire().await.ok();
+                client.generate_content(prompt).await
+            } // _permit is dropped here, handing the slot to the next prompt
+        });
+
+        // join_all keeps results in input order, whatever order they finish in
+        join_all(requests).await
+    }
+}
@@ -0,0 +1,124 @@
+// A synchronous API for programs without an async runtime (only with the `blocking` feature).
+//
+// Game loops such as the macroquad physics app call plain functions and can't `.await`.
+// These wrappers run the normal async client on a small internal Tokio runtime and
+// block the calling thread until the answer is there, so retries, timeouts and
+// errors behave exactly like the async path.
+//
+// Thread-safety expectations:
+// - `GeminiClient` here is `Send + Sync`: share one (e.g. in an `Arc`) across threads.
+//   Concurrent calls are fine; they share the client's runtime and connection pool.
+// - Each call blocks its thread for up to the client's timeout. In a UI or game loop,
+//   call it from a worker thread (like `WeatherFetcher` in rapier_physics), not the
+//   render thread.
+// - Never call these functions from inside an async runtime (e.g. in a `#[tokio::main]`
+//   program): blocking inside an async task panics. Use the async API there instead.
+use once_cell::sync::Lazy;
+use serde::de::DeserializeOwned;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::{GeminiError, GenerationResult, Prompt};
+
+// One runtime for the free function, created on first use and kept for the whole
+// program, instead of a throwaway runtime per call.
+static SHARED_RUNTIME: Lazy<Runtime> = Lazy::new(new_runtime);
+// Its HTTP client lives just as long, so connections are reused between calls.
+// Set up like the async clients' shared one, but with its own pool.
+static SHARED_HTTP_CLIENT: Lazy<reqwest::Client> =
+    Lazy::new(|| crate::build_http_client(None).unwrap_or_else(|_| reqwest::Client::new()));
+
+// 'current_thread': no worker threads are spawned; the calling thread drives the work
+// while it waits inside block_on.
+fn new_runtime() -> Runtime {
+    Builder::new_current_thread()
+        .enable_all() // timers (for timeouts/backoff) and network IO
+        .build()
+        .expect("failed to start the internal Tokio runtime")
+}
+
+/// Blocking version of [`crate::GeminiClient`].
+pub struct GeminiClient {
+    inner: crate::GeminiClient,
+    runtime: Runtime,
+}
+
+impl GeminiClient {
+    /// Create a blocking client for `api_key` (model from `GEMINI_MODEL`, like the async one).
+    ///
+    /// # Panics
+    /// If the internal runtime can't be created (the OS refused to create it).
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self::from_client(crate::GeminiClient::new(api_key))
+    }
+
+    /// Create a blocking client using the `GEMINI_API_KEY` env var.
+    pub fn from_env() -> Result<Self, GeminiError> {
+        crate::GeminiClient::from_env().map(Self::from_client)
+    }
+
+    /// Wrap an async client configured with the usual builder methods
+    /// (`with_model`, `with_timeout`, `with_proxy`, `with_system_instruction`, ...).
+    ///
+    /// A client from `with_http_client` is used as it is, so don't share that
+    /// `reqwest::Client` with async code.
+    pub fn from_client(client: crate::GeminiClient) -> Self {
+        Self {
+            // Pooled connections belong to the runtime that opened them, so this client
+            // gets its own pool (with the same proxy) instead of sharing the async clients' one.
+            inner: client.with_own_connection_pool(),
+            runtime: new_runtime(),
+        }
+    }
+
+    /// The wrapped async client (for its getters such as `model()`).
+    pub fn inner(&self) -> &crate::GeminiClient {
+        &self.inner
+    }
+
+    /// Blocking [`crate::GeminiClient::generate_content`].
+    pub fn generate_content(&self, prompt: &str) -> Result<String, GeminiError> {
+        self.runtime.block_on(self.inner.generate_content(prompt))
+    }
+
+    /// Blocking [`crate::GeminiClient::generate_content_detailed`].
+    pub fn generate_content_detailed(&self, prompt: &str) -> Result<GenerationResult, GeminiError> {
+        self.runtime
+            .block_on(self.inner.generate_content_detailed(prompt))
+    }
+
+    /// Blocking [`crate::GeminiClient::generate_json`].
+    pub fn generate_json<T: DeserializeOwned>(
+        &self,
+        prompt: &str,
+        schema: serde_json::Value,
+    ) -> Result<T, GeminiError> {
+        self.runtime
+            .block_on(self.inner.generate_json(prompt, schema))
+    }
+
+    /// Blocking [`crate::GeminiClient::count_tokens`].
+    pub fn count_tokens<'a>(&self, prompt: impl Into<Prompt<'a>>) -> Result<u32, GeminiError> {
+        self.runtime.block_on(self.inner.count_tokens(prompt))
+    }
+}
+
+/// Blocking [`crate::generate_content`]: same arguments, same errors, no `.await`.
+/// If `api_key_opt` is None, `GEMINI_API_KEY` is used.
+///
+/// ```no_run
+/// let answer = rust_gemini_llm_client::blocking::generate_content_blocking("Hello", None)?;
+/// # Ok::<(), rust_gemini_llm_client::GeminiError>(())
+/// ```
+pub fn generate_content_blocking(
+    prompt: &str,
+    api_key_opt: Option<String>,
+) -> Result<String, GeminiError> {
+    let client = match api_key_opt {
+        Some(k) => crate::GeminiClient::new(k),
+        None => crate::GeminiClient::from_env()?,
+    };
+    // Not the async clients' pool, for the same reason as in `from_client`. A client made
+    // here never has a proxy or a custom HTTP client, so nothing of its own is lost.
+    let client = client.with_http_client(SHARED_HTTP_CLIENT.clone());
+    SHARED_RUNTIME.block_on(client.generate_content(prompt))
+}
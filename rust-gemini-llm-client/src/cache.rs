@@ -0,0 +1,210 @@
+// Response caching for development: replaying the same prompt shouldn't burn quota.
+//
+// A cache maps a key (SHA-256 of the model plus the full request body, so the system
+// instruction, safety settings, JSON schema, ... all count) to the final answer text.
+// Hits skip HTTP entirely; only complete, unblocked answers are stored.
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+/// Storage for cached answers. Plug one into a client with `GeminiClient::with_cache`.
+///
+/// Methods take `&self` because one cache is shared by every request of a client
+/// (possibly from several tasks at once), so implementations use interior
+/// mutability such as a `Mutex`.
+// 'Send + Sync' supertraits: any implementation can be shared across threads.
+pub trait ResponseCache: Send + Sync {
+    /// The cached answer for `key`, if there is one
+    fn get(&self, key: &str) -> Option<String>;
+    /// Remember `value` as the answer for `key`
+    fn put(&self, key: &str, value: &str);
+}
+
+// Lets callers keep an Arc to the cache (e.g. to inspect it) while the client uses it too.
+impl<T: ResponseCache + ?Sized> ResponseCache for Arc<T> {
+    fn get(&self, key: &str) -> Option<String> {
+        (**self).get(key)
+    }
+
+    fn put(&self, key: &str, value: &str) {
+        (**self).put(key, value)
+    }
+}
+
+/// Hex-encoded SHA-256 of `model` and the serialized request body.
+pub(crate) fn cache_key(model: &str, request_json: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(model.as_bytes());
+    // A separator so ("ab", "c") and ("a", "bc") can't collide
+    hasher.update(b"\n");
+    hasher.update(request_json.as_bytes());
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// In-memory cache that forgets the least recently used answer once `capacity` is reached.
+pub struct MemoryCache {
+    capacity: usize,
+    inner: Mutex<LruState>,
+}
+
+#[derive(Default)]
+struct LruState {
+    entries: HashMap<String, String>,
+    // Keys from least to most recently used. Moving a key is O(n), which is fine
+    // for the few hundred entries a development cache holds.
+    order: VecDeque<String>,
+}
+
+impl LruState {
+    fn touch(&mut self, key: &str) {
+        if let Some(index) = self.order.iter().position(|k| k == key) {
+            // remove(index) returns Option<String>; the key moves to the back
+            if let Some(k) = self.order.remove(index) {
+                self.order.push_back(k);
+            }
+        }
+    }
+}
+
+impl MemoryCache {
+    /// A cache holding at most `capacity` answers (at least 1)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            inner: Mutex::new(LruState::default()),
+        }
+    }
+
+    /// Number of cached answers
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // A poisoned mutex only means another thread panicked mid-update; the map is
+    // still usable, so recover it instead of propagating the panic.
+    fn lock(&self) -> std::sync::MutexGuard<'_, LruState> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ResponseCache for MemoryCache {
+    fn get(&self, key: &str) -> Option<String> {
+        let mut state = self.lock();
+        let value = state.entries.get(key).cloned()?;
+        state.touch(key);
+        Some(value)
+    }
+
+    fn put(&self, key: &str, value: &str) {
+        let mut state = self.lock();
+        if state
+            .entries
+            .insert(key.to_string(), value.to_string())
+            .is_some()
+        {
+            state.touch(key);
+            return;
+        }
+        state.order.push_back(key.to_string());
+        while state.entries.len() > self.capacity {
+            match state.order.pop_front() {
+                Some(oldest) => {
+                    state.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+/// One line of the cache file
+#[derive(Serialize, Deserialize)]
+struct FileEntry {
+    key: String,
+    value: String,
+}
+
+/// Cache persisted to a JSON-lines file, so answers survive restarts.
+///
+/// Each `put` appends one `{"key": ..., "value": ...}` line; on open the file is read
+/// back, with later lines winning. Delete the file to clear the cache.
+pub struct FileCache {
+    path: PathBuf,
+    entries: Mutex<HashMap<String, String>>,
+}
+
+impl FileCache {
+    /// Open (or start) the cache file at `path`. Unreadable lines are skipped.
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = HashMap::new();
+        match File::open(&path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    // A line that isn't valid JSON (e.g. a half-written one) is simply skipped
+                    if let Ok(entry) = serde_json::from_str::<FileEntry>(&line?) {
+                        entries.insert(entry.key, entry.value);
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        Ok(Self {
+            path,
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// Number of cached answers
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.len()).unwrap_or(0)
+    }
+
+    /// Whether nothing is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ResponseCache for FileCache {
+    fn get(&self, key: &str) -> Option<String> {
+        self.entries.lock().ok()?.get(key).cloned()
+    }
+
+    fn put(&self, key: &str, value: &str) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+        entries.insert(key.to_string(), value.to_string());
+
+        let entry = FileEntry {
+            key: key.to_string(),
+            value: value.to_string(),
+        };
+        // Caching is best effort: a failed write only means a future miss
+        if let Ok(line) = serde_json::to_string(&entry) {
+            let _ = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .and_then(|mut file| writeln!(file, "{}", line));
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// Embeddings: turn text into a vector of floats so similar texts end up close together.
+//
+// The vectors are what semantic search compares: embed every chat message once, embed
+// the query, then rank messages by `cosine_similarity` to the query.
+use serde::{Deserialize, Serialize};
+
+use crate::{GeminiError, Part};
+
+/// Embedding model used unless `GeminiClient::with_embedding_model` says otherwise.
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-004";
+
+// Body of `:embedContent`, and one entry of `:batchEmbedContents`.
+// The batch endpoint wants the model repeated in every entry, as `models/<id>`.
+#[derive(Serialize)]
+pub(crate) struct EmbedContentRequest {
+    model: String,
+    content: EmbedText,
+}
+
+// Content without a role: {"parts": [{"text": "..."}]}
+#[derive(Serialize)]
+struct EmbedText {
+    parts: Vec<Part>,
+}
+
+impl EmbedContentRequest {
+    pub(crate) fn new(model: &str, text: &str) -> Self {
+        Self {
+            model: format!("models/{}", model),
+            content: EmbedText {
+                parts: vec![Part::text(text)],
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct BatchEmbedContentsRequest {
+    pub(crate) requests: Vec<EmbedContentRequest>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Embedding {
+    values: Vec<f32>,
+}
+
+// `:embedContent` reply: {"embedding": {"values": [...]}}
+#[derive(Deserialize, Debug)]
+pub(crate) struct EmbedContentResponse {
+    embedding: Embedding,
+}
+
+impl EmbedContentResponse {
+    pub(crate) fn into_values(self) -> Vec<f32> {
+        self.embedding.values
+    }
+}
+
+// `:batchEmbedContents` reply: {"embeddings": [{"values": [...]}, ...]}, in request order
+#[derive(Deserialize, Debug)]
+pub(crate) struct BatchEmbedContentsResponse {
+    embeddings: Vec<Embedding>,
+}
+
+impl BatchEmbedContentsResponse {
+    pub(crate) fn into_vectors(self) -> Vec<Vec<f32>> {
+        // .into_iter() consumes the Vec, so each `values` is moved out, not copied
+        self.embeddings.into_iter().map(|e| e.values).collect()
+    }
+}
+
+/// Parse a `batchEmbedContents` response body into one vector per input text.
+pub fn parse_batch_embeddings(body: &str) -> Result<Vec<Vec<f32>>, GeminiError> {
+    serde_json::from_str::<BatchEmbedContentsResponse>(body)
+        .map(BatchEmbedContentsResponse::into_vectors)
+        .map_err(|e| GeminiError::Parse(e.to_string()))
+}
+
+/// Cosine similarity of two vectors: 1.0 = same direction, 0.0 = unrelated, -1.0 = opposite.
+///
+/// Returns 0.0 if the lengths differ or either vector is all zeros, so a bad input
+/// ranks last instead of producing NaN.
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    // zip pairs up the elements like Python's zip(a, b)
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
@@ -0,0 +1,125 @@
+// A typed error for everything that can go wrong when talking to Gemini.
+// Callers can `match` on it instead of string-matching a `Box<dyn Error>`
+// (think of a sealed exception hierarchy in Java, but checked by the compiler).
+use std::fmt;
+
+#[derive(Debug)]
+pub enum GeminiError {
+    /// No API key was passed and `GEMINI_API_KEY` is not set
+    MissingApiKey,
+    /// A builder setting was rejected (e.g. a malformed base URL or proxy URL)
+    InvalidConfig(String),
+    /// The API answered with a non-success status (other than 429)
+    Http { status: u16, body: String },
+    /// The API answered 429 Too Many Requests. `retry_after` is in seconds.
+    RateLimited { retry_after: Option<u64> },
+    /// The request never got a response (DNS, TLS, connection reset, ...). The error
+    /// carries no URL: request URLs hold the API key (`?key=...`).
+    Network(reqwest::Error),
+    /// The response body was not the JSON we expected
+    Parse(String),
+    /// JSON mode (`generate_json`) returned text that doesn't match the requested type.
+    /// `text` is the model's raw output, useful for logging or a retry prompt.
+    InvalidJson {
+        source: serde_json::Error,
+        text: String,
+    },
+    /// Gemini refused to answer (e.g. `promptFeedback.blockReason = "SAFETY"`)
+    Blocked { reason: String },
+    /// Connecting or reading the response took longer than the client's timeout
+    Timeout,
+    /// The caller cancelled the request through its `CancellationToken`
+    Cancelled,
+}
+
+impl GeminiError {
+    /// Build the error for a non-success HTTP response from its status code,
+    /// `Retry-After` header (if any), and body text.
+    pub fn from_status(status: u16, retry_after: Option<&str>, body: String) -> Self {
+        if status == 429 {
+            GeminiError::RateLimited {
+                // Retry-After is usually a number of seconds; ignore the HTTP-date form
+                retry_after: retry_after.and_then(|v| v.trim().parse().ok()),
+            }
+        } else {
+            GeminiError::Http { status, body }
+        }
+    }
+
+    /// The HTTP status behind this error, if there was one
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            GeminiError::Http { status, .. } => Some(*status),
+            GeminiError::RateLimited { .. } => Some(429),
+            GeminiError::Network(e) => e.status().map(|s| s.as_u16()),
+            _ => None,
+        }
+    }
+}
+
+// Display is the human-readable message shown by `{}`.
+impl fmt::Display for GeminiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeminiError::MissingApiKey => {
+                write!(f, "GEMINI_API_KEY is not set and no API key was provided")
+            }
+            GeminiError::InvalidConfig(msg) => {
+                write!(f, "Invalid Gemini client configuration: {}", msg)
+            }
+            GeminiError::Http { status, body } => {
+                write!(f, "Request failed: {} - {}", status, body)
+            }
+            GeminiError::RateLimited {
+                retry_after: Some(secs),
+            } => {
+                write!(f, "Rate limited by Gemini API (retry after {}s)", secs)
+            }
+            GeminiError::RateLimited { retry_after: None } => {
+                write!(f, "Rate limited by Gemini API")
+            }
+            GeminiError::Network(e) => write!(f, "Network error: {}", e),
+            GeminiError::Parse(msg) => write!(f, "Could not parse Gemini response: {}", msg),
+            GeminiError::InvalidJson { source, .. } => {
+                write!(
+                    f,
+                    "Gemini returned JSON that doesn't match the expected type: {}",
+                    source
+                )
+            }
+            GeminiError::Blocked { reason } => write!(f, "Gemini blocked the request: {}", reason),
+            GeminiError::Timeout => write!(f, "Request to Gemini timed out"),
+            GeminiError::Cancelled => write!(f, "Request to Gemini was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for GeminiError {
+    // source() exposes the underlying cause, so error reporters can print the whole chain
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GeminiError::Network(e) => Some(e),
+            GeminiError::InvalidJson { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// `From` lets the `?` operator convert a reqwest::Error into a GeminiError automatically.
+impl From<reqwest::Error> for GeminiError {
+    fn from(e: reqwest::Error) -> Self {
+        // reqwest reports its own connect timeout as an error; surface it like our deadline
+        if e.is_timeout() {
+            GeminiError::Timeout
+        } else {
+            // reqwest's message ends with "for url (...)", and our URLs end with `?key=<API key>`.
+            // These messages reach logs and HTTP responses, so the URL has to go.
+            GeminiError::Network(e.without_url())
+        }
+    }
+}
+
+// A response body that isn't the JSON we expected; without the URL, as above
+pub(crate) fn body_error(e: reqwest::Error) -> GeminiError {
+    GeminiError::Parse(e.without_url().to_string())
+}
@@ -11,88 +11,23 @@
 // IMPORTS
 // Similar to `import` in Java/Python or `import/require` in JS.
 // "Crates" are like npm packages or Maven dependencies.
-use dotenv::dotenv;
-use futures_util::StreamExt; // Extension trait for streams (like RxJS or Java Streams)
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
-use std::env;
-use std::error::Error;
-
-// STRUCTS & ATTRIBUTES
-// Structs are like Classes in Java/Python but hold DATA ONLY.
-// Behavior is added separately via `impl` blocks (not shown here).
-//
-// `#[derive(...)]` is a macro attribute. It's similar to Java Annotations or Python Decorators.
-// It automatically generates code for specific "Traits" (interfaces).
-// Here, we auto-generate code to Serialize this struct to JSON.
-#[derive(Serialize)]
-struct GenerateContentRequest {
-    // `Vec<T>` is a growable array, like ArrayList<T> in Java, List in Python, or Array in JS.
-    // It owns its data (heap allocated).
-    contents: Vec<Content>,
-}
-
-#[derive(Serialize)]
-struct Content {
-    // `String` is a heap-allocated, growable string (like Java's String but mutable).
-    // It is distinct from `&str` (string slice), which is just a view/pointer to string data.
-    role: String,
-    parts: Vec<Part>, //Part is a user-defined type (typically a struct or enum) that represents a single element stored in the Vec<Part>—the data model for one "part" in your program.
-}
-
-#[derive(Serialize)]
-struct Part {
-    text: String,
-}
-
-// DESERIALIZATION STRUCTS
-// `#[derive(Deserialize, Debug)]`:
-// - Deserialize: Allows creating this struct from JSON.
-// - Debug: Allows printing the struct using `{:?}` (like toString() for debugging).
-#[derive(Deserialize, Debug)]
-struct GenerateContentResponse {
-    // OPTION TYPE
-    // Rust doesn't have `null`. Instead, it uses `Option<T>`.
-    // It can be `Some(value)` or `None`.
-    // This forces you to handle the "null" case explicitly, preventing NullPointerExceptions.
-    // Similar to Java's `Optional<T>`, but used everywhere.
-    candidates: Option<Vec<Candidate>>,
-}
-
-#[derive(Deserialize, Debug)]
-struct Candidate {
-    content: Option<ResponseContent>,
-    // Renaming fields for JSON mapping (like @JsonProperty in Jackson).
-    #[serde(rename = "finishReason")]
-    finish_reason: Option<String>,
-}
-
-#[derive(Deserialize, Debug)]
-struct ResponseContent {
-    parts: Option<Vec<ResponsePart>>,
-}
-
-#[derive(Deserialize, Debug)]
-struct ResponsePart {
-    text: Option<String>,
-}
-
-// CONSTANTS
-// `&str` is a "String Slice". It points to UTF-8 bytes in memory.
-// Here, it points to static memory embedded in the binary.
-const GEMINI_API_URL: &str = "https://generativelanguage.googleapis.com/v1beta/models/gemini-flash-latest:streamGenerateContent";
+// The request/response structs (STRUCTS & ATTRIBUTES, OPTION TYPE, etc.) live in src/lib.rs;
+// this binary only uses the library's public API, just like any other consumer would.
+use rust_gemini_llm_client::GeminiClient;
 
 // MAIN FUNCTION & ASYNC
 // `#[tokio::main]` sets up the async runtime (event loop).
 // Rust doesn't have a built-in runtime like Node.js or the JVM; you must pull one in.
-use rust_gemini_llm_client::generate_content;
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let prompt = "Explain how Rust's ownership model works in 3 sentences.";
-    println!("Sending prompt: {}\n", prompt);
 
-    match generate_content(prompt, None).await {
+    // `?` works here because main returns a Result: a missing GEMINI_API_KEY ends the program.
+    // The model comes from GEMINI_MODEL (or the library default) unless overridden with `.with_model(...)`.
+    let client = GeminiClient::from_env()?;
+    println!("Sending prompt to {}: {}\n", client.model(), prompt);
+
+    match client.generate_content(prompt).await {
         Ok(resp) => {
             println!("Response:\n{}\n", resp);
         }
@@ -0,0 +1,104 @@
+// Chat history types: what the caller passes in when there is more than one turn.
+//
+// Gemini has no server-side conversation state. To continue a chat, the whole
+// history is sent again as `contents`, alternating `user` and `model` turns.
+use crate::{Content, Part};
+
+/// Who wrote a message. Gemini calls the assistant side `model`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    User,
+    Model,
+}
+
+impl Role {
+    /// The role string used in the JSON body
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Model => "model",
+        }
+    }
+}
+
+/// One turn of a conversation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub text: String,
+}
+
+impl ChatMessage {
+    /// A message typed by the user
+    pub fn user(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            text: text.into(),
+        }
+    }
+
+    /// A reply from the model
+    pub fn model(text: impl Into<String>) -> Self {
+        Self {
+            role: Role::Model,
+            text: text.into(),
+        }
+    }
+}
+
+/// Either a single prompt or a whole message history.
+///
+/// Functions take `impl Into<Prompt>`, so callers can pass `"Hello"`, a `&String`,
+/// or `&history` (a `Vec<ChatMessage>` or slice) without wrapping it themselves.
+// The lifetime 'a says the Prompt only borrows the text; nothing is copied until
+// the request body is built.
+#[derive(Debug, Clone, Copy)]
+pub enum Prompt<'a> {
+    Text(&'a str),
+    Messages(&'a [ChatMessage]),
+}
+
+impl<'a> Prompt<'a> {
+    /// The `contents` array sent to the API (shared by generate and countTokens).
+    pub(crate) fn contents(&self) -> Vec<Content> {
+        match self {
+            Prompt::Text(text) => vec![content(Role::User, text)],
+            Prompt::Messages(messages) => messages
+                .iter()
+                .map(|message| content(message.role, &message.text))
+                .collect(),
+        }
+    }
+}
+
+fn content(role: Role, text: &str) -> Content {
+    Content {
+        role: role.as_str().to_string(),
+        parts: vec![Part::text(text)],
+    }
+}
+
+// 'From' conversions are what make `impl Into<Prompt>` accept all of these types.
+impl<'a> From<&'a str> for Prompt<'a> {
+    fn from(text: &'a str) -> Self {
+        Prompt::Text(text)
+    }
+}
+
+impl<'a> From<&'a String> for Prompt<'a> {
+    fn from(text: &'a String) -> Self {
+        Prompt::Text(text)
+    }
+}
+
+impl<'a> From<&'a [ChatMessage]> for Prompt<'a> {
+    fn from(messages: &'a [ChatMessage]) -> Self {
+        Prompt::Messages(messages)
+    }
+}
+
+impl<'a> From<&'a Vec<ChatMessage>> for Prompt<'a> {
+    fn from(messages: &'a Vec<ChatMessage>) -> Self {
+        Prompt::Messages(messages)
+    }
+}
@@ -0,0 +1,59 @@
+// Model metadata from `GET /v1beta/models`.
+//
+// Handy for pickers ("which models can chat?") and for warning before a prompt
+// exceeds `input_token_limit` (see `GeminiClient::count_tokens`).
+use serde::Deserialize;
+
+use crate::GeminiError;
+
+/// One entry of the models list.
+// 'default' on the struct: fields the API leaves out get their Default (empty / 0)
+// instead of failing the whole list.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModelInfo {
+    /// Resource name, e.g. `models/gemini-flash-latest`
+    pub name: String,
+    /// Human-friendly name, e.g. `Gemini Flash Latest`
+    pub display_name: String,
+    /// Maximum prompt size in tokens
+    pub input_token_limit: u32,
+    /// Maximum answer size in tokens
+    pub output_token_limit: u32,
+    /// API methods the model accepts, e.g. `generateContent`, `countTokens`, `embedContent`
+    pub supported_generation_methods: Vec<String>,
+}
+
+impl ModelInfo {
+    /// The id to pass to `GeminiClient::with_model` (the name without `models/`)
+    pub fn id(&self) -> &str {
+        self.name.strip_prefix("models/").unwrap_or(&self.name)
+    }
+
+    /// Whether the model accepts `method` (e.g. `"embedContent"`)
+    pub fn supports(&self, method: &str) -> bool {
+        self.supported_generation_methods
+            .iter()
+            .any(|m| m == method)
+    }
+
+    /// Whether the model can be used with `generate_content`
+    pub fn supports_generate_content(&self) -> bool {
+        self.supports("generateContent")
+    }
+}
+
+/// One page of `GET /models`
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct ListModelsResponse {
+    pub(crate) models: Vec<ModelInfo>,
+    pub(crate) next_page_token: Option<String>,
+}
+
+/// Parse one page of a `GET /models` response body (useful for tests and captured responses).
+pub fn parse_models_list(body: &str) -> Result<Vec<ModelInfo>, GeminiError> {
+    serde_json::from_str::<ListModelsResponse>(body)
+        .map(|page| page.models)
+        .map_err(|e| GeminiError::Parse(e.to_string()))
+}
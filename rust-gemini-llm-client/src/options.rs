@@ -0,0 +1,24 @@
+// Generation settings: how random the answer may be and how long it may get.
+//
+// They travel in the request's `generation_config`, next to the JSON-mode settings. A field
+// left as None isn't sent, so the model's own default applies.
+use std::ops::RangeInclusive;
+
+/// The temperatures Gemini accepts
+pub const TEMPERATURE_RANGE: RangeInclusive<f32> = 0.0..=2.0;
+
+/// Sampling settings sent with every generation request of a client.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GenerationOptions {
+    /// Randomness, from 0.0 (almost always the same answer) to 2.0 (most varied)
+    pub temperature: Option<f32>,
+    /// Longest answer in tokens. A longer answer is cut off with finish reason `MAX_TOKENS`.
+    pub max_output_tokens: Option<u32>,
+}
+
+impl GenerationOptions {
+    /// True if nothing is set, i.e. the model's defaults apply
+    pub fn is_empty(&self) -> bool {
+        self.temperature.is_none() && self.max_output_tokens.is_none()
+    }
+}
@@ -0,0 +1,179 @@
+// What comes back from Gemini, and how streamed chunks are folded into one result.
+//
+// With `alt=sse` every chunk is a complete GenerateContentResponse JSON object holding
+// the next few words. Text is concatenated; metadata such as `finishReason` usually
+// only appears on the last chunk.
+use serde::Deserialize;
+
+use crate::tools::{FunctionCall, ModelReply};
+use crate::GeminiError;
+
+/// Finish reasons that mean the answer was withheld rather than completed.
+const BLOCKING_FINISH_REASONS: [&str; 5] = [
+    "SAFETY",
+    "RECITATION",
+    "BLOCKLIST",
+    "PROHIBITED_CONTENT",
+    "SPII",
+];
+
+/// The outcome of a generation call.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GenerationResult {
+    /// All text parts, concatenated (may be partial, see `finish_reason`)
+    pub text: String,
+    /// Why generation stopped: `STOP` (normal), `MAX_TOKENS`, `SAFETY`, ...
+    pub finish_reason: Option<String>,
+    /// True if the answer was withheld (safety, recitation, ...)
+    pub blocked: bool,
+    /// Token counts reported by the API (absent if the server didn't send them)
+    pub usage: Option<UsageMetadata>,
+    /// Functions the model asked to call (only when tools were offered)
+    pub function_calls: Vec<FunctionCall>,
+}
+
+/// Token usage for one call, as reported in the response's `usageMetadata`.
+///
+/// Billing is per token, so `prompt_token_count` and `candidates_token_count`
+/// are what a cost estimate needs.
+// 'rename_all': maps snake_case fields to the API's camelCase keys in one line.
+// 'default': a count the server leaves out (e.g. no candidates when blocked) becomes 0.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UsageMetadata {
+    /// Tokens in the prompt (including the system instruction)
+    pub prompt_token_count: u32,
+    /// Tokens in the generated answer
+    pub candidates_token_count: u32,
+    /// Everything billed for this call
+    pub total_token_count: u32,
+}
+
+impl GenerationResult {
+    /// True if the model ran out of output tokens and the text is cut off
+    pub fn is_truncated(&self) -> bool {
+        self.finish_reason.as_deref() == Some("MAX_TOKENS")
+    }
+
+    /// The text, or `GeminiError::Blocked` if the answer was withheld.
+    // 'self' (not &self): consumes the result so the String can be moved out without a copy.
+    pub fn into_text(self) -> Result<String, GeminiError> {
+        if self.blocked {
+            return Err(GeminiError::Blocked {
+                reason: self.finish_reason.unwrap_or_else(|| "unknown".to_string()),
+            });
+        }
+        Ok(self.text)
+    }
+
+    /// Function calls if the model made any, otherwise the text (blocked answers are errors).
+    pub fn into_reply(self) -> Result<ModelReply, GeminiError> {
+        if !self.blocked && !self.function_calls.is_empty() {
+            return Ok(ModelReply::FunctionCalls(self.function_calls));
+        }
+        self.into_text().map(ModelReply::Text)
+    }
+}
+
+// Deserialize: Allows creating this struct from JSON.
+// Debug: Allows printing the struct with {:?} for debugging.
+#[derive(Deserialize, Debug)]
+struct GenerateContentResponse {
+    // Option<T>: Represents a value that might be missing (null safety).
+    // Rust has no 'null'. You must handle the None case explicitly.
+    candidates: Option<Vec<Candidate>>,
+    // Present when the prompt itself was rejected (no candidates are returned then)
+    #[serde(rename = "promptFeedback")]
+    prompt_feedback: Option<PromptFeedback>,
+    #[serde(rename = "usageMetadata")]
+    usage_metadata: Option<UsageMetadata>,
+}
+
+#[derive(Deserialize, Debug)]
+struct PromptFeedback {
+    #[serde(rename = "blockReason")]
+    block_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Candidate {
+    content: Option<ResponseContent>,
+    // Renames the JSON field "finishReason" to the Rust field "finish_reason"
+    #[serde(rename = "finishReason")]
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ResponseContent {
+    parts: Option<Vec<ResponsePart>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ResponsePart {
+    text: Option<String>,
+    // Set instead of `text` when the model wants a declared function to be called
+    #[serde(rename = "functionCall")]
+    function_call: Option<FunctionCall>,
+}
+
+/// Body of a `countTokens` reply: `{"totalTokens": 31}`
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CountTokensResponse {
+    pub(crate) total_tokens: u32,
+}
+
+/// Folds streamed chunks into a `GenerationResult`.
+#[derive(Default)]
+pub(crate) struct ResponseAccumulator {
+    result: GenerationResult,
+}
+
+impl ResponseAccumulator {
+    /// Parse one streamed JSON chunk and merge it into the result.
+    pub(crate) fn push_chunk(&mut self, payload: &str) -> Result<(), GeminiError> {
+        // ::<Type> syntax specifies what to parse into (Generics)
+        let parsed = serde_json::from_str::<GenerateContentResponse>(payload)
+            .map_err(|e| GeminiError::Parse(e.to_string()))?;
+
+        // Streamed chunks carry running totals, so the last one seen is the final count
+        if let Some(usage) = parsed.usage_metadata {
+            self.result.usage = Some(usage);
+        }
+
+        // The prompt itself was rejected: there is no answer at all
+        if let Some(reason) = parsed.prompt_feedback.and_then(|f| f.block_reason) {
+            return Err(GeminiError::Blocked { reason });
+        }
+
+        // Nested 'if let' to safely access deeply nested Option types
+        // This avoids NullPointerExceptions by design.
+        for candidate in parsed.candidates.unwrap_or_default() {
+            if let Some(parts) = candidate.content.and_then(|c| c.parts) {
+                for part in parts {
+                    if let Some(text) = part.text {
+                        self.result.text.push_str(&text); // Append to output buffer
+                    }
+                    if let Some(call) = part.function_call {
+                        self.result.function_calls.push(call);
+                    }
+                }
+            }
+            if let Some(reason) = candidate.finish_reason {
+                self.result.blocked = BLOCKING_FINISH_REASONS.contains(&reason.as_str());
+                self.result.finish_reason = Some(reason);
+            }
+        }
+        Ok(())
+    }
+
+    /// The text received so far.
+    pub(crate) fn text(&self) -> &str {
+        &self.result.text
+    }
+
+    /// The accumulated result.
+    pub(crate) fn finish(self) -> GenerationResult {
+        self.result
+    }
+}
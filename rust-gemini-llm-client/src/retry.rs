@@ -0,0 +1,89 @@
+// Retry policy for transient Gemini failures (429 Too Many Requests and 5xx errors).
+// Each retry waits exponentially longer (base, 2x base, 4x base, ...) with random jitter
+// so that many clients backing off at once don't retry in lockstep.
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::GeminiError;
+
+/// How often and how patiently to retry a failed request.
+// Clone + Copy: the policy is a handful of numbers, so copying it is cheap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 disables retrying)
+    pub max_retries: u32,
+    /// Delay before the first retry; doubles on every further retry
+    pub base_delay: Duration,
+    /// Upper bound for any single delay, including a server-provided `Retry-After`
+    pub max_delay: Duration,
+    /// Randomize delays to between 50% and 100% of their nominal value
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Whether `error` is worth retrying (rate limiting or a server-side failure)
+    pub fn should_retry(&self, error: &GeminiError, attempt: u32) -> bool {
+        if attempt >= self.max_retries {
+            return false;
+        }
+        match error {
+            GeminiError::RateLimited { .. } => true,
+            GeminiError::Http { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+
+    /// Delay before retry number `attempt` (0-based). A `Retry-After` value from the
+    /// server wins over the computed backoff; both are capped at `max_delay`.
+    pub fn delay_for(&self, attempt: u32, retry_after: Option<u64>) -> Duration {
+        if let Some(secs) = retry_after {
+            return Duration::from_secs(secs).min(self.max_delay);
+        }
+
+        // checked_pow/checked_mul avoid overflow panics for absurd attempt counts
+        let factor = 2u32.checked_pow(attempt).unwrap_or(u32::MAX);
+        let nominal = self
+            .base_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+
+        if self.jitter {
+            nominal.mul_f64(0.5 + 0.5 * random_fraction())
+        } else {
+            nominal
+        }
+    }
+}
+
+/// A cheap pseudo-random number in [0, 1). Good enough for jitter; not for anything secret.
+fn random_fraction() -> f64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+    // Scramble the low bits a little (xorshift) so consecutive calls differ more
+    let mut x = nanos as u64 ^ 0x9E37_79B9_7F4A_7C15;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    (x % 10_000) as f64 / 10_000.0
+}
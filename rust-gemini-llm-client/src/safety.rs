@@ -0,0 +1,55 @@
+// Safety settings: how strictly Gemini filters each kind of harmful content.
+//
+// Without settings the API applies its own defaults. A setting overrides the
+// threshold for one category, e.g. relax harassment filtering for a moderation tool
+// or tighten dangerous-content filtering for a kids' app.
+use serde::{Deserialize, Serialize};
+
+/// The kinds of content Gemini can filter.
+// Each variant is renamed to the exact string the API expects.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HarmCategory {
+    #[serde(rename = "HARM_CATEGORY_HARASSMENT")]
+    Harassment,
+    #[serde(rename = "HARM_CATEGORY_HATE_SPEECH")]
+    HateSpeech,
+    #[serde(rename = "HARM_CATEGORY_SEXUALLY_EXPLICIT")]
+    SexuallyExplicit,
+    #[serde(rename = "HARM_CATEGORY_DANGEROUS_CONTENT")]
+    DangerousContent,
+    #[serde(rename = "HARM_CATEGORY_CIVIC_INTEGRITY")]
+    CivicIntegrity,
+}
+
+/// How likely content must be to be harmful before it is blocked.
+// 'rename_all': BlockOnlyHigh <-> "BLOCK_ONLY_HIGH", and so on for every variant.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum HarmBlockThreshold {
+    /// Block when the probability is low, medium or high (strictest)
+    BlockLowAndAbove,
+    /// Block medium and high probability
+    BlockMediumAndAbove,
+    /// Block only high probability
+    BlockOnlyHigh,
+    /// Never block, but still report safety ratings
+    BlockNone,
+    /// Turn the filter off entirely
+    Off,
+}
+
+/// One category/threshold pair, sent as `{"category": "...", "threshold": "..."}`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SafetySetting {
+    pub category: HarmCategory,
+    pub threshold: HarmBlockThreshold,
+}
+
+impl SafetySetting {
+    pub fn new(category: HarmCategory, threshold: HarmBlockThreshold) -> Self {
+        Self {
+            category,
+            threshold,
+        }
+    }
+}
@@ -0,0 +1,46 @@
+// Minimal Server-Sent Events reader. With `?alt=sse` Gemini streams lines like
+//
+//   data: {"candidates": [...]}
+//   <blank line>
+//
+// Network chunks can end anywhere (even mid-line), so bytes are buffered until a full
+// line is available. Only `data:` lines matter here; comments and other fields are skipped.
+
+#[derive(Default)]
+pub(crate) struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    /// Feed raw bytes and get back the payload of every complete `data:` line.
+    pub(crate) fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut payloads = Vec::new();
+
+        // Pull out one line at a time; anything after the last '\n' stays buffered.
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            if let Some(payload) = data_payload(&line) {
+                payloads.push(payload);
+            }
+        }
+        payloads
+    }
+
+    /// Flush a final line that was not terminated by a newline.
+    pub(crate) fn finish(&mut self) -> Option<String> {
+        let line = std::mem::take(&mut self.buffer);
+        data_payload(&line)
+    }
+}
+
+fn data_payload(line: &[u8]) -> Option<String> {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim_end_matches(['\r', '\n']);
+    let payload = line.strip_prefix("data:")?.trim_start();
+    if payload.is_empty() {
+        None
+    } else {
+        Some(payload.to_string())
+    }
+}
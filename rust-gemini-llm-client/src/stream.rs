@@ -0,0 +1,136 @@
+// Token-by-token output: hand each piece of text to the caller as soon as Gemini sends it,
+// instead of collecting the whole answer first (what `generate_content` does).
+//
+// The result is a `Stream` (the async version of an Iterator, like an RxJS Observable or a
+// Python async generator). Nothing is sent until the stream is first polled, and dropping
+// the stream closes the HTTP connection, which is how callers cancel a running answer.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use futures_util::stream::{self, BoxStream, StreamExt};
+
+use crate::response::ResponseAccumulator;
+use crate::sse::SseParser;
+use crate::{GeminiClient, GeminiError, GenerateContentRequest, GenerationResult};
+
+/// One item of a streamed answer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent {
+    /// The next piece of text (a few words; chunk sizes are up to the API)
+    Text(String),
+    /// Generation finished. The result holds the full text, finish reason and usage.
+    /// Always the last item of a successful stream.
+    Done(GenerationResult),
+}
+
+/// The stream returned by `GeminiClient::generate_content_stream`.
+// BoxStream<'static, T>: a heap-allocated stream that borrows nothing, so it can be
+// stored, returned from handlers, or moved to another task.
+pub type EventStream = BoxStream<'static, Result<StreamEvent, GeminiError>>;
+
+// The stream is a small state machine: first connect, then read chunks until the body ends.
+// The variants are boxed so the state that moves between polls stays pointer-sized.
+enum State {
+    Connecting(Box<(GeminiClient, GenerateContentRequest)>),
+    Reading(Box<Reader>),
+    Finished,
+}
+
+struct Reader {
+    response: reqwest::Response,
+    // How long to wait for the next chunk before giving up
+    idle_timeout: Duration,
+    parser: SseParser,
+    output: ResponseAccumulator,
+    // Text deltas parsed from a network chunk but not yet handed out
+    pending: VecDeque<String>,
+    body_ended: bool,
+}
+
+impl Reader {
+    /// Merge parsed SSE payloads into the result and queue the new text of each one.
+    fn absorb(&mut self, payloads: impl IntoIterator<Item = String>) -> Result<(), GeminiError> {
+        for payload in payloads {
+            let before = self.output.text().len();
+            self.output.push_chunk(&payload)?;
+            let delta = &self.output.text()[before..];
+            if !delta.is_empty() {
+                self.pending.push_back(delta.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn event_stream(client: GeminiClient, request: GenerateContentRequest) -> EventStream {
+    // unfold: build a stream from a state and an async "produce the next item" function
+    // (like a generator that yields and keeps its local variables between calls).
+    stream::unfold(State::Connecting(Box::new((client, request))), next_event).boxed()
+}
+
+async fn next_event(mut state: State) -> Option<(Result<StreamEvent, GeminiError>, State)> {
+    // Each iteration advances the state; `return` hands one item to the caller.
+    loop {
+        state =
+            match state {
+                State::Finished => return None,
+                State::Connecting(connect) => {
+                    // '*connect' moves the tuple out of its Box
+                    let (client, request) = *connect;
+                    // The client's timeout bounds connecting, including retries of 429/5xx
+                    let response = client
+                        .with_deadline(client.post_with_retry(&client.stream_url(), &request))
+                        .await;
+                    match response {
+                        Ok(response) => State::Reading(Box::new(Reader {
+                            response,
+                            idle_timeout: client.timeout(),
+                            parser: SseParser::default(),
+                            output: ResponseAccumulator::default(),
+                            pending: VecDeque::new(),
+                            body_ended: false,
+                        })),
+                        Err(e) => return Some((Err(e), State::Finished)),
+                    }
+                }
+                State::Reading(mut reader) => {
+                    if let Some(text) = reader.pending.pop_front() {
+                        return Some((Ok(StreamEvent::Text(text)), State::Reading(reader)));
+                    }
+                    if reader.body_ended {
+                        // std::mem::take swaps in an empty accumulator so we can consume this one
+                        let result = std::mem::take(&mut reader.output).finish();
+                        let last = match result.finish_reason.clone() {
+                            // A blocked answer ends the stream with an error, like generate_content
+                            Some(reason) if result.blocked => Err(GeminiError::Blocked { reason }),
+                            _ => Ok(StreamEvent::Done(result)),
+                        };
+                        return Some((last, State::Finished));
+                    }
+
+                    // chunk() resolves to the next piece of the body, or None once it is complete.
+                    // Each gap between chunks gets the full timeout, so long answers aren't cut off.
+                    let parsed =
+                        match tokio::time::timeout(reader.idle_timeout, reader.response.chunk())
+                            .await
+                        {
+                            Err(_) => Err(GeminiError::Timeout),
+                            Ok(Ok(Some(bytes))) => {
+                                let payloads = reader.parser.push(&bytes);
+                                reader.absorb(payloads)
+                            }
+                            Ok(Err(e)) => Err(e.into()),
+                            Ok(Ok(None)) => {
+                                reader.body_ended = true;
+                                let last = reader.parser.finish();
+                                reader.absorb(last)
+                            }
+                        };
+                    match parsed {
+                        Ok(()) => State::Reading(reader),
+                        Err(e) => return Some((Err(e), State::Finished)),
+                    }
+                }
+            };
+    }
+}
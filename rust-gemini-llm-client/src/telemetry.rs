@@ -0,0 +1,78 @@
+// `tracing` instrumentation (only compiled with the `tracing` feature).
+//
+// Spans are like structured log scopes: everything that happens while the future runs
+// is attached to the span, and subscribers (console, OpenTelemetry, ...) decide what to
+// do with it. The crate only emits data; the application installs the subscriber.
+//
+// Never record the API key or the prompt text. The key is part of the request URL,
+// so raw reqwest errors are not recorded either, only the error kind.
+use std::future::Future;
+use std::time::Instant;
+
+use tracing::field::Empty;
+use tracing::{Instrument, Span};
+
+use crate::{GeminiError, GenerateContentRequest, Part};
+
+/// The `gemini.generate_content` span. Fields marked `Empty` are filled in later.
+pub(crate) fn generate_span(model: &str, request: &GenerateContentRequest) -> Span {
+    tracing::info_span!(
+        "gemini.generate_content",
+        model = model,
+        prompt_chars = prompt_chars(request),
+        status = Empty,
+        retries = Empty,
+        elapsed_ms = Empty,
+        error = Empty,
+    )
+}
+
+/// Run `work` inside `span`, then record how long it took and how it ended.
+pub(crate) async fn instrument<T>(
+    work: impl Future<Output = Result<T, GeminiError>>,
+    span: Span,
+) -> Result<T, GeminiError> {
+    let started = Instant::now();
+    // .instrument() enters the span every time the future is polled
+    let result = work.instrument(span.clone()).await;
+    span.record("elapsed_ms", started.elapsed().as_millis() as u64);
+    if let Err(error) = &result {
+        span.record("error", error_kind(error));
+    }
+    result
+}
+
+/// Record one HTTP attempt on the current span (later attempts overwrite earlier ones).
+pub(crate) fn record_attempt(status: u16, attempt: u32) {
+    let span = Span::current();
+    span.record("status", status);
+    span.record("retries", attempt);
+}
+
+/// Length of the user's text in characters (not bytes), without the text itself.
+fn prompt_chars(request: &GenerateContentRequest) -> usize {
+    request
+        .contents
+        .iter()
+        .flat_map(|content| &content.parts)
+        .map(|part| match part {
+            Part::Text { text } => text.chars().count(),
+            Part::InlineData { .. } => 0,
+        })
+        .sum()
+}
+
+fn error_kind(error: &GeminiError) -> &'static str {
+    match error {
+        GeminiError::MissingApiKey => "missing_api_key",
+        GeminiError::InvalidConfig(_) => "invalid_config",
+        GeminiError::Http { .. } => "http",
+        GeminiError::RateLimited { .. } => "rate_limited",
+        GeminiError::Network(_) => "network",
+        GeminiError::Parse(_) => "parse",
+        GeminiError::InvalidJson { .. } => "invalid_json",
+        GeminiError::Blocked { .. } => "blocked",
+        GeminiError::Timeout => "timeout",
+        GeminiError::Cancelled => "cancelled",
+    }
+}
@@ -0,0 +1,70 @@
+// Function calling: describe functions the model may ask you to run.
+//
+// The model never runs code itself. It answers with a `functionCall` part naming one
+// of the declared functions plus JSON arguments, and your program decides what to do
+// (e.g. the physics chat spawns a ball at the requested position).
+use serde::{Deserialize, Serialize};
+
+/// A group of functions offered to the model (the API's `Tool` object).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Tool {
+    pub function_declarations: Vec<FunctionDeclaration>,
+}
+
+impl Tool {
+    /// A tool offering `functions`
+    pub fn new(functions: Vec<FunctionDeclaration>) -> Self {
+        Self {
+            function_declarations: functions,
+        }
+    }
+}
+
+/// One function the model may call.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FunctionDeclaration {
+    /// Name the model uses in its `functionCall` (letters, digits, `_`)
+    pub name: String,
+    /// What the function does; the model reads this to decide when to call it
+    pub description: String,
+    /// JSON schema of the arguments, e.g.
+    /// `{"type": "object", "properties": {"x": {"type": "number"}}, "required": ["x"]}`
+    // Functions without arguments leave the key out entirely
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub parameters: Option<serde_json::Value>,
+}
+
+impl FunctionDeclaration {
+    /// A function without parameters
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            parameters: None,
+        }
+    }
+
+    /// Describe the arguments with a JSON schema (builder style)
+    pub fn with_parameters(mut self, schema: serde_json::Value) -> Self {
+        self.parameters = Some(schema);
+        self
+    }
+}
+
+/// A call the model wants you to make: `{"name": "add_ball", "args": {"x": 1.5, "y": 4}}`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FunctionCall {
+    pub name: String,
+    // serde_json::Value is "any JSON" (like a Map<String, Object> or a Python dict);
+    // use serde_json::from_value to turn it into your own struct.
+    #[serde(default)]
+    pub args: serde_json::Value,
+}
+
+/// What `generate_content_with_tools` produced: an answer, or a request to call functions.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelReply {
+    Text(String),
+    /// One or more calls, in the order the model listed them
+    FunctionCalls(Vec<FunctionCall>),
+}
@@ -0,0 +1,139 @@
+//! Batch prompts: order, partial failures, and the concurrency limit.
+
+use std::time::{Duration, Instant};
+
+use rust_gemini_llm_client::{BatchOptions, GeminiClient, GeminiError, RetryPolicy};
+use wiremock::matchers::{body_string_contains, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn sse_body(text: &str) -> String {
+    format!(
+        "data: {{\"candidates\": [{{\"content\": {{\"parts\": [{{\"text\": \"{}\"}}]}}}}]}}\n\n",
+        text
+    )
+}
+
+async fn answer(server: &MockServer, prompt: &str, response: ResponseTemplate) {
+    Mock::given(method("POST"))
+        .and(body_string_contains(prompt))
+        .respond_with(response)
+        .mount(server)
+        .await;
+}
+
+fn client_for(server: &MockServer) -> GeminiClient {
+    GeminiClient::new("test-key")
+        .with_model("test-model")
+        .with_base_url(&server.uri())
+        .expect("valid base URL")
+        .with_retry_policy(RetryPolicy::none())
+}
+
+#[tokio::test]
+async fn one_failure_does_not_abort_the_batch() {
+    let server = MockServer::start().await;
+    // The first prompt is the slowest, so results finish out of order
+    answer(
+        &server,
+        "first",
+        ResponseTemplate::new(200)
+            .set_body_string(sse_body("one"))
+            .set_delay(Duration::from_millis(100)),
+    )
+    .await;
+    answer(
+        &server,
+        "second",
+        ResponseTemplate::new(400).set_body_string("bad prompt"),
+    )
+    .await;
+    answer(
+        &server,
+        "third",
+        ResponseTemplate::new(200).set_body_string(sse_body("three")),
+    )
+    .await;
+
+    let prompts = vec![
+        "first".to_string(),
+        "second".to_string(),
+        "third".to_string(),
+    ];
+    let results = client_for(&server)
+        .generate_content_batch(prompts, BatchOptions::default())
+        .await;
+
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap(), "one");
+    assert!(
+        matches!(results[1], Err(GeminiError::Http { status: 400, .. })),
+        "{:?}",
+        results[1]
+    );
+    assert_eq!(results[2].as_ref().unwrap(), "three");
+}
+
+#[tokio::test]
+async fn concurrency_is_bounded() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(sse_body("ok"))
+                .set_delay(Duration::from_millis(150)),
+        )
+        .expect(4)
+        .mount(&server)
+        .await;
+
+    let prompts: Vec<String> = (0..4).map(|i| format!("prompt {}", i)).collect();
+    let started = Instant::now();
+    let results = client_for(&server)
+        .generate_content_batch(prompts, BatchOptions::with_max_concurrency(2))
+        .await;
+
+    assert!(results.iter().all(|r| r.is_ok()));
+    // Two at a time means at least two rounds of 150 ms
+    assert!(
+        started.elapsed() >= Duration::from_millis(300),
+        "took {:?}",
+        started.elapsed()
+    );
+}
+
+#[tokio::test]
+async fn retry_can_be_turned_off_per_batch() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .expect(1) // retrying would hit the server again
+        .mount(&server)
+        .await;
+
+    let client = client_for(&server).with_retry_policy(RetryPolicy {
+        max_retries: 3,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(5),
+        jitter: false,
+    });
+    let options = BatchOptions {
+        max_concurrency: 1,
+        retry: false,
+    };
+    let results = client
+        .generate_content_batch(vec!["x".to_string()], options)
+        .await;
+    assert!(matches!(
+        results[0],
+        Err(GeminiError::Http { status: 503, .. })
+    ));
+}
+
+#[tokio::test]
+async fn empty_batch_is_empty() {
+    let server = MockServer::start().await;
+    assert!(client_for(&server)
+        .generate_content_batch(Vec::new(), BatchOptions::default())
+        .await
+        .is_empty());
+}
@@ -0,0 +1,122 @@
+//! Smoke tests for the blocking API (run with `--features blocking`).
+//!
+//! These are plain `#[test]`s: no async runtime on the calling thread, just like a game loop.
+
+use std::sync::Arc;
+use std::thread;
+
+use rust_gemini_llm_client::blocking::GeminiClient;
+use rust_gemini_llm_client::{GeminiError, RetryPolicy};
+use tokio::runtime::Runtime;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const SSE_BODY: &str =
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"pong\"}]}}]}\n\n";
+
+/// Start a mock Gemini server on its own multi-threaded runtime. The runtime's worker
+/// threads keep serving after `block_on` returns, as long as the runtime is alive.
+fn mock_gemini(status: u16) -> (Runtime, MockServer) {
+    let runtime = Runtime::new().expect("runtime");
+    let server = runtime.block_on(async {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/models/test-model:streamGenerateContent"))
+            .respond_with(ResponseTemplate::new(status).set_body_string(SSE_BODY))
+            .mount(&server)
+            .await;
+        server
+    });
+    (runtime, server)
+}
+
+fn client_for(server: &MockServer) -> GeminiClient {
+    GeminiClient::from_client(
+        rust_gemini_llm_client::GeminiClient::new("test-key")
+            .with_model("test-model")
+            .with_base_url(&server.uri())
+            .expect("valid base URL")
+            .with_retry_policy(RetryPolicy::none()),
+    )
+}
+
+#[test]
+fn generate_content_without_a_runtime() {
+    let (_runtime, server) = mock_gemini(200);
+    let client = client_for(&server);
+    assert_eq!(client.generate_content("ping").unwrap(), "pong");
+    assert_eq!(client.inner().model(), "test-model");
+}
+
+#[test]
+fn errors_match_the_async_path() {
+    let (_runtime, server) = mock_gemini(400);
+    let err = client_for(&server).generate_content("ping").unwrap_err();
+    assert!(
+        matches!(err, GeminiError::Http { status: 400, .. }),
+        "got {:?}",
+        err
+    );
+}
+
+#[test]
+fn one_client_can_be_shared_across_threads() {
+    let (_runtime, server) = mock_gemini(200);
+    let client = Arc::new(client_for(&server));
+
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let client = Arc::clone(&client);
+            thread::spawn(move || client.generate_content(&format!("ping {}", i)))
+        })
+        .collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap().unwrap(), "pong");
+    }
+}
+
+#[test]
+fn a_proxy_set_on_the_async_client_is_kept() {
+    // Nothing answers at gemini.invalid: only the proxy, the mock, can reply
+    let (_runtime, proxy) = mock_gemini(200);
+    let client = GeminiClient::from_client(
+        rust_gemini_llm_client::GeminiClient::new("test-key")
+            .with_model("test-model")
+            .with_base_url("http://gemini.invalid")
+            .expect("valid base URL")
+            .with_proxy(&proxy.uri())
+            .expect("valid proxy URL")
+            .with_retry_policy(RetryPolicy::none()),
+    );
+    assert_eq!(client.generate_content("ping").unwrap(), "pong");
+}
+
+#[test]
+fn a_custom_http_client_is_kept() {
+    let runtime = Runtime::new().expect("runtime");
+    let server = runtime.block_on(async {
+        let server = MockServer::start().await;
+        // Only requests from the custom client carry this header
+        Mock::given(method("POST"))
+            .and(header("x-client", "custom"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(SSE_BODY))
+            .mount(&server)
+            .await;
+        server
+    });
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("x-client", "custom".parse().unwrap());
+    let http = reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .unwrap();
+
+    let client = GeminiClient::from_client(
+        rust_gemini_llm_client::GeminiClient::new("test-key")
+            .with_base_url(&server.uri())
+            .expect("valid base URL")
+            .with_http_client(http)
+            .with_retry_policy(RetryPolicy::none()),
+    );
+    assert_eq!(client.generate_content("ping").unwrap(), "pong");
+}
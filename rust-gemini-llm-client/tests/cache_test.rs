@@ -0,0 +1,136 @@
+//! Response caching: hits skip HTTP, misses fill the cache, LRU eviction, file persistence.
+
+use std::sync::Arc;
+
+use rust_gemini_llm_client::{FileCache, GeminiClient, MemoryCache, ResponseCache, RetryPolicy};
+use wiremock::matchers::{body_string_contains, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// The answer arrives in three SSE chunks; the cache must hold the joined text
+const STREAMED: &str = concat!(
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hello\"}]}}]}\n\n",
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \", \"}]}}]}\n\n",
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"world\"}]}, \"finishReason\": \"STOP\"}]}\n\n",
+);
+
+fn client_for(server: &MockServer) -> GeminiClient {
+    GeminiClient::new("test-key")
+        .with_model("test-model")
+        .with_base_url(&server.uri())
+        .expect("valid base URL")
+        .with_retry_policy(RetryPolicy::none())
+}
+
+#[tokio::test]
+async fn second_identical_request_is_a_hit() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(STREAMED))
+        .expect(1) // the second call must not reach the server
+        .mount(&server)
+        .await;
+
+    let cache = Arc::new(MemoryCache::new(10));
+    let client = client_for(&server).with_cache(cache.clone());
+
+    assert_eq!(client.generate_content("hi").await.unwrap(), "Hello, world");
+    assert_eq!(client.generate_content("hi").await.unwrap(), "Hello, world");
+    assert_eq!(cache.len(), 1);
+}
+
+#[tokio::test]
+async fn streamed_answer_is_cached_as_the_joined_text() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(STREAMED))
+        .mount(&server)
+        .await;
+
+    let cache = Arc::new(MemoryCache::new(10));
+    let client = client_for(&server).with_cache(cache.clone());
+    client.generate_content("hi").await.unwrap();
+
+    assert_eq!(
+        cache.get(&client.cache_key("hi")).as_deref(),
+        Some("Hello, world")
+    );
+}
+
+#[tokio::test]
+async fn different_prompt_or_options_is_a_miss() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("first"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(STREAMED))
+        .expect(2) // plain, then again with a system instruction
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("second"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(STREAMED))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let cache = Arc::new(MemoryCache::new(10));
+    let client = client_for(&server).with_cache(cache.clone());
+    client.generate_content("first").await.unwrap();
+    client.generate_content("second").await.unwrap();
+
+    let tutor = client_for(&server)
+        .with_system_instruction("Be brief")
+        .with_cache(cache.clone());
+    assert_ne!(tutor.cache_key("first"), client.cache_key("first"));
+    tutor.generate_content("first").await.unwrap();
+    assert_eq!(cache.len(), 3);
+}
+
+#[tokio::test]
+async fn failures_are_not_cached() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let cache = Arc::new(MemoryCache::new(10));
+    let client = client_for(&server).with_cache(cache.clone());
+    assert!(client.generate_content("hi").await.is_err());
+    assert!(client.generate_content("hi").await.is_err());
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn memory_cache_evicts_the_least_recently_used() {
+    let cache = MemoryCache::new(2);
+    cache.put("a", "1");
+    cache.put("b", "2");
+    // Reading "a" makes "b" the oldest
+    assert_eq!(cache.get("a").as_deref(), Some("1"));
+    cache.put("c", "3");
+
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get("b"), None);
+    assert_eq!(cache.get("a").as_deref(), Some("1"));
+    assert_eq!(cache.get("c").as_deref(), Some("3"));
+}
+
+#[test]
+fn file_cache_survives_reopening() {
+    let path = std::env::temp_dir().join(format!("gemini_cache_test_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let cache = FileCache::open(&path).expect("open");
+    assert!(cache.is_empty());
+    cache.put("k1", "first answer");
+    cache.put("k2", "line\nbreaks survive");
+    cache.put("k1", "newer answer");
+
+    let reopened = FileCache::open(&path).expect("reopen");
+    assert_eq!(reopened.len(), 2);
+    assert_eq!(reopened.get("k1").as_deref(), Some("newer answer"));
+    assert_eq!(reopened.get("k2").as_deref(), Some("line\nbreaks survive"));
+
+    std::fs::remove_file(&path).expect("cleanup");
+}
@@ -0,0 +1,34 @@
+//! Tests for `GeminiClient` configuration.
+//!
+//! These tests never hit the network; they only check how requests would be built.
+
+use rust_gemini_llm_client::GeminiClient;
+
+#[test]
+fn endpoint_url_uses_chosen_model() {
+    let client = GeminiClient::new("test-key").with_model("gemini-2.0-flash");
+
+    let url = client.endpoint_url("streamGenerateContent");
+
+    assert_eq!(client.model(), "gemini-2.0-flash");
+    assert!(
+        url.contains("/models/gemini-2.0-flash:streamGenerateContent"),
+        "unexpected url: {}",
+        url
+    );
+    assert!(url.ends_with("?key=test-key"), "unexpected url: {}", url);
+}
+
+#[test]
+fn with_model_overrides_previous_model() {
+    let client = GeminiClient::new("test-key")
+        .with_model("gemini-1.5-pro")
+        .with_model("gemini-2.0-flash");
+
+    assert!(client
+        .endpoint_url("generateContent")
+        .contains("gemini-2.0-flash:generateContent"));
+    assert!(!client
+        .endpoint_url("generateContent")
+        .contains("gemini-1.5-pro"));
+}
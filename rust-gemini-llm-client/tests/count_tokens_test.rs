@@ -0,0 +1,89 @@
+//! countTokens: request serialization and error handling against a mock server.
+
+use rust_gemini_llm_client::{ChatMessage, GeminiClient, GeminiError, RetryPolicy};
+use serde_json::json;
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const COUNT_PATH: &str = "/models/test-model:countTokens";
+
+fn client_for(server: &MockServer) -> GeminiClient {
+    GeminiClient::new("test-key")
+        .with_model("test-model")
+        .with_base_url(&server.uri())
+        .expect("valid base URL")
+        .with_retry_policy(RetryPolicy::none())
+}
+
+#[test]
+fn single_prompt_uses_the_generate_content_shape() {
+    let client = GeminiClient::new("test-key");
+    assert_eq!(
+        client.count_tokens_body("Hello"),
+        json!({"contents": [{"role": "user", "parts": [{"text": "Hello"}]}]})
+    );
+}
+
+#[test]
+fn message_history_keeps_roles_and_order() {
+    let history = vec![
+        ChatMessage::user("What is Rust?"),
+        ChatMessage::model("A systems programming language."),
+        ChatMessage::user("Is it fast?"),
+    ];
+    let client = GeminiClient::new("test-key");
+    assert_eq!(
+        client.count_tokens_body(&history),
+        json!({"contents": [
+            {"role": "user", "parts": [{"text": "What is Rust?"}]},
+            {"role": "model", "parts": [{"text": "A systems programming language."}]},
+            {"role": "user", "parts": [{"text": "Is it fast?"}]}
+        ]})
+    );
+    // A slice works the same as the Vec
+    assert_eq!(
+        client.count_tokens_body(&history[..1]),
+        client.count_tokens_body("What is Rust?")
+    );
+}
+
+#[tokio::test]
+async fn returns_total_tokens() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(COUNT_PATH))
+        .and(body_json(
+            json!({"contents": [{"role": "user", "parts": [{"text": "Hello"}]}]}),
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"totalTokens": 31})))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let total = client_for(&server)
+        .count_tokens("Hello")
+        .await
+        .expect("count");
+    assert_eq!(total, 31);
+}
+
+#[tokio::test]
+async fn bad_request_is_http_error() {
+    let server = MockServer::start().await;
+    let body = r#"{"error": {"code": 400, "message": "* CountTokensRequest.contents: contents is not specified"}}"#;
+    Mock::given(method("POST"))
+        .and(path(COUNT_PATH))
+        .respond_with(ResponseTemplate::new(400).set_body_string(body))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let empty: Vec<ChatMessage> = Vec::new();
+    match client_for(&server).count_tokens(&empty).await.unwrap_err() {
+        GeminiError::Http { status, body: b } => {
+            assert_eq!(status, 400);
+            assert_eq!(b, body);
+        }
+        other => panic!("expected Http, got {:?}", other),
+    }
+}
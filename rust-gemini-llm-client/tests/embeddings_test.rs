@@ -0,0 +1,92 @@
+//! Embedding requests, batch responses and cosine similarity.
+
+use rust_gemini_llm_client::{
+    cosine_similarity, parse_batch_embeddings, GeminiClient, RetryPolicy, DEFAULT_EMBEDDING_MODEL,
+};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[test]
+fn single_embedding_request_shape() {
+    let client = GeminiClient::new("test-key");
+    assert_eq!(client.embedding_model(), DEFAULT_EMBEDDING_MODEL);
+    assert_eq!(
+        client.embed_content_body("hello"),
+        json!({
+            "model": "models/text-embedding-004",
+            "content": {"parts": [{"text": "hello"}]}
+        })
+    );
+}
+
+#[test]
+fn batch_request_repeats_the_overridden_model() {
+    let client = GeminiClient::new("test-key").with_embedding_model("gemini-embedding-001");
+    assert_eq!(
+        client.embed_contents_body(&["first", "second"]),
+        json!({"requests": [
+            {"model": "models/gemini-embedding-001", "content": {"parts": [{"text": "first"}]}},
+            {"model": "models/gemini-embedding-001", "content": {"parts": [{"text": "second"}]}}
+        ]})
+    );
+}
+
+#[test]
+fn parses_multiple_embeddings_in_order() {
+    let body = r#"{
+      "embeddings": [
+        {"values": [0.013168523, -0.008711934, -0.046782676]},
+        {"values": [-0.010486893, 0.0283457, 0.051219]}
+      ]
+    }"#;
+    let vectors = parse_batch_embeddings(body).expect("valid body");
+    assert_eq!(vectors.len(), 2);
+    assert_eq!(vectors[0], vec![0.013168523, -0.008711934, -0.046782676]);
+    assert_eq!(vectors[1][2], 0.051219);
+}
+
+#[test]
+fn cosine_similarity_basics() {
+    assert!((cosine_similarity(&[1.0, 2.0], &[2.0, 4.0]) - 1.0).abs() < 1e-6);
+    assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 3.0]).abs() < 1e-6);
+    assert!((cosine_similarity(&[1.0, 0.0], &[-1.0, 0.0]) + 1.0).abs() < 1e-6);
+    // Degenerate inputs rank last instead of producing NaN
+    assert_eq!(cosine_similarity(&[1.0, 2.0], &[1.0]), 0.0);
+    assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+}
+
+#[tokio::test]
+async fn embed_contents_calls_the_batch_endpoint() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/models/text-embedding-004:batchEmbedContents"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "embeddings": [{"values": [1.0, 0.0]}, {"values": [0.0, 1.0]}]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/models/text-embedding-004:embedContent"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"embedding": {"values": [0.5, 0.5]}})),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = GeminiClient::new("test-key")
+        .with_base_url(&server.uri())
+        .expect("valid base URL")
+        .with_retry_policy(RetryPolicy::none());
+    let vectors = client.embed_contents(&["a", "b"]).await.expect("batch");
+    assert_eq!(vectors, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+    assert_eq!(
+        client.embed_content("c").await.expect("single"),
+        vec![0.5, 0.5]
+    );
+
+    // An empty batch never reaches the server (the mock expects exactly one batch call)
+    assert!(client.embed_contents(&[]).await.expect("empty").is_empty());
+}
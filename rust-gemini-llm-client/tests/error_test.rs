@@ -0,0 +1,95 @@
+//! `GeminiError` construction from canned HTTP responses and bodies.
+
+use rust_gemini_llm_client::{parse_stream_body, GeminiClient, GeminiError};
+
+#[test]
+fn missing_api_key() {
+    std::env::remove_var("GEMINI_API_KEY");
+    let err = GeminiClient::from_env().err().expect("expected an error");
+    assert!(matches!(err, GeminiError::MissingApiKey));
+}
+
+#[test]
+fn http_error_keeps_status_and_body() {
+    let body = r#"{"error": {"code": 400, "message": "API key not valid"}}"#.to_string();
+    let err = GeminiError::from_status(400, None, body.clone());
+
+    match &err {
+        GeminiError::Http { status, body: b } => {
+            assert_eq!(*status, 400);
+            assert_eq!(b, &body);
+        }
+        other => panic!("expected Http, got {:?}", other),
+    }
+    assert_eq!(err.status(), Some(400));
+}
+
+#[test]
+fn too_many_requests_is_rate_limited() {
+    let err = GeminiError::from_status(429, Some("30"), String::new());
+    assert!(matches!(
+        err,
+        GeminiError::RateLimited {
+            retry_after: Some(30)
+        }
+    ));
+
+    // An HTTP-date Retry-After (or none at all) still counts as rate limiting
+    let err = GeminiError::from_status(429, Some("Wed, 21 Oct 2015 07:28:00 GMT"), String::new());
+    assert!(matches!(
+        err,
+        GeminiError::RateLimited { retry_after: None }
+    ));
+}
+
+#[tokio::test]
+async fn network_error_wraps_reqwest() {
+    // Port 1 is reserved and nothing listens there, so the connection is refused
+    let reqwest_err = reqwest::get("http://127.0.0.1:1/").await.unwrap_err();
+    let err = GeminiError::from(reqwest_err);
+
+    assert!(matches!(err, GeminiError::Network(_)));
+    assert!(std::error::Error::source(&err).is_some());
+}
+
+#[test]
+fn malformed_body_is_parse_error() {
+    let err = parse_stream_body("data: {\"candidates\": [oops\n\n").unwrap_err();
+    assert!(matches!(err, GeminiError::Parse(_)), "got {:?}", err);
+}
+
+#[test]
+fn blocked_prompt_is_blocked_error() {
+    let body = "data: {\"promptFeedback\": {\"blockReason\": \"SAFETY\"}}\n\n";
+    match parse_stream_body(body).unwrap_err() {
+        GeminiError::Blocked { reason } => assert_eq!(reason, "SAFETY"),
+        other => panic!("expected Blocked, got {:?}", other),
+    }
+}
+
+#[test]
+fn stream_body_text_is_concatenated() {
+    let body = concat!(
+        "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hello\"}], \"role\": \"model\"}}]}\r\n\r\n",
+        "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \", world\"}], \"role\": \"model\"}, \"finishReason\": \"STOP\"}]}\r\n\r\n",
+    );
+    assert_eq!(parse_stream_body(body).unwrap(), "Hello, world");
+}
+
+#[tokio::test]
+async fn network_errors_do_not_reveal_the_api_key() {
+    // Nothing listens on port 1. The key travels in the URL (`?key=...`), which reqwest
+    // would print in its message.
+    let failed = reqwest::get("http://127.0.0.1:1/v1beta/models?key=secret-key-123")
+        .await
+        .unwrap_err();
+    let err = GeminiError::from(failed);
+
+    assert!(matches!(err, GeminiError::Network(_)), "got {:?}", err);
+    assert!(!err.to_string().contains("secret-key-123"), "{}", err);
+    assert!(
+        !format!("{:?}", err).contains("secret-key-123"),
+        "{:?}",
+        err
+    );
+}
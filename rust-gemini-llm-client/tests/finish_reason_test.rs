@@ -0,0 +1,48 @@
+//! Finish reasons and safety blocks parsed from canned streaming bodies.
+
+use rust_gemini_llm_client::{parse_stream_body, parse_stream_result, GeminiError};
+
+const SAFETY_BLOCKED: &str = concat!(
+    "data: {\"candidates\": [{\"finishReason\": \"SAFETY\", \"safetyRatings\": ",
+    "[{\"category\": \"HARM_CATEGORY_DANGEROUS_CONTENT\", \"probability\": \"HIGH\", \"blocked\": true}]}]}\n\n",
+);
+
+const MAX_TOKENS: &str = concat!(
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Once upon \"}], \"role\": \"model\"}}]}\n\n",
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"a time\"}], \"role\": \"model\"}, ",
+    "\"finishReason\": \"MAX_TOKENS\"}]}\n\n",
+);
+
+#[test]
+fn safety_blocked_candidate_is_reported() {
+    let result = parse_stream_result(SAFETY_BLOCKED).expect("valid body");
+    assert!(result.blocked);
+    assert_eq!(result.finish_reason.as_deref(), Some("SAFETY"));
+    assert!(result.text.is_empty());
+
+    // The plain-text API turns it into an error instead of an empty string
+    match parse_stream_body(SAFETY_BLOCKED).unwrap_err() {
+        GeminiError::Blocked { reason } => assert_eq!(reason, "SAFETY"),
+        other => panic!("expected Blocked, got {:?}", other),
+    }
+}
+
+#[test]
+fn max_tokens_keeps_the_partial_text() {
+    let result = parse_stream_result(MAX_TOKENS).expect("valid body");
+    assert_eq!(result.text, "Once upon a time");
+    assert_eq!(result.finish_reason.as_deref(), Some("MAX_TOKENS"));
+    assert!(result.is_truncated());
+    assert!(!result.blocked);
+
+    assert_eq!(parse_stream_body(MAX_TOKENS).unwrap(), "Once upon a time");
+}
+
+#[test]
+fn normal_stop_is_not_truncated() {
+    let body = "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hi\"}]}, \"finishReason\": \"STOP\"}]}\n\n";
+    let result = parse_stream_result(body).expect("valid body");
+    assert_eq!(result.finish_reason.as_deref(), Some("STOP"));
+    assert!(!result.is_truncated());
+    assert!(!result.blocked);
+}
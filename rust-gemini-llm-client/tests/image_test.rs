@@ -0,0 +1,45 @@
+//! Multimodal (image + text) request bodies, and the unchanged text-only shape.
+
+use rust_gemini_llm_client::GeminiClient;
+use serde_json::json;
+
+// A complete 1x1 red PNG
+const TINY_PNG: [u8; 70] = [
+    0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1f, 0x15, 0xc4,
+    0x89, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xf8, 0xcf, 0xc0, 0xf0,
+    0x1f, 0x00, 0x05, 0x00, 0x01, 0xff, 0x89, 0x99, 0x3d, 0x1d, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45,
+    0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+];
+const TINY_PNG_BASE64: &str =
+    "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR4nGP4z8DwHwAFAAH/iZk9HQAAAABJRU5ErkJggg==";
+
+#[test]
+fn image_bytes_become_base64_inline_data() {
+    let client = GeminiClient::new("test-key");
+    let body = client.image_request_body("What color is this pixel?", &TINY_PNG, "image/png");
+
+    assert_eq!(
+        body,
+        json!({
+            "contents": [{
+                "role": "user",
+                "parts": [
+                    {"inline_data": {"mime_type": "image/png", "data": TINY_PNG_BASE64}},
+                    {"text": "What color is this pixel?"}
+                ]
+            }]
+        })
+    );
+}
+
+#[test]
+fn text_only_request_serializes_exactly_as_before() {
+    // Compare the raw string so nothing extra (e.g. "inline_data": null) sneaks into text parts
+    let client = GeminiClient::new("test-key").with_system_instruction("Be brief");
+    let raw = serde_json::to_string(&client.request_body("Hello")).unwrap();
+    assert_eq!(
+        raw,
+        r#"{"contents":[{"parts":[{"text":"Hello"}],"role":"user"}],"system_instruction":{"parts":[{"text":"Be brief"}]}}"#
+    );
+}
@@ -0,0 +1,119 @@
+//! JSON mode: request shape, typed parsing, and malformed model output.
+
+use rust_gemini_llm_client::{GeminiClient, GeminiError, RetryPolicy};
+use serde::Deserialize;
+use serde_json::json;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const STREAM_PATH: &str = "/models/test-model:streamGenerateContent";
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct City {
+    name: String,
+    population: u64,
+}
+
+fn schema() -> serde_json::Value {
+    json!({
+        "type": "object",
+        "properties": {"name": {"type": "string"}, "population": {"type": "integer"}},
+        "required": ["name", "population"]
+    })
+}
+
+// The model's JSON arrives as ordinary text parts, possibly split across chunks
+fn sse_text_chunks(chunks: &[&str]) -> String {
+    chunks
+        .iter()
+        .map(|text| {
+            let chunk = json!({"candidates": [{"content": {"parts": [{"text": text}]}}]});
+            format!("data: {}\n\n", chunk)
+        })
+        .collect()
+}
+
+fn client_for(server: &MockServer) -> GeminiClient {
+    GeminiClient::new("test-key")
+        .with_model("test-model")
+        .with_base_url(&server.uri())
+        .expect("valid base URL")
+        .with_retry_policy(RetryPolicy::none())
+}
+
+#[test]
+fn json_mode_sets_the_generation_config() {
+    let body = GeminiClient::new("test-key").json_request_body("Largest city in Canada", schema());
+    assert_eq!(
+        body,
+        json!({
+            "contents": [{"role": "user", "parts": [{"text": "Largest city in Canada"}]}],
+            "generation_config": {
+                "response_mime_type": "application/json",
+                "response_schema": schema()
+            }
+        })
+    );
+    // Plain requests don't send a generation config at all
+    assert!(GeminiClient::new("test-key")
+        .request_body("Hi")
+        .get("generation_config")
+        .is_none());
+}
+
+#[tokio::test]
+async fn json_answer_deserializes_into_the_struct() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(STREAM_PATH))
+        .and(body_partial_json(
+            json!({"generation_config": {"response_mime_type": "application/json"}}),
+        ))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string(sse_text_chunks(&[
+                "{\"name\": \"Toronto\", ",
+                "\"population\": 2794356}",
+            ])),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let city: City = client_for(&server)
+        .generate_json("Largest city in Canada", schema())
+        .await
+        .expect("valid JSON answer");
+    assert_eq!(
+        city,
+        City {
+            name: "Toronto".to_string(),
+            population: 2_794_356
+        }
+    );
+}
+
+#[tokio::test]
+async fn malformed_json_is_invalid_json_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(STREAM_PATH))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_string(sse_text_chunks(&["{\"name\": \"Toronto\", \"popul"])),
+        )
+        .mount(&server)
+        .await;
+
+    let err = client_for(&server)
+        .generate_json::<City>("Largest city in Canada", schema())
+        .await
+        .unwrap_err();
+    match &err {
+        GeminiError::InvalidJson { text, .. } => {
+            assert_eq!(text, "{\"name\": \"Toronto\", \"popul")
+        }
+        other => panic!("expected InvalidJson, got {:?}", other),
+    }
+    // The serde error is kept as the source for error reporters
+    assert!(std::error::Error::source(&err).is_some());
+}
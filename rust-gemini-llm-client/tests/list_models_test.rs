@@ -0,0 +1,98 @@
+//! Models list parsing (captured JSON) and paging against a mock server.
+
+use rust_gemini_llm_client::{parse_models_list, GeminiClient, RetryPolicy};
+use serde_json::json;
+use wiremock::matchers::{method, path, query_param, query_param_is_missing};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Trimmed from a real `GET /v1beta/models` response
+const CAPTURED: &str = r#"{
+  "models": [
+    {
+      "name": "models/embedding-001",
+      "version": "001",
+      "displayName": "Embedding 001",
+      "description": "Obtain a distributed representation of a text.",
+      "inputTokenLimit": 2048,
+      "outputTokenLimit": 1,
+      "supportedGenerationMethods": ["embedContent"]
+    },
+    {
+      "name": "models/gemini-flash-latest",
+      "version": "2.5",
+      "displayName": "Gemini Flash Latest",
+      "description": "Latest release of Gemini Flash",
+      "inputTokenLimit": 1048576,
+      "outputTokenLimit": 65536,
+      "supportedGenerationMethods": ["generateContent", "countTokens", "createCachedContent", "batchGenerateContent"],
+      "temperature": 1,
+      "topP": 0.95,
+      "topK": 64,
+      "maxTemperature": 2,
+      "thinking": true
+    }
+  ],
+  "nextPageToken": "Cg1nZW1pbmktMS41LXBybw=="
+}"#;
+
+#[test]
+fn parses_a_captured_models_list() {
+    let models = parse_models_list(CAPTURED).expect("valid list");
+    assert_eq!(models.len(), 2);
+
+    let flash = &models[1];
+    assert_eq!(flash.name, "models/gemini-flash-latest");
+    assert_eq!(flash.id(), "gemini-flash-latest");
+    assert_eq!(flash.display_name, "Gemini Flash Latest");
+    assert_eq!(flash.input_token_limit, 1_048_576);
+    assert_eq!(flash.output_token_limit, 65_536);
+    assert!(flash.supports("countTokens"));
+}
+
+#[test]
+fn filters_models_that_can_generate_content() {
+    let models = parse_models_list(CAPTURED).expect("valid list");
+    let chat: Vec<&str> = models
+        .iter()
+        .filter(|m| m.supports_generate_content())
+        .map(|m| m.id())
+        .collect();
+    assert_eq!(chat, vec!["gemini-flash-latest"]);
+    assert!(models[0].supports("embedContent"));
+}
+
+#[tokio::test]
+async fn follows_next_page_token() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/models"))
+        .and(query_param("key", "test-key"))
+        .and(query_param_is_missing("pageToken"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{"name": "models/a", "supportedGenerationMethods": ["generateContent"]}],
+            "nextPageToken": "page-2"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/models"))
+        .and(query_param("pageToken", "page-2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{"name": "models/b"}]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let models = GeminiClient::new("test-key")
+        .with_base_url(&server.uri())
+        .expect("valid base URL")
+        .with_retry_policy(RetryPolicy::none())
+        .list_models()
+        .await
+        .expect("list");
+    let ids: Vec<&str> = models.iter().map(|m| m.id()).collect();
+    assert_eq!(ids, vec!["a", "b"]);
+    assert!(!models[1].supports_generate_content());
+}
@@ -0,0 +1,25 @@
+//! `GEMINI_MODEL` handling lives in its own test binary because it mutates the
+//! process environment, which would race with tests running in parallel threads.
+
+use rust_gemini_llm_client::{GeminiClient, DEFAULT_MODEL};
+
+#[test]
+fn gemini_model_env_var_sets_default_model() {
+    std::env::remove_var("GEMINI_MODEL");
+    assert_eq!(GeminiClient::new("k").model(), DEFAULT_MODEL);
+
+    std::env::set_var("GEMINI_MODEL", "gemini-2.0-flash");
+    let client = GeminiClient::new("k");
+    assert_eq!(client.model(), "gemini-2.0-flash");
+    assert!(client
+        .endpoint_url("streamGenerateContent")
+        .contains("gemini-2.0-flash"));
+
+    // An explicit model always wins over the env var
+    assert_eq!(
+        client.with_model("gemini-1.5-pro").model(),
+        "gemini-1.5-pro"
+    );
+
+    std::env::remove_var("GEMINI_MODEL");
+}
@@ -0,0 +1,93 @@
+//! Exact JSON layout of the generateContent request body.
+
+use rust_gemini_llm_client::{GeminiClient, GenerationOptions};
+use serde_json::json;
+
+#[test]
+fn system_instruction_is_omitted_when_not_set() {
+    let client = GeminiClient::new("test-key");
+    assert_eq!(client.system_instruction(), None);
+
+    let body = client.request_body("Hello");
+    assert_eq!(
+        body,
+        json!({
+            "contents": [{"role": "user", "parts": [{"text": "Hello"}]}]
+        })
+    );
+    assert!(body.get("system_instruction").is_none());
+}
+
+#[test]
+fn system_instruction_uses_the_parts_shape() {
+    let client = GeminiClient::new("test-key")
+        .with_system_instruction("You are a physics tutor, keep answers under 3 sentences");
+    assert_eq!(
+        client.system_instruction(),
+        Some("You are a physics tutor, keep answers under 3 sentences")
+    );
+
+    assert_eq!(
+        client.request_body("Why do balls bounce?"),
+        json!({
+            "contents": [{"role": "user", "parts": [{"text": "Why do balls bounce?"}]}],
+            "system_instruction": {
+                "parts": [{"text": "You are a physics tutor, keep answers under 3 sentences"}]
+            }
+        })
+    );
+}
+
+#[test]
+fn generation_options_go_in_generation_config() {
+    let client = GeminiClient::new("test-key").with_generation_options(GenerationOptions {
+        temperature: Some(0.2),
+        max_output_tokens: Some(256),
+    });
+
+    assert_eq!(
+        client.request_body("Hello"),
+        json!({
+            "contents": [{"role": "user", "parts": [{"text": "Hello"}]}],
+            "generation_config": {"temperature": 0.2f32, "max_output_tokens": 256}
+        })
+    );
+}
+
+#[test]
+fn unset_generation_options_are_left_out() {
+    let client = GeminiClient::new("test-key").with_generation_options(GenerationOptions {
+        max_output_tokens: Some(100),
+        ..GenerationOptions::default()
+    });
+    assert_eq!(
+        client.request_body("Hello")["generation_config"],
+        json!({"max_output_tokens": 100})
+    );
+
+    let client =
+        GeminiClient::new("test-key").with_generation_options(GenerationOptions::default());
+    assert!(client
+        .request_body("Hello")
+        .get("generation_config")
+        .is_none());
+}
+
+#[test]
+fn json_mode_keeps_the_generation_options() {
+    let client = GeminiClient::new("test-key").with_generation_options(GenerationOptions {
+        temperature: Some(0.0),
+        max_output_tokens: None,
+    });
+
+    let body = client.json_request_body("List three colors", json!({"type": "array"}));
+
+    assert_eq!(
+        body["generation_config"],
+        json!({
+            "response_mime_type": "application/json",
+            "response_schema": {"type": "array"},
+            "temperature": 0.0
+        })
+    );
+}
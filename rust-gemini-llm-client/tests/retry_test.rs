@@ -0,0 +1,126 @@
+//! Retry behaviour against a local mock server (wiremock).
+
+use std::time::Duration;
+
+use rust_gemini_llm_client::{GeminiClient, GeminiError, RetryPolicy};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const STREAM_PATH: &str = "/models/test-model:streamGenerateContent";
+
+fn sse_body(text: &str) -> String {
+    format!(
+        "data: {{\"candidates\": [{{\"content\": {{\"parts\": [{{\"text\": \"{}\"}}]}}}}]}}\n\n",
+        text
+    )
+}
+
+fn fast_policy(max_retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_retries,
+        base_delay: Duration::from_millis(5),
+        max_delay: Duration::from_millis(50),
+        jitter: false,
+    }
+}
+
+fn client_for(server: &MockServer, policy: RetryPolicy) -> GeminiClient {
+    GeminiClient::new("test-key")
+        .with_model("test-model")
+        .with_base_url(&server.uri())
+        .expect("valid base URL")
+        .with_retry_policy(policy)
+}
+
+#[tokio::test]
+async fn retries_server_errors_then_succeeds() {
+    let server = MockServer::start().await;
+    // Mocks are tried in the order they were mounted: two 503s, then a success
+    Mock::given(method("POST"))
+        .and(path(STREAM_PATH))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path(STREAM_PATH))
+        .respond_with(ResponseTemplate::new(200).set_body_string(sse_body("hello")))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let text = client_for(&server, fast_policy(3))
+        .generate_content("hi")
+        .await
+        .expect("third attempt should succeed");
+    assert_eq!(text, "hello");
+}
+
+#[tokio::test]
+async fn gives_up_after_max_retries() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let err = client_for(&server, fast_policy(2))
+        .generate_content("hi")
+        .await
+        .expect_err("every attempt is rate limited");
+    assert!(matches!(
+        err,
+        GeminiError::RateLimited {
+            retry_after: Some(0)
+        }
+    ));
+}
+
+#[tokio::test]
+async fn client_errors_are_not_retried() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("bad request"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let err = client_for(&server, fast_policy(3))
+        .generate_content("hi")
+        .await
+        .expect_err("400 is not transient");
+    assert_eq!(err.status(), Some(400));
+}
+
+#[test]
+fn backoff_doubles_and_is_capped() {
+    let policy = fast_policy(5);
+    assert_eq!(policy.delay_for(0, None), Duration::from_millis(5));
+    assert_eq!(policy.delay_for(1, None), Duration::from_millis(10));
+    assert_eq!(policy.delay_for(2, None), Duration::from_millis(20));
+    assert_eq!(policy.delay_for(10, None), Duration::from_millis(50));
+}
+
+#[test]
+fn retry_after_wins_but_is_capped() {
+    let policy = RetryPolicy {
+        max_delay: Duration::from_secs(10),
+        ..RetryPolicy::default()
+    };
+    assert_eq!(policy.delay_for(0, Some(3)), Duration::from_secs(3));
+    assert_eq!(policy.delay_for(0, Some(120)), Duration::from_secs(10));
+}
+
+#[test]
+fn jitter_stays_within_half_and_full_delay() {
+    let policy = RetryPolicy {
+        base_delay: Duration::from_millis(100),
+        ..RetryPolicy::default()
+    };
+    for _ in 0..20 {
+        let delay = policy.delay_for(0, None);
+        assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+    }
+}
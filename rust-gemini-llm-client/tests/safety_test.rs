@@ -0,0 +1,80 @@
+//! Safety settings: exact API strings and when they are sent.
+
+use rust_gemini_llm_client::{GeminiClient, HarmBlockThreshold, HarmCategory, SafetySetting};
+use serde_json::json;
+
+#[test]
+fn every_category_uses_the_api_string() {
+    let cases = [
+        (HarmCategory::Harassment, "HARM_CATEGORY_HARASSMENT"),
+        (HarmCategory::HateSpeech, "HARM_CATEGORY_HATE_SPEECH"),
+        (
+            HarmCategory::SexuallyExplicit,
+            "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+        ),
+        (
+            HarmCategory::DangerousContent,
+            "HARM_CATEGORY_DANGEROUS_CONTENT",
+        ),
+        (
+            HarmCategory::CivicIntegrity,
+            "HARM_CATEGORY_CIVIC_INTEGRITY",
+        ),
+    ];
+    for (category, expected) in cases {
+        assert_eq!(serde_json::to_value(category).unwrap(), json!(expected));
+        assert_eq!(
+            serde_json::from_value::<HarmCategory>(json!(expected)).unwrap(),
+            category
+        );
+    }
+}
+
+#[test]
+fn every_threshold_uses_the_api_string() {
+    let cases = [
+        (HarmBlockThreshold::BlockLowAndAbove, "BLOCK_LOW_AND_ABOVE"),
+        (
+            HarmBlockThreshold::BlockMediumAndAbove,
+            "BLOCK_MEDIUM_AND_ABOVE",
+        ),
+        (HarmBlockThreshold::BlockOnlyHigh, "BLOCK_ONLY_HIGH"),
+        (HarmBlockThreshold::BlockNone, "BLOCK_NONE"),
+        (HarmBlockThreshold::Off, "OFF"),
+    ];
+    for (threshold, expected) in cases {
+        assert_eq!(serde_json::to_value(threshold).unwrap(), json!(expected));
+        assert_eq!(
+            serde_json::from_value::<HarmBlockThreshold>(json!(expected)).unwrap(),
+            threshold
+        );
+    }
+}
+
+#[test]
+fn nothing_is_sent_by_default() {
+    let client = GeminiClient::new("test-key");
+    assert!(client.safety_settings().is_empty());
+    assert!(client.request_body("Hi").get("safety_settings").is_none());
+}
+
+#[test]
+fn settings_are_sent_with_every_request() {
+    let client = GeminiClient::new("test-key").with_safety_settings(vec![
+        SafetySetting::new(HarmCategory::Harassment, HarmBlockThreshold::BlockOnlyHigh),
+        SafetySetting::new(
+            HarmCategory::DangerousContent,
+            HarmBlockThreshold::BlockLowAndAbove,
+        ),
+    ]);
+    let expected = json!([
+        {"category": "HARM_CATEGORY_HARASSMENT", "threshold": "BLOCK_ONLY_HIGH"},
+        {"category": "HARM_CATEGORY_DANGEROUS_CONTENT", "threshold": "BLOCK_LOW_AND_ABOVE"}
+    ]);
+    assert_eq!(client.request_body("Hi")["safety_settings"], expected);
+    // JSON mode builds on the same request, so the settings come along
+    assert_eq!(
+        client.json_request_body("Hi", json!({"type": "string"}))["safety_settings"],
+        expected
+    );
+}
@@ -0,0 +1,100 @@
+//! Sequential calls reuse one HTTP client, and therefore one pooled connection.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use rust_gemini_llm_client::GeminiClient;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+const SSE_BODY: &str =
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"ok\"}]}}]}\n\n";
+
+/// A tiny keep-alive HTTP server that answers every request with `SSE_BODY` and
+/// counts how many TCP connections it accepted. Returns (base URL, counter).
+async fn counting_server() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    let counter = connections.clone();
+    tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            counter.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(serve_connection(socket));
+        }
+    });
+    (format!("http://{}", addr), connections)
+}
+
+/// Answer requests on one connection until the client closes it.
+async fn serve_connection(mut socket: TcpStream) {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        // Read until we have the headers plus a body of Content-Length bytes
+        let request_len = loop {
+            if let Some(end) = find_header_end(&buffer) {
+                let body_len = content_length(&buffer[..end]);
+                if buffer.len() >= end + body_len {
+                    break end + body_len;
+                }
+            }
+            match socket.read(&mut chunk).await {
+                Ok(0) | Err(_) => return,
+                Ok(n) => buffer.extend_from_slice(&chunk[..n]),
+            }
+        };
+        buffer.drain(..request_len);
+
+        let response = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncontent-length: {}\r\n\r\n{}",
+            SSE_BODY.len(),
+            SSE_BODY
+        );
+        if socket.write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn find_header_end(buffer: &[u8]) -> Option<usize> {
+    buffer
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .map(|i| i + 4)
+}
+
+fn content_length(headers: &[u8]) -> usize {
+    String::from_utf8_lossy(headers)
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse().ok())
+                .flatten()
+        })
+        .unwrap_or(0)
+}
+
+#[tokio::test]
+async fn sequential_calls_reuse_one_connection() {
+    let (base_url, connections) = counting_server().await;
+
+    // Two separate GeminiClients, as the REST API creates one per request
+    for _ in 0..2 {
+        let text = GeminiClient::new("test-key")
+            .with_base_url(&base_url)
+            .expect("valid base URL")
+            .generate_content("hi")
+            .await
+            .expect("mock server answers");
+        assert_eq!(text, "ok");
+    }
+
+    assert_eq!(
+        connections.load(Ordering::SeqCst),
+        1,
+        "the second call should reuse the pooled connection"
+    );
+}
@@ -0,0 +1,155 @@
+//! Streaming: text deltas arrive one by one, then a final Done with the whole result.
+
+use std::time::Duration;
+
+use futures_util::StreamExt;
+use rust_gemini_llm_client::{ChatMessage, GeminiClient, GeminiError, RetryPolicy, StreamEvent};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use wiremock::matchers::{body_string_contains, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const STREAMED: &str = concat!(
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hello\"}]}}]}\n\n",
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \", \"}]}}]}\n\n",
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"world\"}]}, \"finishReason\": \"STOP\"}], ",
+    "\"usageMetadata\": {\"promptTokenCount\": 2, \"candidatesTokenCount\": 3, \"totalTokenCount\": 5}}\n\n",
+);
+
+fn client_for(url: &str) -> GeminiClient {
+    GeminiClient::new("test-key")
+        .with_model("test-model")
+        .with_base_url(url)
+        .expect("valid base URL")
+        .with_retry_policy(RetryPolicy::none())
+}
+
+async fn collect(client: &GeminiClient, prompt: &str) -> Vec<Result<StreamEvent, GeminiError>> {
+    client.generate_content_stream(prompt).collect().await
+}
+
+#[tokio::test]
+async fn deltas_arrive_in_order_then_done() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/models/test-model:streamGenerateContent"))
+        .and(query_param("alt", "sse"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(STREAMED))
+        .mount(&server)
+        .await;
+
+    let events: Vec<StreamEvent> = collect(&client_for(&server.uri()), "hi")
+        .await
+        .into_iter()
+        .map(|event| event.expect("no errors"))
+        .collect();
+
+    assert_eq!(events.len(), 4);
+    assert_eq!(events[0], StreamEvent::Text("Hello".to_string()));
+    assert_eq!(events[1], StreamEvent::Text(", ".to_string()));
+    assert_eq!(events[2], StreamEvent::Text("world".to_string()));
+    match &events[3] {
+        StreamEvent::Done(result) => {
+            assert_eq!(result.text, "Hello, world");
+            assert_eq!(result.finish_reason.as_deref(), Some("STOP"));
+            assert_eq!(result.usage.map(|u| u.total_token_count), Some(5));
+        }
+        other => panic!("expected Done, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn chat_history_is_sent_as_contents() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("\"role\":\"model\""))
+        .respond_with(ResponseTemplate::new(200).set_body_string(STREAMED))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let history = vec![
+        ChatMessage::user("hi"),
+        ChatMessage::model("hello"),
+        ChatMessage::user("again"),
+    ];
+    let events: Vec<_> = client_for(&server.uri())
+        .generate_content_stream(&history)
+        .collect()
+        .await;
+    assert!(events.iter().all(Result::is_ok));
+}
+
+#[tokio::test]
+async fn http_error_is_the_only_item() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("bad request"))
+        .mount(&server)
+        .await;
+
+    let events = collect(&client_for(&server.uri()), "hi").await;
+    assert_eq!(events.len(), 1);
+    assert!(
+        matches!(events[0], Err(GeminiError::Http { status: 400, .. })),
+        "got {:?}",
+        events[0]
+    );
+}
+
+#[tokio::test]
+async fn blocked_answer_ends_with_an_error() {
+    let server = MockServer::start().await;
+    let body = concat!(
+        "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Sure\"}]}}]}\n\n",
+        "data: {\"candidates\": [{\"finishReason\": \"SAFETY\"}]}\n\n",
+    );
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(&server)
+        .await;
+
+    let events = collect(&client_for(&server.uri()), "hi").await;
+    assert_eq!(events.len(), 2);
+    assert_eq!(
+        events[0].as_ref().ok(),
+        Some(&StreamEvent::Text("Sure".to_string()))
+    );
+    assert!(matches!(&events[1], Err(GeminiError::Blocked { reason }) if reason == "SAFETY"));
+}
+
+#[tokio::test]
+async fn first_delta_arrives_before_the_answer_is_complete() {
+    // A hand-rolled server: send one chunk, then stall. A non-streaming client would
+    // wait for the whole body; the stream must hand out "Hello" right away.
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut request = [0u8; 4096];
+        let _ = socket.read(&mut request).await;
+        let chunk =
+            "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hello\"}]}}]}\n\n";
+        let head = "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ntransfer-encoding: chunked\r\n\r\n";
+        let first = format!("{}{:x}\r\n{}\r\n", head, chunk.len(), chunk);
+        socket.write_all(first.as_bytes()).await.unwrap();
+        // Never finish the body
+        tokio::time::sleep(Duration::from_secs(30)).await;
+    });
+
+    let client = client_for(&format!("http://{}", addr)).with_timeout(Duration::from_millis(500));
+    let mut stream = client.generate_content_stream("hi");
+
+    let first = tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("first delta arrives without waiting for the body to end");
+    assert_eq!(
+        first.unwrap().unwrap(),
+        StreamEvent::Text("Hello".to_string())
+    );
+
+    // The stalled body then hits the idle timeout
+    let next = stream.next().await.expect("an error item");
+    assert!(matches!(next, Err(GeminiError::Timeout)), "got {:?}", next);
+    assert!(stream.next().await.is_none());
+}
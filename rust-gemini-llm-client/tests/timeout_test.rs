@@ -0,0 +1,79 @@
+//! Timeouts and cancellation against a server that accepts connections but never answers.
+
+use std::time::{Duration, Instant};
+
+use rust_gemini_llm_client::{CancellationToken, GeminiClient, GeminiError, RetryPolicy};
+use tokio::net::TcpListener;
+
+/// Start a TCP server that accepts every connection and then says nothing.
+/// Returns its base URL.
+async fn silent_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("local addr");
+    tokio::spawn(async move {
+        let mut open = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            // Keep the socket alive so the client waits instead of seeing a reset
+            open.push(socket);
+        }
+    });
+    format!("http://{}", addr)
+}
+
+async fn client_for_silent_server() -> GeminiClient {
+    GeminiClient::new("test-key")
+        .with_base_url(&silent_server().await)
+        .expect("valid base URL")
+        .with_retry_policy(RetryPolicy::none())
+}
+
+#[tokio::test]
+async fn hung_request_times_out() {
+    let client = client_for_silent_server()
+        .await
+        .with_timeout(Duration::from_millis(200));
+    assert_eq!(client.timeout(), Duration::from_millis(200));
+
+    let started = Instant::now();
+    let err = client
+        .generate_content("hi")
+        .await
+        .expect_err("server never answers");
+
+    assert!(matches!(err, GeminiError::Timeout), "got {:?}", err);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn cancellation_stops_a_hung_request() {
+    let client = client_for_silent_server().await;
+    let cancel = CancellationToken::new();
+
+    let stop = cancel.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        stop.cancel();
+    });
+
+    let started = Instant::now();
+    let err = client
+        .generate_content_cancellable("hi", &cancel)
+        .await
+        .expect_err("request was cancelled");
+
+    assert!(matches!(err, GeminiError::Cancelled), "got {:?}", err);
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn already_cancelled_token_returns_immediately() {
+    let client = client_for_silent_server().await;
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+
+    let err = client
+        .generate_content_cancellable("hi", &cancel)
+        .await
+        .expect_err("token was cancelled up front");
+    assert!(matches!(err, GeminiError::Cancelled));
+}
@@ -0,0 +1,96 @@
+//! Function calling: tool declarations going out, function calls coming back.
+
+use rust_gemini_llm_client::{
+    parse_stream_result, FunctionCall, FunctionDeclaration, GeminiClient, ModelReply, Tool,
+};
+use serde_json::json;
+
+fn add_ball_tool() -> Tool {
+    Tool::new(vec![
+        FunctionDeclaration::new("add_ball", "Drop a ball at (x, y) in meters").with_parameters(
+            json!({
+                "type": "object",
+                "properties": {"x": {"type": "number"}, "y": {"type": "number"}},
+                "required": ["x", "y"]
+            }),
+        ),
+        FunctionDeclaration::new("clear_scene", "Remove every ball"),
+    ])
+}
+
+#[test]
+fn tools_serialize_into_the_request() {
+    let body = GeminiClient::new("test-key").tools_request_body("Drop a ball", &[add_ball_tool()]);
+    assert_eq!(
+        body,
+        json!({
+            "contents": [{"role": "user", "parts": [{"text": "Drop a ball"}]}],
+            "tools": [{"function_declarations": [
+                {
+                    "name": "add_ball",
+                    "description": "Drop a ball at (x, y) in meters",
+                    "parameters": {
+                        "type": "object",
+                        "properties": {"x": {"type": "number"}, "y": {"type": "number"}},
+                        "required": ["x", "y"]
+                    }
+                },
+                {"name": "clear_scene", "description": "Remove every ball"}
+            ]}]
+        })
+    );
+    // Without tools the key is left out, as before
+    assert!(GeminiClient::new("test-key")
+        .request_body("Hi")
+        .get("tools")
+        .is_none());
+}
+
+#[test]
+fn tool_declarations_round_trip() {
+    let tool = add_ball_tool();
+    let json = serde_json::to_value(&tool).unwrap();
+    let back: Tool = serde_json::from_value(json).unwrap();
+    assert_eq!(back, tool);
+}
+
+#[test]
+fn function_calls_are_parsed_from_the_response() {
+    let body = concat!(
+        "data: {\"candidates\": [{\"content\": {\"role\": \"model\", \"parts\": [",
+        "{\"functionCall\": {\"name\": \"add_ball\", \"args\": {\"x\": 1.5, \"y\": 4}}},",
+        "{\"functionCall\": {\"name\": \"clear_scene\"}}",
+        "]}, \"finishReason\": \"STOP\"}]}\n\n",
+    );
+    let result = parse_stream_result(body).expect("valid body");
+    assert!(result.text.is_empty());
+
+    match result.into_reply().expect("not blocked") {
+        ModelReply::FunctionCalls(calls) => {
+            assert_eq!(calls.len(), 2);
+            assert_eq!(calls[0].name, "add_ball");
+            assert_eq!(calls[0].args, json!({"x": 1.5, "y": 4}));
+            // A call without arguments still parses
+            assert_eq!(calls[1].args, serde_json::Value::Null);
+
+            // ...and serializes back to the API's shape
+            let json = serde_json::to_value(&calls[0]).unwrap();
+            assert_eq!(
+                json,
+                json!({"name": "add_ball", "args": {"x": 1.5, "y": 4}})
+            );
+            assert_eq!(
+                serde_json::from_value::<FunctionCall>(json).unwrap(),
+                calls[0]
+            );
+        }
+        other => panic!("expected function calls, got {:?}", other),
+    }
+}
+
+#[test]
+fn plain_text_reply_is_text() {
+    let body = "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Done!\"}]}}]}\n\n";
+    let reply = parse_stream_result(body).unwrap().into_reply().unwrap();
+    assert_eq!(reply, ModelReply::Text("Done!".to_string()));
+}
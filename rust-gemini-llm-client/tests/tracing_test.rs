@@ -0,0 +1,149 @@
+//! The `gemini.generate_content` span and its fields (run with `--features tracing`).
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rust_gemini_llm_client::{GeminiClient, GeminiError, RetryPolicy};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const API_KEY: &str = "secret-test-key";
+const PROMPT: &str = "Tell me a secret about élan";
+
+/// Field values of every span, by span name, rendered as strings.
+type Captured = Arc<Mutex<HashMap<String, HashMap<String, String>>>>;
+
+/// A test layer that remembers span fields (both at creation and later `record` calls).
+struct CaptureLayer {
+    spans: Captured,
+    names: Mutex<HashMap<Id, String>>,
+}
+
+struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), value.to_string());
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for CaptureLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _ctx: Context<'_, S>) {
+        let name = attrs.metadata().name().to_string();
+        self.names.lock().unwrap().insert(id.clone(), name.clone());
+        let mut spans = self.spans.lock().unwrap();
+        attrs.record(&mut FieldVisitor(spans.entry(name).or_default()));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        let Some(name) = self.names.lock().unwrap().get(id).cloned() else {
+            return;
+        };
+        let mut spans = self.spans.lock().unwrap();
+        values.record(&mut FieldVisitor(spans.entry(name).or_default()));
+    }
+}
+
+fn capture() -> (Captured, impl Subscriber) {
+    let spans = Captured::default();
+    let layer = CaptureLayer {
+        spans: spans.clone(),
+        names: Mutex::new(HashMap::new()),
+    };
+    (spans, tracing_subscriber::registry().with(layer))
+}
+
+fn client_for(server: &MockServer) -> GeminiClient {
+    GeminiClient::new(API_KEY)
+        .with_model("test-model")
+        .with_base_url(&server.uri())
+        .expect("valid base URL")
+        .with_retry_policy(RetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            jitter: false,
+        })
+}
+
+#[tokio::test]
+async fn span_records_model_status_retries_and_timing() {
+    let (spans, subscriber) = capture();
+    // #[tokio::test] runs on one thread, so a thread-local default subscriber sees everything
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"ok\"}]}}]}\n\n",
+        ))
+        .mount(&server)
+        .await;
+
+    client_for(&server)
+        .generate_content(PROMPT)
+        .await
+        .expect("second attempt succeeds");
+
+    let spans = spans.lock().unwrap();
+    let fields = spans
+        .get("gemini.generate_content")
+        .expect("span was emitted");
+    assert_eq!(fields["model"], "test-model");
+    assert_eq!(fields["prompt_chars"], PROMPT.chars().count().to_string());
+    assert_eq!(fields["status"], "200");
+    assert_eq!(fields["retries"], "1");
+    assert!(
+        fields["elapsed_ms"].parse::<u64>().is_ok(),
+        "elapsed_ms = {}",
+        fields["elapsed_ms"]
+    );
+    assert!(!fields.contains_key("error"));
+
+    // Neither the key nor the prompt may end up in telemetry
+    for value in fields.values() {
+        assert!(!value.contains(API_KEY), "API key leaked: {}", value);
+        assert!(!value.contains("secret about"), "prompt leaked: {}", value);
+    }
+}
+
+#[tokio::test]
+async fn failures_record_the_error_kind() {
+    let (spans, subscriber) = capture();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("bad request"))
+        .mount(&server)
+        .await;
+
+    let err = client_for(&server)
+        .generate_content(PROMPT)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, GeminiError::Http { status: 400, .. }));
+
+    let spans = spans.lock().unwrap();
+    let fields = &spans["gemini.generate_content"];
+    assert_eq!(fields["status"], "400");
+    assert_eq!(fields["retries"], "0");
+    assert_eq!(fields["error"], "http");
+}
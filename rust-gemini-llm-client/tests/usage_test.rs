@@ -0,0 +1,45 @@
+//! `usageMetadata` parsing from a captured streaming response.
+
+use rust_gemini_llm_client::{parse_stream_result, UsageMetadata};
+
+// Shape of a real `streamGenerateContent?alt=sse` reply: every chunk carries running
+// token totals, and the last chunk has the final counts plus the finish reason.
+const BODY: &str = concat!(
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Rust is a systems \"}],\"role\": \"model\"},",
+    "\"index\": 0}],\"usageMetadata\": {\"promptTokenCount\": 8,\"candidatesTokenCount\": 4,",
+    "\"totalTokenCount\": 12},\"modelVersion\": \"gemini-flash-latest\"}\r\n\r\n",
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"programming language.\"}],\"role\": \"model\"},",
+    "\"finishReason\": \"STOP\",\"index\": 0}],\"usageMetadata\": {\"promptTokenCount\": 8,",
+    "\"candidatesTokenCount\": 9,\"totalTokenCount\": 17,",
+    "\"promptTokensDetails\": [{\"modality\": \"TEXT\",\"tokenCount\": 8}]},\"modelVersion\": \"gemini-flash-latest\"}\r\n\r\n",
+);
+
+#[test]
+fn usage_comes_from_the_last_chunk() {
+    let result = parse_stream_result(BODY).expect("valid body");
+
+    assert_eq!(result.text, "Rust is a systems programming language.");
+    assert_eq!(
+        result.usage,
+        Some(UsageMetadata {
+            prompt_token_count: 8,
+            candidates_token_count: 9,
+            total_token_count: 17,
+        })
+    );
+}
+
+#[test]
+fn missing_usage_is_none_and_missing_counts_are_zero() {
+    let result = parse_stream_result("data: {\"candidates\": []}\n\n").expect("valid body");
+    assert_eq!(result.usage, None);
+
+    // A blocked answer has no candidate tokens, so the API omits that count
+    let body = "data: {\"candidates\": [{\"finishReason\": \"SAFETY\"}], \"usageMetadata\": {\"promptTokenCount\": 5, \"totalTokenCount\": 5}}\n\n";
+    let usage = parse_stream_result(body)
+        .expect("valid body")
+        .usage
+        .expect("usage");
+    assert_eq!(usage.prompt_token_count, 5);
+    assert_eq!(usage.candidates_token_count, 0);
+}
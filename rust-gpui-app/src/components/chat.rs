@@ -3,15 +3,11 @@
 //! This module provides a ChatView component that orchestrates
 //! the MessagesArea, ChatInput, and GeminiService components.
 
-use gpui::{
-    prelude::*,
-    div, IntoElement, ParentElement, Styled, Window,
-    Entity,
-};
-use crate::theme::colors;
 use super::chat_input::{ChatInput, ChatInputEvent};
-use super::messages_area::{MessagesArea, MessageEvent};
+use super::messages_area::{MessageEvent, MessagesArea, MessagesAreaEvent};
 use crate::services::gemini_service::{GeminiService, GeminiServiceEvent};
+use crate::theme::colors;
+use gpui::{div, prelude::*, rgb, Entity, IntoElement, MouseButton, ParentElement, Styled, Window};
 
 /// A chat view component that orchestrates messages, input, and AI service
 pub struct ChatView {
@@ -21,6 +17,8 @@ pub struct ChatView {
     chat_input: Entity<ChatInput>,
     /// Gemini service for AI responses
     gemini_service: Entity<GeminiService>,
+    /// How many messages are stuck in the offline queue (drives the "Retry all" bar)
+    unsent_count: usize,
 }
 
 impl ChatView {
@@ -29,68 +27,144 @@ impl ChatView {
         let messages_area = cx.new(|cx| MessagesArea::new(cx));
         let chat_input = cx.new(|cx| ChatInput::new(cx));
         let gemini_service = cx.new(|cx| GeminiService::new(cx));
-        
+
         // Subscribe to chat input events - forward to both messages area and gemini service
         let messages_area_clone = messages_area.clone();
         let gemini_service_clone = gemini_service.clone();
-        cx.subscribe(&chat_input, move |_this, _emitter, event: &ChatInputEvent, cx| {
-            match event {
-                ChatInputEvent::SendMessage(text) => {
-                    // Add user message to messages area
-                    messages_area_clone.update(cx, |area, cx| {
-                        area.add_message(super::messages_area::ChatMessage::user(text.clone()));
-                        cx.notify();
-                    });
-                    
-                    // Send to gemini service for processing
-                    gemini_service_clone.update(cx, |service, cx| {
-                        service.handle_chat_input(event, cx);
-                    });
+        cx.subscribe(
+            &chat_input,
+            move |_this, _emitter, event: &ChatInputEvent, cx| {
+                match event {
+                    ChatInputEvent::SendMessage(text) => {
+                        // Add user message to messages area
+                        let message = super::messages_area::ChatMessage::user(text.clone());
+                        let transcript_id = message.id.clone();
+                        messages_area_clone.update(cx, |area, cx| {
+                            area.add_message(message);
+                            cx.notify();
+                        });
+
+                        // Send to gemini service for processing; the id lets the reply find its prompt
+                        gemini_service_clone.update(cx, |service, cx| {
+                            service.submit(transcript_id, text.clone(), cx);
+                        });
+                    }
                 }
-            }
-        }).detach();
-        
+            },
+        )
+        .detach();
+
         // Subscribe to gemini service events - forward assistant messages to messages area
         let messages_area_clone2 = messages_area.clone();
-        cx.subscribe(&gemini_service, move |_this, _emitter, event: &GeminiServiceEvent, cx| {
-            match event {
-                // GeminiServiceEvent::AssistantMessage(text) => {
+        cx.subscribe(
+            &gemini_service,
+            move |this, _emitter, event: &GeminiServiceEvent, cx| {
+                match event {
+                    // GeminiServiceEvent::AssistantMessage(text) => {
                     // messages_area_clone2.update(cx, |area, cx| {
                     //     area.add_message(super::messages_area::ChatMessage::assistant(text.clone()));
                     //     cx.notify();
                     // });
-                // }
-                // Use structured blocks to render code and rich content properly
-                GeminiServiceEvent::AssistantMessageParsed(blocks) => {
-                    messages_area_clone2.update(cx, |area, cx| {
-                        area.add_message(super::messages_area::ChatMessage::assistant_with_blocks(blocks.clone()));
+                    // }
+                    // Replies stream in: an empty message under its prompt, filled piece by piece
+                    GeminiServiceEvent::StreamStarted {
+                        reply_to,
+                        message_id,
+                    } => {
+                        messages_area_clone2.update(cx, |area, cx| {
+                            area.start_stream(reply_to, message_id.clone());
+                            cx.notify();
+                        });
+                    }
+                    GeminiServiceEvent::StreamDelta { message_id, text } => {
+                        messages_area_clone2.update(cx, |area, cx| {
+                            if area.apply_delta(message_id, text) {
+                                cx.notify();
+                            }
+                        });
+                    }
+                    // Once whole, the text is parsed so code and rich content render properly
+                    GeminiServiceEvent::StreamComplete { message_id } => {
+                        messages_area_clone2.update(cx, |area, cx| {
+                            area.complete_stream(message_id);
+                            cx.notify();
+                        });
+                    }
+                    GeminiServiceEvent::StreamAborted { message_id } => {
+                        messages_area_clone2.update(cx, |area, cx| {
+                            area.abort_stream(message_id);
+                            cx.notify();
+                        });
+                    }
+                    GeminiServiceEvent::Error(error) => {
+                        messages_area_clone2.update(cx, |area, cx| {
+                            area.add_message(super::messages_area::ChatMessage::assistant(
+                                format!("Error: {}", error),
+                            ));
+                            cx.notify();
+                        });
+                    }
+                    GeminiServiceEvent::Processing => {
+                        // Could show a loading indicator
+                    }
+                    GeminiServiceEvent::QueueChanged { unsent } => {
+                        this.unsent_count = unsent.len();
+                        messages_area_clone2.update(cx, |area, cx| {
+                            area.set_unsent(unsent.iter().cloned());
+                            cx.notify();
+                        });
                         cx.notify();
-                    });
+                    }
                 }
-                GeminiServiceEvent::Error(error) => {
-                    messages_area_clone2.update(cx, |area, cx| {
-                        area.add_message(super::messages_area::ChatMessage::assistant(
-                            format!("Error: {}", error)
-                        ));
-                        cx.notify();
-                    });
-                }
-                GeminiServiceEvent::Processing => {
-                    // Could show a loading indicator
+            },
+        )
+        .detach();
+
+        // Clicking a "Not sent" badge retries the whole queue (order must be kept anyway)
+        let gemini_service_clone2 = gemini_service.clone();
+        cx.subscribe(
+            &messages_area,
+            move |_this, _emitter, event: &MessagesAreaEvent, cx| {
+                if let MessagesAreaEvent::RetryUnsent = event {
+                    gemini_service_clone2.update(cx, |service, cx| service.retry_all(cx));
                 }
+            },
+        )
+        .detach();
+
+        // Put messages left unsent by a previous run back into the transcript
+        let pending = gemini_service.read(cx).pending_messages();
+        let unsent = gemini_service.read(cx).unsent_ids();
+        let unsent_count = unsent.len();
+        messages_area.update(cx, |area, _cx| {
+            for pending in pending {
+                let mut message = super::messages_area::ChatMessage::user(pending.text);
+                message.id = pending.transcript_id;
+                area.add_message(message);
             }
-        }).detach();
-        
+            area.set_unsent(unsent);
+        });
+        gemini_service.update(cx, |service, cx| service.retry_all(cx));
+
         Self {
             messages_area,
             chat_input,
             gemini_service,
+            unsent_count,
         }
     }
+
+    /// Retry every message in the offline queue
+    fn retry_all(&mut self, cx: &mut Context<Self>) {
+        self.gemini_service
+            .update(cx, |service, cx| service.retry_all(cx));
+    }
 }
 
 impl Render for ChatView {
-    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let unsent_count = self.unsent_count;
+
         div()
             .id("chat-view")
             .flex()
@@ -99,6 +173,41 @@ impl Render for ChatView {
             .bg(colors::background())
             // Messages area
             .child(self.messages_area.clone())
+            // Offline banner with a "Retry all" button
+            .when(unsent_count > 0, |d| {
+                d.child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .justify_between()
+                        .px_4()
+                        .py_2()
+                        .bg(colors::surface())
+                        .text_sm()
+                        .text_color(colors::warning())
+                        .child(format!(
+                            "{} message(s) not sent — waiting for connection",
+                            unsent_count
+                        ))
+                        .child(
+                            div()
+                                .id("retry-all-button")
+                                .px_3()
+                                .py_1()
+                                .rounded_md()
+                                .bg(colors::primary())
+                                .text_color(rgb(0xffffff))
+                                .cursor_pointer()
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(|this, _event, _window, cx| {
+                                        this.retry_all(cx);
+                                    }),
+                                )
+                                .child("Retry all"),
+                        ),
+                )
+            })
             // Chat input area
             .child(self.chat_input.clone())
     }
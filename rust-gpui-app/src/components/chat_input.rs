@@ -1,15 +1,24 @@
 //! Chat input component with text field and send button.
 //!
 //! This component handles text input and emits events when messages are sent.
+//!
+//! Enter sends; Shift+Enter starts a new line, so prompts with code keep their shape.
+//! Up and Down move between the lines of a multiline message; Up on the first line
+//! recalls earlier messages and Down on the last line walks back to the draft (see
+//! [`InputHistory`]). What a key means is decided by [`key_action`], a plain function
+//! the tests can call without a window.
+//!
+//! Keys reach this component before the text input inside it (the capture phase), so a
+//! recall can stop the text input from also moving the cursor for the same key.
 
+use super::input_history::InputHistory;
+use super::text_buffer::TextBuffer;
+use super::text_input::TextInput;
+use crate::theme::colors;
 use gpui::{
-    prelude::*,
-    div, IntoElement, ParentElement, Styled, Window,
-    Entity, MouseButton, EventEmitter, FocusHandle, Focusable, KeyDownEvent,
-    px, rgb,
+    div, prelude::*, px, rgb, Entity, EventEmitter, FocusHandle, Focusable, IntoElement,
+    KeyDownEvent, MouseButton, ParentElement, Styled, Window,
 };
-use crate::theme::colors;
-use super::text_input::TextInput;
 
 /// Events emitted by the chat input component
 #[derive(Clone, Debug)]
@@ -18,10 +27,45 @@ pub enum ChatInputEvent {
     SendMessage(String),
 }
 
+/// What a key press in the chat input does
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyAction {
+    /// Send the message
+    Send,
+    /// Insert a line break
+    Newline,
+    /// Show an older sent message
+    Older,
+    /// Show a newer sent message, or the draft
+    Newer,
+    /// Anything else: the text input edits as usual
+    Edit,
+}
+
+/// Decide what `key` (GPUI's key name, e.g. `"enter"`) does, given whether Shift is held
+/// and where the cursor is in `buffer`
+pub fn key_action(key: &str, shift: bool, buffer: &TextBuffer) -> KeyAction {
+    match key {
+        "enter" if shift => KeyAction::Newline,
+        "enter" => KeyAction::Send,
+        // Shift+Up/Down select text instead, and away from the edges they move between lines
+        "up" if !shift && buffer.cursor_on_first_line() => KeyAction::Older,
+        "down" if !shift && buffer.cursor_on_last_line() => KeyAction::Newer,
+        _ => KeyAction::Edit,
+    }
+}
+
+/// Whether `text` is worth sending: blank or whitespace-only messages are not
+pub fn can_send(text: &str) -> bool {
+    !text.trim().is_empty()
+}
+
 /// A chat input component with text field and send button
 pub struct ChatInput {
     /// Text input entity
     text_input: Entity<TextInput>,
+    /// Messages sent this session, for Up/Down recall
+    history: InputHistory,
     /// Focus handle
     focus_handle: FocusHandle,
 }
@@ -29,7 +73,10 @@ pub struct ChatInput {
 impl ChatInput {
     /// Create a new chat input
     pub fn new(cx: &mut Context<Self>) -> Self {
-        let text_input = cx.new(|cx| TextInput::new(cx, "Type a message..."));
+        let text_input = cx.new(|cx| TextInput::new(cx, "Type a message...").with_multiline(true));
+        // Re-render on every edit, so the send button follows the text
+        cx.observe(&text_input, |_this, _input, cx| cx.notify())
+            .detach();
 
         // Prefill default text
         text_input.update(cx, |input, _cx| {
@@ -38,6 +85,7 @@ impl ChatInput {
 
         Self {
             text_input,
+            history: InputHistory::new(),
             focus_handle: cx.focus_handle(),
         }
     }
@@ -45,10 +93,11 @@ impl ChatInput {
     /// Submit the current message
     fn submit(&mut self, cx: &mut Context<Self>) {
         let text = self.text_input.read(cx).text().to_string();
-        if !text.trim().is_empty() {
-            // Emit the send message event
+        if can_send(&text) {
+            // Emit the send message event, line breaks and all
+            self.history.push(text.clone());
             cx.emit(ChatInputEvent::SendMessage(text));
-            
+
             // Clear input
             self.text_input.update(cx, |input, _cx| {
                 input.clear();
@@ -57,12 +106,48 @@ impl ChatInput {
         cx.notify();
     }
 
-    /// Handle key down for Enter key submission
+    /// Handle key down: Enter submits, Shift+Enter adds a line, Up/Down at the edges recall.
+    /// Keys it acts on go no further, so the text input doesn't handle them as well.
     fn handle_key_down(&mut self, event: &KeyDownEvent, cx: &mut Context<Self>) {
-        if event.keystroke.key == "enter" && !event.keystroke.modifiers.shift {
-            self.submit(cx);
+        let action = key_action(
+            &event.keystroke.key,
+            event.keystroke.modifiers.shift,
+            self.text_input.read(cx).buffer(),
+        );
+        if action != KeyAction::Edit {
+            cx.stop_propagation();
+        }
+        match action {
+            KeyAction::Send => self.submit(cx),
+            KeyAction::Newline => {
+                self.text_input.update(cx, |input, cx| {
+                    input.insert("\n");
+                    cx.notify();
+                });
+            }
+            KeyAction::Older => {
+                let text = self.text_input.read(cx).text().to_string();
+                if let Some(recalled) = self.history.up(&text) {
+                    self.show(recalled, cx);
+                }
+            }
+            KeyAction::Newer => {
+                let text = self.text_input.read(cx).text().to_string();
+                if let Some(recalled) = self.history.down(&text) {
+                    self.show(recalled, cx);
+                }
+            }
+            KeyAction::Edit => {}
         }
     }
+
+    /// Put a recalled message (or the draft) in the input, cursor at the end
+    fn show(&mut self, text: String, cx: &mut Context<Self>) {
+        self.text_input.update(cx, |input, cx| {
+            input.set_text(text);
+            cx.notify();
+        });
+    }
 }
 
 impl EventEmitter<ChatInputEvent> for ChatInput {}
@@ -76,11 +161,13 @@ impl Focusable for ChatInput {
 impl Render for ChatInput {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let focus_handle = self.focus_handle.clone();
-        
+        let enabled = can_send(self.text_input.read(cx).text());
+
         div()
             .id("chat-input-container")
             .track_focus(&focus_handle)
-            .on_key_down(cx.listener(|this, event: &KeyDownEvent, _window, cx| {
+            // Capture: before the text input sees the key
+            .capture_key_down(cx.listener(|this, event: &KeyDownEvent, _window, cx| {
                 this.handle_key_down(event, cx);
             }))
             .flex()
@@ -95,15 +182,24 @@ impl Render for ChatInput {
                     .id("send-button")
                     .px_4()
                     .py_2()
-                    .bg(colors::primary())
                     .rounded_lg()
                     .text_color(rgb(0xffffff))
-                    .cursor_pointer()
-                    .hover(|style| style.bg(colors::secondary()))
-                    .on_mouse_down(MouseButton::Left, cx.listener(|this, _event, _window, cx| {
-                        this.submit(cx);
-                    }))
-                    .child("Send")
+                    .when(enabled, |button| {
+                        button
+                            .bg(colors::primary())
+                            .cursor_pointer()
+                            .hover(|style| style.bg(colors::secondary()))
+                            .on_mouse_down(
+                                MouseButton::Left,
+                                cx.listener(|this, _event, _window, cx| {
+                                    this.submit(cx);
+                                }),
+                            )
+                    })
+                    .when(!enabled, |button| {
+                        button.bg(colors::border()).cursor_not_allowed()
+                    })
+                    .child("Send"),
             )
     }
-}
\ No newline at end of file
+}
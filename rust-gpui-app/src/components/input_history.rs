@@ -0,0 +1,111 @@
+//! Recall of sent messages with Up and Down, shell style.
+//!
+//! Plain data plus logic, like [`TextBuffer`](super::text_buffer::TextBuffer): the chat
+//! input passes in its current text and gets back the text to show, if any.
+//!
+//! - Up in an empty input shows the last message sent; more Ups go further back.
+//! - Down goes forward again, and past the newest message brings back the draft, the
+//!   text the input held before browsing started.
+//! - Editing a recalled message ends browsing: it is a new draft now, and the entry in
+//!   the history stays as it was sent.
+//!
+//! The history lives for the session only and keeps the last [`HISTORY_CAPACITY`]
+//! messages.
+
+use std::collections::VecDeque;
+
+/// Most messages kept; older ones are forgotten
+pub const HISTORY_CAPACITY: usize = 100;
+
+/// Sent messages, oldest first, and where browsing is
+#[derive(Clone, Debug, Default)]
+pub struct InputHistory {
+    entries: VecDeque<String>,
+    browsing: Option<Browsing>,
+}
+
+#[derive(Clone, Debug)]
+struct Browsing {
+    /// The entry on show
+    index: usize,
+    /// What the input held before the first Up
+    draft: String,
+}
+
+impl InputHistory {
+    /// An empty history
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember a sent message and stop browsing. Sending the same message twice in a row
+    /// keeps one entry, so repeats don't have to be stepped through.
+    pub fn push(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        self.browsing = None;
+        if self.entries.back() == Some(&message) {
+            return;
+        }
+        if self.entries.len() == HISTORY_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(message);
+    }
+
+    /// The sent messages, oldest first
+    pub fn entries(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(String::as_str)
+    }
+
+    /// Whether a recalled message is on show
+    pub fn is_browsing(&self) -> bool {
+        self.browsing.is_some()
+    }
+
+    /// Up, with `text` in the input: the older message to show, or `None` to leave the
+    /// input alone (it holds a draft, or the oldest message is already on show)
+    pub fn up(&mut self, text: &str) -> Option<String> {
+        self.detach_if_edited(text);
+        let index = match &mut self.browsing {
+            Some(browsing) if browsing.index == 0 => return None,
+            Some(browsing) => {
+                browsing.index -= 1;
+                browsing.index
+            }
+            None if !text.is_empty() || self.entries.is_empty() => return None,
+            None => {
+                let index = self.entries.len() - 1;
+                self.browsing = Some(Browsing {
+                    index,
+                    draft: text.to_string(),
+                });
+                index
+            }
+        };
+        Some(self.entries[index].clone())
+    }
+
+    /// Down, with `text` in the input: the newer message to show, the draft once past the
+    /// newest, or `None` when not browsing
+    pub fn down(&mut self, text: &str) -> Option<String> {
+        self.detach_if_edited(text);
+        let browsing = self.browsing.as_mut()?;
+        if browsing.index + 1 < self.entries.len() {
+            browsing.index += 1;
+            Some(self.entries[browsing.index].clone())
+        } else {
+            self.browsing.take().map(|browsing| browsing.draft)
+        }
+    }
+
+    // The input no longer shows the recalled entry, so the user changed it
+    fn detach_if_edited(&mut self, text: &str) {
+        let edited = self
+            .browsing
+            .as_ref()
+            .is_some_and(|browsing| self.entries[browsing.index] != text);
+        if edited {
+            self.browsing = None;
+        }
+    }
+}
@@ -2,18 +2,24 @@
 //!
 //! This component displays a scrollable list of chat messages
 //! and handles message events to update the display.
+//!
+//! Streamed replies arrive in pieces: [`MessagesArea::start_stream`] puts an empty reply
+//! (with the streaming cursor) under its prompt, [`MessagesArea::apply_delta`] appends
+//! each piece, and [`MessagesArea::complete_stream`] turns the finished text into rich
+//! blocks. Pieces or completions for a reply that is no longer streaming are ignored,
+//! so a late event can't change a finished message.
+
+use std::collections::HashSet;
 
+use crate::theme::colors;
+use crate::utils::parser::parse_assistant_response;
 use gpui::{
-    prelude::*,
-    div, IntoElement, ParentElement, Styled, Window,
-    EventEmitter, ClipboardItem,
+    div, prelude::*, ClipboardItem, EventEmitter, IntoElement, MouseButton, ParentElement, Styled,
+    Window,
 };
-use crate::theme::colors;
 
 // Re-export message item types
-pub use super::message_item::{
-    ChatMessage, ContentBlock, ExecutionStatus, MessageAction,
-};
+pub use super::message_item::{ChatMessage, ContentBlock, ExecutionStatus, MessageAction};
 
 // --- Events ---
 
@@ -26,6 +32,8 @@ pub enum MessagesAreaEvent {
     AddAssistantMessage(String),
     /// Action triggered from message UI
     MessageAction(MessageAction),
+    /// The "Not sent — retry" badge was clicked
+    RetryUnsent,
 }
 
 /// Events that the messages area can receive
@@ -35,7 +43,7 @@ pub enum MessageEvent {
     UserMessage(String),
     /// Add an assistant message
     AssistantMessage(String),
-    /// Update streaming state
+    /// Append the next piece of a streaming message
     StreamingUpdate { message_id: String, content: String },
     /// Complete streaming
     StreamingComplete(String),
@@ -44,19 +52,22 @@ pub enum MessageEvent {
 // --- Messages Area Component ---
 
 /// A messages area component that displays chat messages
+#[derive(Default)]
 pub struct MessagesArea {
     /// The list of messages
     messages: Vec<ChatMessage>,
+    /// Ids of user messages that failed to send (shown with a badge)
+    unsent: HashSet<String>,
 }
 
 impl MessagesArea {
     /// Create a new messages area
     pub fn new(_cx: &mut Context<Self>) -> Self {
-
         Self {
             messages: vec![
                 // ChatMessage::assistant("Hello! How can I help you today?"),
             ],
+            unsent: HashSet::new(),
         }
     }
 
@@ -65,6 +76,81 @@ impl MessagesArea {
         self.messages.push(message);
     }
 
+    /// Insert a reply directly below the message it answers.
+    ///
+    /// Queued messages may be answered long after later prompts were typed, so
+    /// appending at the end would put the reply under the wrong prompt.
+    pub fn insert_reply(&mut self, reply_to: &str, message: ChatMessage) {
+        match self.messages.iter().position(|m| m.id == reply_to) {
+            Some(index) => self.messages.insert(index + 1, message),
+            None => self.messages.push(message),
+        }
+    }
+
+    /// The messages, top to bottom
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    /// Put an empty assistant reply with id `message_id` under the message it answers,
+    /// marked as streaming so it shows the cursor until [`complete_stream`](Self::complete_stream)
+    pub fn start_stream(&mut self, reply_to: &str, message_id: String) {
+        let mut message = ChatMessage::assistant("");
+        message.id = message_id;
+        message.is_streaming = true;
+        self.insert_reply(reply_to, message);
+    }
+
+    /// Append `text` to a streaming reply. Returns `false`, changing nothing, when no
+    /// message with that id is streaming (it finished, was aborted, or never started).
+    pub fn apply_delta(&mut self, message_id: &str, text: &str) -> bool {
+        let Some(msg) = self.streaming_mut(message_id) else {
+            return false;
+        };
+        match msg.blocks.last_mut() {
+            Some(ContentBlock::Text(current)) => *current = format!("{}{}", current, text).into(),
+            _ => msg.blocks.push(ContentBlock::Text(text.to_string().into())),
+        }
+        true
+    }
+
+    /// Finish a streaming reply and hide its cursor. Returns `false` when no message with
+    /// that id is streaming, so a second completion is a no-op.
+    ///
+    /// The text is only parsed into blocks now: halfway through, an opened code fence
+    /// with no closing one yet would make the reply jump between layouts.
+    pub fn complete_stream(&mut self, message_id: &str) -> bool {
+        let Some(msg) = self.streaming_mut(message_id) else {
+            return false;
+        };
+        let blocks = parse_assistant_response(&msg.get_full_text());
+        if !blocks.is_empty() {
+            msg.blocks = blocks;
+        }
+        msg.is_streaming = false;
+        true
+    }
+
+    /// Remove a streaming reply whose request failed. Finished replies are kept.
+    pub fn abort_stream(&mut self, message_id: &str) -> bool {
+        let count = self.messages.len();
+        self.messages
+            .retain(|m| !(m.id == message_id && m.is_streaming));
+        self.messages.len() != count
+    }
+
+    /// The message with this id, if it is still streaming
+    fn streaming_mut(&mut self, message_id: &str) -> Option<&mut ChatMessage> {
+        self.messages
+            .iter_mut()
+            .find(|m| m.id == message_id && m.is_streaming)
+    }
+
+    /// Replace the set of messages that show the "Not sent" badge
+    pub fn set_unsent(&mut self, ids: impl IntoIterator<Item = String>) {
+        self.unsent = ids.into_iter().collect();
+    }
+
     /// Handle message events
     #[allow(dead_code)]
     fn handle_message_event(&mut self, event: &MessageEvent, cx: &mut Context<Self>) {
@@ -75,18 +161,14 @@ impl MessagesArea {
             MessageEvent::AssistantMessage(content) => {
                 self.add_message(ChatMessage::assistant(content.clone()));
             }
-            MessageEvent::StreamingUpdate { message_id, content } => {
-                if let Some(msg) = self.messages.iter_mut().find(|m| &m.id == message_id) {
-                    if let Some(ContentBlock::Text(text)) = msg.blocks.last_mut() {
-                        *text = content.clone().into();
-                    }
-                    msg.is_streaming = true;
-                }
+            MessageEvent::StreamingUpdate {
+                message_id,
+                content,
+            } => {
+                self.apply_delta(message_id, content);
             }
             MessageEvent::StreamingComplete(message_id) => {
-                if let Some(msg) = self.messages.iter_mut().find(|m| &m.id == message_id) {
-                    msg.is_streaming = false;
-                }
+                self.complete_stream(message_id);
             }
         }
         cx.notify();
@@ -107,7 +189,10 @@ impl MessagesArea {
             }
             // Struct-like enum variant with named fields
             // { message_id, is_positive } destructures the fields directly
-            MessageAction::RateMessage { message_id, is_positive } => {
+            MessageAction::RateMessage {
+                message_id,
+                is_positive,
+            } => {
                 // 'if let' is syntactic sugar for pattern matching on Option<T>
                 // Option<T> is Rust's way of handling nullable values, like Optional<T> in Java but built-in
                 // iter_mut() returns a mutable iterator over the Vec (vector, like ArrayList)
@@ -122,7 +207,10 @@ impl MessagesArea {
                 }
             }
             // Another enum variant with named fields
-            MessageAction::ExecuteCode { message_id, code: _ } => {
+            MessageAction::ExecuteCode {
+                message_id,
+                code: _,
+            } => {
                 // Set status to running - update UI immediately
                 if let Some(msg) = self.messages.iter_mut().find(|m| &m.id == message_id) {
                     // Mutable iteration over the vector of content blocks
@@ -131,7 +219,10 @@ impl MessagesArea {
                         // Pattern matching on enum variants within the loop
                         // ContentBlock::Code destructures to get execution_status field
                         // .. ignores other fields in the struct
-                        if let ContentBlock::Code { execution_status, .. } = block {
+                        if let ContentBlock::Code {
+                            execution_status, ..
+                        } = block
+                        {
                             // Dereference *execution_status to assign to the mutable reference
                             // ExecutionStatus is an enum, similar to Java enums
                             *execution_status = ExecutionStatus::Running;
@@ -162,10 +253,14 @@ impl MessagesArea {
                         if let Some(msg) = area.messages.iter_mut().find(|m| m.id == message_id) {
                             // Update all code blocks to success status
                             for block in &mut msg.blocks {
-                                if let ContentBlock::Code { execution_status, .. } = block {
+                                if let ContentBlock::Code {
+                                    execution_status, ..
+                                } = block
+                                {
                                     // Create a success result with simulated output
                                     // .into() converts &str to String (owned string)
-                                    *execution_status = ExecutionStatus::Success("Output: 42".into());
+                                    *execution_status =
+                                        ExecutionStatus::Success("Output: 42".into());
                                 }
                             }
                             // Notify UI to update with the results
@@ -185,7 +280,10 @@ impl MessagesArea {
                 }
             }
             // Enum variant with multiple fields
-            MessageAction::EditMessage { message_id, new_content } => {
+            MessageAction::EditMessage {
+                message_id,
+                new_content,
+            } => {
                 // Create a new branch instead of overwriting
                 if let Some(msg) = self.messages.iter_mut().find(|m| &m.id == message_id) {
                     msg.total_branches += 1;
@@ -197,12 +295,17 @@ impl MessagesArea {
                     cx.notify();
                 }
             }
-            MessageAction::NavigateBranch { message_id, direction } => {
+            MessageAction::NavigateBranch {
+                message_id,
+                direction,
+            } => {
                 if let Some(msg) = self.messages.iter_mut().find(|m| &m.id == message_id) {
                     // Type casting and method chaining
                     // as i32 converts u32 to i32 for arithmetic
                     // max() and min() are methods on i32, similar to Math.max/min in Java
-                    let new_index = (msg.branch_index as i32 + direction).max(1).min(msg.total_branches as i32);
+                    let new_index = (msg.branch_index as i32 + direction)
+                        .max(1)
+                        .min(msg.total_branches as i32);
                     msg.branch_index = new_index as u32; // Cast back to u32
                     cx.notify();
                 }
@@ -219,7 +322,11 @@ impl MessagesArea {
 
     /// Send a user message and fetch an AI response
     /// Demonstrates async programming, ownership transfer, and UI state management in Rust
-    pub fn send_message_and_get_ai_response(&mut self, user_message: String, cx: &mut Context<Self>) {
+    pub fn send_message_and_get_ai_response(
+        &mut self,
+        user_message: String,
+        cx: &mut Context<Self>,
+    ) {
         // Add the user message to the UI immediately
         // ChatMessage::user() is an associated function (static method in Java terms)
         // user_message.clone() creates a copy because String is owned and we need it later
@@ -275,7 +382,7 @@ impl MessagesArea {
 impl EventEmitter<MessagesAreaEvent> for MessagesArea {}
 
 impl Render for MessagesArea {
-    fn render(&mut self, window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let messages: Vec<_> = self.messages.iter().cloned().collect();
         let has_messages = !messages.is_empty();
 
@@ -289,7 +396,29 @@ impl Render for MessagesArea {
             .gap_4()
             // Render messages using ChatMessage's render method
             .children(messages.into_iter().map(|msg| {
-                msg.render_message(window)
+                let is_unsent = self.unsent.contains(&msg.id);
+                div()
+                    .flex()
+                    .flex_col()
+                    .child(msg.render_message(window))
+                    // Badge under user messages that are waiting in the offline queue
+                    .when(is_unsent, |d| {
+                        d.child(
+                            div()
+                                .ml_auto()
+                                .px_2()
+                                .text_xs()
+                                .text_color(colors::warning())
+                                .cursor_pointer()
+                                .on_mouse_down(
+                                    MouseButton::Left,
+                                    cx.listener(|_this, _event, _window, cx| {
+                                        cx.emit(MessagesAreaEvent::RetryUnsent);
+                                    }),
+                                )
+                                .child("⚠️ Not sent — retry"),
+                        )
+                    })
             }))
             // Empty state
             .when(!has_messages, |d| {
@@ -300,8 +429,8 @@ impl Render for MessagesArea {
                         .justify_center()
                         .h_full()
                         .text_color(colors::text_muted())
-                        .child("No messages yet. Start a conversation!")
+                        .child("No messages yet. Start a conversation!"),
                 )
             })
     }
-}
\ No newline at end of file
+}
@@ -11,6 +11,8 @@
 //! - [`button`] - Interactive button elements
 //! - [`card`] - Container components with styling
 //! - [`color_swatch`] - Color display elements
+//! - [`input_history`] - Up/Down recall of sent chat messages
+//! - [`text_buffer`] - Cursor and selection editing model behind [`TextInput`]
 //!
 //! # Example
 //!
@@ -28,8 +30,10 @@ pub mod card;
 pub mod chat;
 pub mod chat_input;
 pub mod color_swatch;
+pub mod input_history;
 pub mod message_item;
 pub mod messages_area;
+pub mod text_buffer;
 pub mod text_input;
 
 // Re-export component functions for convenient access
@@ -38,6 +42,8 @@ pub use card::{card, card_centered, card_full, card_sized};
 pub use chat::ChatView;
 pub use chat_input::{ChatInput, ChatInputEvent};
 pub use color_swatch::{color_swatch, color_swatch_row, color_swatch_sized};
+pub use input_history::InputHistory;
 pub use message_item::{ChatMessage, ContentBlock, ExecutionStatus, MessageAction};
-pub use messages_area::{MessagesArea, MessagesAreaEvent, MessageEvent};
-pub use text_input::TextInput;
\ No newline at end of file
+pub use messages_area::{MessageEvent, MessagesArea, MessagesAreaEvent};
+pub use text_buffer::TextBuffer;
+pub use text_input::TextInput;
@@ -0,0 +1,351 @@
+//! Editing model behind [`TextInput`](super::text_input::TextInput).
+//!
+//! Plain data plus logic, with no GPUI types, so every editing rule can be tested
+//! without a window. The buffer holds the text, a cursor and an optional selection
+//! anchor; the selection is whatever lies between the anchor and the cursor.
+//!
+//! Positions are byte offsets into the `String`, and they always sit on a `char`
+//! boundary: moving left from after "é" jumps both of its bytes, and slicing the
+//! text at the cursor never panics.
+//!
+//! Pasting goes through [`TextBuffer::paste`], which fits the clipboard to the field:
+//! line endings become `\n`, or spaces in a single-line field, and anything past
+//! [`paste_limit`](TextBuffer::with_paste_limit) characters is dropped, so a stray
+//! paste of a whole log file can't freeze the UI.
+
+use std::ops::Range;
+
+/// Most characters a single paste may insert unless changed with
+/// [`TextBuffer::with_paste_limit`]
+pub const DEFAULT_PASTE_LIMIT: usize = 10_000;
+
+/// Text with a cursor and a selection
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextBuffer {
+    text: String,
+    /// Byte offset of the cursor
+    cursor: usize,
+    /// Where the selection started; `None` (or equal to `cursor`) means no selection
+    anchor: Option<usize>,
+    /// Whether pasted line breaks are kept
+    multiline: bool,
+    /// Most characters one paste inserts
+    paste_limit: usize,
+}
+
+impl Default for TextBuffer {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            cursor: 0,
+            anchor: None,
+            multiline: false,
+            paste_limit: DEFAULT_PASTE_LIMIT,
+        }
+    }
+}
+
+impl TextBuffer {
+    /// An empty single-line buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep line breaks in pasted text instead of turning them into spaces
+    pub fn with_multiline(mut self, multiline: bool) -> Self {
+        self.multiline = multiline;
+        self
+    }
+
+    /// Cut pastes down to `limit` characters instead of [`DEFAULT_PASTE_LIMIT`]
+    pub fn with_paste_limit(mut self, limit: usize) -> Self {
+        self.paste_limit = limit;
+        self
+    }
+
+    /// The current text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Byte offset of the cursor
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The selected byte range, start first, or `None` when nothing is selected
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.anchor.filter(|&a| a != self.cursor)?;
+        Some(anchor.min(self.cursor)..anchor.max(self.cursor))
+    }
+
+    /// The selected text, if any
+    pub fn selected_text(&self) -> Option<&str> {
+        self.selection().map(|range| &self.text[range])
+    }
+
+    /// Replace the whole text; the cursor goes to the end and the selection is dropped
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+        self.anchor = None;
+    }
+
+    /// Empty the buffer
+    pub fn clear(&mut self) {
+        self.set_text(String::new());
+    }
+
+    /// Type `s`: it replaces the selection, or goes in at the cursor
+    pub fn insert(&mut self, s: &str) {
+        self.delete_selection();
+        self.text.insert_str(self.cursor, s);
+        self.cursor += s.len();
+    }
+
+    /// Paste `s` like [`insert`](Self::insert), after fitting it to the field (see the
+    /// module docs). Returns how many characters were cut off the end; 0 means all of it
+    /// went in.
+    pub fn paste(&mut self, s: &str) -> usize {
+        // "\r\n" (Windows) and a lone "\r" (old Mac) are both one line break
+        let s = s.replace("\r\n", "\n").replace('\r', "\n");
+        let s = if self.multiline {
+            s
+        } else {
+            s.replace('\n', " ")
+        };
+        let (kept, dropped) = match s.char_indices().nth(self.paste_limit) {
+            Some((end, _)) => (&s[..end], s[end..].chars().count()),
+            None => (s.as_str(), 0),
+        };
+        self.insert(kept);
+        dropped
+    }
+
+    /// Cut: remove the selection and return it for the clipboard
+    pub fn cut(&mut self) -> Option<String> {
+        let selected = self.selected_text()?.to_string();
+        self.delete_selection();
+        Some(selected)
+    }
+
+    /// Backspace: delete the selection, or the character before the cursor
+    pub fn backspace(&mut self) {
+        if !self.delete_selection() && self.cursor > 0 {
+            let start = self.prev_boundary(self.cursor);
+            self.text.replace_range(start..self.cursor, "");
+            self.cursor = start;
+        }
+    }
+
+    /// Delete: delete the selection, or the character after the cursor
+    pub fn delete(&mut self) {
+        if !self.delete_selection() && self.cursor < self.text.len() {
+            let end = self.next_boundary(self.cursor);
+            self.text.replace_range(self.cursor..end, "");
+        }
+    }
+
+    /// Left arrow. Without `select`, a selection collapses to its start instead.
+    pub fn move_left(&mut self, select: bool) {
+        match self.selection() {
+            Some(range) if !select => self.move_to(range.start, false),
+            _ => self.move_to(self.prev_boundary(self.cursor), select),
+        }
+    }
+
+    /// Right arrow. Without `select`, a selection collapses to its end instead.
+    pub fn move_right(&mut self, select: bool) {
+        match self.selection() {
+            Some(range) if !select => self.move_to(range.end, false),
+            _ => self.move_to(self.next_boundary(self.cursor), select),
+        }
+    }
+
+    /// Alt/Ctrl + left: to the start of this word, or of the previous one
+    pub fn move_word_left(&mut self, select: bool) {
+        let before = &self.text[..self.cursor];
+        // Skip the gap before the cursor, then the word before that
+        let word_end = before.trim_end_matches(|c: char| !is_word_char(c)).len();
+        let word_start = before[..word_end].trim_end_matches(is_word_char).len();
+        self.move_to(word_start, select);
+    }
+
+    /// Alt/Ctrl + right: to the end of this word, or of the next one
+    pub fn move_word_right(&mut self, select: bool) {
+        let after = &self.text[self.cursor..];
+        let gap = after.len() - after.trim_start_matches(|c: char| !is_word_char(c)).len();
+        let word = after[gap..].len() - after[gap..].trim_start_matches(is_word_char).len();
+        self.move_to(self.cursor + gap + word, select);
+    }
+
+    /// Up arrow: to the same column on the line above, or its end if that line is shorter.
+    /// On the first line there is nowhere to go up to, so it goes to the start of the text.
+    pub fn move_up(&mut self, select: bool) {
+        let line_start = self.line_start(self.cursor);
+        let target = match line_start.checked_sub(1) {
+            Some(prev_end) => self.at_column(self.line_start(prev_end), prev_end, self.column()),
+            None => 0,
+        };
+        self.move_to(target, select);
+    }
+
+    /// Down arrow: to the same column on the line below, or its end if that line is shorter.
+    /// On the last line it goes to the end of the text.
+    pub fn move_down(&mut self, select: bool) {
+        let target = match self.text[self.cursor..].find('\n') {
+            Some(offset) => {
+                let next_start = self.cursor + offset + 1;
+                self.at_column(next_start, self.line_end(next_start), self.column())
+            }
+            None => self.text.len(),
+        };
+        self.move_to(target, select);
+    }
+
+    /// Whether the cursor is on the first line, where Up can't go any higher
+    pub fn cursor_on_first_line(&self) -> bool {
+        !self.text[..self.cursor].contains('\n')
+    }
+
+    /// Whether the cursor is on the last line, where Down can't go any lower
+    pub fn cursor_on_last_line(&self) -> bool {
+        !self.text[self.cursor..].contains('\n')
+    }
+
+    /// Home: to the start of the text
+    pub fn move_home(&mut self, select: bool) {
+        self.move_to(0, select);
+    }
+
+    /// End: to the end of the text
+    pub fn move_end(&mut self, select: bool) {
+        self.move_to(self.text.len(), select);
+    }
+
+    /// Select everything, with the cursor at the end
+    pub fn select_all(&mut self) {
+        self.anchor = Some(0);
+        self.cursor = self.text.len();
+    }
+
+    /// The text cut where it is drawn differently: before the selection (or cursor), the
+    /// selection itself, and after it
+    pub fn segments(&self) -> (&str, &str, &str) {
+        let range = self.selection().unwrap_or(self.cursor..self.cursor);
+        (
+            &self.text[..range.start],
+            &self.text[range.clone()],
+            &self.text[range.end..],
+        )
+    }
+
+    /// The text line by line, each cut like [`segments`](Self::segments), for fields that
+    /// show more than one line. There is always at least one line.
+    pub fn lines(&self) -> Vec<Line<'_>> {
+        let range = self.selection().unwrap_or(self.cursor..self.cursor);
+        let mut start = 0;
+        self.text
+            .split('\n')
+            .map(|text| {
+                let end = start + text.len();
+                // The part of the selection on this line; the "\n" itself is never drawn
+                let (from, to) = (range.start.clamp(start, end), range.end.clamp(start, end));
+                let line = Line {
+                    before: &self.text[start..from],
+                    selected: &self.text[from..to],
+                    after: &self.text[to..end],
+                    cursor: (start..=end)
+                        .contains(&self.cursor)
+                        .then(|| self.cursor - start),
+                };
+                start = end + 1;
+                line
+            })
+            .collect()
+    }
+
+    /// Whether the cursor is at the end of the selection (it was made moving right)
+    pub fn cursor_at_selection_end(&self) -> bool {
+        self.selection()
+            .is_none_or(|range| range.end == self.cursor)
+    }
+
+    // Move the cursor; `select` keeps (or starts) the selection, otherwise it is dropped
+    fn move_to(&mut self, position: usize, select: bool) {
+        if select {
+            self.anchor.get_or_insert(self.cursor);
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = position;
+    }
+
+    // Start of the line holding byte `i`
+    fn line_start(&self, i: usize) -> usize {
+        self.text[..i].rfind('\n').map_or(0, |j| j + 1)
+    }
+
+    // End of the line holding byte `i`, before its "\n"
+    fn line_end(&self, i: usize) -> usize {
+        self.text[i..].find('\n').map_or(self.text.len(), |j| i + j)
+    }
+
+    // How many characters the cursor is from the start of its line
+    fn column(&self) -> usize {
+        self.text[self.line_start(self.cursor)..self.cursor]
+            .chars()
+            .count()
+    }
+
+    // Byte offset `column` characters into the line `start..end`, or `end` if it is shorter
+    fn at_column(&self, start: usize, end: usize, column: usize) -> usize {
+        self.text[start..end]
+            .char_indices()
+            .nth(column)
+            .map_or(end, |(j, _)| start + j)
+    }
+
+    // Remove the selected text, if any; returns whether there was some
+    fn delete_selection(&mut self) -> bool {
+        let Some(range) = self.selection() else {
+            self.anchor = None;
+            return false;
+        };
+        self.text.replace_range(range.clone(), "");
+        self.cursor = range.start;
+        self.anchor = None;
+        true
+    }
+
+    // Start of the character before byte `i`
+    fn prev_boundary(&self, i: usize) -> usize {
+        self.text[..i]
+            .char_indices()
+            .next_back()
+            .map_or(0, |(j, _)| j)
+    }
+
+    // End of the character at byte `i`
+    fn next_boundary(&self, i: usize) -> usize {
+        self.text[i..]
+            .chars()
+            .next()
+            .map_or(i, |c| i + c.len_utf8())
+    }
+}
+
+/// One line of a [`TextBuffer`]: the text before, in and after the selection
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Line<'a> {
+    pub before: &'a str,
+    pub selected: &'a str,
+    pub after: &'a str,
+    /// Byte offset of the cursor from the start of the line, if it is on this line
+    pub cursor: Option<usize>,
+}
+
+// Letters, digits and `_` make up words; everything else separates them
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
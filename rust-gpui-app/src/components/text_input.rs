@@ -1,82 +1,156 @@
 //! Text input component for capturing user text.
 //!
-//! A basic text input field that handles keyboard input and displays text.
+//! A text field, single-line unless built [`with_multiline`](TextInput::with_multiline).
+//! The editing rules (cursor, selection, word jumps) live in [`TextBuffer`]; this
+//! component maps keystrokes onto it and draws the result. Enter is left to the parent,
+//! which decides whether it sends or calls [`insert`](TextInput::insert) with `"\n"`.
+//!
+//! A multiline field grows with its text up to [`MAX_INPUT_HEIGHT`], then scrolls.
+//!
+//! Cmd/Ctrl + C, X and V copy, cut and paste through the system clipboard. A paste
+//! longer than the buffer's paste limit is cut short, and the field says so until the
+//! next keystroke.
 
+use crate::components::text_buffer::TextBuffer;
+use crate::theme::colors;
 use gpui::{
-    prelude::*,
-    div, IntoElement, ParentElement, SharedString, Styled, Window,
-    FocusHandle, Focusable, KeyDownEvent, MouseButton,
-    px,
+    div, prelude::*, px, ClipboardItem, FocusHandle, Focusable, IntoElement, KeyDownEvent,
+    MouseButton, ParentElement, Rgba, SharedString, Styled, Window,
 };
-use crate::theme::colors;
+
+/// Height in pixels a multiline field grows to before it scrolls (about six lines)
+pub const MAX_INPUT_HEIGHT: f32 = 160.0;
 
 /// A simple text input component
 pub struct TextInput {
-    /// The current text content
-    text: String,
+    /// The text, cursor and selection
+    buffer: TextBuffer,
     /// Placeholder text shown when empty
     placeholder: SharedString,
     /// Focus handle for keyboard events
     focus_handle: FocusHandle,
+    /// Characters the last paste left out, shown until the next keystroke
+    truncated: usize,
 }
 
 impl TextInput {
     /// Create a new text input with optional placeholder
     pub fn new(cx: &mut Context<Self>, placeholder: impl Into<SharedString>) -> Self {
         Self {
-            text: String::new(),
+            buffer: TextBuffer::new(),
             placeholder: placeholder.into(),
             focus_handle: cx.focus_handle(),
+            truncated: 0,
         }
     }
 
+    /// Cut pastes down to `limit` characters instead of
+    /// [`DEFAULT_PASTE_LIMIT`](crate::components::text_buffer::DEFAULT_PASTE_LIMIT)
+    pub fn with_paste_limit(mut self, limit: usize) -> Self {
+        self.buffer = self.buffer.with_paste_limit(limit);
+        self
+    }
+
+    /// Keep line breaks, from pastes and [`insert`](Self::insert), and grow to show them
+    pub fn with_multiline(mut self, multiline: bool) -> Self {
+        self.buffer = self.buffer.with_multiline(multiline);
+        self
+    }
+
     /// Get the current text
     pub fn text(&self) -> &str {
-        &self.text
+        self.buffer.text()
+    }
+
+    /// The text with its cursor and selection, e.g. to see which line the cursor is on
+    pub fn buffer(&self) -> &TextBuffer {
+        &self.buffer
     }
 
-    /// Set the text content
+    /// Set the text content; the cursor moves to the end
     pub fn set_text(&mut self, text: impl Into<String>) {
-        self.text = text.into();
+        self.buffer.set_text(text);
     }
 
     /// Clear the text
     pub fn clear(&mut self) {
-        self.text.clear();
+        self.buffer.clear();
+    }
+
+    /// Type `text` at the cursor, replacing any selection
+    pub fn insert(&mut self, text: &str) {
+        self.buffer.insert(text);
     }
 
     /// Check if the input is empty
     pub fn is_empty(&self) -> bool {
-        self.text.is_empty()
+        self.buffer.text().is_empty()
     }
 
     /// Handle a key down event
     fn handle_key_down(&mut self, event: &KeyDownEvent, cx: &mut Context<Self>) {
-        let key = &event.keystroke.key;
-        
-        if key == "backspace" {
-            self.text.pop();
-            cx.notify();
-        } else if key == "space" {
-            self.text.push(' ');
-            cx.notify();
-        } else if key == "enter" {
+        let key = event.keystroke.key.as_str();
+        let modifiers = &event.keystroke.modifiers;
+        // Shift extends the selection; Alt (macOS) or Ctrl (elsewhere) jumps by words
+        let select = modifiers.shift;
+        let by_word = modifiers.alt || modifiers.control;
+        let shortcut = modifiers.platform || modifiers.control;
+        self.truncated = 0;
+        let buffer = &mut self.buffer;
+
+        match key {
+            "left" if modifiers.platform => buffer.move_home(select),
+            "right" if modifiers.platform => buffer.move_end(select),
+            "left" if by_word => buffer.move_word_left(select),
+            "right" if by_word => buffer.move_word_right(select),
+            "left" => buffer.move_left(select),
+            "right" => buffer.move_right(select),
+            "up" => buffer.move_up(select),
+            "down" => buffer.move_down(select),
+            "home" => buffer.move_home(select),
+            "end" => buffer.move_end(select),
+            "backspace" => buffer.backspace(),
+            "delete" => buffer.delete(),
+            "a" if shortcut => buffer.select_all(),
+            "c" if shortcut => {
+                if let Some(selected) = buffer.selected_text() {
+                    cx.write_to_clipboard(ClipboardItem::new_string(selected.to_string()));
+                }
+            }
+            "x" if shortcut => {
+                if let Some(selected) = buffer.cut() {
+                    cx.write_to_clipboard(ClipboardItem::new_string(selected));
+                }
+            }
+            "v" if shortcut => {
+                // Images and other non-text clipboard contents are ignored
+                if let Some(text) = cx.read_from_clipboard().and_then(|item| item.text()) {
+                    self.truncated = buffer.paste(&text);
+                }
+            }
             // Enter is handled by parent
-            cx.notify();
-        } else if let Some(ref ch) = event.keystroke.key_char {
-            // Use key_char for actual character input
-            self.text.push_str(ch);
-            cx.notify();
-        } else if key.len() == 1 {
-            // Single character key without key_char
-            let ch = if event.keystroke.modifiers.shift {
-                key.to_uppercase()
-            } else {
-                key.to_lowercase()
-            };
-            self.text.push_str(&ch);
-            cx.notify();
+            "enter" => {}
+            // Other shortcuts (Cmd+W, Ctrl+Z, ...) are not text
+            _ if shortcut => return,
+            "space" => buffer.insert(" "),
+            _ => {
+                if let Some(ch) = &event.keystroke.key_char {
+                    // Use key_char for actual character input
+                    buffer.insert(ch);
+                } else if key.chars().count() == 1 {
+                    // Single character key without key_char
+                    let ch = if select {
+                        key.to_uppercase()
+                    } else {
+                        key.to_lowercase()
+                    };
+                    buffer.insert(&ch);
+                } else {
+                    return;
+                }
+            }
         }
+        cx.notify();
     }
 }
 
@@ -90,49 +164,98 @@ impl Render for TextInput {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let focus_handle = self.focus_handle.clone();
         let is_focused = self.focus_handle.is_focused(window);
-        let display_text: SharedString = if self.text.is_empty() {
-            self.placeholder.clone()
-        } else {
-            self.text.clone().into()
-        };
-        let text_color = if self.text.is_empty() {
+        let is_empty = self.buffer.text().is_empty();
+        let text_color = if is_empty {
             colors::text_muted()
         } else {
             colors::text()
         };
 
+        // Each line is drawn in three runs so the cursor and the selection land where they
+        // belong: before | selection (highlighted) | after, with the cursor on whichever
+        // side of the selection it sits
+        let cursor = || div().w(px(2.0)).h_4().bg(colors::text());
+        let highlight = Rgba {
+            a: 0.35,
+            ..colors::primary()
+        };
+
+        let lines = if is_empty {
+            vec![div()
+                .flex()
+                .items_center()
+                .h_6()
+                .when(is_focused, |d| d.child(cursor()))
+                .child(self.placeholder.clone())]
+        } else {
+            self.buffer
+                .lines()
+                .into_iter()
+                .map(|line| {
+                    let selected_end = line.before.len() + line.selected.len();
+                    let cursor_first = is_focused && line.cursor == Some(line.before.len());
+                    let cursor_last =
+                        is_focused && !cursor_first && line.cursor == Some(selected_end);
+                    div()
+                        .flex()
+                        .items_center()
+                        .h_6()
+                        .flex_shrink_0()
+                        .whitespace_nowrap()
+                        .child(SharedString::from(line.before.to_string()))
+                        .when(cursor_first, |d| d.child(cursor()))
+                        .when(!line.selected.is_empty(), |d| {
+                            d.child(
+                                div()
+                                    .bg(highlight)
+                                    .rounded_sm()
+                                    .child(SharedString::from(line.selected.to_string())),
+                            )
+                        })
+                        .when(cursor_last, |d| d.child(cursor()))
+                        .child(SharedString::from(line.after.to_string()))
+                })
+                .collect()
+        };
+
         div()
             .id("text-input")
             .track_focus(&focus_handle)
             .on_key_down(cx.listener(|this, event: &KeyDownEvent, _window, cx| {
                 this.handle_key_down(event, cx);
             }))
-            .on_mouse_down(MouseButton::Left, cx.listener(move |_this, _event, window, _cx| {
-                window.focus(&focus_handle);
-            }))
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |_this, _event, window, _cx| {
+                    window.focus(&focus_handle);
+                }),
+            )
             .flex_grow()
             .p_2()
             .bg(colors::surface())
             .rounded_lg()
             .border_1()
-            .border_color(if is_focused { colors::primary() } else { colors::border() })
+            .border_color(if is_focused {
+                colors::primary()
+            } else {
+                colors::border()
+            })
             .text_color(text_color)
             .cursor_text()
             .child(
                 div()
+                    .id("text-input-lines")
                     .flex()
-                    .items_center()
-                    .h_6()
-                    .child(display_text)
-                    .when(is_focused, |d| {
-                        d.child(
-                            div()
-                                .w(px(2.0))
-                                .h_4()
-                                .bg(colors::text())
-                                .ml_px()
-                        )
-                    })
+                    .flex_col()
+                    .max_h(px(MAX_INPUT_HEIGHT))
+                    .overflow_y_scroll()
+                    .children(lines),
             )
+            .when(self.truncated > 0, |d| {
+                d.child(div().text_xs().text_color(colors::warning()).child(format!(
+                    "Paste cut short: {} characters left out",
+                    self.truncated
+                )))
+            })
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,35 @@
+//! The Tokio runtime that network calls run on.
+//!
+//! GPUI schedules its own tasks, but reqwest needs a Tokio reactor underneath. Building
+//! a runtime per request and calling `block_on` would park a thread for the whole
+//! request, so instead one multi-threaded runtime is started the first time it's
+//! needed and kept for the life of the app.
+//!
+//! Work goes in with [`Handle::spawn`]; the `JoinHandle` that comes back is an ordinary
+//! future, so a GPUI task can `.await` it without ever blocking a frame. Dropping the
+//! handle leaves the work running; `abort()` on it (or its `AbortHandle`) cancels it.
+
+use std::sync::OnceLock;
+
+use tokio::runtime::{Builder, Handle, Runtime};
+
+/// Worker threads: requests are one at a time, plus the odd connectivity probe
+const WORKER_THREADS: usize = 2;
+
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/// The shared runtime, started on first use
+pub fn handle() -> Handle {
+    RUNTIME
+        .get_or_init(|| {
+            Builder::new_multi_thread()
+                .worker_threads(WORKER_THREADS)
+                .thread_name("network-io")
+                .enable_all()
+                .build()
+                // Only fails when the OS won't give us threads; nothing would work then
+                .expect("could not start the network runtime")
+        })
+        .handle()
+        .clone()
+}
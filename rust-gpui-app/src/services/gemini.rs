@@ -3,11 +3,16 @@
 //! This module provides functionality to send messages to and receive responses
 //! from Google's Gemini AI models, with support for both regular and streaming responses.
 
+use crate::services::sse::SseDecoder;
 use crate::state::{ChatMessage, MessageRole};
 use gpui::SharedString;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
+
+/// Root of the Gemini API; also used as the target of the connectivity probe
+pub const API_BASE_URL: &str = "https://generativelanguage.googleapis.com";
 
 /// Gemini API request structure
 #[derive(Serialize)]
@@ -46,27 +51,34 @@ struct SafetySetting {
     threshold: String,
 }
 
-/// Gemini API response structure
+/// Gemini API response structure, also the shape of each streamed chunk.
+///
+/// Everything defaults to empty: the last chunk of a stream may carry only a finish
+/// reason or token counts, and that is not an error.
 #[derive(Deserialize)]
 struct GeminiResponse {
+    #[serde(default)]
     candidates: Vec<GeminiCandidate>,
 }
 
 /// Candidate response from Gemini
 #[derive(Deserialize)]
 struct GeminiCandidate {
+    #[serde(default)]
     content: GeminiContentResponse,
 }
 
 /// Content in Gemini response
-#[derive(Deserialize)]
+#[derive(Default, Deserialize)]
 struct GeminiContentResponse {
+    #[serde(default)]
     parts: Vec<GeminiPartResponse>,
 }
 
 /// Part in Gemini response
 #[derive(Deserialize)]
 struct GeminiPartResponse {
+    #[serde(default)]
     text: String,
 }
 
@@ -87,6 +99,7 @@ pub struct GeminiClient {
     client: Client,
     api_key: String,
     model: String,
+    base_url: String,
 }
 
 impl GeminiClient {
@@ -96,9 +109,16 @@ impl GeminiClient {
             client: Client::new(),
             api_key,
             model: "gemini-pro".to_string(), // Default model
+            base_url: API_BASE_URL.to_string(),
         }
     }
 
+    /// Talk to another server than [`API_BASE_URL`], such as a local stand-in in tests
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
     /// Set the model to use
     pub fn with_model(mut self, model: String) -> Self {
         self.model = model;
@@ -111,10 +131,35 @@ impl GeminiClient {
         messages: &[ChatMessage],
     ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
         let url = format!(
-            "https://generativelanguage.googleapis.com/v1beta/models/{}:generateContent?key={}",
-            self.model, self.api_key
+            "{}/v1beta/models/{}:generateContent?key={}",
+            self.base_url, self.model, self.api_key
         );
 
+        let response = self
+            .client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .json(&Self::request(messages))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response).await);
+        }
+
+        let gemini_response: GeminiResponse = response.json().await?;
+        let text = gemini_response
+            .candidates
+            .first()
+            .and_then(|candidate| candidate.content.parts.first())
+            .map(|part| part.text.clone())
+            .unwrap_or_else(|| "No response generated".to_string());
+
+        Ok(text)
+    }
+
+    /// The request body for a conversation, oldest message first
+    fn request(messages: &[ChatMessage]) -> GeminiRequest {
         let contents = messages
             .iter()
             .map(|msg| GeminiContent {
@@ -128,7 +173,7 @@ impl GeminiClient {
             })
             .collect();
 
-        let request = GeminiRequest {
+        GeminiRequest {
             contents,
             generation_config: Some(GenerationConfig {
                 temperature: Some(0.7),
@@ -137,44 +182,108 @@ impl GeminiClient {
                 max_output_tokens: Some(1024),
             }),
             safety_settings: None,
-        };
+        }
+    }
+
+    /// Cheap connectivity check: a HEAD request to the API root.
+    ///
+    /// Any HTTP response (even a 404) means the network and the server are reachable;
+    /// only connection failures and timeouts count as offline.
+    pub async fn probe(&self) -> bool {
+        self.client
+            .head(&self.base_url)
+            .timeout(Duration::from_secs(5))
+            .send()
+            .await
+            .is_ok()
+    }
 
-        let response = self.client
+    /// Send a message and stream the response: `on_delta` is called with each piece of
+    /// text as it arrives, and the whole reply is returned at the end.
+    ///
+    /// Uses `streamGenerateContent?alt=sse`, which answers with Server-Sent Events
+    /// (see [`SseDecoder`]) instead of one JSON body.
+    pub async fn stream_message(
+        &self,
+        messages: &[ChatMessage],
+        mut on_delta: impl FnMut(&str),
+    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        let url = format!(
+            "{}/v1beta/models/{}:streamGenerateContent?alt=sse&key={}",
+            self.base_url, self.model, self.api_key
+        );
+
+        let mut response = self
+            .client
             .post(&url)
             .header("Content-Type", "application/json")
-            .json(&request)
+            .json(&Self::request(messages))
             .send()
             .await?;
 
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await?;
-            if let Ok(error) = serde_json::from_str::<GeminiError>(&error_text) {
-                return Err(format!("Gemini API error: {}", error.error.message).into());
-            } else {
-                return Err(format!("HTTP error {}: {}", status, error_text).into());
+            return Err(api_error(response).await);
+        }
+
+        let mut decoder = SseDecoder::new();
+        let mut full_text = String::new();
+        let mut on_event = |data: String| -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            let text = chunk_text(&data)?;
+            if !text.is_empty() {
+                on_delta(&text);
+                full_text.push_str(&text);
             }
+            Ok(())
+        };
+        // chunk() hands over the body piece by piece as the network delivers it
+        while let Some(chunk) = response.chunk().await? {
+            decoder
+                .push(&chunk)
+                .into_iter()
+                .try_for_each(&mut on_event)?;
         }
+        decoder.finish().into_iter().try_for_each(&mut on_event)?;
 
-        let gemini_response: GeminiResponse = response.json().await?;
-        let text = gemini_response
-            .candidates
-            .first()
-            .and_then(|candidate| candidate.content.parts.first())
-            .map(|part| part.text.clone())
-            .unwrap_or_else(|| "No response generated".to_string());
+        if full_text.is_empty() {
+            full_text = "No response generated".to_string();
+            on_delta(&full_text);
+        }
+        Ok(full_text)
+    }
+}
 
-        Ok(text)
+/// The text in one streamed chunk (the `data` of one SSE event): the text of every part
+/// of the first candidate. A chunk holding an API error instead becomes an `Err`.
+pub fn chunk_text(data: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    if let Ok(error) = serde_json::from_str::<GeminiError>(data) {
+        return Err(format!("Gemini API error: {}", error.error.message).into());
     }
+    let chunk: GeminiResponse = serde_json::from_str(data)?;
+    Ok(chunk
+        .candidates
+        .first()
+        .map(|candidate| {
+            candidate
+                .content
+                .parts
+                .iter()
+                .map(|part| part.text.as_str())
+                .collect()
+        })
+        .unwrap_or_default())
+}
 
-    /// Send a message with streaming response (placeholder for future implementation)
-    pub async fn stream_message(
-        &self,
-        messages: &[ChatMessage],
-    ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-        // For now, just call the regular send_message
-        // Streaming implementation would require processing Server-Sent Events
-        self.send_message(messages).await
+/// Turn an unsuccessful response into an error, with Gemini's message when it sent one
+async fn api_error(response: reqwest::Response) -> Box<dyn std::error::Error + Send + Sync> {
+    let status = response.status();
+    let error_text = match response.text().await {
+        Ok(text) => text,
+        Err(e) => return e.into(),
+    };
+    if let Ok(error) = serde_json::from_str::<GeminiError>(&error_text) {
+        format!("Gemini API error: {}", error.error.message).into()
+    } else {
+        format!("HTTP error {}: {}", status, error_text).into()
     }
 }
 
@@ -191,7 +300,20 @@ pub async fn send_message(
 pub async fn stream_message(
     api_key: &str,
     messages: &[ChatMessage],
+    on_delta: impl FnMut(&str),
 ) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
     let client = GeminiClient::new(api_key.to_string());
-    client.stream_message(messages).await
-}
\ No newline at end of file
+    client.stream_message(messages, on_delta).await
+}
+
+/// Whether an error from [`GeminiClient::send_message`] means "could not reach the
+/// server" (worth queueing and retrying) rather than "the server said no".
+pub fn is_connectivity_error(error: &(dyn std::error::Error + 'static)) -> bool {
+    // downcast_ref is like `instanceof` + cast in Java: it returns Some only if the
+    // boxed error really is a reqwest::Error
+    error
+        .downcast_ref::<reqwest::Error>()
+        // is_body: the connection dropped while a (streamed) reply was coming in
+        .map(|e| e.is_connect() || e.is_timeout() || e.is_request() || e.is_body())
+        .unwrap_or(false)
+}
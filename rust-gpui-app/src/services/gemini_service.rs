@@ -2,40 +2,91 @@
 //!
 //! This service subscribes to ChatInputEvent from the chat input component
 //! and emits assistant messages after querying the Gemini API.
+//!
+//! Every user message goes through an [`OfflineQueue`]. Messages are sent one at a
+//! time, oldest first; if the network is down the message stays queued (and is saved
+//! to disk) and is retried with backoff once a connectivity probe succeeds.
+//!
+//! Replies are streamed. Each one is announced with `StreamStarted`, grows through
+//! `StreamDelta` events as Gemini writes it, and ends with `StreamComplete` (or
+//! `StreamAborted` if the request failed part-way, so the half reply can be dropped).
+//!
+//! Network IO runs on the shared [`background`] runtime; the GPUI side only awaits
+//! its results, so no frame ever waits on the network. The request in flight can be
+//! cancelled with [`GeminiService::cancel`]; clearing the conversation does so too,
+//! since its reply would belong to a conversation that is gone. Sending a new message
+//! supersedes it: its half-written reply is aborted, but the message itself stays
+//! unsent at the front of the queue, so it is sent again before the new one and the
+//! conversation keeps its order.
 use dotenv::dotenv;
+use std::path::PathBuf;
+use std::time::Duration;
 
-use gpui::{prelude::*, EventEmitter, SharedString};
 use crate::components::chat_input::ChatInputEvent;
-use crate::services::gemini::GeminiClient;
+use crate::services::background;
+use crate::services::gemini::{is_connectivity_error, GeminiClient, API_BASE_URL};
+use crate::services::offline_queue::{now_ms, OfflineQueue, PendingMessage, DEFAULT_QUEUE_PATH};
 use crate::state::{ChatMessage, MessageRole};
-use crate::utils::parser::parse_assistant_response;
 use chrono::Utc;
+use gpui::{prelude::*, EventEmitter, SharedString};
 
 /// Events emitted by the Gemini service
 #[derive(Clone, Debug)]
 pub enum GeminiServiceEvent {
     /// An assistant message was generated (raw string, legacy)
     // AssistantMessage(String),
-    /// An assistant message parsed into structured content blocks
-    AssistantMessageParsed(Vec<crate::components::message_item::ContentBlock>),
+    /// A streamed reply is on its way. `reply_to` is the transcript id of the user
+    /// message it answers; `message_id` names the reply in the events that follow.
+    StreamStarted {
+        reply_to: String,
+        message_id: String,
+    },
+    /// The next piece of a streamed reply's text
+    StreamDelta { message_id: String, text: String },
+    /// The streamed reply is whole
+    StreamComplete { message_id: String },
+    /// The request failed; what was streamed so far should be thrown away
+    StreamAborted { message_id: String },
     /// An error occurred while generating a response
     Error(String),
     /// Processing started
     Processing,
+    /// The set of user messages that failed to send changed (transcript ids, oldest first)
+    QueueChanged { unsent: Vec<String> },
+}
+
+/// The request being answered right now
+struct InFlight {
+    /// Transcript id of the user message it sends
+    transcript_id: String,
+    /// Id of the streamed reply
+    message_id: String,
+    /// Cancels the request on the background runtime
+    abort: tokio::task::AbortHandle,
 }
 
 /// Gemini service for processing chat messages
 pub struct GeminiService {
-    /// Whether we're currently processing
-    is_processing: bool,
+    /// The request in flight, if any; at most one at a time
+    in_flight: Option<InFlight>,
+    /// Where network IO runs
+    runtime: tokio::runtime::Handle,
     /// Whether the API is configured
     is_configured: bool,
     /// The API key for Gemini
     api_key: Option<String>,
+    /// Root of the Gemini API (a local stand-in in tests)
+    base_url: String,
+    /// Where unsent messages are saved between runs
+    queue_path: PathBuf,
     /// Conversation history for context
     conversation_history: Vec<ChatMessage>,
     /// Message ID counter
     next_message_id: u64,
+    /// User messages that still have to be sent, oldest first
+    queue: OfflineQueue,
+    /// Whether a backoff timer is already waiting to retry the queue
+    retry_scheduled: bool,
 }
 
 impl GeminiService {
@@ -44,13 +95,35 @@ impl GeminiService {
         dotenv().ok();
         // Try to get API key from environment
         let api_key = std::env::var("GEMINI_API_KEY").ok();
-        
+        Self::with_config(api_key, API_BASE_URL, DEFAULT_QUEUE_PATH)
+    }
+
+    /// A service with an explicit key, API root and queue file, instead of the
+    /// environment and the defaults (tests point it at a local server)
+    pub fn with_config(
+        api_key: Option<String>,
+        base_url: impl Into<String>,
+        queue_path: impl Into<PathBuf>,
+    ) -> Self {
+        let queue_path = queue_path.into();
+
+        // Messages that were still unsent when the app last closed
+        let queue = OfflineQueue::load(&queue_path).unwrap_or_else(|e| {
+            eprintln!("Ignoring {}: {}", queue_path.display(), e);
+            OfflineQueue::new()
+        });
+
         Self {
-            is_processing: false,
+            in_flight: None,
+            runtime: background::handle(),
             is_configured: api_key.is_some(),
             api_key,
+            base_url: base_url.into(),
+            queue_path,
             conversation_history: Vec::new(),
             next_message_id: 0,
+            queue,
+            retry_scheduled: false,
         }
     }
 
@@ -58,66 +131,155 @@ impl GeminiService {
     pub fn handle_chat_input(&mut self, event: &ChatInputEvent, cx: &mut Context<Self>) {
         match event {
             ChatInputEvent::SendMessage(text) => {
-                self.process_message(text.clone(), cx);
+                let transcript_id = uuid::Uuid::new_v4().to_string();
+                self.submit(transcript_id, text.clone(), cx);
             }
         }
     }
 
-    /// Process a user message and generate a response
-    fn process_message(&mut self, text: String, cx: &mut Context<Self>) {
-        // Check if already processing
-        if self.is_processing {
+    /// Queue a user message for sending. `transcript_id` is the id of the message
+    /// in the transcript, echoed back in `StreamStarted::reply_to`.
+    pub fn submit(&mut self, transcript_id: String, text: String, cx: &mut Context<Self>) {
+        // Without a key the message can never be sent, so don't queue it
+        if self.api_key.is_none() {
+            cx.emit(GeminiServiceEvent::Error(
+                "No Gemini API key configured. Set GEMINI_API_KEY environment variable."
+                    .to_string(),
+            ));
+            cx.notify();
             return;
         }
 
-        // Emit processing event
-        self.is_processing = true;
-        cx.emit(GeminiServiceEvent::Processing);
+        // The new message supersedes the reply being streamed. Only the stream stops: its
+        // message keeps its place (and its "Not sent" badge) at the front of the queue.
+        let superseded = self.abort_in_flight(cx).is_some();
+
+        self.queue.push(transcript_id, text);
+        self.save_queue();
+        if superseded {
+            self.emit_queue_changed(cx);
+        }
+        self.process_queue(cx);
+    }
+
+    /// Messages restored from disk or waiting to be sent, oldest first
+    pub fn pending_messages(&self) -> Vec<PendingMessage> {
+        self.queue.iter().cloned().collect()
+    }
+
+    /// Transcript ids of messages that failed to send
+    pub fn unsent_ids(&self) -> Vec<String> {
+        self.queue.unsent_ids()
+    }
+
+    /// Retry every queued message now (the "Retry all" button)
+    pub fn retry_all(&mut self, cx: &mut Context<Self>) {
+        self.queue.retry_all(now_ms());
+        self.save_queue();
+        self.process_queue(cx);
+    }
+
+    /// Persist the queue so unsent messages survive a restart
+    fn save_queue(&self) {
+        if let Err(e) = self.queue.save(&self.queue_path) {
+            eprintln!("Could not save {}: {}", self.queue_path.display(), e);
+        }
+    }
+
+    /// Tell listeners which messages should show the "Not sent" badge
+    fn emit_queue_changed(&self, cx: &mut Context<Self>) {
+        cx.emit(GeminiServiceEvent::QueueChanged {
+            unsent: self.queue.unsent_ids(),
+        });
         cx.notify();
+    }
 
-        // Check if API key is configured
+    /// Send the oldest queued message if nothing is in flight and its backoff elapsed.
+    /// Only one request runs at a time so replies arrive in prompt order.
+    fn process_queue(&mut self, cx: &mut Context<Self>) {
+        if self.in_flight.is_some() {
+            return;
+        }
         let Some(api_key) = self.api_key.clone() else {
-            self.is_processing = false;
-            cx.emit(GeminiServiceEvent::Error(
-                "No Gemini API key configured. Set GEMINI_API_KEY environment variable.".to_string()
-            ));
-            cx.notify();
+            return;
+        };
+        let Some(pending) = self.queue.ready(now_ms()).cloned() else {
+            // Front message is still backing off (or the queue is empty)
+            self.schedule_retry(cx);
             return;
         };
 
-        // Add user message to conversation history
-        let user_message = ChatMessage {
+        // Emit processing event
+        cx.emit(GeminiServiceEvent::Processing);
+        cx.notify();
+
+        // History only contains delivered messages; the pending prompt goes last
+        let mut messages = self.conversation_history.clone();
+        messages.push(ChatMessage {
             id: self.next_message_id,
             role: MessageRole::User,
-            content: SharedString::from(text.clone()),
+            content: SharedString::from(pending.text.clone()),
             timestamp: Utc::now(),
-        };
-        self.next_message_id += 1;
-        self.conversation_history.push(user_message);
+        });
 
-        // Clone what we need for the async task
-        let messages = self.conversation_history.clone();
+        let message_id = uuid::Uuid::new_v4().to_string();
+        cx.emit(GeminiServiceEvent::StreamStarted {
+            reply_to: pending.transcript_id.clone(),
+            message_id: message_id.clone(),
+        });
+
+        // The request runs on the background runtime, so the UI keeps drawing while the
+        // reply streams in; each piece of text comes back over the channel
+        let (deltas, mut received) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let client = GeminiClient::new(api_key)
+            .with_base_url(self.base_url.clone())
+            .with_model("gemini-2.0-flash".to_string());
+        let request = self.runtime.spawn(async move {
+            client
+                .stream_message(&messages, |text| {
+                    // Only fails if the UI side is gone, and then nobody is reading
+                    deltas.send(text.to_string()).ok();
+                })
+                .await
+            // `deltas` is dropped here (or when the task is aborted), which ends the loop below
+        });
+        self.in_flight = Some(InFlight {
+            transcript_id: pending.transcript_id.clone(),
+            message_id: message_id.clone(),
+            abort: request.abort_handle(),
+        });
 
-        // Spawn async task to call Gemini API
         cx.spawn(async move |this: gpui::WeakEntity<GeminiService>, cx| {
-            // reqwest requires a Tokio runtime, so we spawn a blocking task with its own runtime
-            let result = tokio::runtime::Builder::new_current_thread()
-                .enable_all()
-                .build()
-                .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
-                .and_then(|rt| {
-                    rt.block_on(async {
-                        let client = GeminiClient::new(api_key).with_model("gemini-2.0-flash".to_string());
-                        client.send_message(&messages).await
-                    })
-                });
+            while let Some(text) = received.recv().await {
+                let message_id = message_id.clone();
+                this.update(cx, |_service, inner_cx| {
+                    inner_cx.emit(GeminiServiceEvent::StreamDelta { message_id, text });
+                })
+                .ok();
+            }
+            // Awaiting a JoinHandle suspends this task; it never blocks the thread
+            let result = request.await.unwrap_or_else(|e| Err(e.into()));
 
             this.update(cx, |service, inner_cx| {
-                service.is_processing = false;
+                // Cancelled meanwhile: cancel() has already cleaned up after it
+                if service.in_flight.as_ref().map(|f| &f.message_id) != Some(&message_id) {
+                    return;
+                }
+                service.in_flight = None;
+                let transcript_id = pending.transcript_id.as_str();
 
                 match result {
                     Ok(response_text) => {
-                        // Add assistant message to conversation history (raw string)
+                        service.queue.mark_sent(transcript_id);
+
+                        // The prompt and its reply become part of the conversation
+                        service.conversation_history.push(ChatMessage {
+                            id: service.next_message_id,
+                            role: MessageRole::User,
+                            content: SharedString::from(pending.text.clone()),
+                            timestamp: Utc::now(),
+                        });
+                        service.next_message_id += 1;
                         let assistant_message = ChatMessage {
                             id: service.next_message_id,
                             role: MessageRole::Assistant,
@@ -127,29 +289,99 @@ impl GeminiService {
                         service.next_message_id += 1;
                         service.conversation_history.push(assistant_message);
 
-                        // Emit legacy raw string event for backwards compatibility
-                        // inner_cx.emit(GeminiServiceEvent::AssistantMessage(response_text.clone()));
+                        inner_cx.emit(GeminiServiceEvent::StreamComplete { message_id });
 
-                        // Parse assistant response into structured ContentBlocks and emit parsed event
-                        let blocks = parse_assistant_response(&response_text);
-                        //convert blocks to json and pretty print
-                        // print!("Parsed assistant response blocks: {}\n", serde_json::to_string_pretty(&blocks).unwrap());
-                        inner_cx.emit(GeminiServiceEvent::AssistantMessageParsed(blocks));
+                        // We're evidently online: anything still backing off can go now
+                        service.queue.retry_all(now_ms());
+                    }
+                    Err(e) if is_connectivity_error(e.as_ref()) => {
+                        // Keep the message and try again later; the retry streams a fresh reply
+                        inner_cx.emit(GeminiServiceEvent::StreamAborted { message_id });
+                        service.queue.mark_failed(transcript_id, now_ms());
                     }
                     Err(e) => {
+                        // The API rejected the message; retrying won't help
+                        inner_cx.emit(GeminiServiceEvent::StreamAborted { message_id });
+                        service.queue.remove(transcript_id);
                         inner_cx.emit(GeminiServiceEvent::Error(e.to_string()));
                     }
                 }
-                inner_cx.notify();
-            }).ok();
+
+                service.save_queue();
+                service.emit_queue_changed(inner_cx);
+                service.process_queue(inner_cx);
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    /// Wait for the front message's backoff, probe connectivity, then resume.
+    /// A failed probe counts as another failed attempt, so the wait keeps growing.
+    fn schedule_retry(&mut self, cx: &mut Context<Self>) {
+        if self.retry_scheduled {
+            return;
+        }
+        let (Some(delay_ms), Some(api_key)) =
+            (self.queue.ms_until_ready(now_ms()), self.api_key.clone())
+        else {
+            return;
+        };
+        self.retry_scheduled = true;
+        let runtime = self.runtime.clone();
+        let client = GeminiClient::new(api_key).with_base_url(self.base_url.clone());
+
+        cx.spawn(async move |this: gpui::WeakEntity<GeminiService>, cx| {
+            cx.background_executor()
+                .timer(Duration::from_millis(delay_ms))
+                .await;
+            let probe = runtime.spawn(async move { client.probe().await });
+
+            let online = probe.await.unwrap_or(false);
+
+            this.update(cx, |service, inner_cx| {
+                service.retry_scheduled = false;
+                if online {
+                    service.queue.retry_all(now_ms());
+                } else if let Some(front) =
+                    service.queue.iter().next().map(|m| m.transcript_id.clone())
+                {
+                    service.queue.mark_failed(&front, now_ms());
+                    service.save_queue();
+                }
+                service.process_queue(inner_cx);
+            })
+            .ok();
         })
         .detach();
     }
-    
 
     /// Check if the service is processing
     pub fn is_processing(&self) -> bool {
-        self.is_processing
+        self.in_flight.is_some()
+    }
+
+    /// Stop the request in flight, if any. Its message is dropped from the queue and the
+    /// half-streamed reply is aborted; the next queued message goes out instead.
+    pub fn cancel(&mut self, cx: &mut Context<Self>) {
+        if let Some(transcript_id) = self.abort_in_flight(cx) {
+            self.queue.remove(&transcript_id);
+            self.save_queue();
+            self.emit_queue_changed(cx);
+            self.process_queue(cx);
+        }
+    }
+
+    /// Abort the request in flight, if any, and return the transcript id of its message.
+    /// The message is left in the queue; its late result is ignored, because `in_flight`
+    /// no longer names its reply.
+    fn abort_in_flight(&mut self, cx: &mut Context<Self>) -> Option<String> {
+        let in_flight = self.in_flight.take()?;
+        in_flight.abort.abort();
+        cx.emit(GeminiServiceEvent::StreamAborted {
+            message_id: in_flight.message_id,
+        });
+        Some(in_flight.transcript_id)
     }
 
     /// Check if the service has a configured API key
@@ -157,10 +389,11 @@ impl GeminiService {
         self.is_configured
     }
 
-    /// Clear conversation history
-    pub fn clear_history(&mut self) {
+    /// Clear conversation history, cancelling the reply in flight
+    pub fn clear_history(&mut self, cx: &mut Context<Self>) {
         self.conversation_history.clear();
+        self.cancel(cx);
     }
 }
 
-impl EventEmitter<GeminiServiceEvent> for GeminiService {}
\ No newline at end of file
+impl EventEmitter<GeminiServiceEvent> for GeminiService {}
@@ -1,5 +1,9 @@
+pub mod background;
 pub mod gemini;
 pub mod gemini_service;
+pub mod offline_queue;
+pub mod sse;
 
 pub use gemini::GeminiClient;
-pub use gemini_service::{GeminiService, GeminiServiceEvent};
\ No newline at end of file
+pub use gemini_service::{GeminiService, GeminiServiceEvent};
+pub use offline_queue::{OfflineQueue, PendingMessage};
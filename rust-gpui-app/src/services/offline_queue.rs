@@ -0,0 +1,203 @@
+//! Offline queue for chat messages that could not be delivered.
+//!
+//! When the network drops, user prompts wait here instead of being lost. Messages
+//! are always sent oldest-first, so replies land under the prompts they answer.
+//!
+//! The queue is plain data plus logic: no GPUI types and no network calls. Time is
+//! passed in as milliseconds since the Unix epoch, which keeps the backoff rules
+//! easy to test and lets the queue be saved to disk exactly as it is in memory.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where the queue is persisted between runs (relative to the working directory)
+pub const DEFAULT_QUEUE_PATH: &str = ".pending_messages.json";
+
+/// Current time in milliseconds since the Unix epoch
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// A user message waiting to be sent
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingMessage {
+    /// Id of the message in the transcript (MessagesArea), used to place the reply
+    pub transcript_id: String,
+    /// The prompt text
+    pub text: String,
+    /// How many times sending has failed so far
+    pub attempts: u32,
+    /// Earliest time (ms since epoch) the next attempt may start
+    pub next_attempt_at: u64,
+}
+
+/// Exponential backoff between attempts: base, 2x base, 4x base, ... up to `max_ms`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Backoff {
+    pub base_ms: u64,
+    pub max_ms: u64,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            base_ms: 2_000,
+            max_ms: 60_000,
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay to wait after the `attempts`-th failure (1 = first failure)
+    pub fn delay_ms(&self, attempts: u32) -> u64 {
+        // Cap the exponent so the shift below can't overflow
+        let exponent = attempts.saturating_sub(1).min(20);
+        self.base_ms.saturating_mul(1 << exponent).min(self.max_ms)
+    }
+}
+
+/// FIFO queue of messages that still have to be sent.
+///
+/// Only the front message is ever handed out. A later message is never sent while
+/// an earlier one is still waiting, otherwise the conversation would be reordered.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct OfflineQueue {
+    /// VecDeque is a ring buffer: cheap push at the back and pop at the front
+    messages: VecDeque<PendingMessage>,
+    // Backoff settings are code, not data, so they are not written to disk
+    #[serde(skip)]
+    backoff: Backoff,
+}
+
+impl OfflineQueue {
+    /// Create an empty queue with the default backoff
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use a custom backoff (builder style)
+    pub fn with_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Load a queue saved by [`OfflineQueue::save`]. A missing file is an empty queue.
+    pub fn load(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Self::new());
+        }
+        let json = std::fs::read_to_string(path)?;
+        serde_json::from_str(&json)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Write the queue to disk (an empty queue removes the file)
+    pub fn save(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let path = path.as_ref();
+        if self.messages.is_empty() {
+            return match std::fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        std::fs::write(path, json)
+    }
+
+    /// Add a message to the back of the queue; it can be sent right away
+    pub fn push(&mut self, transcript_id: impl Into<String>, text: impl Into<String>) {
+        self.messages.push_back(PendingMessage {
+            transcript_id: transcript_id.into(),
+            text: text.into(),
+            attempts: 0,
+            next_attempt_at: 0,
+        });
+    }
+
+    /// Number of queued messages
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    /// Whether nothing is waiting
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// All queued messages, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &PendingMessage> {
+        self.messages.iter()
+    }
+
+    /// The message to send next, if its backoff has elapsed
+    pub fn ready(&self, now: u64) -> Option<&PendingMessage> {
+        self.messages.front().filter(|m| m.next_attempt_at <= now)
+    }
+
+    /// Milliseconds until the front message may be retried (0 = now, None = queue empty)
+    pub fn ms_until_ready(&self, now: u64) -> Option<u64> {
+        self.messages
+            .front()
+            .map(|m| m.next_attempt_at.saturating_sub(now))
+    }
+
+    /// The front message was delivered: remove it. Returns it, or `None` if
+    /// `transcript_id` is not at the front (ordering would be violated).
+    pub fn mark_sent(&mut self, transcript_id: &str) -> Option<PendingMessage> {
+        if self.messages.front()?.transcript_id != transcript_id {
+            return None;
+        }
+        self.messages.pop_front()
+    }
+
+    /// The front message failed: push its next attempt back. Returns the delay in ms.
+    pub fn mark_failed(&mut self, transcript_id: &str, now: u64) -> Option<u64> {
+        let backoff = self.backoff;
+        let front = self.messages.front_mut()?;
+        if front.transcript_id != transcript_id {
+            return None;
+        }
+        front.attempts += 1;
+        let delay = backoff.delay_ms(front.attempts);
+        front.next_attempt_at = now + delay;
+        Some(delay)
+    }
+
+    /// Make every message eligible immediately ("Retry all", or the network came back)
+    pub fn retry_all(&mut self, now: u64) {
+        for message in &mut self.messages {
+            message.next_attempt_at = now;
+        }
+    }
+
+    /// Drop a message that can never succeed (e.g. the API rejected it)
+    pub fn remove(&mut self, transcript_id: &str) -> Option<PendingMessage> {
+        let index = self
+            .messages
+            .iter()
+            .position(|m| m.transcript_id == transcript_id)?;
+        self.messages.remove(index)
+    }
+
+    /// Transcript ids that should show the "Not sent" badge.
+    ///
+    /// Once the front message has failed, everything behind it is stuck as well.
+    /// Messages that are merely waiting for an in-flight request are not flagged.
+    pub fn unsent_ids(&self) -> Vec<String> {
+        match self.messages.front() {
+            Some(front) if front.attempts > 0 => self
+                .messages
+                .iter()
+                .map(|m| m.transcript_id.clone())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
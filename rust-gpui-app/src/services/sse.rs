@@ -0,0 +1,76 @@
+//! Server-Sent Events decoding for streamed Gemini replies.
+//!
+//! With `?alt=sse`, `streamGenerateContent` answers with a text stream like
+//!
+//! ```text
+//! data: {"candidates":[{"content":{"parts":[{"text":"Hel"}]}}]}
+//!
+//! data: {"candidates":[{"content":{"parts":[{"text":"lo"}]}}]}
+//!
+//! ```
+//!
+//! Each event is one or more `data:` lines ended by a blank line. The network hands
+//! the bytes over in chunks that can end anywhere, even inside a multi-byte character,
+//! so [`SseDecoder`] keeps the unfinished tail until the rest arrives. Like
+//! [`OfflineQueue`](super::offline_queue::OfflineQueue), it is plain logic with no
+//! network, so it can be tested on its own.
+
+/// Turns chunks of an SSE stream into the `data` of each complete event
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    /// Bytes after the last line break
+    pending: Vec<u8>,
+    /// `data:` lines of the event being read
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    /// A decoder at the start of a stream
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the next chunk; returns the data of every event it completed, in order
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            // A whole line, so any multi-byte character in it is whole too
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.is_empty() {
+                events.extend(self.take_event());
+            } else {
+                self.read_line(line);
+            }
+        }
+        events
+    }
+
+    /// The stream ended: the data of a last event that had no blank line after it
+    pub fn finish(&mut self) -> Option<String> {
+        let tail = std::mem::take(&mut self.pending);
+        self.read_line(String::from_utf8_lossy(&tail).trim_end_matches('\r'));
+        self.take_event()
+    }
+
+    // One line of an event
+    fn read_line(&mut self, line: &str) {
+        if let Some(value) = line.strip_prefix("data:") {
+            self.data
+                .push(value.strip_prefix(' ').unwrap_or(value).to_string());
+        }
+        // Comments (`: ...`) and the `event:`, `id:` and `retry:` fields aren't used by Gemini
+    }
+
+    // The event read so far, joining its `data:` lines with line breaks as the SSE spec says
+    fn take_event(&mut self) -> Option<String> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let event = self.data.join("\n");
+        self.data.clear();
+        Some(event)
+    }
+}
@@ -0,0 +1,359 @@
+//! Requests on the shared network runtime against a local stand-in for Gemini (no API
+//! key or internet needed): first driven from a plain thread the way GPUI drives them,
+//! then through a real `GeminiService` in a gpui `TestAppContext`.
+
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use gpui::{AppContext, Entity, TestAppContext};
+use rust_gpui_app::services::background;
+use rust_gpui_app::services::offline_queue::now_ms;
+use rust_gpui_app::services::{GeminiClient, GeminiService, GeminiServiceEvent, OfflineQueue};
+use rust_gpui_app::{ChatMessage, MessageRole};
+
+// Long enough to notice a hang, short enough to fail fast
+const DEADLINE: Duration = Duration::from_secs(10);
+
+/// A one-route Gemini: streams "re: <prompt>" in two SSE events. A prompt containing
+/// "slow" waits `delay` before answering; "stall" sends one event and then hangs.
+fn serve(delay: Duration) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let body = String::from_utf8(body).unwrap();
+                // The prompt is the last message; earlier ones are conversation history
+                let prompt = ["first slow", "second", "stall"]
+                    .into_iter()
+                    .filter_map(|p| body.rfind(p).map(|at| (at, p)))
+                    .max()
+                    .unwrap()
+                    .1;
+
+                if prompt.contains("slow") {
+                    thread::sleep(delay);
+                }
+                let event = |text: &str| {
+                    format!("data: {{\"candidates\":[{{\"content\":{{\"parts\":[{{\"text\":\"{}\"}}]}}}}]}}\r\n\r\n", text)
+                };
+                // Writes may fail once a cancelled client has hung up; that's expected
+                write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n").ok();
+                write!(stream, "{}", event("re: ")).ok();
+                stream.flush().ok();
+                if prompt == "stall" {
+                    thread::sleep(DEADLINE);
+                }
+                write!(stream, "{}", event(prompt)).ok();
+            });
+        }
+    });
+    url
+}
+
+fn prompt(text: &str) -> Vec<ChatMessage> {
+    vec![ChatMessage {
+        id: 0,
+        role: MessageRole::User,
+        content: text.to_string().into(),
+        timestamp: Utc::now(),
+    }]
+}
+
+/// Where a request reports back: its prompt, the deltas seen, and the result
+type Done = mpsc::Sender<(&'static str, Vec<String>, Result<String, String>)>;
+
+/// Start a streamed request on the shared runtime; its deltas and result arrive on `done`
+fn send(url: &str, text: &'static str, done: &Done) -> tokio::task::JoinHandle<()> {
+    let client = GeminiClient::new("test-key".to_string()).with_base_url(url);
+    let done = done.clone();
+    background::handle().spawn(async move {
+        let mut deltas = Vec::new();
+        let result = client
+            .stream_message(&prompt(text), |delta| deltas.push(delta.to_string()))
+            .await;
+        done.send((text, deltas, result.map_err(|e| e.to_string())))
+            .ok();
+    })
+}
+
+#[test]
+fn two_rapid_messages_both_get_their_replies() {
+    let url = serve(Duration::from_millis(300));
+    let (done, results) = mpsc::channel();
+    send(&url, "first slow", &done);
+    send(&url, "second", &done);
+
+    // The slow reply doesn't hold up the quick one, and neither is lost
+    let (text, deltas, result) = results.recv_timeout(DEADLINE).expect("second reply");
+    assert_eq!(text, "second");
+    assert_eq!(deltas, ["re: ", "second"]);
+    assert_eq!(result.as_deref(), Ok("re: second"));
+
+    let (text, _, result) = results.recv_timeout(DEADLINE).expect("first reply");
+    assert_eq!(text, "first slow");
+    assert_eq!(result.as_deref(), Ok("re: first slow"));
+}
+
+#[test]
+fn a_cancelled_request_stops_and_the_next_one_still_works() {
+    let url = serve(Duration::ZERO);
+    let (done, results) = mpsc::channel();
+
+    let stalled = send(&url, "stall", &done);
+    thread::sleep(Duration::from_millis(200));
+    stalled.abort();
+    send(&url, "second", &done);
+
+    // Only the second request reports back: the cancelled one never finishes
+    let (text, _, result) = results.recv_timeout(DEADLINE).expect("second reply");
+    assert_eq!(text, "second");
+    assert_eq!(result.as_deref(), Ok("re: second"));
+    assert!(results.recv_timeout(Duration::from_millis(300)).is_err());
+}
+
+/// Everything a service emitted, in order
+type Events = Rc<RefCell<Vec<GeminiServiceEvent>>>;
+
+/// A queue file of this test's own, starting out empty
+fn queue_file(name: &str) -> PathBuf {
+    let queue_path =
+        std::env::temp_dir().join(format!("gpui-app-{}-{}.json", std::process::id(), name));
+    std::fs::remove_file(&queue_path).ok();
+    queue_path
+}
+
+/// A service talking to `url`, with its own queue file, and a log of its events
+fn service(
+    cx: &mut TestAppContext,
+    url: &str,
+    name: &str,
+) -> (Entity<GeminiService>, Events, PathBuf) {
+    let queue_path = queue_file(name);
+    let (service, events) = service_with_queue(cx, url, &queue_path);
+    (service, events, queue_path)
+}
+
+/// A service that starts with whatever is saved in `queue_path`
+fn service_with_queue(
+    cx: &mut TestAppContext,
+    url: &str,
+    queue_path: &Path,
+) -> (Entity<GeminiService>, Events) {
+    let service =
+        cx.new(|_| GeminiService::with_config(Some("test-key".to_string()), url, queue_path));
+
+    let events = Events::default();
+    let log = events.clone();
+    cx.update(|cx| {
+        cx.subscribe(&service, move |_, event: &GeminiServiceEvent, _| {
+            log.borrow_mut().push(event.clone())
+        })
+        .detach();
+    });
+    (service, events)
+}
+
+/// Run GPUI's tasks until `done` holds. The replies arrive from the network threads, so
+/// between rounds the test waits a little for them instead of parking the executor.
+fn run_until(
+    cx: &mut TestAppContext,
+    events: &Events,
+    done: impl Fn(&[GeminiServiceEvent]) -> bool,
+) {
+    let start = Instant::now();
+    loop {
+        cx.run_until_parked();
+        if done(&events.borrow()) {
+            return;
+        }
+        assert!(
+            start.elapsed() < DEADLINE,
+            "timed out; events so far: {:?}",
+            events.borrow()
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// The reply ids in `StreamStarted` order
+fn started(events: &[GeminiServiceEvent]) -> Vec<String> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            GeminiServiceEvent::StreamStarted { message_id, .. } => Some(message_id.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The user message each reply answers, in `StreamStarted` order
+fn replying_to(events: &[GeminiServiceEvent]) -> Vec<String> {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            GeminiServiceEvent::StreamStarted { reply_to, .. } => Some(reply_to.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The transcript ids of the queued messages, oldest first
+fn queued(service: &Entity<GeminiService>, cx: &mut TestAppContext) -> Vec<String> {
+    service.read_with(cx, |s, _| {
+        s.pending_messages()
+            .into_iter()
+            .map(|m| m.transcript_id)
+            .collect()
+    })
+}
+
+fn completed(events: &[GeminiServiceEvent], id: &str) -> bool {
+    events
+        .iter()
+        .any(|e| matches!(e, GeminiServiceEvent::StreamComplete { message_id } if message_id == id))
+}
+
+fn aborted(events: &[GeminiServiceEvent], id: &str) -> bool {
+    events
+        .iter()
+        .any(|e| matches!(e, GeminiServiceEvent::StreamAborted { message_id } if message_id == id))
+}
+
+/// The text streamed into reply `id`
+fn reply(events: &[GeminiServiceEvent], id: &str) -> String {
+    events
+        .iter()
+        .filter_map(|e| match e {
+            GeminiServiceEvent::StreamDelta { message_id, text } if message_id == id => {
+                Some(text.as_str())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[gpui::test]
+fn a_second_message_supersedes_the_one_in_flight(cx: &mut TestAppContext) {
+    let url = serve(Duration::from_millis(300));
+    let (service, events, queue_path) = service(cx, &url, "supersede");
+
+    service.update(cx, |s, cx| {
+        s.submit("user-1".to_string(), "first slow".to_string(), cx)
+    });
+    service.update(cx, |s, cx| {
+        s.submit("user-2".to_string(), "second".to_string(), cx)
+    });
+
+    // The first reply is dropped at once, but not its message: that is sent again, and
+    // the second isn't lost behind it
+    run_until(cx, &events, |events| {
+        started(events).len() == 3 && completed(events, &started(events)[2])
+    });
+    let events = events.borrow();
+    let ids = started(&events);
+    assert_eq!(replying_to(&events), ["user-1", "user-1", "user-2"]);
+    assert!(aborted(&events, &ids[0]));
+    assert!(!completed(&events, &ids[0]));
+    assert_eq!(reply(&events, &ids[1]), "re: first slow");
+    assert_eq!(reply(&events, &ids[2]), "re: second");
+    assert!(!service.read_with(cx, |s, _| s.is_processing()));
+    assert!(service.read_with(cx, |s, _| s.pending_messages().is_empty()));
+
+    std::fs::remove_file(queue_path).ok();
+}
+
+#[gpui::test]
+fn messages_sent_one_after_another_both_complete(cx: &mut TestAppContext) {
+    let url = serve(Duration::ZERO);
+    let (service, events, queue_path) = service(cx, &url, "in-turn");
+
+    service.update(cx, |s, cx| {
+        s.submit("user-1".to_string(), "first slow".to_string(), cx)
+    });
+    run_until(cx, &events, |events| {
+        started(events)
+            .first()
+            .is_some_and(|id| completed(events, id))
+    });
+    service.update(cx, |s, cx| {
+        s.submit("user-2".to_string(), "second".to_string(), cx)
+    });
+    run_until(cx, &events, |events| {
+        started(events)
+            .get(1)
+            .is_some_and(|id| completed(events, id))
+    });
+
+    // Neither reply was aborted, and each carries its own text
+    let events = events.borrow();
+    let ids = started(&events);
+    assert!(ids.iter().all(|id| !aborted(&events, id)));
+    assert_eq!(reply(&events, &ids[0]), "re: first slow");
+    assert_eq!(reply(&events, &ids[1]), "re: second");
+
+    std::fs::remove_file(queue_path).ok();
+}
+
+#[gpui::test]
+fn a_queued_message_being_resent_stays_queued_when_a_new_one_is_sent(cx: &mut TestAppContext) {
+    let url = serve(Duration::from_millis(300));
+    // A message that failed to send before, as a previous run would have saved it
+    let queue_path = queue_file("resend");
+    let mut queue = OfflineQueue::new();
+    queue.push("user-1", "first slow");
+    queue.mark_failed("user-1", now_ms());
+    queue.save(&queue_path).unwrap();
+    let (service, events) = service_with_queue(cx, &url, &queue_path);
+
+    // "Retry all" starts resending it; the user types another message meanwhile
+    service.update(cx, |s, cx| s.retry_all(cx));
+    run_until(cx, &events, |events| started(events).len() == 1);
+    service.update(cx, |s, cx| {
+        s.submit("user-2".to_string(), "second".to_string(), cx)
+    });
+
+    // Only the stream stopped: the message is still first in line, on disk as well, and
+    // still marked as not sent
+    assert!(aborted(&events.borrow(), &started(&events.borrow())[0]));
+    assert_eq!(queued(&service, cx), ["user-1", "user-2"]);
+    assert_eq!(OfflineQueue::load(&queue_path).unwrap().len(), 2);
+    assert!(service
+        .read_with(cx, |s, _| s.unsent_ids())
+        .contains(&"user-1".to_string()));
+
+    // It is then sent again before the new one, and both get their replies
+    run_until(cx, &events, |events| {
+        started(events).len() == 3 && completed(events, &started(events)[2])
+    });
+    let events = events.borrow();
+    let ids = started(&events);
+    assert_eq!(replying_to(&events), ["user-1", "user-1", "user-2"]);
+    assert_eq!(reply(&events, &ids[1]), "re: first slow");
+    assert_eq!(reply(&events, &ids[2]), "re: second");
+    assert!(queued(&service, cx).is_empty());
+
+    std::fs::remove_file(queue_path).ok();
+}
@@ -0,0 +1,74 @@
+//! How the chat input reads Enter and when it refuses to send (no window needed).
+
+use rust_gpui_app::components::chat_input::{can_send, key_action, KeyAction};
+use rust_gpui_app::components::text_buffer::TextBuffer;
+
+fn buffer(text: &str) -> TextBuffer {
+    let mut b = TextBuffer::new().with_multiline(true);
+    b.set_text(text);
+    b
+}
+
+#[test]
+fn enter_sends() {
+    assert_eq!(key_action("enter", false, &buffer("")), KeyAction::Send);
+}
+
+#[test]
+fn shift_enter_starts_a_new_line() {
+    assert_eq!(key_action("enter", true, &buffer("")), KeyAction::Newline);
+}
+
+#[test]
+fn up_and_down_recall_history_unless_selecting() {
+    let one_line = buffer("hello");
+    assert_eq!(key_action("up", false, &one_line), KeyAction::Older);
+    assert_eq!(key_action("down", false, &one_line), KeyAction::Newer);
+    assert_eq!(key_action("up", true, &one_line), KeyAction::Edit);
+    assert_eq!(key_action("down", true, &one_line), KeyAction::Edit);
+}
+
+#[test]
+fn up_and_down_move_between_lines_before_recalling() {
+    // The cursor is at the end, on the last of three lines
+    let mut message = buffer("fn main() {\n    run();\n}");
+    assert_eq!(key_action("up", false, &message), KeyAction::Edit);
+    assert_eq!(key_action("down", false, &message), KeyAction::Newer);
+
+    message.move_up(false);
+    assert_eq!(key_action("up", false, &message), KeyAction::Edit);
+    assert_eq!(key_action("down", false, &message), KeyAction::Edit);
+
+    message.move_up(false);
+    assert_eq!(key_action("up", false, &message), KeyAction::Older);
+    assert_eq!(key_action("down", false, &message), KeyAction::Edit);
+}
+
+#[test]
+fn other_keys_are_left_to_the_text_input() {
+    for key in ["a", "space", "backspace", "left", "tab"] {
+        assert_eq!(
+            key_action(key, false, &buffer("")),
+            KeyAction::Edit,
+            "{key}"
+        );
+        assert_eq!(
+            key_action(key, true, &buffer("")),
+            KeyAction::Edit,
+            "shift-{key}"
+        );
+    }
+}
+
+#[test]
+fn blank_messages_are_not_sent() {
+    assert!(!can_send(""));
+    assert!(!can_send("   "));
+    assert!(!can_send("\n\n\t \n"));
+}
+
+#[test]
+fn messages_with_line_breaks_are_sent() {
+    assert!(can_send("x"));
+    assert!(can_send("fn main() {\n    println!(\"hi\");\n}\n"));
+}
@@ -0,0 +1,92 @@
+//! Up/Down recall of sent messages in the chat input (no window needed).
+
+use rust_gpui_app::components::input_history::{InputHistory, HISTORY_CAPACITY};
+
+fn history(messages: &[&str]) -> InputHistory {
+    let mut h = InputHistory::new();
+    for message in messages {
+        h.push(*message);
+    }
+    h
+}
+
+#[test]
+fn up_walks_back_and_down_walks_forward() {
+    let mut h = history(&["one", "two", "three"]);
+
+    assert_eq!(h.up("").as_deref(), Some("three"));
+    assert_eq!(h.up("three").as_deref(), Some("two"));
+    assert_eq!(h.up("two").as_deref(), Some("one"));
+    // Nothing older; the input stays as it is
+    assert_eq!(h.up("one"), None);
+    assert!(h.is_browsing());
+
+    assert_eq!(h.down("one").as_deref(), Some("two"));
+    assert_eq!(h.down("two").as_deref(), Some("three"));
+}
+
+#[test]
+fn down_past_the_newest_restores_the_draft_once() {
+    let mut h = history(&["one", "two"]);
+    assert_eq!(h.up("").as_deref(), Some("two"));
+
+    assert_eq!(h.down("two").as_deref(), Some(""));
+    assert!(!h.is_browsing());
+    // Back at the draft, Down has nowhere to go
+    assert_eq!(h.down(""), None);
+    // And Up starts over from the newest
+    assert_eq!(h.up("").as_deref(), Some("two"));
+}
+
+#[test]
+fn up_leaves_a_draft_alone() {
+    let mut h = history(&["one"]);
+    assert_eq!(h.up("half-typed"), None);
+    assert_eq!(h.down("half-typed"), None);
+    assert!(!h.is_browsing());
+
+    assert_eq!(InputHistory::new().up(""), None);
+}
+
+#[test]
+fn editing_a_recalled_message_detaches_it() {
+    let mut h = history(&["one", "two"]);
+    h.up("");
+
+    // The user changed "two" to "two!": it is a draft now, so arrows don't replace it
+    assert_eq!(h.up("two!"), None);
+    assert!(!h.is_browsing());
+    assert_eq!(h.down("two!"), None);
+    // The history still holds what was sent
+    assert_eq!(h.entries().collect::<Vec<_>>(), ["one", "two"]);
+}
+
+#[test]
+fn sending_ends_browsing_and_skips_repeats() {
+    let mut h = history(&["one", "two"]);
+    h.up("");
+    h.push("two");
+    assert!(!h.is_browsing());
+    assert_eq!(h.entries().collect::<Vec<_>>(), ["one", "two"]);
+
+    h.push("one");
+    assert_eq!(h.entries().collect::<Vec<_>>(), ["one", "two", "one"]);
+}
+
+#[test]
+fn only_the_last_hundred_messages_are_kept() {
+    let mut h = InputHistory::new();
+    for i in 0..HISTORY_CAPACITY + 5 {
+        h.push(format!("message {i}"));
+    }
+
+    assert_eq!(h.entries().count(), HISTORY_CAPACITY);
+    assert_eq!(h.entries().next(), Some("message 5"));
+    assert_eq!(h.up("").as_deref(), Some("message 104"));
+}
+
+#[test]
+fn multiline_messages_come_back_whole() {
+    let mut h = history(&["fn main() {\n}\n"]);
+    assert_eq!(h.up("").as_deref(), Some("fn main() {\n}\n"));
+}
@@ -0,0 +1,96 @@
+//! Ordering and backoff rules of the offline message queue (no network needed).
+
+use rust_gpui_app::services::offline_queue::{Backoff, OfflineQueue};
+
+fn queue() -> OfflineQueue {
+    OfflineQueue::new().with_backoff(Backoff {
+        base_ms: 1_000,
+        max_ms: 8_000,
+    })
+}
+
+#[test]
+fn only_the_oldest_message_is_ready() {
+    let mut q = queue();
+    q.push("a", "first");
+    q.push("b", "second");
+
+    assert_eq!(q.ready(0).map(|m| m.transcript_id.as_str()), Some("a"));
+    // Sending "b" before "a" would reorder the conversation
+    assert!(q.mark_sent("b").is_none());
+    assert_eq!(q.len(), 2);
+
+    assert_eq!(q.mark_sent("a").map(|m| m.text), Some("first".to_string()));
+    assert_eq!(q.ready(0).map(|m| m.transcript_id.as_str()), Some("b"));
+}
+
+#[test]
+fn failures_back_off_exponentially_and_cap() {
+    let mut q = queue();
+    q.push("a", "hello");
+
+    assert_eq!(q.mark_failed("a", 0), Some(1_000));
+    assert!(q.ready(999).is_none());
+    assert!(q.ready(1_000).is_some());
+
+    assert_eq!(q.mark_failed("a", 1_000), Some(2_000));
+    assert_eq!(q.mark_failed("a", 3_000), Some(4_000));
+    assert_eq!(q.mark_failed("a", 7_000), Some(8_000));
+    assert_eq!(q.mark_failed("a", 15_000), Some(8_000));
+    assert_eq!(q.ms_until_ready(15_000), Some(8_000));
+}
+
+#[test]
+fn messages_behind_a_failed_one_wait_too() {
+    let mut q = queue();
+    q.push("a", "first");
+    q.push("b", "second");
+    assert!(q.unsent_ids().is_empty(), "nothing has failed yet");
+
+    q.mark_failed("a", 0);
+    assert_eq!(q.unsent_ids(), vec!["a".to_string(), "b".to_string()]);
+    // "b" is not handed out while "a" is backing off
+    assert!(q.ready(10).is_none());
+}
+
+#[test]
+fn retry_all_makes_the_front_ready_immediately() {
+    let mut q = queue();
+    q.push("a", "first");
+    q.mark_failed("a", 0);
+    q.mark_failed("a", 1_000);
+
+    q.retry_all(1_500);
+    assert_eq!(q.ready(1_500).map(|m| m.attempts), Some(2));
+}
+
+#[test]
+fn removing_a_rejected_message_unblocks_the_rest() {
+    let mut q = queue();
+    q.push("a", "first");
+    q.push("b", "second");
+
+    assert!(q.remove("a").is_some());
+    assert_eq!(q.ready(0).map(|m| m.transcript_id.as_str()), Some("b"));
+}
+
+#[test]
+fn queue_survives_a_save_and_load() {
+    let path = std::env::temp_dir().join(format!("offline_queue_test_{}.json", std::process::id()));
+
+    let mut q = queue();
+    q.push("a", "first");
+    q.push("b", "second");
+    q.mark_failed("a", 0);
+    q.save(&path).expect("save");
+
+    let loaded = OfflineQueue::load(&path).expect("load");
+    let original: Vec<_> = q.iter().cloned().collect();
+    let restored: Vec<_> = loaded.iter().cloned().collect();
+    assert_eq!(original, restored);
+
+    // Saving an empty queue deletes the file; loading a missing file is an empty queue
+    OfflineQueue::new().save(&path).expect("save empty");
+    assert!(!path.exists());
+    assert!(OfflineQueue::load(&path).expect("load missing").is_empty());
+}
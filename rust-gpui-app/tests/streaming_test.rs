@@ -0,0 +1,159 @@
+//! How streamed replies grow in the messages area, and how the SSE stream is cut into
+//! chunks (no window or network needed).
+
+use rust_gpui_app::components::message_item::{ChatMessage, ContentBlock};
+use rust_gpui_app::components::messages_area::MessagesArea;
+use rust_gpui_app::services::gemini::chunk_text;
+use rust_gpui_app::services::sse::SseDecoder;
+
+/// A messages area holding one prompt (id "prompt"), with reply "reply" streaming under it
+fn streaming() -> MessagesArea {
+    let mut area = MessagesArea::default();
+    let mut prompt = ChatMessage::user("Say hello");
+    prompt.id = "prompt".to_string();
+    area.add_message(prompt);
+    area.start_stream("prompt", "reply".to_string());
+    area
+}
+
+fn reply(area: &MessagesArea) -> &ChatMessage {
+    area.messages()
+        .iter()
+        .find(|m| m.id == "reply")
+        .expect("the reply is shown")
+}
+
+#[test]
+fn a_started_stream_shows_an_empty_reply_with_the_cursor() {
+    let area = streaming();
+
+    assert_eq!(area.messages()[1].id, "reply");
+    assert!(reply(&area).is_streaming);
+    assert!(!reply(&area).is_user);
+    assert_eq!(reply(&area).get_full_text(), "");
+}
+
+#[test]
+fn deltas_are_appended_in_order() {
+    let mut area = streaming();
+    for piece in ["Hel", "lo, ", "wor", "ld!"] {
+        assert!(area.apply_delta("reply", piece));
+    }
+
+    assert_eq!(reply(&area).get_full_text(), "Hello, world!");
+    assert_eq!(reply(&area).blocks.len(), 1);
+    assert!(reply(&area).is_streaming);
+}
+
+#[test]
+fn completion_parses_the_text_and_hides_the_cursor() {
+    let mut area = streaming();
+    area.apply_delta("reply", "Here:\n\n```rust\nfn ");
+    // Mid-stream the half-open code fence is still plain text
+    assert!(matches!(reply(&area).blocks[..], [ContentBlock::Text(_)]));
+    area.apply_delta("reply", "main() {}\n```\n");
+
+    assert!(area.complete_stream("reply"));
+    let reply = reply(&area);
+    assert!(!reply.is_streaming);
+    assert!(matches!(
+        &reply.blocks[..],
+        [ContentBlock::Text(_), ContentBlock::Code { .. }]
+    ));
+}
+
+#[test]
+fn deltas_after_completion_are_ignored() {
+    let mut area = streaming();
+    area.apply_delta("reply", "Done.");
+    area.complete_stream("reply");
+
+    assert!(!area.apply_delta("reply", " Or not?"));
+    assert!(!area.complete_stream("reply"));
+    assert_eq!(reply(&area).get_full_text(), "Done.");
+    assert!(!reply(&area).is_streaming);
+}
+
+#[test]
+fn events_for_unknown_replies_change_nothing() {
+    let mut area = streaming();
+
+    assert!(!area.apply_delta("other", "stray"));
+    assert!(!area.complete_stream("other"));
+    assert!(!area.abort_stream("other"));
+    // Nor can a delta address the user's own message
+    assert!(!area.apply_delta("prompt", "stray"));
+    assert_eq!(area.messages().len(), 2);
+    assert_eq!(area.messages()[0].get_full_text(), "Say hello");
+}
+
+#[test]
+fn completing_one_reply_leaves_another_streaming() {
+    let mut area = streaming();
+    area.start_stream("prompt", "second".to_string());
+    area.apply_delta("reply", "first");
+    area.apply_delta("second", "second");
+
+    area.complete_stream("second");
+    assert!(reply(&area).is_streaming);
+    assert!(area.apply_delta("reply", " reply"));
+    assert_eq!(reply(&area).get_full_text(), "first reply");
+}
+
+#[test]
+fn an_aborted_reply_is_removed_but_a_finished_one_is_kept() {
+    let mut area = streaming();
+    area.apply_delta("reply", "half a rep");
+    assert!(area.abort_stream("reply"));
+    assert_eq!(area.messages().len(), 1);
+
+    area.start_stream("prompt", "reply".to_string());
+    area.apply_delta("reply", "whole");
+    area.complete_stream("reply");
+    assert!(!area.abort_stream("reply"));
+    assert_eq!(area.messages().len(), 2);
+}
+
+#[test]
+fn sse_events_can_be_split_anywhere() {
+    let stream = "data: {\"a\":1}\r\n\r\n: keep-alive\n\ndata: é\n\n";
+    let mut decoder = SseDecoder::new();
+    let mut events = Vec::new();
+    // One byte at a time, splitting "é" (two bytes) down the middle
+    for byte in stream.as_bytes() {
+        events.extend(decoder.push(&[*byte]));
+    }
+
+    assert_eq!(events, ["{\"a\":1}", "é"]);
+    assert_eq!(decoder.finish(), None);
+}
+
+#[test]
+fn sse_data_lines_join_and_a_last_unterminated_event_is_kept() {
+    let mut decoder = SseDecoder::new();
+    assert_eq!(
+        decoder.push(b"data: one\ndata:two\n\ndata: last"),
+        ["one\ntwo"]
+    );
+    assert_eq!(decoder.finish().as_deref(), Some("last"));
+}
+
+#[test]
+fn chunk_text_joins_the_parts_of_the_first_candidate() {
+    let data =
+        r#"{"candidates":[{"content":{"parts":[{"text":"Hel"},{"text":"lo"}],"role":"model"}}]}"#;
+    assert_eq!(chunk_text(data).unwrap(), "Hello");
+
+    // The last chunk may hold only the finish reason and token counts
+    let last = r#"{"candidates":[{"finishReason":"STOP"}],"usageMetadata":{"totalTokenCount":9}}"#;
+    assert_eq!(chunk_text(last).unwrap(), "");
+}
+
+#[test]
+fn chunk_text_reports_api_errors() {
+    let data = r#"{"error":{"code":429,"message":"Quota exceeded","status":"RESOURCE_EXHAUSTED"}}"#;
+    let error = chunk_text(data).unwrap_err();
+    assert!(error.to_string().contains("Quota exceeded"), "{error}");
+
+    assert!(chunk_text("not json").is_err());
+}
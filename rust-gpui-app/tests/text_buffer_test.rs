@@ -0,0 +1,326 @@
+//! Cursor, selection and editing rules of the text input (no window needed).
+
+use rust_gpui_app::components::text_buffer::TextBuffer;
+
+fn buffer(text: &str) -> TextBuffer {
+    let mut b = TextBuffer::new();
+    b.set_text(text);
+    b
+}
+
+#[test]
+fn typing_goes_in_at_the_cursor() {
+    let mut b = buffer("helo");
+    b.move_left(false);
+    b.insert("l");
+
+    assert_eq!(b.text(), "hello");
+    // The cursor follows what was typed, so typing on continues in order
+    assert_eq!(b.cursor(), 4);
+    b.insert("!");
+    assert_eq!(b.text(), "hell!o");
+}
+
+#[test]
+fn backspace_and_delete_take_the_character_either_side() {
+    let mut b = buffer("abcd");
+    b.move_left(false);
+    b.move_left(false);
+    b.backspace();
+    assert_eq!((b.text(), b.cursor()), ("acd", 1));
+
+    b.delete();
+    assert_eq!((b.text(), b.cursor()), ("ad", 1));
+
+    b.move_home(false);
+    b.backspace();
+    b.move_end(false);
+    b.delete();
+    assert_eq!(b.text(), "ad");
+}
+
+#[test]
+fn shift_selects_and_typing_replaces_the_selection() {
+    let mut b = buffer("hello world");
+    b.move_word_left(true);
+    assert_eq!(b.selected_text(), Some("world"));
+
+    b.insert("there");
+    assert_eq!(b.text(), "hello there");
+    assert_eq!(b.selection(), None);
+    assert_eq!(b.cursor(), b.text().len());
+}
+
+#[test]
+fn backspace_and_delete_remove_the_selection() {
+    let mut b = buffer("abcdef");
+    b.move_home(false);
+    b.move_right(false);
+    b.move_right(true);
+    b.move_right(true);
+    assert_eq!(b.selection(), Some(1..3));
+
+    b.delete();
+    assert_eq!((b.text(), b.cursor()), ("adef", 1));
+
+    b.move_end(true);
+    b.backspace();
+    assert_eq!(b.text(), "a");
+}
+
+#[test]
+fn arrows_without_shift_collapse_the_selection() {
+    let mut b = buffer("abcdef");
+    b.move_left(true);
+    b.move_left(true);
+    b.move_left(false);
+    // To the start of the selection, not one further
+    assert_eq!((b.cursor(), b.selection()), (4, None));
+
+    b.move_right(true);
+    b.move_right(true);
+    b.move_right(false);
+    assert_eq!((b.cursor(), b.selection()), (6, None));
+}
+
+#[test]
+fn selecting_back_past_the_anchor_flips_the_selection() {
+    let mut b = buffer("abcd");
+    b.move_left(false);
+    b.move_left(false);
+    b.move_right(true);
+    assert_eq!(b.selection(), Some(2..3));
+    assert!(b.cursor_at_selection_end());
+
+    b.move_left(true);
+    b.move_left(true);
+    assert_eq!(b.selection(), Some(1..2));
+    assert!(!b.cursor_at_selection_end());
+}
+
+#[test]
+fn word_jumps_skip_spaces_and_punctuation() {
+    let mut b = buffer("let x = foo_bar(1);");
+    b.move_word_left(false);
+    assert_eq!(b.cursor(), "let x = foo_bar(".len());
+    b.move_word_left(false);
+    assert_eq!(b.cursor(), "let x = ".len());
+    b.move_word_left(false);
+    b.move_word_left(false);
+    assert_eq!(b.cursor(), 0);
+    b.move_word_left(false);
+    assert_eq!(b.cursor(), 0);
+
+    b.move_word_right(false);
+    assert_eq!(b.cursor(), "let".len());
+    b.move_word_right(false);
+    assert_eq!(b.cursor(), "let x".len());
+    b.move_word_right(false);
+    assert_eq!(b.cursor(), "let x = foo_bar".len());
+}
+
+#[test]
+fn the_cursor_never_splits_a_multi_byte_character() {
+    // "é" is 2 bytes, "日" 3 and "🦀" 4
+    let mut b = buffer("é日🦀");
+    b.move_left(false);
+    assert_eq!(b.cursor(), "é日".len());
+    b.move_left(false);
+    assert_eq!(b.cursor(), "é".len());
+
+    b.insert("x");
+    assert_eq!(b.text(), "éx日🦀");
+    b.move_left(false);
+    b.backspace();
+    assert_eq!((b.text(), b.cursor()), ("x日🦀", 0));
+
+    b.move_right(false);
+    b.delete();
+    assert_eq!(b.text(), "x🦀");
+
+    b.move_right(true);
+    assert_eq!(b.selected_text(), Some("🦀"));
+}
+
+#[test]
+fn words_include_non_ascii_letters() {
+    let mut b = buffer("café crème");
+    b.move_word_left(true);
+    assert_eq!(b.selected_text(), Some("crème"));
+    b.move_word_left(true);
+    assert_eq!(b.selected_text(), Some("café crème"));
+}
+
+#[test]
+fn setting_or_clearing_the_text_keeps_the_cursor_in_bounds() {
+    let mut b = buffer("a long line of text");
+    b.move_word_left(true);
+    b.set_text("short");
+    assert_eq!((b.cursor(), b.selection()), (5, None));
+
+    b.select_all();
+    b.clear();
+    assert_eq!((b.text(), b.cursor(), b.selection()), ("", 0, None));
+
+    // Nothing to move over or delete; nothing panics
+    b.move_left(true);
+    b.move_word_right(true);
+    b.backspace();
+    b.delete();
+    assert_eq!(b.cursor(), 0);
+}
+
+#[test]
+fn segments_split_the_text_around_the_selection() {
+    let mut b = buffer("one two three");
+    assert_eq!(b.segments(), ("one two three", "", ""));
+
+    b.move_word_left(false);
+    b.move_word_left(true);
+    assert_eq!(b.segments(), ("one ", "two ", "three"));
+
+    b.select_all();
+    assert_eq!(b.segments(), ("", "one two three", ""));
+}
+
+#[test]
+fn paste_replaces_the_selection() {
+    let mut b = buffer("key: PLACEHOLDER");
+    b.move_word_left(true);
+    assert_eq!(b.paste("AIzaSy-123"), 0);
+
+    assert_eq!(b.text(), "key: AIzaSy-123");
+    assert_eq!((b.cursor(), b.selection()), (b.text().len(), None));
+}
+
+#[test]
+fn paste_flattens_line_breaks_in_a_single_line_field() {
+    let mut b = buffer("");
+    b.paste("fn main() {\r\n    run();\r}\n");
+    assert_eq!(b.text(), "fn main() {     run(); } ");
+
+    let mut b = TextBuffer::new().with_multiline(true);
+    b.paste("a\r\nb\rc\n");
+    assert_eq!(b.text(), "a\nb\nc\n");
+}
+
+#[test]
+fn long_pastes_are_truncated_on_a_character_boundary() {
+    let mut b = TextBuffer::new().with_paste_limit(3);
+    b.set_text("[]");
+    b.move_left(false);
+
+    // Three characters, nine bytes, go in; the other two are reported
+    assert_eq!(b.paste("é日🦀xy"), 2);
+    assert_eq!(b.text(), "[é日🦀]");
+    assert_eq!(b.cursor(), "[é日🦀".len());
+
+    assert_eq!(b.paste("abc"), 0);
+}
+
+#[test]
+fn cut_removes_and_returns_the_selection() {
+    let mut b = buffer("copy this");
+    assert_eq!(b.cut(), None);
+    assert_eq!(b.text(), "copy this");
+
+    b.move_word_left(true);
+    assert_eq!(b.cut().as_deref(), Some("this"));
+    assert_eq!((b.text(), b.cursor()), ("copy ", 5));
+}
+
+#[test]
+fn lines_split_the_selection_and_place_the_cursor() {
+    let mut b = TextBuffer::new().with_multiline(true);
+    b.paste("ab\ncd\n");
+    b.move_left(false);
+    for _ in 0..4 {
+        b.move_left(true);
+    }
+    // "b" to "d" is selected, with the cursor before "b"
+    let lines = b.lines();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(
+        (lines[0].before, lines[0].selected, lines[0].after),
+        ("a", "b", "")
+    );
+    assert_eq!(lines[0].cursor, Some(1));
+    assert_eq!(
+        (lines[1].before, lines[1].selected, lines[1].after),
+        ("", "cd", "")
+    );
+    assert_eq!(lines[1].cursor, None);
+    // The trailing newline leaves an empty last line to type on
+    assert_eq!(
+        (
+            lines[2].before,
+            lines[2].selected,
+            lines[2].after,
+            lines[2].cursor
+        ),
+        ("", "", "", None)
+    );
+
+    b.move_end(false);
+    assert_eq!(b.lines()[2].cursor, Some(0));
+}
+
+#[test]
+fn up_and_down_move_between_lines_keeping_the_column() {
+    let mut b = buffer("hello\nhi\nworld");
+    // After the "r" of "world"
+    b.move_left(false);
+    b.move_left(false);
+    assert_eq!(b.cursor(), 12);
+
+    // "hi" is too short, so the cursor stops at its end
+    b.move_up(false);
+    assert_eq!(b.cursor(), 8);
+    b.move_up(false);
+    assert_eq!(b.cursor(), 2);
+    assert!(b.cursor_on_first_line());
+
+    // No line above the first: to the start of the text
+    b.move_up(false);
+    assert_eq!(b.cursor(), 0);
+
+    b.move_down(false);
+    assert_eq!(b.cursor(), 6);
+    b.move_down(false);
+    assert_eq!(b.cursor(), 9);
+    assert!(b.cursor_on_last_line());
+    // No line below the last: to the end of the text
+    b.move_down(false);
+    assert_eq!(b.cursor(), b.text().len());
+}
+
+#[test]
+fn up_and_down_count_columns_in_characters() {
+    let mut b = buffer("héllo\nabcdef");
+    b.move_up(false);
+    // Column 6 of "abcdef" is past the end of "héllo", whose 5 characters are 6 bytes
+    assert_eq!(b.cursor(), 6);
+    b.move_left(false);
+    b.move_left(false);
+    b.move_left(false);
+    b.move_down(false);
+    assert_eq!(b.cursor(), 7 + 2);
+}
+
+#[test]
+fn shift_up_selects_up_to_the_line_above() {
+    let mut b = buffer("one\ntwo");
+    b.move_up(true);
+    assert_eq!(b.selected_text(), Some("\ntwo"));
+    b.move_down(false);
+    assert_eq!(b.selection(), None);
+}
+
+#[test]
+fn single_line_text_is_both_first_and_last_line() {
+    let mut b = buffer("one line");
+    assert!(b.cursor_on_first_line() && b.cursor_on_last_line());
+    b.set_text("one\n");
+    assert!(!b.cursor_on_first_line());
+    assert!(b.cursor_on_last_line());
+}
@@ -0,0 +1,143 @@
+// API token authentication. Routes that cost money (`/prompt`) or change data (POST/PUT/PATCH/DELETE
+// on `/items`) require an `Authorization: Bearer <token>` header; reads stay open.
+//
+// The check is an axum middleware (like a servlet Filter in Java or Express middleware):
+// it runs before the handler and can answer with a 401 instead of calling it.
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::error::ApiError;
+use crate::Db;
+
+/// Which bearer tokens are accepted.
+///
+/// `AuthConfig::default()` is *disabled* (every request passes), which is what
+/// `AppState::default()` and the tests use; the server builds it with `from_env`.
+#[derive(Debug, Clone, Default)]
+pub struct AuthConfig {
+    enabled: bool,
+    tokens: Vec<String>,
+}
+
+impl AuthConfig {
+    /// Accept every request (no header needed)
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Require one of `tokens`. Empty tokens are ignored; with none left, every
+    /// protected request is rejected.
+    pub fn with_tokens<I, S>(tokens: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            enabled: true,
+            tokens: tokens
+                .into_iter()
+                .map(Into::into)
+                .filter(|token: &String| !token.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Parse a comma-separated list such as `"token-a, token-b"`
+    pub fn parse(list: &str) -> Self {
+        Self::with_tokens(list.split(',').map(str::trim))
+    }
+
+    /// Tokens from `API_TOKENS` (comma-separated). Setting `AUTH_DISABLED=true` turns
+    /// authentication off, for local development only.
+    pub fn from_env() -> Self {
+        let disabled = std::env::var("AUTH_DISABLED")
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        if disabled {
+            return Self::disabled();
+        }
+        Self::parse(&std::env::var("API_TOKENS").unwrap_or_default())
+    }
+
+    /// Whether protected routes check tokens at all
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Number of accepted tokens
+    pub fn token_count(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Whether `token` is one of the accepted tokens
+    pub fn allows(&self, token: &str) -> bool {
+        // Compare against every token, so the response time doesn't reveal which one was close
+        self.tokens.iter().fold(false, |found, known| {
+            constant_time_eq(known.as_bytes(), token.as_bytes()) | found
+        })
+    }
+}
+
+/// Byte comparison whose running time doesn't depend on where the inputs differ.
+// A plain `==` returns at the first different byte, so an attacker timing many requests
+// could guess a token one character at a time.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    // XOR is 0 only for equal bytes; OR-ing every XOR together is 0 only if all bytes match
+    a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// The token from an `Authorization: Bearer <token>` header
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    // The scheme name is case-insensitive ("bearer" works too)
+    let (scheme, token) = value.split_once(' ')?;
+    if scheme.eq_ignore_ascii_case("bearer") && !token.trim().is_empty() {
+        Some(token.trim())
+    } else {
+        None
+    }
+}
+
+/// The `access_token` query parameter of a WebSocket handshake.
+// Browsers can't set headers on `new WebSocket(url)`, so `/ws` takes the token in the URL
+// instead. Only upgrade requests may do this: URLs end up in browser history and proxy logs,
+// and everything else can use the header. (Our own request log records the path only.)
+pub fn websocket_token(request: &Request) -> Option<&str> {
+    let upgrade = request.headers().get(header::UPGRADE)?.to_str().ok()?;
+    if !upgrade.eq_ignore_ascii_case("websocket") {
+        return None;
+    }
+    request
+        .uri()
+        .query()?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("access_token="))
+        .filter(|token| !token.is_empty())
+}
+
+/// Middleware: reject the request with a 401 unless it carries an accepted bearer token.
+pub async fn require_token(
+    State(db): State<Db>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let auth = db.auth();
+    if auth.is_enabled() {
+        let token = bearer_token(request.headers())
+            .or_else(|| websocket_token(&request))
+            .ok_or_else(|| {
+                ApiError::Unauthorized("Missing `Authorization: Bearer <token>` header".to_string())
+            })?;
+        if !auth.allows(token) {
+            return Err(ApiError::Unauthorized("Invalid API token".to_string()));
+        }
+    }
+    Ok(next.run(request).await)
+}
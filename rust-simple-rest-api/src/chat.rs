@@ -0,0 +1,180 @@
+// `GET /ws`: a chat over a WebSocket, for clients (like the web UI) that want a persistent
+// connection instead of one `/prompt` request per message.
+//
+// Protocol: every text frame the client sends is one user message. The server answers with
+// JSON text frames:
+//   {"type": "delta", "text": "..."}              one per piece of the answer, in order
+//   {"type": "done", "finish_reason": "STOP"}     after the last delta
+//   {"type": "error", "error": {"code", "message", "request_id"}}   instead of `done` on failure
+// The conversation so far is kept per connection and sent to Gemini with each new message,
+// so follow-up questions work. It is forgotten when the socket closes.
+use axum::{
+    extract::{
+        ws::{rejection::WebSocketUpgradeRejection, Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+};
+use futures_util::StreamExt;
+use rust_gemini_llm_client::{ChatMessage, GeminiClient, StreamEvent};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::{ApiError, ApiQuery, RequestContext};
+use crate::prompt::gemini_error_to_api_error;
+use crate::Db;
+
+/// Most messages (user and model turns together) kept per connection; older turns are
+/// dropped first so a long chat doesn't grow the Gemini request without bound.
+pub const MAX_CHAT_MESSAGES: usize = 40;
+
+// Query string of the upgrade request: `/ws?api_key=...` plays the role of the `api_key`
+// field in a `/prompt` body (browsers can't send a body or headers with the handshake).
+#[derive(Debug, Default, Deserialize)]
+pub struct ChatQuery {
+    pub api_key: Option<String>,
+}
+
+/// `GET /ws`: upgrade the connection and run a chat session on it.
+// Taking `Result<WebSocketUpgrade, _>` instead of `WebSocketUpgrade` lets us turn a plain
+// (non-WebSocket) GET into our usual error body instead of axum's plain-text rejection.
+pub async fn handle_ws(
+    State(db): State<Db>,
+    ApiQuery(query): ApiQuery<ChatQuery>,
+    upgrade: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Result<Response, ApiError> {
+    let upgrade = upgrade.map_err(|rejection| ApiError::BadRequest(rejection.body_text()))?;
+    // The session runs after this handler returns (outside the request-context task local),
+    // so capture the id for error frames now.
+    let request_id = RequestContext::current().request_id;
+    // `on_upgrade` answers 101 Switching Protocols; the closure runs once the handshake is done
+    Ok(upgrade.on_upgrade(move |socket| chat_session(socket, db, query.api_key, request_id)))
+}
+
+/// One connection: read user messages until the client goes away, answering each in turn.
+async fn chat_session(mut socket: WebSocket, db: Db, api_key: Option<String>, request_id: String) {
+    let client = match db.gemini_client(api_key) {
+        Ok(client) => client,
+        Err(e) => {
+            // e.g. no API key anywhere: say so, then hang up (every message would fail the same way)
+            let _ = send(
+                &mut socket,
+                error_frame(gemini_error_to_api_error(e), &request_id),
+            )
+            .await;
+            let _ = socket.close().await;
+            return;
+        }
+    };
+    let mut history: Vec<ChatMessage> = Vec::new();
+
+    // `recv` yields None once the connection is closed. Ping/pong is answered by axum itself.
+    while let Some(incoming) = socket.recv().await {
+        let text = match incoming {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) => break,
+            Ok(Message::Binary(_)) => {
+                let error = ApiError::BadRequest("Only text frames are supported".to_string());
+                if send(&mut socket, error_frame(error, &request_id))
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+                continue;
+            }
+            Ok(_) => continue,
+            Err(e) => {
+                // The connection is probably gone, but try to tell the client why we stop
+                tracing::debug!("websocket receive failed: {}", e);
+                let error = ApiError::BadRequest(format!("WebSocket error: {}", e));
+                let _ = send(&mut socket, error_frame(error, &request_id)).await;
+                break;
+            }
+        };
+
+        if text.trim().is_empty() {
+            let error = ApiError::validation("Message must not be blank");
+            if send(&mut socket, error_frame(error, &request_id))
+                .await
+                .is_err()
+            {
+                break;
+            }
+            continue;
+        }
+
+        history.push(ChatMessage::user(text));
+        match answer(&mut socket, &client, &history, &request_id).await {
+            Ok(Some(reply)) => history.push(ChatMessage::model(reply)),
+            // Gemini failed: forget the question too, so turns keep alternating user/model
+            Ok(None) => {
+                history.pop();
+            }
+            // The client disconnected mid-answer
+            Err(_) => break,
+        }
+        trim_history(&mut history);
+    }
+}
+
+/// Stream Gemini's answer to `history` onto the socket. Returns the full reply, `None` if
+/// Gemini failed (an error frame was sent), or `Err` if the socket is closed.
+// While this runs, new messages from the client wait in the socket; they are answered in order.
+// Returning early on a send error drops the Gemini stream, which cancels the upstream request.
+async fn answer(
+    socket: &mut WebSocket,
+    client: &GeminiClient,
+    history: &[ChatMessage],
+    request_id: &str,
+) -> Result<Option<String>, axum::Error> {
+    let mut events = client.generate_content_stream(history);
+    let mut reply = String::new();
+    while let Some(event) = events.next().await {
+        match event {
+            Ok(StreamEvent::Text(text)) => {
+                send(socket, json!({ "type": "delta", "text": text })).await?;
+                reply.push_str(&text);
+            }
+            Ok(StreamEvent::Done(result)) => {
+                send(
+                    socket,
+                    json!({ "type": "done", "finish_reason": result.finish_reason }),
+                )
+                .await?;
+                return Ok(Some(reply));
+            }
+            Err(e) => {
+                send(
+                    socket,
+                    error_frame(gemini_error_to_api_error(e), request_id),
+                )
+                .await?;
+                return Ok(None);
+            }
+        }
+    }
+    // The event stream always ends with Done or an error; treat anything else as a failure
+    let error = ApiError::Upstream("Gemini stream ended without an answer".to_string());
+    send(socket, error_frame(error, request_id)).await?;
+    Ok(None)
+}
+
+/// `{"type": "error", "error": {...}}`: the HTTP error envelope plus the frame type
+fn error_frame(error: ApiError, request_id: &str) -> Value {
+    let mut frame = error.to_json(request_id);
+    frame["type"] = json!("error");
+    frame
+}
+
+async fn send(socket: &mut WebSocket, frame: Value) -> Result<(), axum::Error> {
+    socket.send(Message::Text(frame.to_string())).await
+}
+
+/// Drop the oldest turns beyond `MAX_CHAT_MESSAGES`, two at a time so the history still
+/// starts with a user message.
+fn trim_history(history: &mut Vec<ChatMessage>) {
+    while history.len() > MAX_CHAT_MESSAGES {
+        history.drain(..2);
+    }
+}
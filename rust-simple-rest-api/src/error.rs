@@ -0,0 +1,325 @@
+// A single error type for every handler. Instead of each handler picking its own shape
+// (bare status codes, ad-hoc `json!({"error": ...})`), handlers return `Result<T, ApiError>`
+// and this module decides how the error looks on the wire:
+//
+//   {"error": {"code": "not_found", "message": "...", "details": {...}, "request_id": "req-..."}}
+//
+// Clients that don't accept JSON (e.g. `Accept: text/plain`) get a one-line text body instead.
+// This is the Rust equivalent of a Spring `@ControllerAdvice` or an Express error middleware.
+use axum::{
+    extract::{
+        rejection::JsonRejection, rejection::PathRejection, rejection::QueryRejection, FromRequest,
+        FromRequestParts, Request,
+    },
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use utoipa::ToSchema;
+
+/// Header used to read an incoming request id and echo it back on every response.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Every error the API can return. Each variant maps to one HTTP status code.
+// Enums in Rust can carry data per variant (like sealed classes in Kotlin or tagged unions).
+#[derive(Debug, Clone)]
+pub enum ApiError {
+    /// 404: the resource (or route) does not exist
+    NotFound(String),
+    /// 400: the request is malformed (bad query string, bad path segment, bad JSON syntax)
+    BadRequest(String),
+    /// 422: the request is well-formed but its contents are invalid
+    Validation {
+        message: String,
+        details: Option<Value>,
+    },
+    /// 401: missing or wrong credentials
+    Unauthorized(String),
+    /// 409: the request conflicts with the current state (e.g. a stale version)
+    Conflict(String),
+    /// 429: the client is sending too many requests
+    RateLimited {
+        message: String,
+        retry_after_secs: Option<u64>,
+    },
+    /// 502: a service we depend on (Gemini) failed
+    Upstream(String),
+    /// 500: something went wrong on our side
+    Internal(String),
+}
+
+impl ApiError {
+    /// Shorthand for a validation error without details
+    pub fn validation(message: impl Into<String>) -> Self {
+        ApiError::Validation {
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    /// The HTTP status code for this error
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Validation { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// A stable, machine-readable error code (clients should switch on this, not on `message`)
+    pub fn code(&self) -> &'static str {
+        match self {
+            ApiError::NotFound(_) => "not_found",
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Validation { .. } => "validation_error",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::RateLimited { .. } => "rate_limited",
+            ApiError::Upstream(_) => "upstream_error",
+            ApiError::Internal(_) => "internal_error",
+        }
+    }
+
+    /// The human-readable message
+    pub fn message(&self) -> &str {
+        match self {
+            ApiError::NotFound(m)
+            | ApiError::BadRequest(m)
+            | ApiError::Unauthorized(m)
+            | ApiError::Conflict(m)
+            | ApiError::Upstream(m)
+            | ApiError::Internal(m) => m,
+            ApiError::Validation { message, .. } | ApiError::RateLimited { message, .. } => message,
+        }
+    }
+
+    /// The envelope sent for this error
+    pub fn envelope(&self, request_id: &str) -> ErrorEnvelope {
+        let details = match self {
+            ApiError::Validation { details, .. } => details.clone(),
+            _ => None,
+        };
+        ErrorEnvelope {
+            error: ErrorBody {
+                code: self.code().to_string(),
+                message: self.message().to_string(),
+                details,
+                request_id: request_id.to_string(),
+            },
+        }
+    }
+
+    /// Build the JSON envelope for this error
+    pub fn to_json(&self, request_id: &str) -> Value {
+        // Serializing plain strings can't fail; Null would only ever appear if it somehow did
+        serde_json::to_value(self.envelope(request_id)).unwrap_or_default()
+    }
+}
+
+/// The JSON body of every error response: `{"error": {...}}`.
+// A struct rather than a `json!` literal so the OpenAPI schema (see openapi.rs) is derived
+// from exactly what is sent.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ErrorEnvelope {
+    pub error: ErrorBody,
+}
+
+/// What went wrong, and the id to quote when asking about it
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ErrorBody {
+    /// Machine-readable kind, e.g. `not_found` or `validation_error`
+    #[schema(example = "not_found")]
+    pub code: String,
+    /// Human-readable explanation
+    pub message: String,
+    /// Extra information; only present for some validation errors
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+    /// Also sent as the `x-request-id` response header
+    #[schema(example = "req-000000000000002a")]
+    pub request_id: String,
+}
+
+// Display is what `{}` prints; implementing it (plus Error) makes ApiError a "real" error type.
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+// IntoResponse is how axum turns a handler's return value into an HTTP response.
+// Because ApiError implements it, handlers can simply `return Err(ApiError::NotFound(..))`.
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let ctx = RequestContext::current();
+        let status = self.status();
+
+        let mut response = if ctx.wants_json {
+            (status, Json(self.to_json(&ctx.request_id))).into_response()
+        } else {
+            let body = format!(
+                "{} {}: {} (request_id: {})\n",
+                status.as_u16(),
+                self.code(),
+                self.message(),
+                ctx.request_id
+            );
+            (
+                status,
+                [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+                body,
+            )
+                .into_response()
+        };
+
+        if let ApiError::RateLimited {
+            retry_after_secs: Some(secs),
+            ..
+        } = &self
+        {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(*secs));
+        }
+        // RFC 7235: a 401 says which authentication scheme the client should use
+        if let ApiError::Unauthorized(_) = &self {
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        }
+        response
+    }
+}
+
+// Axum's built-in extractors reject bad input with their own plain-text responses.
+// Converting those rejections into ApiError keeps the error shape uniform.
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        match rejection {
+            // The JSON parsed but didn't match the struct (missing field, wrong type)
+            JsonRejection::JsonDataError(e) => ApiError::Validation {
+                message: "Request body failed validation".to_string(),
+                details: Some(json!({ "reason": e.body_text() })),
+            },
+            other => ApiError::BadRequest(other.body_text()),
+        }
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        ApiError::BadRequest(rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::BadRequest(rejection.body_text())
+    }
+}
+
+/// `Json<T>` extractor whose rejection is an `ApiError`.
+// The derive generates the FromRequest impl: "extract via axum::Json, map the rejection to ApiError".
+#[derive(FromRequest)]
+#[from_request(via(Json), rejection(ApiError))]
+pub struct ApiJson<T>(pub T);
+
+/// `Path<T>` extractor whose rejection is an `ApiError`.
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Path), rejection(ApiError))]
+pub struct ApiPath<T>(pub T);
+
+/// `Query<T>` extractor whose rejection is an `ApiError`.
+#[derive(FromRequestParts)]
+#[from_request(via(axum::extract::Query), rejection(ApiError))]
+pub struct ApiQuery<T>(pub T);
+
+/// Per-request information the error renderer needs.
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    pub request_id: String,
+    pub wants_json: bool,
+}
+
+// task_local! is like a ThreadLocal in Java, but scoped to one async task (one request)
+// instead of one OS thread. That matters because tokio moves tasks between threads.
+tokio::task_local! {
+    static REQUEST_CONTEXT: RequestContext;
+}
+
+impl RequestContext {
+    /// The context of the request currently being handled. Outside of the
+    /// `request_context` middleware (e.g. in unit tests) a fresh id and JSON are used.
+    pub fn current() -> RequestContext {
+        REQUEST_CONTEXT
+            .try_with(|ctx| ctx.clone())
+            .unwrap_or_else(|_| RequestContext {
+                request_id: next_request_id(),
+                wants_json: true,
+            })
+    }
+}
+
+/// Generate a process-unique request id like `req-000000000000002a`.
+pub fn next_request_id() -> String {
+    // A static atomic counter: shared by all threads without a lock.
+    static COUNTER: AtomicU64 = AtomicU64::new(1);
+    format!("req-{:016x}", COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Returns true unless the `Accept` header explicitly excludes JSON.
+/// No `Accept` header means "anything goes", so JSON is the default.
+pub fn accepts_json(headers: &HeaderMap) -> bool {
+    let Some(accept) = headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()) else {
+        return true;
+    };
+    accept.split(',').any(|media| {
+        let media = media.split(';').next().unwrap_or("").trim();
+        media == "*/*"
+            || media == "application/*"
+            || media == "application/json"
+            || media.ends_with("+json")
+    })
+}
+
+/// Middleware that assigns a request id (reusing `x-request-id` if the client sent one),
+/// records whether the client wants JSON errors, and echoes the id on the response.
+pub async fn request_context(mut req: Request, next: Next) -> Response {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(next_request_id);
+
+    let ctx = RequestContext {
+        request_id: request_id.clone(),
+        wants_json: accepts_json(req.headers()),
+    };
+    // Also store it in the request extensions so other middleware (logging) can read it
+    req.extensions_mut().insert(ctx.clone());
+
+    let mut response = REQUEST_CONTEXT.scope(ctx, next.run(req)).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Fallback handler for routes that don't exist
+pub async fn route_not_found() -> ApiError {
+    ApiError::NotFound("No route matches this path".to_string())
+}
@@ -0,0 +1,43 @@
+// Conditional GETs: `ETag` on responses and `304 Not Modified` for a matching `If-None-Match`.
+//
+// A client that polls keeps the ETag of its last response and sends it back. If the data hasn't
+// changed, the server answers with an empty 304 and the client reuses what it already has, so
+// polling every second costs a few bytes instead of the whole list. Our tags are *weak*
+// (`W/"..."`): they promise the same data, not byte-for-byte the same JSON.
+use axum::{
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+/// A weak entity tag: `W/"<tag>"`
+pub fn weak(tag: impl std::fmt::Display) -> String {
+    format!("W/\"{}\"", tag)
+}
+
+/// Whether `If-None-Match` lists `etag` (or is `*`), i.e. the client's copy is current.
+// Weak comparison, as RFC 9110 requires for If-None-Match: `W/"1"` and `"1"` match.
+pub fn is_fresh(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let ours = opaque(etag);
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        // One header can hold a comma-separated list of tags
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == ours)
+}
+
+/// `304 Not Modified` carrying the current ETag (and, as the RFC asks, no body)
+pub fn not_modified(etag: &str) -> Response {
+    with_etag(StatusCode::NOT_MODIFIED.into_response(), etag)
+}
+
+/// Add an `ETag` header to `response`
+pub fn with_etag(mut response: Response, etag: &str) -> Response {
+    // Our tags are plain ASCII, so this conversion can't actually fail
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        response.headers_mut().insert(header::ETAG, value);
+    }
+    response
+}
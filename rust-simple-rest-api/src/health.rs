@@ -0,0 +1,33 @@
+// Liveness check for load balancers and container orchestrators. Always open (no token needed).
+use axum::{extract::State, Json};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::Db;
+
+/// Body of `GET /health`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct HealthStatus {
+    /// Always `"ok"` when the server answers at all
+    pub status: String,
+    /// Number of stored items
+    pub items: usize,
+    pub uptime_secs: u64,
+}
+
+/// `GET /health`: `{"status": "ok", "items": <count>, "uptime_secs": <n>}`
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses((status = 200, description = "The server is up", body = HealthStatus))
+)]
+pub async fn health(State(db): State<Db>) -> Json<HealthStatus> {
+    // Taking the read lock also proves the state isn't wedged by a stuck writer
+    let items = db.items().await.items.len();
+    Json(HealthStatus {
+        status: "ok".to_string(),
+        items,
+        uptime_secs: db.uptime().as_secs(),
+    })
+}
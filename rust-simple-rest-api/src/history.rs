@@ -0,0 +1,191 @@
+// Prompt history: every `/prompt` call is remembered (prompt, answer or error, timing) so
+// past conversations can be listed and inspected later via `/prompts`.
+// Kept in memory only; it resets on restart, unlike the items.
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::error::{ApiError, ApiPath, ApiQuery, ErrorEnvelope};
+use crate::models::{check_limit, PageQuery};
+use crate::Db;
+
+/// Oldest records are dropped once the history holds this many
+pub const MAX_PROMPT_HISTORY: usize = 1000;
+
+/// One `/prompt` call and its outcome. Exactly one of `response` and `error` is set.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PromptRecord {
+    pub id: u64,
+    pub prompt: String,
+    pub response: Option<String>,
+    pub error: Option<String>,
+    /// The Gemini model asked (None if the call failed before a client existed, e.g. no API key)
+    pub model: Option<String>,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    pub latency_ms: u64,
+}
+
+/// The stored records, oldest first.
+#[derive(Debug, Default)]
+pub struct PromptHistory {
+    // Ids keep counting up after deletes, so an id is never reused
+    next_id: u64,
+    // VecDeque: a ring buffer, cheap to push at the back and drop from the front
+    records: VecDeque<PromptRecord>,
+}
+
+impl PromptHistory {
+    /// Store a finished call and return the record (with its new id).
+    pub fn record(
+        &mut self,
+        prompt: &str,
+        model: Option<&str>,
+        outcome: Result<&str, String>,
+        latency: Duration,
+    ) -> PromptRecord {
+        self.next_id += 1;
+        let (response, error) = match outcome {
+            Ok(text) => (Some(text.to_string()), None),
+            Err(message) => (None, Some(message)),
+        };
+        let record = PromptRecord {
+            id: self.next_id,
+            prompt: prompt.to_string(),
+            response,
+            error,
+            model: model.map(str::to_string),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            latency_ms: latency.as_millis() as u64,
+        };
+        self.records.push_back(record.clone());
+        while self.records.len() > MAX_PROMPT_HISTORY {
+            self.records.pop_front();
+        }
+        record
+    }
+
+    /// The record with `id`, if it is still stored
+    pub fn get(&self, id: u64) -> Option<&PromptRecord> {
+        self.records.iter().find(|record| record.id == id)
+    }
+
+    /// Delete the record with `id`; returns it if it existed
+    pub fn remove(&mut self, id: u64) -> Option<PromptRecord> {
+        let index = self.records.iter().position(|record| record.id == id)?;
+        self.records.remove(index)
+    }
+
+    /// Number of stored records
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Whether nothing is stored
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// All records, newest first
+    // 'impl Iterator': the caller can chain skip/take without us building a Vec first
+    pub fn newest_first(&self) -> impl Iterator<Item = &PromptRecord> {
+        self.records.iter().rev()
+    }
+}
+
+// One page of prompt records, newest first.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PromptPage {
+    pub prompts: Vec<PromptRecord>,
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+// List past prompts, newest first: `?limit` (default 50, max 500) and `?offset`, like `/items`.
+#[utoipa::path(
+    get,
+    path = "/prompts",
+    tag = "prompt",
+    params(PageQuery),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "One page of recorded prompts", body = PromptPage),
+        (status = 400, description = "Invalid query parameter", body = ErrorEnvelope),
+        (status = 401, description = "Missing or invalid API token", body = ErrorEnvelope),
+    )
+)]
+pub async fn list_prompts(
+    State(db): State<Db>,
+    ApiQuery(page): ApiQuery<PageQuery>,
+) -> Result<Json<PromptPage>, ApiError> {
+    check_limit(page.limit)?;
+    let history = db.history().await;
+    Ok(Json(PromptPage {
+        prompts: history
+            .newest_first()
+            .skip(page.offset)
+            .take(page.limit)
+            .cloned()
+            .collect(),
+        total: history.len(),
+        limit: page.limit,
+        offset: page.offset,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/prompts/{id}",
+    tag = "prompt",
+    params(("id" = u64, Path, description = "Prompt record id")),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The recorded prompt", body = PromptRecord),
+        (status = 401, description = "Missing or invalid API token", body = ErrorEnvelope),
+        (status = 404, description = "No such record", body = ErrorEnvelope),
+    )
+)]
+pub async fn get_prompt(
+    ApiPath(id): ApiPath<u64>,
+    State(db): State<Db>,
+) -> Result<Json<PromptRecord>, ApiError> {
+    db.history()
+        .await
+        .get(id)
+        .cloned()
+        .map(Json)
+        .ok_or_else(|| prompt_not_found(id))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/prompts/{id}",
+    tag = "prompt",
+    params(("id" = u64, Path, description = "Prompt record id")),
+    security(("bearer" = [])),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 401, description = "Missing or invalid API token", body = ErrorEnvelope),
+        (status = 404, description = "No such record", body = ErrorEnvelope),
+    )
+)]
+pub async fn delete_prompt(
+    ApiPath(id): ApiPath<u64>,
+    State(db): State<Db>,
+) -> Result<StatusCode, ApiError> {
+    match db.history_mut().await.remove(id) {
+        Some(_) => Ok(StatusCode::NO_CONTENT),
+        None => Err(prompt_not_found(id)),
+    }
+}
+
+fn prompt_not_found(id: u64) -> ApiError {
+    ApiError::NotFound(format!("Prompt {} not found", id))
+}
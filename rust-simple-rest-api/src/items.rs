@@ -0,0 +1,483 @@
+// Handlers: each is an async function. Axum uses function signatures to determine how to
+// extract parameters from requests (Path, State, Json). Returning `Result<T, ApiError>` lets
+// us use `?` and early returns; axum converts both arms into HTTP responses.
+use std::collections::HashMap;
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+use crate::error::{ApiError, ApiJson, ApiPath, ApiQuery, ErrorEnvelope};
+use crate::etag;
+use crate::models::{
+    check_limit, first_version, normalize_tags, CreateItem, DeleteItemsQuery, DeletedItems, Item,
+    ItemBatch, ItemPage, ListItemsQuery, SortField, SortOrder, TagCount, TagList, UpdateItem,
+    MAX_BATCH_SIZE,
+};
+use crate::storage::ItemStore;
+use crate::Db;
+
+// List items: filter, sort, and paginate according to the query string, then return one page.
+// Note the `.await` in `db.items()`:
+// - `.read()` returns a future that resolves to a read guard once no writer holds the lock.
+// - While waiting, the task is parked and the worker thread runs other requests (a std lock
+//   would block the whole thread instead). The guard releases the lock when it is dropped.
+// The ETag is the items' revision: any change anywhere gives every list URL a new tag, and
+// until then a client sending the old one gets an empty 304.
+#[utoipa::path(
+    get,
+    path = "/items",
+    tag = "items",
+    params(ListItemsQuery, ("If-None-Match" = Option<String>, Header, description = "ETag of a previous response")),
+    responses(
+        (status = 200, description = "One page of items", body = ItemPage, headers(("ETag" = String, description = "Changes whenever any item changes"))),
+        (status = 304, description = "Nothing changed since the `If-None-Match` ETag"),
+        (status = 400, description = "Invalid query parameter", body = ErrorEnvelope),
+    )
+)]
+pub async fn list_items(
+    State(db): State<Db>,
+    headers: HeaderMap,
+    ApiQuery(query): ApiQuery<ListItemsQuery>,
+) -> Result<Response, ApiError> {
+    check_limit(query.limit)?;
+    let tag = query.tag.as_deref().map(|tag| tag.trim().to_lowercase());
+
+    // Clone the matching items (we return owned data) so the read lock is released before sorting.
+    // `.cloned()` uses the Clone trait on Item.
+    let (etag, mut matching): (String, Vec<Item>) = {
+        let items = db.items().await;
+        // Read under the same lock as the items, so the tag describes exactly this data
+        let etag = etag::weak(format!("{:x}-{}", db.instance_id(), db.revision()));
+        if etag::is_fresh(&headers, &etag) {
+            return Ok(etag::not_modified(&etag));
+        }
+        let matching = items
+            .items
+            .values()
+            // `is_none_or`: no filter matches everything, otherwise compare
+            .filter(|item| {
+                query
+                    .completed
+                    .is_none_or(|completed| item.completed == completed)
+            })
+            .filter(|item| tag.as_ref().is_none_or(|tag| item.tags.contains(tag)))
+            .cloned()
+            .collect();
+        (etag, matching)
+    };
+
+    // HashMap order is random, so always sort; the id breaks ties between equal names
+    // to keep pages stable from one request to the next.
+    match query.sort {
+        SortField::Id => matching.sort_by_key(|item| item.id),
+        SortField::Name => matching.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id))),
+    }
+    if query.order == SortOrder::Desc {
+        matching.reverse();
+    }
+
+    let total = matching.len();
+    // skip/take on an iterator is the Rust spelling of SQL's OFFSET/LIMIT
+    let items = matching
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit)
+        .collect();
+    let page = Json(ItemPage {
+        items,
+        total,
+        limit: query.limit,
+        offset: query.offset,
+    });
+    Ok(etag::with_etag(page.into_response(), &etag))
+}
+
+// Create item: extract JSON body and state, insert new item under the write lock, return 201 Created.
+// `db.mutate` also writes the new map to storage; if that fails the handler returns 500.
+#[utoipa::path(
+    post,
+    path = "/items",
+    tag = "items",
+    request_body = CreateItem,
+    security(("bearer" = [])),
+    responses(
+        (status = 201, description = "The new item", body = Item),
+        (status = 400, description = "Invalid tags", body = ErrorEnvelope),
+        (status = 401, description = "Missing or invalid API token", body = ErrorEnvelope),
+        (status = 422, description = "Invalid body or blank name", body = ErrorEnvelope),
+    )
+)]
+pub async fn create_item(
+    State(db): State<Db>,
+    ApiJson(payload): ApiJson<CreateItem>,
+) -> Result<(StatusCode, Json<Item>), ApiError> {
+    check_name(&payload.name).map_err(ApiError::validation)?;
+    let tags = normalize_tags(payload.tags).map_err(ApiError::BadRequest)?;
+    // Computing the id and inserting inside the same `mutate` call (one write lock) is what
+    // keeps concurrent creates from handing out the same id twice.
+    let item = db
+        .mutate(|store| insert_new(store, payload.name, tags))
+        .await?;
+    Ok((StatusCode::CREATED, Json(item)))
+}
+
+// Create many items at once: all of them or none. Every entry is checked before anything is
+// inserted, and the inserts happen inside one `mutate` call, so other requests never see a
+// half-finished batch and a failed save leaves the list as it was.
+#[utoipa::path(
+    post,
+    path = "/items/batch",
+    tag = "items",
+    request_body = ItemBatch<CreateItem>,
+    security(("bearer" = [])),
+    responses(
+        (status = 201, description = "All the new items, in request order", body = ItemBatch<Item>),
+        (status = 400, description = "Invalid tags in an entry", body = ErrorEnvelope),
+        (status = 401, description = "Missing or invalid API token", body = ErrorEnvelope),
+        (status = 422, description = "Empty or oversized batch, or an invalid entry; nothing was created", body = ErrorEnvelope),
+    )
+)]
+pub async fn create_items(
+    State(db): State<Db>,
+    ApiJson(payload): ApiJson<ItemBatch<CreateItem>>,
+) -> Result<(StatusCode, Json<ItemBatch<Item>>), ApiError> {
+    let count = payload.items.len();
+    if count == 0 || count > MAX_BATCH_SIZE {
+        return Err(ApiError::Validation {
+            message: format!(
+                "A batch must contain between 1 and {} items, got {}",
+                MAX_BATCH_SIZE, count
+            ),
+            details: Some(json!({ "max_items": MAX_BATCH_SIZE, "received": count })),
+        });
+    }
+    // Report the first bad entry by its position, so the client knows which one to fix
+    let mut entries = Vec::with_capacity(count);
+    for (index, entry) in payload.items.into_iter().enumerate() {
+        if let Err(reason) = check_name(&entry.name) {
+            return Err(ApiError::Validation {
+                message: format!("items[{}]: {}", index, reason),
+                details: Some(json!({ "index": index, "reason": reason })),
+            });
+        }
+        let tags = normalize_tags(entry.tags)
+            .map_err(|reason| ApiError::BadRequest(format!("items[{}]: {}", index, reason)))?;
+        entries.push((entry.name, tags));
+    }
+
+    let created = db
+        .mutate(|store| {
+            entries
+                .into_iter()
+                .map(|(name, tags)| insert_new(store, name, tags))
+                // Collecting an iterator of Results into Result<Vec, _> stops at the first error
+                .collect::<Result<Vec<Item>, ApiError>>()
+        })
+        .await?;
+    Ok((StatusCode::CREATED, Json(ItemBatch { items: created })))
+}
+
+// Delete every item whose `completed` matches the query, e.g. `DELETE /items?completed=true`
+// to clear finished todos. Returns how many were removed.
+#[utoipa::path(
+    delete,
+    path = "/items",
+    tag = "items",
+    params(DeleteItemsQuery),
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "How many items were deleted", body = DeletedItems),
+        (status = 400, description = "Missing or invalid `completed`", body = ErrorEnvelope),
+        (status = 401, description = "Missing or invalid API token", body = ErrorEnvelope),
+    )
+)]
+pub async fn delete_items(
+    State(db): State<Db>,
+    ApiQuery(query): ApiQuery<DeleteItemsQuery>,
+) -> Result<Json<DeletedItems>, ApiError> {
+    let deleted = db
+        .mutate(|store| {
+            let before = store.items.len();
+            // `retain` keeps the entries for which the closure returns true
+            store
+                .items
+                .retain(|_, item| item.completed != query.completed);
+            Ok(before - store.items.len())
+        })
+        .await?;
+    Ok(Json(DeletedItems { deleted }))
+}
+
+// List every tag in use with the number of items carrying it, most used first
+// (ties in alphabetical order).
+#[utoipa::path(
+    get,
+    path = "/tags",
+    tag = "items",
+    responses((status = 200, description = "Tags in use, most used first", body = TagList))
+)]
+pub async fn list_tags(State(db): State<Db>) -> Json<TagList> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for item in db.items().await.items.values() {
+        for tag in &item.tags {
+            // `entry` looks the key up once and lets us insert or update in place
+            *counts.entry(tag.clone()).or_insert(0) += 1;
+        }
+    }
+    let mut tags: Vec<TagCount> = counts
+        .into_iter()
+        .map(|(tag, count)| TagCount { tag, count })
+        .collect();
+    tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    Json(TagList { tags })
+}
+
+/// Insert a new item with the next id. `tags` must already be normalized.
+fn insert_new(store: &mut ItemStore, name: String, tags: Vec<String>) -> Result<Item, ApiError> {
+    // Take `next_id` and move it on. Ids are never reused, not even the id of a deleted item
+    // (see `ItemStore`). `checked_add` turns the (theoretical) u64 overflow into a 500
+    // instead of a panic.
+    let id = store.next_id;
+    store.next_id = id
+        .checked_add(1)
+        .ok_or_else(|| ApiError::Internal("No item ids left".to_string()))?;
+    let item = Item {
+        id,
+        name,
+        completed: false,
+        tags,
+        version: first_version(),
+    };
+    // Insert and return a clone to the caller
+    store.items.insert(id, item.clone());
+    Ok(item)
+}
+
+/// Names may not be empty or only whitespace
+fn check_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("name must not be blank".to_string());
+    }
+    Ok(())
+}
+
+// Get item by ID. Path extractor converts the `:id` segment into a u64.
+// The ETag is the item's version (`W/"3"`), which can also be sent back as `If-Match`.
+#[utoipa::path(
+    get,
+    path = "/items/{id}",
+    tag = "items",
+    params(
+        ("id" = u64, Path, description = "Item id"),
+        ("If-None-Match" = Option<String>, Header, description = "ETag of a previous response"),
+    ),
+    responses(
+        (status = 200, description = "The item", body = Item, headers(("ETag" = String, description = "The item's version, e.g. `W/\"3\"`"))),
+        (status = 304, description = "The item is still at the `If-None-Match` version"),
+        (status = 400, description = "The id is not a number", body = ErrorEnvelope),
+        (status = 404, description = "No such item", body = ErrorEnvelope),
+    )
+)]
+pub async fn get_item(
+    ApiPath(id): ApiPath<u64>,
+    State(db): State<Db>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let store = db.items().await;
+    // `ok_or_else` turns the Option from HashMap::get into a Result; `?`-style flow without a match.
+    let item = store.items.get(&id).ok_or_else(|| item_not_found(id))?;
+    let etag = etag::weak(item.version);
+    if etag::is_fresh(&headers, &etag) {
+        return Ok(etag::not_modified(&etag));
+    }
+    Ok(etag::with_etag(Json(item.clone()).into_response(), &etag))
+}
+
+// Update item partially (PATCH): only the fields present in the body change.
+// Mutate in place under the write lock and return the updated item (with its new version).
+#[utoipa::path(
+    patch,
+    path = "/items/{id}",
+    tag = "items",
+    params(
+        ("id" = u64, Path, description = "Item id"),
+        ("If-Match" = Option<String>, Header, description = "Only update if the item is at this version"),
+    ),
+    request_body = UpdateItem,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The updated item", body = Item),
+        (status = 400, description = "Invalid tags or `If-Match`", body = ErrorEnvelope),
+        (status = 401, description = "Missing or invalid API token", body = ErrorEnvelope),
+        (status = 404, description = "No such item", body = ErrorEnvelope),
+        (status = 409, description = "The item changed since the given version", body = ErrorEnvelope),
+        (status = 422, description = "Invalid body", body = ErrorEnvelope),
+    )
+)]
+pub async fn update_item(
+    ApiPath(id): ApiPath<u64>,
+    State(db): State<Db>,
+    headers: HeaderMap,
+    ApiJson(payload): ApiJson<UpdateItem>,
+) -> Result<Json<Item>, ApiError> {
+    let expected = expected_version(&headers, payload.version)?;
+    // `transpose` turns Option<Result<..>> into Result<Option<..>>, so `?` can reject bad tags
+    let tags = payload
+        .tags
+        .map(normalize_tags)
+        .transpose()
+        .map_err(ApiError::BadRequest)?;
+    let item = db
+        .mutate(|store| {
+            let item = store.items.get_mut(&id).ok_or_else(|| item_not_found(id))?;
+            check_version(item, expected)?;
+            // Optional fields: only update when provided
+            let name = payload.name.unwrap_or_else(|| item.name.clone());
+            let completed = payload.completed.unwrap_or(item.completed);
+            let tags = tags.unwrap_or_else(|| item.tags.clone());
+            apply_change(item, name, completed, tags);
+            Ok(item.clone())
+        })
+        .await?;
+    Ok(Json(item))
+}
+
+// Replace item (PUT): the body is the item's complete new state. `name` is required, and an
+// omitted `completed` means false (omitted `tags`, none), so a PUT never silently keeps an old value.
+#[utoipa::path(
+    put,
+    path = "/items/{id}",
+    tag = "items",
+    params(
+        ("id" = u64, Path, description = "Item id"),
+        ("If-Match" = Option<String>, Header, description = "Only replace if the item is at this version"),
+    ),
+    request_body = UpdateItem,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "The updated item", body = Item),
+        (status = 400, description = "Missing `name`, invalid tags, or invalid `If-Match`", body = ErrorEnvelope),
+        (status = 401, description = "Missing or invalid API token", body = ErrorEnvelope),
+        (status = 404, description = "No such item", body = ErrorEnvelope),
+        (status = 409, description = "The item changed since the given version", body = ErrorEnvelope),
+        (status = 422, description = "Invalid body", body = ErrorEnvelope),
+    )
+)]
+pub async fn replace_item(
+    ApiPath(id): ApiPath<u64>,
+    State(db): State<Db>,
+    headers: HeaderMap,
+    ApiJson(payload): ApiJson<UpdateItem>,
+) -> Result<Json<Item>, ApiError> {
+    // `let ... else`: bind the value if the pattern matches, otherwise run the (diverging) else block
+    let Some(name) = payload.name else {
+        return Err(ApiError::BadRequest(
+            "PUT replaces the whole item, so `name` is required; use PATCH to change only some fields"
+                .to_string(),
+        ));
+    };
+    let expected = expected_version(&headers, payload.version)?;
+    let tags = normalize_tags(payload.tags.unwrap_or_default()).map_err(ApiError::BadRequest)?;
+    let item = db
+        .mutate(|store| {
+            let item = store.items.get_mut(&id).ok_or_else(|| item_not_found(id))?;
+            check_version(item, expected)?;
+            apply_change(item, name, payload.completed.unwrap_or(false), tags);
+            Ok(item.clone())
+        })
+        .await?;
+    Ok(Json(item))
+}
+
+// Delete item. Return 204 No Content on success. `If-Match` is honored here too.
+#[utoipa::path(
+    delete,
+    path = "/items/{id}",
+    tag = "items",
+    params(
+        ("id" = u64, Path, description = "Item id"),
+        ("If-Match" = Option<String>, Header, description = "Only delete if the item is at this version"),
+    ),
+    security(("bearer" = [])),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 401, description = "Missing or invalid API token", body = ErrorEnvelope),
+        (status = 404, description = "No such item", body = ErrorEnvelope),
+        (status = 409, description = "The item changed since the given version", body = ErrorEnvelope),
+    )
+)]
+pub async fn delete_item(
+    ApiPath(id): ApiPath<u64>,
+    State(db): State<Db>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    let expected = expected_version(&headers, None)?;
+    db.mutate(|store| {
+        let item = store.items.get(&id).ok_or_else(|| item_not_found(id))?;
+        check_version(item, expected)?;
+        store.items.remove(&id);
+        Ok(StatusCode::NO_CONTENT)
+    })
+    .await
+}
+
+/// Set the new field values, bumping the version only if something actually changed
+/// (so a PATCH with an empty body is a true no-op).
+fn apply_change(item: &mut Item, name: String, completed: bool, tags: Vec<String>) {
+    if item.name != name || item.completed != completed || item.tags != tags {
+        item.name = name;
+        item.completed = completed;
+        item.tags = tags;
+        item.version += 1;
+    }
+}
+
+/// The version the client based its change on: the `If-Match` header if present, otherwise the
+/// body's `version` field. `None` means "no precondition" (including `If-Match: *`).
+fn expected_version(
+    headers: &HeaderMap,
+    body_version: Option<u64>,
+) -> Result<Option<u64>, ApiError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(body_version);
+    };
+    let invalid =
+        || ApiError::BadRequest("If-Match must be an item version such as \"3\"".to_string());
+    let value = value.to_str().map_err(|_| invalid())?.trim();
+    if value == "*" {
+        return Ok(None);
+    }
+    // Accept the ETag forms `"3"` and `W/"3"` as well as a bare `3`
+    let version = value
+        .trim_start_matches("W/")
+        .trim_matches('"')
+        .parse()
+        .map_err(|_| invalid())?;
+    match body_version {
+        Some(body) if body != version => Err(ApiError::BadRequest(format!(
+            "If-Match ({}) and body version ({}) disagree",
+            version, body
+        ))),
+        _ => Ok(Some(version)),
+    }
+}
+
+/// 409 Conflict if the item changed since the client read it
+fn check_version(item: &Item, expected: Option<u64>) -> Result<(), ApiError> {
+    match expected {
+        Some(version) if version != item.version => Err(ApiError::Conflict(format!(
+            "Item {} is at version {}, but the request was based on version {}",
+            item.id, item.version, version
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn item_not_found(id: u64) -> ApiError {
+    ApiError::NotFound(format!("Item {} not found", id))
+}
@@ -0,0 +1,180 @@
+// Library half of the crate: everything needed to build the router lives here so that
+// integration tests (in tests/) can construct the exact same app that main.rs serves.
+// Top-level imports. Each import brings types/functions into scope similar to `import` in Java
+// or `require`/`import` in JS/Python, but Rust's `use` is resolved at compile time.
+use axum::{
+    // HTTP method type and header names
+    http::{header, HeaderName, Method},
+    // Wraps an async fn into a tower middleware layer
+    middleware,
+    // Routing helpers (get, post, put, delete)
+    routing::{get, post, put},
+    Router,
+};
+// Arc (atomic reference counted pointer) for shared ownership across tasks
+use std::sync::Arc;
+use tower_http::{
+    cors::{Any, CorsLayer},
+    trace::{DefaultOnResponse, TraceLayer},
+};
+use tracing::Level;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+// `pub mod` declares a submodule (src/<name>.rs) and makes it visible outside the crate.
+pub mod auth;
+pub mod chat;
+pub mod error;
+pub mod etag;
+pub mod health;
+pub mod history;
+pub mod items;
+pub mod metrics;
+pub mod models;
+pub mod openapi;
+pub mod prompt;
+pub mod rate_limit;
+pub mod server;
+pub mod state;
+pub mod storage;
+
+pub use auth::AuthConfig;
+pub use error::ApiError;
+pub use history::{PromptHistory, PromptPage, PromptRecord};
+pub use metrics::Metrics;
+pub use models::{
+    CreateItem, DeleteItemsQuery, DeletedItems, Item, ItemBatch, ItemPage, ListItemsQuery,
+    PageQuery, SortField, SortOrder, TagCount, TagList, UpdateItem, MAX_BATCH_SIZE, MAX_TAGS,
+    MAX_TAG_LEN,
+};
+pub use rate_limit::{RateLimitConfig, RateLimiter};
+pub use server::{serve, shutdown_signal, ServerConfig};
+pub use state::AppState;
+pub use storage::{ItemStore, JsonFileStorage, MemoryStorage, Storage};
+
+// App state type alias: an Arc (thread-safe ref-counted pointer) around the AppState
+// (see state.rs: the items behind a tokio RwLock, plus the storage they are saved to).
+// - Arc<T>: like Java's shared object references, but explicitly reference-counted and
+//   thread-safe. Cloning an Arc increases the ref count; dropping an Arc decreases it.
+// - RwLock<T>: allows multiple concurrent readers or one writer at a time. This
+//   pattern avoids a global mutex if readers dominate. tokio's version never "poisons"
+//   (a panicking writer just releases the lock), so there is no Result to unwrap.
+// In Java you might use `ConcurrentHashMap` or synchronize access; here we compose
+// Arc + RwLock for shared mutable access across async tasks.
+pub type Db = Arc<AppState>;
+
+/// Build the application router with all routes and middleware attached.
+pub fn app(db: Db) -> Router {
+    // Configure CORS. Tower-http provides middleware; we allow common HTTP methods and any origin.
+    let cors = CorsLayer::new()
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        .allow_origin(Any)
+        // Named one by one: browsers never let a wildcard cover `Authorization`, so with
+        // `Any` they would refuse to send the bearer token (see auth.rs)
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::IF_MATCH,
+            header::IF_NONE_MATCH,
+            HeaderName::from_static(error::REQUEST_ID_HEADER),
+        ])
+        // Let browser scripts read the ETag for conditional requests (see etag.rs)
+        .expose_headers([header::ETAG]);
+
+    // Log one line per request at info level: a span carrying the request id, method and path,
+    // and an event with the status and latency when the response is ready. The id comes from
+    // `request_context`, which runs before this layer (see the layer order below).
+    let trace = TraceLayer::new_for_http()
+        .make_span_with(|request: &axum::extract::Request| {
+            let request_id = request
+                .extensions()
+                .get::<error::RequestContext>()
+                .map_or("-", |ctx| ctx.request_id.as_str());
+            tracing::info_span!(
+                "request",
+                request_id = %request_id,
+                method = %request.method(),
+                path = %request.uri().path()
+            )
+        })
+        .on_request(())
+        .on_response(DefaultOnResponse::new().level(Level::INFO));
+
+    // Routes that spend Gemini quota or change data need a bearer token (see auth.rs).
+    // `route_layer` wraps only the methods registered before it, so `GET /items` stays open
+    // while `POST /items` on the same path is protected. The closure builds a fresh layer per use.
+    let protected = || middleware::from_fn_with_state(db.clone(), auth::require_token);
+    // The prompt routes are also rate limited per client (see rate_limit.rs). Layers added
+    // later run first, so the token is checked before a bucket token is spent.
+    let limited = || middleware::from_fn_with_state(db.clone(), rate_limit::limit);
+
+    // Build our application router and attach handlers. `.route` maps paths to handler functions.
+    // `with_state(db)` clones the Arc and makes it available to handlers via the State extractor.
+    Router::new()
+        .route("/health", get(health::health))
+        .route("/metrics", get(metrics::metrics))
+        .route(
+            "/prompt",
+            post(prompt::handle_prompt)
+                .route_layer(limited())
+                .route_layer(protected()),
+        )
+        .route(
+            "/prompt/stream",
+            post(prompt::handle_prompt_stream)
+                .route_layer(limited())
+                .route_layer(protected()),
+        )
+        .route("/ws", get(chat::handle_ws).route_layer(protected()))
+        .route(
+            "/prompts",
+            get(history::list_prompts).route_layer(protected()),
+        )
+        .route(
+            "/prompts/:id",
+            get(history::get_prompt)
+                .delete(history::delete_prompt)
+                .route_layer(protected()),
+        )
+        .route(
+            "/items",
+            get(items::list_items).merge(
+                post(items::create_item)
+                    .delete(items::delete_items)
+                    .route_layer(protected()),
+            ),
+        )
+        .route("/tags", get(items::list_tags))
+        .route(
+            "/items/batch",
+            post(items::create_items).route_layer(protected()),
+        )
+        .route(
+            "/items/:id",
+            get(items::get_item).merge(
+                put(items::replace_item)
+                    .patch(items::update_item)
+                    .delete(items::delete_item)
+                    .route_layer(protected()),
+            ),
+        )
+        // The OpenAPI document and a Swagger UI page to browse it (see openapi.rs)
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", openapi::ApiDoc::openapi()))
+        // Unknown paths get the same structured 404 as a missing item
+        .fallback(error::route_not_found)
+        // Layers wrap everything registered above (the last one added runs first).
+        // `metrics::track` sees the matched route pattern, so it goes on before the others.
+        .layer(middleware::from_fn_with_state(db.clone(), metrics::track))
+        .layer(trace)
+        // request_context gives every request an id that ends up in error bodies, the
+        // x-request-id header and, being outside `trace`, the log span.
+        .layer(middleware::from_fn(error::request_context))
+        .layer(cors)
+        .with_state(db)
+}
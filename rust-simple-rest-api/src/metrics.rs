@@ -0,0 +1,174 @@
+// In-process request metrics, served at `GET /metrics` in the Prometheus text format.
+//
+// A middleware times every request and records it under its route *pattern* (`/items/:id`,
+// not `/items/42`), so the number of series stays small however many items exist. The
+// numbers live in memory and start from zero when the process restarts, which is what
+// Prometheus expects from a counter.
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::Db;
+
+/// Upper bounds (in seconds) of the latency histogram buckets
+pub const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Label used for requests that matched no route (they get the fallback 404)
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Everything recorded for one method + route pair
+#[derive(Debug, Default)]
+struct RouteStats {
+    /// Requests per status code
+    statuses: BTreeMap<u16, u64>,
+    /// `buckets[i]` counts requests that took at most `LATENCY_BUCKETS[i]` seconds
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    total_secs: f64,
+}
+
+/// Request counts and latency histograms per route.
+// A std Mutex (not tokio's) is the right tool here: the lock is held for a few
+// arithmetic operations and never across an `.await`, so it can't stall the runtime.
+// BTreeMap rather than HashMap keeps the `/metrics` output in a stable order.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    routes: Mutex<BTreeMap<(String, String), RouteStats>>,
+}
+
+impl Metrics {
+    /// Record one finished request
+    pub fn record(&self, method: &str, route: &str, status: u16, latency: Duration) {
+        let secs = latency.as_secs_f64();
+        // `lock()` only fails if another thread panicked while holding the lock; the counters
+        // are still usable then, so take the data anyway instead of panicking too.
+        let mut routes = self
+            .routes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let stats = routes
+            .entry((method.to_string(), route.to_string()))
+            .or_default();
+        *stats.statuses.entry(status).or_insert(0) += 1;
+        for (bucket, bound) in stats.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+            if secs <= bound {
+                *bucket += 1;
+            }
+        }
+        stats.count += 1;
+        stats.total_secs += secs;
+    }
+
+    /// Number of requests recorded for `method` + `route` (all statuses)
+    pub fn request_count(&self, method: &str, route: &str) -> u64 {
+        let routes = self
+            .routes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        routes
+            .get(&(method.to_string(), route.to_string()))
+            .map_or(0, |stats| stats.count)
+    }
+
+    /// Everything recorded so far in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let routes = self
+            .routes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut out = String::new();
+        // `writeln!` into a String can't fail, so its Result is ignored with `let _`
+        let _ = writeln!(
+            out,
+            "# HELP http_requests_total Requests handled, by method, route and status."
+        );
+        let _ = writeln!(out, "# TYPE http_requests_total counter");
+        for ((method, route), stats) in routes.iter() {
+            for (status, count) in &stats.statuses {
+                let _ = writeln!(
+                    out,
+                    "http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                    method, route, status, count
+                );
+            }
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP http_request_duration_seconds Request latency, by method and route."
+        );
+        let _ = writeln!(out, "# TYPE http_request_duration_seconds histogram");
+        for ((method, route), stats) in routes.iter() {
+            let labels = format!("method=\"{}\",route=\"{}\"", method, route);
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(stats.buckets) {
+                let _ = writeln!(
+                    out,
+                    "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, count
+                );
+            }
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, stats.count
+            );
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_sum{{{}}} {}",
+                labels, stats.total_secs
+            );
+            let _ = writeln!(
+                out,
+                "http_request_duration_seconds_count{{{}}} {}",
+                labels, stats.count
+            );
+        }
+        out
+    }
+}
+
+/// Middleware: time the request and record it in `db.metrics()`.
+// The latency is measured until the response headers are ready; for `/prompt/stream` that is
+// when the first event arrives, not when the stream ends.
+pub async fn track(
+    State(db): State<Db>,
+    matched: Option<MatchedPath>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().to_string();
+    let route = matched.map_or_else(
+        || UNMATCHED_ROUTE.to_string(),
+        |path| path.as_str().to_string(),
+    );
+    let started = Instant::now();
+    let response = next.run(request).await;
+    db.metrics().record(
+        &method,
+        &route,
+        response.status().as_u16(),
+        started.elapsed(),
+    );
+    response
+}
+
+/// `GET /metrics`: the Prometheus scrape endpoint
+pub async fn metrics(State(db): State<Db>) -> impl IntoResponse {
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        db.metrics().render(),
+    )
+}
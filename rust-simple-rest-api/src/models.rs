@@ -0,0 +1,226 @@
+// Data models shared by the handlers. `pub` makes them visible to main.rs and to tests.
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::error::ApiError;
+
+// Data model: a simple Item struct. `derive` automatically implements common traits.
+// - Debug: allows printing with `{:?}` for debugging
+// - Serialize/Deserialize: provided by serde to convert to/from JSON (like Jackson in Java)
+// - Clone: allow cheap-ish duplication of the value when needed
+// - ToSchema: from utoipa, describes the struct in the OpenAPI document (see openapi.rs)
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct Item {
+    pub id: u64,
+    pub name: String,
+    pub completed: bool,
+    /// Lowercase labels such as "work" or "home" (see `normalize_tags`).
+    // `default`: items saved before tags existed load with no tags.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Starts at 1 and goes up by one whenever the item changes. Clients send it back
+    /// (`If-Match` header or `version` field) so an update based on stale data is refused.
+    // Items saved before versions existed have no field; serde calls the function instead.
+    #[serde(default = "first_version")]
+    pub version: u64,
+}
+
+/// The version of a newly created item
+pub fn first_version() -> u64 {
+    1
+}
+
+// DTO for creating an item: incoming JSON will be deserialized into this struct
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateItem {
+    pub name: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Most tags one item may carry
+pub const MAX_TAGS: usize = 10;
+/// Longest tag, in characters
+pub const MAX_TAG_LEN: usize = 32;
+
+/// Clean up the tags a client sent: trim, lowercase, and drop duplicates (keeping the first
+/// occurrence's position). Empty or overlong tags, or more than `MAX_TAGS` distinct ones,
+/// are an error whose message says which rule was broken.
+pub(crate) fn normalize_tags(tags: Vec<String>) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if tag.is_empty() {
+            return Err("tags must not be empty".to_string());
+        }
+        // `chars().count()`, not `len()`: `len` counts UTF-8 bytes, so "ü" would count as 2
+        if tag.chars().count() > MAX_TAG_LEN {
+            return Err(format!(
+                "tag `{}` is longer than {} characters",
+                tag, MAX_TAG_LEN
+            ));
+        }
+        // A linear `contains` is fine for at most a handful of tags
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(format!(
+            "an item may have at most {} tags, got {}",
+            MAX_TAGS,
+            normalized.len()
+        ));
+    }
+    Ok(normalized)
+}
+
+/// One entry of `GET /tags`: a tag and how many items carry it
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Response of `GET /tags`, most used first
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TagList {
+    pub tags: Vec<TagCount>,
+}
+
+/// Most items `POST /items/batch` creates in one request
+pub const MAX_BATCH_SIZE: usize = 100;
+
+// Body of `POST /items/batch`: `{"items": [{"name": "milk"}, {"name": "eggs"}]}`.
+// The response uses the same shape, with the created items (ids included).
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ItemBatch<T> {
+    pub items: Vec<T>,
+}
+
+// Query string of `DELETE /items`. `completed` is required, so a bare `DELETE /items`
+// can't wipe the whole list by accident.
+// IntoParams: documents the fields as query parameters in the OpenAPI document.
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteItemsQuery {
+    /// Delete the items whose `completed` equals this
+    pub completed: bool,
+}
+
+/// Response of `DELETE /items`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DeletedItems {
+    pub deleted: usize,
+}
+
+// DTO for updating an item. Options are `Option<T>` so they can be omitted in JSON.
+// PATCH applies only the fields that are present (`tags` replaces the whole list); PUT requires
+// `name` and resets an omitted `completed` to false and omitted `tags` to none. `version`, if given, must match the stored item's version.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateItem {
+    pub name: Option<String>,
+    pub completed: Option<bool>,
+    pub tags: Option<Vec<String>>,
+    pub version: Option<u64>,
+}
+
+/// Default page size for `GET /items`
+pub const DEFAULT_LIMIT: usize = 50;
+/// Largest page a client may ask for
+pub const MAX_LIMIT: usize = 500;
+
+/// Reject page sizes outside `1..=MAX_LIMIT` with a 400
+pub(crate) fn check_limit(limit: usize) -> Result<(), ApiError> {
+    if limit == 0 || limit > MAX_LIMIT {
+        return Err(ApiError::BadRequest(format!(
+            "limit must be between 1 and {}, got {}",
+            MAX_LIMIT, limit
+        )));
+    }
+    Ok(())
+}
+
+// Plain `?limit=&offset=` paging for lists without filters (e.g. `/prompts`).
+#[derive(Debug, Clone, Copy, Deserialize, IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub struct PageQuery {
+    /// Page size, 1 to 500 (default 50)
+    pub limit: usize,
+    /// Entries to skip (default 0)
+    pub offset: usize,
+}
+
+impl Default for PageQuery {
+    fn default() -> Self {
+        Self {
+            limit: DEFAULT_LIMIT,
+            offset: 0,
+        }
+    }
+}
+
+/// Field to sort the item list by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortField {
+    #[default]
+    Id,
+    Name,
+}
+
+/// Sort direction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+// Query string of `GET /items`, e.g. `?completed=false&tag=work&sort=name&order=desc&limit=20&offset=40`.
+// Every field is optional in the URL; `#[serde(default)]` fills missing ones from `Default`.
+// Unknown enum values (`?sort=date`) fail deserialization, which becomes a 400.
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(default)]
+#[into_params(parameter_in = Query)]
+pub struct ListItemsQuery {
+    /// Page size, 1 to 500 (default 50)
+    pub limit: usize,
+    /// Items to skip (default 0)
+    pub offset: usize,
+    /// Only completed (`true`) or open (`false`) items
+    pub completed: Option<bool>,
+    /// Only items carrying this tag (compared case-insensitively)
+    pub tag: Option<String>,
+    /// Sort by `id` (default) or `name`
+    #[param(inline)]
+    pub sort: SortField,
+    /// `asc` (default) or `desc`
+    #[param(inline)]
+    pub order: SortOrder,
+}
+
+impl Default for ListItemsQuery {
+    fn default() -> Self {
+        Self {
+            limit: DEFAULT_LIMIT,
+            offset: 0,
+            completed: None,
+            tag: None,
+            sort: SortField::default(),
+            order: SortOrder::default(),
+        }
+    }
+}
+
+// One page of items plus what the client needs to fetch the next one.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ItemPage {
+    pub items: Vec<Item>,
+    /// Number of items matching the filter (across all pages)
+    pub total: usize,
+    pub limit: usize,
+    pub offset: usize,
+}
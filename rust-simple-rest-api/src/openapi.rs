@@ -0,0 +1,86 @@
+// The OpenAPI 3 description of the API, served at `GET /openapi.json` with Swagger UI at `/docs`.
+//
+// Nothing here is written by hand twice: the schemas come from `#[derive(ToSchema)]` on the very
+// structs the handlers (de)serialize, and each operation from the `#[utoipa::path]` attribute
+// on its handler. Change a struct and the document changes with it. Adding a *new* handler
+// still means listing it in `paths(...)` below.
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::error::{ErrorBody, ErrorEnvelope};
+use crate::health::{self, HealthStatus};
+use crate::history::{self, PromptPage, PromptRecord};
+use crate::items;
+use crate::models::{
+    CreateItem, DeletedItems, Item, ItemBatch, ItemPage, SortField, SortOrder, TagCount, TagList,
+    UpdateItem,
+};
+use crate::prompt::{self, PromptRequest, PromptResponse};
+
+/// The generated document; `ApiDoc::openapi()` builds it.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "rust-simple-rest-api",
+        description = "A todo-item API with a Gemini prompt proxy"
+    ),
+    paths(
+        health::health,
+        items::list_items,
+        items::create_item,
+        items::create_items,
+        items::delete_items,
+        items::get_item,
+        items::replace_item,
+        items::update_item,
+        items::delete_item,
+        items::list_tags,
+        prompt::handle_prompt,
+        prompt::handle_prompt_stream,
+        history::list_prompts,
+        history::get_prompt,
+        history::delete_prompt,
+    ),
+    components(schemas(
+        Item,
+        CreateItem,
+        UpdateItem,
+        ItemPage,
+        ItemBatch<CreateItem>,
+        ItemBatch<Item>,
+        DeletedItems,
+        TagCount,
+        TagList,
+        SortField,
+        SortOrder,
+        PromptRequest,
+        PromptResponse,
+        PromptRecord,
+        PromptPage,
+        HealthStatus,
+        ErrorEnvelope,
+        ErrorBody,
+    )),
+    modifiers(&BearerAuth),
+    tags(
+        (name = "items", description = "Todo items"),
+        (name = "prompt", description = "Gemini prompts and their history"),
+        (name = "health", description = "Liveness"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the `bearer` security scheme that protected operations refer to.
+// `Modify` is utoipa's hook for editing the document after the derive has built it.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        // `get_or_insert_with`: take the existing components, or create them if there are none
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+        );
+    }
+}
@@ -0,0 +1,150 @@
+// The /prompt routes forward a prompt to Gemini through the rust-gemini-llm-client crate:
+// `/prompt` answers with the whole text, `/prompt/stream` with Server-Sent Events as it is generated.
+use std::convert::Infallible;
+use std::time::Instant;
+
+use axum::{
+    extract::State,
+    response::sse::{Event, KeepAlive, Sse},
+    Json,
+};
+use futures_util::{stream, Stream, StreamExt};
+use rust_gemini_llm_client::{GeminiError, StreamEvent};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::ToSchema;
+
+use crate::error::{ApiError, ApiJson, ErrorEnvelope, RequestContext};
+use crate::Db;
+
+#[derive(Deserialize, ToSchema)]
+pub struct PromptRequest {
+    pub prompt: String,
+    /// Optional per-call Gemini API key (the server's `GEMINI_API_KEY` otherwise)
+    pub api_key: Option<String>,
+}
+
+/// Body of a successful `POST /prompt`
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PromptResponse {
+    /// Gemini's whole answer
+    pub result: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/prompt",
+    tag = "prompt",
+    request_body = PromptRequest,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Gemini's answer", body = PromptResponse),
+        (status = 400, description = "No Gemini API key available", body = ErrorEnvelope),
+        (status = 401, description = "Missing or invalid API token", body = ErrorEnvelope),
+        (status = 422, description = "Invalid body, or the answer was blocked", body = ErrorEnvelope),
+        (status = 429, description = "Rate limited (see `Retry-After`)", body = ErrorEnvelope),
+        (status = 502, description = "Gemini failed", body = ErrorEnvelope),
+    )
+)]
+pub async fn handle_prompt(
+    State(db): State<Db>,
+    ApiJson(body): ApiJson<PromptRequest>,
+) -> Result<Json<PromptResponse>, ApiError> {
+    let started = Instant::now();
+    let client = db.gemini_client(body.api_key);
+    let model = client.as_ref().ok().map(|c| c.model().to_string());
+    let result = match client {
+        Ok(client) => client.generate_content(&body.prompt).await,
+        Err(e) => Err(e),
+    };
+
+    // Remember the call either way; failures are often the interesting ones
+    let outcome = result.as_deref().map_err(|e| e.to_string());
+    db.history_mut()
+        .await
+        .record(&body.prompt, model.as_deref(), outcome, started.elapsed());
+
+    match result {
+        Ok(result) => Ok(Json(PromptResponse { result })),
+        Err(e) => Err(gemini_error_to_api_error(e)),
+    }
+}
+
+/// `POST /prompt/stream`: the answer as Server-Sent Events.
+///
+/// Each text delta is a plain `data:` event; the stream ends with an `event: done` whose data is
+/// `{"finish_reason": ...}`, or an `event: error` carrying the usual error envelope if Gemini
+/// fails midway. When the client disconnects, axum drops the event stream, which drops the
+/// Gemini stream and with it the upstream HTTP request, so no quota is spent on an answer
+/// nobody reads.
+// 'impl Stream<...>': some stream type the compiler works out (the chain of adapters below),
+// so we don't have to spell out its very long name.
+#[utoipa::path(
+    post,
+    path = "/prompt/stream",
+    tag = "prompt",
+    request_body = PromptRequest,
+    security(("bearer" = [])),
+    responses(
+        (status = 200, description = "Server-Sent Events: text deltas, then `done` or `error`", content_type = "text/event-stream", body = String),
+        (status = 400, description = "No Gemini API key available", body = ErrorEnvelope),
+        (status = 401, description = "Missing or invalid API token", body = ErrorEnvelope),
+        (status = 429, description = "Rate limited (see `Retry-After`)", body = ErrorEnvelope),
+        (status = 502, description = "Gemini rejected the request", body = ErrorEnvelope),
+    )
+)]
+pub async fn handle_prompt_stream(
+    State(db): State<Db>,
+    ApiJson(body): ApiJson<PromptRequest>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    let client = db
+        .gemini_client(body.api_key)
+        .map_err(gemini_error_to_api_error)?;
+    let mut upstream = client.generate_content_stream(body.prompt.as_str());
+
+    // Wait for the first event before answering: if Gemini rejects the request outright, the
+    // caller gets a real HTTP status (400, 429, 502...) instead of a 200 followed by an error event.
+    let first = match upstream.next().await {
+        Some(Err(e)) => return Err(gemini_error_to_api_error(e)),
+        first => first,
+    };
+
+    // The stream outlives this handler (and the request-context task local), so grab the id now
+    let request_id = RequestContext::current().request_id;
+    // stream::iter(Option) yields the first event (if any), then the rest of the upstream
+    let events = stream::iter(first)
+        .chain(upstream)
+        .map(move |item| Ok(sse_event(item, &request_id)));
+
+    // Keep-alive comments stop proxies from closing the connection during a slow answer
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Turn one item of the Gemini stream into an SSE event.
+fn sse_event(item: Result<StreamEvent, GeminiError>, request_id: &str) -> Event {
+    match item {
+        Ok(StreamEvent::Text(text)) => Event::default().data(text),
+        Ok(StreamEvent::Done(result)) => Event::default()
+            .event("done")
+            .data(json!({ "finish_reason": result.finish_reason }).to_string()),
+        Err(e) => Event::default()
+            .event("error")
+            .data(gemini_error_to_api_error(e).to_json(request_id).to_string()),
+    }
+}
+
+/// Map a typed Gemini failure onto the HTTP status that best describes it to our caller.
+pub fn gemini_error_to_api_error(error: GeminiError) -> ApiError {
+    match error {
+        // No key on the server and none in the request: the caller must supply one
+        GeminiError::MissingApiKey => ApiError::BadRequest(error.to_string()),
+        // Pass Gemini's back-pressure on to the caller instead of a blanket 500
+        GeminiError::RateLimited { retry_after } => ApiError::RateLimited {
+            message: error.to_string(),
+            retry_after_secs: retry_after,
+        },
+        GeminiError::Blocked { .. } => ApiError::validation(error.to_string()),
+        // Gemini (or reaching it) failed: that's an upstream problem, not ours
+        _ => ApiError::Upstream(error.to_string()),
+    }
+}
@@ -0,0 +1,239 @@
+// Per-client rate limiting for the routes that spend Gemini quota (`/prompt`, `/prompt/stream`).
+//
+// Token bucket: every client (by IP address) has a bucket holding up to `requests` tokens.
+// A request takes one token; tokens flow back in steadily, `requests` per `period`. So a client
+// can burst up to the full bucket, then continues at the refill rate. An empty bucket is a 429
+// telling the client how long until the next token arrives.
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+
+use crate::error::ApiError;
+use crate::Db;
+
+/// Prompt requests per minute per client when `PROMPT_RATE_PER_MIN` is not set
+pub const DEFAULT_PROMPT_RATE_PER_MIN: u32 = 30;
+
+/// How often idle buckets are looked for and dropped
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How many requests a client may make, and whether to believe `X-Forwarded-For`.
+///
+/// `RateLimitConfig::default()` is *disabled* (no limit), which is what `AppState::default()`
+/// and the tests use; the server builds it with `from_env`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// Bucket size, and tokens added back per `period`. 0 turns the limit off.
+    pub requests: u32,
+    pub period: Duration,
+    /// Key clients by the last `X-Forwarded-For` address instead of the socket address.
+    /// Only turn this on behind a proxy that sets the header, or anyone can pick their own key.
+    pub trust_proxy: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests: 0,
+            period: Duration::from_secs(60),
+            trust_proxy: false,
+        }
+    }
+}
+
+impl RateLimitConfig {
+    /// `requests` per minute per client
+    pub fn per_minute(requests: u32) -> Self {
+        Self {
+            requests,
+            ..Self::default()
+        }
+    }
+
+    /// `PROMPT_RATE_PER_MIN` (default 30, `0` for no limit) and `TRUST_PROXY` (`true`/`1`/`yes`).
+    pub fn from_env() -> Result<Self, String> {
+        let requests = match std::env::var("PROMPT_RATE_PER_MIN") {
+            Ok(value) => value
+                .trim()
+                .parse()
+                .map_err(|_| format!("PROMPT_RATE_PER_MIN `{}` is not a whole number", value))?,
+            Err(_) => DEFAULT_PROMPT_RATE_PER_MIN,
+        };
+        let trust_proxy = std::env::var("TRUST_PROXY")
+            .map(|v| matches!(v.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        Ok(Self {
+            trust_proxy,
+            ..Self::per_minute(requests)
+        })
+    }
+
+    /// Whether requests are limited at all
+    pub fn is_enabled(&self) -> bool {
+        self.requests > 0 && !self.period.is_zero()
+    }
+}
+
+/// One client's bucket
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    /// Fractional, because tokens refill continuously
+    tokens: f64,
+    updated: Instant,
+}
+
+/// The buckets of every client seen recently, shared by all requests through `AppState`.
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    // std Mutex: held only for a little arithmetic, never across an `.await`
+    state: Mutex<Buckets>,
+}
+
+#[derive(Debug)]
+struct Buckets {
+    clients: HashMap<IpAddr, Bucket>,
+    last_pruned: Instant,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+impl RateLimiter {
+    /// A limiter with no clients seen yet
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(Buckets {
+                clients: HashMap::new(),
+                last_pruned: Instant::now(),
+            }),
+        }
+    }
+
+    /// The limits this limiter enforces
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Take a token for `client` now. `Err` holds how long until one is available.
+    pub fn check(&self, client: IpAddr) -> Result<(), Duration> {
+        self.check_at(client, Instant::now())
+    }
+
+    /// `check` as if the current time were `now` (lets tests step through time).
+    pub fn check_at(&self, client: IpAddr, now: Instant) -> Result<(), Duration> {
+        if !self.config.is_enabled() {
+            return Ok(());
+        }
+        let capacity = f64::from(self.config.requests);
+        let per_token = self.config.period.as_secs_f64() / capacity;
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if now.saturating_duration_since(state.last_pruned) >= PRUNE_INTERVAL {
+            self.prune_locked(&mut state, now);
+        }
+
+        // A new client starts with a full bucket
+        let bucket = state.clients.entry(client).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        // Refill for the time since the last visit, but never above the bucket size
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed / per_token).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) * per_token))
+        }
+    }
+
+    /// Drop the buckets that have refilled completely; forgetting them changes nothing,
+    /// since a client we don't know starts with a full bucket anyway.
+    pub fn prune(&self, now: Instant) {
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.prune_locked(&mut state, now);
+    }
+
+    fn prune_locked(&self, state: &mut Buckets, now: Instant) {
+        let full_after = self.config.period;
+        state
+            .clients
+            .retain(|_, bucket| now.saturating_duration_since(bucket.updated) < full_after);
+        state.last_pruned = now;
+    }
+
+    /// Number of clients currently tracked
+    pub fn tracked_clients(&self) -> usize {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clients
+            .len()
+    }
+}
+
+/// The address a request is counted against: the rightmost `X-Forwarded-For` entry when the
+/// proxy is trusted, else the socket's peer address.
+// The rightmost entry is the one our proxy appended; anything left of it came from the client
+// and could be made up. Without connection info (tests calling the router directly) every
+// request shares one "unspecified" key.
+pub fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>, trust_proxy: bool) -> IpAddr {
+    if trust_proxy {
+        let forwarded = headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|last| last.trim().parse().ok());
+        if let Some(ip) = forwarded {
+            return ip;
+        }
+    }
+    peer.map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |addr| addr.ip())
+}
+
+/// Middleware: a 429 with `Retry-After` once the client's bucket is empty.
+pub async fn limit(
+    State(db): State<Db>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let limiter = db.rate_limiter();
+    if limiter.config().is_enabled() {
+        // Present when the server was started with `into_make_service_with_connect_info`
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| *addr);
+        let client = client_ip(request.headers(), peer, limiter.config().trust_proxy);
+        if let Err(wait) = limiter.check(client) {
+            // Retry-After is in whole seconds; round up so the retry doesn't arrive too early
+            let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+            return Err(ApiError::RateLimited {
+                message: format!("Too many prompt requests; try again in {} s", secs.max(1)),
+                retry_after_secs: Some(secs.max(1)),
+            });
+        }
+    }
+    Ok(next.run(request).await)
+}
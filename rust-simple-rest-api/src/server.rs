@@ -0,0 +1,139 @@
+// Running the router on a socket: where to listen (from env vars) and how to stop cleanly.
+//
+// A graceful shutdown stops accepting new connections when SIGTERM/SIGINT arrives but lets
+// requests that are already running finish, so `docker stop` doesn't cut off a half-written
+// response. Requests still running after the grace period are dropped so the process exits.
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::Router;
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+
+/// Where and how to serve, read from the environment by `from_env`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// `BIND_ADDR`, default `0.0.0.0` (all interfaces, required inside containers)
+    pub bind_addr: String,
+    /// `PORT`, default 3000
+    pub port: u16,
+    /// `SHUTDOWN_GRACE_SECS`, default 10: how long in-flight requests may run after a shutdown signal
+    pub shutdown_grace: Duration,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: "0.0.0.0".to_string(),
+            port: 3000,
+            shutdown_grace: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Read `BIND_ADDR`, `PORT` and `SHUTDOWN_GRACE_SECS`; unset variables keep their defaults.
+    pub fn from_env() -> Result<Self, String> {
+        let mut config = Self::default();
+        if let Ok(addr) = std::env::var("BIND_ADDR") {
+            config.bind_addr = addr;
+        }
+        if let Ok(port) = std::env::var("PORT") {
+            config.port = port
+                .trim()
+                .parse()
+                .map_err(|_| format!("PORT `{}` is not a port number", port))?;
+        }
+        if let Ok(secs) = std::env::var("SHUTDOWN_GRACE_SECS") {
+            let secs: u64 = secs.trim().parse().map_err(|_| {
+                format!("SHUTDOWN_GRACE_SECS `{}` is not a number of seconds", secs)
+            })?;
+            config.shutdown_grace = Duration::from_secs(secs);
+        }
+        Ok(config)
+    }
+
+    /// The `host:port` string to bind
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.bind_addr, self.port)
+    }
+}
+
+/// Resolves when the process receives Ctrl+C (SIGINT) or, on Unix, SIGTERM (what `docker stop` sends).
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            // Without a signal handler we can't be told to stop; wait forever instead of exiting now
+            std::future::pending::<()>().await;
+        }
+    };
+
+    // #[cfg(unix)]: SIGTERM only exists on Unix-like systems; Windows just uses Ctrl+C
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    // Whichever signal comes first wins
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("shutdown signal received, draining in-flight requests");
+}
+
+/// Serve `router` on `listener` until `shutdown` resolves, then give in-flight requests up to
+/// `grace` to finish before returning. Returning ends `main`, and with it the process,
+/// which drops any request still running.
+pub async fn serve(
+    listener: TcpListener,
+    router: Router,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+    grace: Duration,
+) -> io::Result<()> {
+    // Notify is a one-shot "doorbell" between tasks: the shutdown future rings it, and the
+    // drain timer below starts when it hears it. A ring before anyone listens is remembered.
+    let draining = Arc::new(Notify::new());
+    let ring = draining.clone();
+    // `with_connect_info` makes each client's socket address available to handlers and
+    // middleware (the rate limiter keys on it)
+    let server = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown.await;
+        ring.notify_one();
+    });
+    // The server's future is awaited in two places below, so it is pinned: it may not move
+    // in memory once polled (`IntoFuture` turns the builder into the actual future first).
+    let server = std::future::IntoFuture::into_future(server);
+    tokio::pin!(server);
+
+    tokio::select! {
+        result = &mut server => return result,
+        _ = draining.notified() => {}
+    }
+
+    // Shutdown has started: the listener is closed and only running requests remain
+    match tokio::time::timeout(grace, server).await {
+        Ok(result) => result,
+        Err(_) => {
+            tracing::warn!(
+                "requests still running after {:?}; shutting down anyway",
+                grace
+            );
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,188 @@
+// The shared application state handed to every handler through axum's `State` extractor.
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rust_gemini_llm_client::{GeminiClient, GeminiError};
+use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::auth::AuthConfig;
+use crate::error::ApiError;
+use crate::history::PromptHistory;
+use crate::metrics::Metrics;
+use crate::rate_limit::{RateLimitConfig, RateLimiter};
+use crate::storage::{ItemStore, MemoryStorage, Storage};
+
+/// Items in memory (the read cache) plus the storage they are written through to,
+/// the prompt history, request metrics, and the settings for authentication, rate limiting,
+/// and reaching Gemini.
+pub struct AppState {
+    items: RwLock<ItemStore>,
+    // 'Box<dyn Storage>': any type implementing the trait, chosen at runtime
+    // (like holding a Java interface reference).
+    storage: Box<dyn Storage>,
+    // A separate lock: recording a prompt never waits for item writers (or the other way round)
+    history: RwLock<PromptHistory>,
+    auth: AuthConfig,
+    // Token buckets for the prompt routes (disabled by default)
+    rate_limiter: RateLimiter,
+    // When this state was created, i.e. roughly when the server started
+    started_at: Instant,
+    // Counts committed item changes (see `revision`). An atomic integer can be read and
+    // bumped from many threads without a lock, like Java's AtomicLong.
+    revision: AtomicU64,
+    // Unix time in milliseconds at startup (see `instance_id`)
+    instance_id: u64,
+    // None means Google's public endpoint
+    gemini_base_url: Option<String>,
+    metrics: Metrics,
+}
+
+impl Default for AppState {
+    /// Empty, in-memory only state (what the tests use)
+    fn default() -> Self {
+        Self {
+            items: RwLock::new(ItemStore::default()),
+            storage: Box::new(MemoryStorage),
+            history: RwLock::default(),
+            auth: AuthConfig::default(),
+            rate_limiter: RateLimiter::default(),
+            started_at: Instant::now(),
+            revision: AtomicU64::new(0),
+            instance_id: unix_millis(),
+            gemini_base_url: None,
+            metrics: Metrics::default(),
+        }
+    }
+}
+
+impl AppState {
+    /// State loaded from `storage`; every later mutation is saved back to it.
+    pub fn with_storage(storage: impl Storage + 'static) -> io::Result<Self> {
+        let items = storage.load()?;
+        Ok(Self {
+            items: RwLock::new(items),
+            storage: Box::new(storage),
+            history: RwLock::default(),
+            auth: AuthConfig::default(),
+            rate_limiter: RateLimiter::default(),
+            started_at: Instant::now(),
+            revision: AtomicU64::new(0),
+            instance_id: unix_millis(),
+            gemini_base_url: None,
+            metrics: Metrics::default(),
+        })
+    }
+
+    /// Require bearer tokens on protected routes (the default state has auth disabled).
+    pub fn with_auth(mut self, auth: AuthConfig) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// The authentication settings
+    pub fn auth(&self) -> &AuthConfig {
+        &self.auth
+    }
+
+    /// Limit how often each client may call the prompt routes (unlimited by default).
+    pub fn with_rate_limit(mut self, config: RateLimitConfig) -> Self {
+        self.rate_limiter = RateLimiter::new(config);
+        self
+    }
+
+    /// The prompt-route rate limiter
+    pub fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    /// Send Gemini requests to `url` instead of Google's API (a mock server in tests,
+    /// or a relay). Checked when a prompt is handled.
+    pub fn with_gemini_base_url(mut self, url: impl Into<String>) -> Self {
+        self.gemini_base_url = Some(url.into());
+        self
+    }
+
+    /// A Gemini client for one prompt request. `api_key` is the key sent with the request;
+    /// without one, `GEMINI_API_KEY` is used.
+    pub fn gemini_client(&self, api_key: Option<String>) -> Result<GeminiClient, GeminiError> {
+        let client = match api_key {
+            Some(key) => GeminiClient::new(key),
+            None => GeminiClient::from_env()?,
+        };
+        match &self.gemini_base_url {
+            Some(url) => client.with_base_url(url),
+            None => Ok(client),
+        }
+    }
+
+    /// How long ago the state (and so the server) started
+    pub fn uptime(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    /// Request counts and latencies (see metrics.rs)
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// How many item changes have been committed since startup. Read it while holding the
+    /// `items()` guard to get the revision of exactly the items you see.
+    pub fn revision(&self) -> u64 {
+        // Relaxed is enough: the items lock already orders the bump against readers
+        self.revision.load(Ordering::Relaxed)
+    }
+
+    /// Differs between runs of the server, so revision 3 of this run can't be mistaken for
+    /// revision 3 of the previous one (the counter starts from 0 on every start).
+    pub fn instance_id(&self) -> u64 {
+        self.instance_id
+    }
+
+    /// Read access to the items. Holding the guard blocks writers, so keep it short.
+    pub async fn items(&self) -> RwLockReadGuard<'_, ItemStore> {
+        self.items.read().await
+    }
+
+    /// Read access to the prompt history
+    pub async fn history(&self) -> RwLockReadGuard<'_, PromptHistory> {
+        self.history.read().await
+    }
+
+    /// Write access to the prompt history (to record or delete entries)
+    pub async fn history_mut(&self) -> RwLockWriteGuard<'_, PromptHistory> {
+        self.history.write().await
+    }
+
+    /// Change the items under the write lock and persist the result.
+    ///
+    /// `change` works on a copy of the map. Only if it succeeds *and* the copy was saved
+    /// does the copy replace the in-memory map, so memory and disk never disagree: a
+    /// failed save is a 500 and the change is not visible to anyone.
+    // 'FnOnce': a closure called exactly once (it may move values out of its captures).
+    // Copying the map on every write is O(n); fine for a todo list of a few thousand items.
+    pub async fn mutate<T>(
+        &self,
+        change: impl FnOnce(&mut ItemStore) -> Result<T, ApiError>,
+    ) -> Result<T, ApiError> {
+        let mut items = self.items.write().await;
+        let mut draft = items.clone();
+        let result = change(&mut draft)?;
+        // The save runs while the write lock is held, so saves happen in the same order as
+        // the changes they record. It is blocking file IO, acceptable for a small JSON file.
+        self.storage.save(&draft).map_err(|e| {
+            tracing::error!("failed to save items: {}", e);
+            ApiError::Internal("Failed to save items".to_string())
+        })?;
+        *items = draft;
+        // Still under the write lock, so no reader sees the new items with the old revision
+        self.revision.fetch_add(1, Ordering::Relaxed);
+        Ok(result)
+    }
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
@@ -0,0 +1,141 @@
+// Where items live between restarts. The handlers never talk to a file directly: they go
+// through the `Storage` trait (think of a Java interface / repository), so the backend can be
+// swapped (JSON file today, SQLite tomorrow) and tests can use one that never touches disk.
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::Item;
+
+/// The items by id, plus the id the next new item gets.
+///
+/// Ids only go up: deleting the newest item doesn't hand its id out again. Otherwise a new
+/// item would reuse both the old id and version 1, and a client's cached `ETag` or stale
+/// `If-Match` for the deleted item would match the new one.
+#[derive(Debug, Clone)]
+pub struct ItemStore {
+    pub items: HashMap<u64, Item>,
+    pub next_id: u64,
+}
+
+impl ItemStore {
+    /// A store holding `items`; new ids continue after the highest of them
+    pub fn new(items: HashMap<u64, Item>) -> Self {
+        let next_id = items.keys().max().map_or(1, |max| max + 1);
+        Self { items, next_id }
+    }
+}
+
+impl Default for ItemStore {
+    /// No items; the first gets id 1
+    fn default() -> Self {
+        Self::new(HashMap::new())
+    }
+}
+
+/// A persistence backend for the item store.
+///
+/// The in-memory store in `AppState` stays the source for reads; `save` is called with
+/// the complete new store after every mutation (write-through).
+// 'Send + Sync': the storage is shared by every request handler across threads.
+pub trait Storage: Send + Sync {
+    /// Everything stored so far (empty the first time)
+    fn load(&self) -> io::Result<ItemStore>;
+    /// Replace what is stored with `store`
+    fn save(&self, store: &ItemStore) -> io::Result<()>;
+}
+
+/// Keeps nothing: items disappear when the process exits. Used by `Db::default()`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemoryStorage;
+
+impl Storage for MemoryStorage {
+    fn load(&self) -> io::Result<ItemStore> {
+        Ok(ItemStore::default())
+    }
+
+    fn save(&self, _store: &ItemStore) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// The file's layout: `{"next_id": 4, "items": [...]}`. Files from before `next_id` existed
+// are a bare array of items; `untagged` lets serde try each shape in turn.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredFile {
+    Store { next_id: u64, items: Vec<Item> },
+    Items(Vec<Item>),
+}
+
+#[derive(Serialize)]
+struct StoredFileRef<'a> {
+    next_id: u64,
+    items: Vec<&'a Item>,
+}
+
+/// Stores all items (sorted by id) and the next id as one JSON object in a file.
+#[derive(Debug, Clone)]
+pub struct JsonFileStorage {
+    path: PathBuf,
+}
+
+impl JsonFileStorage {
+    /// Storage backed by the file at `path`. Nothing is read or created until first use.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// The file this storage reads and writes
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Storage for JsonFileStorage {
+    fn load(&self) -> io::Result<ItemStore> {
+        let json = match fs::read_to_string(&self.path) {
+            Ok(json) => json,
+            // No file yet means no items yet, not an error
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(ItemStore::default()),
+            Err(e) => return Err(e),
+        };
+        // A corrupt file *is* an error: starting empty would overwrite it on the next save
+        let stored: StoredFile = serde_json::from_str(&json)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let by_id = |items: Vec<Item>| items.into_iter().map(|item| (item.id, item)).collect();
+        Ok(match stored {
+            StoredFile::Store { next_id, items } => {
+                let mut store = ItemStore::new(by_id(items));
+                // Never below the items themselves, even if the file was edited by hand
+                store.next_id = store.next_id.max(next_id);
+                store
+            }
+            StoredFile::Items(items) => ItemStore::new(by_id(items)),
+        })
+    }
+
+    fn save(&self, store: &ItemStore) -> io::Result<()> {
+        // Sort so the file is stable and diff-friendly (HashMap order is random)
+        let mut items: Vec<&Item> = store.items.values().collect();
+        items.sort_by_key(|item| item.id);
+        let json = serde_json::to_string_pretty(&StoredFileRef {
+            next_id: store.next_id,
+            items,
+        })?;
+
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        // Write a temporary file next to the real one, then rename it over the original.
+        // A rename is atomic, so a crash mid-write leaves the previous file intact instead
+        // of a half-written one.
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
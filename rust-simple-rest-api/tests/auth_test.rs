@@ -0,0 +1,149 @@
+//! Bearer-token middleware: valid, missing and wrong tokens, and which routes are protected.
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, HeaderMap, HeaderValue, Method, Request, StatusCode},
+    Router,
+};
+use rust_simple_rest_api::{app, auth::bearer_token, AppState, AuthConfig, Db};
+use serde_json::Value;
+use tower::ServiceExt;
+
+const TOKEN: &str = "secret-token";
+
+fn secured() -> Router {
+    app(Arc::new(
+        AppState::default().with_auth(AuthConfig::parse("other-token, secret-token")),
+    ))
+}
+
+fn request(method: Method, uri: &str, token: Option<&str>) -> Request<Body> {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(token) = token {
+        builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+    }
+    builder.body(Body::from(r#"{"name": "milk"}"#)).unwrap()
+}
+
+async fn status(router: &Router, request: Request<Body>) -> StatusCode {
+    router.clone().oneshot(request).await.unwrap().status()
+}
+
+#[tokio::test]
+async fn valid_token_is_accepted() {
+    let router = secured();
+    assert_eq!(
+        status(&router, request(Method::POST, "/items", Some(TOKEN))).await,
+        StatusCode::CREATED
+    );
+    assert_eq!(
+        status(&router, request(Method::PUT, "/items/1", Some(TOKEN))).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(&router, request(Method::DELETE, "/items/1", Some(TOKEN))).await,
+        StatusCode::NO_CONTENT
+    );
+}
+
+#[tokio::test]
+async fn missing_token_is_a_structured_401() {
+    let response = secured()
+        .oneshot(request(Method::POST, "/items", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(response.headers()[header::WWW_AUTHENTICATE], "Bearer");
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"]["code"], "unauthorized");
+    assert!(body["error"]["request_id"].is_string());
+}
+
+#[tokio::test]
+async fn wrong_token_is_rejected() {
+    let router = secured();
+    for uri in ["/items", "/prompt", "/prompt/stream"] {
+        assert_eq!(
+            status(&router, request(Method::POST, uri, Some("nope"))).await,
+            StatusCode::UNAUTHORIZED,
+            "POST {}",
+            uri
+        );
+    }
+    // The mutation did not happen
+    let page = router
+        .oneshot(request(Method::GET, "/items", None))
+        .await
+        .unwrap();
+    let bytes = to_bytes(page.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(serde_json::from_slice::<Value>(&bytes).unwrap()["total"], 0);
+}
+
+#[tokio::test]
+async fn reads_and_health_stay_open() {
+    let router = secured();
+    assert_eq!(
+        status(&router, request(Method::GET, "/items", None)).await,
+        StatusCode::OK
+    );
+    assert_eq!(
+        status(&router, request(Method::GET, "/items/1", None)).await,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        status(&router, request(Method::GET, "/health", None)).await,
+        StatusCode::OK
+    );
+    // Prompt history holds other people's prompts, so it is protected too
+    assert_eq!(
+        status(&router, request(Method::GET, "/prompts", None)).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn disabled_auth_lets_everything_through() {
+    let router = app(Db::default());
+    assert_eq!(
+        status(&router, request(Method::POST, "/items", None)).await,
+        StatusCode::CREATED
+    );
+}
+
+#[test]
+fn token_list_parsing() {
+    let auth = AuthConfig::parse(" a , b,,c ");
+    assert!(auth.is_enabled());
+    assert_eq!(auth.token_count(), 3);
+    assert!(auth.allows("a") && auth.allows("b") && auth.allows("c"));
+    assert!(!auth.allows("") && !auth.allows("ab"));
+
+    // No tokens at all: enabled, but nothing is accepted
+    let empty = AuthConfig::parse("");
+    assert!(empty.is_enabled());
+    assert!(!empty.allows(""));
+
+    assert!(!AuthConfig::disabled().is_enabled());
+}
+
+#[test]
+fn bearer_header_parsing() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(bearer_token(&headers), None);
+
+    headers.insert(
+        header::AUTHORIZATION,
+        HeaderValue::from_static("bearer abc"),
+    );
+    assert_eq!(bearer_token(&headers), Some("abc"));
+    headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Basic abc"));
+    assert_eq!(bearer_token(&headers), None);
+    headers.insert(header::AUTHORIZATION, HeaderValue::from_static("Bearer "));
+    assert_eq!(bearer_token(&headers), None);
+}
@@ -0,0 +1,196 @@
+//! Bulk operations: `POST /items/batch` is all-or-nothing, `DELETE /items?completed=` removes matches.
+
+use std::io;
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use rust_simple_rest_api::{app, AppState, Db, ItemStore, Storage, MAX_BATCH_SIZE};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+async fn send(router: &Router, method: &str, uri: &str, body: Value) -> Response {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    router.clone().oneshot(request).await.unwrap()
+}
+
+async fn body_json(response: Response) -> Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn total(router: &Router) -> u64 {
+    let page = body_json(send(router, "GET", "/items?limit=500", Value::Null).await).await;
+    page["total"].as_u64().unwrap()
+}
+
+fn batch(names: &[&str]) -> Value {
+    let items: Vec<Value> = names.iter().map(|name| json!({ "name": name })).collect();
+    json!({ "items": items })
+}
+
+#[tokio::test]
+async fn batch_creates_every_item_with_ids() {
+    let router = app(Db::default());
+    send(&router, "POST", "/items", json!({ "name": "first" })).await;
+
+    let response = send(
+        &router,
+        "POST",
+        "/items/batch",
+        batch(&["milk", "eggs", "bread"]),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created = body_json(response).await;
+    let items = created["items"].as_array().unwrap();
+    let ids: Vec<u64> = items
+        .iter()
+        .map(|item| item["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, [2, 3, 4]);
+    assert_eq!(items[1]["name"], "eggs");
+    assert_eq!(items[1]["version"], 1);
+    assert_eq!(total(&router).await, 4);
+}
+
+#[tokio::test]
+async fn one_invalid_entry_rejects_the_whole_batch() {
+    let router = app(Db::default());
+
+    let response = send(
+        &router,
+        "POST",
+        "/items/batch",
+        batch(&["milk", "eggs", "  ", "bread"]),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["error"]["code"], "validation_error");
+    assert_eq!(body["error"]["details"]["index"], 2);
+    assert!(body["error"]["request_id"].is_string());
+
+    // A malformed entry (missing `name`) is rejected before anything happens, too
+    let response = send(
+        &router,
+        "POST",
+        "/items/batch",
+        json!({ "items": [{ "name": "milk" }, {}] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Neither batch created anything
+    assert_eq!(total(&router).await, 0);
+}
+
+#[tokio::test]
+async fn batch_size_is_capped() {
+    let router = app(Db::default());
+    let names: Vec<String> = (0..=MAX_BATCH_SIZE)
+        .map(|i| format!("item {}", i))
+        .collect();
+    let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+    let response = send(&router, "POST", "/items/batch", batch(&names)).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["error"]["details"]["max_items"], MAX_BATCH_SIZE);
+    assert_eq!(body["error"]["details"]["received"], MAX_BATCH_SIZE + 1);
+
+    let response = send(&router, "POST", "/items/batch", batch(&[])).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Exactly the maximum is fine
+    let response = send(
+        &router,
+        "POST",
+        "/items/batch",
+        batch(&names[..MAX_BATCH_SIZE]),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(total(&router).await, MAX_BATCH_SIZE as u64);
+}
+
+/// Storage that can never save
+struct BrokenStorage;
+
+impl Storage for BrokenStorage {
+    fn load(&self) -> io::Result<ItemStore> {
+        Ok(ItemStore::default())
+    }
+
+    fn save(&self, _store: &ItemStore) -> io::Result<()> {
+        Err(io::Error::other("disk full"))
+    }
+}
+
+#[tokio::test]
+async fn failed_save_keeps_none_of_the_batch() {
+    let router = app(Arc::new(AppState::with_storage(BrokenStorage).unwrap()));
+    let response = send(&router, "POST", "/items/batch", batch(&["milk", "eggs"])).await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(total(&router).await, 0);
+}
+
+#[tokio::test]
+async fn delete_completed_items() {
+    let router = app(Db::default());
+    send(
+        &router,
+        "POST",
+        "/items/batch",
+        batch(&["a", "b", "c", "d"]),
+    )
+    .await;
+    for id in [1, 3] {
+        send(
+            &router,
+            "PATCH",
+            &format!("/items/{}", id),
+            json!({ "completed": true }),
+        )
+        .await;
+    }
+
+    let response = send(&router, "DELETE", "/items?completed=true", Value::Null).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["deleted"], 2);
+
+    let page = body_json(send(&router, "GET", "/items", Value::Null).await).await;
+    let names: Vec<&str> = page["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["b", "d"]);
+
+    // Nothing left to delete is not an error
+    let response = send(&router, "DELETE", "/items?completed=true", Value::Null).await;
+    assert_eq!(body_json(response).await["deleted"], 0);
+}
+
+#[tokio::test]
+async fn bulk_delete_needs_a_filter() {
+    let router = app(Db::default());
+    send(&router, "POST", "/items/batch", batch(&["a"])).await;
+
+    for uri in ["/items", "/items?completed=maybe"] {
+        let response = send(&router, "DELETE", uri, Value::Null).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "DELETE {}", uri);
+        assert_eq!(body_json(response).await["error"]["code"], "bad_request");
+    }
+    assert_eq!(total(&router).await, 1);
+}
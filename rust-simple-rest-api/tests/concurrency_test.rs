@@ -0,0 +1,79 @@
+//! Many requests hitting the shared `Db` at once: no deadlock, no duplicate ids.
+
+use std::collections::HashSet;
+use std::time::Duration;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    Router,
+};
+use rust_simple_rest_api::{app, Db, Item, ItemPage};
+use tower::ServiceExt;
+
+const TASKS: usize = 100;
+
+async fn create(router: Router, name: String) -> Item {
+    let request = Request::post("/items")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serde_json::json!({ "name": name }).to_string()))
+        .unwrap();
+    let response = router.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn list(router: Router) -> Vec<Item> {
+    let response = router
+        .oneshot(
+            Request::get("/items?limit=500")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice::<ItemPage>(&bytes).unwrap().items
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_creates_and_reads_get_unique_ids() {
+    let router = app(Db::default());
+
+    let mut creates = Vec::new();
+    let mut reads = Vec::new();
+    for i in 0..TASKS {
+        creates.push(tokio::spawn(create(router.clone(), format!("item {}", i))));
+        reads.push(tokio::spawn(list(router.clone())));
+    }
+
+    // A deadlock would hang here forever; the timeout turns it into a failure
+    let all = async {
+        let mut created = Vec::new();
+        for task in creates {
+            created.push(task.await.unwrap());
+        }
+        for task in reads {
+            // Readers see some consistent snapshot: never more items than were created
+            assert!(task.await.unwrap().len() <= TASKS);
+        }
+        created
+    };
+    let created = tokio::time::timeout(Duration::from_secs(10), all)
+        .await
+        .expect("requests deadlocked");
+
+    let ids: HashSet<u64> = created.iter().map(|item| item.id).collect();
+    assert_eq!(ids.len(), TASKS, "every create got its own id");
+    assert_eq!(ids, (1..=TASKS as u64).collect());
+
+    let stored = list(router).await;
+    assert_eq!(stored.len(), TASKS);
+    for item in &created {
+        assert!(stored
+            .iter()
+            .any(|s| s.id == item.id && s.name == item.name));
+    }
+}
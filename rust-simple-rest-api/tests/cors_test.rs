@@ -0,0 +1,40 @@
+//! Cross-origin requests: a browser's preflight must allow the headers this API relies on.
+
+use axum::{
+    body::Body,
+    http::{header, Method, Request, StatusCode},
+};
+use rust_simple_rest_api::{app, Db};
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn preflight_allows_the_bearer_token_and_preconditions() {
+    let request = Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/items/1")
+        .header(header::ORIGIN, "https://example.com")
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PATCH")
+        .header(
+            header::ACCESS_CONTROL_REQUEST_HEADERS,
+            "authorization,content-type,if-match",
+        )
+        .body(Body::empty())
+        .unwrap();
+    let response = app(Db::default()).oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // A wildcard would not do: browsers never let `*` stand for `Authorization`
+    let allowed = response.headers()[header::ACCESS_CONTROL_ALLOW_HEADERS]
+        .to_str()
+        .unwrap()
+        .to_string();
+    for name in [
+        "authorization",
+        "content-type",
+        "if-match",
+        "if-none-match",
+        "x-request-id",
+    ] {
+        assert!(allowed.contains(name), "{} missing from {}", name, allowed);
+    }
+}
@@ -0,0 +1,136 @@
+//! The whole life of an item through the router: create, read, list, update, delete, then 404s.
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use rust_simple_rest_api::{app, Db, Item, ItemPage};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+async fn send(router: &Router, method: &str, uri: &str, body: Option<Value>) -> Response {
+    let builder = Request::builder().method(method).uri(uri);
+    let request = match body {
+        Some(body) => builder
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string())),
+        None => builder.body(Body::empty()),
+    };
+    router.clone().oneshot(request.unwrap()).await.unwrap()
+}
+
+/// Assert the status, then decode the body as `T`
+async fn expect<T: DeserializeOwned>(response: Response, status: StatusCode) -> T {
+    assert_eq!(response.status(), status);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn item_lifecycle() {
+    let router = app(Db::default());
+
+    // Create
+    let milk: Item = expect(
+        send(&router, "POST", "/items", Some(json!({ "name": "milk" }))).await,
+        StatusCode::CREATED,
+    )
+    .await;
+    assert_eq!(
+        (milk.id, milk.name.as_str(), milk.completed),
+        (1, "milk", false)
+    );
+    let eggs: Item = expect(
+        send(&router, "POST", "/items", Some(json!({ "name": "eggs" }))).await,
+        StatusCode::CREATED,
+    )
+    .await;
+    assert_eq!(eggs.id, 2);
+
+    // Read one, and the list
+    let fetched: Item = expect(send(&router, "GET", "/items/1", None).await, StatusCode::OK).await;
+    assert_eq!(fetched.name, "milk");
+    let page: ItemPage = expect(send(&router, "GET", "/items", None).await, StatusCode::OK).await;
+    assert_eq!(page.total, 2);
+    let names: Vec<&str> = page.items.iter().map(|item| item.name.as_str()).collect();
+    assert_eq!(names, ["milk", "eggs"]);
+
+    // Update part of it, then all of it
+    let patched: Item = expect(
+        send(
+            &router,
+            "PATCH",
+            "/items/1",
+            Some(json!({ "completed": true })),
+        )
+        .await,
+        StatusCode::OK,
+    )
+    .await;
+    assert_eq!((patched.name.as_str(), patched.completed), ("milk", true));
+    let replaced: Item = expect(
+        send(
+            &router,
+            "PUT",
+            "/items/1",
+            Some(json!({ "name": "oat milk", "completed": true })),
+        )
+        .await,
+        StatusCode::OK,
+    )
+    .await;
+    assert_eq!(replaced.name, "oat milk");
+    let fetched: Item = expect(send(&router, "GET", "/items/1", None).await, StatusCode::OK).await;
+    assert_eq!(fetched.name, "oat milk");
+
+    // Delete
+    let response = send(&router, "DELETE", "/items/1", None).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let page: ItemPage = expect(send(&router, "GET", "/items", None).await, StatusCode::OK).await;
+    assert_eq!(page.total, 1);
+    assert_eq!(page.items[0].name, "eggs");
+
+    // Everything about the deleted item is now a 404
+    for (method, body) in [
+        ("GET", None),
+        ("PATCH", Some(json!({ "completed": false }))),
+        ("PUT", Some(json!({ "name": "milk" }))),
+        ("DELETE", None),
+    ] {
+        let error: Value = expect(
+            send(&router, method, "/items/1", body).await,
+            StatusCode::NOT_FOUND,
+        )
+        .await;
+        assert_eq!(error["error"]["code"], "not_found", "{} /items/1", method);
+    }
+}
+
+#[tokio::test]
+async fn ids_are_not_reused_while_higher_ones_exist() {
+    let router = app(Db::default());
+    for name in ["a", "b", "c"] {
+        send(&router, "POST", "/items", Some(json!({ "name": name }))).await;
+    }
+    send(&router, "DELETE", "/items/2", None).await;
+    let item: Item = expect(
+        send(&router, "POST", "/items", Some(json!({ "name": "d" }))).await,
+        StatusCode::CREATED,
+    )
+    .await;
+    assert_eq!(item.id, 4);
+}
+
+#[tokio::test]
+async fn non_numeric_id_is_a_400() {
+    let router = app(Db::default());
+    let error: Value = expect(
+        send(&router, "GET", "/items/abc", None).await,
+        StatusCode::BAD_REQUEST,
+    )
+    .await;
+    assert_eq!(error["error"]["code"], "bad_request");
+}
@@ -0,0 +1,117 @@
+//! Error envelope tests: every failure must come back as
+//! `{"error": {"code", "message", "details?", "request_id"}}` (or plain text when asked).
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    middleware,
+    response::Response,
+    routing::get,
+    Router,
+};
+use rust_simple_rest_api::{app, error, ApiError, Db};
+use serde_json::Value;
+use tower::ServiceExt; // for `oneshot`
+
+async fn body_json(response: Response) -> Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).expect("error body should be JSON")
+}
+
+#[tokio::test]
+async fn missing_item_returns_structured_404() {
+    let response = app(Db::default())
+        .oneshot(Request::get("/items/42").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let request_id = response.headers()[error::REQUEST_ID_HEADER]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let body = body_json(response).await;
+    assert_eq!(body["error"]["code"], "not_found");
+    assert_eq!(body["error"]["message"], "Item 42 not found");
+    assert_eq!(body["error"]["request_id"], request_id);
+    assert!(body["error"].get("details").is_none());
+}
+
+#[tokio::test]
+async fn unknown_route_returns_structured_404() {
+    let response = app(Db::default())
+        .oneshot(Request::get("/nope").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(body_json(response).await["error"]["code"], "not_found");
+}
+
+#[tokio::test]
+async fn invalid_body_returns_structured_422() {
+    let request = Request::post("/items")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(error::REQUEST_ID_HEADER, "test-123")
+        .body(Body::from(r#"{"title": "no name field"}"#))
+        .unwrap();
+    let response = app(Db::default()).oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = body_json(response).await;
+    assert_eq!(body["error"]["code"], "validation_error");
+    // A client-supplied request id is reused
+    assert_eq!(body["error"]["request_id"], "test-123");
+    assert!(body["error"]["details"]["reason"]
+        .as_str()
+        .unwrap()
+        .contains("name"));
+}
+
+fn failing_router() -> Router {
+    Router::new()
+        .route(
+            "/boom",
+            get(|| async { Err::<(), _>(ApiError::Internal("forced failure".to_string())) }),
+        )
+        .layer(middleware::from_fn(error::request_context))
+}
+
+#[tokio::test]
+async fn internal_error_returns_structured_500() {
+    let response = failing_router()
+        .oneshot(Request::get("/boom").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    let body = body_json(response).await;
+    assert_eq!(body["error"]["code"], "internal_error");
+    assert_eq!(body["error"]["message"], "forced failure");
+    assert!(body["error"]["request_id"]
+        .as_str()
+        .unwrap()
+        .starts_with("req-"));
+}
+
+#[tokio::test]
+async fn plain_text_clients_get_text_errors() {
+    let request = Request::get("/boom")
+        .header(header::ACCEPT, "text/plain")
+        .body(Body::empty())
+        .unwrap();
+    let response = failing_router().oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(response.headers()[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(
+        text.starts_with("500 internal_error: forced failure (request_id: req-"),
+        "{}",
+        text
+    );
+}
@@ -0,0 +1,189 @@
+//! Conditional GETs: `ETag` on `/items` and `/items/:id`, `304` for a matching `If-None-Match`.
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use rust_simple_rest_api::{app, Db};
+use serde_json::json;
+use tower::ServiceExt;
+
+async fn get(router: &Router, uri: &str, if_none_match: Option<&str>) -> Response {
+    let mut request = Request::get(uri);
+    if let Some(etag) = if_none_match {
+        request = request.header(header::IF_NONE_MATCH, etag);
+    }
+    router
+        .clone()
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+async fn write(router: &Router, method: &str, uri: &str, body: serde_json::Value) -> StatusCode {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    router.clone().oneshot(request).await.unwrap().status()
+}
+
+fn etag_of(response: &Response) -> String {
+    let etag = response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(etag.starts_with("W/\""), "weak tag expected, got {}", etag);
+    etag
+}
+
+async fn assert_not_modified(response: Response, etag: &str) {
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(response.headers()[header::ETAG], etag);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(bytes.is_empty());
+}
+
+#[tokio::test]
+async fn unchanged_list_is_not_modified() {
+    let router = app(Db::default());
+    write(&router, "POST", "/items", json!({ "name": "milk" })).await;
+
+    let response = get(&router, "/items", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = etag_of(&response);
+
+    assert_not_modified(get(&router, "/items", Some(&etag)).await, &etag).await;
+    // Also inside a list of candidates, or compared weakly against a strong form
+    let listed = format!("W/\"nope\", {}", etag);
+    assert_eq!(
+        get(&router, "/items", Some(&listed)).await.status(),
+        StatusCode::NOT_MODIFIED
+    );
+    let strong = etag.trim_start_matches("W/").to_string();
+    assert_eq!(
+        get(&router, "/items", Some(&strong)).await.status(),
+        StatusCode::NOT_MODIFIED
+    );
+    // A different tag gets the full body
+    assert_eq!(
+        get(&router, "/items", Some("W/\"0-0\"")).await.status(),
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn mutation_invalidates_the_list_etag() {
+    let router = app(Db::default());
+    let before = etag_of(&get(&router, "/items", None).await);
+
+    for (method, uri, body) in [
+        ("POST", "/items", json!({ "name": "milk" })),
+        ("PATCH", "/items/1", json!({ "completed": true })),
+        ("DELETE", "/items/1", json!(null)),
+    ] {
+        assert!(
+            write(&router, method, uri, body).await.is_success(),
+            "{} {}",
+            method,
+            uri
+        );
+        let response = get(&router, "/items", Some(&before)).await;
+        assert_eq!(
+            response.status(),
+            StatusCode::OK,
+            "after {} {}",
+            method,
+            uri
+        );
+        assert_ne!(etag_of(&response), before);
+    }
+
+    // A rejected change leaves the tag alone
+    let current = etag_of(&get(&router, "/items", None).await);
+    assert_eq!(
+        write(&router, "PATCH", "/items/99", json!({ "completed": true })).await,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        get(&router, "/items", Some(&current)).await.status(),
+        StatusCode::NOT_MODIFIED
+    );
+}
+
+#[tokio::test]
+async fn item_etag_follows_its_version() {
+    let router = app(Db::default());
+    write(&router, "POST", "/items", json!({ "name": "milk" })).await;
+    write(&router, "POST", "/items", json!({ "name": "eggs" })).await;
+
+    let response = get(&router, "/items/1", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = etag_of(&response);
+    assert_eq!(etag, "W/\"1\"");
+    assert_not_modified(get(&router, "/items/1", Some(&etag)).await, &etag).await;
+
+    // Changing another item doesn't affect this one's tag
+    write(&router, "PATCH", "/items/2", json!({ "completed": true })).await;
+    assert_eq!(
+        get(&router, "/items/1", Some(&etag)).await.status(),
+        StatusCode::NOT_MODIFIED
+    );
+
+    write(&router, "PATCH", "/items/1", json!({ "completed": true })).await;
+    let response = get(&router, "/items/1", Some(&etag)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let etag = etag_of(&response);
+    assert_eq!(etag, "W/\"2\"");
+
+    // The ETag doubles as an If-Match precondition
+    let request = Request::patch("/items/1")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::IF_MATCH, &etag)
+        .body(Body::from(r#"{"name": "oat milk"}"#))
+        .unwrap();
+    assert_eq!(
+        router.clone().oneshot(request).await.unwrap().status(),
+        StatusCode::OK
+    );
+
+    // Missing items are still a 404, whatever the client sends
+    assert_eq!(
+        get(&router, "/items/9", Some("*")).await.status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn the_highest_id_is_not_reused_after_a_delete() {
+    let router = app(Db::default());
+    write(&router, "POST", "/items", json!({ "name": "milk" })).await;
+    write(&router, "POST", "/items", json!({ "name": "eggs" })).await;
+    let stale_etag = etag_of(&get(&router, "/items/2", None).await);
+    write(&router, "DELETE", "/items/2", json!(null)).await;
+
+    // The next item gets id 3, not the deleted item's 2
+    write(&router, "POST", "/items", json!({ "name": "bread" })).await;
+    assert_eq!(
+        get(&router, "/items/3", None).await.status(),
+        StatusCode::OK
+    );
+
+    // The deleted item's tag describes nothing any more: no 304, and no write based on it
+    assert_eq!(
+        get(&router, "/items/2", Some(&stale_etag)).await.status(),
+        StatusCode::NOT_FOUND
+    );
+    let request = Request::delete("/items/2")
+        .header(header::IF_MATCH, &stale_etag)
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(
+        router.clone().oneshot(request).await.unwrap().status(),
+        StatusCode::NOT_FOUND
+    );
+}
@@ -0,0 +1,128 @@
+//! Prompt history: `/prompt` calls are recorded and can be listed, fetched, and deleted.
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use rust_simple_rest_api::{app, AppState};
+use serde_json::Value;
+use tower::ServiceExt;
+use wiremock::matchers::{body_string_contains, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const ANSWER: &str =
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Paris\"}]}, \"finishReason\": \"STOP\"}]}\n\n";
+
+async fn gemini_mock() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("capital"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(ANSWER))
+        .mount(&server)
+        .await;
+    // Anything else is rejected, so a prompt can fail on purpose
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("bad prompt"))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn send(router: &Router, request: Request<Body>) -> Response {
+    router.clone().oneshot(request).await.unwrap()
+}
+
+async fn body_json(response: Response) -> Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn prompt(router: &Router, text: &str) -> StatusCode {
+    let request = Request::post("/prompt")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(
+            serde_json::json!({ "prompt": text, "api_key": "test-key" }).to_string(),
+        ))
+        .unwrap();
+    send(router, request).await.status()
+}
+
+async fn get(router: &Router, uri: &str) -> Response {
+    send(router, Request::get(uri).body(Body::empty()).unwrap()).await
+}
+
+#[tokio::test]
+async fn successful_and_failed_prompts_are_recorded() {
+    let server = gemini_mock().await;
+    let router = app(Arc::new(
+        AppState::default().with_gemini_base_url(server.uri()),
+    ));
+
+    assert_eq!(
+        prompt(&router, "What is the capital of France?").await,
+        StatusCode::OK
+    );
+    assert_eq!(prompt(&router, "nonsense").await, StatusCode::BAD_GATEWAY);
+
+    let page = body_json(get(&router, "/prompts").await).await;
+    assert_eq!(page["total"], 2);
+    let prompts = page["prompts"].as_array().unwrap();
+    // Newest first
+    assert_eq!(prompts[0]["prompt"], "nonsense");
+    assert!(prompts[0]["response"].is_null());
+    assert!(prompts[0]["error"].as_str().unwrap().contains("400"));
+    assert_eq!(prompts[1]["prompt"], "What is the capital of France?");
+    assert_eq!(prompts[1]["response"], "Paris");
+    assert!(prompts[1]["error"].is_null());
+    assert!(prompts[1]["model"].is_string());
+    assert!(prompts[1]["created_at"].as_u64().unwrap() > 0);
+    assert!(prompts[1]["latency_ms"].is_u64());
+
+    // Fetch a single record by id
+    let id = prompts[1]["id"].as_u64().unwrap();
+    let response = get(&router, &format!("/prompts/{}", id)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["response"], "Paris");
+}
+
+#[tokio::test]
+async fn prompts_page_and_delete() {
+    let server = gemini_mock().await;
+    let router = app(Arc::new(
+        AppState::default().with_gemini_base_url(server.uri()),
+    ));
+    for i in 0..3 {
+        prompt(&router, &format!("capital #{}", i)).await;
+    }
+
+    let page = body_json(get(&router, "/prompts?limit=1&offset=1").await).await;
+    assert_eq!(page["total"], 3);
+    assert_eq!(page["prompts"].as_array().unwrap().len(), 1);
+    assert_eq!(page["prompts"][0]["prompt"], "capital #1");
+    assert_eq!(
+        get(&router, "/prompts?limit=0").await.status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    let response = send(
+        &router,
+        Request::delete("/prompts/1").body(Body::empty()).unwrap(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(
+        get(&router, "/prompts/1").await.status(),
+        StatusCode::NOT_FOUND
+    );
+    let response = send(
+        &router,
+        Request::delete("/prompts/1").body(Body::empty()).unwrap(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(body_json(get(&router, "/prompts").await).await["total"], 2);
+}
@@ -0,0 +1,152 @@
+//! `GET /items` query parameters: pagination, filtering, sorting, and their validation.
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use rust_simple_rest_api::{app, Db};
+use serde_json::Value;
+use tower::ServiceExt;
+
+async fn body_json(response: Response) -> Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn send(router: &Router, request: Request<Body>) -> Response {
+    router.clone().oneshot(request).await.unwrap()
+}
+
+/// A router with items 1..=5 named e, d, c, b, a; even ids are completed.
+async fn seeded() -> Router {
+    let router = app(Db::default());
+    for name in ["e", "d", "c", "b", "a"] {
+        let request = Request::post("/items")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(serde_json::json!({ "name": name }).to_string()))
+            .unwrap();
+        assert_eq!(send(&router, request).await.status(), StatusCode::CREATED);
+    }
+    for id in [2, 4] {
+        let request = Request::patch(format!("/items/{}", id))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"completed": true}"#))
+            .unwrap();
+        assert_eq!(send(&router, request).await.status(), StatusCode::OK);
+    }
+    router
+}
+
+async fn list(router: &Router, query: &str) -> Value {
+    let response = send(
+        router,
+        Request::get(format!("/items{}", query))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK, "GET /items{}", query);
+    body_json(response).await
+}
+
+fn ids(page: &Value) -> Vec<u64> {
+    page["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["id"].as_u64().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn defaults_return_everything_sorted_by_id() {
+    let page = list(&seeded().await, "").await;
+    assert_eq!(ids(&page), vec![1, 2, 3, 4, 5]);
+    assert_eq!(page["total"], 5);
+    assert_eq!(page["limit"], 50);
+    assert_eq!(page["offset"], 0);
+}
+
+#[tokio::test]
+async fn limit_and_offset_select_a_page() {
+    let router = seeded().await;
+    let page = list(&router, "?limit=2&offset=1").await;
+    assert_eq!(ids(&page), vec![2, 3]);
+    assert_eq!(
+        page["total"], 5,
+        "total counts all items, not just the page"
+    );
+
+    // Past the end is an empty page, not an error
+    assert_eq!(ids(&list(&router, "?offset=10").await), Vec::<u64>::new());
+}
+
+#[tokio::test]
+async fn completed_filters() {
+    let router = seeded().await;
+    assert_eq!(ids(&list(&router, "?completed=true").await), vec![2, 4]);
+    let open = list(&router, "?completed=false").await;
+    assert_eq!(ids(&open), vec![1, 3, 5]);
+    assert_eq!(open["total"], 3);
+}
+
+#[tokio::test]
+async fn sort_and_order() {
+    let router = seeded().await;
+    assert_eq!(ids(&list(&router, "?sort=name").await), vec![5, 4, 3, 2, 1]);
+    assert_eq!(
+        ids(&list(&router, "?sort=name&order=desc").await),
+        vec![1, 2, 3, 4, 5]
+    );
+    assert_eq!(
+        ids(&list(&router, "?order=desc").await),
+        vec![5, 4, 3, 2, 1]
+    );
+}
+
+#[tokio::test]
+async fn filter_sort_and_pagination_combine() {
+    // Open items are 1 (e), 3 (c), 5 (a); by name: 5, 3, 1; second page of size 2: [1]
+    let page = list(
+        &seeded().await,
+        "?completed=false&sort=name&limit=2&offset=2",
+    )
+    .await;
+    assert_eq!(ids(&page), vec![1]);
+    assert_eq!(page["total"], 3);
+    assert_eq!(page["limit"], 2);
+    assert_eq!(page["offset"], 2);
+}
+
+#[tokio::test]
+async fn invalid_parameters_are_400() {
+    let router = app(Db::default());
+    for query in [
+        "?limit=0",
+        "?limit=501",
+        "?limit=ten",
+        "?offset=-1",
+        "?completed=maybe",
+        "?sort=date",
+        "?order=up",
+    ] {
+        let response = send(
+            &router,
+            Request::get(format!("/items{}", query))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+        assert_eq!(
+            response.status(),
+            StatusCode::BAD_REQUEST,
+            "GET /items{}",
+            query
+        );
+        let body = body_json(response).await;
+        assert_eq!(body["error"]["code"], "bad_request");
+        assert!(!body["error"]["message"].as_str().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,49 @@
+//! Request log lines carry the same request id the client gets back.
+
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use axum::{body::Body, http::Request};
+use rust_simple_rest_api::{app, Db};
+use tower::ServiceExt;
+
+/// Collects everything the subscriber writes
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Captured {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn the_request_span_records_the_request_id() {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    // Only for this thread; a `#[tokio::test]` runs the whole request on it
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let request = Request::get("/items/42")
+        .header("x-request-id", "trace-me-42")
+        .body(Body::empty())
+        .unwrap();
+    let response = app(Db::default()).oneshot(request).await.unwrap();
+    assert_eq!(response.headers()["x-request-id"], "trace-me-42");
+
+    let logs = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    assert!(
+        logs.contains("request{request_id=trace-me-42 method=GET path=/items/42}"),
+        "{}",
+        logs
+    );
+}
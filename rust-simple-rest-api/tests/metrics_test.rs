@@ -0,0 +1,148 @@
+//! `/metrics`: per-route request counts and latency histograms in the Prometheus text format.
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    Router,
+};
+use rust_simple_rest_api::{app, metrics::LATENCY_BUCKETS, Db, Metrics};
+use std::time::Duration;
+use tower::ServiceExt;
+
+async fn get(router: &Router, uri: &str) -> StatusCode {
+    router
+        .clone()
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+async fn scrape(router: &Router) -> String {
+    let response = router
+        .clone()
+        .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain; version=0.0.4"));
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+/// The value of the sample line starting with `series` (name plus labels)
+fn sample(text: &str, series: &str) -> Option<f64> {
+    text.lines()
+        .find_map(|line| line.strip_prefix(series)?.strip_prefix(' '))
+        .map(|value| value.parse().unwrap())
+}
+
+#[tokio::test]
+async fn metrics_count_requests_per_route_and_status() {
+    let router = app(Db::default());
+    for _ in 0..3 {
+        assert_eq!(get(&router, "/health").await, StatusCode::OK);
+    }
+    // Different ids share the `/items/:id` series
+    assert_eq!(get(&router, "/items/1").await, StatusCode::NOT_FOUND);
+    assert_eq!(get(&router, "/items/2").await, StatusCode::NOT_FOUND);
+    assert_eq!(get(&router, "/no/such/path").await, StatusCode::NOT_FOUND);
+
+    let text = scrape(&router).await;
+    assert!(text.contains("# TYPE http_requests_total counter"));
+    assert!(text.contains("# TYPE http_request_duration_seconds histogram"));
+    assert_eq!(
+        sample(
+            &text,
+            r#"http_requests_total{method="GET",route="/health",status="200"}"#
+        ),
+        Some(3.0)
+    );
+    assert_eq!(
+        sample(
+            &text,
+            r#"http_requests_total{method="GET",route="/items/:id",status="404"}"#
+        ),
+        Some(2.0)
+    );
+    assert_eq!(
+        sample(
+            &text,
+            r#"http_requests_total{method="GET",route="unmatched",status="404"}"#
+        ),
+        Some(1.0)
+    );
+    assert_eq!(
+        sample(
+            &text,
+            r#"http_request_duration_seconds_count{method="GET",route="/health"}"#
+        ),
+        Some(3.0)
+    );
+    assert_eq!(
+        sample(
+            &text,
+            r#"http_request_duration_seconds_bucket{method="GET",route="/health",le="+Inf"}"#
+        ),
+        Some(3.0)
+    );
+    assert!(
+        !text.contains("/items/1"),
+        "raw paths must not become labels"
+    );
+
+    // The scrape itself shows up in the next one
+    let text = scrape(&router).await;
+    assert_eq!(
+        sample(
+            &text,
+            r#"http_requests_total{method="GET",route="/metrics",status="200"}"#
+        ),
+        Some(1.0)
+    );
+}
+
+#[test]
+fn histogram_buckets_are_cumulative() {
+    let metrics = Metrics::default();
+    metrics.record("GET", "/items", 200, Duration::from_millis(2));
+    metrics.record("GET", "/items", 200, Duration::from_millis(70));
+    metrics.record("GET", "/items", 500, Duration::from_secs(30));
+    assert_eq!(metrics.request_count("GET", "/items"), 3);
+    assert_eq!(metrics.request_count("POST", "/items"), 0);
+
+    let text = metrics.render();
+    let bucket = |le: &str| {
+        sample(
+            &text,
+            &format!(
+                r#"http_request_duration_seconds_bucket{{method="GET",route="/items",le="{}"}}"#,
+                le
+            ),
+        )
+    };
+    assert_eq!(bucket("0.005"), Some(1.0));
+    assert_eq!(bucket("0.05"), Some(1.0));
+    assert_eq!(bucket("0.1"), Some(2.0));
+    assert_eq!(
+        bucket(&LATENCY_BUCKETS[LATENCY_BUCKETS.len() - 1].to_string()),
+        Some(2.0)
+    );
+    assert_eq!(bucket("+Inf"), Some(3.0));
+    let sum = sample(
+        &text,
+        r#"http_request_duration_seconds_sum{method="GET",route="/items"}"#,
+    )
+    .unwrap();
+    assert!((sum - 30.072).abs() < 1e-9);
+    assert_eq!(
+        sample(
+            &text,
+            r#"http_requests_total{method="GET",route="/items",status="500"}"#
+        ),
+        Some(1.0)
+    );
+}
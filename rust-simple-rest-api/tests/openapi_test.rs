@@ -0,0 +1,97 @@
+//! `/openapi.json` is generated from the handlers and models; `/docs` serves Swagger UI.
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use rust_simple_rest_api::{app, Db};
+use serde_json::Value;
+use tower::ServiceExt;
+
+async fn get(router: &Router, uri: &str) -> Response {
+    router
+        .clone()
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
+
+async fn spec() -> Value {
+    let response = get(&app(Db::default()), "/openapi.json").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn item_and_prompt_routes_are_documented() {
+    let spec = spec().await;
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+    let paths = &spec["paths"];
+    for (path, method) in [
+        ("/items", "get"),
+        ("/items", "post"),
+        ("/items/{id}", "get"),
+        ("/items/{id}", "put"),
+        ("/items/{id}", "patch"),
+        ("/items/{id}", "delete"),
+        ("/items/batch", "post"),
+        ("/tags", "get"),
+        ("/prompt", "post"),
+        ("/prompt/stream", "post"),
+        ("/health", "get"),
+    ] {
+        assert!(
+            paths[path][method].is_object(),
+            "{} {} missing",
+            method.to_uppercase(),
+            path
+        );
+    }
+    // Protected operations name the bearer scheme; reads don't
+    assert!(paths["/items"]["post"]["security"][0]["bearer"].is_array());
+    assert!(paths["/items"]["get"]["security"].is_null());
+    assert_eq!(
+        spec["components"]["securitySchemes"]["bearer"]["scheme"],
+        "bearer"
+    );
+}
+
+#[tokio::test]
+async fn schemas_match_the_structs() {
+    let spec = spec().await;
+    let schemas = &spec["components"]["schemas"];
+    let item = &schemas["Item"]["properties"];
+    for field in ["id", "name", "completed", "tags", "version"] {
+        assert!(item[field].is_object(), "Item.{} missing", field);
+    }
+    assert!(schemas["CreateItem"]["properties"]["name"].is_object());
+    assert!(schemas["UpdateItem"]["properties"]["completed"].is_object());
+    let error = &schemas["ErrorBody"]["properties"];
+    for field in ["code", "message", "request_id", "details"] {
+        assert!(error[field].is_object(), "ErrorBody.{} missing", field);
+    }
+
+    // Responses point at those schemas
+    let created = &spec["paths"]["/items"]["post"]["responses"]["201"]["content"]
+        ["application/json"]["schema"];
+    assert_eq!(created["$ref"], "#/components/schemas/Item");
+    let not_found = &spec["paths"]["/items/{id}"]["get"]["responses"]["404"]["content"]
+        ["application/json"]["schema"];
+    assert_eq!(not_found["$ref"], "#/components/schemas/ErrorEnvelope");
+}
+
+#[tokio::test]
+async fn swagger_ui_is_served() {
+    let router = app(Db::default());
+    let response = get(&router, "/docs/").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(String::from_utf8_lossy(&bytes).contains("swagger"));
+}
@@ -0,0 +1,140 @@
+//! `POST /prompt/stream` against a mocked Gemini backend.
+
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    Router,
+};
+use rust_simple_rest_api::{app, AppState};
+use serde_json::Value;
+use tower::ServiceExt;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const STREAMED: &str = concat!(
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Hello\"}]}}]}\n\n",
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \", \"}]}}]}\n\n",
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"world\"}]}, \"finishReason\": \"STOP\"}]}\n\n",
+);
+
+fn router_for(server: &MockServer) -> Router {
+    app(Arc::new(
+        AppState::default().with_gemini_base_url(server.uri()),
+    ))
+}
+
+fn prompt_request() -> Request<Body> {
+    Request::post("/prompt/stream")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"prompt": "hi", "api_key": "test-key"}"#))
+        .unwrap()
+}
+
+/// Split an SSE body into (event name, data) pairs.
+fn parse_events(body: &str) -> Vec<(String, String)> {
+    body.split("\n\n")
+        .filter(|block| !block.trim().is_empty())
+        .map(|block| {
+            let mut event = "message".to_string();
+            let mut data = Vec::new();
+            for line in block.lines() {
+                if let Some(name) = line.strip_prefix("event:") {
+                    event = name.trim().to_string();
+                } else if let Some(value) = line.strip_prefix("data:") {
+                    data.push(value.strip_prefix(' ').unwrap_or(value));
+                }
+            }
+            (event, data.join("\n"))
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn deltas_arrive_as_ordered_sse_events() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!(
+            "/models/{}:streamGenerateContent",
+            rust_gemini_llm_client::GeminiClient::new("k").model()
+        )))
+        .respond_with(ResponseTemplate::new(200).set_body_string(STREAMED))
+        .mount(&server)
+        .await;
+
+    let response = router_for(&server).oneshot(prompt_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/event-stream"
+    );
+
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let events = parse_events(std::str::from_utf8(&bytes).unwrap());
+
+    let messages: Vec<&str> = events
+        .iter()
+        .filter(|(event, _)| event == "message")
+        .map(|(_, data)| data.as_str())
+        .collect();
+    assert_eq!(messages, vec!["Hello", ", ", "world"]);
+
+    let (last_event, last_data) = events.last().unwrap();
+    assert_eq!(last_event, "done");
+    let done: Value = serde_json::from_str(last_data).unwrap();
+    assert_eq!(done["finish_reason"], "STOP");
+}
+
+#[tokio::test]
+async fn upstream_rejection_is_an_http_error_not_a_stream() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(403).set_body_string("API key not valid"))
+        .mount(&server)
+        .await;
+
+    let response = router_for(&server).oneshot(prompt_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"]["code"], "upstream_error");
+}
+
+#[tokio::test]
+async fn blocked_answer_ends_with_an_error_event() {
+    let server = MockServer::start().await;
+    let body = concat!(
+        "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Sure\"}]}}]}\n\n",
+        "data: {\"candidates\": [{\"finishReason\": \"SAFETY\"}]}\n\n",
+    );
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(body))
+        .mount(&server)
+        .await;
+
+    let response = router_for(&server).oneshot(prompt_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let events = parse_events(std::str::from_utf8(&bytes).unwrap());
+
+    assert_eq!(events[0], ("message".to_string(), "Sure".to_string()));
+    let (event, data) = events.last().unwrap();
+    assert_eq!(event, "error");
+    let error: Value = serde_json::from_str(data).unwrap();
+    assert_eq!(error["error"]["code"], "validation_error");
+}
+
+#[tokio::test]
+async fn an_unreachable_gemini_does_not_leak_the_api_key() {
+    // Nothing listens on port 1, so connecting fails; the request URL holds `?key=test-key`
+    let router = app(Arc::new(
+        AppState::default().with_gemini_base_url("http://127.0.0.1:1"),
+    ));
+
+    let response = router.oneshot(prompt_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = std::str::from_utf8(&bytes).unwrap();
+    assert!(!body.contains("test-key"), "{}", body);
+}
@@ -0,0 +1,54 @@
+//! `/prompt` error mapping from typed Gemini errors to HTTP responses.
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+};
+use rust_gemini_llm_client::GeminiError;
+use rust_simple_rest_api::{app, prompt::gemini_error_to_api_error, ApiError, Db};
+use tower::ServiceExt;
+
+#[test]
+fn rate_limited_maps_to_429() {
+    let err = gemini_error_to_api_error(GeminiError::RateLimited {
+        retry_after: Some(7),
+    });
+    assert_eq!(err.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(matches!(
+        err,
+        ApiError::RateLimited {
+            retry_after_secs: Some(7),
+            ..
+        }
+    ));
+}
+
+#[test]
+fn missing_api_key_maps_to_400() {
+    let err = gemini_error_to_api_error(GeminiError::MissingApiKey);
+    assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+}
+
+#[test]
+fn other_failures_map_to_502() {
+    let err = gemini_error_to_api_error(GeminiError::Http {
+        status: 500,
+        body: "boom".to_string(),
+    });
+    assert_eq!(err.status(), StatusCode::BAD_GATEWAY);
+}
+
+#[tokio::test]
+async fn prompt_without_any_api_key_is_400() {
+    std::env::remove_var("GEMINI_API_KEY");
+    let request = Request::post("/prompt")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(r#"{"prompt": "hi"}"#))
+        .unwrap();
+    let response = app(Db::default()).oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"]["code"], "bad_request");
+}
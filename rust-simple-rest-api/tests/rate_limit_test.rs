@@ -0,0 +1,227 @@
+//! Per-client token buckets on `/prompt` and `/prompt/stream`.
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, HeaderMap, HeaderValue, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use rust_simple_rest_api::{
+    app, rate_limit::client_ip, serve, AppState, RateLimitConfig, RateLimiter,
+};
+use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tower::ServiceExt;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const ANSWER: &str =
+    "data: {\"candidates\": [{\"content\": {\"parts\": [{\"text\": \"Paris\"}]}, \"finishReason\": \"STOP\"}]}\n\n";
+const PROMPT: &str = r#"{"prompt": "capital of France?", "api_key": "test-key"}"#;
+
+async fn gemini_mock() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(ANSWER))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn prompt(router: &Router, uri: &str, client: &str) -> Response {
+    let request = Request::post(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("x-forwarded-for", client)
+        .body(Body::from(PROMPT))
+        .unwrap();
+    router.clone().oneshot(request).await.unwrap()
+}
+
+#[tokio::test]
+async fn bucket_runs_dry_then_refills() {
+    let server = gemini_mock().await;
+    // 2 requests per 400 ms: a burst of two, then one more every 200 ms
+    let limit = RateLimitConfig {
+        requests: 2,
+        period: Duration::from_millis(400),
+        trust_proxy: true,
+    };
+    let router = app(Arc::new(
+        AppState::default()
+            .with_gemini_base_url(server.uri())
+            .with_rate_limit(limit),
+    ));
+
+    assert_eq!(
+        prompt(&router, "/prompt", "10.0.0.1").await.status(),
+        StatusCode::OK
+    );
+    // The streaming route draws from the same bucket
+    assert_eq!(
+        prompt(&router, "/prompt/stream", "10.0.0.1").await.status(),
+        StatusCode::OK
+    );
+
+    let response = prompt(&router, "/prompt", "10.0.0.1").await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"]["code"], "rate_limited");
+    assert!(body["error"]["request_id"].is_string());
+
+    // Other clients and other routes are unaffected
+    assert_eq!(
+        prompt(&router, "/prompt", "10.0.0.2").await.status(),
+        StatusCode::OK
+    );
+    let items = router
+        .clone()
+        .oneshot(Request::get("/items").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(items.status(), StatusCode::OK);
+
+    // One token is back after 200 ms, but only one
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert_eq!(
+        prompt(&router, "/prompt", "10.0.0.1").await.status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        prompt(&router, "/prompt", "10.0.0.1").await.status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
+
+#[test]
+fn token_bucket_arithmetic() {
+    let limiter = RateLimiter::new(RateLimitConfig::per_minute(3));
+    let client: IpAddr = "192.0.2.7".parse().unwrap();
+    let start = Instant::now();
+
+    for _ in 0..3 {
+        assert!(limiter.check_at(client, start).is_ok());
+    }
+    // Empty: the next token is 60 s / 3 = 20 s away
+    let wait = limiter.check_at(client, start).unwrap_err();
+    assert_eq!(wait.as_secs(), 20);
+    let wait = limiter
+        .check_at(client, start + Duration::from_secs(15))
+        .unwrap_err();
+    assert_eq!(wait.as_secs(), 5);
+    assert!(limiter
+        .check_at(client, start + Duration::from_secs(20))
+        .is_ok());
+
+    // After a long pause the bucket is full again, not fuller
+    let later = start + Duration::from_secs(3600);
+    for _ in 0..3 {
+        assert!(limiter.check_at(client, later).is_ok());
+    }
+    assert!(limiter.check_at(client, later).is_err());
+}
+
+#[test]
+fn idle_clients_are_pruned() {
+    let limiter = RateLimiter::new(RateLimitConfig::per_minute(10));
+    let start = Instant::now();
+    limiter
+        .check_at("192.0.2.1".parse().unwrap(), start)
+        .unwrap();
+    limiter
+        .check_at(
+            "192.0.2.2".parse().unwrap(),
+            start + Duration::from_secs(50),
+        )
+        .unwrap();
+    assert_eq!(limiter.tracked_clients(), 2);
+
+    // Only the first has been idle for a whole period
+    limiter.prune(start + Duration::from_secs(70));
+    assert_eq!(limiter.tracked_clients(), 1);
+    // Checks prune on their own once in a while
+    limiter
+        .check_at(
+            "192.0.2.3".parse().unwrap(),
+            start + Duration::from_secs(200),
+        )
+        .unwrap();
+    assert_eq!(limiter.tracked_clients(), 1);
+}
+
+#[test]
+fn disabled_limit_allows_everything() {
+    let limiter = RateLimiter::default();
+    assert!(!limiter.config().is_enabled());
+    let client: IpAddr = "192.0.2.1".parse().unwrap();
+    for _ in 0..1000 {
+        assert!(limiter.check(client).is_ok());
+    }
+    assert_eq!(limiter.tracked_clients(), 0);
+}
+
+#[test]
+fn client_address_selection() {
+    let peer = Some("203.0.113.9:5000".parse().unwrap());
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "x-forwarded-for",
+        HeaderValue::from_static("1.1.1.1, 198.51.100.4"),
+    );
+
+    // Untrusted: the header is ignored
+    assert_eq!(
+        client_ip(&headers, peer, false),
+        "203.0.113.9".parse::<IpAddr>().unwrap()
+    );
+    // Trusted: the address our proxy appended (the last one), not what the client claimed
+    assert_eq!(
+        client_ip(&headers, peer, true),
+        "198.51.100.4".parse::<IpAddr>().unwrap()
+    );
+    // Unparseable header: fall back to the socket
+    headers.insert("x-forwarded-for", HeaderValue::from_static("garbage"));
+    assert_eq!(
+        client_ip(&headers, peer, true),
+        "203.0.113.9".parse::<IpAddr>().unwrap()
+    );
+}
+
+/// POST /prompt over a raw connection and return the status line
+async fn raw_prompt(addr: std::net::SocketAddr) -> String {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let request = format!(
+        "POST /prompt HTTP/1.1\r\nhost: test\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+        PROMPT.len(),
+        PROMPT
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response.lines().next().unwrap_or_default().to_string()
+}
+
+#[tokio::test]
+async fn served_router_keys_on_the_peer_address() {
+    let server = gemini_mock().await;
+    let state = AppState::default()
+        .with_gemini_base_url(server.uri())
+        .with_rate_limit(RateLimitConfig::per_minute(1));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(serve(
+        listener,
+        app(Arc::new(state)),
+        std::future::pending(),
+        Duration::from_secs(1),
+    ));
+
+    assert!(raw_prompt(addr).await.starts_with("HTTP/1.1 200"));
+    assert!(raw_prompt(addr).await.starts_with("HTTP/1.1 429"));
+}
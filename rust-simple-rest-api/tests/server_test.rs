@@ -0,0 +1,118 @@
+//! Graceful shutdown over a real socket, `/health`, and the env-based server config.
+
+use std::time::{Duration, Instant};
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    routing::get,
+    Router,
+};
+use rust_simple_rest_api::{app, serve, Db, ServerConfig};
+use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tower::ServiceExt;
+
+/// A router whose only route takes `delay` to answer
+fn slow_router(delay: Duration) -> Router {
+    Router::new().route(
+        "/slow",
+        get(move || async move {
+            tokio::time::sleep(delay).await;
+            "finished"
+        }),
+    )
+}
+
+/// Send `GET /slow` over a raw connection (no HTTP client crate needed)
+async fn start_slow_request(addr: std::net::SocketAddr) -> TcpStream {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /slow HTTP/1.1\r\nhost: test\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    stream
+}
+
+#[tokio::test]
+async fn in_flight_request_completes_after_shutdown_starts() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (trigger, shutdown) = oneshot::channel::<()>();
+    let server = tokio::spawn(serve(
+        listener,
+        slow_router(Duration::from_millis(300)),
+        async move {
+            let _ = shutdown.await;
+        },
+        Duration::from_secs(5),
+    ));
+
+    let mut stream = start_slow_request(addr).await;
+    // Let the request reach the handler, then ask the server to stop mid-request
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    trigger.send(()).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "got {:?}", response);
+    assert!(response.ends_with("finished"));
+
+    // With the request done, the server exits on its own
+    let result = tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server stopped after draining");
+    assert!(result.unwrap().is_ok());
+    // ...and no longer accepts connections
+    assert!(TcpStream::connect(addr).await.is_err());
+}
+
+#[tokio::test]
+async fn grace_period_bounds_the_wait() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (trigger, shutdown) = oneshot::channel::<()>();
+    let server = tokio::spawn(serve(
+        listener,
+        slow_router(Duration::from_secs(30)),
+        async move {
+            let _ = shutdown.await;
+        },
+        Duration::from_millis(200),
+    ));
+
+    let _stream = start_slow_request(addr).await;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let started = Instant::now();
+    trigger.send(()).unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server gave up on the slow request")
+        .unwrap()
+        .unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn health_reports_status_items_and_uptime() {
+    let response = app(Db::default())
+        .oneshot(Request::get("/health").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["status"], "ok");
+    assert_eq!(body["items"], 0);
+    assert!(body["uptime_secs"].is_u64());
+}
+
+#[test]
+fn server_config_defaults() {
+    let config = ServerConfig::default();
+    assert_eq!(config.address(), "0.0.0.0:3000");
+    assert_eq!(config.shutdown_grace, Duration::from_secs(10));
+}
@@ -0,0 +1,174 @@
+//! Items are written through to storage and come back after a "restart".
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use rust_simple_rest_api::{app, AppState, ItemStore, JsonFileStorage, Storage};
+use serde_json::Value;
+use tower::ServiceExt;
+
+fn data_file(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("rest_api_{}_{}.json", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn router_for(path: &PathBuf) -> Router {
+    let state = AppState::with_storage(JsonFileStorage::new(path)).expect("load items");
+    app(Arc::new(state))
+}
+
+fn json_request(method: &str, uri: &str, body: Value) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+async fn body_json(response: Response) -> Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+#[tokio::test]
+async fn items_survive_a_restart() {
+    let path = data_file("restart");
+
+    let router = router_for(&path);
+    for name in ["milk", "eggs", "bread"] {
+        let response = router
+            .clone()
+            .oneshot(json_request(
+                "POST",
+                "/items",
+                serde_json::json!({ "name": name }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+    let response = router
+        .clone()
+        .oneshot(json_request(
+            "PATCH",
+            "/items/2",
+            serde_json::json!({ "completed": true }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = router
+        .oneshot(Request::delete("/items/3").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // A fresh state loaded from the same file sees every change
+    let restarted = router_for(&path);
+    let response = restarted
+        .clone()
+        .oneshot(Request::get("/items/2").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_json(response).await;
+    assert_eq!(body["name"], "eggs");
+    assert_eq!(body["completed"], true);
+
+    let response = restarted
+        .clone()
+        .oneshot(Request::get("/items/3").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Ids continue after the highest ever handed out: 3 was deleted, but isn't reused
+    let response = restarted
+        .oneshot(json_request(
+            "POST",
+            "/items",
+            serde_json::json!({ "name": "jam" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(body_json(response).await["id"], 4);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn missing_file_loads_empty_and_corrupt_file_is_an_error() {
+    let path = data_file("corrupt");
+    assert!(JsonFileStorage::new(&path).load().unwrap().items.is_empty());
+
+    std::fs::write(&path, "not json").unwrap();
+    let err = AppState::with_storage(JsonFileStorage::new(&path))
+        .err()
+        .expect("corrupt file");
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn a_file_of_just_items_still_loads() {
+    // The layout before `next_id` was stored: a bare array
+    let path = data_file("legacy");
+    std::fs::write(
+        &path,
+        r#"[{"id": 2, "name": "milk", "completed": false, "version": 3}]"#,
+    )
+    .unwrap();
+
+    let store = JsonFileStorage::new(&path).load().unwrap();
+    assert_eq!(store.items[&2].name, "milk");
+    assert_eq!(store.next_id, 3);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+/// Storage whose writes always fail, e.g. a full disk
+struct BrokenStorage;
+
+impl Storage for BrokenStorage {
+    fn load(&self) -> io::Result<ItemStore> {
+        Ok(ItemStore::default())
+    }
+
+    fn save(&self, _store: &ItemStore) -> io::Result<()> {
+        Err(io::Error::other("disk full"))
+    }
+}
+
+#[tokio::test]
+async fn failed_write_is_a_500_and_changes_nothing() {
+    let router = app(Arc::new(AppState::with_storage(BrokenStorage).unwrap()));
+
+    let response = router
+        .clone()
+        .oneshot(json_request(
+            "POST",
+            "/items",
+            serde_json::json!({ "name": "milk" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body_json(response).await["error"]["code"], "internal_error");
+
+    // Memory did not diverge from (the failed) storage
+    let response = router
+        .oneshot(Request::get("/items").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(body_json(response).await["total"], 0);
+}
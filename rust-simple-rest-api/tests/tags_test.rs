@@ -0,0 +1,178 @@
+//! Item tags: normalization on create/update, `GET /items?tag=`, and the `GET /tags` counts.
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use rust_simple_rest_api::{app, Db, MAX_TAGS, MAX_TAG_LEN};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+async fn send(router: &Router, method: &str, uri: &str, body: Value) -> Response {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    router.clone().oneshot(request).await.unwrap()
+}
+
+async fn body_json(response: Response) -> Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+/// Names of the items `GET /items{query}` returns
+async fn names(router: &Router, query: &str) -> Vec<String> {
+    let page = body_json(send(router, "GET", &format!("/items{}", query), Value::Null).await).await;
+    page["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["name"].as_str().unwrap().to_string())
+        .collect()
+}
+
+async fn seeded() -> Router {
+    let router = app(Db::default());
+    let batch = json!({ "items": [
+        { "name": "report", "tags": ["work", "urgent"] },
+        { "name": "laundry", "tags": ["home"] },
+        { "name": "taxes", "tags": ["home", "urgent"] },
+        { "name": "gym" },
+    ]});
+    assert_eq!(
+        send(&router, "POST", "/items/batch", batch).await.status(),
+        StatusCode::CREATED
+    );
+    router
+}
+
+#[tokio::test]
+async fn tags_are_normalized_on_create() {
+    let router = app(Db::default());
+    let response = send(
+        &router,
+        "POST",
+        "/items",
+        json!({ "name": "milk", "tags": [" Home ", "shopping", "HOME", "Shopping"] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(
+        body_json(response).await["tags"],
+        json!(["home", "shopping"])
+    );
+
+    // No tags given means none
+    let response = send(&router, "POST", "/items", json!({ "name": "eggs" })).await;
+    assert_eq!(body_json(response).await["tags"], json!([]));
+}
+
+#[tokio::test]
+async fn invalid_tags_are_a_structured_400() {
+    let router = app(Db::default());
+    let too_long = "x".repeat(MAX_TAG_LEN + 1);
+    let too_many: Vec<String> = (0..=MAX_TAGS).map(|i| format!("tag{}", i)).collect();
+    for tags in [
+        json!(["ok", ""]),
+        json!(["   "]),
+        json!([too_long]),
+        json!(too_many),
+    ] {
+        let response = send(
+            &router,
+            "POST",
+            "/items",
+            json!({ "name": "milk", "tags": tags }),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "tags {}", tags);
+        let body = body_json(response).await;
+        assert_eq!(body["error"]["code"], "bad_request");
+        assert!(body["error"]["request_id"].is_string());
+    }
+    // Exactly the limits are fine, and duplicates don't count twice
+    let mut at_limit: Vec<String> = (0..MAX_TAGS).map(|i| format!("tag{}", i)).collect();
+    at_limit.push("TAG0".to_string());
+    at_limit[1] = "y".repeat(MAX_TAG_LEN);
+    let response = send(
+        &router,
+        "POST",
+        "/items",
+        json!({ "name": "milk", "tags": at_limit }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Nothing invalid was stored, in a batch either
+    let batch =
+        json!({ "items": [{ "name": "a", "tags": ["fine"] }, { "name": "b", "tags": [""] }] });
+    let response = send(&router, "POST", "/items/batch", batch).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(body_json(response).await["error"]["message"]
+        .as_str()
+        .unwrap()
+        .starts_with("items[1]"));
+    assert_eq!(names(&router, "").await, ["milk"]);
+}
+
+#[tokio::test]
+async fn filter_items_by_tag() {
+    let router = seeded().await;
+    assert_eq!(names(&router, "?tag=urgent").await, ["report", "taxes"]);
+    assert_eq!(
+        names(&router, "?tag=HOME&sort=name").await,
+        ["laundry", "taxes"]
+    );
+    assert_eq!(
+        names(&router, "?tag=home&completed=true").await,
+        Vec::<String>::new()
+    );
+    assert!(names(&router, "?tag=garden").await.is_empty());
+}
+
+#[tokio::test]
+async fn update_replaces_tags() {
+    let router = seeded().await;
+
+    let response = send(&router, "PATCH", "/items/4", json!({ "tags": ["Health"] })).await;
+    let item = body_json(response).await;
+    assert_eq!(item["tags"], json!(["health"]));
+    assert_eq!(item["version"], 2);
+
+    // PATCH without tags keeps them
+    let item =
+        body_json(send(&router, "PATCH", "/items/4", json!({ "completed": true })).await).await;
+    assert_eq!(item["tags"], json!(["health"]));
+
+    // PUT without tags clears them
+    let item = body_json(send(&router, "PUT", "/items/4", json!({ "name": "gym" })).await).await;
+    assert_eq!(item["tags"], json!([]));
+
+    let response = send(&router, "PATCH", "/items/4", json!({ "tags": [""] })).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn tags_are_counted() {
+    let router = seeded().await;
+    let tags = body_json(send(&router, "GET", "/tags", Value::Null).await).await;
+    // Most used first; equal counts alphabetically
+    assert_eq!(
+        tags,
+        json!({ "tags": [
+            { "tag": "home", "count": 2 },
+            { "tag": "urgent", "count": 2 },
+            { "tag": "work", "count": 1 },
+        ]})
+    );
+
+    send(&router, "DELETE", "/items/3", Value::Null).await;
+    let tags = body_json(send(&router, "GET", "/tags", Value::Null).await).await;
+    assert_eq!(tags["tags"][0], json!({ "tag": "home", "count": 1 }));
+    assert_eq!(tags["tags"].as_array().unwrap().len(), 3);
+}
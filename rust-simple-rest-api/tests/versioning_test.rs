@@ -0,0 +1,236 @@
+//! Item versions: every change bumps `version`, stale `If-Match`/body versions get a 409, and
+//! PUT (full replace) vs PATCH (partial update).
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, Method, Request, StatusCode},
+    response::Response,
+    Router,
+};
+use rust_simple_rest_api::{app, Db};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+async fn send(
+    router: &Router,
+    method: Method,
+    uri: &str,
+    if_match: Option<&str>,
+    body: Value,
+) -> Response {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::CONTENT_TYPE, "application/json");
+    if let Some(version) = if_match {
+        builder = builder.header(header::IF_MATCH, version);
+    }
+    router
+        .clone()
+        .oneshot(builder.body(Body::from(body.to_string())).unwrap())
+        .await
+        .unwrap()
+}
+
+async fn body_json(response: Response) -> Value {
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+/// A router holding one item, `{"id": 1, "name": "milk", "version": 1}`
+async fn with_item() -> Router {
+    let router = app(Db::default());
+    let response = send(
+        &router,
+        Method::POST,
+        "/items",
+        None,
+        json!({ "name": "milk" }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(body_json(response).await["version"], 1);
+    router
+}
+
+#[tokio::test]
+async fn every_change_bumps_the_version() {
+    let router = with_item().await;
+
+    let response = send(
+        &router,
+        Method::PATCH,
+        "/items/1",
+        None,
+        json!({ "completed": true }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["version"], 2);
+
+    let response = send(
+        &router,
+        Method::PUT,
+        "/items/1",
+        Some("\"2\""),
+        json!({ "name": "oat milk" }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let item = body_json(response).await;
+    assert_eq!(item["version"], 3);
+    assert_eq!(item["name"], "oat milk");
+    // PUT replaces the whole item, so the omitted `completed` is back to false
+    assert_eq!(item["completed"], false);
+
+    let response = send(&router, Method::GET, "/items/1", None, Value::Null).await;
+    assert_eq!(body_json(response).await["version"], 3);
+}
+
+#[tokio::test]
+async fn stale_if_match_is_a_conflict() {
+    let router = with_item().await;
+    send(
+        &router,
+        Method::PATCH,
+        "/items/1",
+        None,
+        json!({ "name": "bread" }),
+    )
+    .await;
+
+    // Both ETag-style and bare versions are understood
+    for stale in ["\"1\"", "W/\"1\"", "1"] {
+        let response = send(
+            &router,
+            Method::PATCH,
+            "/items/1",
+            Some(stale),
+            json!({ "completed": true }),
+        )
+        .await;
+        assert_eq!(
+            response.status(),
+            StatusCode::CONFLICT,
+            "If-Match: {}",
+            stale
+        );
+        let body = body_json(response).await;
+        assert_eq!(body["error"]["code"], "conflict");
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("version 2"));
+    }
+    let response = send(
+        &router,
+        Method::DELETE,
+        "/items/1",
+        Some("\"1\""),
+        Value::Null,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // Nothing was written
+    let item = body_json(send(&router, Method::GET, "/items/1", None, Value::Null).await).await;
+    assert_eq!(item["completed"], false);
+    assert_eq!(item["version"], 2);
+
+    // The current version (or `*`) goes through
+    let response = send(
+        &router,
+        Method::PATCH,
+        "/items/1",
+        Some("\"2\""),
+        json!({ "completed": true }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send(&router, Method::DELETE, "/items/1", Some("*"), Value::Null).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn stale_body_version_is_a_conflict() {
+    let router = with_item().await;
+    send(
+        &router,
+        Method::PATCH,
+        "/items/1",
+        None,
+        json!({ "completed": true }),
+    )
+    .await;
+
+    let response = send(
+        &router,
+        Method::PUT,
+        "/items/1",
+        None,
+        json!({ "name": "x", "version": 1 }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = send(
+        &router,
+        Method::PATCH,
+        "/items/1",
+        None,
+        json!({ "name": "x", "version": 2 }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_json(response).await["version"], 3);
+}
+
+#[tokio::test]
+async fn malformed_if_match_is_a_bad_request() {
+    let router = with_item().await;
+    let response = send(
+        &router,
+        Method::PATCH,
+        "/items/1",
+        Some("\"abc\""),
+        json!({}),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    // Header and body must agree when both are sent
+    let response = send(
+        &router,
+        Method::PATCH,
+        "/items/1",
+        Some("\"1\""),
+        json!({ "version": 2 }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn put_requires_name_but_patch_may_omit_it() {
+    let router = with_item().await;
+
+    let response = send(
+        &router,
+        Method::PUT,
+        "/items/1",
+        None,
+        json!({ "completed": true }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(body_json(response).await["error"]["message"]
+        .as_str()
+        .unwrap()
+        .contains("name"));
+
+    // An empty PATCH changes nothing, not even the version
+    let response = send(&router, Method::PATCH, "/items/1", None, json!({})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let item = body_json(response).await;
+    assert_eq!(item["name"], "milk");
+    assert_eq!(item["completed"], false);
+    assert_eq!(item["version"], 1);
+}
@@ -0,0 +1,162 @@
+//! `GET /ws`: chat over a WebSocket against a mocked Gemini backend, on a real socket.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use futures_util::{SinkExt, StreamExt};
+use rust_simple_rest_api::{app, AppState, AuthConfig};
+use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use wiremock::matchers::{body_string_contains, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+fn chunk(text: &str, finish: bool) -> String {
+    let finish = if finish {
+        r#", "finishReason": "STOP""#
+    } else {
+        ""
+    };
+    format!(
+        "data: {{\"candidates\": [{{\"content\": {{\"parts\": [{{\"text\": \"{}\"}}]}}{}}}]}}\n\n",
+        text, finish
+    )
+}
+
+async fn gemini_mock() -> MockServer {
+    let server = MockServer::start().await;
+    // The follow-up only gets this answer if the first reply was sent back as history
+    Mock::given(method("POST"))
+        .and(body_string_contains("Paris"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(chunk("Lyon", true)))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(body_string_contains("France"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string(chunk("Pa", false) + &chunk("ris", true)),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(400).set_body_string("bad prompt"))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn start(state: AppState) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app(Arc::new(state))).await });
+    addr
+}
+
+async fn next_frame(socket: &mut Socket) -> Value {
+    loop {
+        match socket.next().await.expect("socket closed").unwrap() {
+            Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+            Message::Close(frame) => panic!("closed: {:?}", frame),
+            _ => continue,
+        }
+    }
+}
+
+/// Send one message and collect the frames of the answer, up to `done` or `error`
+async fn ask(socket: &mut Socket, text: &str) -> Vec<Value> {
+    socket.send(Message::Text(text.to_string())).await.unwrap();
+    let mut frames = Vec::new();
+    loop {
+        let frame = next_frame(socket).await;
+        let last = frame["type"] != "delta";
+        frames.push(frame);
+        if last {
+            return frames;
+        }
+    }
+}
+
+#[tokio::test]
+async fn reply_is_streamed_and_history_is_kept() {
+    let server = gemini_mock().await;
+    let addr = start(AppState::default().with_gemini_base_url(server.uri())).await;
+    let (mut socket, _) = connect_async(format!("ws://{}/ws?api_key=test-key", addr))
+        .await
+        .unwrap();
+
+    let frames = ask(&mut socket, "What is the capital of France?").await;
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0]["type"], "delta");
+    assert_eq!(frames[0]["text"], "Pa");
+    assert_eq!(frames[1]["text"], "ris");
+    assert_eq!(frames[2]["type"], "done");
+    assert_eq!(frames[2]["finish_reason"], "STOP");
+
+    // The follow-up carries the earlier turns
+    let frames = ask(&mut socket, "And the second largest city?").await;
+    assert_eq!(frames[0]["text"], "Lyon");
+    assert_eq!(frames[1]["type"], "done");
+}
+
+#[tokio::test]
+async fn gemini_failure_is_an_error_frame_and_the_chat_goes_on() {
+    let server = gemini_mock().await;
+    let addr = start(AppState::default().with_gemini_base_url(server.uri())).await;
+    let (mut socket, _) = connect_async(format!("ws://{}/ws?api_key=test-key", addr))
+        .await
+        .unwrap();
+
+    let frames = ask(&mut socket, "nonsense").await;
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0]["type"], "error");
+    assert_eq!(frames[0]["error"]["code"], "upstream_error");
+    assert!(frames[0]["error"]["request_id"].is_string());
+
+    let frames = ask(&mut socket, "   ").await;
+    assert_eq!(frames[0]["error"]["code"], "validation_error");
+
+    // The failed question was not kept, so this is a fresh first turn
+    let frames = ask(&mut socket, "What is the capital of France?").await;
+    assert_eq!(frames.last().unwrap()["type"], "done");
+}
+
+#[tokio::test]
+async fn token_may_be_passed_in_the_query_string() {
+    let server = gemini_mock().await;
+    let state = AppState::default()
+        .with_gemini_base_url(server.uri())
+        .with_auth(AuthConfig::parse("secret"));
+    let addr = start(state).await;
+
+    // No token: the handshake itself is refused with a 401
+    let error = connect_async(format!("ws://{}/ws?api_key=test-key", addr))
+        .await
+        .unwrap_err();
+    match error {
+        tokio_tungstenite::tungstenite::Error::Http(response) => assert_eq!(response.status(), 401),
+        other => panic!("unexpected error: {}", other),
+    }
+
+    let url = format!("ws://{}/ws?api_key=test-key&access_token=secret", addr);
+    let (mut socket, _) = connect_async(url).await.unwrap();
+    let frames = ask(&mut socket, "What is the capital of France?").await;
+    assert_eq!(frames.last().unwrap()["type"], "done");
+}
+
+#[tokio::test]
+async fn plain_get_is_a_structured_400() {
+    let addr = start(AppState::default()).await;
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /ws HTTP/1.1\r\nhost: test\r\nconnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 400"), "got {:?}", response);
+    assert!(response.contains("\"code\":\"bad_request\""));
+}
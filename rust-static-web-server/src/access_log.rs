@@ -0,0 +1,221 @@
+// Access logging: one line per request, written after the response has been sent, so it can
+// say how the request ended (status, size, time taken) rather than just that it arrived.
+//
+// Two formats, picked with `ACCESS_LOG_FORMAT`:
+//   combined (default), the Apache/nginx "combined" format plus the time taken:
+//     127.0.0.1 - - [16/Oct/2026:09:30:00 +0000] "GET /app.js HTTP/1.1" 200 5120 "-" "curl/8.5.0" 412us
+//   json, one object per line, for log shippers:
+//     {"time":"...","remote":"127.0.0.1","method":"GET","path":"/app.js","status":200,...}
+//
+// Lines go to stdout, and also to a file when `ACCESS_LOG=path` is set (a "tee").
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+/// How each access log line is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Apache/nginx combined log format, plus elapsed microseconds
+    #[default]
+    Combined,
+    /// One JSON object per line
+    Json,
+}
+
+impl LogFormat {
+    /// The name used in `ACCESS_LOG_FORMAT`
+    pub fn name(self) -> &'static str {
+        match self {
+            LogFormat::Combined => "combined",
+            LogFormat::Json => "json",
+        }
+    }
+
+    /// Parse an `ACCESS_LOG_FORMAT` value: `combined` or `json`
+    pub fn parse(value: &str) -> Result<LogFormat, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "combined" | "" => Ok(LogFormat::Combined),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!(
+                "ACCESS_LOG_FORMAT `{}` is not `combined` or `json`",
+                other
+            )),
+        }
+    }
+}
+
+/// Everything logged about one request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// When the request finished
+    pub time: SystemTime,
+    pub remote: Option<SocketAddr>,
+    pub method: String,
+    /// The request target as received (still percent-encoded)
+    pub path: String,
+    /// e.g. `HTTP/1.1`
+    pub version: String,
+    pub status: u16,
+    /// Body bytes sent (0 for HEAD and 304)
+    pub bytes: usize,
+    /// From receiving the request to finishing the response
+    pub elapsed: Duration,
+    pub referer: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl Entry {
+    /// The log line for this entry, without the trailing newline
+    pub fn format(&self, format: LogFormat) -> String {
+        let remote = self
+            .remote
+            .map(|addr| addr.ip().to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let micros = self.elapsed.as_micros();
+        match format {
+            LogFormat::Combined => format!(
+                "{} - - [{}] \"{} {} {}\" {} {} \"{}\" \"{}\" {}us",
+                remote,
+                clf_time(self.time),
+                self.method,
+                self.path,
+                self.version,
+                self.status,
+                self.bytes,
+                quoted(self.referer.as_deref()),
+                quoted(self.user_agent.as_deref()),
+                micros
+            ),
+            LogFormat::Json => format!(
+                "{{\"time\":{},\"remote\":{},\"method\":{},\"path\":{},\"version\":{},\"status\":{},\"bytes\":{},\"elapsed_us\":{},\"referer\":{},\"user_agent\":{}}}",
+                json_string(Some(&httpdate::fmt_http_date(self.time))),
+                json_string(self.remote.map(|a| a.ip().to_string()).as_deref()),
+                json_string(Some(&self.method)),
+                json_string(Some(&self.path)),
+                json_string(Some(&self.version)),
+                self.status,
+                self.bytes,
+                micros,
+                json_string(self.referer.as_deref()),
+                json_string(self.user_agent.as_deref())
+            ),
+        }
+    }
+}
+
+/// Where access log lines (and warnings) go. Shared by all workers: the writers sit behind a
+/// Mutex so lines from different threads never interleave mid-line.
+pub struct AccessLog {
+    format: LogFormat,
+    // `Box<dyn Write + Send>` is "any writer that may move between threads": stdout, a file,
+    // or an in-memory buffer in tests
+    sinks: Mutex<Vec<Box<dyn Write + Send>>>,
+}
+
+impl AccessLog {
+    /// Log to stdout
+    pub fn stdout(format: LogFormat) -> AccessLog {
+        AccessLog::to_writer(format, io::stdout())
+    }
+
+    /// Log to any writer (tests use an in-memory buffer)
+    pub fn to_writer(format: LogFormat, writer: impl Write + Send + 'static) -> AccessLog {
+        AccessLog {
+            format,
+            sinks: Mutex::new(vec![Box::new(writer)]),
+        }
+    }
+
+    /// Log nothing at all
+    pub fn disabled() -> AccessLog {
+        AccessLog {
+            format: LogFormat::default(),
+            sinks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Also append every line to the file at `path`, creating it if needed
+    pub fn tee_to_file(self, path: &Path) -> io::Result<AccessLog> {
+        let file: File = OpenOptions::new().create(true).append(true).open(path)?;
+        self.lock().push(Box::new(file));
+        Ok(self)
+    }
+
+    /// Write the line for one finished request
+    pub fn record(&self, entry: &Entry) {
+        self.write_line(&entry.format(self.format));
+    }
+
+    /// Write a warning, e.g. a response that couldn't be delivered
+    pub fn warn(&self, message: &str) {
+        let line = match self.format {
+            LogFormat::Combined => format!("WARN {}", message),
+            LogFormat::Json => format!(
+                "{{\"level\":\"warn\",\"message\":{}}}",
+                json_string(Some(message))
+            ),
+        };
+        self.write_line(&line);
+    }
+
+    fn write_line(&self, line: &str) {
+        for sink in self.lock().iter_mut() {
+            // Logging must never take the server down; a full disk just loses log lines
+            let _ = writeln!(sink, "{}", line).and_then(|_| sink.flush());
+        }
+    }
+
+    // A worker that panicked while logging leaves the lock "poisoned"; the writers are still
+    // usable, so carry on with them
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Box<dyn Write + Send>>> {
+        self.sinks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// Common Log Format time: `16/Oct/2026:09:30:00 +0000`, rearranged from the HTTP date
+// `Fri, 16 Oct 2026 09:30:00 GMT` so no date library is needed
+fn clf_time(time: SystemTime) -> String {
+    let http = httpdate::fmt_http_date(time);
+    let parts: Vec<&str> = http.split(' ').collect();
+    match parts.as_slice() {
+        [_, day, month, year, clock, _] => format!("{}/{}/{}:{} +0000", day, month, year, clock),
+        _ => http,
+    }
+}
+
+// A header value for the combined format: `-` when absent, inner quotes escaped
+fn quoted(value: Option<&str>) -> String {
+    value.map_or("-".to_string(), |v| {
+        v.replace('\\', "\\\\").replace('"', "\\\"")
+    })
+}
+
+// A JSON string literal (or `null`), with quotes, backslashes and control characters escaped
+fn json_string(value: Option<&str>) -> String {
+    let Some(value) = value else {
+        return "null".to_string();
+    };
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
@@ -0,0 +1,99 @@
+// Conditional requests: letting the browser ask "has this changed since I last downloaded it?"
+//
+// Every file response carries two "validators":
+//   ETag: "1f4-17c5a8e3b2c4d000"           an opaque version tag (here: size and mtime in hex)
+//   Last-Modified: Tue, 15 Oct 2024 ...    the file's modification time
+// On the next visit the browser sends them back as `If-None-Match` / `If-Modified-Since`.
+// If nothing changed the server answers `304 Not Modified` with no body, and the browser
+// uses its cached copy. The file doesn't even need to be opened: the metadata is enough.
+use std::fs::Metadata;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::compress::Encoding;
+
+/// The validators for one version of a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validators {
+    /// Strong ETag, quotes included: `"<size hex>-<mtime nanos hex>"`
+    pub etag: String,
+    /// Modification time, if the filesystem reports one
+    pub modified: Option<SystemTime>,
+}
+
+impl Validators {
+    /// Validators for a file of `len` bytes last modified at `modified`
+    pub fn new(len: u64, modified: Option<SystemTime>) -> Validators {
+        // Nanosecond precision: two saves in the same second still get different tags
+        let nanos = modified
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_nanos())
+            .unwrap_or(0);
+        Validators {
+            etag: format!("\"{:x}-{:x}\"", len, nanos),
+            modified,
+        }
+    }
+
+    /// Validators from a file's metadata (no need to open the file)
+    pub fn from_metadata(metadata: &Metadata) -> Validators {
+        Validators::new(metadata.len(), metadata.modified().ok())
+    }
+
+    /// The ETag for a compressed copy, e.g. `"1f4-17c5a8e3b2c4d000-gzip"`. A strong ETag promises
+    /// byte-for-byte identical bodies, so the gzip bytes need a tag of their own.
+    pub fn encoded_etag(&self, coding: &str) -> String {
+        format!("{}-{}\"", self.etag.trim_end_matches('"'), coding)
+    }
+
+    /// The `Last-Modified` header value, e.g. `Tue, 15 Oct 2024 08:12:31 GMT`
+    pub fn last_modified(&self) -> Option<String> {
+        self.modified.map(httpdate::fmt_http_date)
+    }
+
+    /// Whether the client's cached copy is still current, given its `If-None-Match` and
+    /// `If-Modified-Since` request headers.
+    ///
+    /// As RFC 9110 requires, `If-Modified-Since` is ignored when `If-None-Match` is present:
+    /// the ETag is the more precise of the two.
+    pub fn not_modified(
+        &self,
+        if_none_match: Option<&str>,
+        if_modified_since: Option<&str>,
+    ) -> bool {
+        if let Some(tags) = if_none_match {
+            return tags.split(',').map(str::trim).any(|tag| {
+                // If-None-Match uses the "weak" comparison: `W/"x"` matches `"x"`. A tag from a
+                // compressed copy matches too: the file behind it is the same.
+                let tag = tag.trim_start_matches("W/");
+                tag == "*"
+                    || tag == self.etag
+                    || Encoding::ALL
+                        .iter()
+                        .any(|encoding| tag == self.encoded_etag(encoding.name()))
+            });
+        }
+        match (
+            if_modified_since.and_then(|v| httpdate::parse_http_date(v).ok()),
+            self.modified,
+        ) {
+            // HTTP dates have one-second resolution, so compare whole seconds: a file modified
+            // at 10:00:00.7 is "not modified since" 10:00:00
+            (Some(since), Some(modified)) => whole_seconds(modified) <= since,
+            // An unparseable date (or no mtime) means "send the file"
+            _ => false,
+        }
+    }
+}
+
+/// The `Cache-Control` header value for a `max-age` in seconds
+pub fn cache_control(max_age: u64) -> String {
+    format!("public, max-age={}", max_age)
+}
+
+// Drop the sub-second part of a timestamp
+fn whole_seconds(time: SystemTime) -> SystemTime {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => UNIX_EPOCH + Duration::from_secs(since.as_secs()),
+        Err(_) => time,
+    }
+}
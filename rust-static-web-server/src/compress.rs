@@ -0,0 +1,101 @@
+// Response compression. Text compresses very well (HTML/JS often shrink to a quarter of their
+// size), so when the browser says it can decompress (`Accept-Encoding: gzip, deflate, br`) we
+// send a compressed copy and label it with `Content-Encoding: gzip`.
+//
+// Images, video and fonts like woff2 are already compressed; squeezing them again costs CPU and
+// saves nothing, so only text-like types are compressed. Tiny files are skipped too: the gzip
+// header alone is ~20 bytes, and below about a kilobyte the saving isn't worth the work.
+use std::io::{self, Write};
+
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+
+/// Files smaller than this many bytes are sent uncompressed
+pub const MIN_COMPRESS_SIZE: u64 = 1024;
+
+/// A content coding the server can produce
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Gzip,
+    /// HTTP's "deflate" is the zlib format (RFC 1950), not raw deflate
+    Deflate,
+}
+
+impl Encoding {
+    /// Every supported coding, in order of preference
+    pub const ALL: [Encoding; 2] = [Encoding::Gzip, Encoding::Deflate];
+
+    /// The token used in `Accept-Encoding` and `Content-Encoding`
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+        }
+    }
+
+    /// Compress `data` with this coding
+    pub fn encode(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        // Both encoders wrap a Vec<u8> and compress whatever is written into them;
+        // `finish` flushes the trailer (checksum, length) and hands the Vec back
+        match self {
+            Encoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Encoding::Deflate => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Pick the coding to use from an `Accept-Encoding` header: gzip if acceptable, else deflate,
+/// else None (send the file as-is).
+///
+/// Entries look like `gzip`, `gzip;q=0.8` or `*`; a `q=0` weight means "not acceptable".
+pub fn negotiate(accept_encoding: Option<&str>) -> Option<Encoding> {
+    let accept = accept_encoding?;
+    // (coding, acceptable) pairs, e.g. [("gzip", true), ("br", true), ("deflate", false)]
+    let entries: Vec<(String, bool)> = accept
+        .split(',')
+        .filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let coding = parts.next()?.trim().to_ascii_lowercase();
+            if coding.is_empty() {
+                return None;
+            }
+            let rejected = parts.any(|param| {
+                param
+                    .trim()
+                    .strip_prefix("q=")
+                    .and_then(|q| q.trim().parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            Some((coding, !rejected))
+        })
+        .collect();
+
+    Encoding::ALL.into_iter().find(|encoding| {
+        // An explicit entry for the coding decides; otherwise `*` does
+        let explicit = entries.iter().find(|(coding, _)| coding == encoding.name());
+        let wildcard = entries.iter().find(|(coding, _)| coding == "*");
+        match (explicit, wildcard) {
+            (Some((_, acceptable)), _) => *acceptable,
+            (None, Some((_, acceptable))) => *acceptable,
+            (None, None) => false,
+        }
+    })
+}
+
+/// Whether a Content-Type is worth compressing: text, JSON, JavaScript, XML and SVG
+pub fn is_compressible(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or("").trim();
+    mime.starts_with("text/")
+        || matches!(
+            mime,
+            "application/javascript" | "application/json" | "application/xml" | "image/svg+xml"
+        )
+}
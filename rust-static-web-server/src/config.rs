@@ -0,0 +1,213 @@
+// Server settings, read once at startup and then shared (read-only) by every worker thread.
+//
+// Each setting comes from, in order of precedence: a command-line flag (`--port 9000`), an
+// environment variable (`PORT=9000`), or the built-in default. Tests call `Config::parse` with
+// their own argument list and variables instead of touching the real process environment,
+// which would leak between tests running in parallel.
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::{worker_count, ErrorPages, LogFormat};
+
+/// Where to listen, what to serve, and how.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// `BIND_ADDR`, default `0.0.0.0` (all interfaces, needed inside containers)
+    pub bind_addr: String,
+    /// `--port` / `PORT`, default 8080. Port 0 asks the OS for any free port.
+    pub port: u16,
+    /// `--root` / `STATIC_ROOT`, default `static`: the directory files are served from.
+    /// Stored canonicalized (absolute, symlinks resolved) whenever the directory exists.
+    pub root: PathBuf,
+    /// `WORKERS`, default one per CPU: threads answering requests
+    pub workers: usize,
+    /// `ENABLE_DIR_LISTING=1`: answer directories without an `index.html` with a page listing
+    /// their contents (instead of a 404)
+    pub dir_listing: bool,
+    /// `CACHE_MAX_AGE` (seconds): sent as `Cache-Control: public, max-age=N` on file responses.
+    /// Unset means no Cache-Control header; browsers still revalidate with ETag/Last-Modified.
+    pub cache_max_age: Option<u64>,
+    /// Gzip/deflate text responses for clients that accept it. On by default;
+    /// `DISABLE_COMPRESSION=1` turns it off.
+    pub compression: bool,
+    /// `404.html` / `403.html` from the root, read when the config is built
+    pub error_pages: ErrorPages,
+    /// `ACCESS_LOG_FORMAT`: `combined` (default) or `json`
+    pub log_format: LogFormat,
+    /// `ACCESS_LOG=path`: also append access log lines to this file
+    pub access_log: Option<PathBuf>,
+}
+
+impl Config {
+    /// Serve `root` with the defaults: compression on, every optional feature off. The root's
+    /// custom error pages, if any, are read here.
+    // `impl Into<PathBuf>` accepts a &str, a String, a &Path or a PathBuf, like an overloaded constructor
+    pub fn new(root: impl Into<PathBuf>) -> Config {
+        let root = root.into();
+        let root = root.canonicalize().unwrap_or(root);
+        Config {
+            bind_addr: "0.0.0.0".to_string(),
+            port: 8080,
+            error_pages: ErrorPages::load(&root),
+            // The handler compares resolved file paths against this, so it must be canonical
+            // too; a root that doesn't exist (yet) is kept as given and rejected by `parse`
+            root,
+            workers: worker_count(None),
+            dir_listing: false,
+            cache_max_age: None,
+            compression: true,
+            log_format: LogFormat::default(),
+            access_log: None,
+        }
+    }
+
+    /// Builder-style setter: `Config::new("static").with_dir_listing(true)`
+    pub fn with_dir_listing(mut self, enabled: bool) -> Config {
+        self.dir_listing = enabled;
+        self
+    }
+
+    /// Builder-style setter for the `Cache-Control: max-age` in seconds
+    pub fn with_cache_max_age(mut self, seconds: Option<u64>) -> Config {
+        self.cache_max_age = seconds;
+        self
+    }
+
+    /// Builder-style setter for response compression
+    pub fn with_compression(mut self, enabled: bool) -> Config {
+        self.compression = enabled;
+        self
+    }
+
+    /// The configuration of this process: its command-line flags and environment variables.
+    pub fn load() -> Result<Config, String> {
+        // `skip(1)` drops the program name, like argv[0] in C
+        Config::parse(std::env::args().skip(1), |name| std::env::var(name).ok())
+    }
+
+    /// Build a configuration from command-line `args` and an environment lookup `env`.
+    ///
+    /// Flags: `--port N` and `--root DIR` (also `--port=N`, `--root=DIR`). Env vars: `BIND_ADDR`,
+    /// `PORT`, `STATIC_ROOT`, `WORKERS`, `ENABLE_DIR_LISTING`, `CACHE_MAX_AGE`,
+    /// `DISABLE_COMPRESSION`, `ACCESS_LOG`, `ACCESS_LOG_FORMAT`. Bad values, unknown flags and a
+    /// static root that isn't an existing directory are errors with a message meant for the
+    /// person starting the server.
+    pub fn parse<I, F>(args: I, env: F) -> Result<Config, String>
+    where
+        I: IntoIterator<Item = String>,
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut port_flag = None;
+        let mut root_flag = None;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // `--port=9000` and `--port 9000` are both accepted
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+            let slot = match name.as_str() {
+                "--port" => &mut port_flag,
+                "--root" => &mut root_flag,
+                _ => {
+                    return Err(format!(
+                        "unknown argument `{}` (usage: [--port N] [--root DIR])",
+                        arg
+                    ))
+                }
+            };
+            let value = inline.or_else(|| args.next());
+            *slot = Some(value.ok_or_else(|| format!("{} needs a value", name))?);
+        }
+
+        let root = root_flag
+            .or_else(|| env("STATIC_ROOT"))
+            .unwrap_or_else(|| "static".to_string());
+        let mut config = Config::new(root);
+        if let Some(addr) = env("BIND_ADDR") {
+            config.bind_addr = addr.trim().to_string();
+        }
+        if let Some(port) = port_flag.or_else(|| env("PORT")) {
+            config.port = port
+                .trim()
+                .parse()
+                .map_err(|_| format!("port `{}` is not a number between 0 and 65535", port))?;
+        }
+        config.workers = worker_count(env("WORKERS").as_deref());
+        config.dir_listing = flag(env("ENABLE_DIR_LISTING").as_deref());
+        if let Some(value) = env("CACHE_MAX_AGE") {
+            config.cache_max_age =
+                Some(value.trim().parse().map_err(|_| {
+                    format!("CACHE_MAX_AGE `{}` is not a number of seconds", value)
+                })?);
+        }
+        config.compression = !flag(env("DISABLE_COMPRESSION").as_deref());
+        if let Some(format) = env("ACCESS_LOG_FORMAT") {
+            config.log_format = LogFormat::parse(&format)?;
+        }
+        config.access_log = env("ACCESS_LOG")
+            .filter(|path| !path.trim().is_empty())
+            .map(PathBuf::from);
+
+        // Fail now with a clear message, not with a 404 for every request later
+        if !config.root.is_dir() {
+            return Err(format!(
+                "static root `{}` is not an existing directory (set STATIC_ROOT or --root)",
+                config.root.display()
+            ));
+        }
+        Ok(config)
+    }
+
+    /// The `host:port` string to bind
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.bind_addr, self.port)
+    }
+}
+
+// Display is what `{}` prints: the one-line summary main logs at startup
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let max_age = match self.cache_max_age {
+            Some(secs) => format!("{}s", secs),
+            None => "off".to_string(),
+        };
+        let mut pages = Vec::new();
+        if self.error_pages.not_found.is_some() {
+            pages.push("404");
+        }
+        if self.error_pages.forbidden.is_some() {
+            pages.push("403");
+        }
+        let pages = if pages.is_empty() {
+            "none".to_string()
+        } else {
+            pages.join(",")
+        };
+        write!(
+            f,
+            "root={} bind={} workers={} dir_listing={} compression={} cache_max_age={} error_pages={} access_log={}{}",
+            self.root.display(),
+            self.address(),
+            self.workers,
+            self.dir_listing,
+            self.compression,
+            max_age,
+            pages,
+            self.log_format.name(),
+            self.access_log
+                .as_ref()
+                .map(|path| format!(" -> {}", path.display()))
+                .unwrap_or_default()
+        )
+    }
+}
+
+/// Whether an on/off env var value means "on": `1`, `true`, `yes` or `on`, in any case.
+pub fn flag(value: Option<&str>) -> bool {
+    // `matches!` is a boolean `match`: true if the value fits one of the patterns
+    matches!(
+        value.map(|v| v.trim().to_ascii_lowercase()).as_deref(),
+        Some("1" | "true" | "yes" | "on")
+    )
+}
@@ -0,0 +1,62 @@
+// Custom error pages. A bare "404 Not Found" string looks broken to visitors, so if the site
+// ships its own `404.html` / `403.html` (in the static root), those are sent instead, with the
+// right status code. They are read once when the configuration is built and kept in memory:
+// error responses then never touch the disk, and editing the files needs a restart.
+use std::fs;
+use std::path::Path;
+
+use tiny_http::{Response, ResponseBox};
+
+use crate::{escapes_root, header};
+
+/// The site's error pages, if it has any.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ErrorPages {
+    /// `<root>/404.html`
+    pub not_found: Option<Vec<u8>>,
+    /// `<root>/403.html`
+    pub forbidden: Option<Vec<u8>>,
+}
+
+impl ErrorPages {
+    /// Read `404.html` and `403.html` from the static root. Missing or unreadable files just
+    /// mean the plain-text fallback is used.
+    pub fn load(root: &Path) -> ErrorPages {
+        ErrorPages {
+            not_found: read_page(root, "404.html"),
+            forbidden: read_page(root, "403.html"),
+        }
+    }
+
+    /// The 404 response: the custom page if there is one, plain text otherwise
+    pub fn not_found(&self) -> ResponseBox {
+        respond(404, self.not_found.as_deref(), "404 Not Found")
+    }
+
+    /// The 403 response: the custom page if there is one, plain text otherwise
+    pub fn forbidden(&self) -> ResponseBox {
+        respond(403, self.forbidden.as_deref(), "403 Forbidden")
+    }
+}
+
+// The file names are fixed, so no part of a request can steer this lookup. The only way out
+// of the root would be a symlink, and `escapes_root` refuses those just as it does for requests.
+fn read_page(root: &Path, name: &str) -> Option<Vec<u8>> {
+    let path = root.join(name);
+    if !path.is_file() || escapes_root(&path, root) {
+        return None;
+    }
+    fs::read(path).ok()
+}
+
+fn respond(status: u16, page: Option<&[u8]>, fallback: &str) -> ResponseBox {
+    match page {
+        Some(page) => Response::from_data(page)
+            .with_status_code(status)
+            .with_header(header("Content-Type", "text/html; charset=utf-8"))
+            .boxed(),
+        None => Response::from_string(fallback)
+            .with_status_code(status)
+            .boxed(),
+    }
+}
@@ -0,0 +1,311 @@
+// The server's request handling, as a library. `main.rs` owns the socket; `serve` runs the
+// accept loop and `handle` turns one request into one response. Splitting it this way means
+// the tests in `tests/` can call `handle` directly with a fake request
+// (`tiny_http::TestRequest`) instead of starting a real server.
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::{Instant, SystemTime};
+
+use tiny_http::{Header, Method, Request, Response, ResponseBox, Server, StatusCode};
+
+pub mod access_log;
+pub mod cache;
+pub mod compress;
+pub mod config;
+pub mod error_page;
+pub mod listing;
+pub mod mime;
+pub mod pool;
+pub mod range;
+pub mod url;
+
+pub use access_log::{AccessLog, Entry, LogFormat};
+pub use cache::Validators;
+pub use compress::{Encoding, MIN_COMPRESS_SIZE};
+pub use config::Config;
+pub use error_page::ErrorPages;
+pub use pool::WorkerPool;
+pub use range::{parse_range, ByteRange, RangeOutcome};
+pub use url::{decode_path, resolve_safe_path, PathError};
+
+/// Accept requests from `server` and answer each one on a pool of `workers` threads using `handler`.
+///
+/// Returns once `server.unblock()` is called (main does that on Ctrl-C), after every request
+/// already accepted has been answered. Taking the handler as a parameter lets tests plug in
+/// their own, e.g. one that is deliberately slow. Each finished request is written to `log`.
+pub fn serve<F>(server: &Server, workers: usize, log: AccessLog, handler: F)
+where
+    F: Fn(&Request) -> ResponseBox + Send + Sync + 'static,
+{
+    let pool = WorkerPool::new(workers, move |request: Request| {
+        let started = Instant::now();
+        let response = handler(&request);
+        // Everything about the request is captured now: `respond` consumes it
+        let mut entry = Entry {
+            time: SystemTime::now(),
+            remote: request.remote_addr().copied(),
+            method: request.method().to_string(),
+            path: request.url().to_string(),
+            version: format!("HTTP/{}", request.http_version()),
+            status: response.status_code().0,
+            bytes: response.data_length().unwrap_or(0),
+            elapsed: Default::default(),
+            referer: header_value(&request, "Referer").map(str::to_string),
+            user_agent: header_value(&request, "User-Agent").map(str::to_string),
+        };
+        // HEAD and 304 responses announce a length but send no body
+        if *request.method() == Method::Head || entry.status == 304 {
+            entry.bytes = 0;
+        }
+        match request.respond(response) {
+            Ok(()) => {
+                entry.elapsed = started.elapsed();
+                entry.time = SystemTime::now();
+                log.record(&entry);
+            }
+            // Usually the client hung up mid-response; worth knowing, not worth crashing over
+            Err(error) => log.warn(&format!(
+                "could not send the response to {} {}: {}",
+                entry.method, entry.path, error
+            )),
+        }
+    });
+    // The accept loop itself never touches the disk: it only hands requests to the pool.
+    // `incoming_requests` ends when the server is unblocked.
+    for request in server.incoming_requests() {
+        pool.dispatch(request);
+    }
+    pool.shutdown();
+}
+
+/// How many workers to run, from the `WORKERS` env var value. Missing, unparseable or zero
+/// values fall back to the number of CPUs (or 1 if that can't be determined).
+pub fn worker_count(value: Option<&str>) -> usize {
+    value
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or_else(|| {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        })
+}
+
+/// Build the response for `request`, serving files from `config.root`.
+///
+/// `ResponseBox` is tiny_http's "any response" type: the body is a boxed `Read`, so a whole file,
+/// a slice of a file and a short error string all fit the same return type.
+///
+/// GET and HEAD are served; any other method gets `405 Method Not Allowed`.
+pub fn handle(request: &Request, config: &Config) -> ResponseBox {
+    let response = match request.method() {
+        Method::Get => respond(request, config),
+        // HEAD is "GET, but only the headers": build the GET response, then drop its body
+        Method::Head => without_body(respond(request, config)),
+        // 405 must say which methods would have worked
+        _ => text(405, "405 Method Not Allowed").with_header(header("Allow", "GET, HEAD")),
+    };
+    // tiny_http switches to chunked encoding (and drops Content-Length) above 32 KB by default.
+    // Every body here has a known length, so always send Content-Length instead: browsers use
+    // it for download progress, and a HEAD response is only useful with it.
+    response.with_chunked_threshold(usize::MAX)
+}
+
+// The GET response for `request`
+fn respond(request: &Request, config: &Config) -> ResponseBox {
+    // Decode `/my%20file.txt` to `/my file.txt` and map it to a path under the root ("/" is
+    // the root directory itself). The traversal check runs on the decoded path, so an encoded
+    // `..` (`%2e%2e`) is caught too.
+    let mut path = match resolve_safe_path(&config.root, request.url()) {
+        Ok(path) => path,
+        Err(PathError::Traversal) => return config.error_pages.forbidden(),
+        Err(PathError::Malformed) => return text(400, "400 Bad Request"),
+    };
+    if escapes_root(&path, &config.root) {
+        return config.error_pages.forbidden();
+    }
+
+    if path.is_dir() {
+        // A directory is served by its index.html; without one, it is either listed or a 404
+        let index = path.join("index.html");
+        if index.is_file() {
+            if escapes_root(&index, &config.root) {
+                return config.error_pages.forbidden();
+            }
+            path = index;
+        } else if config.dir_listing {
+            // The listing shows the decoded URL; it decoded fine just above
+            let url = decode_path(request.url()).unwrap_or_default();
+            return match listing::render(&path, &url) {
+                Ok(page) => Response::from_string(page)
+                    .with_header(header("Content-Type", "text/html; charset=utf-8"))
+                    .boxed(),
+                Err(_) => text(500, "500 Internal Server Error"),
+            };
+        } else {
+            return config.error_pages.not_found();
+        }
+    }
+    if !path.is_file() {
+        return config.error_pages.not_found();
+    }
+    // `?`-style early returns don't work here because the function returns a response, not a
+    // Result, so each failure is matched and turned into a 500 explicitly
+    let metadata = match fs::metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(_) => return text(500, "500 Internal Server Error"),
+    };
+    let file_len = metadata.len();
+
+    let mime = content_type(&path);
+    let range = parse_range(header_value(request, "Range"), file_len);
+    // Compress whole-file responses of text-like files that are big enough, if the client
+    // accepts it. Ranges always refer to the uncompressed bytes, so those are never compressed.
+    let compressible = config.compression && compress::is_compressible(mime);
+    let encoding = if compressible && file_len >= MIN_COMPRESS_SIZE && range == RangeOutcome::Full {
+        compress::negotiate(header_value(request, "Accept-Encoding"))
+    } else {
+        None
+    };
+
+    // Validators and caching headers go on every file response, including 304 and 206
+    let validators = Validators::from_metadata(&metadata);
+    let etag = match encoding {
+        Some(encoding) => validators.encoded_etag(encoding.name()),
+        None => validators.etag.clone(),
+    };
+    let mut caching = vec![header("ETag", &etag)];
+    if let Some(last_modified) = validators.last_modified() {
+        caching.push(header("Last-Modified", &last_modified));
+    }
+    if let Some(max_age) = config.cache_max_age {
+        caching.push(header("Cache-Control", &cache::cache_control(max_age)));
+    }
+    if compressible {
+        // Tells caches (CDNs, proxies) that the body depends on Accept-Encoding, so they
+        // don't hand a gzip body to a client that can't read it
+        caching.push(header("Vary", "Accept-Encoding"));
+    }
+    // The browser's copy is current: answer from the metadata alone, without opening the file
+    if validators.not_modified(
+        header_value(request, "If-None-Match"),
+        header_value(request, "If-Modified-Since"),
+    ) {
+        return with_headers(Response::empty(304).boxed(), caching);
+    }
+
+    let mut file = match File::open(&path) {
+        Ok(file) => file,
+        Err(_) => return text(500, "500 Internal Server Error"),
+    };
+
+    let content_type = header("Content-Type", mime);
+    if let Some(encoding) = encoding {
+        // Compressing needs the whole file in memory; the size check above keeps this to
+        // text assets, which are small next to the images and videos that stream from disk
+        let mut data = Vec::with_capacity(file_len as usize);
+        let compressed = match file
+            .read_to_end(&mut data)
+            .and_then(|_| encoding.encode(&data))
+        {
+            Ok(compressed) => compressed,
+            Err(_) => return text(500, "500 Internal Server Error"),
+        };
+        // from_data sets Content-Length to the compressed size
+        let response = Response::from_data(compressed)
+            .with_header(content_type)
+            .with_header(header("Content-Encoding", encoding.name()))
+            .boxed();
+        return with_headers(response, caching);
+    }
+
+    let response = match range {
+        RangeOutcome::Full => Response::from_file(file)
+            .with_header(content_type)
+            // Tells the browser it may send Range requests for this file next time
+            .with_header(header("Accept-Ranges", "bytes"))
+            .boxed(),
+        RangeOutcome::Partial(range) => {
+            // Jump to the first requested byte, then `take` stops reading after the last one,
+            // so only the slice is ever read from disk (the file is not loaded into memory)
+            if file.seek(SeekFrom::Start(range.start)).is_err() {
+                return text(500, "500 Internal Server Error");
+            }
+            let body = file.take(range.size());
+            Response::new(
+                StatusCode(206),
+                vec![
+                    content_type,
+                    header("Accept-Ranges", "bytes"),
+                    header("Content-Range", &range.content_range(file_len)),
+                ],
+                body,
+                Some(range.size() as usize),
+                None,
+            )
+            .boxed()
+        }
+        // 416 says how big the file really is, so the client can ask again with a valid range
+        RangeOutcome::Unsatisfiable => {
+            return text(416, "416 Range Not Satisfiable")
+                .with_header(header("Content-Range", &format!("bytes */{}", file_len)))
+        }
+    };
+    with_headers(response, caching)
+}
+
+// Belt and braces behind the `..` check: resolve the path the way the OS will (following
+// symlinks) and make sure it still lies inside the canonical static root. This also stops a
+// symlink in the static directory from exposing files elsewhere on the machine.
+// Paths that don't exist can't escape anywhere; they end up as 404s.
+pub(crate) fn escapes_root(path: &Path, root: &Path) -> bool {
+    match path.canonicalize() {
+        Ok(resolved) => !resolved.starts_with(root),
+        Err(_) => false,
+    }
+}
+
+/// Choose a Content-Type based on the file extension (see src/mime.rs)
+pub fn content_type(path: &Path) -> &'static str {
+    static_serve_core::mime_for_path(path)
+}
+
+/// The value of the first request header called `name` (case-insensitive), if any
+pub fn header_value<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+// Header::from_bytes only fails on non-ASCII input; every header built here is plain ASCII
+pub(crate) fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("ASCII header")
+}
+
+// The same status and headers with an empty body. `data_length` is kept, so Content-Length
+// still announces the size of the body a GET would have received.
+fn without_body(response: ResponseBox) -> ResponseBox {
+    Response::new(
+        response.status_code(),
+        response.headers().to_vec(),
+        Box::new(io::empty()) as Box<dyn Read + Send>,
+        response.data_length(),
+        None,
+    )
+}
+
+// Add several headers to a response
+fn with_headers(mut response: ResponseBox, headers: Vec<Header>) -> ResponseBox {
+    for header in headers {
+        response.add_header(header);
+    }
+    response
+}
+
+// A short plain-text response such as "404 Not Found"
+fn text(status: u16, body: &str) -> ResponseBox {
+    Response::from_string(body).with_status_code(status).boxed()
+}
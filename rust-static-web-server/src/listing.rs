@@ -0,0 +1,109 @@
+// Directory listing: an HTML page with one row per entry (name, size, last modified), like the
+// "Index of /" pages Apache and nginx generate. Only used when `Config::dir_listing` is on and
+// the directory has no index.html.
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::url::{encode_path, encode_segment};
+
+// One row of the listing
+struct Entry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+/// Render the listing page for `dir`, which the client requested as the (already decoded)
+/// `url_path`, e.g. `/docs/`.
+///
+/// Links are absolute (`/docs/file.txt`), so they work whether or not the request URL ended in
+/// a slash. Entries whose names start with a dot are left out.
+pub fn render(dir: &Path, url_path: &str) -> io::Result<String> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        // Names that aren't valid UTF-8 can't be put in a link reliably; skip them
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        if name.starts_with('.') {
+            continue;
+        }
+        // `fs::metadata` follows symlinks, so a link to a directory is listed as a directory
+        let metadata = fs::metadata(entry.path())?;
+        entries.push(Entry {
+            name,
+            is_dir: metadata.is_dir(),
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        });
+    }
+    // Directories first, then alphabetically. Comparing tuples compares field by field,
+    // and `!is_dir` puts `false` (a directory) before `true`.
+    entries.sort_by(|a, b| (!a.is_dir, &a.name).cmp(&(!b.is_dir, &b.name)));
+
+    let base = if url_path.ends_with('/') {
+        url_path.to_string()
+    } else {
+        format!("{}/", url_path)
+    };
+    let title = format!("Index of {}", escape_html(&base));
+
+    let mut rows = String::new();
+    if base != "/" {
+        // The parent directory, spelled out rather than "../" (which the server rejects)
+        let parent = match base.trim_end_matches('/').rfind('/') {
+            Some(i) => &base[..=i],
+            None => "/",
+        };
+        rows.push_str(&format!(
+            "<tr><td><a href=\"{}\">Parent directory</a></td><td></td><td></td></tr>\n",
+            escape_html(&encode_path(parent))
+        ));
+    }
+    for entry in &entries {
+        let slash = if entry.is_dir { "/" } else { "" };
+        let size = if entry.is_dir {
+            "-".to_string()
+        } else {
+            entry.size.to_string()
+        };
+        let modified = entry
+            .modified
+            .map(httpdate::fmt_http_date)
+            .unwrap_or_default();
+        rows.push_str(&format!(
+            "<tr><td><a href=\"{}{}{}\">{}{}</a></td><td>{}</td><td>{}</td></tr>\n",
+            escape_html(&encode_path(&base)),
+            escape_html(&encode_segment(&entry.name)),
+            slash,
+            escape_html(&entry.name),
+            slash,
+            size,
+            modified
+        ));
+    }
+
+    Ok(format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{title}</title></head>\n<body>\n<h1>{title}</h1>\n<table>\n<tr><th>Name</th><th>Size</th><th>Last modified</th></tr>\n{rows}</table>\n</body>\n</html>\n"
+    ))
+}
+
+/// Escape the characters that are special in HTML text and attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
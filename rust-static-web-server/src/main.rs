@@ -1,9 +1,9 @@
-// Import the File type for reading files from the filesystem
-use std::fs::File;
-// Import Path for checking and manipulating filesystem paths
-use std::path::Path;
-// Import types from the tiny_http crate used to run a basic HTTP server
-use tiny_http::{Server, Response, Header};
+// Arc ("atomically reference-counted") lets the Ctrl-C handler and main share the server
+use std::sync::Arc;
+// Import the Server type from the tiny_http crate used to run a basic HTTP server
+use tiny_http::Server;
+// The request handler and the worker-pool accept loop live in src/lib.rs
+use rust_static_web_server::{handle, serve, AccessLog, Config};
 
 // Rust-focused notes for developers coming from Java/JavaScript/Python:
 // - Ownership & borrowing: Rust enforces ownership rules at compile time. Values have a single owner
@@ -19,83 +19,62 @@ use tiny_http::{Server, Response, Header};
 //   custom enums). It replaces many imperative try/catch patterns with clear, explicit branches.
 // - Borrowed references (`&str`, `&Path`, etc.): methods that return references give you a borrow
 //   tied to the lifetime of the source. You don't copy data unless you explicitly clone it.
-// - Ignoring Results: using `let _ = request.respond(response);` intentionally drops the Result to
-//   avoid unused-result warnings, but it also ignores potential errors. Better to log or handle them
-//   when reliability matters.
+// - Ignoring Results: `let _ = ...;` intentionally drops a Result to avoid unused-result warnings,
+//   but it also ignores potential errors. Better to log or handle them when reliability matters
+//   (see how `serve` in src/lib.rs logs a failed `request.respond(...)` as a warning).
 
 // The program entry point
 fn main() {
-    // Create an HTTP server bound to 0.0.0.0:8080 so it is reachable from other machines/containers
-    // The call returns a Result; unwrap will panic if binding fails
-    let server = Server::http("0.0.0.0:8080").unwrap();
-    // Print a startup message to stdout
-    println!("Server started on http://0.0.0.0:8080");
-
-    // Iterate over incoming HTTP requests; this blocks and yields each request as it arrives
-    for request in server.incoming_requests() {
-        // Extract the requested URL path (e.g. "/" or "/style.css")
-        let url = request.url();
-        // Log the HTTP method and URL to stdout for debugging
-        println!("Received request: {} {}", request.method(), url);
+    // Settings come from --port/--root flags, env vars (PORT, STATIC_ROOT, ...) or defaults
+    // (0.0.0.0:8080 serving ./static). A bad setting, or a static root that doesn't exist,
+    // stops the server before it starts rather than misbehaving later.
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("Invalid configuration: {}", message);
+            std::process::exit(1);
+        }
+    };
 
-        // Decide which file to serve based on the requested URL
-        let file_path = if url == "/" {
-            // Serve the default index file when the root path is requested
-            "static/index.html".to_string()
-        } else {
-            // Security check: reject requests that attempt directory traversal
-            if url.contains("..") {
-                // Build a 403 Forbidden response if traversal is detected
-                let response = Response::from_string("403 Forbidden").with_status_code(403);
-                // Send the response to the client and ignore the result
-                let _ = request.respond(response);
-                // Skip further processing for this request
-                continue;
+    // Create an HTTP server bound to the configured address (0.0.0.0 by default, so it is
+    // reachable from other machines/containers). Binding fails if the port is already taken.
+    let server = match Server::http(config.address()) {
+        Ok(server) => Arc::new(server),
+        Err(error) => {
+            eprintln!("Could not listen on {}: {}", config.address(), error);
+            std::process::exit(1);
+        }
+    };
+    // One access log line per request goes to stdout, and to the ACCESS_LOG file if set
+    let mut log = AccessLog::stdout(config.log_format);
+    if let Some(path) = &config.access_log {
+        log = match log.tee_to_file(path) {
+            Ok(log) => log,
+            Err(error) => {
+                eprintln!("Could not open access log {}: {}", path.display(), error);
+                std::process::exit(1);
             }
-            // Map the URL path to a file under the static/ directory
-            format!("static{}", url)
         };
+    }
 
-        // Create a Path object for filesystem checks
-        let path = Path::new(&file_path);
+    // Print a startup message with the resolved configuration to stdout
+    println!("Server started on http://{}", config.address());
+    println!("Configuration: {}", config);
 
-        // If the path exists and is a file, try to open and serve it
-        if path.exists() && path.is_file() {
-            match File::open(&path) {
-                Ok(file) => {
-                    // Choose a Content-Type header based on the file extension
-                    let content_type = if file_path.ends_with(".html") {
-                        "text/html"
-                    } else if file_path.ends_with(".css") {
-                        "text/css"
-                    } else if file_path.ends_with(".js") {
-                        "application/javascript"
-                    } else if file_path.ends_with(".png") {
-                        "image/png"
-                    } else if file_path.ends_with(".jpg") || file_path.ends_with(".jpeg") {
-                        "image/jpeg"
-                    } else {
-                        // Fallback content type for unknown extensions
-                        "text/plain"
-                    };
+    // On Ctrl-C (or SIGTERM from `docker stop`), unblock the accept loop so `serve` returns.
+    // The closure runs on a separate signal-handling thread, hence the Arc clone.
+    let signalled = Arc::clone(&server);
+    ctrlc::set_handler(move || {
+        println!("Shutting down, finishing in-flight requests...");
+        signalled.unblock();
+    })
+    .expect("failed to install the Ctrl-C handler");
 
-                    // Create a Content-Type header from bytes and unwrap the Result
-                    let header = Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).unwrap();
-                    // Build a response that streams the opened file and attach the header
-                    let response = Response::from_file(file).with_header(header);
-                    // Send the response to the client and ignore the result
-                    let _ = request.respond(response);
-                },
-                Err(_) => {
-                    // If the file could not be opened, return a 500 Internal Server Error
-                    let response = Response::from_string("500 Internal Server Error").with_status_code(500);
-                    let _ = request.respond(response);
-                }
-            }
-        } else {
-            // If the file does not exist, return a 404 Not Found
-            let response = Response::from_string("404 Not Found").with_status_code(404);
-            let _ = request.respond(response);
-        }
-    }
-}
\ No newline at end of file
+    // Each worker lets the library decide what to send (see src/lib.rs) and logs the outcome.
+    // `move` gives the closure its own copy of `config`, shared by all workers.
+    let workers = config.workers;
+    serve(&server, workers, log, move |request| {
+        handle(request, &config)
+    });
+    println!("Server stopped");
+}
@@ -0,0 +1,5 @@
+// File extension -> MIME type. The table itself lives in the shared `static-serve-core` crate,
+// so this server and rust-docker-server send the same Content-Type for the same file; this
+// module keeps the names the rest of the server (and its tests) already use.
+
+pub use static_serve_core::mime::{mime_for_extension as from_extension, DEFAULT_MIME};
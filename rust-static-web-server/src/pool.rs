@@ -0,0 +1,108 @@
+// A fixed pool of worker threads. The accept loop in `serve` only receives requests and drops
+// them into a channel; the workers take them out and do the slow part (disk IO, writing the
+// response). One slow client then only ties up one worker instead of the whole server.
+//
+// This is the classic thread pool from Java's `Executors.newFixedThreadPool`, built by hand:
+// an mpsc ("multi-producer, single-consumer") channel is the work queue, and because only one
+// thread may own the receiving end, the workers share it behind an `Arc<Mutex<...>>`.
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use tiny_http::Request;
+
+/// Worker threads that each take a `Request` from a shared queue and pass it to a handler.
+pub struct WorkerPool {
+    // `Option` so `shutdown` can drop the sender (closing the channel) before joining workers
+    sender: Option<Sender<Request>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    /// Start `size` workers (at least one) that each call `handler` for every request they receive.
+    ///
+    /// The handler is shared by all workers, so it must be `Send + Sync` (safe to move to and
+    /// call from other threads) and `'static` (it may not borrow anything from the caller's stack).
+    pub fn new<F>(size: usize, handler: F) -> WorkerPool
+    where
+        F: Fn(Request) + Send + Sync + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<Request>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let handler = Arc::new(handler);
+
+        let workers = (0..size.max(1))
+            .map(|id| {
+                let receiver = Arc::clone(&receiver);
+                let handler = Arc::clone(&handler);
+                thread::Builder::new()
+                    .name(format!("worker-{}", id))
+                    .spawn(move || work(&receiver, &*handler))
+                    .expect("failed to spawn worker thread")
+            })
+            .collect();
+
+        WorkerPool {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    /// Number of worker threads
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Queue a request for the next free worker
+    pub fn dispatch(&self, request: Request) {
+        if let Some(sender) = &self.sender {
+            // Sending only fails if every worker has died (a panicking handler); the request is
+            // then dropped, which closes its connection
+            if sender.send(request).is_err() {
+                eprintln!("No workers left to handle the request");
+            }
+        }
+    }
+
+    /// Stop accepting work and wait for the workers to finish everything already queued.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        // Dropping the only sender closes the channel: each worker's `recv` keeps returning the
+        // requests still queued, then returns Err, which ends its loop
+        drop(self.sender.take());
+        for worker in self.workers.drain(..) {
+            if worker.join().is_err() {
+                eprintln!("A worker thread panicked");
+            }
+        }
+    }
+}
+
+// Drop is Rust's destructor. Stopping here too means a pool that goes out of scope (e.g. when
+// `serve` returns early) still lets in-flight responses finish instead of killing them.
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// The body of each worker thread
+fn work(receiver: &Mutex<Receiver<Request>>, handler: &(dyn Fn(Request) + Send + Sync)) {
+    loop {
+        // The lock guard is a temporary, so the mutex is released at the end of this statement,
+        // before the (possibly slow) handler runs; other workers can pick up requests meanwhile
+        let next = match receiver.lock() {
+            Ok(receiver) => receiver.recv(),
+            // Another worker panicked while holding the lock; keep going with the data anyway
+            Err(poisoned) => poisoned.into_inner().recv(),
+        };
+        match next {
+            Ok(request) => handler(request),
+            // The channel is closed and empty: the pool is shutting down
+            Err(_) => break,
+        }
+    }
+}
@@ -0,0 +1,6 @@
+// HTTP range requests: `Range: bytes=start-end` asks for a slice of a file instead of all of it.
+// The parser lives in the shared `static-serve-core` crate, so this server and
+// rust-docker-server read a header the same way; `handle` in lib.rs turns the outcome into
+// a 200, 206 or 416.
+
+pub use static_serve_core::range::{parse_range, ByteRange, RangeOutcome};
@@ -0,0 +1,31 @@
+// Turning the request target (`/my%20file.txt?v=2`) into a path we can look up on disk, and back.
+//
+// URLs can only contain a limited set of ASCII characters; anything else is "percent-encoded"
+// as %XX per byte of its UTF-8 form, so a space becomes %20 and "é" becomes %C3%A9. The path
+// has to be decoded before it names a real file, and the traversal check has to run on the
+// decoded form, or `/%2e%2e/secret` would sneak a `..` past it.
+
+// Decoding (and the traversal check) is shared with rust-docker-server via `static-serve-core`
+pub use static_serve_core::path::{decode_path, resolve_safe_path, PathError};
+
+/// Percent-encode one path segment (e.g. a file name): every byte except the unreserved
+/// characters of RFC 3986 (letters, digits, `-._~`) is written as `%XX`.
+pub fn encode_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Percent-encode a decoded path, keeping its `/` separators
+pub fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(encode_segment)
+        .collect::<Vec<_>>()
+        .join("/")
+}
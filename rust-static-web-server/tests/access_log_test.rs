@@ -0,0 +1,212 @@
+//! Access logging: one line per request with its status, size and timing, in the combined or
+//! JSON format, optionally copied to a file.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+use rust_static_web_server::{handle, serve, AccessLog, Config, Entry, LogFormat};
+use static_serve_core::test_support::TempSite;
+use tiny_http::Server;
+
+/// An in-memory log the test can read after the server has written to it
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Captured {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+const FILES: &[(&str, &str)] = &[("index.html", "<h1>home</h1>")];
+
+fn get(addr: SocketAddr, path: &str) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: localhost\r\nUser-Agent: log-test\r\nConnection: close\r\n\r\n",
+        path
+    )
+    .unwrap();
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).unwrap();
+}
+
+/// Serve `root`, GET each path in turn, then stop the server so every line has been written
+fn run(root: &Path, log: AccessLog, paths: &[&str]) {
+    let config = Config::new(root).with_compression(false);
+    let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+    let addr = server.server_addr().to_ip().unwrap();
+    let running = Arc::clone(&server);
+    let thread =
+        thread::spawn(move || serve(&running, 1, log, move |request| handle(request, &config)));
+    for path in paths {
+        get(addr, path);
+    }
+    server.unblock();
+    thread.join().unwrap();
+}
+
+#[test]
+fn combined_lines_for_a_200_and_a_404() {
+    let root = TempSite::with_files("combined", FILES);
+    let captured = Captured::default();
+    run(
+        &root,
+        AccessLog::to_writer(LogFormat::Combined, captured.clone()),
+        &["/index.html", "/missing.txt"],
+    );
+
+    let lines = captured.lines();
+    assert_eq!(lines.len(), 2, "{:?}", lines);
+    assert!(lines[0].starts_with("127.0.0.1 - - ["), "{}", lines[0]);
+    assert!(
+        lines[0].contains("\"GET /index.html HTTP/1.1\" 200 13 \"-\" \"log-test\" "),
+        "{}",
+        lines[0]
+    );
+    assert!(
+        lines[1].contains("\"GET /missing.txt HTTP/1.1\" 404 "),
+        "{}",
+        lines[1]
+    );
+    for line in &lines {
+        assert!(line.ends_with("us"), "{}", line);
+    }
+}
+
+#[test]
+fn json_lines_for_a_200_and_a_404() {
+    let root = TempSite::with_files("json", FILES);
+    let captured = Captured::default();
+    run(
+        &root,
+        AccessLog::to_writer(LogFormat::Json, captured.clone()),
+        &["/index.html", "/missing.txt"],
+    );
+
+    let lines = captured.lines();
+    assert_eq!(lines.len(), 2, "{:?}", lines);
+    assert!(lines[0].starts_with('{') && lines[0].ends_with('}'));
+    assert!(lines[0].contains("\"method\":\"GET\",\"path\":\"/index.html\""));
+    assert!(lines[0].contains("\"status\":200,\"bytes\":13,\"elapsed_us\":"));
+    assert!(lines[0].contains("\"user_agent\":\"log-test\""));
+    assert!(lines[0].contains("\"referer\":null"));
+    assert!(lines[1].contains("\"path\":\"/missing.txt\""));
+    assert!(lines[1].contains("\"status\":404"));
+}
+
+#[test]
+fn tees_to_a_file() {
+    let root = TempSite::with_files("tee", FILES);
+    let file = root.join("access.log");
+    let captured = Captured::default();
+    let log = AccessLog::to_writer(LogFormat::Combined, captured.clone())
+        .tee_to_file(&file)
+        .unwrap();
+    run(&root, log, &["/index.html"]);
+
+    let written = fs::read_to_string(&file).unwrap();
+    assert_eq!(written.lines().collect::<Vec<_>>(), captured.lines());
+    assert!(written.contains("\"GET /index.html HTTP/1.1\" 200 13"));
+}
+
+#[test]
+fn disabled_log_writes_nothing() {
+    let root = TempSite::with_files("disabled", FILES);
+    // Nothing to capture: this only checks serving still works without any sink
+    run(&root, AccessLog::disabled(), &["/index.html"]);
+}
+
+fn entry() -> Entry {
+    Entry {
+        time: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        remote: Some("10.0.0.7:52000".parse().unwrap()),
+        method: "GET".to_string(),
+        path: "/a\"b".to_string(),
+        version: "HTTP/1.1".to_string(),
+        status: 206,
+        bytes: 10,
+        elapsed: Duration::from_micros(1234),
+        referer: Some("https://example.com/".to_string()),
+        user_agent: None,
+    }
+}
+
+#[test]
+fn formats_an_entry() {
+    assert_eq!(
+        entry().format(LogFormat::Combined),
+        "10.0.0.7 - - [14/Nov/2023:22:13:20 +0000] \"GET /a\"b HTTP/1.1\" 206 10 \"https://example.com/\" \"-\" 1234us"
+    );
+    assert_eq!(
+        entry().format(LogFormat::Json),
+        "{\"time\":\"Tue, 14 Nov 2023 22:13:20 GMT\",\"remote\":\"10.0.0.7\",\"method\":\"GET\",\"path\":\"/a\\\"b\",\"version\":\"HTTP/1.1\",\"status\":206,\"bytes\":10,\"elapsed_us\":1234,\"referer\":\"https://example.com/\",\"user_agent\":null}"
+    );
+}
+
+#[test]
+fn warnings_match_the_format() {
+    let captured = Captured::default();
+    AccessLog::to_writer(LogFormat::Combined, captured.clone()).warn("client went away");
+    AccessLog::to_writer(LogFormat::Json, captured.clone()).warn("said \"bye\"");
+    assert_eq!(
+        captured.lines(),
+        vec![
+            "WARN client went away".to_string(),
+            "{\"level\":\"warn\",\"message\":\"said \\\"bye\\\"\"}".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn parses_the_format_setting() {
+    assert_eq!(LogFormat::parse("combined"), Ok(LogFormat::Combined));
+    assert_eq!(LogFormat::parse(" JSON "), Ok(LogFormat::Json));
+    assert!(LogFormat::parse("xml").unwrap_err().contains("xml"));
+}
+
+#[test]
+fn config_reads_the_log_settings() {
+    let root = TempSite::with_files("config", FILES);
+    let root_str = root.to_str().unwrap().to_string();
+    let env = |name: &str| match name {
+        "STATIC_ROOT" => Some(root_str.clone()),
+        "ACCESS_LOG" => Some("/var/log/static.log".to_string()),
+        "ACCESS_LOG_FORMAT" => Some("json".to_string()),
+        _ => None,
+    };
+    let config = Config::parse(Vec::new(), env).unwrap();
+    assert_eq!(config.log_format, LogFormat::Json);
+    assert_eq!(
+        config.access_log,
+        Some(PathBuf::from("/var/log/static.log"))
+    );
+
+    let bad = |name: &str| match name {
+        "STATIC_ROOT" => Some(root_str.clone()),
+        "ACCESS_LOG_FORMAT" => Some("xml".to_string()),
+        _ => None,
+    };
+    assert!(Config::parse(Vec::new(), bad).is_err());
+}
@@ -0,0 +1,156 @@
+//! Caching headers and conditional requests: ETag, Last-Modified, Cache-Control and 304s.
+
+use std::io::Read;
+use std::time::{Duration, UNIX_EPOCH};
+
+use rust_static_web_server::{handle, Config, Validators};
+use static_serve_core::test_support::TempSite;
+use tiny_http::{Header, ResponseBox, TestRequest};
+
+const FILES: &[(&str, &str)] = &[("app.js", "console.log('hi');")];
+
+fn get(config: &Config, headers: &[(&str, &str)]) -> ResponseBox {
+    let mut request = TestRequest::new().with_path("/app.js");
+    for (name, value) in headers {
+        request = request.with_header(Header::from_bytes(*name, *value).unwrap());
+    }
+    handle(&request.into(), config)
+}
+
+fn header(response: &ResponseBox, name: &'static str) -> Option<String> {
+    response
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.to_string())
+}
+
+fn body(response: ResponseBox) -> String {
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body).unwrap();
+    body
+}
+
+#[test]
+fn file_responses_carry_validators() {
+    let root = TempSite::with_files("validators", FILES);
+    let response = get(&Config::new(root.path()), &[]);
+    assert_eq!(response.status_code().0, 200);
+    let etag = header(&response, "ETag").unwrap();
+    assert!(etag.starts_with("\"12-") && etag.ends_with('"'), "{}", etag);
+    assert!(header(&response, "Last-Modified")
+        .unwrap()
+        .ends_with(" GMT"));
+    // No max-age configured, no Cache-Control
+    assert_eq!(header(&response, "Cache-Control"), None);
+}
+
+#[test]
+fn matching_etag_is_304_without_a_body() {
+    let root = TempSite::with_files("etag", FILES);
+    let config = Config::new(root.path());
+    let etag = header(&get(&config, &[]), "ETag").unwrap();
+
+    for value in [
+        etag.clone(),
+        format!("W/{}", etag),
+        format!("\"nope\", {}", etag),
+        "*".into(),
+    ] {
+        let response = get(&config, &[("If-None-Match", &value)]);
+        assert_eq!(response.status_code().0, 304, "{}", value);
+        assert_eq!(header(&response, "ETag").as_ref(), Some(&etag));
+        assert_eq!(response.data_length(), Some(0));
+        assert_eq!(body(response), "");
+    }
+}
+
+#[test]
+fn stale_etag_gets_the_file() {
+    let root = TempSite::with_files("stale", FILES);
+    let config = Config::new(root.path());
+    let old = header(&get(&config, &[]), "ETag").unwrap();
+    // Same mtime second is possible, but the size changes the tag either way
+    root.write("app.js", "console.log('changed');");
+
+    let response = get(&config, &[("If-None-Match", &old)]);
+    assert_eq!(response.status_code().0, 200);
+    assert_ne!(header(&response, "ETag"), Some(old));
+    assert_eq!(body(response), "console.log('changed');");
+}
+
+#[test]
+fn if_modified_since() {
+    let root = TempSite::with_files("since", FILES);
+    let config = Config::new(root.path());
+    let last_modified = header(&get(&config, &[]), "Last-Modified").unwrap();
+
+    // The exact Last-Modified value, or any later date: not modified
+    for since in [last_modified.as_str(), "Fri, 01 Jan 2100 00:00:00 GMT"] {
+        let response = get(&config, &[("If-Modified-Since", since)]);
+        assert_eq!(response.status_code().0, 304, "{}", since);
+        assert_eq!(body(response), "");
+    }
+    // An earlier date or garbage: send the file
+    for since in ["Thu, 01 Jan 1970 00:00:00 GMT", "yesterday"] {
+        let response = get(&config, &[("If-Modified-Since", since)]);
+        assert_eq!(response.status_code().0, 200, "{}", since);
+        assert_eq!(body(response), "console.log('hi');");
+    }
+}
+
+#[test]
+fn etag_wins_over_if_modified_since() {
+    let root = TempSite::with_files("precedence", FILES);
+    let response = get(
+        &Config::new(root.path()),
+        &[
+            ("If-None-Match", "\"something-else\""),
+            ("If-Modified-Since", "Fri, 01 Jan 2100 00:00:00 GMT"),
+        ],
+    );
+    assert_eq!(response.status_code().0, 200);
+}
+
+#[test]
+fn cache_control_max_age_is_configurable() {
+    let root = TempSite::with_files("max-age", FILES);
+    let config = Config::new(root.path()).with_cache_max_age(Some(3600));
+    let response = get(&config, &[]);
+    assert_eq!(
+        header(&response, "Cache-Control").as_deref(),
+        Some("public, max-age=3600")
+    );
+    // ...on 304s and partial responses too
+    let etag = header(&response, "ETag").unwrap();
+    let not_modified = get(&config, &[("If-None-Match", &etag)]);
+    assert_eq!(
+        header(&not_modified, "Cache-Control").as_deref(),
+        Some("public, max-age=3600")
+    );
+    let partial = get(&config, &[("Range", "bytes=0-6")]);
+    assert_eq!(partial.status_code().0, 206);
+    assert_eq!(
+        header(&partial, "Cache-Control").as_deref(),
+        Some("public, max-age=3600")
+    );
+    assert_eq!(header(&partial, "ETag"), Some(etag));
+}
+
+#[test]
+fn validators_from_size_and_mtime() {
+    let modified = UNIX_EPOCH + Duration::new(1_700_000_000, 500);
+    let validators = Validators::new(255, Some(modified));
+    assert_eq!(
+        validators.etag,
+        format!("\"ff-{:x}\"", 1_700_000_000_000_000_500u128)
+    );
+    assert_eq!(
+        validators.last_modified().as_deref(),
+        Some("Tue, 14 Nov 2023 22:13:20 GMT")
+    );
+    // Sub-second mtimes still match their whole-second HTTP date
+    assert!(validators.not_modified(None, Some("Tue, 14 Nov 2023 22:13:20 GMT")));
+    assert!(!validators.not_modified(None, Some("Tue, 14 Nov 2023 22:13:19 GMT")));
+    assert!(!Validators::new(1, None).not_modified(None, Some("Tue, 14 Nov 2023 22:13:20 GMT")));
+}
@@ -0,0 +1,185 @@
+//! Response compression: gzip/deflate for text when accepted, and every case where it's skipped.
+
+use std::io::Read;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use rust_static_web_server::{compress::negotiate, handle, Config, Encoding};
+use static_serve_core::test_support::TempSite;
+use tiny_http::{Header, ResponseBox, TestRequest};
+
+fn page() -> String {
+    "<p>Hello, compressible world!</p>\n".repeat(200)
+}
+
+/// Two copies of the compressible page, one under a type that isn't compressed, and a file
+/// too small to be worth compressing
+fn files() -> [(&'static str, String); 3] {
+    [
+        ("index.html", page()),
+        ("photo.png", page()),
+        ("tiny.css", "body { margin: 0 }".to_string()),
+    ]
+}
+
+fn get(config: &Config, path: &str, headers: &[(&str, &str)]) -> ResponseBox {
+    let mut request = TestRequest::new().with_path(path);
+    for (name, value) in headers {
+        request = request.with_header(Header::from_bytes(*name, *value).unwrap());
+    }
+    handle(&request.into(), config)
+}
+
+fn header(response: &ResponseBox, name: &'static str) -> Option<String> {
+    response
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.to_string())
+}
+
+fn bytes(response: ResponseBox) -> Vec<u8> {
+    let mut body = Vec::new();
+    response.into_reader().read_to_end(&mut body).unwrap();
+    body
+}
+
+#[test]
+fn gzip_body_decompresses_to_the_original() {
+    let root = TempSite::with_files("gzip", &files());
+    let response = get(
+        &Config::new(root.path()),
+        "/",
+        &[("Accept-Encoding", "gzip, deflate, br")],
+    );
+    assert_eq!(response.status_code().0, 200);
+    assert_eq!(
+        header(&response, "Content-Encoding").as_deref(),
+        Some("gzip")
+    );
+    assert_eq!(
+        header(&response, "Content-Type").as_deref(),
+        Some("text/html")
+    );
+    assert_eq!(
+        header(&response, "Vary").as_deref(),
+        Some("Accept-Encoding")
+    );
+    assert!(header(&response, "ETag").unwrap().ends_with("-gzip\""));
+
+    let length = response.data_length().unwrap();
+    let compressed = bytes(response);
+    // Content-Length is the compressed size, and it really is smaller
+    assert_eq!(length, compressed.len());
+    assert!(
+        compressed.len() < page().len() / 4,
+        "{} bytes",
+        compressed.len()
+    );
+
+    let mut original = String::new();
+    GzDecoder::new(&compressed[..])
+        .read_to_string(&mut original)
+        .unwrap();
+    assert_eq!(original, page());
+}
+
+#[test]
+fn deflate_when_gzip_is_not_accepted() {
+    let root = TempSite::with_files("deflate", &files());
+    let response = get(
+        &Config::new(root.path()),
+        "/index.html",
+        &[("Accept-Encoding", "deflate")],
+    );
+    assert_eq!(
+        header(&response, "Content-Encoding").as_deref(),
+        Some("deflate")
+    );
+    let mut original = String::new();
+    ZlibDecoder::new(&bytes(response)[..])
+        .read_to_string(&mut original)
+        .unwrap();
+    assert_eq!(original, page());
+}
+
+#[test]
+fn no_accept_encoding_sends_the_plain_file() {
+    let root = TempSite::with_files("plain", &files());
+    let response = get(&Config::new(root.path()), "/index.html", &[]);
+    assert_eq!(header(&response, "Content-Encoding"), None);
+    // Still varies: a cache must not reuse this for a gzip-capable client blindly
+    assert_eq!(
+        header(&response, "Vary").as_deref(),
+        Some("Accept-Encoding")
+    );
+    assert_eq!(bytes(response), page().into_bytes());
+}
+
+#[test]
+fn compression_is_skipped_where_it_does_not_help() {
+    let root = TempSite::with_files("skipped", &files());
+    let config = Config::new(root.path());
+    let gzip = [("Accept-Encoding", "gzip")];
+    // Already-compressed type
+    let png = get(&config, "/photo.png", &gzip);
+    assert_eq!(header(&png, "Content-Encoding"), None);
+    assert_eq!(header(&png, "Vary"), None);
+    // Under the size threshold
+    let tiny = get(&config, "/tiny.css", &gzip);
+    assert_eq!(header(&tiny, "Content-Encoding"), None);
+    assert_eq!(bytes(tiny), b"body { margin: 0 }");
+    // Ranges refer to the uncompressed bytes
+    let partial = get(
+        &config,
+        "/index.html",
+        &[("Accept-Encoding", "gzip"), ("Range", "bytes=0-2")],
+    );
+    assert_eq!(partial.status_code().0, 206);
+    assert_eq!(header(&partial, "Content-Encoding"), None);
+    assert_eq!(bytes(partial), b"<p>");
+    // q=0 means "not acceptable"
+    let refused = get(
+        &config,
+        "/index.html",
+        &[("Accept-Encoding", "gzip;q=0, deflate;q=0")],
+    );
+    assert_eq!(header(&refused, "Content-Encoding"), None);
+}
+
+#[test]
+fn disable_compression_escape_hatch() {
+    let root = TempSite::with_files("disabled", &files());
+    let config = Config::new(root.path()).with_compression(false);
+    let response = get(&config, "/index.html", &[("Accept-Encoding", "gzip")]);
+    assert_eq!(header(&response, "Content-Encoding"), None);
+    assert_eq!(header(&response, "Vary"), None);
+    assert_eq!(bytes(response), page().into_bytes());
+}
+
+#[test]
+fn compressed_etag_revalidates() {
+    let root = TempSite::with_files("etag", &files());
+    let config = Config::new(root.path());
+    let gzip = ("Accept-Encoding", "gzip");
+    let etag = header(&get(&config, "/index.html", &[gzip]), "ETag").unwrap();
+    let response = get(&config, "/index.html", &[gzip, ("If-None-Match", &etag)]);
+    assert_eq!(response.status_code().0, 304);
+    assert_eq!(header(&response, "ETag"), Some(etag));
+}
+
+#[test]
+fn accept_encoding_negotiation() {
+    assert_eq!(negotiate(None), None);
+    assert_eq!(negotiate(Some("")), None);
+    assert_eq!(negotiate(Some("br")), None);
+    assert_eq!(negotiate(Some("gzip")), Some(Encoding::Gzip));
+    assert_eq!(negotiate(Some("deflate, gzip")), Some(Encoding::Gzip));
+    assert_eq!(negotiate(Some("GZIP;q=0.5")), Some(Encoding::Gzip));
+    assert_eq!(
+        negotiate(Some("gzip;q=0, deflate")),
+        Some(Encoding::Deflate)
+    );
+    assert_eq!(negotiate(Some("*")), Some(Encoding::Gzip));
+    assert_eq!(negotiate(Some("*, gzip;q=0")), Some(Encoding::Deflate));
+    assert_eq!(negotiate(Some("identity")), None);
+}
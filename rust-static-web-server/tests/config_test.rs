@@ -0,0 +1,163 @@
+//! Startup configuration: flags and env vars, validation, a real server on an ephemeral port,
+//! and the canonical-root check.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::thread;
+
+use rust_static_web_server::{handle, serve, AccessLog, Config};
+use static_serve_core::test_support::TempSite;
+use tiny_http::{Server, TestRequest};
+
+const FILES: &[(&str, &str)] = &[
+    ("public/index.html", "<h1>home</h1>"),
+    ("secret.txt", "top secret"),
+];
+
+fn parse(args: &[&str], env: &[(&str, &str)]) -> Result<Config, String> {
+    let env: HashMap<String, String> = env
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    Config::parse(args.iter().map(|a| a.to_string()), |name| {
+        env.get(name).cloned()
+    })
+}
+
+#[test]
+fn env_vars_override_defaults_and_flags_override_env() {
+    let root = TempSite::with_files("precedence", FILES);
+    let public = root.join("public");
+    let public = public.to_str().unwrap();
+
+    let config = parse(&[], &[("STATIC_ROOT", public)]).unwrap();
+    assert_eq!(config.address(), "0.0.0.0:8080");
+    assert_eq!(config.root, fs::canonicalize(public).unwrap());
+
+    let config = parse(
+        &[],
+        &[
+            ("STATIC_ROOT", public),
+            ("BIND_ADDR", "127.0.0.1"),
+            ("PORT", "9000"),
+        ],
+    )
+    .unwrap();
+    assert_eq!(config.address(), "127.0.0.1:9000");
+
+    let config = parse(&["--port", "9100", "--root", public], &[("PORT", "9000")]).unwrap();
+    assert_eq!(config.port, 9100);
+    let config = parse(&["--port=9200", &format!("--root={}", public)], &[]).unwrap();
+    assert_eq!(config.port, 9200);
+    assert!(config.root.is_absolute());
+}
+
+#[test]
+fn feature_switches_come_from_env() {
+    let root = TempSite::with_files("features", FILES);
+    let public = root.join("public");
+    let config = parse(
+        &["--root", public.to_str().unwrap()],
+        &[
+            ("WORKERS", "3"),
+            ("ENABLE_DIR_LISTING", "1"),
+            ("CACHE_MAX_AGE", "60"),
+            ("DISABLE_COMPRESSION", "true"),
+        ],
+    )
+    .unwrap();
+    assert_eq!(config.workers, 3);
+    assert!(config.dir_listing);
+    assert_eq!(config.cache_max_age, Some(60));
+    assert!(!config.compression);
+    let summary = config.to_string();
+    assert!(
+        summary.contains("workers=3") && summary.contains("cache_max_age=60s"),
+        "{}",
+        summary
+    );
+}
+
+#[test]
+fn invalid_settings_are_clear_errors() {
+    let root = TempSite::with_files("errors", FILES);
+    let public = root.join("public");
+    let public = public.to_str().unwrap();
+
+    let missing = parse(&["--root", "/definitely/not/here"], &[]).unwrap_err();
+    assert!(
+        missing.contains("/definitely/not/here") && missing.contains("STATIC_ROOT"),
+        "{}",
+        missing
+    );
+    // A file is not a directory
+    let file = root.join("secret.txt");
+    assert!(parse(&["--root", file.to_str().unwrap()], &[]).is_err());
+
+    assert!(parse(&["--root", public, "--port", "http"], &[])
+        .unwrap_err()
+        .contains("http"));
+    assert!(parse(&["--root", public], &[("PORT", "70000")]).is_err());
+    assert!(parse(&["--root", public, "--port"], &[])
+        .unwrap_err()
+        .contains("needs a value"));
+    assert!(parse(&["--verbose"], &[])
+        .unwrap_err()
+        .contains("--verbose"));
+    assert!(parse(&["--root", public], &[("CACHE_MAX_AGE", "1h")]).is_err());
+}
+
+#[test]
+fn serves_a_temp_root_on_an_ephemeral_port() {
+    let root = TempSite::with_files("server", FILES);
+    let public = root.join("public");
+    let config = parse(
+        &["--port", "0", "--root", public.to_str().unwrap()],
+        &[("BIND_ADDR", "127.0.0.1"), ("WORKERS", "2")],
+    )
+    .unwrap();
+
+    let server = Arc::new(Server::http(config.address()).unwrap());
+    let addr = server.server_addr().to_ip().unwrap();
+    let running = Arc::clone(&server);
+    let workers = config.workers;
+    let server_thread = thread::spawn(move || {
+        serve(&running, workers, AccessLog::disabled(), move |request| {
+            handle(request, &config)
+        })
+    });
+
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream
+        .write_all(b"GET / HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.ends_with("<h1>home</h1>"));
+
+    server.unblock();
+    server_thread.join().unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinks_out_of_the_root_are_forbidden() {
+    let root = TempSite::with_files("symlink", FILES);
+    let public = root.join("public");
+    std::os::unix::fs::symlink(root.join("secret.txt"), public.join("leak.txt")).unwrap();
+    std::os::unix::fs::symlink(&root, public.join("outside")).unwrap();
+
+    let config = Config::new(&public).with_dir_listing(true);
+    for url in ["/leak.txt", "/outside/", "/outside/secret.txt"] {
+        let response = handle(&TestRequest::new().with_path(url).into(), &config);
+        assert_eq!(response.status_code().0, 403, "{}", url);
+    }
+    // A symlink that stays inside the root is fine
+    std::os::unix::fs::symlink(public.join("index.html"), public.join("home.html")).unwrap();
+    let response = handle(&TestRequest::new().with_path("/home.html").into(), &config);
+    assert_eq!(response.status_code().0, 200);
+}
@@ -0,0 +1,103 @@
+//! Custom 404/403 pages from the static root, and the plain-text fallback without them.
+
+use std::fs;
+use std::io::Read;
+
+use rust_static_web_server::{handle, Config};
+use static_serve_core::test_support::TempSite;
+use tiny_http::{ResponseBox, TestRequest};
+
+const HOME: &[(&str, &str)] = &[("public/index.html", "<h1>home</h1>")];
+const ERROR_PAGES: &[(&str, &str)] = &[
+    ("public/404.html", "<h1>Nothing here</h1>"),
+    ("public/403.html", "<h1>Keep out</h1>"),
+];
+
+fn get(config: &Config, path: &str) -> ResponseBox {
+    handle(&TestRequest::new().with_path(path).into(), config)
+}
+
+fn content_type(response: &ResponseBox) -> Option<String> {
+    response
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Content-Type"))
+        .map(|h| h.value.to_string())
+}
+
+fn body(response: ResponseBox) -> String {
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body).unwrap();
+    body
+}
+
+#[test]
+fn custom_pages_are_served_with_their_status() {
+    let root = TempSite::with_files("custom", &[HOME, ERROR_PAGES].concat());
+    let config = Config::new(root.join("public"));
+
+    let missing = get(&config, "/nope.html");
+    assert_eq!(missing.status_code().0, 404);
+    assert_eq!(
+        content_type(&missing).as_deref(),
+        Some("text/html; charset=utf-8")
+    );
+    assert_eq!(body(missing), "<h1>Nothing here</h1>");
+
+    let forbidden = get(&config, "/%2e%2e/secret");
+    assert_eq!(forbidden.status_code().0, 403);
+    assert_eq!(
+        content_type(&forbidden).as_deref(),
+        Some("text/html; charset=utf-8")
+    );
+    assert_eq!(body(forbidden), "<h1>Keep out</h1>");
+}
+
+#[test]
+fn plain_text_without_pages() {
+    let root = TempSite::with_files("plain", HOME);
+    let config = Config::new(root.join("public"));
+    let missing = get(&config, "/nope.html");
+    assert_eq!(missing.status_code().0, 404);
+    assert_ne!(
+        content_type(&missing).as_deref(),
+        Some("text/html; charset=utf-8")
+    );
+    assert_eq!(body(missing), "404 Not Found");
+    assert_eq!(body(get(&config, "/../secret")), "403 Forbidden");
+}
+
+#[test]
+fn pages_are_cached_when_the_config_is_built() {
+    let root = TempSite::with_files("cached", &[HOME, ERROR_PAGES].concat());
+    let config = Config::new(root.join("public"));
+    assert!(config.error_pages.not_found.is_some());
+    assert!(config.to_string().contains("error_pages=404,403"));
+    // Deleting the file afterwards doesn't matter: the page is already in memory
+    fs::remove_file(root.join("public/404.html")).unwrap();
+    assert_eq!(body(get(&config, "/gone")), "<h1>Nothing here</h1>");
+    // ...and a fresh config no longer has it
+    let reloaded = Config::new(root.join("public"));
+    assert_eq!(reloaded.error_pages.not_found, None);
+    assert_eq!(body(get(&reloaded, "/gone")), "404 Not Found");
+}
+
+#[test]
+fn only_one_page_present() {
+    let root = TempSite::with_files("partial", HOME);
+    root.write("public/404.html", "custom 404");
+    let config = Config::new(root.join("public"));
+    assert_eq!(body(get(&config, "/gone")), "custom 404");
+    assert_eq!(body(get(&config, "/../x")), "403 Forbidden");
+}
+
+#[cfg(unix)]
+#[test]
+fn error_page_symlinked_outside_the_root_is_ignored() {
+    let root = TempSite::with_files("symlink", HOME);
+    root.write("secret.html", "top secret");
+    std::os::unix::fs::symlink(root.join("secret.html"), root.join("public/404.html")).unwrap();
+    let config = Config::new(root.join("public"));
+    assert_eq!(config.error_pages.not_found, None);
+    assert_eq!(body(get(&config, "/gone")), "404 Not Found");
+}
@@ -0,0 +1,117 @@
+//! Directory listings over a temp tree: the listing page, the index.html fallback, hidden files,
+//! and the disabled-by-default behaviour.
+
+use std::io::Read;
+
+use rust_static_web_server::{config::flag, handle, Config};
+use static_serve_core::test_support::TempSite;
+use tiny_http::{ResponseBox, TestRequest};
+
+/// The tree every test serves, with hidden entries, a space in a name and an empty directory
+const TREE: &[(&str, &str)] = &[
+    ("a.txt", "hello"),
+    (".secret", "hidden"),
+    ("my file.txt", "spaced"),
+    ("sub/b.txt", "bee"),
+    ("sub/.git/", ""),
+    ("docs/index.html", "<p>docs home</p>"),
+];
+
+fn get(config: &Config, path: &str) -> ResponseBox {
+    handle(&TestRequest::new().with_path(path).into(), config)
+}
+
+fn body(response: ResponseBox) -> String {
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body).unwrap();
+    body
+}
+
+#[test]
+fn root_listing_shows_entries_but_not_hidden_ones() {
+    let root = TempSite::with_files("root", TREE);
+    let response = get(&Config::new(root.path()).with_dir_listing(true), "/");
+    assert_eq!(response.status_code().0, 200);
+    let content_type = response
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Content-Type"))
+        .unwrap();
+    assert_eq!(content_type.value.as_str(), "text/html; charset=utf-8");
+
+    let page = body(response);
+    assert!(page.contains("<title>Index of /</title>"));
+    assert!(
+        page.contains(r#"<a href="/a.txt">a.txt</a></td><td>5</td>"#),
+        "{}",
+        page
+    );
+    assert!(page.contains(r#"<a href="/sub/">sub/</a></td><td>-</td>"#));
+    assert!(page.contains(r#"<a href="/my%20file.txt">my file.txt</a>"#));
+    assert!(page.contains(" GMT</td>"), "modified time is shown");
+    assert!(!page.contains(".secret"));
+    assert!(!page.contains("Parent directory"));
+    // Directories are listed before files
+    assert!(page.find("/docs/").unwrap() < page.find("/a.txt").unwrap());
+}
+
+#[test]
+fn nested_listing_links_keep_the_url_prefix() {
+    let root = TempSite::with_files("nested", TREE);
+    let config = Config::new(root.path()).with_dir_listing(true);
+    // With or without the trailing slash, links point inside /sub/
+    for url in ["/sub/", "/sub"] {
+        let page = body(get(&config, url));
+        assert!(
+            page.contains(r#"<a href="/sub/b.txt">b.txt</a>"#),
+            "{}: {}",
+            url,
+            page
+        );
+        assert!(page.contains(r#"<a href="/">Parent directory</a>"#));
+        assert!(!page.contains(".git"));
+    }
+}
+
+#[test]
+fn directory_with_index_html_serves_it() {
+    let root = TempSite::with_files("index", TREE);
+    for config in [
+        Config::new(root.path()),
+        Config::new(root.path()).with_dir_listing(true),
+    ] {
+        let response = get(&config, "/docs/");
+        assert_eq!(response.status_code().0, 200);
+        assert_eq!(body(response), "<p>docs home</p>");
+    }
+}
+
+#[test]
+fn listing_is_off_by_default() {
+    let root = TempSite::with_files("disabled", TREE);
+    let config = Config::new(root.path());
+    assert_eq!(get(&config, "/").status_code().0, 404);
+    assert_eq!(get(&config, "/sub/").status_code().0, 404);
+    // Files are still served
+    assert_eq!(body(get(&config, "/sub/b.txt")), "bee");
+}
+
+#[test]
+fn traversal_is_still_rejected() {
+    let root = TempSite::with_files("traversal", TREE);
+    let config = Config::new(root.join("sub")).with_dir_listing(true);
+    assert_eq!(get(&config, "/..").status_code().0, 403);
+    assert_eq!(get(&config, "/../").status_code().0, 403);
+    assert_eq!(get(&config, "/.git/../..").status_code().0, 403);
+}
+
+#[test]
+fn flag_values() {
+    for on in ["1", "true", "YES", " on "] {
+        assert!(flag(Some(on)), "{}", on);
+    }
+    for off in ["0", "false", "", "enabled"] {
+        assert!(!flag(Some(off)), "{}", off);
+    }
+    assert!(!flag(None));
+}
@@ -0,0 +1,196 @@
+//! HEAD, 405 for other methods, and 400 for request targets that aren't paths.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use rust_static_web_server::{decode_path, handle, serve, AccessLog, Config, PathError};
+use static_serve_core::test_support::TempSite;
+use tiny_http::{Method, ResponseBox, Server, TestRequest};
+
+fn files() -> [(&'static str, String); 2] {
+    [
+        ("index.html", "<h1>home</h1>".to_string()),
+        // Big enough that tiny_http would normally switch to chunked encoding
+        ("big.txt", "x".repeat(100_000)),
+    ]
+}
+
+fn request(config: &Config, method: Method, path: &str) -> ResponseBox {
+    handle(
+        &TestRequest::new()
+            .with_method(method)
+            .with_path(path)
+            .into(),
+        config,
+    )
+}
+
+fn header(response: &ResponseBox, name: &'static str) -> Option<String> {
+    response
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.to_string())
+}
+
+fn body(response: ResponseBox) -> Vec<u8> {
+    let mut body = Vec::new();
+    response.into_reader().read_to_end(&mut body).unwrap();
+    body
+}
+
+/// Run the real server on an ephemeral port
+fn start(config: Config) -> (Arc<Server>, SocketAddr, JoinHandle<()>) {
+    let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+    let addr = server.server_addr().to_ip().unwrap();
+    let running = Arc::clone(&server);
+    let thread = thread::spawn(move || {
+        serve(&running, 1, AccessLog::disabled(), move |r| {
+            handle(r, &config)
+        })
+    });
+    (server, addr, thread)
+}
+
+/// Send raw bytes and read the whole response
+fn exchange(addr: SocketAddr, raw: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(raw.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn head_has_get_headers_and_no_body() {
+    let root = TempSite::with_files("head", &files());
+    let config = Config::new(root.path()).with_cache_max_age(Some(60));
+    let get = request(&config, Method::Get, "/index.html");
+    let head = request(&config, Method::Head, "/index.html");
+
+    assert_eq!(head.status_code().0, 200);
+    for name in [
+        "Content-Type",
+        "ETag",
+        "Last-Modified",
+        "Cache-Control",
+        "Accept-Ranges",
+    ] {
+        assert!(header(&head, name).is_some(), "{}", name);
+        assert_eq!(header(&head, name), header(&get, name), "{}", name);
+    }
+    // Content-Length announces the GET body's size, but nothing is sent
+    assert_eq!(head.data_length(), Some(13));
+    assert_eq!(get.data_length(), Some(13));
+    assert!(body(head).is_empty());
+}
+
+#[test]
+fn head_on_a_missing_file_is_a_bodiless_404() {
+    let root = TempSite::with_files("head-missing", &files());
+    let head = request(&Config::new(root.path()), Method::Head, "/nope.txt");
+    assert_eq!(head.status_code().0, 404);
+    assert_eq!(head.data_length(), Some("404 Not Found".len()));
+    assert!(body(head).is_empty());
+}
+
+#[test]
+fn other_methods_are_405_with_allow() {
+    let root = TempSite::with_files("post", &files());
+    let config = Config::new(root.path());
+    for method in [
+        Method::Post,
+        Method::Put,
+        Method::Delete,
+        Method::Patch,
+        Method::Options,
+    ] {
+        let response = request(&config, method.clone(), "/index.html");
+        assert_eq!(response.status_code().0, 405, "{}", method);
+        assert_eq!(header(&response, "Allow").as_deref(), Some("GET, HEAD"));
+    }
+}
+
+#[test]
+fn wire_level_head_and_post() {
+    let root = TempSite::with_files("wire", &files());
+    let (server, addr, thread) = start(Config::new(root.path()));
+
+    let head = exchange(
+        addr,
+        "HEAD /big.txt HTTP/1.1\r\nHost: t\r\nConnection: close\r\n\r\n",
+    );
+    assert!(head.starts_with("HTTP/1.1 200"), "{}", head);
+    assert!(head.contains("Content-Length: 100000\r\n"), "{}", head);
+    assert!(!head.contains("Transfer-Encoding"), "{}", head);
+    assert!(head.ends_with("\r\n\r\n"), "no body after the headers");
+
+    // Large GETs carry Content-Length too, not chunked encoding
+    let get = exchange(
+        addr,
+        "GET /big.txt HTTP/1.1\r\nHost: t\r\nConnection: close\r\n\r\n",
+    );
+    assert!(get.contains("Content-Length: 100000\r\n"));
+    assert!(get.ends_with(&"x".repeat(100)));
+
+    let post = exchange(
+        addr,
+        "POST /index.html HTTP/1.1\r\nHost: t\r\nContent-Length: 2\r\nConnection: close\r\n\r\nhi",
+    );
+    assert!(post.starts_with("HTTP/1.1 405"), "{}", post);
+    assert!(post.contains("Allow: GET, HEAD\r\n"));
+
+    server.unblock();
+    thread.join().unwrap();
+}
+
+#[test]
+fn malformed_request_lines_are_400() {
+    let root = TempSite::with_files("malformed", &files());
+    let (server, addr, thread) = start(Config::new(root.path()));
+    for line in [
+        "GET index.html HTTP/1.1",
+        "GET * HTTP/1.1",
+        "GET\tnope",
+        "NONSENSE",
+    ] {
+        let response = exchange(
+            addr,
+            &format!("{}\r\nHost: t\r\nConnection: close\r\n\r\n", line),
+        );
+        assert!(
+            response.starts_with("HTTP/1.1 400"),
+            "{:?} -> {:?}",
+            line,
+            response
+        );
+        assert!(
+            !response.contains("<h1>home</h1>"),
+            "{:?} was served as /",
+            line
+        );
+    }
+    server.unblock();
+    thread.join().unwrap();
+}
+
+#[test]
+fn absolute_form_targets_are_accepted() {
+    let root = TempSite::with_files("absolute", &files());
+    let config = Config::new(root.path());
+    let response = handle(
+        &TestRequest::new()
+            .with_path("http://example.com/index.html")
+            .into(),
+        &config,
+    );
+    assert_eq!(body(response), b"<h1>home</h1>");
+    assert_eq!(decode_path("HTTPS://example.com").unwrap(), "/");
+    assert_eq!(decode_path("http://example.com?x=1").unwrap(), "/");
+    assert_eq!(decode_path("*"), Err(PathError::Malformed));
+    assert_eq!(decode_path("index.html"), Err(PathError::Malformed));
+    // A URL in the query string is not an absolute-form target
+    assert_eq!(decode_path("/go?to=http://x/y").unwrap(), "/go");
+}
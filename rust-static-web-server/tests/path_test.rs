@@ -0,0 +1,141 @@
+//! URL decoding and content types: encoded paths, the MIME table, and traversal via `%2e%2e`.
+
+use std::io::Read;
+use std::path::Path;
+
+use rust_static_web_server::{
+    content_type, decode_path, handle, mime, url::encode_path, Config, PathError,
+};
+use static_serve_core::test_support::TempSite;
+use tiny_http::{ResponseBox, TestRequest};
+
+const FILES: &[(&str, &str)] = &[
+    ("public/my file.txt", "spaced"),
+    ("public/my dir/café.txt", "accented"),
+    ("public/a+b.txt", "plus"),
+    ("public/data.xyz", "unknown"),
+    ("public/LICENSE", "no extension"),
+    ("public/app.MJS", "export {}"),
+    // Outside the served root
+    ("secret.txt", "top secret"),
+];
+
+fn get(root: &Path, path: &str) -> ResponseBox {
+    handle(
+        &TestRequest::new().with_path(path).into(),
+        &Config::new(root.join("public")),
+    )
+}
+
+fn body(response: ResponseBox) -> String {
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body).unwrap();
+    body
+}
+
+fn content_type_of(response: &ResponseBox) -> String {
+    let header = response
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Content-Type"))
+        .unwrap();
+    header.value.as_str().to_string()
+}
+
+#[test]
+fn encoded_paths_are_decoded_before_lookup() {
+    let root = TempSite::with_files("decode", FILES);
+    assert_eq!(body(get(&root, "/my%20file.txt")), "spaced");
+    assert_eq!(body(get(&root, "/my%20dir/caf%C3%A9.txt")), "accented");
+    // `+` is literal in a path, and the query string is ignored
+    assert_eq!(body(get(&root, "/a+b.txt")), "plus");
+    assert_eq!(body(get(&root, "/my%20file.txt?v=2#top")), "spaced");
+}
+
+#[test]
+fn content_type_comes_from_the_table() {
+    let root = TempSite::with_files("mime", FILES);
+    assert_eq!(content_type_of(&get(&root, "/my%20file.txt")), "text/plain");
+    // Extensions match case-insensitively
+    assert_eq!(
+        content_type_of(&get(&root, "/app.MJS")),
+        "application/javascript"
+    );
+}
+
+#[test]
+fn unknown_extensions_are_octet_stream() {
+    let root = TempSite::with_files("unknown", FILES);
+    assert_eq!(
+        content_type_of(&get(&root, "/data.xyz")),
+        "application/octet-stream"
+    );
+    assert_eq!(
+        content_type_of(&get(&root, "/LICENSE")),
+        "application/octet-stream"
+    );
+}
+
+#[test]
+fn encoded_traversal_is_forbidden() {
+    let root = TempSite::with_files("traversal", FILES);
+    for url in [
+        "/../secret.txt",
+        "/%2e%2e/secret.txt",
+        "/%2E%2E%2Fsecret.txt",
+        "/.%2e/secret.txt",
+        "/..%5csecret.txt",
+    ] {
+        let response = get(&root, url);
+        assert_eq!(response.status_code().0, 403, "{}", url);
+        assert_ne!(body(response), "top secret");
+    }
+    // Dots inside a name are fine
+    assert_eq!(get(&root, "/my..file.txt").status_code().0, 404);
+}
+
+#[test]
+fn malformed_encoding_is_a_bad_request() {
+    let root = TempSite::with_files("malformed", FILES);
+    for url in ["/%zz.txt", "/file%2", "/%ff%fe.txt", "/nul%00.txt"] {
+        assert_eq!(get(&root, url).status_code().0, 400, "{}", url);
+    }
+}
+
+#[test]
+fn decoding_and_encoding() {
+    assert_eq!(decode_path("/a%20b/c?d=%20").unwrap(), "/a b/c");
+    assert_eq!(decode_path("/%2e%2e"), Err(PathError::Traversal));
+    assert_eq!(decode_path("/%G0"), Err(PathError::Malformed));
+    assert_eq!(encode_path("/my dir/café.txt"), "/my%20dir/caf%C3%A9.txt");
+    // Round trip
+    assert_eq!(
+        decode_path(&encode_path("/x y/#1?.txt")).unwrap(),
+        "/x y/#1?.txt"
+    );
+}
+
+#[test]
+fn mime_table_covers_common_assets() {
+    for (file, expected) in [
+        ("index.html", "text/html"),
+        ("style.css", "text/css"),
+        ("app.js", "application/javascript"),
+        ("mod.mjs", "application/javascript"),
+        ("data.json", "application/json"),
+        ("logo.svg", "image/svg+xml"),
+        ("a.png", "image/png"),
+        ("a.jpg", "image/jpeg"),
+        ("a.gif", "image/gif"),
+        ("a.webp", "image/webp"),
+        ("favicon.ico", "image/x-icon"),
+        ("font.woff2", "font/woff2"),
+        ("notes.txt", "text/plain"),
+        ("doc.pdf", "application/pdf"),
+        ("clip.mp4", "video/mp4"),
+        ("module.wasm", "application/wasm"),
+    ] {
+        assert_eq!(content_type(Path::new(file)), expected, "{}", file);
+    }
+    assert_eq!(mime::from_extension("nope"), mime::DEFAULT_MIME);
+}
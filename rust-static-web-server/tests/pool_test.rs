@@ -0,0 +1,102 @@
+//! The worker pool: a slow request doesn't block a fast one, and shutdown finishes in-flight work.
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use rust_static_web_server::{serve, worker_count, AccessLog, WorkerPool};
+use tiny_http::{Response, Server};
+
+const SLOW: Duration = Duration::from_millis(800);
+
+/// Serve on an ephemeral port with a handler where `/slow` sleeps (the test hook) and anything
+/// else answers at once
+fn start(workers: usize) -> (Arc<Server>, SocketAddr, JoinHandle<()>) {
+    let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+    let addr = server.server_addr().to_ip().unwrap();
+    let running = Arc::clone(&server);
+    let handle = thread::spawn(move || {
+        serve(&running, workers, AccessLog::disabled(), |request| {
+            if request.url() == "/slow" {
+                thread::sleep(SLOW);
+            }
+            Response::from_string(request.url().to_string()).boxed()
+        })
+    });
+    (server, addr, handle)
+}
+
+fn send(addr: SocketAddr, path: &str) -> TcpStream {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: test\r\nConnection: close\r\n\r\n",
+        path
+    )
+    .unwrap();
+    stream
+}
+
+fn read(mut stream: TcpStream) -> String {
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn fast_request_is_not_stuck_behind_a_slow_one() {
+    let (server, addr, handle) = start(2);
+
+    let slow = send(addr, "/slow");
+    // Let the slow request reach a worker first
+    thread::sleep(Duration::from_millis(100));
+
+    let started = Instant::now();
+    let fast = read(send(addr, "/fast"));
+    assert!(fast.starts_with("HTTP/1.1 200"), "got {:?}", fast);
+    assert!(fast.ends_with("/fast"));
+    assert!(
+        started.elapsed() < SLOW / 2,
+        "fast request took {:?}",
+        started.elapsed()
+    );
+
+    assert!(read(slow).ends_with("/slow"));
+    server.unblock();
+    handle.join().unwrap();
+}
+
+#[test]
+fn shutdown_finishes_in_flight_requests() {
+    let (server, addr, handle) = start(1);
+
+    let slow = send(addr, "/slow");
+    thread::sleep(Duration::from_millis(100));
+    // Stop accepting while the slow request is still being handled
+    server.unblock();
+
+    let response = read(slow);
+    assert!(response.starts_with("HTTP/1.1 200"), "got {:?}", response);
+    assert!(response.ends_with("/slow"));
+    // `serve` returns once the pool has drained
+    handle.join().unwrap();
+}
+
+#[test]
+fn pool_always_has_a_worker() {
+    assert_eq!(WorkerPool::new(0, drop).size(), 1);
+    assert_eq!(WorkerPool::new(3, drop).size(), 3);
+}
+
+#[test]
+fn worker_count_parsing() {
+    assert_eq!(worker_count(Some("4")), 4);
+    assert_eq!(worker_count(Some(" 2 ")), 2);
+    // Zero, garbage and unset fall back to the CPU count
+    let default = worker_count(None);
+    assert!(default >= 1);
+    assert_eq!(worker_count(Some("0")), default);
+    assert_eq!(worker_count(Some("many")), default);
+}
@@ -0,0 +1,95 @@
+//! Range requests against a 36-byte fixture: full, partial, open-ended, suffix and out-of-bounds.
+
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use rust_static_web_server::{handle, Config};
+use tiny_http::{Header, ResponseBox, TestRequest};
+
+const ALPHABET: &str = "0123456789abcdefghijklmnopqrstuvwxyz";
+
+fn fixtures() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures")
+}
+
+/// GET /alphabet.txt, with a `Range` header if one is given
+fn get(range: Option<&str>) -> ResponseBox {
+    let mut request = TestRequest::new().with_path("/alphabet.txt");
+    if let Some(range) = range {
+        request = request.with_header(Header::from_bytes("Range", range).unwrap());
+    }
+    handle(&request.into(), &Config::new(fixtures()))
+}
+
+fn header<'a>(response: &'a ResponseBox, name: &'static str) -> Option<&'a str> {
+    response
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+fn body(response: ResponseBox) -> String {
+    let mut body = String::new();
+    response.into_reader().read_to_string(&mut body).unwrap();
+    body
+}
+
+#[test]
+fn no_range_sends_the_whole_file() {
+    let response = get(None);
+    assert_eq!(response.status_code().0, 200);
+    assert_eq!(header(&response, "Accept-Ranges"), Some("bytes"));
+    assert_eq!(header(&response, "Content-Range"), None);
+    assert_eq!(body(response), ALPHABET);
+}
+
+#[test]
+fn bounded_range_sends_only_the_slice() {
+    let response = get(Some("bytes=0-9"));
+    assert_eq!(response.status_code().0, 206);
+    assert_eq!(header(&response, "Content-Range"), Some("bytes 0-9/36"));
+    assert_eq!(header(&response, "Content-Type"), Some("text/plain"));
+    assert_eq!(response.data_length(), Some(10));
+    assert_eq!(body(response), "0123456789");
+}
+
+#[test]
+fn open_ended_range_runs_to_the_end() {
+    let response = get(Some("bytes=30-"));
+    assert_eq!(response.status_code().0, 206);
+    assert_eq!(header(&response, "Content-Range"), Some("bytes 30-35/36"));
+    assert_eq!(body(response), "uvwxyz");
+}
+
+#[test]
+fn suffix_range_sends_the_last_bytes() {
+    let response = get(Some("bytes=-3"));
+    assert_eq!(response.status_code().0, 206);
+    assert_eq!(header(&response, "Content-Range"), Some("bytes 33-35/36"));
+    assert_eq!(body(response), "xyz");
+}
+
+#[test]
+fn end_past_the_file_is_clamped() {
+    let response = get(Some("bytes=34-1000"));
+    assert_eq!(response.status_code().0, 206);
+    assert_eq!(header(&response, "Content-Range"), Some("bytes 34-35/36"));
+    assert_eq!(body(response), "yz");
+}
+
+#[test]
+fn start_past_the_file_is_416() {
+    for range in ["bytes=36-", "bytes=100-200", "bytes=-0"] {
+        let response = get(Some(range));
+        assert_eq!(response.status_code().0, 416, "{}", range);
+        assert_eq!(header(&response, "Content-Range"), Some("bytes */36"));
+    }
+}
+
+#[test]
+fn multi_range_falls_back_to_the_full_body() {
+    let response = get(Some("bytes=0-1,5-6"));
+    assert_eq!(response.status_code().0, 200);
+    assert_eq!(body(response), ALPHABET);
+}
@@ -0,0 +1,96 @@
+// Air pollution (`/data/2.5/air_pollution`): an overall air quality index from 1 (good) to
+// 5 (very poor), plus the concentrations it was computed from.
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// The air pollution response. `list` holds a single entry for the current conditions.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct AirQualityResponse {
+    pub list: Vec<AirQuality>,
+}
+
+/// Air quality at one moment
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct AirQuality {
+    /// Unix timestamp of the measurement
+    #[serde(default)]
+    pub dt: i64,
+    pub main: AirQualityIndex,
+    #[serde(default)]
+    pub components: Components,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AirQualityIndex {
+    /// 1 to 5; see `AqiCategory`
+    pub aqi: u8,
+}
+
+/// Pollutant concentrations in μg/m³. Stations don't measure everything, so any of them may be
+/// missing; `Default` (all None) covers a response without `components` at all.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Components {
+    #[serde(default)]
+    pub pm2_5: Option<f64>,
+    #[serde(default)]
+    pub pm10: Option<f64>,
+    #[serde(default)]
+    pub o3: Option<f64>,
+    #[serde(default)]
+    pub no2: Option<f64>,
+}
+
+impl AirQualityResponse {
+    /// The current conditions, if the response has any
+    pub fn current(&self) -> Option<&AirQuality> {
+        self.list.first()
+    }
+}
+
+impl AirQuality {
+    /// The index as a category; `None` for a value outside 1-5
+    pub fn category(&self) -> Option<AqiCategory> {
+        AqiCategory::from_index(self.main.aqi)
+    }
+}
+
+/// OpenWeather's names for the five index values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AqiCategory {
+    Good,
+    Fair,
+    Moderate,
+    Poor,
+    VeryPoor,
+}
+
+impl AqiCategory {
+    /// Map an index (1 = Good ... 5 = Very Poor) to its category
+    pub fn from_index(aqi: u8) -> Option<AqiCategory> {
+        match aqi {
+            1 => Some(AqiCategory::Good),
+            2 => Some(AqiCategory::Fair),
+            3 => Some(AqiCategory::Moderate),
+            4 => Some(AqiCategory::Poor),
+            5 => Some(AqiCategory::VeryPoor),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AqiCategory::Good => "Good",
+            AqiCategory::Fair => "Fair",
+            AqiCategory::Moderate => "Moderate",
+            AqiCategory::Poor => "Poor",
+            AqiCategory::VeryPoor => "Very Poor",
+        }
+    }
+}
+
+impl fmt::Display for AqiCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,153 @@
+// Command-line parsing for the `rust-weather-api-client` binary. It lives in the library
+// so the parser can be tested without running the program.
+//
+// Hand-rolled rather than using a crate like clap: with a handful of flags, a loop over the
+// arguments is short and shows how `std::env::args()` works.
+use crate::{OutputFormat, Units};
+
+/// Printed for `--help`, and after an argument error
+pub const USAGE: &str = "\
+Usage: rust-weather-api-client [OPTIONS] <CITY>...
+       rust-weather-api-client [OPTIONS] --lat <LAT> --lon <LON>
+
+Fetch the current weather for one or more cities, or for a position. Quote names with
+spaces: \"New York\".
+
+Options:
+  --units <UNITS>    metric (default), imperial or standard
+  --lang <LANG>      language for descriptions, e.g. en, fr, de
+  --country <CODE>   only match cities in this country, e.g. GB, CA, US
+  --lat <LAT>        latitude in degrees, -90 to 90 (use with --lon)
+  --lon <LON>        longitude in degrees, -180 to 180 (use with --lat)
+  --forecast         show the 5-day forecast, one line per day, instead
+  --aqi              also show the air quality (not with --forecast)
+  --output <FORMAT>  text (default), json or csv
+  -q, --quiet        don't print the \"Fetching weather for...\" line
+  -h, --help         show this help";
+
+/// What the user asked for on the command line.
+// PartialEq only, not Eq: f64 can't be Eq because NaN != NaN
+#[derive(Debug, Clone, PartialEq)]
+pub struct Args {
+    /// The cities, in the order given; empty when `coords` is set
+    pub cities: Vec<String>,
+    /// `--lat`/`--lon`: a position to look up instead of cities
+    pub coords: Option<(f64, f64)>,
+    /// `--country`: ISO 3166 code narrowing the city lookup
+    pub country: Option<String>,
+    pub units: Units,
+    /// Language code for the weather descriptions; the API default (English) if `None`
+    pub lang: Option<String>,
+    /// `--forecast`: show the 5-day forecast instead of the current weather
+    pub forecast: bool,
+    /// `--aqi`: add the air quality to the current weather
+    pub aqi: bool,
+    pub output: OutputFormat,
+    /// `--quiet`: no progress line on stderr
+    pub quiet: bool,
+}
+
+/// Parse the arguments after the program name. Flags may come before, between or after the
+/// city names, as `--units imperial` or `--units=imperial`.
+///
+/// Returns an error message (to print above `USAGE`) for unknown flags, missing or invalid
+/// values, and when neither cities nor a full `--lat`/`--lon` pair is given.
+// `impl IntoIterator<Item = String>` accepts `std::env::args().skip(1)` as well as a Vec in tests
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut cities = Vec::new();
+    let mut units = Units::default();
+    let mut lang = None;
+    let mut country = None;
+    let mut lat = None;
+    let mut lon = None;
+    let mut forecast = false;
+    let mut aqi = false;
+    let mut output = OutputFormat::default();
+    let mut quiet = false;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // The one short flag; -h is handled by wants_help
+        if arg == "-q" {
+            quiet = true;
+            continue;
+        }
+        if !arg.starts_with("--") {
+            cities.push(arg);
+            continue;
+        }
+        // `--units=imperial` carries its value inline; `--units imperial` takes the next arg
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} needs a value", flag))
+        };
+        match flag.as_str() {
+            "--units" => units = Units::parse(&value()?)?,
+            "--lang" => lang = Some(value()?),
+            "--country" => country = Some(value()?),
+            "--lat" => lat = Some(degrees("--lat", &value()?, 90.0)?),
+            "--lon" => lon = Some(degrees("--lon", &value()?, 180.0)?),
+            // A switch: it takes no value, so `--forecast=yes` is a mistake
+            "--forecast" if inline.is_none() => forecast = true,
+            "--forecast" => return Err("--forecast takes no value".to_string()),
+            "--aqi" if inline.is_none() => aqi = true,
+            "--aqi" => return Err("--aqi takes no value".to_string()),
+            "--output" => output = OutputFormat::parse(&value()?)?,
+            "--quiet" if inline.is_none() => quiet = true,
+            "--quiet" => return Err("--quiet takes no value".to_string()),
+            _ => return Err(format!("unknown option '{}'", arg)),
+        }
+    }
+
+    // Matching on the pair checks both options at once
+    let coords = match (lat, lon) {
+        (Some(lat), Some(lon)) => Some((lat, lon)),
+        (None, None) => None,
+        _ => return Err("--lat and --lon must be given together".to_string()),
+    };
+    if coords.is_some() && !cities.is_empty() {
+        return Err("give either cities or --lat/--lon, not both".to_string());
+    }
+    // The air pollution endpoint only reports the present, so there's nothing to pair with
+    // each forecast day
+    if aqi && forecast {
+        return Err("--aqi can't be combined with --forecast".to_string());
+    }
+    if coords.is_none() && cities.is_empty() {
+        return Err("no city given".to_string());
+    }
+    Ok(Args {
+        cities,
+        coords,
+        country,
+        units,
+        lang,
+        forecast,
+        aqi,
+        output,
+        quiet,
+    })
+}
+
+// A latitude or longitude: a number from -limit to limit
+fn degrees(flag: &str, value: &str, limit: f64) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        // `(-limit..=limit).contains(..)` also rejects NaN, which fails every comparison
+        Ok(degrees) if (-limit..=limit).contains(&degrees) => Ok(degrees),
+        _ => Err(format!(
+            "{} must be a number from -{} to {}, got '{}'",
+            flag, limit, limit, value
+        )),
+    }
+}
+
+/// Whether the arguments ask for help (`-h` or `--help` anywhere)
+pub fn wants_help(args: &[String]) -> bool {
+    args.iter().any(|arg| arg == "-h" || arg == "--help")
+}
@@ -0,0 +1,323 @@
+// `WeatherClient` holds what every request shares: the API key, units, language and base URL,
+// plus one reqwest::Client. Reusing that client keeps connections open between requests
+// (like sharing one OkHttpClient instead of creating one per call).
+//
+// OpenWeather is phasing out city-name queries on its weather endpoints, so a city is first
+// looked up with the geocoding API, and the weather is then fetched by latitude/longitude.
+use std::time::Duration;
+
+use futures::future::join_all;
+use serde::de::DeserializeOwned;
+
+use crate::air::AirQualityResponse;
+use crate::error::WeatherError;
+use crate::forecast::ForecastResponse;
+use crate::models::{GeoLocation, WeatherResponse};
+use crate::units::Units;
+
+/// OpenWeatherMap's API root. Endpoint paths such as `data/2.5/weather` and `geo/1.0/direct`
+/// are appended to it.
+pub const BASE_URL: &str = "https://api.openweathermap.org";
+
+/// How many matches `geocode` asks for (the API allows at most 5)
+pub const GEOCODE_LIMIT: u32 = 5;
+
+/// How long one request may take, from connecting to the end of the body
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// When to try a request again. Only transient failures are retried (see
+/// `WeatherError::is_transient`); the wait doubles after each attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts in total, including the first; 1 means no retries
+    pub max_attempts: u32,
+    /// Wait before the second attempt; then twice that before the third, and so on
+    pub initial_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Try once and give up
+    pub fn none() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1,
+            initial_backoff: Duration::ZERO,
+        }
+    }
+
+    /// The wait after failed attempt number `attempt` (1-based): initial, 2x, 4x...
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        // checked_pow avoids overflow with absurd attempt counts; saturate instead
+        let factor = 2u32
+            .checked_pow(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff.saturating_mul(factor)
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, 500ms then 1s apart
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WeatherClient {
+    pub api_key: String,
+    pub units: Units,
+    /// Descriptions in this language (e.g. `fr`); `None` means the API's default, English
+    pub lang: Option<String>,
+    /// ISO 3166 country code (e.g. `CA`) added to every geocoding query, to tell apart
+    /// cities that share a name
+    pub country: Option<String>,
+    /// Where requests go; point it at a mock server in tests
+    pub base_url: String,
+    /// Per-request time limit; a request that takes longer fails as a network error
+    pub timeout: Duration,
+    pub retry: RetryPolicy,
+    /// Fetch the air quality along with the current weather, into `WeatherResponse::air_quality`
+    pub air_quality: bool,
+    // Private: callers configure the client through the fields above
+    http: reqwest::Client,
+}
+
+impl WeatherClient {
+    /// A client with metric units, English descriptions and the real API.
+    // `impl Into<String>` accepts both &str and String, like overloading the constructor
+    pub fn new(api_key: impl Into<String>) -> WeatherClient {
+        WeatherClient {
+            api_key: api_key.into(),
+            units: Units::default(),
+            lang: None,
+            country: None,
+            base_url: BASE_URL.to_string(),
+            timeout: DEFAULT_TIMEOUT,
+            retry: RetryPolicy::default(),
+            air_quality: false,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    // The with_* methods take `self` by value and hand it back, so they chain like a
+    // Java builder: WeatherClient::new(key).with_units(Units::Imperial)
+    pub fn with_units(mut self, units: Units) -> WeatherClient {
+        self.units = units;
+        self
+    }
+
+    pub fn with_lang(mut self, lang: impl Into<String>) -> WeatherClient {
+        self.lang = Some(lang.into());
+        self
+    }
+
+    pub fn with_country(mut self, country: impl Into<String>) -> WeatherClient {
+        self.country = Some(country.into());
+        self
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> WeatherClient {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> WeatherClient {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> WeatherClient {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_air_quality(mut self, air_quality: bool) -> WeatherClient {
+        self.air_quality = air_quality;
+        self
+    }
+
+    /// Build (but don't send) the request for a data endpoint (`weather`, `forecast` or
+    /// `air_pollution`) at a position, with the key, units and language as query parameters.
+    pub fn data_request(
+        &self,
+        endpoint: &str,
+        lat: f64,
+        lon: f64,
+    ) -> Result<reqwest::Request, WeatherError> {
+        let (lat, lon) = (lat.to_string(), lon.to_string());
+        let mut query = vec![
+            ("lat", lat.as_str()),
+            ("lon", lon.as_str()),
+            ("units", self.units.as_str()),
+        ];
+        if let Some(lang) = &self.lang {
+            query.push(("lang", lang));
+        }
+        self.request(&format!("data/2.5/{}", endpoint), &query)
+    }
+
+    /// Build (but don't send) the geocoding request for `city`, narrowed to `country` if set.
+    pub fn geocode_request(&self, city: &str) -> Result<reqwest::Request, WeatherError> {
+        // The API reads "London,CA" as city London in country CA
+        let q = match &self.country {
+            Some(country) => format!("{},{}", city, country),
+            None => city.to_string(),
+        };
+        let limit = GEOCODE_LIMIT.to_string();
+        self.request("geo/1.0/direct", &[("q", &q), ("limit", &limit)])
+    }
+
+    // Every endpoint takes the key as `appid`
+    fn request(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<reqwest::Request, WeatherError> {
+        let url = format!("{}/{}", self.base_url.trim_end_matches('/'), path);
+        // .query() URL-encodes the parameters, so cities like "New York" work.
+        let request = self
+            .http
+            .get(url)
+            .query(query)
+            .query(&[("appid", &self.api_key)])
+            .timeout(self.timeout);
+        // build() fails only if the URL is invalid, e.g. a base_url without "http://"
+        Ok(request.build()?)
+    }
+
+    /// Places called `city`, best match first; empty if there are none.
+    pub async fn geocode(&self, city: &str) -> Result<Vec<GeoLocation>, WeatherError> {
+        self.fetch(self.geocode_request(city)?).await
+    }
+
+    /// The best match for `city`, or `CityNotFound`
+    pub async fn locate(&self, city: &str) -> Result<GeoLocation, WeatherError> {
+        // into_iter().next() takes the first element by value, like list.stream().findFirst()
+        self.geocode(city)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(WeatherError::CityNotFound)
+    }
+
+    /// The current weather for `city`: the top geocoding match, fetched by coordinates.
+    pub async fn get_weather(&self, city: &str) -> Result<WeatherResponse, WeatherError> {
+        let location = self.locate(city).await?;
+        self.get_weather_by_coords(location.lat, location.lon).await
+    }
+
+    /// The current weather at a latitude/longitude in decimal degrees, with the air quality if
+    /// the client was built `with_air_quality(true)`.
+    pub async fn get_weather_by_coords(
+        &self,
+        lat: f64,
+        lon: f64,
+    ) -> Result<WeatherResponse, WeatherError> {
+        let weather = self.fetch(self.data_request("weather", lat, lon)?);
+        if !self.air_quality {
+            return weather.await;
+        }
+        // join! runs both requests at the same time and waits for both
+        let (weather, air) = futures::join!(weather, self.get_air_quality(lat, lon));
+        let mut weather = weather?;
+        weather.air_quality = air?.current().cloned();
+        Ok(weather)
+    }
+
+    /// The air quality index and pollutant concentrations at a latitude/longitude.
+    pub async fn get_air_quality(
+        &self,
+        lat: f64,
+        lon: f64,
+    ) -> Result<AirQualityResponse, WeatherError> {
+        self.fetch(self.data_request("air_pollution", lat, lon)?)
+            .await
+    }
+
+    /// The 5-day forecast for `city`: 40 entries, one every 3 hours. Use
+    /// `ForecastResponse::daily` for one summary per day.
+    pub async fn get_forecast(&self, city: &str) -> Result<ForecastResponse, WeatherError> {
+        let location = self.locate(city).await?;
+        self.get_forecast_by_coords(location.lat, location.lon)
+            .await
+    }
+
+    /// The 5-day forecast at a latitude/longitude in decimal degrees.
+    pub async fn get_forecast_by_coords(
+        &self,
+        lat: f64,
+        lon: f64,
+    ) -> Result<ForecastResponse, WeatherError> {
+        self.fetch(self.data_request("forecast", lat, lon)?).await
+    }
+
+    /// The current weather for several cities at once, in the order given. Each city gets its
+    /// own result, so one unknown city doesn't hide the others.
+    pub async fn get_many(
+        &self,
+        cities: &[&str],
+    ) -> Vec<(String, Result<WeatherResponse, WeatherError>)> {
+        // Calling get_weather only creates the futures; nothing is sent yet. join_all then
+        // polls them all together, so the requests overlap instead of waiting on each other,
+        // and returns the results in input order (like CompletableFuture.allOf + join).
+        let results = join_all(cities.iter().map(|city| self.get_weather(city))).await;
+        cities
+            .iter()
+            .map(|city| city.to_string())
+            .zip(results)
+            .collect()
+    }
+
+    // Send `request`, retrying transient failures as the retry policy allows.
+    //
+    // Generic over the response type, like <T> T fetch(Class<T> type) in Java, except that
+    // the compiler infers T from the caller's return type.
+    async fn fetch<T: DeserializeOwned>(
+        &self,
+        request: reqwest::Request,
+    ) -> Result<T, WeatherError> {
+        let max_attempts = self.retry.max_attempts.max(1);
+        let mut attempt = 1;
+        loop {
+            // Sending consumes a Request, so each attempt sends a copy. try_clone only fails
+            // for streaming bodies, and these are all body-less GETs.
+            let copy = request.try_clone().expect("GET requests can be cloned");
+            let error = match self.fetch_once(copy).await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            if !error.is_transient() {
+                return Err(error);
+            }
+            if attempt == max_attempts {
+                // Retrying was pointless; say so, so the user knows it wasn't a one-off
+                return Err(if attempt > 1 {
+                    WeatherError::GaveUp {
+                        attempts: attempt,
+                        last: Box::new(error),
+                    }
+                } else {
+                    error
+                });
+            }
+            tokio::time::sleep(self.retry.backoff(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn fetch_once<T: DeserializeOwned>(
+        &self,
+        request: reqwest::Request,
+    ) -> Result<T, WeatherError> {
+        let response = self.http.execute(request).await?;
+        let status = response.status();
+        // Read the body as text first so a bad body becomes Parse, not Network
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(WeatherError::from_response(status.as_u16(), &body));
+        }
+        Ok(serde_json::from_str(&body)?)
+    }
+}
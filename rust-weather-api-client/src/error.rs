@@ -0,0 +1,113 @@
+// The one error type the library returns. Where Java would have a small exception hierarchy
+// (CityNotFoundException extends WeatherException, ...), Rust uses an enum: callers `match`
+// on the variant instead of writing one catch block per class.
+use std::error::Error;
+use std::fmt;
+
+use serde::Deserialize;
+
+#[derive(Debug)]
+pub enum WeatherError {
+    /// OpenWeather doesn't know the city (HTTP 404)
+    CityNotFound,
+    /// The API key is missing, wrong or not activated yet (HTTP 401)
+    InvalidApiKey,
+    /// Any other unsuccessful status, e.g. 429 when over the rate limit
+    Http(u16),
+    /// The request never got an answer: DNS, connection refused, timeout...
+    Network(reqwest::Error),
+    /// The server answered, but not with the JSON we expected
+    Parse(serde_json::Error),
+    /// A transient failure (see `is_transient`) that lasted through every retry
+    GaveUp {
+        attempts: u32,
+        last: Box<WeatherError>,
+    },
+}
+
+// OpenWeather's error body, e.g. {"cod":"404","message":"city not found"}.
+// `cod` is a string on some endpoints and a number on others, so take either.
+#[derive(Deserialize)]
+struct ErrorBody {
+    cod: Code,
+}
+
+// #[serde(untagged)] tries each variant in turn, like a union type in TypeScript
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Code {
+    Number(u16),
+    Text(String),
+}
+
+impl WeatherError {
+    /// Map an unsuccessful response to an error. The `cod` in the body wins when it can be
+    /// read; otherwise the HTTP status decides.
+    pub fn from_response(status: u16, body: &str) -> WeatherError {
+        let code = serde_json::from_str::<ErrorBody>(body)
+            .ok()
+            .and_then(|body| match body.cod {
+                Code::Number(code) => Some(code),
+                Code::Text(text) => text.parse().ok(),
+            })
+            .unwrap_or(status);
+        match code {
+            404 => WeatherError::CityNotFound,
+            401 => WeatherError::InvalidApiKey,
+            code => WeatherError::Http(code),
+        }
+    }
+
+    /// Whether trying again later might work: a failed connection, a timeout, rate limiting
+    /// (429) or a server error (5xx). Mistakes on our side, like an unknown city, are final.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            WeatherError::Network(error) => error.is_connect() || error.is_timeout(),
+            WeatherError::Http(status) => *status == 429 || (500..600).contains(status),
+            _ => false,
+        }
+    }
+}
+
+// Display is the message a user sees (like getMessage()); Debug is for developers.
+impl fmt::Display for WeatherError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeatherError::CityNotFound => write!(f, "city not found"),
+            WeatherError::InvalidApiKey => write!(f, "invalid API key"),
+            WeatherError::Http(status) => write!(f, "request failed with status {}", status),
+            WeatherError::Network(error) if error.is_timeout() => write!(f, "request timed out"),
+            WeatherError::Network(error) => write!(f, "network error: {}", error),
+            WeatherError::Parse(error) => write!(f, "unexpected response: {}", error),
+            WeatherError::GaveUp { attempts, last } => {
+                write!(f, "gave up after {} attempts: {}", attempts, last)
+            }
+        }
+    }
+}
+
+impl Error for WeatherError {
+    // The wrapped error, like Java's getCause()
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WeatherError::Network(error) => Some(error),
+            WeatherError::Parse(error) => Some(error),
+            // Deref the Box to lend out the error inside it
+            WeatherError::GaveUp { last, .. } => Some(&**last),
+            _ => None,
+        }
+    }
+}
+
+// These From impls let `?` convert the underlying errors automatically
+impl From<reqwest::Error> for WeatherError {
+    fn from(error: reqwest::Error) -> Self {
+        WeatherError::Network(error)
+    }
+}
+
+impl From<serde_json::Error> for WeatherError {
+    fn from(error: serde_json::Error) -> Self {
+        WeatherError::Parse(error)
+    }
+}
@@ -0,0 +1,149 @@
+// The 5-day forecast (`/data/2.5/forecast`): one entry every 3 hours, 40 in total, plus
+// helpers to condense those entries into one line per day.
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Weather, Wind};
+
+/// The whole forecast response. Fields we don't use (`cod`, `message`, `cnt`) are ignored:
+/// serde skips unknown JSON keys by default, like Jackson with FAIL_ON_UNKNOWN_PROPERTIES off.
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ForecastResponse {
+    pub list: Vec<ForecastEntry>,
+    pub city: ForecastCity,
+}
+
+/// One 3-hour slot
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ForecastEntry {
+    /// Start of the slot, in seconds since 1970-01-01 UTC (a Unix timestamp)
+    pub dt: i64,
+    pub main: ForecastMain,
+    pub weather: Vec<Weather>,
+    pub wind: Wind,
+    /// Probability of precipitation, 0.0 to 1.0. Older responses leave it out.
+    #[serde(default)]
+    pub pop: f64,
+    /// Only present when rain is expected in the slot
+    #[serde(default)]
+    pub rain: Option<ForecastRain>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ForecastMain {
+    pub temp: f64,
+    pub temp_min: f64,
+    pub temp_max: f64,
+    // Option + default: a missing humidity becomes None instead of a parse error
+    #[serde(default)]
+    pub humidity: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ForecastRain {
+    /// Rain volume over the 3-hour slot, in mm
+    #[serde(rename = "3h", default)]
+    pub three_hours_mm: Option<f64>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ForecastCity {
+    pub name: String,
+    #[serde(default)]
+    pub country: Option<String>,
+    /// The city's offset from UTC in seconds, so slots can be grouped by *local* day
+    #[serde(default)]
+    pub timezone: i64,
+}
+
+/// One row of the daily table
+#[derive(Debug, Clone, PartialEq)]
+pub struct DaySummary {
+    /// Local date, e.g. `2026-10-16`
+    pub date: String,
+    /// Three-letter weekday, e.g. `Fri`
+    pub weekday: &'static str,
+    pub min: f64,
+    pub max: f64,
+    /// Highest chance of precipitation in any slot that day
+    pub pop: f64,
+    /// The most common description that day
+    pub description: String,
+}
+
+impl ForecastResponse {
+    /// Group the 3-hour slots by local calendar day, in order.
+    pub fn daily(&self) -> Vec<DaySummary> {
+        let mut days: Vec<(i64, Vec<&ForecastEntry>)> = Vec::new();
+        for entry in &self.list {
+            // Integer division rounding down (div_euclid) so times before 1970 still work
+            let day = (entry.dt + self.city.timezone).div_euclid(86_400);
+            match days.last_mut() {
+                Some((last, entries)) if *last == day => entries.push(entry),
+                _ => days.push((day, vec![entry])),
+            }
+        }
+
+        days.into_iter()
+            .map(|(day, entries)| {
+                let (year, month, date) = civil_from_days(day);
+                DaySummary {
+                    date: format!("{:04}-{:02}-{:02}", year, month, date),
+                    weekday: weekday(day),
+                    // f64 isn't Ord (NaN), so fold with f64::min/max instead of Iterator::min
+                    min: entries
+                        .iter()
+                        .map(|e| e.main.temp_min)
+                        .fold(f64::INFINITY, f64::min),
+                    max: entries
+                        .iter()
+                        .map(|e| e.main.temp_max)
+                        .fold(f64::NEG_INFINITY, f64::max),
+                    pop: entries.iter().map(|e| e.pop).fold(0.0, f64::max),
+                    description: most_common_description(&entries),
+                }
+            })
+            .collect()
+    }
+}
+
+fn most_common_description(entries: &[&ForecastEntry]) -> String {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for entry in entries {
+        if let Some(weather) = entry.weather.first() {
+            match counts.iter_mut().find(|(d, _)| *d == weather.description) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((&weather.description, 1)),
+            }
+        }
+    }
+    // max_by_key returns the *last* maximum; reversing first makes ties go to the earliest
+    counts
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, count)| *count)
+        .map(|(description, _)| description.to_string())
+        .unwrap_or_default()
+}
+
+/// Year, month (1-12) and day (1-31) of a count of days since 1970-01-01.
+///
+/// This is Howard Hinnant's `civil_from_days` algorithm: it works in 400-year "eras" starting
+/// on 1 March, so the leap day falls at the end of each year and needs no special case.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097; // day of era, 0..=146096
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365; // year of era
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // day of year, from 1 March
+    let mp = (5 * doy + 2) / 153; // month, counting March as 0
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Three-letter weekday of a count of days since 1970-01-01 (a Thursday)
+pub fn weekday(days: i64) -> &'static str {
+    const NAMES: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    NAMES[days.rem_euclid(7) as usize]
+}
@@ -0,0 +1,64 @@
+// The library half of this crate. `main.rs` is a thin CLI on top of it, and other
+// crates in the repo (e.g. rapier_physics) can depend on it via a path dependency:
+//
+//   rust-weather-api-client = { path = "../rust-weather-api-client" }
+//
+// In Java terms this is the difference between a runnable jar and a library jar;
+// Cargo lets one package be both at once (src/lib.rs + src/main.rs).
+
+// 'pub mod' makes the module (and its pub items) visible to users of the library.
+pub mod air;
+pub mod cli;
+pub mod client;
+pub mod error;
+pub mod forecast;
+pub mod models;
+pub mod output;
+pub mod units;
+
+use std::env;
+use std::sync::OnceLock;
+
+use tokio::runtime::Runtime;
+
+pub use air::{AirQuality, AirQualityResponse, AqiCategory};
+pub use client::{RetryPolicy, WeatherClient, BASE_URL, DEFAULT_TIMEOUT, GEOCODE_LIMIT};
+pub use error::WeatherError;
+pub use forecast::{DaySummary, ForecastResponse};
+pub use models::{GeoLocation, WeatherResponse, WeatherSummary};
+pub use output::OutputFormat;
+pub use units::Units;
+
+/// Read `OPENWEATHER_API_KEY` (after loading `.env`, if present).
+pub fn api_key_from_env() -> Option<String> {
+    dotenv::dotenv().ok();
+    env::var("OPENWEATHER_API_KEY")
+        .ok()
+        .filter(|k| !k.trim().is_empty())
+}
+
+// The runtime behind `fetch_summary_blocking`, started on the first call and kept for the
+// rest of the program instead of being built and torn down on every call. OnceLock is std's
+// thread-safe "initialize once" cell (like a lazily created Java singleton).
+static BLOCKING_RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+fn blocking_runtime() -> &'static Runtime {
+    BLOCKING_RUNTIME.get_or_init(|| {
+        // One worker thread drives the network IO, so several threads may block on
+        // their own requests at the same time.
+        tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .expect("failed to start the Tokio runtime for blocking calls")
+    })
+}
+
+/// Blocking helper for callers without an async runtime (e.g. a game loop that
+/// fetches on a plain `std::thread`). Every call shares one small Tokio runtime.
+pub fn fetch_summary_blocking(city: &str, api_key: &str) -> Result<WeatherSummary, WeatherError> {
+    // The summary's fields are in metric units (temperature_c, wind_speed_ms)
+    let client = WeatherClient::new(api_key).with_units(Units::Metric);
+    let weather = blocking_runtime().block_on(client.get_weather(city))?;
+    Ok(weather.summary())
+}
@@ -1,81 +1,121 @@
-// 'mod' declares a submodule. It looks for a file named 'models.rs' or 'models/mod.rs'.
-// This is similar to having a class in the same package in Java.
-mod models;
-
 // 'use' brings items into scope, similar to 'import' in Java.
-use dotenv::dotenv;
-use std::env;
-use std::error::Error;
-use models::WeatherResponse;
+// The HTTP code and models live in the library crate (src/lib.rs); the binary
+// refers to it by the package name with '-' replaced by '_'.
+use std::process::ExitCode;
 
-// Constants are declared with 'const'. Type annotation is mandatory.
-// 'static' lifetime is inferred for string literals.
-const BASE_URL: &str = "https://api.openweathermap.org/data/2.5/weather";
+use futures::future::join_all;
+use rust_weather_api_client::cli::{parse_args, wants_help, USAGE};
+use rust_weather_api_client::output::{write_current, write_forecast, PlaceResult};
+use rust_weather_api_client::{ForecastResponse, WeatherClient};
 
 // #[tokio::main] is a macro that transforms the async main function into a synchronous one
 // that initializes the Tokio runtime and executes the async code.
 // Java doesn't have a direct equivalent, but it's like setting up a main thread that joins on a CompletableFuture.
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
-    // Load environment variables from a .env file, if present.
-    dotenv().ok();
+// ExitCode lets main choose the process exit status, like System.exit(n) but without
+// skipping destructors: 0 for success, 1 if a city failed, 2 for bad arguments.
+async fn main() -> ExitCode {
+    // std::env::args() yields the program name first (like argv[0] in C); skip it.
+    // collect() gathers the iterator into a Vec<String>, like stream().collect(toList()).
+    let raw: Vec<String> = std::env::args().skip(1).collect();
+    if wants_help(&raw) {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let args = match parse_args(raw) {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
 
-    // Fetch the API key from environment variables.
-    // env::var returns a Result<String, VarError>.
-    // expect() unwraps the Result, panicking with the message if it's an Err.
+    // Load the API key from the environment (a .env file is read first, if present).
+    // api_key_from_env returns an Option<String>.
+    // expect() unwraps the Option, panicking with the message if it's None.
     // In Java, this would be like System.getenv("OPENWEATHER_API_KEY") and throwing a RuntimeException if null.
-    let api_key = env::var("OPENWEATHER_API_KEY")
+    let api_key = rust_weather_api_client::api_key_from_env()
         .expect("OPENWEATHER_API_KEY must be set in .env file");
 
-    // Define the city we want to look up.
-    // 'let' binds a variable. Variables are immutable by default.
-    let city = "London";
-
-    println!("Fetching weather for {}...", city);
-
-    // Call the async function. In Rust, calling an async function returns a Future (like CompletableFuture).
-    // .await suspends the current function until the Future completes.
-    // The '?' operator checks the Result. If Ok, it unwraps the value. If Err, it returns the error from the function immediately.
-    // This is a concise way to do error propagation, replacing try-catch blocks for checked exceptions.
-    let weather = get_weather(city, &api_key).await?;
-
-    // Print the result using the Debug implementation (derived in models.rs).
-    // {:?} is the debug formatter. {} is the display formatter (like toString()).
-    println!("Full Weather Data: {:?}", weather);
-
-    println!("---------------------------------");
-    println!("Weather in {}: {}", weather.name, weather.weather[0].description);
-    println!("Temperature: {:.2}°C", weather.main.temp);
-    println!("Humidity: {}%", weather.main.humidity);
-    println!("Wind Speed: {} m/s", weather.wind.speed);
+    // One client for all cities; the flags become its settings. With --aqi, each weather
+    // request also fetches the air quality at the same coordinates.
+    let mut client = WeatherClient::new(api_key)
+        .with_units(args.units)
+        .with_air_quality(args.aqi);
+    if let Some(lang) = &args.lang {
+        client = client.with_lang(lang.as_str());
+    }
+    if let Some(country) = &args.country {
+        client = client.with_country(country.as_str());
+    }
+    // Point the CLI at a mock or a proxy instead of api.openweathermap.org
+    if let Ok(base_url) = std::env::var("OPENWEATHER_BASE_URL") {
+        client = client.with_base_url(base_url);
+    }
 
-    // Return Ok(()) to indicate success. () is the unit type, similar to void in Java, but it's an actual value.
-    Ok(())
-}
+    // A position needs no geocoding; it's labelled with its coordinates
+    let places: Vec<String> = match args.coords {
+        Some((lat, lon)) => vec![format!("{}, {}", lat, lon)],
+        None => args.cities.clone(),
+    };
+    // Progress goes to stderr, so stdout holds only the results (safe to pipe into jq)
+    if !args.quiet {
+        let what = if args.forecast { "forecast" } else { "weather" };
+        eprintln!("Fetching {} for {}...", what, places.join("; "));
+    }
 
-// An async function definition.
-// Arguments are passed by reference (&str) to avoid copying strings (borrowing).
-// Returns a Result<WeatherResponse, Box<dyn Error>>.
-// Box<dyn Error> is a type-erased error object, similar to throwing 'Exception' in Java.
-async fn get_weather(city: &str, api_key: &str) -> Result<WeatherResponse, Box<dyn Error>> {
-    // Construct the URL. format! is a macro for string interpolation.
-    let url = format!("{}?q={}&appid={}&units=metric", BASE_URL, city, api_key);
+    // Borrow each String as &str, the type get_many takes
+    let cities: Vec<&str> = args.cities.iter().map(String::as_str).collect();
+    let (stdout, stderr) = (std::io::stdout(), std::io::stderr());
+    // lock() holds stdout for the whole report instead of once per line
+    let (mut out, mut errors) = (stdout.lock(), stderr.lock());
 
-    // reqwest::get is an async HTTP call.
-    // .await waits for the response.
-    // ? propagates any network errors.
-    let response = reqwest::get(&url).await?;
+    // All cities are requested at once and printed in the order given. Calling an async
+    // function returns a Future (like CompletableFuture); .await suspends main until the
+    // results are in.
+    let (written, failed) = if args.forecast {
+        let results: Vec<PlaceResult<ForecastResponse>> = match args.coords {
+            Some((lat, lon)) => vec![(
+                places[0].clone(),
+                client.get_forecast_by_coords(lat, lon).await,
+            )],
+            None => {
+                // get_many only covers the current weather; forecasts overlap the same way
+                let forecasts = join_all(cities.iter().map(|city| client.get_forecast(city))).await;
+                places.into_iter().zip(forecasts).collect()
+            }
+        };
+        (
+            write_forecast(&mut out, &mut errors, args.output, &results, client.units),
+            any_failed(&results),
+        )
+    } else {
+        let results = match args.coords {
+            Some((lat, lon)) => vec![(
+                places[0].clone(),
+                client.get_weather_by_coords(lat, lon).await,
+            )],
+            None => client.get_many(&cities).await,
+        };
+        (
+            write_current(&mut out, &mut errors, args.output, &results, client.units),
+            any_failed(&results),
+        )
+    };
 
-    // Check if the status is success (200-299).
-    if response.status().is_success() {
-        // Parse the JSON body into our WeatherResponse struct.
-        // This uses Serde under the hood.
-        let weather_data = response.json::<WeatherResponse>().await?;
-        Ok(weather_data)
+    // Writing only fails if stdout is gone, e.g. piped into `head` which has exited
+    if let Err(error) = written {
+        eprintln!("error: {}", error);
+        return ExitCode::FAILURE;
+    }
+    // Non-zero if any city failed, in every output format
+    if failed {
+        ExitCode::FAILURE
     } else {
-        // If the API returns an error, we can construct a custom error message.
-        // .into() converts the String into a Box<dyn Error>.
-        let error_msg = format!("Request failed with status: {}", response.status());
-        Err(error_msg.into())
+        ExitCode::SUCCESS
     }
 }
+
+fn any_failed<T>(results: &[PlaceResult<T>]) -> bool {
+    results.iter().any(|(_, result)| result.is_err())
+}
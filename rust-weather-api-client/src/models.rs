@@ -1,10 +1,13 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+use crate::air::AirQuality;
 
 // In Rust, we use structs to define data structures, similar to Java classes with public fields.
 // The #[derive(Deserialize)] attribute is a macro that automatically generates code
 // to create this struct from JSON (like Jackson's ObjectMapper would use reflection).
+// Serialize is the reverse (struct to JSON), used by `--output json`.
 // Debug allows us to print the struct using {:?} format specifier (like toString()).
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct WeatherResponse {
     // 'pub' makes the field accessible from other modules (like public in Java).
     // If omitted, fields are private to the module by default.
@@ -12,16 +15,25 @@ pub struct WeatherResponse {
     pub weather: Vec<Weather>, // Vec<T> is a growable array, similar to ArrayList<T>
     pub main: Main,
     pub wind: Wind,
+    // Option<T> because OpenWeatherMap leaves "rain" out entirely when it's dry.
+    // #[serde(default)] turns a missing field into None (like @JsonInclude(NON_NULL) in reverse).
+    #[serde(default)]
+    pub rain: Option<Rain>,
     pub name: String, // String is an owned, heap-allocated string (like Java's String)
+    // Not part of OpenWeather's weather response: WeatherClient fills it in when asked for
+    // air quality too, so the two travel together. skip_serializing_if leaves it out of
+    // `--output json` when it wasn't requested.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub air_quality: Option<AirQuality>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Coord {
     pub lon: f64, // f64 is a 64-bit floating point number (like double in Java)
     pub lat: f64,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Weather {
     pub id: u32, // u32 is an unsigned 32-bit integer. Java doesn't have unsigned primitives by default.
     pub main: String,
@@ -29,7 +41,7 @@ pub struct Weather {
     pub icon: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Main {
     pub temp: f64,
     pub feels_like: f64,
@@ -39,8 +51,83 @@ pub struct Main {
     pub humidity: u32,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Wind {
     pub speed: f64,
     pub deg: u32,
-}
\ No newline at end of file
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Rain {
+    // The JSON key is "1h", which isn't a valid Rust identifier, so we rename it.
+    #[serde(rename = "1h")]
+    pub one_hour_mm: Option<f64>,
+}
+
+/// One match from the geocoding API (`/geo/1.0/direct`)
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct GeoLocation {
+    pub name: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// ISO 3166 country code, e.g. "GB"
+    pub country: String,
+    /// State or region, when the API knows one (e.g. "Ontario")
+    #[serde(default)]
+    pub state: Option<String>,
+}
+
+/// The handful of numbers most callers care about, flattened out of `WeatherResponse`.
+// Clone + PartialEq make it easy to pass around and compare in tests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeatherSummary {
+    pub city: String,
+    /// Human-readable conditions, e.g. "light rain"
+    pub description: String,
+    pub temperature_c: f64,
+    pub wind_speed_ms: f64,
+    /// Meteorological direction the wind comes *from* (0 = north, 90 = east)
+    pub wind_deg: u32,
+    /// Rough chance of rain between 0.0 and 1.0 (see `WeatherResponse::summary`)
+    pub rain_probability: f64,
+}
+
+impl WeatherResponse {
+    /// Condense the response into a `WeatherSummary`.
+    ///
+    /// The current-weather endpoint has no forecast probability, so the rain
+    /// chance is estimated: measured rainfall in the last hour counts most,
+    /// a rainy condition group ("Rain", "Drizzle", "Thunderstorm") counts half.
+    pub fn summary(&self) -> WeatherSummary {
+        let rainy_condition = self
+            .weather
+            .iter()
+            .any(|w| matches!(w.main.as_str(), "Rain" | "Drizzle" | "Thunderstorm"));
+        let rain_mm = self
+            .rain
+            .as_ref()
+            .and_then(|r| r.one_hour_mm)
+            .unwrap_or(0.0);
+
+        let rain_probability = if rain_mm > 0.0 {
+            (0.5 + rain_mm / 4.0).min(1.0)
+        } else if rainy_condition {
+            0.5
+        } else {
+            0.0
+        };
+
+        WeatherSummary {
+            city: self.name.clone(),
+            description: self
+                .weather
+                .first()
+                .map(|w| w.description.clone())
+                .unwrap_or_default(),
+            temperature_c: self.main.temp,
+            wind_speed_ms: self.wind.speed,
+            wind_deg: self.wind.deg,
+            rain_probability,
+        }
+    }
+}
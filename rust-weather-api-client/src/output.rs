@@ -0,0 +1,279 @@
+// What the CLI prints, in the format chosen with `--output`: text for people, JSON and CSV for
+// other programs (`jq`, a spreadsheet, a shell script).
+//
+// The functions write to any `io::Write` rather than calling println!, so tests can capture the
+// output in a Vec<u8>, the way a Java test would pass a StringWriter instead of System.out.
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::air::{AirQuality, AqiCategory};
+use crate::error::WeatherError;
+use crate::forecast::ForecastResponse;
+use crate::models::WeatherResponse;
+use crate::units::Units;
+
+/// The `--output` formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// A block per city, for reading in a terminal
+    #[default]
+    Text,
+    /// One JSON array holding every city's full response (or error)
+    Json,
+    /// A header row, then one row per city (or per forecast day)
+    Csv,
+}
+
+impl OutputFormat {
+    /// Parse `text`, `json` or `csv` (any case)
+    pub fn parse(value: &str) -> Result<OutputFormat, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!(
+                "invalid output '{}' (expected text, json or csv)",
+                value
+            )),
+        }
+    }
+}
+
+/// One place's result: the name the user asked for, and the response or why it failed
+pub type PlaceResult<T> = (String, Result<T, WeatherError>);
+
+// A JSON array element. Lifetimes ('a) let it borrow the results instead of copying them;
+// skip_serializing_if leaves out whichever of `response`/`error` is None.
+#[derive(Serialize)]
+struct JsonEntry<'a, T> {
+    city: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response: Option<&'a T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Write the current weather for each place to `out`. Failures go to `errors` (stderr), except
+/// in JSON, where they are entries in the array so the document stays complete.
+pub fn write_current(
+    out: &mut impl Write,
+    errors: &mut impl Write,
+    format: OutputFormat,
+    results: &[PlaceResult<WeatherResponse>],
+    units: Units,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Text => write_text(out, errors, results, |out, weather| {
+            // {:.2} rounds to two decimal places
+            writeln!(out, "Weather in {}: {}", weather.name, description(weather))?;
+            writeln!(
+                out,
+                "Temperature: {:.2}{}",
+                weather.main.temp,
+                units.temperature_symbol()
+            )?;
+            writeln!(out, "Humidity: {}%", weather.main.humidity)?;
+            writeln!(
+                out,
+                "Wind Speed: {} {}",
+                weather.wind.speed,
+                units.speed_symbol()
+            )?;
+            if let Some(air) = &weather.air_quality {
+                writeln!(out, "Air Quality: {}", air_summary(air))?;
+            }
+            Ok(())
+        }),
+        OutputFormat::Json => write_json(out, results),
+        OutputFormat::Csv => {
+            // The air quality columns are only there when it was fetched (--aqi)
+            let with_air = results
+                .iter()
+                .any(|(_, result)| matches!(result, Ok(w) if w.air_quality.is_some()));
+            write!(out, "city,temp,humidity,wind,description")?;
+            if with_air {
+                write!(out, ",aqi,aqi_category,pm2_5,pm10,o3,no2")?;
+            }
+            writeln!(out)?;
+            for (place, result) in results {
+                match result {
+                    Ok(weather) => {
+                        write!(
+                            out,
+                            "{},{},{},{},{}",
+                            csv_field(place),
+                            weather.main.temp,
+                            weather.main.humidity,
+                            weather.wind.speed,
+                            csv_field(description(weather))
+                        )?;
+                        if with_air {
+                            write!(out, "{}", air_columns(weather.air_quality.as_ref()))?;
+                        }
+                        writeln!(out)?;
+                    }
+                    Err(error) => report(errors, place, error)?,
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Write the 5-day forecast for each place: a table per place in text, one row per day in CSV,
+/// and the full 3-hourly response in JSON.
+pub fn write_forecast(
+    out: &mut impl Write,
+    errors: &mut impl Write,
+    format: OutputFormat,
+    results: &[PlaceResult<ForecastResponse>],
+    units: Units,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Text => write_text(out, errors, results, |out, forecast| {
+            let symbol = units.temperature_symbol();
+            // {:<14} pads to 14 characters, left-aligned; {:>6.1} right-aligns with one decimal
+            writeln!(
+                out,
+                "{:<14} {:>8} {:>8} {:>5}  Conditions",
+                "Day", "Min", "Max", "Rain"
+            )?;
+            for day in forecast.daily() {
+                writeln!(
+                    out,
+                    "{} {:<10} {:>6.1}{} {:>6.1}{} {:>4.0}%  {}",
+                    day.weekday,
+                    day.date,
+                    day.min,
+                    symbol,
+                    day.max,
+                    symbol,
+                    day.pop * 100.0,
+                    day.description
+                )?;
+            }
+            Ok(())
+        }),
+        OutputFormat::Json => write_json(out, results),
+        OutputFormat::Csv => {
+            writeln!(out, "city,date,weekday,min,max,pop,description")?;
+            for (place, result) in results {
+                match result {
+                    Ok(forecast) => {
+                        for day in forecast.daily() {
+                            writeln!(
+                                out,
+                                "{},{},{},{},{},{},{}",
+                                csv_field(place),
+                                day.date,
+                                day.weekday,
+                                day.min,
+                                day.max,
+                                day.pop,
+                                csv_field(&day.description)
+                            )?;
+                        }
+                    }
+                    Err(error) => report(errors, place, error)?,
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+// Text: each place under its own header, then a blank line. `body` prints one response; it's
+// a closure parameter, like passing a BiConsumer<Writer, T> in Java.
+fn write_text<W: Write, E: Write, T>(
+    out: &mut W,
+    errors: &mut E,
+    results: &[PlaceResult<T>],
+    body: impl Fn(&mut W, &T) -> io::Result<()>,
+) -> io::Result<()> {
+    for (place, result) in results {
+        writeln!(out, "=== {} ===", place)?;
+        match result {
+            Ok(response) => body(out, response)?,
+            Err(error) => report(errors, place, error)?,
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn write_json<T: Serialize>(out: &mut impl Write, results: &[PlaceResult<T>]) -> io::Result<()> {
+    let entries: Vec<JsonEntry<T>> = results
+        .iter()
+        .map(|(place, result)| JsonEntry {
+            city: place,
+            // .ok() / .err() split the Result into two Options
+            response: result.as_ref().ok(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        })
+        .collect();
+    serde_json::to_writer_pretty(&mut *out, &entries)?;
+    writeln!(out)
+}
+
+// The error's Display text is already readable ("city not found", "invalid API key")
+fn report(errors: &mut impl Write, place: &str, error: &WeatherError) -> io::Result<()> {
+    writeln!(errors, "Could not fetch weather for {}: {}", place, error)
+}
+
+/// One line for people, e.g. `Fair (AQI 2) · PM2.5 3.4 μg/m³ · PM10 5.1 μg/m³`. Only the
+/// pollutants the station measured are listed.
+pub fn air_summary(air: &AirQuality) -> String {
+    let mut summary = match air.category() {
+        Some(category) => format!("{} (AQI {})", category, air.main.aqi),
+        None => format!("AQI {}", air.main.aqi),
+    };
+    let c = &air.components;
+    for (name, value) in [
+        ("PM2.5", c.pm2_5),
+        ("PM10", c.pm10),
+        ("O3", c.o3),
+        ("NO2", c.no2),
+    ] {
+        if let Some(value) = value {
+            summary.push_str(&format!(" · {} {} μg/m³", name, value));
+        }
+    }
+    summary
+}
+
+// The trailing CSV columns for the air quality, each left empty when unknown
+fn air_columns(air: Option<&AirQuality>) -> String {
+    // Option<f64> to a cell: the number, or nothing
+    let cell = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+    match air {
+        Some(air) => format!(
+            ",{},{},{},{},{},{}",
+            air.main.aqi,
+            air.category().map(AqiCategory::as_str).unwrap_or_default(),
+            cell(air.components.pm2_5),
+            cell(air.components.pm10),
+            cell(air.components.o3),
+            cell(air.components.no2)
+        ),
+        None => ",,,,,,".to_string(),
+    }
+}
+
+fn description(weather: &WeatherResponse) -> &str {
+    weather
+        .weather
+        .first()
+        .map_or("", |w| w.description.as_str())
+}
+
+/// Quote a CSV field if it needs it (RFC 4180): fields containing a comma, quote or line break
+/// are wrapped in quotes, with inner quotes doubled. "New York" stays bare; "Washington, D.C."
+/// becomes `"Washington, D.C."`.
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,63 @@
+use std::fmt;
+
+// An enum with no data is like a Java enum: a closed set of named values.
+// Copy lets it be passed by value freely (like a Java primitive), since it's tiny.
+/// Which measurement system OpenWeatherMap reports in (the `units` query parameter).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Units {
+    /// Celsius, metres per second
+    #[default]
+    Metric,
+    /// Fahrenheit, miles per hour
+    Imperial,
+    /// Kelvin, metres per second (the API's own default)
+    Standard,
+}
+
+impl Units {
+    /// Parse `metric`, `imperial` or `standard` (any case)
+    pub fn parse(value: &str) -> Result<Units, String> {
+        match value.to_ascii_lowercase().as_str() {
+            "metric" => Ok(Units::Metric),
+            "imperial" => Ok(Units::Imperial),
+            "standard" => Ok(Units::Standard),
+            _ => Err(format!(
+                "invalid units '{}' (expected metric, imperial or standard)",
+                value
+            )),
+        }
+    }
+
+    /// The value sent as `units=` in the request
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Units::Metric => "metric",
+            Units::Imperial => "imperial",
+            Units::Standard => "standard",
+        }
+    }
+
+    /// Suffix for temperatures, e.g. `°C`
+    pub fn temperature_symbol(self) -> &'static str {
+        match self {
+            Units::Metric => "°C",
+            Units::Imperial => "°F",
+            Units::Standard => "K",
+        }
+    }
+
+    /// Suffix for wind speeds, e.g. `m/s`
+    pub fn speed_symbol(self) -> &'static str {
+        match self {
+            Units::Imperial => "mph",
+            Units::Metric | Units::Standard => "m/s",
+        }
+    }
+}
+
+// Display is Rust's toString(): it lets a Units be used with {} in format strings
+impl fmt::Display for Units {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,175 @@
+use rust_weather_api_client::output::{air_summary, write_current, PlaceResult};
+use rust_weather_api_client::{
+    AirQualityResponse, AqiCategory, OutputFormat, RetryPolicy, Units, WeatherClient,
+    WeatherResponse,
+};
+use wiremock::matchers::{path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Captured /data/2.5/air_pollution responses: London with every component, and a station
+// that only measures NO2 and PM10
+const FULL: &str = include_str!("fixtures/air_pollution.json");
+const PARTIAL: &str = include_str!("fixtures/air_pollution_partial.json");
+const WEATHER: &str = include_str!("fixtures/weather.json");
+
+fn json(body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(body.to_string(), "application/json")
+}
+
+#[test]
+fn parses_every_component() {
+    let response: AirQualityResponse = serde_json::from_str(FULL).unwrap();
+    let air = response.current().unwrap();
+    assert_eq!(air.main.aqi, 2);
+    assert_eq!(air.components.pm2_5, Some(3.4));
+    assert_eq!(air.components.pm10, Some(5.1));
+    assert_eq!(air.components.o3, Some(61.51));
+    assert_eq!(air.components.no2, Some(13.54));
+    assert_eq!(air.category(), Some(AqiCategory::Fair));
+}
+
+#[test]
+fn missing_components_are_none() {
+    let response: AirQualityResponse = serde_json::from_str(PARTIAL).unwrap();
+    let air = response.current().unwrap();
+    assert_eq!(air.components.pm2_5, None);
+    assert_eq!(air.components.o3, None);
+    assert_eq!(air.components.pm10, Some(61.2));
+
+    // No components object at all, and an empty list
+    let bare: AirQualityResponse =
+        serde_json::from_str(r#"{"list":[{"main":{"aqi":1}}]}"#).unwrap();
+    assert_eq!(bare.current().unwrap().components.no2, None);
+    let empty: AirQualityResponse = serde_json::from_str(r#"{"list":[]}"#).unwrap();
+    assert!(empty.current().is_none());
+}
+
+#[test]
+fn index_maps_to_categories() {
+    let names: Vec<&str> = (1..=5)
+        .map(|aqi| AqiCategory::from_index(aqi).unwrap().as_str())
+        .collect();
+    assert_eq!(names, vec!["Good", "Fair", "Moderate", "Poor", "Very Poor"]);
+    assert_eq!(AqiCategory::from_index(0), None);
+    assert_eq!(AqiCategory::from_index(6), None);
+    assert_eq!(AqiCategory::VeryPoor.to_string(), "Very Poor");
+}
+
+#[test]
+fn summary_lists_only_measured_pollutants() {
+    let response: AirQualityResponse = serde_json::from_str(PARTIAL).unwrap();
+    assert_eq!(
+        air_summary(response.current().unwrap()),
+        "Poor (AQI 4) · PM10 61.2 μg/m³ · NO2 48.7 μg/m³"
+    );
+}
+
+#[tokio::test]
+async fn fetched_alongside_the_weather() {
+    let server = MockServer::start().await;
+    Mock::given(path("/data/2.5/weather"))
+        .respond_with(json(WEATHER))
+        .expect(1)
+        .mount(&server)
+        .await;
+    // The air quality is asked for at the same coordinates
+    Mock::given(path("/data/2.5/air_pollution"))
+        .and(query_param("lat", "51.5"))
+        .and(query_param("lon", "-0.12"))
+        .respond_with(json(FULL))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = WeatherClient::new("k")
+        .with_base_url(server.uri())
+        .with_air_quality(true);
+
+    let weather = client.get_weather_by_coords(51.5, -0.12).await.unwrap();
+    let air = weather.air_quality.expect("air quality was requested");
+    assert_eq!(air.category(), Some(AqiCategory::Fair));
+}
+
+#[tokio::test]
+async fn not_fetched_unless_asked() {
+    let server = MockServer::start().await;
+    Mock::given(path("/data/2.5/weather"))
+        .respond_with(json(WEATHER))
+        .mount(&server)
+        .await;
+    Mock::given(path("/data/2.5/air_pollution"))
+        .respond_with(json(FULL))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let client = WeatherClient::new("k").with_base_url(server.uri());
+
+    let weather = client.get_weather_by_coords(51.5, -0.12).await.unwrap();
+    assert!(weather.air_quality.is_none());
+}
+
+#[tokio::test]
+async fn an_air_quality_failure_fails_the_city() {
+    let server = MockServer::start().await;
+    Mock::given(path("/data/2.5/weather"))
+        .respond_with(json(WEATHER))
+        .mount(&server)
+        .await;
+    Mock::given(path("/data/2.5/air_pollution"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&server)
+        .await;
+    let client = WeatherClient::new("k")
+        .with_base_url(server.uri())
+        .with_retry(RetryPolicy::none())
+        .with_air_quality(true);
+
+    assert!(client.get_weather_by_coords(0.0, 0.0).await.is_err());
+}
+
+fn london_with_air() -> Vec<PlaceResult<WeatherResponse>> {
+    let mut weather: WeatherResponse = serde_json::from_str(WEATHER).unwrap();
+    let air: AirQualityResponse = serde_json::from_str(FULL).unwrap();
+    weather.air_quality = air.current().cloned();
+    vec![("London".to_string(), Ok(weather))]
+}
+
+fn render(format: OutputFormat) -> String {
+    let mut out = Vec::new();
+    write_current(
+        &mut out,
+        &mut Vec::new(),
+        format,
+        &london_with_air(),
+        Units::Metric,
+    )
+    .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn text_output_adds_a_line() {
+    assert!(render(OutputFormat::Text).contains(
+        "Wind Speed: 4.12 m/s\nAir Quality: Fair (AQI 2) · PM2.5 3.4 μg/m³ · PM10 5.1 μg/m³ · O3 61.51 μg/m³ · NO2 13.54 μg/m³\n"
+    ));
+}
+
+#[test]
+fn csv_output_adds_columns() {
+    assert_eq!(
+        render(OutputFormat::Csv),
+        "\
+city,temp,humidity,wind,description,aqi,aqi_category,pm2_5,pm10,o3,no2
+London,15.32,72,4.12,broken clouds,2,Fair,3.4,5.1,61.51,13.54
+"
+    );
+}
+
+#[test]
+fn json_output_includes_it() {
+    let json: serde_json::Value = serde_json::from_str(&render(OutputFormat::Json)).unwrap();
+    assert_eq!(json[0]["response"]["air_quality"]["main"]["aqi"], 2);
+    assert_eq!(
+        json[0]["response"]["air_quality"]["components"]["pm2_5"],
+        3.4
+    );
+}
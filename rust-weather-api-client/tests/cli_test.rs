@@ -0,0 +1,152 @@
+use rust_weather_api_client::cli::{parse_args, wants_help, Args};
+use rust_weather_api_client::{OutputFormat, Units};
+
+// Turn string literals into the owned Strings std::env::args() would give
+fn parse(args: &[&str]) -> Result<Args, String> {
+    parse_args(args.iter().map(|s| s.to_string()))
+}
+
+#[test]
+fn single_city_uses_the_defaults() {
+    let args = parse(&["London"]).unwrap();
+    assert_eq!(args.cities, vec!["London"]);
+    assert_eq!(args.units, Units::Metric);
+    assert_eq!(args.lang, None);
+}
+
+#[test]
+fn flags_before_the_cities() {
+    let args = parse(&["--units", "imperial", "--lang", "fr", "Paris", "New York"]).unwrap();
+    assert_eq!(args.cities, vec!["Paris", "New York"]);
+    assert_eq!(args.units, Units::Imperial);
+    assert_eq!(args.lang.as_deref(), Some("fr"));
+}
+
+#[test]
+fn flags_after_and_between_the_cities() {
+    let args = parse(&["Oslo", "--units=standard", "Bergen", "--lang=no"]).unwrap();
+    assert_eq!(args.cities, vec!["Oslo", "Bergen"]);
+    assert_eq!(args.units, Units::Standard);
+    assert_eq!(args.lang.as_deref(), Some("no"));
+}
+
+#[test]
+fn units_are_case_insensitive() {
+    assert_eq!(
+        parse(&["--units", "IMPERIAL", "Austin"]).unwrap().units,
+        Units::Imperial
+    );
+}
+
+#[test]
+fn invalid_units_are_an_error() {
+    let error = parse(&["--units", "kelvin", "London"]).unwrap_err();
+    assert!(error.contains("kelvin"), "{}", error);
+}
+
+#[test]
+fn missing_values_and_unknown_flags_are_errors() {
+    assert_eq!(
+        parse(&["London", "--units"]).unwrap_err(),
+        "--units needs a value"
+    );
+    assert_eq!(parse(&["--lang"]).unwrap_err(), "--lang needs a value");
+    assert!(parse(&["--verbose", "London"])
+        .unwrap_err()
+        .contains("--verbose"));
+}
+
+#[test]
+fn no_city_is_an_error() {
+    assert_eq!(parse(&[]).unwrap_err(), "no city given");
+    assert_eq!(parse(&["--units", "metric"]).unwrap_err(), "no city given");
+}
+
+#[test]
+fn help_flag_is_detected() {
+    assert!(wants_help(&["London".to_string(), "-h".to_string()]));
+    assert!(wants_help(&["--help".to_string()]));
+    assert!(!wants_help(&["London".to_string()]));
+}
+
+#[test]
+fn units_map_to_api_values_and_symbols() {
+    assert_eq!(Units::Metric.as_str(), "metric");
+    assert_eq!(Units::Imperial.to_string(), "imperial");
+    assert_eq!(Units::Imperial.temperature_symbol(), "°F");
+    assert_eq!(Units::Standard.temperature_symbol(), "K");
+    assert_eq!(Units::Imperial.speed_symbol(), "mph");
+}
+
+#[test]
+fn forecast_is_a_switch() {
+    assert!(!parse(&["London"]).unwrap().forecast);
+    let args = parse(&["--forecast", "London", "Paris"]).unwrap();
+    assert!(args.forecast);
+    assert_eq!(args.cities, vec!["London", "Paris"]);
+    assert_eq!(
+        parse(&["London", "--forecast=yes"]).unwrap_err(),
+        "--forecast takes no value"
+    );
+}
+
+#[test]
+fn coordinates_instead_of_cities() {
+    let args = parse(&["--lat", "51.5", "--lon=-0.12"]).unwrap();
+    assert_eq!(args.coords, Some((51.5, -0.12)));
+    assert!(args.cities.is_empty());
+}
+
+#[test]
+fn coordinates_must_come_in_pairs_and_in_range() {
+    assert_eq!(
+        parse(&["--lat", "51.5"]).unwrap_err(),
+        "--lat and --lon must be given together"
+    );
+    assert!(parse(&["--lat", "91", "--lon", "0"])
+        .unwrap_err()
+        .starts_with("--lat must be a number from -90 to 90"));
+    assert!(parse(&["--lat", "0", "--lon", "east"]).is_err());
+    assert!(parse(&["--lat", "NaN", "--lon", "0"]).is_err());
+}
+
+#[test]
+fn coordinates_and_cities_do_not_mix() {
+    assert_eq!(
+        parse(&["London", "--lat", "1", "--lon", "2"]).unwrap_err(),
+        "give either cities or --lat/--lon, not both"
+    );
+}
+
+#[test]
+fn country_narrows_the_cities() {
+    let args = parse(&["London", "--country", "CA"]).unwrap();
+    assert_eq!(args.country.as_deref(), Some("CA"));
+    assert_eq!(args.coords, None);
+}
+
+#[test]
+fn output_and_quiet() {
+    let args = parse(&["London"]).unwrap();
+    assert_eq!(args.output, OutputFormat::Text);
+    assert!(!args.quiet);
+
+    let args = parse(&["--output", "json", "-q", "London"]).unwrap();
+    assert_eq!(args.output, OutputFormat::Json);
+    assert!(args.quiet);
+    assert!(parse(&["London", "--quiet", "--output=csv"]).unwrap().quiet);
+    assert_eq!(
+        parse(&["London", "--output", "xml"]).unwrap_err(),
+        "invalid output 'xml' (expected text, json or csv)"
+    );
+}
+
+#[test]
+fn aqi_is_a_switch_for_current_weather_only() {
+    assert!(!parse(&["London"]).unwrap().aqi);
+    assert!(parse(&["--aqi", "London"]).unwrap().aqi);
+    assert_eq!(
+        parse(&["--aqi", "--forecast", "London"]).unwrap_err(),
+        "--aqi can't be combined with --forecast"
+    );
+}
@@ -0,0 +1,130 @@
+use std::error::Error;
+
+use rust_weather_api_client::{Units, WeatherClient, WeatherError, WeatherResponse, BASE_URL};
+
+#[test]
+fn not_found_body_maps_to_city_not_found() {
+    // What OpenWeather sends for an unknown city: cod is a string here
+    let body = r#"{"cod":"404","message":"city not found"}"#;
+    assert!(matches!(
+        WeatherError::from_response(404, body),
+        WeatherError::CityNotFound
+    ));
+}
+
+#[test]
+fn unauthorized_body_maps_to_invalid_api_key() {
+    // ...and for a bad key: cod is a number
+    let body = r#"{"cod":401,"message":"Invalid API key. Please see https://openweathermap.org/faq#error401 for more info."}"#;
+    assert!(matches!(
+        WeatherError::from_response(401, body),
+        WeatherError::InvalidApiKey
+    ));
+}
+
+#[test]
+fn other_statuses_keep_their_code() {
+    let body = r#"{"cod":429,"message":"Your account is temporarily blocked due to exceeding of requests limitation"}"#;
+    assert!(matches!(
+        WeatherError::from_response(429, body),
+        WeatherError::Http(429)
+    ));
+}
+
+#[test]
+fn status_decides_when_the_body_is_not_json() {
+    // A proxy in front of the API might answer with HTML
+    let html = "<html><body>Bad Gateway</body></html>";
+    assert!(matches!(
+        WeatherError::from_response(502, html),
+        WeatherError::Http(502)
+    ));
+    assert!(matches!(
+        WeatherError::from_response(404, ""),
+        WeatherError::CityNotFound
+    ));
+}
+
+#[test]
+fn bad_json_becomes_a_parse_error() {
+    let json_error = serde_json::from_str::<WeatherResponse>(r#"{"name":"London"}"#).unwrap_err();
+    let error = WeatherError::from(json_error);
+    assert!(matches!(error, WeatherError::Parse(_)));
+    // The serde error is kept as the cause
+    assert!(error.source().is_some());
+    assert!(error.to_string().starts_with("unexpected response"));
+}
+
+#[test]
+fn error_messages_are_readable() {
+    assert_eq!(WeatherError::CityNotFound.to_string(), "city not found");
+    assert_eq!(WeatherError::InvalidApiKey.to_string(), "invalid API key");
+    assert_eq!(
+        WeatherError::Http(500).to_string(),
+        "request failed with status 500"
+    );
+}
+
+#[test]
+fn new_uses_the_defaults() {
+    let client = WeatherClient::new("key");
+    assert_eq!(client.api_key, "key");
+    assert_eq!(client.units, Units::Metric);
+    assert_eq!(client.lang, None);
+    assert_eq!(client.country, None);
+    assert_eq!(client.base_url, BASE_URL);
+}
+
+#[test]
+fn data_request_carries_the_settings() {
+    let client = WeatherClient::new("secret")
+        .with_units(Units::Imperial)
+        .with_lang("fr")
+        .with_base_url("http://localhost:8080/");
+    let request = client.data_request("weather", 40.7128, -74.006).unwrap();
+    assert_eq!(request.method(), "GET");
+    // The trailing slash on the base URL isn't doubled
+    assert_eq!(
+        request.url().as_str(),
+        "http://localhost:8080/data/2.5/weather?lat=40.7128&lon=-74.006&units=imperial&lang=fr&appid=secret"
+    );
+}
+
+#[test]
+fn data_request_without_a_language() {
+    let request = WeatherClient::new("k")
+        .data_request("forecast", 59.9, 10.75)
+        .unwrap();
+    assert_eq!(
+        request.url().as_str(),
+        "https://api.openweathermap.org/data/2.5/forecast?lat=59.9&lon=10.75&units=metric&appid=k"
+    );
+}
+
+#[test]
+fn geocode_request_encodes_the_city() {
+    let request = WeatherClient::new("k").geocode_request("New York").unwrap();
+    assert_eq!(
+        request.url().as_str(),
+        "https://api.openweathermap.org/geo/1.0/direct?q=New+York&limit=5&appid=k"
+    );
+}
+
+#[test]
+fn geocode_request_adds_the_country() {
+    let request = WeatherClient::new("k")
+        .with_country("CA")
+        .geocode_request("London")
+        .unwrap();
+    assert_eq!(
+        request.url().as_str(),
+        "https://api.openweathermap.org/geo/1.0/direct?q=London%2CCA&limit=5&appid=k"
+    );
+}
+
+#[test]
+fn invalid_base_url_is_an_error() {
+    let client = WeatherClient::new("k").with_base_url("not a url");
+    assert!(client.data_request("weather", 0.0, 0.0).is_err());
+    assert!(client.geocode_request("Oslo").is_err());
+}
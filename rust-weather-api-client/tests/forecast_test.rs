@@ -0,0 +1,91 @@
+use rust_weather_api_client::forecast::{civil_from_days, weekday};
+use rust_weather_api_client::ForecastResponse;
+
+// A real /data/2.5/forecast response for London, trimmed to 11 slots. include_str! embeds the
+// file at compile time, like reading a classpath resource but checked by the compiler.
+const FIXTURE: &str = include_str!("fixtures/forecast.json");
+
+fn fixture() -> ForecastResponse {
+    serde_json::from_str(FIXTURE).expect("fixture should parse")
+}
+
+#[test]
+fn parses_the_captured_response() {
+    let forecast = fixture();
+    assert_eq!(forecast.city.name, "London");
+    assert_eq!(forecast.city.country.as_deref(), Some("GB"));
+    assert_eq!(forecast.city.timezone, 3600);
+    assert_eq!(forecast.list.len(), 11);
+
+    let first = &forecast.list[0];
+    assert_eq!(first.dt, 1_792_173_600);
+    assert_eq!(first.main.temp, 12.1);
+    assert_eq!(first.main.humidity, Some(78));
+    assert_eq!(first.weather[0].description, "broken clouds");
+    assert_eq!(first.wind.speed, 3.1);
+}
+
+#[test]
+fn optional_fields_may_be_missing() {
+    let forecast = fixture();
+    // Only the rainy slots carry a `rain` object
+    assert!(forecast.list[0].rain.is_none());
+    let rain = forecast.list[2].rain.as_ref().expect("slot 2 has rain");
+    assert_eq!(rain.three_hours_mm, Some(0.42));
+    // The last slot has no `pop` at all, which defaults to 0
+    assert_eq!(forecast.list[10].pop, 0.0);
+}
+
+#[test]
+fn minimal_entry_parses() {
+    let json = r#"{
+        "list": [{
+            "dt": 0,
+            "main": {"temp": 1.0, "temp_min": 0.5, "temp_max": 1.5},
+            "weather": [{"id": 701, "main": "Mist", "description": "mist", "icon": "50d"}],
+            "wind": {"speed": 0.0, "deg": 0}
+        }],
+        "city": {"name": "Nowhere"}
+    }"#;
+    let forecast: ForecastResponse = serde_json::from_str(json).unwrap();
+    assert_eq!(forecast.list[0].main.humidity, None);
+    assert_eq!(forecast.city.timezone, 0);
+}
+
+#[test]
+fn groups_slots_by_local_day() {
+    let days = fixture().daily();
+    // UTC+1: 18:00 and 21:00 UTC are still the 16th, 00:00 UTC on the 18th is 01:00 local
+    let dates: Vec<&str> = days.iter().map(|d| d.date.as_str()).collect();
+    assert_eq!(dates, vec!["2026-10-16", "2026-10-17", "2026-10-18"]);
+    let weekdays: Vec<&str> = days.iter().map(|d| d.weekday).collect();
+    assert_eq!(weekdays, vec!["Fri", "Sat", "Sun"]);
+
+    let saturday = &days[1];
+    assert_eq!(saturday.min, 8.0);
+    assert_eq!(saturday.max, 16.7);
+    assert_eq!(saturday.pop, 0.74);
+    assert_eq!(saturday.description, "light rain");
+
+    // Two slots, one each: the tie goes to the earlier description
+    assert_eq!(days[0].description, "broken clouds");
+    assert_eq!(days[2].description, "few clouds");
+}
+
+#[test]
+fn civil_dates_from_day_counts() {
+    assert_eq!(civil_from_days(0), (1970, 1, 1));
+    assert_eq!(civil_from_days(-1), (1969, 12, 31));
+    // 2024 is a leap year; 2100 is not
+    assert_eq!(civil_from_days(19_782), (2024, 2, 29));
+    assert_eq!(civil_from_days(19_783), (2024, 3, 1));
+    assert_eq!(civil_from_days(47_540), (2100, 2, 28));
+    assert_eq!(civil_from_days(47_541), (2100, 3, 1));
+}
+
+#[test]
+fn weekdays_from_day_counts() {
+    assert_eq!(weekday(0), "Thu");
+    assert_eq!(weekday(-1), "Wed");
+    assert_eq!(weekday(20_742), "Fri"); // 2026-10-16
+}
@@ -0,0 +1,114 @@
+use rust_weather_api_client::{GeoLocation, WeatherClient, WeatherError};
+use wiremock::matchers::{path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Captured /geo/1.0/direct responses: five places matching "London", and none at all
+const LONDON_MATCHES: &str = include_str!("fixtures/geocode_london.json");
+const NO_MATCHES: &str = include_str!("fixtures/geocode_none.json");
+const WEATHER: &str = include_str!("fixtures/weather.json");
+
+fn json(body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(body.to_string(), "application/json")
+}
+
+#[test]
+fn parses_multiple_matches() {
+    let places: Vec<GeoLocation> = serde_json::from_str(LONDON_MATCHES).unwrap();
+    assert_eq!(places.len(), 5);
+    assert_eq!(places[0].name, "London");
+    assert_eq!(places[0].country, "GB");
+    assert_eq!(places[0].lat, 51.5073219);
+    assert_eq!(places[2].state.as_deref(), Some("Ontario"));
+    // Not every match has a state
+    assert_eq!(places[3].state, None);
+}
+
+#[test]
+fn parses_no_matches() {
+    let places: Vec<GeoLocation> = serde_json::from_str(NO_MATCHES).unwrap();
+    assert!(places.is_empty());
+}
+
+#[tokio::test]
+async fn geocode_returns_every_match_in_order() {
+    let server = MockServer::start().await;
+    Mock::given(path("/geo/1.0/direct"))
+        .and(query_param("q", "London"))
+        .and(query_param("limit", "5"))
+        .respond_with(json(LONDON_MATCHES))
+        .mount(&server)
+        .await;
+    let client = WeatherClient::new("k").with_base_url(server.uri());
+
+    let places = client.geocode("London").await.unwrap();
+    let countries: Vec<&str> = places.iter().map(|p| p.country.as_str()).collect();
+    assert_eq!(countries, vec!["GB", "GB", "CA", "GB", "US"]);
+}
+
+#[tokio::test]
+async fn weather_uses_the_top_match() {
+    let server = MockServer::start().await;
+    Mock::given(path("/geo/1.0/direct"))
+        .respond_with(json(LONDON_MATCHES))
+        .mount(&server)
+        .await;
+    // Only the first match's coordinates are fetched
+    Mock::given(path("/data/2.5/weather"))
+        .and(query_param("lat", "51.5073219"))
+        .and(query_param("lon", "-0.1276474"))
+        .respond_with(json(WEATHER))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = WeatherClient::new("k").with_base_url(server.uri());
+
+    let weather = client.get_weather("London").await.unwrap();
+    assert_eq!(weather.name, "London");
+}
+
+#[tokio::test]
+async fn country_narrows_the_lookup() {
+    let server = MockServer::start().await;
+    let canada =
+        r#"[{"name":"London","lat":42.9832406,"lon":-81.243372,"country":"CA","state":"Ontario"}]"#;
+    Mock::given(path("/geo/1.0/direct"))
+        .and(query_param("q", "London,CA"))
+        .respond_with(json(canada))
+        .mount(&server)
+        .await;
+    Mock::given(path("/data/2.5/forecast"))
+        .and(query_param("lat", "42.9832406"))
+        .respond_with(json(include_str!("fixtures/forecast.json")))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = WeatherClient::new("k")
+        .with_country("CA")
+        .with_base_url(server.uri());
+
+    let location = client.locate("London").await.unwrap();
+    assert_eq!(location.state.as_deref(), Some("Ontario"));
+    assert!(client.get_forecast("London").await.is_ok());
+}
+
+#[tokio::test]
+async fn no_matches_is_city_not_found() {
+    let server = MockServer::start().await;
+    Mock::given(path("/geo/1.0/direct"))
+        .respond_with(json(NO_MATCHES))
+        .mount(&server)
+        .await;
+    // With nothing to look up, the weather endpoint must not be called
+    Mock::given(path("/data/2.5/weather"))
+        .respond_with(json(WEATHER))
+        .expect(0)
+        .mount(&server)
+        .await;
+    let client = WeatherClient::new("k").with_base_url(server.uri());
+
+    assert!(client.geocode("Atlantis").await.unwrap().is_empty());
+    assert!(matches!(
+        client.get_weather("Atlantis").await,
+        Err(WeatherError::CityNotFound)
+    ));
+}
@@ -0,0 +1,97 @@
+use std::time::{Duration, Instant};
+
+use rust_weather_api_client::{WeatherClient, WeatherError};
+use wiremock::matchers::{method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const LONDON: &str = include_str!("fixtures/weather.json");
+const LONDON_MATCHES: &str = include_str!("fixtures/geocode_london.json");
+const NO_MATCHES: &str = include_str!("fixtures/geocode_none.json");
+
+// A mock OpenWeather that can geocode London and nothing else. Every response waits `delay`.
+async fn server(delay: Duration) -> MockServer {
+    let server = MockServer::start().await;
+    let json = |body: &str| {
+        ResponseTemplate::new(200)
+            .set_body_raw(body.to_string(), "application/json")
+            .set_delay(delay)
+    };
+    Mock::given(method("GET"))
+        .and(path("/geo/1.0/direct"))
+        .and(query_param("q", "London"))
+        .respond_with(json(LONDON_MATCHES))
+        .mount(&server)
+        .await;
+    // Mocks are tried in mount order, so this catches every other city
+    Mock::given(method("GET"))
+        .and(path("/geo/1.0/direct"))
+        .respond_with(json(NO_MATCHES))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/data/2.5/weather"))
+        .respond_with(json(LONDON))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn one_bad_city_does_not_stop_the_others() {
+    let server = server(Duration::ZERO).await;
+    let client = WeatherClient::new("test-key").with_base_url(server.uri());
+
+    let results = client.get_many(&["Lodnon", "London"]).await;
+
+    assert_eq!(results.len(), 2);
+    // Input order is kept, whatever order the responses arrive in
+    assert_eq!(results[0].0, "Lodnon");
+    assert!(matches!(results[0].1, Err(WeatherError::CityNotFound)));
+    assert_eq!(results[1].0, "London");
+    let london = results[1].1.as_ref().expect("London should succeed");
+    assert_eq!(london.name, "London");
+    assert_eq!(london.main.humidity, 72);
+}
+
+#[tokio::test]
+async fn sends_the_key_and_units() {
+    let server = MockServer::start().await;
+    Mock::given(path("/data/2.5/weather"))
+        .and(query_param("lat", "51.5"))
+        .and(query_param("lon", "-0.12"))
+        .and(query_param("appid", "test-key"))
+        .and(query_param("units", "metric"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(LONDON, "application/json"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = WeatherClient::new("test-key").with_base_url(server.uri());
+
+    let weather = client.get_weather_by_coords(51.5, -0.12).await.unwrap();
+    assert_eq!(weather.wind.deg, 240);
+    // expect(1) is checked when the server is dropped at the end of the test
+}
+
+#[tokio::test]
+async fn requests_run_concurrently() {
+    // Geocoding then fetching takes 600ms per city: three one after another would take 1.8s
+    let server = server(Duration::from_millis(300)).await;
+    let client = WeatherClient::new("test-key").with_base_url(server.uri());
+
+    let started = Instant::now();
+    let results = client.get_many(&["London", "London", "London"]).await;
+    let elapsed = started.elapsed();
+
+    assert_eq!(results.len(), 3);
+    assert!(
+        elapsed < Duration::from_millis(1200),
+        "took {:?}, so the requests weren't concurrent",
+        elapsed
+    );
+}
+
+#[tokio::test]
+async fn no_cities_no_requests() {
+    let client = WeatherClient::new("test-key").with_base_url("http://127.0.0.1:9");
+    assert!(client.get_many(&[]).await.is_empty());
+}
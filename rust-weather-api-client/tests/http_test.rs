@@ -0,0 +1,135 @@
+// The client against a local mock of OpenWeather: no API key or network needed. Each test
+// serves a fixture and checks both what the client sent and what it made of the answer.
+use rust_weather_api_client::{RetryPolicy, Units, WeatherClient, WeatherError, BASE_URL};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const WEATHER: &str = include_str!("fixtures/weather.json");
+const GEOCODE: &str = include_str!("fixtures/geocode_london.json");
+const ERROR_401: &str = include_str!("fixtures/error_401.json");
+const ERROR_404: &str = include_str!("fixtures/error_404.json");
+
+fn respond(status: u16, body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(status).set_body_raw(body.to_string(), "application/json")
+}
+
+// Retries off, so each failure is exactly one request
+fn client(server: &MockServer) -> WeatherClient {
+    WeatherClient::new("test-key")
+        .with_base_url(server.uri())
+        .with_retry(RetryPolicy::none())
+}
+
+// "path?query" of every request the server received, in order
+async fn sent(server: &MockServer) -> Vec<String> {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| {
+            format!(
+                "{}?{}",
+                request.url.path(),
+                request.url.query().unwrap_or_default()
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn default_client_talks_to_openweathermap() {
+    assert_eq!(BASE_URL, "https://api.openweathermap.org");
+    assert_eq!(WeatherClient::new("k").base_url, BASE_URL);
+}
+
+#[tokio::test]
+async fn success_sends_the_full_query() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/geo/1.0/direct"))
+        .respond_with(respond(200, GEOCODE))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/data/2.5/weather"))
+        .respond_with(respond(200, WEATHER))
+        .mount(&server)
+        .await;
+
+    let weather = client(&server)
+        .with_units(Units::Imperial)
+        .with_lang("de")
+        .get_weather("London")
+        .await
+        .unwrap();
+
+    assert_eq!(weather.name, "London");
+    assert_eq!(weather.main.temp, 15.32);
+    assert_eq!(
+        sent(&server).await,
+        vec![
+            "/geo/1.0/direct?q=London&limit=5&appid=test-key",
+            "/data/2.5/weather?lat=51.5073219&lon=-0.1276474&units=imperial&lang=de&appid=test-key",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn unauthorized_is_invalid_api_key() {
+    let server = MockServer::start().await;
+    Mock::given(path("/geo/1.0/direct"))
+        .respond_with(respond(401, ERROR_401))
+        .mount(&server)
+        .await;
+
+    let result = client(&server).get_weather("London").await;
+    assert!(matches!(result, Err(WeatherError::InvalidApiKey)));
+    // Nothing else is tried after the lookup is refused
+    assert_eq!(sent(&server).await.len(), 1);
+}
+
+#[tokio::test]
+async fn not_found_is_city_not_found() {
+    let server = MockServer::start().await;
+    Mock::given(path("/data/2.5/weather"))
+        .respond_with(respond(404, ERROR_404))
+        .mount(&server)
+        .await;
+
+    let result = client(&server).get_weather_by_coords(1.0, 2.0).await;
+    assert!(matches!(result, Err(WeatherError::CityNotFound)));
+    assert_eq!(
+        sent(&server).await,
+        vec!["/data/2.5/weather?lat=1&lon=2&units=metric&appid=test-key"]
+    );
+}
+
+#[tokio::test]
+async fn malformed_json_is_a_parse_error() {
+    let server = MockServer::start().await;
+    // Cut off halfway through, as a dropped connection or a broken proxy might
+    Mock::given(path("/data/2.5/weather"))
+        .respond_with(respond(200, &WEATHER[..WEATHER.len() / 2]))
+        .mount(&server)
+        .await;
+
+    let result = client(&server).get_weather_by_coords(0.0, 0.0).await;
+    assert!(matches!(result, Err(WeatherError::Parse(_))));
+}
+
+#[tokio::test]
+async fn wrong_shape_is_a_parse_error() {
+    let server = MockServer::start().await;
+    // Valid JSON, but not a weather response
+    Mock::given(path("/data/2.5/weather"))
+        .respond_with(respond(200, r#"{"cod":200,"message":"ok"}"#))
+        .mount(&server)
+        .await;
+
+    let error = client(&server)
+        .get_weather_by_coords(0.0, 0.0)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("missing field"));
+}
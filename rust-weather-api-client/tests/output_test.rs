@@ -0,0 +1,154 @@
+use rust_weather_api_client::output::{csv_field, write_current, write_forecast, PlaceResult};
+use rust_weather_api_client::{
+    ForecastResponse, OutputFormat, Units, WeatherError, WeatherResponse,
+};
+
+// London from the fixture, then a typo that wasn't found
+fn current() -> Vec<PlaceResult<WeatherResponse>> {
+    let london = serde_json::from_str(include_str!("fixtures/weather.json")).unwrap();
+    vec![
+        ("London".to_string(), Ok(london)),
+        ("Lodnon".to_string(), Err(WeatherError::CityNotFound)),
+    ]
+}
+
+fn forecast() -> Vec<PlaceResult<ForecastResponse>> {
+    let london = serde_json::from_str(include_str!("fixtures/forecast.json")).unwrap();
+    vec![("London".to_string(), Ok(london))]
+}
+
+// Run a writer and return (stdout, stderr) as Strings
+fn capture(
+    write: impl FnOnce(&mut Vec<u8>, &mut Vec<u8>) -> std::io::Result<()>,
+) -> (String, String) {
+    let (mut out, mut errors) = (Vec::new(), Vec::new());
+    write(&mut out, &mut errors).unwrap();
+    (
+        String::from_utf8(out).unwrap(),
+        String::from_utf8(errors).unwrap(),
+    )
+}
+
+#[test]
+fn current_as_text() {
+    let (out, errors) = capture(|out, errors| {
+        write_current(out, errors, OutputFormat::Text, &current(), Units::Metric)
+    });
+    assert_eq!(
+        out,
+        "\
+=== London ===
+Weather in London: broken clouds
+Temperature: 15.32°C
+Humidity: 72%
+Wind Speed: 4.12 m/s
+
+=== Lodnon ===
+
+"
+    );
+    assert_eq!(
+        errors,
+        "Could not fetch weather for Lodnon: city not found\n"
+    );
+}
+
+#[test]
+fn current_as_csv() {
+    let (out, errors) = capture(|out, errors| {
+        write_current(out, errors, OutputFormat::Csv, &current(), Units::Metric)
+    });
+    assert_eq!(
+        out,
+        "\
+city,temp,humidity,wind,description
+London,15.32,72,4.12,broken clouds
+"
+    );
+    assert_eq!(
+        errors,
+        "Could not fetch weather for Lodnon: city not found\n"
+    );
+}
+
+#[test]
+fn current_as_json() {
+    let (out, errors) = capture(|out, errors| {
+        write_current(out, errors, OutputFormat::Json, &current(), Units::Metric)
+    });
+    // In JSON the failure is part of the document, not a stderr line
+    assert_eq!(errors, "");
+
+    let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(json.as_array().unwrap().len(), 2);
+    assert_eq!(json[0]["city"], "London");
+    assert_eq!(json[0]["response"]["name"], "London");
+    assert_eq!(json[0]["response"]["main"]["temp"], 15.32);
+    assert_eq!(
+        json[0]["response"]["weather"][0]["description"],
+        "broken clouds"
+    );
+    assert!(json[0].get("error").is_none());
+    assert_eq!(
+        json[1],
+        serde_json::json!({"city": "Lodnon", "error": "city not found"})
+    );
+}
+
+#[test]
+fn forecast_as_text() {
+    let (out, _) = capture(|out, errors| {
+        write_forecast(out, errors, OutputFormat::Text, &forecast(), Units::Metric)
+    });
+    assert_eq!(
+        out,
+        "\
+=== London ===
+Day                 Min      Max  Rain  Conditions
+Fri 2026-10-16    9.8°C   12.5°C   12%  broken clouds
+Sat 2026-10-17    8.0°C   16.7°C   74%  light rain
+Sun 2026-10-18    9.5°C   10.5°C    0%  few clouds
+
+"
+    );
+}
+
+#[test]
+fn forecast_as_csv() {
+    let (out, _) = capture(|out, errors| {
+        write_forecast(out, errors, OutputFormat::Csv, &forecast(), Units::Imperial)
+    });
+    assert_eq!(
+        out,
+        "\
+city,date,weekday,min,max,pop,description
+London,2026-10-16,Fri,9.8,12.5,0.12,broken clouds
+London,2026-10-17,Sat,8,16.7,0.74,light rain
+London,2026-10-18,Sun,9.5,10.5,0,few clouds
+"
+    );
+}
+
+#[test]
+fn forecast_as_json_keeps_every_slot() {
+    let (out, _) = capture(|out, errors| {
+        write_forecast(out, errors, OutputFormat::Json, &forecast(), Units::Metric)
+    });
+    let json: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(json[0]["response"]["list"].as_array().unwrap().len(), 11);
+    assert_eq!(json[0]["response"]["list"][2]["rain"]["3h"], 0.42);
+}
+
+#[test]
+fn csv_fields_are_quoted_when_needed() {
+    assert_eq!(csv_field("New York"), "New York");
+    assert_eq!(csv_field("Washington, D.C."), "\"Washington, D.C.\"");
+    assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+}
+
+#[test]
+fn output_formats_parse() {
+    assert_eq!(OutputFormat::parse("JSON"), Ok(OutputFormat::Json));
+    assert_eq!(OutputFormat::parse("csv"), Ok(OutputFormat::Csv));
+    assert!(OutputFormat::parse("xml").is_err());
+}
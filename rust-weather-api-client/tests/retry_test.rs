@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+use rust_weather_api_client::{RetryPolicy, WeatherClient, WeatherError, DEFAULT_TIMEOUT};
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const WEATHER: &str = include_str!("fixtures/weather.json");
+
+// The default policy's timing, but with millisecond waits so the tests stay fast
+fn fast_retries() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(10),
+    }
+}
+
+fn client(server: &MockServer) -> WeatherClient {
+    WeatherClient::new("k")
+        .with_base_url(server.uri())
+        .with_retry(fast_retries())
+}
+
+async fn requests_received(server: &MockServer) -> usize {
+    server.received_requests().await.unwrap().len()
+}
+
+#[tokio::test]
+async fn retries_a_503_then_succeeds() {
+    let server = MockServer::start().await;
+    // up_to_n_times(1): this mock answers once, then the next one takes over
+    Mock::given(path("/data/2.5/weather"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(path("/data/2.5/weather"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(WEATHER, "application/json"))
+        .mount(&server)
+        .await;
+
+    let weather = client(&server).get_weather_by_coords(51.5, -0.12).await;
+    assert_eq!(weather.unwrap().name, "London");
+    assert_eq!(requests_received(&server).await, 2);
+}
+
+#[tokio::test]
+async fn does_not_retry_a_404() {
+    let server = MockServer::start().await;
+    Mock::given(path("/data/2.5/weather"))
+        .respond_with(ResponseTemplate::new(404).set_body_raw(
+            r#"{"cod":"404","message":"city not found"}"#,
+            "application/json",
+        ))
+        .mount(&server)
+        .await;
+
+    let result = client(&server).get_weather_by_coords(0.0, 0.0).await;
+    assert!(matches!(result, Err(WeatherError::CityNotFound)));
+    assert_eq!(requests_received(&server).await, 1);
+}
+
+#[tokio::test]
+async fn gives_up_after_three_attempts() {
+    let server = MockServer::start().await;
+    Mock::given(path("/data/2.5/weather"))
+        .respond_with(ResponseTemplate::new(429))
+        .mount(&server)
+        .await;
+
+    let error = client(&server)
+        .get_weather_by_coords(0.0, 0.0)
+        .await
+        .unwrap_err();
+    assert_eq!(requests_received(&server).await, 3);
+    assert!(matches!(
+        &error,
+        WeatherError::GaveUp { attempts: 3, last } if matches!(**last, WeatherError::Http(429))
+    ));
+    assert_eq!(
+        error.to_string(),
+        "gave up after 3 attempts: request failed with status 429"
+    );
+}
+
+#[tokio::test]
+async fn a_slow_server_times_out() {
+    let server = MockServer::start().await;
+    Mock::given(path("/data/2.5/weather"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(WEATHER, "application/json")
+                .set_delay(Duration::from_secs(2)),
+        )
+        .mount(&server)
+        .await;
+    let client = WeatherClient::new("k")
+        .with_base_url(server.uri())
+        .with_timeout(Duration::from_millis(100))
+        .with_retry(RetryPolicy::none());
+
+    let error = client.get_weather_by_coords(0.0, 0.0).await.unwrap_err();
+    assert!(error.is_transient());
+    assert_eq!(error.to_string(), "request timed out");
+}
+
+#[tokio::test]
+async fn connection_errors_are_retried() {
+    // Nothing listens on port 1, so every attempt is refused
+    let client = WeatherClient::new("k")
+        .with_base_url("http://127.0.0.1:1")
+        .with_retry(fast_retries());
+
+    let error = client.get_weather_by_coords(0.0, 0.0).await.unwrap_err();
+    assert!(matches!(error, WeatherError::GaveUp { attempts: 3, .. }));
+}
+
+#[test]
+fn backoff_doubles() {
+    let policy = RetryPolicy::default();
+    assert_eq!(policy.max_attempts, 3);
+    assert_eq!(policy.backoff(1), Duration::from_millis(500));
+    assert_eq!(policy.backoff(2), Duration::from_secs(1));
+    assert_eq!(policy.backoff(3), Duration::from_secs(2));
+    // Silly attempt counts saturate instead of overflowing
+    assert_eq!(policy.backoff(100), Duration::from_millis(500) * u32::MAX);
+}
+
+#[test]
+fn defaults() {
+    let client = WeatherClient::new("k");
+    assert_eq!(client.timeout, DEFAULT_TIMEOUT);
+    assert_eq!(DEFAULT_TIMEOUT, Duration::from_secs(10));
+    assert_eq!(client.retry, RetryPolicy::default());
+}
+
+#[test]
+fn only_transient_errors_are_retried() {
+    assert!(WeatherError::Http(500).is_transient());
+    assert!(WeatherError::Http(503).is_transient());
+    assert!(WeatherError::Http(429).is_transient());
+    assert!(!WeatherError::Http(400).is_transient());
+    assert!(!WeatherError::CityNotFound.is_transient());
+    assert!(!WeatherError::InvalidApiKey.is_transient());
+}
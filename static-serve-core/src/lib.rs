@@ -0,0 +1,15 @@
+//! Pieces shared by the two static file servers (`rust-static-web-server` and
+//! `rust-docker-server`): which Content-Type a file gets, how a request path is turned
+//! into a file path that can't escape the static root, and which bytes a `Range` header
+//! asks for. Keeping them in one crate stops the servers from drifting apart.
+
+pub mod mime;
+pub mod path;
+pub mod range;
+// Only compiled for the servers' tests (see Cargo.toml)
+#[cfg(feature = "test-support")]
+pub mod test_support;
+
+pub use mime::{mime_for_extension, mime_for_path, DEFAULT_MIME};
+pub use path::{decode_path, resolve_safe_path, PathError};
+pub use range::{parse_range, ByteRange, RangeOutcome};
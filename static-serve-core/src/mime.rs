@@ -0,0 +1,80 @@
+// File extension -> MIME type. Browsers rely on Content-Type to decide what to do with a
+// response: a script sent as text/plain won't run, and an unknown type served as text can be
+// rendered as garbage. Unknown extensions get `application/octet-stream` ("some bytes"), which
+// browsers download instead of displaying.
+use std::path::Path;
+
+/// Sent for extensions not in the table
+pub const DEFAULT_MIME: &str = "application/octet-stream";
+
+// A plain slice of pairs is plenty for a few dozen entries: a linear scan of a small array
+// is as fast as a HashMap lookup and needs no initialisation. `&[(&str, &str)]` is a borrowed
+// array of tuples that lives in the binary itself (`'static`).
+const TYPES: &[(&str, &str)] = &[
+    // Documents and code
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "application/javascript"),
+    ("mjs", "application/javascript"),
+    ("json", "application/json"),
+    ("map", "application/json"),
+    ("xml", "application/xml"),
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("yaml", "application/yaml"),
+    ("yml", "application/yaml"),
+    ("toml", "application/toml"),
+    ("webmanifest", "application/manifest+json"),
+    ("pdf", "application/pdf"),
+    ("wasm", "application/wasm"),
+    // Images
+    ("svg", "image/svg+xml"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("ico", "image/x-icon"),
+    ("bmp", "image/bmp"),
+    // Fonts
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    // Audio and video
+    ("mp3", "audio/mpeg"),
+    ("wav", "audio/wav"),
+    ("ogg", "audio/ogg"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+    ("flac", "audio/flac"),
+    ("m4a", "audio/mp4"),
+    ("mov", "video/quicktime"),
+    ("ogv", "video/ogg"),
+    // Archives and binaries
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("7z", "application/x-7z-compressed"),
+];
+
+/// The MIME type for a file extension (without the dot), case-insensitively.
+pub fn mime_for_extension(extension: &str) -> &'static str {
+    TYPES
+        .iter()
+        .find(|(ext, _)| ext.eq_ignore_ascii_case(extension))
+        .map(|(_, mime)| *mime)
+        .unwrap_or(DEFAULT_MIME)
+}
+
+/// The MIME type for a file, from its extension. Files without one (`LICENSE`) get
+/// `DEFAULT_MIME`.
+pub fn mime_for_path(path: &Path) -> &'static str {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(extension) => mime_for_extension(extension),
+        None => DEFAULT_MIME,
+    }
+}
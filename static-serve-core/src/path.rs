@@ -0,0 +1,101 @@
+// Turning the request target (`/my%20file.txt?v=2`) into a path we can look up on disk.
+//
+// URLs can only contain a limited set of ASCII characters; anything else is "percent-encoded"
+// as %XX per byte of its UTF-8 form, so a space becomes %20 and "é" becomes %C3%A9. The path
+// has to be decoded before it names a real file, and the traversal check has to run on the
+// decoded form, or `/%2e%2e/secret` would sneak a `..` past it.
+use std::path::{Component, Path, PathBuf};
+
+/// Why a request path was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// Not a path (`*`, `foo`), not valid percent-encoding, or not UTF-8 once decoded (400)
+    Malformed,
+    /// A `..` segment (after decoding) that could climb out of the static root (403)
+    Traversal,
+}
+
+/// Decode the path part of a request URL.
+///
+/// The query string and fragment are dropped, `%XX` escapes are decoded, and the result is
+/// checked for `..` segments and NUL bytes. A `+` stays a `+`: it only means "space" in query
+/// strings, not in paths. The target must be a path starting with `/`, or an absolute URL
+/// (`http://host/path`, which HTTP/1.1 servers must also accept); anything else is malformed.
+pub fn decode_path(url: &str) -> Result<String, PathError> {
+    let url = strip_origin(url);
+    if !url.starts_with('/') {
+        return Err(PathError::Malformed);
+    }
+    // Everything from the first `?` or `#` on is not part of the path
+    let path = url.split(['?', '#']).next().unwrap_or("");
+
+    let raw = path.as_bytes();
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] == b'%' {
+            // `get` returns None instead of panicking when the escape is cut short ("%4")
+            let hex = raw.get(i + 1..i + 3).ok_or(PathError::Malformed)?;
+            let hex = std::str::from_utf8(hex).map_err(|_| PathError::Malformed)?;
+            bytes.push(u8::from_str_radix(hex, 16).map_err(|_| PathError::Malformed)?);
+            i += 3;
+        } else {
+            bytes.push(raw[i]);
+            i += 1;
+        }
+    }
+    let decoded = String::from_utf8(bytes).map_err(|_| PathError::Malformed)?;
+
+    if decoded.contains('\0') {
+        return Err(PathError::Malformed);
+    }
+    // Backslashes are separators on Windows, so `..\` counts as well
+    if decoded.split(['/', '\\']).any(|segment| segment == "..") {
+        return Err(PathError::Traversal);
+    }
+    Ok(decoded)
+}
+
+// `http://example.com/a?b` -> `/a?b`, and `http://example.com` -> `/`. Other targets are
+// returned unchanged.
+fn strip_origin(url: &str) -> &str {
+    let lower = url.get(..8).unwrap_or(url).to_ascii_lowercase();
+    let rest = if lower.starts_with("http://") {
+        &url[7..]
+    } else if lower.starts_with("https://") {
+        &url[8..]
+    } else {
+        return url;
+    };
+    match rest.find(['/', '?']) {
+        Some(i) if rest[i..].starts_with('/') => &rest[i..],
+        _ => "/",
+    }
+}
+
+/// The file under `root` that a request target names, e.g. `/css/site%20v2.css?x=1` under
+/// `/srv/www` is `/srv/www/css/site v2.css`.
+///
+/// The target is decoded with `decode_path`, then normalized segment by segment without
+/// touching the filesystem: empty and `.` segments are skipped, and every other segment must
+/// be a plain name, so the result always lies inside `root` (`/` gives `root` itself).
+/// Symlinks inside the root are not resolved here; a caller that must not follow them out of
+/// the root should canonicalize the result and compare.
+pub fn resolve_safe_path(root: &Path, url_path: &str) -> Result<PathBuf, PathError> {
+    let decoded = decode_path(url_path)?;
+    let mut path = root.to_path_buf();
+    for segment in decoded.split('/') {
+        if segment.is_empty() || segment == "." {
+            continue;
+        }
+        // `components` is how the OS would read the segment: anything but one plain name
+        // (a Windows drive like `C:`, a `..\` hidden inside, ...) could point elsewhere
+        let mut components = Path::new(segment).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(name)), None) => path.push(name),
+            (Some(Component::CurDir), None) => {}
+            _ => return Err(PathError::Traversal),
+        }
+    }
+    Ok(path)
+}
@@ -0,0 +1,103 @@
+// HTTP range requests: `Range: bytes=start-end` asks for a slice of a file instead of all of it.
+// Browsers send these to seek inside a video or to resume a download, and expect a
+// `206 Partial Content` answer that carries only those bytes.
+//
+// Three spellings are allowed for a single range (RFC 9110, section 14.1.2). Both ends are
+// inclusive, so `bytes=0-0` is one byte:
+//   bytes=10-19   bytes 10 through 19
+//   bytes=10-     byte 10 through the end of the file (open-ended)
+//   bytes=-5      the last 5 bytes (a "suffix" range)
+//
+// Both servers answer ranges through this one parser, so a header means the same to each.
+
+/// A satisfiable range, already clamped to the file: `start..=end`, both inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// Number of bytes in the range (never zero: `end >= start`)
+    pub fn size(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    /// The `Content-Range` value for this range of a `total`-byte file, e.g. `bytes 0-9/36`
+    pub fn content_range(&self, total: u64) -> String {
+        format!("bytes {}-{}/{}", self.start, self.end, total)
+    }
+}
+
+/// What to do with a request's `Range` header.
+// An enum with data, like a sealed class in Kotlin: the compiler makes the caller handle all three.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeOutcome {
+    /// No usable range: send the whole file with 200. This covers a missing header, a unit
+    /// other than `bytes`, syntax we don't understand, and multi-range requests (`bytes=0-1,5-6`),
+    /// which the spec lets a server answer with the full body.
+    Full,
+    /// Send just this slice with 206
+    Partial(ByteRange),
+    /// The range starts past the end of the file: 416 Range Not Satisfiable
+    Unsatisfiable,
+}
+
+/// Decide how to answer a `Range` header for a file of `file_len` bytes.
+pub fn parse_range(header: Option<&str>, file_len: u64) -> RangeOutcome {
+    // `let ... else` is an early return when the pattern doesn't match (a guard clause)
+    let Some(spec) = header.and_then(|h| h.trim().strip_prefix("bytes=")) else {
+        return RangeOutcome::Full;
+    };
+    if spec.contains(',') {
+        return RangeOutcome::Full;
+    }
+    let Some((first, last)) = spec.trim().split_once('-') else {
+        return RangeOutcome::Full;
+    };
+    // `.ok()` turns the parse Result into an Option; an empty string parses to None here
+    let first = first.trim();
+    let last = last.trim();
+    let start: Option<u64> = if first.is_empty() {
+        None
+    } else {
+        first.parse().ok()
+    };
+    let end: Option<u64> = if last.is_empty() {
+        None
+    } else {
+        last.parse().ok()
+    };
+
+    match (first.is_empty(), start, last.is_empty(), end) {
+        // bytes=-N: the last N bytes (the whole file if N is bigger than it)
+        (true, _, false, Some(suffix)) => {
+            if suffix == 0 || file_len == 0 {
+                RangeOutcome::Unsatisfiable
+            } else {
+                RangeOutcome::Partial(ByteRange {
+                    start: file_len.saturating_sub(suffix),
+                    end: file_len - 1,
+                })
+            }
+        }
+        // bytes=N-: from N to the end
+        (false, Some(start), true, _) => from(start, file_len.saturating_sub(1), file_len),
+        // bytes=N-M: an end past the file is clamped to the last byte
+        (false, Some(start), false, Some(end)) if end >= start => {
+            from(start, end.min(file_len.saturating_sub(1)), file_len)
+        }
+        // Anything else (`bytes=-`, `bytes=9-3`, non-numbers) is invalid, and an invalid Range
+        // header is ignored rather than rejected
+        _ => RangeOutcome::Full,
+    }
+}
+
+// A range that starts at or past the end of the file can't be satisfied
+fn from(start: u64, end: u64, file_len: u64) -> RangeOutcome {
+    if start >= file_len {
+        RangeOutcome::Unsatisfiable
+    } else {
+        RangeOutcome::Partial(ByteRange { start, end })
+    }
+}
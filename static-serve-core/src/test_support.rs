@@ -0,0 +1,83 @@
+//! Test fixtures for the servers' integration tests, behind the `test-support` feature so
+//! they never end up in a server binary. A server enables it for its tests only:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! static-serve-core = { path = "../static-serve-core", features = ["test-support"] }
+//! ```
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A directory of files to serve, under the system temp dir. It is removed when dropped,
+/// so a test cleans up after itself even when an assertion fails halfway.
+///
+/// Each test binary is its own process, so the process id keeps test files (and two
+/// `cargo test` runs) apart, and `name` the tests within one file. Like a failing assertion,
+/// every method panics if the filesystem says no.
+pub struct TempSite {
+    root: PathBuf,
+}
+
+impl TempSite {
+    /// A fresh, empty directory; anything an earlier run left behind is removed first
+    pub fn new(name: &str) -> TempSite {
+        let root = std::env::temp_dir().join(format!("temp-site-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        TempSite { root }
+    }
+
+    /// A fresh directory holding `files`, as `(path, contents)` pairs. A path ending in `/`
+    /// is an empty directory instead.
+    ///
+    /// A test file usually lists its site once, `const FILES: &[(&str, &str)] = &[...]`,
+    /// and every test calls `TempSite::with_files("its-name", FILES)`.
+    pub fn with_files<C: AsRef<[u8]>>(name: &str, files: &[(&str, C)]) -> TempSite {
+        let site = TempSite::new(name);
+        for (path, contents) in files {
+            if path.ends_with('/') {
+                fs::create_dir_all(site.root.join(path)).unwrap();
+            } else {
+                site.write(path, contents);
+            }
+        }
+        site
+    }
+
+    /// Write `contents` to `path` under the root, creating the directories on the way.
+    /// Also for changing a file halfway through a test.
+    pub fn write(&self, path: &str, contents: impl AsRef<[u8]>) -> &TempSite {
+        let file = self.root.join(path);
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(file, contents).unwrap();
+        self
+    }
+
+    /// The root directory
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+}
+
+// `root.join(...)` and `root.display()` work on the site itself, like on a `Path`
+impl Deref for TempSite {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl AsRef<Path> for TempSite {
+    fn as_ref(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Drop for TempSite {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
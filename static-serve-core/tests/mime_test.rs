@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use static_serve_core::{mime_for_extension, mime_for_path, DEFAULT_MIME};
+
+#[test]
+fn broad_table_of_types() {
+    for (file, expected) in [
+        ("index.html", "text/html"),
+        ("old.htm", "text/html"),
+        ("site.css", "text/css"),
+        ("app.js", "application/javascript"),
+        ("module.mjs", "application/javascript"),
+        ("data.json", "application/json"),
+        ("app.js.map", "application/json"),
+        ("feed.xml", "application/xml"),
+        ("notes.txt", "text/plain"),
+        ("README.md", "text/markdown"),
+        ("table.csv", "text/csv"),
+        ("config.yaml", "application/yaml"),
+        ("site.webmanifest", "application/manifest+json"),
+        ("doc.pdf", "application/pdf"),
+        ("app.wasm", "application/wasm"),
+        ("logo.svg", "image/svg+xml"),
+        ("photo.png", "image/png"),
+        ("photo.jpg", "image/jpeg"),
+        ("photo.jpeg", "image/jpeg"),
+        ("anim.gif", "image/gif"),
+        ("photo.webp", "image/webp"),
+        ("photo.avif", "image/avif"),
+        ("favicon.ico", "image/x-icon"),
+        ("font.woff", "font/woff"),
+        ("font.woff2", "font/woff2"),
+        ("font.ttf", "font/ttf"),
+        ("song.mp3", "audio/mpeg"),
+        ("clip.mp4", "video/mp4"),
+        ("clip.webm", "video/webm"),
+        ("bundle.zip", "application/zip"),
+        ("bundle.tar.gz", "application/gzip"),
+    ] {
+        assert_eq!(mime_for_path(Path::new(file)), expected, "{}", file);
+    }
+}
+
+#[test]
+fn extensions_are_case_insensitive() {
+    assert_eq!(mime_for_path(Path::new("INDEX.HTML")), "text/html");
+    assert_eq!(mime_for_extension("Png"), "image/png");
+}
+
+#[test]
+fn unknown_or_missing_extensions_get_the_default() {
+    assert_eq!(DEFAULT_MIME, "application/octet-stream");
+    assert_eq!(mime_for_path(Path::new("data.xyz")), DEFAULT_MIME);
+    assert_eq!(mime_for_path(Path::new("LICENSE")), DEFAULT_MIME);
+    assert_eq!(mime_for_path(Path::new(".env")), DEFAULT_MIME);
+    assert_eq!(mime_for_extension(""), DEFAULT_MIME);
+}
@@ -0,0 +1,71 @@
+use std::path::{Path, PathBuf};
+
+use static_serve_core::{decode_path, resolve_safe_path, PathError};
+
+fn resolve(url: &str) -> Result<PathBuf, PathError> {
+    resolve_safe_path(Path::new("/srv/www"), url)
+}
+
+#[test]
+fn resolves_plain_paths_under_the_root() {
+    assert_eq!(resolve("/"), Ok(PathBuf::from("/srv/www")));
+    assert_eq!(
+        resolve("/index.html"),
+        Ok(PathBuf::from("/srv/www/index.html"))
+    );
+    assert_eq!(
+        resolve("/css/site.css?v=2#top"),
+        Ok(PathBuf::from("/srv/www/css/site.css"))
+    );
+    assert_eq!(resolve("/docs/"), Ok(PathBuf::from("/srv/www/docs")));
+}
+
+#[test]
+fn decodes_percent_escapes() {
+    assert_eq!(
+        resolve("/my%20file.txt"),
+        Ok(PathBuf::from("/srv/www/my file.txt"))
+    );
+    assert_eq!(resolve("/caf%C3%A9"), Ok(PathBuf::from("/srv/www/café")));
+    // `+` is only a space in query strings
+    assert_eq!(resolve("/a+b"), Ok(PathBuf::from("/srv/www/a+b")));
+}
+
+#[test]
+fn normalizes_without_touching_the_filesystem() {
+    // None of these exist; normalization is purely textual, so no symlinks are followed
+    assert_eq!(resolve("//a///b"), Ok(PathBuf::from("/srv/www/a/b")));
+    assert_eq!(resolve("/./a/./b/."), Ok(PathBuf::from("/srv/www/a/b")));
+    assert_eq!(resolve("/%2e/a"), Ok(PathBuf::from("/srv/www/a")));
+}
+
+#[test]
+fn rejects_traversal_in_any_spelling() {
+    for url in [
+        "/..",
+        "/../etc/passwd",
+        "/a/../../etc/passwd",
+        "/%2e%2e/secret",
+        "/%2E%2E/secret",
+        "/.%2e/secret",
+        "/a/%2e%2e%2f%2e%2e/secret",
+        "/..%5csecret",
+        "/a\\..\\..\\secret",
+    ] {
+        assert_eq!(resolve(url), Err(PathError::Traversal), "{}", url);
+    }
+}
+
+#[test]
+fn rejects_malformed_targets() {
+    for url in ["*", "index.html", "/%G0", "/%4", "/%00", "/%FF", ""] {
+        assert_eq!(resolve(url), Err(PathError::Malformed), "{}", url);
+    }
+}
+
+#[test]
+fn decode_path_accepts_absolute_urls() {
+    assert_eq!(decode_path("http://example.com/a%20b?c").unwrap(), "/a b");
+    assert_eq!(decode_path("HTTPS://example.com").unwrap(), "/");
+    assert_eq!(decode_path("/go?to=http://x/y").unwrap(), "/go");
+}
@@ -0,0 +1,72 @@
+use static_serve_core::{parse_range, ByteRange, RangeOutcome};
+
+fn partial(start: u64, end: u64) -> RangeOutcome {
+    RangeOutcome::Partial(ByteRange { start, end })
+}
+
+#[test]
+fn the_three_spellings_of_a_range() {
+    assert_eq!(parse_range(Some("bytes=0-9"), 100), partial(0, 9));
+    assert_eq!(parse_range(Some("bytes=90-"), 100), partial(90, 99));
+    assert_eq!(parse_range(Some("bytes=-10"), 100), partial(90, 99));
+    // Both ends are inclusive, so this is one byte
+    assert_eq!(parse_range(Some("bytes=0-0"), 100), partial(0, 0));
+    assert_eq!(parse_range(Some(" bytes= 2 - 4 "), 100), partial(2, 4));
+}
+
+#[test]
+fn ranges_past_the_end_are_clamped_to_the_file() {
+    assert_eq!(parse_range(Some("bytes=50-500"), 100), partial(50, 99));
+    assert_eq!(parse_range(Some("bytes=-500"), 100), partial(0, 99));
+}
+
+#[test]
+fn a_range_that_starts_past_the_end_is_unsatisfiable() {
+    assert_eq!(
+        parse_range(Some("bytes=100-"), 100),
+        RangeOutcome::Unsatisfiable
+    );
+    assert_eq!(
+        parse_range(Some("bytes=-0"), 100),
+        RangeOutcome::Unsatisfiable
+    );
+    // Nothing can be satisfied in an empty file
+    assert_eq!(
+        parse_range(Some("bytes=0-"), 0),
+        RangeOutcome::Unsatisfiable
+    );
+    assert_eq!(
+        parse_range(Some("bytes=-1"), 0),
+        RangeOutcome::Unsatisfiable
+    );
+}
+
+#[test]
+fn unusable_headers_mean_the_whole_file() {
+    assert_eq!(parse_range(None, 100), RangeOutcome::Full);
+    // Invalid headers are ignored, not rejected, and so are multiple ranges
+    for header in [
+        "items=0-1",
+        "bytes=9-1",
+        "bytes=-",
+        "bytes=x-",
+        "bytes=a-b",
+        "bytes=3",
+        "bytes=0-1,5-6",
+        "bytes=-5,0-1",
+    ] {
+        assert_eq!(
+            parse_range(Some(header), 100),
+            RangeOutcome::Full,
+            "{}",
+            header
+        );
+    }
+}
+
+#[test]
+fn content_range_and_size() {
+    let range = ByteRange { start: 0, end: 9 };
+    assert_eq!(range.size(), 10);
+    assert_eq!(range.content_range(36), "bytes 0-9/36");
+}